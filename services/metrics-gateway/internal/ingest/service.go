@@ -1,9 +1,11 @@
 package ingest
 
 import (
+	"fmt"
 	"io"
 	"math"
 	"regexp"
+	"strconv"
 
 	"metrics-gateway/internal/metrics"
 	pb "metrics-gateway/internal/pb/metrics/v1"
@@ -15,6 +17,8 @@ import (
 
 var idRe = regexp.MustCompile(`^[a-zA-Z0-9_-]{1,64}$`)
 
+const maxRegion = 256
+
 // Service implements the MetricsIngestor gRPC service for ingesting metric batches.
 type Service struct {
 	pb.UnimplementedMetricsIngestorServer
@@ -64,6 +68,7 @@ func (s *Service) Push(stream pb.MetricsIngestor_PushServer) error {
 		s.handleRaidOps(batch.GetRaidOps(), &c)
 		s.handleRaidStates(batch.GetRaidStates(), &c)
 		s.handleFuseOps(batch.GetFuseOps(), &c)
+		s.handleVolumeStates(batch.GetVolumeStates(), &c)
 		s.handleProcess(batch.GetProcess(), &c)
 	}
 }
@@ -129,11 +134,21 @@ func (s *Service) handleDiskStates(states []*pb.DiskState, c *pushCounters) {
 }
 
 func validateDiskState(st *pb.DiskState) bool {
-	return validID(st.GetDiskId()) && finiteNonNeg(st.GetQueueDepth())
+	return validID(st.GetDiskId()) &&
+		finiteNonNeg(st.GetQueueDepth()) &&
+		finiteNonNeg(st.GetTemperatureCelsius())
 }
 
 func (s *Service) applyDiskState(st *pb.DiskState) {
-	s.m.Disks.QueueDepth.WithLabelValues(st.GetDiskId()).Set(st.GetQueueDepth())
+	diskID := st.GetDiskId()
+	s.m.Disks.QueueDepth.WithLabelValues(diskID).Set(st.GetQueueDepth())
+	setGaugeBool(s.m.Disks.Missing.WithLabelValues(diskID), st.GetMissing())
+	setGaugeBool(s.m.Disks.NeedsRebuild.WithLabelValues(diskID), st.GetNeedsRebuild())
+	s.m.Disks.GrownDefects.WithLabelValues(diskID).Set(float64(st.GetGrownDefects()))
+	s.m.Disks.ReallocatedSectors.WithLabelValues(diskID).Set(float64(st.GetReallocatedSectors()))
+	s.m.Disks.Temperature.WithLabelValues(diskID).Set(st.GetTemperatureCelsius())
+	setGaugeBool(s.m.Disks.PreFail.WithLabelValues(diskID), st.GetPreFail())
+	s.m.Disks.RebuildSources.WithLabelValues(diskID).Set(float64(st.GetRebuildSourceChunks()))
 }
 
 func (s *Service) handleRaidOps(ops []*pb.RaidOp, c *pushCounters) {
@@ -155,6 +170,9 @@ func validateRaidOp(op *pb.RaidOp) bool {
 }
 
 func (s *Service) applyRaidOp(op *pb.RaidOp) bool {
+	if op.GetBackground() {
+		return s.applyRaidBackground(op)
+	}
 	switch op.GetOp() {
 	case pb.IoOpType_IO_OP_READ:
 		s.applyRaidRead(op)
@@ -167,8 +185,21 @@ func (s *Service) applyRaidOp(op *pb.RaidOp) bool {
 	}
 }
 
+// applyRaidBackground counts rebuild and scrub traffic against its class only,
+// so the per-operation RAID metrics keep describing user IO.
+func (s *Service) applyRaidBackground(op *pb.RaidOp) bool {
+	switch op.GetOp() {
+	case pb.IoOpType_IO_OP_READ, pb.IoOpType_IO_OP_WRITE:
+		s.m.Raid.ClassBytes.WithLabelValues(op.GetRaidId(), "background").Add(float64(op.GetBytes()))
+		return true
+	default:
+		return false
+	}
+}
+
 func (s *Service) applyRaidRead(op *pb.RaidOp) {
 	raidID := op.GetRaidId()
+	s.m.Raid.ClassBytes.WithLabelValues(raidID, "foreground").Add(float64(op.GetBytes()))
 
 	recordIO(
 		s.m.Raid.ReadOps.WithLabelValues(raidID),
@@ -185,10 +216,24 @@ func (s *Service) applyRaidRead(op *pb.RaidOp) {
 	if op.GetRaid3ParityRead() {
 		s.m.Raid.Raid3ParityReads.WithLabelValues(raidID).Add(1)
 	}
+
+	if op.GetDegraded() {
+		s.m.Raid.DegradedReads.WithLabelValues(raidID).Add(1)
+	}
+
+	if hits := op.GetReadAheadHitStripes(); hits > 0 {
+		s.m.Raid.ReadAheadHits.WithLabelValues(raidID).Add(float64(hits))
+	}
+	if misses := op.GetReadAheadMissStripes(); misses > 0 {
+		s.m.Raid.ReadAheadMisses.WithLabelValues(raidID).Add(float64(misses))
+	}
+
+	s.recordRegion(raidID, op.GetRegion())
 }
 
 func (s *Service) applyRaidWrite(op *pb.RaidOp) {
 	raidID := op.GetRaidId()
+	s.m.Raid.ClassBytes.WithLabelValues(raidID, "foreground").Add(float64(op.GetBytes()))
 
 	recordIO(
 		s.m.Raid.WriteOps.WithLabelValues(raidID),
@@ -205,6 +250,25 @@ func (s *Service) applyRaidWrite(op *pb.RaidOp) {
 	if op.GetRaid3PartialStripeWrite() {
 		s.m.Raid.Raid3PartialStripe.WithLabelValues(raidID).Add(1)
 	}
+
+	if hits := op.GetParityCacheHitStripes(); hits > 0 {
+		s.m.Raid.ParityCacheHits.WithLabelValues(raidID).Add(float64(hits))
+	}
+	if misses := op.GetParityCacheMissStripes(); misses > 0 {
+		s.m.Raid.ParityCacheMisses.WithLabelValues(raidID).Add(float64(misses))
+	}
+
+	s.recordRegion(raidID, op.GetRegion())
+}
+
+// recordRegion counts an operation against its 1-based volume region; 0 means
+// the source did not attribute it and regions past maxRegion are dropped to
+// bound label cardinality.
+func (s *Service) recordRegion(raidID string, region uint32) {
+	if region == 0 || region > maxRegion {
+		return
+	}
+	s.m.Raid.RegionOps.WithLabelValues(raidID, strconv.FormatUint(uint64(region), 10)).Add(1)
 }
 
 func (s *Service) handleRaidStates(states []*pb.RaidState, c *pushCounters) {
@@ -219,7 +283,23 @@ func (s *Service) handleRaidStates(states []*pb.RaidState, c *pushCounters) {
 }
 
 func validateRaidState(st *pb.RaidState) bool {
-	return validID(st.GetRaidId()) && finiteNonNeg(st.GetRaid1ResyncProgress())
+	return validID(st.GetRaidId()) &&
+		finiteNonNeg(st.GetRaid1ResyncProgress()) &&
+		finiteNonNeg(st.GetRebuildBytesPerSecond()) &&
+		finiteNonNeg(st.GetRebuildEtaSeconds()) &&
+		finiteNonNeg(st.GetBalanceBytesPerSecond()) &&
+		validWriteHolePolicy(st.GetWriteHolePolicy())
+}
+
+// validWriteHolePolicy accepts the policies raid-cli reports, or none, so the
+// policy label stays bounded.
+func validWriteHolePolicy(policy string) bool {
+	switch policy {
+	case "", "unprotected", "journal", "full_stripe", "ppl":
+		return true
+	default:
+		return false
+	}
 }
 
 func (s *Service) applyRaidState(st *pb.RaidState) {
@@ -229,6 +309,38 @@ func (s *Service) applyRaidState(st *pb.RaidState) {
 	setGaugeBool(s.m.Raid.DegradedState.WithLabelValues(raidID), st.GetDegraded())
 	s.m.Raid.FailedDisks.WithLabelValues(raidID).Set(float64(st.GetFailedDisks()))
 	setGaugeBool(s.m.Raid.RebuildInProgress.WithLabelValues(raidID), st.GetRebuildInProgress())
+	s.m.Raid.Uncorrectable.WithLabelValues(raidID).Set(float64(st.GetUncorrectableStripes()))
+	setGaugeBool(s.m.Raid.ReadOnly.WithLabelValues(raidID), st.GetReadOnly())
+	s.m.Raid.StaleMembers.WithLabelValues(raidID).Set(float64(st.GetStaleMembers()))
+	if capacity := st.GetPoolCapacityBytes(); capacity > 0 {
+		s.m.Raid.PoolUsedBytes.WithLabelValues(raidID).Set(float64(st.GetPoolUsedBytes()))
+		s.m.Raid.PoolCapacityBytes.WithLabelValues(raidID).Set(float64(capacity))
+	}
+	if st.GetCheckStripesChecked() > 0 {
+		s.m.Raid.CheckRepairable.WithLabelValues(raidID).Set(float64(st.GetCheckRepairableStripes()))
+		s.m.Raid.CheckUncorrectable.WithLabelValues(raidID).Set(float64(st.GetCheckUncorrectableStripes()))
+	}
+	if total := st.GetRebuildStripesTotal(); total > 0 {
+		s.m.Raid.RebuildTotal.WithLabelValues(raidID).Set(float64(total))
+		s.m.Raid.RebuildDone.WithLabelValues(raidID).Set(float64(st.GetRebuildStripesDone()))
+		s.m.Raid.RebuildRate.WithLabelValues(raidID).Set(st.GetRebuildBytesPerSecond())
+		s.m.Raid.RebuildETA.WithLabelValues(raidID).Set(st.GetRebuildEtaSeconds())
+	}
+	if total := st.GetScrubStripesTotal(); total > 0 {
+		s.m.Raid.ScrubTotal.WithLabelValues(raidID).Set(float64(total))
+		s.m.Raid.ScrubDone.WithLabelValues(raidID).Set(float64(st.GetScrubStripesDone()))
+		s.m.Raid.ScrubMismatches.WithLabelValues(raidID).Set(float64(st.GetScrubMismatches()))
+	}
+	if total := st.GetBalanceBytesTotal(); total > 0 {
+		s.m.Raid.BalanceTotal.WithLabelValues(raidID).Set(float64(total))
+		s.m.Raid.BalanceDone.WithLabelValues(raidID).Set(float64(st.GetBalanceBytesDone()))
+		s.m.Raid.BalanceRate.WithLabelValues(raidID).Set(st.GetBalanceBytesPerSecond())
+	}
+	if policy := st.GetWriteHolePolicy(); policy != "" {
+		s.m.Raid.TornStripes.WithLabelValues(raidID, policy).Set(float64(st.GetTornStripes()))
+		s.m.Raid.ReplayedStripes.WithLabelValues(raidID, policy).Set(float64(st.GetWriteHoleReplayedStripes()))
+		s.m.Raid.RejectedWrites.WithLabelValues(raidID, policy).Set(float64(st.GetFullStripeRejectedWrites()))
+	}
 }
 
 func (s *Service) handleFuseOps(ops []*pb.FuseOp, c *pushCounters) {
@@ -259,6 +371,7 @@ func (s *Service) applyFuseOp(op *pb.FuseOp) bool {
 			op.GetBytes(),
 			op.GetLatencySeconds(),
 		)
+		recordFileBytes(s.m.Fuse.FileReadBytes, op)
 	case pb.FuseOpType_FUSE_OP_WRITE:
 		recordIO(
 			s.m.Fuse.WriteOps,
@@ -267,6 +380,7 @@ func (s *Service) applyFuseOp(op *pb.FuseOp) bool {
 			op.GetBytes(),
 			op.GetLatencySeconds(),
 		)
+		recordFileBytes(s.m.Fuse.FileWriteBytes, op)
 	case pb.FuseOpType_FUSE_OP_OPEN:
 		s.m.Fuse.OpenOps.Add(1)
 	case pb.FuseOpType_FUSE_OP_FSYNC:
@@ -282,6 +396,45 @@ func (s *Service) applyFuseOp(op *pb.FuseOp) bool {
 	return true
 }
 
+// recordFileBytes attributes the bytes of a FUSE op to its file when the client sent an inode.
+func recordFileBytes(cv *prometheus.CounterVec, op *pb.FuseOp) {
+	if op.GetInode() == 0 || op.GetBytes() == 0 {
+		return
+	}
+	inode := strconv.FormatUint(op.GetInode(), 10)
+	nameHash := fmt.Sprintf("%016x", op.GetNameHash())
+	cv.WithLabelValues(inode, nameHash).Add(float64(op.GetBytes()))
+}
+
+func (s *Service) handleVolumeStates(states []*pb.VolumeState, c *pushCounters) {
+	for _, st := range states {
+		if !validateVolumeState(st) {
+			c.rejectSample()
+			continue
+		}
+		s.applyVolumeState(st)
+		c.acceptSample()
+	}
+}
+
+func validateVolumeState(st *pb.VolumeState) bool {
+	return validID(st.GetRaidId()) &&
+		st.GetUsedBytes() <= st.GetCapacityBytes() &&
+		st.GetFreeBytes() <= st.GetCapacityBytes()
+}
+
+func (s *Service) applyVolumeState(st *pb.VolumeState) {
+	raidID := st.GetRaidId()
+
+	s.m.Volume.CapacityBytes.WithLabelValues(raidID).Set(float64(st.GetCapacityBytes()))
+	s.m.Volume.UsedBytes.WithLabelValues(raidID).Set(float64(st.GetUsedBytes()))
+	s.m.Volume.FreeBytes.WithLabelValues(raidID).Set(float64(st.GetFreeBytes()))
+	s.m.Volume.Files.WithLabelValues(raidID).Set(float64(st.GetFileCount()))
+	s.m.Volume.DirtyBytes.WithLabelValues(raidID).Set(float64(st.GetDirtyBytes()))
+	s.m.Volume.SnapshotUsedBytes.WithLabelValues(raidID).Set(float64(st.GetSnapshotUsedBytes()))
+	s.m.Volume.SnapshotPoolBytes.WithLabelValues(raidID).Set(float64(st.GetSnapshotPoolBytes()))
+}
+
 func (s *Service) handleProcess(ps *pb.ProcessSample, c *pushCounters) {
 	if ps == nil {
 		return
@@ -296,6 +449,26 @@ func (s *Service) handleProcess(ps *pb.ProcessSample, c *pushCounters) {
 
 	s.m.Process.ResidentMemory.Set(float64(ps.GetResidentMemoryBytes()))
 	c.acceptSample()
+
+	// Clients without compression support leave the ratio at zero; a real
+	// ratio is never below one.
+	if ps.GetCompressionRatio() == 0 {
+		return
+	}
+
+	if finiteNonNeg(ps.GetCompressionRatio()) {
+		s.m.Process.CompressionRatio.Set(ps.GetCompressionRatio())
+		c.acceptSample()
+	} else {
+		c.rejectSample()
+	}
+
+	if finiteNonNeg(ps.GetCompressionCpuSeconds()) {
+		s.m.Process.CompressionCPUSeconds.Set(ps.GetCompressionCpuSeconds())
+		c.acceptSample()
+	} else {
+		c.rejectSample()
+	}
 }
 
 func recordIO(ops prometheus.Counter, bytes prometheus.Counter, latency prometheus.Observer, nbytes uint64, latSec float64) {
@@ -59,6 +59,8 @@ func TestApplyRaidWriteUpdatesMetrics(t *testing.T) {
 		LatencySeconds:          0.25,
 		Raid3ParityWrite:        true,
 		Raid3PartialStripeWrite: true,
+		ParityCacheHitStripes:   3,
+		ParityCacheMissStripes:  1,
 	}
 
 	if ok := svc.applyRaidOp(op); !ok {
@@ -77,6 +79,50 @@ func TestApplyRaidWriteUpdatesMetrics(t *testing.T) {
 	if v := testutil.ToFloat64(svc.m.Raid.Raid3PartialStripe.WithLabelValues("raid3")); v != 1 {
 		t.Fatalf("expected raid3 partial stripe writes to be 1, got %f", v)
 	}
+	if v := testutil.ToFloat64(svc.m.Raid.ParityCacheHits.WithLabelValues("raid3")); v != 3 {
+		t.Fatalf("expected parity cache hits to be 3, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ParityCacheMisses.WithLabelValues("raid3")); v != 1 {
+		t.Fatalf("expected parity cache misses to be 1, got %f", v)
+	}
+}
+
+func TestApplyRaidOpSplitsTrafficByClass(t *testing.T) {
+	svc := newTestService(t)
+
+	user := &pb.RaidOp{
+		RaidId: "raid1",
+		Op:     pb.IoOpType_IO_OP_WRITE,
+		Bytes:  1024,
+	}
+	rebuild := &pb.RaidOp{
+		RaidId:     "raid1",
+		Op:         pb.IoOpType_IO_OP_READ,
+		Bytes:      4096,
+		Background: true,
+	}
+
+	if ok := svc.applyRaidOp(user); !ok {
+		t.Fatal("expected applyRaidOp to accept user IO")
+	}
+	if ok := svc.applyRaidOp(rebuild); !ok {
+		t.Fatal("expected applyRaidOp to accept background IO")
+	}
+
+	if v := testutil.ToFloat64(svc.m.Raid.ClassBytes.WithLabelValues("raid1", "foreground")); v != 1024 {
+		t.Fatalf("expected foreground bytes to be 1024, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ClassBytes.WithLabelValues("raid1", "background")); v != 4096 {
+		t.Fatalf("expected background bytes to be 4096, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ReadOps.WithLabelValues("raid1")); v != 0 {
+		t.Fatalf("expected background IO to stay out of raid read ops, got %f", v)
+	}
+
+	rebuild.Op = pb.IoOpType_IO_OP_UNSPECIFIED
+	if ok := svc.applyRaidOp(rebuild); ok {
+		t.Fatal("expected applyRaidOp to reject background IO without an op type")
+	}
 }
 
 func TestHandleProcessTracksAcceptReject(t *testing.T) {
@@ -100,6 +146,42 @@ func TestHandleProcessTracksAcceptReject(t *testing.T) {
 	}
 }
 
+func TestHandleProcessTracksCompression(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleProcess(&pb.ProcessSample{
+		CpuSeconds:            1,
+		CompressionRatio:      2.5,
+		CompressionCpuSeconds: -1,
+	}, counters)
+
+	if counters.acceptedSamples != 3 {
+		t.Fatalf("expected accepted samples to be 3, got %d", counters.acceptedSamples)
+	}
+	if counters.rejectedSamples != 1 {
+		t.Fatalf("expected rejected samples to be 1, got %d", counters.rejectedSamples)
+	}
+	if v := testutil.ToFloat64(svc.m.Process.CompressionRatio); v != 2.5 {
+		t.Fatalf("expected compression ratio to be 2.5, got %f", v)
+	}
+
+	svc.handleProcess(&pb.ProcessSample{
+		CompressionRatio:      math.Inf(1),
+		CompressionCpuSeconds: 0.5,
+	}, counters)
+
+	if counters.rejectedSamples != 2 {
+		t.Fatalf("expected rejected samples to be 2, got %d", counters.rejectedSamples)
+	}
+	if v := testutil.ToFloat64(svc.m.Process.CompressionRatio); v != 2.5 {
+		t.Fatalf("expected compression ratio to stay 2.5, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Process.CompressionCPUSeconds); v != 0.5 {
+		t.Fatalf("expected compression cpu seconds to be 0.5, got %f", v)
+	}
+}
+
 func TestHandleDiskOpsTracksAcceptReject(t *testing.T) {
 	svc := newTestService(t)
 	counters := &pushCounters{}
@@ -216,17 +298,107 @@ func TestHandleDiskStatesAppliesQueueDepth(t *testing.T) {
 	}
 }
 
+func TestHandleDiskStatesAppliesHealth(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleDiskStates([]*pb.DiskState{
+		{DiskId: "disk0", Missing: true},
+		{DiskId: "disk1", QueueDepth: 2, NeedsRebuild: true},
+	}, counters)
+
+	if counters.acceptedSamples != 2 {
+		t.Fatalf("expected accepted samples to be 2, got %d", counters.acceptedSamples)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.Missing.WithLabelValues("disk0")); v != 1 {
+		t.Fatalf("expected disk0 missing to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.NeedsRebuild.WithLabelValues("disk0")); v != 0 {
+		t.Fatalf("expected disk0 needs rebuild to be 0, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.NeedsRebuild.WithLabelValues("disk1")); v != 1 {
+		t.Fatalf("expected disk1 needs rebuild to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.QueueDepth.WithLabelValues("disk1")); v != 2 {
+		t.Fatalf("expected disk1 queue depth to be 2, got %f", v)
+	}
+}
+
+func TestHandleDiskStatesAppliesWear(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleDiskStates([]*pb.DiskState{
+		{DiskId: "disk0", GrownDefects: 9, ReallocatedSectors: 8, TemperatureCelsius: 41, PreFail: true},
+		{DiskId: "disk1", TemperatureCelsius: math.Inf(1)},
+	}, counters)
+
+	if counters.acceptedSamples != 1 || counters.rejectedSamples != 1 {
+		t.Fatalf("expected 1 accepted and 1 rejected sample, got %d and %d", counters.acceptedSamples, counters.rejectedSamples)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.GrownDefects.WithLabelValues("disk0")); v != 9 {
+		t.Fatalf("expected disk0 grown defects to be 9, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.ReallocatedSectors.WithLabelValues("disk0")); v != 8 {
+		t.Fatalf("expected disk0 reallocated sectors to be 8, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.Temperature.WithLabelValues("disk0")); v != 41 {
+		t.Fatalf("expected disk0 temperature to be 41, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.PreFail.WithLabelValues("disk0")); v != 1 {
+		t.Fatalf("expected disk0 pre-fail to be 1, got %f", v)
+	}
+}
+
+func TestHandleDiskStatesAppliesRebuildSources(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleDiskStates([]*pb.DiskState{
+		{DiskId: "disk0", RebuildSourceChunks: 25},
+		{DiskId: "disk1", NeedsRebuild: true},
+	}, counters)
+
+	if counters.acceptedSamples != 2 || counters.rejectedSamples != 0 {
+		t.Fatalf("expected 2 accepted and 0 rejected samples, got %d and %d", counters.acceptedSamples, counters.rejectedSamples)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.RebuildSources.WithLabelValues("disk0")); v != 25 {
+		t.Fatalf("expected disk0 rebuild source chunks to be 25, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Disks.RebuildSources.WithLabelValues("disk1")); v != 0 {
+		t.Fatalf("expected disk1 rebuild source chunks to be 0, got %f", v)
+	}
+}
+
 func TestHandleRaidStatesAppliesHealth(t *testing.T) {
 	svc := newTestService(t)
 	counters := &pushCounters{}
 
 	svc.handleRaidStates([]*pb.RaidState{
 		{
-			RaidId:              "raid1",
-			Raid1ResyncProgress: 0.5,
-			Degraded:            true,
-			FailedDisks:         2,
-			RebuildInProgress:   true,
+			RaidId:                    "raid1",
+			Raid1ResyncProgress:       0.5,
+			Degraded:                  true,
+			FailedDisks:               2,
+			RebuildInProgress:         true,
+			PoolUsedBytes:             4096,
+			PoolCapacityBytes:         8192,
+			CheckStripesChecked:       64,
+			CheckRepairableStripes:    3,
+			CheckUncorrectableStripes: 1,
+			UncorrectableStripes:      5,
+			ReadOnly:                  true,
+			RebuildStripesTotal:       100,
+			RebuildStripesDone:        25,
+			RebuildBytesPerSecond:     4096,
+			RebuildEtaSeconds:         30,
+			ScrubStripesTotal:         64,
+			ScrubStripesDone:          64,
+			ScrubMismatches:           4,
+			StaleMembers:              1,
+			BalanceBytesTotal:         8192,
+			BalanceBytesDone:          2048,
+			BalanceBytesPerSecond:     512,
 		},
 		{
 			RaidId:              "raid1",
@@ -253,6 +425,84 @@ func TestHandleRaidStatesAppliesHealth(t *testing.T) {
 	if v := testutil.ToFloat64(svc.m.Raid.RebuildInProgress.WithLabelValues("raid1")); v != 1 {
 		t.Fatalf("expected rebuild in progress to be 1, got %f", v)
 	}
+	if v := testutil.ToFloat64(svc.m.Raid.PoolUsedBytes.WithLabelValues("raid1")); v != 4096 {
+		t.Fatalf("expected pool used bytes to be 4096, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.PoolCapacityBytes.WithLabelValues("raid1")); v != 8192 {
+		t.Fatalf("expected pool capacity bytes to be 8192, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.CheckRepairable.WithLabelValues("raid1")); v != 3 {
+		t.Fatalf("expected repairable stripes to be 3, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.CheckUncorrectable.WithLabelValues("raid1")); v != 1 {
+		t.Fatalf("expected uncorrectable stripes to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.Uncorrectable.WithLabelValues("raid1")); v != 5 {
+		t.Fatalf("expected read uncorrectable stripes to be 5, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ReadOnly.WithLabelValues("raid1")); v != 1 {
+		t.Fatalf("expected read only to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.RebuildTotal.WithLabelValues("raid1")); v != 100 {
+		t.Fatalf("expected rebuild stripes total to be 100, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.RebuildDone.WithLabelValues("raid1")); v != 25 {
+		t.Fatalf("expected rebuild stripes done to be 25, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.RebuildRate.WithLabelValues("raid1")); v != 4096 {
+		t.Fatalf("expected rebuild rate to be 4096, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.RebuildETA.WithLabelValues("raid1")); v != 30 {
+		t.Fatalf("expected rebuild eta to be 30, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ScrubDone.WithLabelValues("raid1")); v != 64 {
+		t.Fatalf("expected scrub stripes done to be 64, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ScrubMismatches.WithLabelValues("raid1")); v != 4 {
+		t.Fatalf("expected scrub mismatches to be 4, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.StaleMembers.WithLabelValues("raid1")); v != 1 {
+		t.Fatalf("expected stale members to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.BalanceDone.WithLabelValues("raid1")); v != 2048 {
+		t.Fatalf("expected balance bytes done to be 2048, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.BalanceRate.WithLabelValues("raid1")); v != 512 {
+		t.Fatalf("expected balance rate to be 512, got %f", v)
+	}
+}
+
+func TestHandleRaidStatesAppliesWriteHoleCounters(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleRaidStates([]*pb.RaidState{
+		{
+			RaidId:                   "raid3",
+			WriteHolePolicy:          "journal",
+			TornStripes:              2,
+			WriteHoleReplayedStripes: 5,
+		},
+		{
+			RaidId:                   "raid5",
+			WriteHolePolicy:          "full_stripe",
+			FullStripeRejectedWrites: 7,
+		},
+		{RaidId: "raid6", WriteHolePolicy: "raid-z"},
+	}, counters)
+
+	if counters.acceptedSamples != 2 || counters.rejectedSamples != 1 {
+		t.Fatalf("expected 2 accepted and 1 rejected sample, got %d and %d", counters.acceptedSamples, counters.rejectedSamples)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.TornStripes.WithLabelValues("raid3", "journal")); v != 2 {
+		t.Fatalf("expected torn stripes to be 2, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ReplayedStripes.WithLabelValues("raid3", "journal")); v != 5 {
+		t.Fatalf("expected replayed stripes to be 5, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.RejectedWrites.WithLabelValues("raid5", "full_stripe")); v != 7 {
+		t.Fatalf("expected rejected writes to be 7, got %f", v)
+	}
 }
 
 func TestHandleFuseOpsTracksAllOps(t *testing.T) {
@@ -290,16 +540,82 @@ func TestHandleFuseOpsTracksAllOps(t *testing.T) {
 	}
 }
 
+func TestHandleFuseOpsAttributesBytesToFiles(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleFuseOps([]*pb.FuseOp{
+		{Op: pb.FuseOpType_FUSE_OP_READ, Bytes: 10, Inode: 7, NameHash: 0xab},
+		{Op: pb.FuseOpType_FUSE_OP_READ, Bytes: 5, Inode: 7, NameHash: 0xab},
+		{Op: pb.FuseOpType_FUSE_OP_WRITE, Bytes: 20, Inode: 9},
+		{Op: pb.FuseOpType_FUSE_OP_WRITE, Bytes: 30},
+	}, counters)
+
+	if v := testutil.ToFloat64(svc.m.Fuse.FileReadBytes.WithLabelValues("7", "00000000000000ab")); v != 15 {
+		t.Fatalf("expected file read bytes to be 15, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Fuse.FileWriteBytes.WithLabelValues("9", "0000000000000000")); v != 20 {
+		t.Fatalf("expected file write bytes to be 20, got %f", v)
+	}
+	if n := testutil.CollectAndCount(svc.m.Fuse.FileWriteBytes); n != 1 {
+		t.Fatalf("expected ops without an inode to be skipped, got %d series", n)
+	}
+}
+
+func TestHandleVolumeStatesAppliesUsage(t *testing.T) {
+	svc := newTestService(t)
+	counters := &pushCounters{}
+
+	svc.handleVolumeStates([]*pb.VolumeState{
+		{RaidId: "raid5", CapacityBytes: 1 << 20, UsedBytes: 4096, FreeBytes: (1 << 20) - 4096, FileCount: 3, DirtyBytes: 512, SnapshotUsedBytes: 256, SnapshotPoolBytes: 8192},
+		{RaidId: "raid5", CapacityBytes: 10, UsedBytes: 20},
+		{RaidId: "", CapacityBytes: 10},
+	}, counters)
+
+	if counters.acceptedSamples != 1 {
+		t.Fatalf("expected accepted samples to be 1, got %d", counters.acceptedSamples)
+	}
+	if counters.rejectedSamples != 2 {
+		t.Fatalf("expected rejected samples to be 2, got %d", counters.rejectedSamples)
+	}
+
+	if v := testutil.ToFloat64(svc.m.Volume.CapacityBytes.WithLabelValues("raid5")); v != 1<<20 {
+		t.Fatalf("expected capacity to be %d, got %f", 1<<20, v)
+	}
+	if v := testutil.ToFloat64(svc.m.Volume.UsedBytes.WithLabelValues("raid5")); v != 4096 {
+		t.Fatalf("expected used bytes to be 4096, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Volume.FreeBytes.WithLabelValues("raid5")); v != (1<<20)-4096 {
+		t.Fatalf("expected free bytes to be %d, got %f", (1<<20)-4096, v)
+	}
+	if v := testutil.ToFloat64(svc.m.Volume.Files.WithLabelValues("raid5")); v != 3 {
+		t.Fatalf("expected file count to be 3, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Volume.DirtyBytes.WithLabelValues("raid5")); v != 512 {
+		t.Fatalf("expected dirty bytes to be 512, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Volume.SnapshotUsedBytes.WithLabelValues("raid5")); v != 256 {
+		t.Fatalf("expected snapshot used bytes to be 256, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Volume.SnapshotPoolBytes.WithLabelValues("raid5")); v != 8192 {
+		t.Fatalf("expected snapshot pool bytes to be 8192, got %f", v)
+	}
+}
+
 func TestApplyRaidReadTracksExtras(t *testing.T) {
 	svc := newTestService(t)
 
 	op := &pb.RaidOp{
-		RaidId:           "raid1",
-		Op:               pb.IoOpType_IO_OP_READ,
-		Bytes:            512,
-		LatencySeconds:   0.1,
-		ServedFromDiskId: "disk0",
-		Raid3ParityRead:  true,
+		RaidId:               "raid1",
+		Op:                   pb.IoOpType_IO_OP_READ,
+		Bytes:                512,
+		LatencySeconds:       0.1,
+		ServedFromDiskId:     "disk0",
+		Raid3ParityRead:      true,
+		Degraded:             true,
+		Region:               3,
+		ReadAheadHitStripes:  5,
+		ReadAheadMissStripes: 2,
 	}
 
 	if ok := svc.applyRaidOp(op); !ok {
@@ -315,6 +631,24 @@ func TestApplyRaidReadTracksExtras(t *testing.T) {
 	if v := testutil.ToFloat64(svc.m.Raid.Raid3ParityReads.WithLabelValues("raid1")); v != 1 {
 		t.Fatalf("expected parity reads to be 1, got %f", v)
 	}
+	if v := testutil.ToFloat64(svc.m.Raid.DegradedReads.WithLabelValues("raid1")); v != 1 {
+		t.Fatalf("expected degraded reads to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.RegionOps.WithLabelValues("raid1", "3")); v != 1 {
+		t.Fatalf("expected region 3 ops to be 1, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ReadAheadHits.WithLabelValues("raid1")); v != 5 {
+		t.Fatalf("expected read-ahead hits to be 5, got %f", v)
+	}
+	if v := testutil.ToFloat64(svc.m.Raid.ReadAheadMisses.WithLabelValues("raid1")); v != 2 {
+		t.Fatalf("expected read-ahead misses to be 2, got %f", v)
+	}
+
+	op.Region = maxRegion + 1
+	svc.applyRaidOp(op)
+	if n := testutil.CollectAndCount(svc.m.Raid.RegionOps); n != 1 {
+		t.Fatalf("expected out-of-range region to be dropped, got %d series", n)
+	}
 }
 
 func TestRecordIOAndHelpers(t *testing.T) {
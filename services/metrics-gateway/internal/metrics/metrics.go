@@ -14,14 +14,21 @@ var defaultLatencyBuckets = []float64{
 
 // DiskMetrics bundles Prometheus metrics tracking disk IO behavior.
 type DiskMetrics struct {
-	ReadOps      *prometheus.CounterVec
-	WriteOps     *prometheus.CounterVec
-	ReadBytes    *prometheus.CounterVec
-	WriteBytes   *prometheus.CounterVec
-	ReadLatency  *prometheus.HistogramVec
-	WriteLatency *prometheus.HistogramVec
-	QueueDepth   *prometheus.GaugeVec
-	Errors       *prometheus.CounterVec
+	ReadOps            *prometheus.CounterVec
+	WriteOps           *prometheus.CounterVec
+	ReadBytes          *prometheus.CounterVec
+	WriteBytes         *prometheus.CounterVec
+	ReadLatency        *prometheus.HistogramVec
+	WriteLatency       *prometheus.HistogramVec
+	QueueDepth         *prometheus.GaugeVec
+	Missing            *prometheus.GaugeVec
+	NeedsRebuild       *prometheus.GaugeVec
+	GrownDefects       *prometheus.GaugeVec
+	ReallocatedSectors *prometheus.GaugeVec
+	Temperature        *prometheus.GaugeVec
+	PreFail            *prometheus.GaugeVec
+	RebuildSources     *prometheus.GaugeVec
+	Errors             *prometheus.CounterVec
 }
 
 // RaidMetrics bundles Prometheus metrics tracking RAID volume behavior.
@@ -40,6 +47,33 @@ type RaidMetrics struct {
 	DegradedState      *prometheus.GaugeVec
 	FailedDisks        *prometheus.GaugeVec
 	RebuildInProgress  *prometheus.GaugeVec
+	PoolUsedBytes      *prometheus.GaugeVec
+	PoolCapacityBytes  *prometheus.GaugeVec
+	CheckRepairable    *prometheus.GaugeVec
+	CheckUncorrectable *prometheus.GaugeVec
+	DegradedReads      *prometheus.CounterVec
+	Uncorrectable      *prometheus.GaugeVec
+	ReadOnly           *prometheus.GaugeVec
+	StaleMembers       *prometheus.GaugeVec
+	RebuildTotal       *prometheus.GaugeVec
+	RebuildDone        *prometheus.GaugeVec
+	RebuildRate        *prometheus.GaugeVec
+	RebuildETA         *prometheus.GaugeVec
+	ScrubTotal         *prometheus.GaugeVec
+	ScrubDone          *prometheus.GaugeVec
+	ScrubMismatches    *prometheus.GaugeVec
+	BalanceTotal       *prometheus.GaugeVec
+	BalanceDone        *prometheus.GaugeVec
+	BalanceRate        *prometheus.GaugeVec
+	TornStripes        *prometheus.GaugeVec
+	ReplayedStripes    *prometheus.GaugeVec
+	RejectedWrites     *prometheus.GaugeVec
+	RegionOps          *prometheus.CounterVec
+	ReadAheadHits      *prometheus.CounterVec
+	ReadAheadMisses    *prometheus.CounterVec
+	ParityCacheHits    *prometheus.CounterVec
+	ParityCacheMisses  *prometheus.CounterVec
+	ClassBytes         *prometheus.CounterVec
 }
 
 // FuseMetrics bundles Prometheus metrics tracking FUSE-level operations.
@@ -53,12 +87,28 @@ type FuseMetrics struct {
 	ReadLatency  prometheus.Histogram
 	WriteLatency prometheus.Histogram
 	Errors       prometheus.Counter
+
+	FileReadBytes  *prometheus.CounterVec
+	FileWriteBytes *prometheus.CounterVec
+}
+
+// VolumeMetrics bundles Prometheus gauges tracking filesystem capacity and usage.
+type VolumeMetrics struct {
+	CapacityBytes     *prometheus.GaugeVec
+	UsedBytes         *prometheus.GaugeVec
+	FreeBytes         *prometheus.GaugeVec
+	Files             *prometheus.GaugeVec
+	DirtyBytes        *prometheus.GaugeVec
+	SnapshotUsedBytes *prometheus.GaugeVec
+	SnapshotPoolBytes *prometheus.GaugeVec
 }
 
 // ProcessMetrics bundles Prometheus gauges tracking simulated process usage.
 type ProcessMetrics struct {
-	CPUSeconds     prometheus.Gauge
-	ResidentMemory prometheus.Gauge
+	CPUSeconds            prometheus.Gauge
+	ResidentMemory        prometheus.Gauge
+	CompressionRatio      prometheus.Gauge
+	CompressionCPUSeconds prometheus.Gauge
 }
 
 // AllMetrics aggregates all metric families used by the gateway.
@@ -66,6 +116,7 @@ type AllMetrics struct {
 	Disks   *DiskMetrics
 	Raid    *RaidMetrics
 	Fuse    *FuseMetrics
+	Volume  *VolumeMetrics
 	Process *ProcessMetrics
 }
 
@@ -82,6 +133,7 @@ func NewMetricsRegistry() (*prometheus.Registry, *AllMetrics) {
 		Disks:   NewDiskMetrics(reg),
 		Raid:    NewRaidMetrics(reg),
 		Fuse:    NewFuseMetrics(reg),
+		Volume:  NewVolumeMetrics(reg),
 		Process: NewProcessMetrics(reg),
 	}
 
@@ -158,8 +210,15 @@ func NewDiskMetrics(reg prometheus.Registerer) *DiskMetrics {
 			defaultLatencyBuckets,
 			"disk_id",
 		),
-		QueueDepth: newGaugeVec(reg, "disk_queue_depth", "Current disk queue depth", "disk_id"),
-		Errors:     newCounterVec(reg, "disk_errors", "Total disk errors", "disk_id"),
+		QueueDepth:         newGaugeVec(reg, "disk_queue_depth", "Current disk queue depth", "disk_id"),
+		Missing:            newGaugeVec(reg, "disk_missing", "Disk is missing from the array (0/1)", "disk_id"),
+		NeedsRebuild:       newGaugeVec(reg, "disk_needs_rebuild", "Disk holds stale data awaiting rebuild (0/1)", "disk_id"),
+		GrownDefects:       newGaugeVec(reg, "disk_grown_defects", "Defects grown since the disk was installed", "disk_id"),
+		ReallocatedSectors: newGaugeVec(reg, "disk_reallocated_sectors", "Defects remapped to spare sectors", "disk_id"),
+		Temperature:        newGaugeVec(reg, "disk_temperature_celsius", "Disk temperature (degrees Celsius)", "disk_id"),
+		PreFail:            newGaugeVec(reg, "disk_pre_fail", "Disk predicts its own failure (0/1)", "disk_id"),
+		RebuildSources:     newGaugeVec(reg, "disk_rebuild_source_chunks", "Chunks the latest rebuild has read from this disk", "disk_id"),
+		Errors:             newCounterVec(reg, "disk_errors", "Total disk errors", "disk_id"),
 	}
 }
 
@@ -192,6 +251,33 @@ func NewRaidMetrics(reg prometheus.Registerer) *RaidMetrics {
 		DegradedState:      newGaugeVec(reg, "raid_degraded_state", "RAID degraded state (0/1)", "raid"),
 		FailedDisks:        newGaugeVec(reg, "raid_failed_disks", "Number of failed disks in RAID", "raid"),
 		RebuildInProgress:  newGaugeVec(reg, "raid_rebuild_in_progress", "RAID rebuild in progress (0/1)", "raid"),
+		PoolUsedBytes:      newGaugeVec(reg, "raid_pool_used_bytes", "Physical bytes allocated from a thin pool", "raid"),
+		PoolCapacityBytes:  newGaugeVec(reg, "raid_pool_capacity_bytes", "Physical bytes available to a thin pool", "raid"),
+		CheckRepairable:    newGaugeVec(reg, "raid_check_repairable_stripes", "Inconsistent stripes found by the last check that redundancy can repair", "raid"),
+		CheckUncorrectable: newGaugeVec(reg, "raid_check_uncorrectable_stripes", "Stripes found by the last check that redundancy cannot repair", "raid"),
+		DegradedReads:      newCounterVec(reg, "raid_degraded_reads", "RAID reads served while members were missing or rebuilding", "raid"),
+		Uncorrectable:      newGaugeVec(reg, "raid_uncorrectable_stripes", "Logical stripes that could not be reconstructed on read", "raid"),
+		ReadOnly:           newGaugeVec(reg, "raid_read_only", "RAID volume refuses writes after too many uncorrectable errors (0/1)", "raid"),
+		StaleMembers:       newGaugeVec(reg, "raid_stale_members", "Members that rejoined with stale data and were marked for rebuild", "raid"),
+		RebuildTotal:       newGaugeVec(reg, "raid_rebuild_stripes_total", "Stripes to repair in the current rebuild pass", "raid"),
+		RebuildDone:        newGaugeVec(reg, "raid_rebuild_stripes_done", "Stripes repaired in the current rebuild pass", "raid"),
+		RebuildRate:        newGaugeVec(reg, "raid_rebuild_bytes_per_second", "Average rebuild rate since the pass started (bytes/s)", "raid"),
+		RebuildETA:         newGaugeVec(reg, "raid_rebuild_eta_seconds", "Estimated seconds until the rebuild pass completes", "raid"),
+		ScrubTotal:         newGaugeVec(reg, "raid_scrub_stripes_total", "Stripes to verify in the current scrub", "raid"),
+		ScrubDone:          newGaugeVec(reg, "raid_scrub_stripes_done", "Stripes verified in the current scrub", "raid"),
+		ScrubMismatches:    newGaugeVec(reg, "raid_scrub_mismatches", "Inconsistent stripes found by the current scrub", "raid"),
+		BalanceTotal:       newGaugeVec(reg, "raid_balance_bytes_total", "Bytes to move onto the members added by a grow", "raid"),
+		BalanceDone:        newGaugeVec(reg, "raid_balance_bytes_done", "Bytes moved so far by the running balance", "raid"),
+		BalanceRate:        newGaugeVec(reg, "raid_balance_bytes_per_second", "Average balance rate since the mount (bytes/s)", "raid"),
+		TornStripes:        newGaugeVec(reg, "raid_write_hole_torn_stripes", "Stripes whose data reached the members without their redundancy", "raid", "policy"),
+		ReplayedStripes:    newGaugeVec(reg, "raid_write_hole_replayed_stripes", "Stripes rewritten from the journal or partial parity log on open", "raid", "policy"),
+		RejectedWrites:     newGaugeVec(reg, "raid_write_hole_rejected_writes", "Partial-stripe writes refused by the full-stripe policy", "raid", "policy"),
+		RegionOps:          newCounterVec(reg, "raid_region_ops", "RAID operations per logical region of the volume", "raid", "region"),
+		ReadAheadHits:      newCounterVec(reg, "raid_read_ahead_hit_stripes", "Stripes served from the read-ahead cache", "raid"),
+		ReadAheadMisses:    newCounterVec(reg, "raid_read_ahead_miss_stripes", "Stripes read from disk while read-ahead was active", "raid"),
+		ParityCacheHits:    newCounterVec(reg, "raid_parity_cache_hit_stripes", "Partial-stripe writes served from cached parity", "raid"),
+		ParityCacheMisses:  newCounterVec(reg, "raid_parity_cache_miss_stripes", "Partial-stripe writes that found no cached parity", "raid"),
+		ClassBytes:         newCounterVec(reg, "raid_io_class_bytes", "RAID bytes moved by user (foreground) and rebuild or scrub (background) IO", "raid", "class"),
 	}
 }
 
@@ -207,13 +293,31 @@ func NewFuseMetrics(reg prometheus.Registerer) *FuseMetrics {
 		ReadLatency:  newHistogram(reg, "fuse_read_latency_seconds", "FUSE read latency (seconds)", defaultLatencyBuckets),
 		WriteLatency: newHistogram(reg, "fuse_write_latency_seconds", "FUSE write latency (seconds)", defaultLatencyBuckets),
 		Errors:       newCounter(reg, "fuse_errors", "Total FUSE errors"),
+
+		FileReadBytes:  newCounterVec(reg, "fuse_file_read_bytes", "Bytes read via FUSE per file", "inode", "name_hash"),
+		FileWriteBytes: newCounterVec(reg, "fuse_file_write_bytes", "Bytes written via FUSE per file", "inode", "name_hash"),
+	}
+}
+
+// NewVolumeMetrics registers volume capacity metrics with the provided registry.
+func NewVolumeMetrics(reg prometheus.Registerer) *VolumeMetrics {
+	return &VolumeMetrics{
+		CapacityBytes:     newGaugeVec(reg, "volume_capacity_bytes", "Logical capacity of the mounted filesystem (bytes)", "raid"),
+		UsedBytes:         newGaugeVec(reg, "volume_used_bytes", "Bytes used by files and metadata on the mounted filesystem", "raid"),
+		FreeBytes:         newGaugeVec(reg, "volume_free_bytes", "Bytes still available for new data on the mounted filesystem", "raid"),
+		Files:             newGaugeVec(reg, "volume_files", "Number of files stored on the mounted filesystem", "raid"),
+		DirtyBytes:        newGaugeVec(reg, "volume_dirty_bytes", "File writes held in the write buffer and not yet on the array (bytes)", "raid"),
+		SnapshotUsedBytes: newGaugeVec(reg, "volume_snapshot_used_bytes", "Bytes preserved by copy-on-write for snapshots of the mounted filesystem", "raid"),
+		SnapshotPoolBytes: newGaugeVec(reg, "volume_snapshot_pool_bytes", "Bytes reserved for copy-on-write snapshot data on the mounted filesystem", "raid"),
 	}
 }
 
 // NewProcessMetrics registers process metrics with the provided registry.
 func NewProcessMetrics(reg prometheus.Registerer) *ProcessMetrics {
 	return &ProcessMetrics{
-		CPUSeconds:     newGauge(reg, "process_cpu_seconds", "Simulated CPU seconds used by the RAID simulator"),
-		ResidentMemory: newGauge(reg, "process_resident_memory", "Simulated resident memory (bytes)"),
+		CPUSeconds:            newGauge(reg, "process_cpu_seconds", "Simulated CPU seconds used by the RAID simulator"),
+		ResidentMemory:        newGauge(reg, "process_resident_memory", "Simulated resident memory (bytes)"),
+		CompressionRatio:      newGauge(reg, "process_compression_ratio", "Logical bytes per stored byte across compressed volumes"),
+		CompressionCPUSeconds: newGauge(reg, "process_compression_cpu_seconds", "Seconds spent compressing and decompressing volume blocks"),
 	}
 }
@@ -23,6 +23,9 @@ func TestNewMetricsRegistryInitializesAllGroups(t *testing.T) {
 	if all.Fuse == nil {
 		t.Fatal("expected fuse metrics to be initialized")
 	}
+	if all.Volume == nil {
+		t.Fatal("expected volume metrics to be initialized")
+	}
 	if all.Process == nil {
 		t.Fatal("expected process metrics to be initialized")
 	}
@@ -31,6 +34,7 @@ func TestNewMetricsRegistryInitializesAllGroups(t *testing.T) {
 	all.Disks.WriteOps.WithLabelValues("disk0").Add(5)
 	all.Raid.ReadOps.WithLabelValues("raid0").Add(10)
 	all.Fuse.ReadOps.Inc()
+	all.Volume.UsedBytes.WithLabelValues("raid0").Set(4096)
 	all.Process.CPUSeconds.Set(1.23)
 
 	if _, err := reg.Gather(); err != nil {
@@ -150,6 +150,7 @@ type MetricsBatch struct {
 	RaidOps       []*RaidOp              `protobuf:"bytes,20,rep,name=raid_ops,json=raidOps,proto3" json:"raid_ops,omitempty"`
 	RaidStates    []*RaidState           `protobuf:"bytes,21,rep,name=raid_states,json=raidStates,proto3" json:"raid_states,omitempty"`
 	FuseOps       []*FuseOp              `protobuf:"bytes,30,rep,name=fuse_ops,json=fuseOps,proto3" json:"fuse_ops,omitempty"`
+	VolumeStates  []*VolumeState         `protobuf:"bytes,31,rep,name=volume_states,json=volumeStates,proto3" json:"volume_states,omitempty"`
 	Process       *ProcessSample         `protobuf:"bytes,40,opt,name=process,proto3" json:"process,omitempty"`
 	unknownFields protoimpl.UnknownFields
 	sizeCache     protoimpl.SizeCache
@@ -253,6 +254,14 @@ func (x *MetricsBatch) GetFuseOps() []*FuseOp {
 	return nil
 }
 
+// GetVolumeStates returns the VolumeStates field.
+func (x *MetricsBatch) GetVolumeStates() []*VolumeState {
+	if x != nil {
+		return x.VolumeStates
+	}
+	return nil
+}
+
 // GetProcess returns the Process field.
 func (x *MetricsBatch) GetProcess() *ProcessSample {
 	if x != nil {
@@ -350,11 +359,18 @@ func (x *DiskOp) GetError() bool {
 
 // DiskState captures a point-in-time disk state sample.
 type DiskState struct {
-	state         protoimpl.MessageState `protogen:"open.v1"`
-	DiskId        string                 `protobuf:"bytes,1,opt,name=disk_id,json=diskId,proto3" json:"disk_id,omitempty"`
-	QueueDepth    float64                `protobuf:"fixed64,2,opt,name=queue_depth,json=queueDepth,proto3" json:"queue_depth,omitempty"` // gauge
-	unknownFields protoimpl.UnknownFields
-	sizeCache     protoimpl.SizeCache
+	state               protoimpl.MessageState `protogen:"open.v1"`
+	DiskId              string                 `protobuf:"bytes,1,opt,name=disk_id,json=diskId,proto3" json:"disk_id,omitempty"`
+	QueueDepth          float64                `protobuf:"fixed64,2,opt,name=queue_depth,json=queueDepth,proto3" json:"queue_depth,omitempty"` // gauge
+	Missing             bool                   `protobuf:"varint,3,opt,name=missing,proto3" json:"missing,omitempty"`
+	NeedsRebuild        bool                   `protobuf:"varint,4,opt,name=needs_rebuild,json=needsRebuild,proto3" json:"needs_rebuild,omitempty"`
+	GrownDefects        uint64                 `protobuf:"varint,5,opt,name=grown_defects,json=grownDefects,proto3" json:"grown_defects,omitempty"`
+	ReallocatedSectors  uint64                 `protobuf:"varint,6,opt,name=reallocated_sectors,json=reallocatedSectors,proto3" json:"reallocated_sectors,omitempty"`
+	TemperatureCelsius  float64                `protobuf:"fixed64,7,opt,name=temperature_celsius,json=temperatureCelsius,proto3" json:"temperature_celsius,omitempty"` // gauge
+	PreFail             bool                   `protobuf:"varint,8,opt,name=pre_fail,json=preFail,proto3" json:"pre_fail,omitempty"`
+	RebuildSourceChunks uint64                 `protobuf:"varint,9,opt,name=rebuild_source_chunks,json=rebuildSourceChunks,proto3" json:"rebuild_source_chunks,omitempty"` // chunks the latest rebuild has read from this disk
+	unknownFields       protoimpl.UnknownFields
+	sizeCache           protoimpl.SizeCache
 }
 
 // Reset resets the message to its zero value.
@@ -407,6 +423,62 @@ func (x *DiskState) GetQueueDepth() float64 {
 	return 0
 }
 
+// GetMissing returns the Missing field.
+func (x *DiskState) GetMissing() bool {
+	if x != nil {
+		return x.Missing
+	}
+	return false
+}
+
+// GetNeedsRebuild returns the NeedsRebuild field.
+func (x *DiskState) GetNeedsRebuild() bool {
+	if x != nil {
+		return x.NeedsRebuild
+	}
+	return false
+}
+
+// GetGrownDefects returns the GrownDefects field.
+func (x *DiskState) GetGrownDefects() uint64 {
+	if x != nil {
+		return x.GrownDefects
+	}
+	return 0
+}
+
+// GetReallocatedSectors returns the ReallocatedSectors field.
+func (x *DiskState) GetReallocatedSectors() uint64 {
+	if x != nil {
+		return x.ReallocatedSectors
+	}
+	return 0
+}
+
+// GetTemperatureCelsius returns the TemperatureCelsius field.
+func (x *DiskState) GetTemperatureCelsius() float64 {
+	if x != nil {
+		return x.TemperatureCelsius
+	}
+	return 0
+}
+
+// GetPreFail returns the PreFail field.
+func (x *DiskState) GetPreFail() bool {
+	if x != nil {
+		return x.PreFail
+	}
+	return false
+}
+
+// GetRebuildSourceChunks returns the RebuildSourceChunks field.
+func (x *DiskState) GetRebuildSourceChunks() uint64 {
+	if x != nil {
+		return x.RebuildSourceChunks
+	}
+	return 0
+}
+
 // ----- RAID -----
 // RaidOp represents a single RAID IO operation sample.
 type RaidOp struct {
@@ -419,9 +491,16 @@ type RaidOp struct {
 	// RAID1: jeśli READ został obsłużony przez konkretny dysk (do metryki raid1_reads_from_disk)
 	ServedFromDiskId string `protobuf:"bytes,10,opt,name=served_from_disk_id,json=servedFromDiskId,proto3" json:"served_from_disk_id,omitempty"` // label: disk_id (tylko gdy ma sens)
 	// RAID3: dodatkowe liczniki specyficzne dla parzystości / partial stripe
-	Raid3ParityRead         bool `protobuf:"varint,20,opt,name=raid3_parity_read,json=raid3ParityRead,proto3" json:"raid3_parity_read,omitempty"`
-	Raid3ParityWrite        bool `protobuf:"varint,21,opt,name=raid3_parity_write,json=raid3ParityWrite,proto3" json:"raid3_parity_write,omitempty"`
-	Raid3PartialStripeWrite bool `protobuf:"varint,22,opt,name=raid3_partial_stripe_write,json=raid3PartialStripeWrite,proto3" json:"raid3_partial_stripe_write,omitempty"`
+	Raid3ParityRead         bool   `protobuf:"varint,20,opt,name=raid3_parity_read,json=raid3ParityRead,proto3" json:"raid3_parity_read,omitempty"`
+	Raid3ParityWrite        bool   `protobuf:"varint,21,opt,name=raid3_parity_write,json=raid3ParityWrite,proto3" json:"raid3_parity_write,omitempty"`
+	Raid3PartialStripeWrite bool   `protobuf:"varint,22,opt,name=raid3_partial_stripe_write,json=raid3PartialStripeWrite,proto3" json:"raid3_partial_stripe_write,omitempty"`
+	Degraded                bool   `protobuf:"varint,30,opt,name=degraded,proto3" json:"degraded,omitempty"`
+	Region                  uint32 `protobuf:"varint,40,opt,name=region,proto3" json:"region,omitempty"`
+	ReadAheadHitStripes     uint64 `protobuf:"varint,50,opt,name=read_ahead_hit_stripes,json=readAheadHitStripes,proto3" json:"read_ahead_hit_stripes,omitempty"`
+	ReadAheadMissStripes    uint64 `protobuf:"varint,51,opt,name=read_ahead_miss_stripes,json=readAheadMissStripes,proto3" json:"read_ahead_miss_stripes,omitempty"`
+	ParityCacheHitStripes   uint64 `protobuf:"varint,52,opt,name=parity_cache_hit_stripes,json=parityCacheHitStripes,proto3" json:"parity_cache_hit_stripes,omitempty"`
+	ParityCacheMissStripes  uint64 `protobuf:"varint,53,opt,name=parity_cache_miss_stripes,json=parityCacheMissStripes,proto3" json:"parity_cache_miss_stripes,omitempty"`
+	Background              bool   `protobuf:"varint,60,opt,name=background,proto3" json:"background,omitempty"` // rebuild or scrub traffic rather than user IO
 	unknownFields           protoimpl.UnknownFields
 	sizeCache               protoimpl.SizeCache
 }
@@ -532,6 +611,62 @@ func (x *RaidOp) GetRaid3PartialStripeWrite() bool {
 	return false
 }
 
+// GetDegraded returns the Degraded field.
+func (x *RaidOp) GetDegraded() bool {
+	if x != nil {
+		return x.Degraded
+	}
+	return false
+}
+
+// GetRegion returns the Region field.
+func (x *RaidOp) GetRegion() uint32 {
+	if x != nil {
+		return x.Region
+	}
+	return 0
+}
+
+// GetReadAheadHitStripes returns the ReadAheadHitStripes field.
+func (x *RaidOp) GetReadAheadHitStripes() uint64 {
+	if x != nil {
+		return x.ReadAheadHitStripes
+	}
+	return 0
+}
+
+// GetReadAheadMissStripes returns the ReadAheadMissStripes field.
+func (x *RaidOp) GetReadAheadMissStripes() uint64 {
+	if x != nil {
+		return x.ReadAheadMissStripes
+	}
+	return 0
+}
+
+// GetParityCacheHitStripes returns the ParityCacheHitStripes field.
+func (x *RaidOp) GetParityCacheHitStripes() uint64 {
+	if x != nil {
+		return x.ParityCacheHitStripes
+	}
+	return 0
+}
+
+// GetParityCacheMissStripes returns the ParityCacheMissStripes field.
+func (x *RaidOp) GetParityCacheMissStripes() uint64 {
+	if x != nil {
+		return x.ParityCacheMissStripes
+	}
+	return 0
+}
+
+// GetBackground returns the Background field.
+func (x *RaidOp) GetBackground() bool {
+	if x != nil {
+		return x.Background
+	}
+	return false
+}
+
 // RaidState captures a point-in-time RAID state sample.
 type RaidState struct {
 	state  protoimpl.MessageState `protogen:"open.v1"`
@@ -539,11 +674,33 @@ type RaidState struct {
 	// RAID1
 	Raid1ResyncProgress float64 `protobuf:"fixed64,10,opt,name=raid1_resync_progress,json=raid1ResyncProgress,proto3" json:"raid1_resync_progress,omitempty"` // 0..1 (gauge), jeśli nie dotyczy => pomiń (ustaw 0 albo nie wysyłaj)
 	// Ogólne stany RAID
-	Degraded          bool   `protobuf:"varint,20,opt,name=degraded,proto3" json:"degraded,omitempty"`                                              // gauge 0/1
-	FailedDisks       uint32 `protobuf:"varint,21,opt,name=failed_disks,json=failedDisks,proto3" json:"failed_disks,omitempty"`                     // gauge (liczba)
-	RebuildInProgress bool   `protobuf:"varint,22,opt,name=rebuild_in_progress,json=rebuildInProgress,proto3" json:"rebuild_in_progress,omitempty"` // gauge 0/1
-	unknownFields     protoimpl.UnknownFields
-	sizeCache         protoimpl.SizeCache
+	Degraded                  bool    `protobuf:"varint,20,opt,name=degraded,proto3" json:"degraded,omitempty"`                                              // gauge 0/1
+	FailedDisks               uint32  `protobuf:"varint,21,opt,name=failed_disks,json=failedDisks,proto3" json:"failed_disks,omitempty"`                     // gauge (liczba)
+	RebuildInProgress         bool    `protobuf:"varint,22,opt,name=rebuild_in_progress,json=rebuildInProgress,proto3" json:"rebuild_in_progress,omitempty"` // gauge 0/1
+	PoolUsedBytes             uint64  `protobuf:"varint,30,opt,name=pool_used_bytes,json=poolUsedBytes,proto3" json:"pool_used_bytes,omitempty"`
+	PoolCapacityBytes         uint64  `protobuf:"varint,31,opt,name=pool_capacity_bytes,json=poolCapacityBytes,proto3" json:"pool_capacity_bytes,omitempty"`
+	CheckStripesChecked       uint64  `protobuf:"varint,40,opt,name=check_stripes_checked,json=checkStripesChecked,proto3" json:"check_stripes_checked,omitempty"`
+	CheckRepairableStripes    uint64  `protobuf:"varint,41,opt,name=check_repairable_stripes,json=checkRepairableStripes,proto3" json:"check_repairable_stripes,omitempty"`
+	CheckUncorrectableStripes uint64  `protobuf:"varint,42,opt,name=check_uncorrectable_stripes,json=checkUncorrectableStripes,proto3" json:"check_uncorrectable_stripes,omitempty"`
+	UncorrectableStripes      uint64  `protobuf:"varint,43,opt,name=uncorrectable_stripes,json=uncorrectableStripes,proto3" json:"uncorrectable_stripes,omitempty"`
+	ReadOnly                  bool    `protobuf:"varint,44,opt,name=read_only,json=readOnly,proto3" json:"read_only,omitempty"`
+	RebuildStripesTotal       uint64  `protobuf:"varint,50,opt,name=rebuild_stripes_total,json=rebuildStripesTotal,proto3" json:"rebuild_stripes_total,omitempty"`
+	RebuildStripesDone        uint64  `protobuf:"varint,51,opt,name=rebuild_stripes_done,json=rebuildStripesDone,proto3" json:"rebuild_stripes_done,omitempty"`
+	RebuildBytesPerSecond     float64 `protobuf:"fixed64,52,opt,name=rebuild_bytes_per_second,json=rebuildBytesPerSecond,proto3" json:"rebuild_bytes_per_second,omitempty"`
+	RebuildEtaSeconds         float64 `protobuf:"fixed64,53,opt,name=rebuild_eta_seconds,json=rebuildEtaSeconds,proto3" json:"rebuild_eta_seconds,omitempty"`
+	ScrubStripesTotal         uint64  `protobuf:"varint,60,opt,name=scrub_stripes_total,json=scrubStripesTotal,proto3" json:"scrub_stripes_total,omitempty"`
+	ScrubStripesDone          uint64  `protobuf:"varint,61,opt,name=scrub_stripes_done,json=scrubStripesDone,proto3" json:"scrub_stripes_done,omitempty"`
+	ScrubMismatches           uint64  `protobuf:"varint,62,opt,name=scrub_mismatches,json=scrubMismatches,proto3" json:"scrub_mismatches,omitempty"`
+	StaleMembers              uint64  `protobuf:"varint,70,opt,name=stale_members,json=staleMembers,proto3" json:"stale_members,omitempty"`
+	BalanceBytesTotal         uint64  `protobuf:"varint,80,opt,name=balance_bytes_total,json=balanceBytesTotal,proto3" json:"balance_bytes_total,omitempty"` // data to move onto the members added by a grow
+	BalanceBytesDone          uint64  `protobuf:"varint,81,opt,name=balance_bytes_done,json=balanceBytesDone,proto3" json:"balance_bytes_done,omitempty"`
+	BalanceBytesPerSecond     float64 `protobuf:"fixed64,82,opt,name=balance_bytes_per_second,json=balanceBytesPerSecond,proto3" json:"balance_bytes_per_second,omitempty"`
+	WriteHolePolicy           string  `protobuf:"bytes,90,opt,name=write_hole_policy,json=writeHolePolicy,proto3" json:"write_hole_policy,omitempty"` // empty when the source does not report write-hole counters
+	TornStripes               uint64  `protobuf:"varint,91,opt,name=torn_stripes,json=tornStripes,proto3" json:"torn_stripes,omitempty"`
+	WriteHoleReplayedStripes  uint64  `protobuf:"varint,92,opt,name=write_hole_replayed_stripes,json=writeHoleReplayedStripes,proto3" json:"write_hole_replayed_stripes,omitempty"`
+	FullStripeRejectedWrites  uint64  `protobuf:"varint,93,opt,name=full_stripe_rejected_writes,json=fullStripeRejectedWrites,proto3" json:"full_stripe_rejected_writes,omitempty"`
+	unknownFields             protoimpl.UnknownFields
+	sizeCache                 protoimpl.SizeCache
 }
 
 // Reset resets the message to its zero value.
@@ -620,6 +777,182 @@ func (x *RaidState) GetRebuildInProgress() bool {
 	return false
 }
 
+// GetPoolUsedBytes returns the PoolUsedBytes field.
+func (x *RaidState) GetPoolUsedBytes() uint64 {
+	if x != nil {
+		return x.PoolUsedBytes
+	}
+	return 0
+}
+
+// GetPoolCapacityBytes returns the PoolCapacityBytes field.
+func (x *RaidState) GetPoolCapacityBytes() uint64 {
+	if x != nil {
+		return x.PoolCapacityBytes
+	}
+	return 0
+}
+
+// GetCheckStripesChecked returns the CheckStripesChecked field.
+func (x *RaidState) GetCheckStripesChecked() uint64 {
+	if x != nil {
+		return x.CheckStripesChecked
+	}
+	return 0
+}
+
+// GetCheckRepairableStripes returns the CheckRepairableStripes field.
+func (x *RaidState) GetCheckRepairableStripes() uint64 {
+	if x != nil {
+		return x.CheckRepairableStripes
+	}
+	return 0
+}
+
+// GetCheckUncorrectableStripes returns the CheckUncorrectableStripes field.
+func (x *RaidState) GetCheckUncorrectableStripes() uint64 {
+	if x != nil {
+		return x.CheckUncorrectableStripes
+	}
+	return 0
+}
+
+// GetUncorrectableStripes returns the UncorrectableStripes field.
+func (x *RaidState) GetUncorrectableStripes() uint64 {
+	if x != nil {
+		return x.UncorrectableStripes
+	}
+	return 0
+}
+
+// GetReadOnly returns the ReadOnly field.
+func (x *RaidState) GetReadOnly() bool {
+	if x != nil {
+		return x.ReadOnly
+	}
+	return false
+}
+
+// GetRebuildStripesTotal returns the RebuildStripesTotal field.
+func (x *RaidState) GetRebuildStripesTotal() uint64 {
+	if x != nil {
+		return x.RebuildStripesTotal
+	}
+	return 0
+}
+
+// GetRebuildStripesDone returns the RebuildStripesDone field.
+func (x *RaidState) GetRebuildStripesDone() uint64 {
+	if x != nil {
+		return x.RebuildStripesDone
+	}
+	return 0
+}
+
+// GetRebuildBytesPerSecond returns the RebuildBytesPerSecond field.
+func (x *RaidState) GetRebuildBytesPerSecond() float64 {
+	if x != nil {
+		return x.RebuildBytesPerSecond
+	}
+	return 0
+}
+
+// GetRebuildEtaSeconds returns the RebuildEtaSeconds field.
+func (x *RaidState) GetRebuildEtaSeconds() float64 {
+	if x != nil {
+		return x.RebuildEtaSeconds
+	}
+	return 0
+}
+
+// GetScrubStripesTotal returns the ScrubStripesTotal field.
+func (x *RaidState) GetScrubStripesTotal() uint64 {
+	if x != nil {
+		return x.ScrubStripesTotal
+	}
+	return 0
+}
+
+// GetScrubStripesDone returns the ScrubStripesDone field.
+func (x *RaidState) GetScrubStripesDone() uint64 {
+	if x != nil {
+		return x.ScrubStripesDone
+	}
+	return 0
+}
+
+// GetScrubMismatches returns the ScrubMismatches field.
+func (x *RaidState) GetScrubMismatches() uint64 {
+	if x != nil {
+		return x.ScrubMismatches
+	}
+	return 0
+}
+
+// GetStaleMembers returns the StaleMembers field.
+func (x *RaidState) GetStaleMembers() uint64 {
+	if x != nil {
+		return x.StaleMembers
+	}
+	return 0
+}
+
+// GetBalanceBytesTotal returns the BalanceBytesTotal field.
+func (x *RaidState) GetBalanceBytesTotal() uint64 {
+	if x != nil {
+		return x.BalanceBytesTotal
+	}
+	return 0
+}
+
+// GetBalanceBytesDone returns the BalanceBytesDone field.
+func (x *RaidState) GetBalanceBytesDone() uint64 {
+	if x != nil {
+		return x.BalanceBytesDone
+	}
+	return 0
+}
+
+// GetBalanceBytesPerSecond returns the BalanceBytesPerSecond field.
+func (x *RaidState) GetBalanceBytesPerSecond() float64 {
+	if x != nil {
+		return x.BalanceBytesPerSecond
+	}
+	return 0
+}
+
+// GetWriteHolePolicy returns the WriteHolePolicy field.
+func (x *RaidState) GetWriteHolePolicy() string {
+	if x != nil {
+		return x.WriteHolePolicy
+	}
+	return ""
+}
+
+// GetTornStripes returns the TornStripes field.
+func (x *RaidState) GetTornStripes() uint64 {
+	if x != nil {
+		return x.TornStripes
+	}
+	return 0
+}
+
+// GetWriteHoleReplayedStripes returns the WriteHoleReplayedStripes field.
+func (x *RaidState) GetWriteHoleReplayedStripes() uint64 {
+	if x != nil {
+		return x.WriteHoleReplayedStripes
+	}
+	return 0
+}
+
+// GetFullStripeRejectedWrites returns the FullStripeRejectedWrites field.
+func (x *RaidState) GetFullStripeRejectedWrites() uint64 {
+	if x != nil {
+		return x.FullStripeRejectedWrites
+	}
+	return 0
+}
+
 // FuseOp represents a single FUSE operation sample.
 type FuseOp struct {
 	state          protoimpl.MessageState `protogen:"open.v1"`
@@ -627,6 +960,8 @@ type FuseOp struct {
 	Bytes          uint64                 `protobuf:"varint,2,opt,name=bytes,proto3" json:"bytes,omitempty"`                                          // dla READ/WRITE
 	LatencySeconds float64                `protobuf:"fixed64,3,opt,name=latency_seconds,json=latencySeconds,proto3" json:"latency_seconds,omitempty"` // histogram dla READ/WRITE (>= 0)
 	Error          bool                   `protobuf:"varint,4,opt,name=error,proto3" json:"error,omitempty"`
+	Inode          uint64                 `protobuf:"varint,10,opt,name=inode,proto3" json:"inode,omitempty"`
+	NameHash       uint64                 `protobuf:"varint,11,opt,name=name_hash,json=nameHash,proto3" json:"name_hash,omitempty"`
 	unknownFields  protoimpl.UnknownFields
 	sizeCache      protoimpl.SizeCache
 }
@@ -697,20 +1032,151 @@ func (x *FuseOp) GetError() bool {
 	return false
 }
 
+// GetInode returns the Inode field.
+func (x *FuseOp) GetInode() uint64 {
+	if x != nil {
+		return x.Inode
+	}
+	return 0
+}
+
+// GetNameHash returns the NameHash field.
+func (x *FuseOp) GetNameHash() uint64 {
+	if x != nil {
+		return x.NameHash
+	}
+	return 0
+}
+
+// VolumeState reports capacity and space usage sampled from the filesystem layer.
+type VolumeState struct {
+	state             protoimpl.MessageState `protogen:"open.v1"`
+	RaidId            string                 `protobuf:"bytes,1,opt,name=raid_id,json=raidId,proto3" json:"raid_id,omitempty"`
+	CapacityBytes     uint64                 `protobuf:"varint,2,opt,name=capacity_bytes,json=capacityBytes,proto3" json:"capacity_bytes,omitempty"`
+	UsedBytes         uint64                 `protobuf:"varint,3,opt,name=used_bytes,json=usedBytes,proto3" json:"used_bytes,omitempty"`
+	FreeBytes         uint64                 `protobuf:"varint,4,opt,name=free_bytes,json=freeBytes,proto3" json:"free_bytes,omitempty"`
+	FileCount         uint64                 `protobuf:"varint,5,opt,name=file_count,json=fileCount,proto3" json:"file_count,omitempty"`
+	DirtyBytes        uint64                 `protobuf:"varint,6,opt,name=dirty_bytes,json=dirtyBytes,proto3" json:"dirty_bytes,omitempty"`                        // file writes held in the write buffer
+	SnapshotUsedBytes uint64                 `protobuf:"varint,7,opt,name=snapshot_used_bytes,json=snapshotUsedBytes,proto3" json:"snapshot_used_bytes,omitempty"` // blocks preserved by copy-on-write for snapshots
+	SnapshotPoolBytes uint64                 `protobuf:"varint,8,opt,name=snapshot_pool_bytes,json=snapshotPoolBytes,proto3" json:"snapshot_pool_bytes,omitempty"` // copy-on-write pool reserved for snapshots
+	unknownFields     protoimpl.UnknownFields
+	sizeCache         protoimpl.SizeCache
+}
+
+// Reset resets the message to its zero value.
+func (x *VolumeState) Reset() {
+	*x = VolumeState{}
+	mi := &file_metrics_v1_ingest_proto_msgTypes[6]
+	ms := protoimpl.X.MessageStateOf(protoimpl.Pointer(x))
+	ms.StoreMessageInfo(mi)
+}
+
+// String returns the string representation of the value.
+func (x *VolumeState) String() string {
+	return protoimpl.X.MessageStringOf(x)
+}
+
+// ProtoMessage marks the type as a protobuf message.
+func (*VolumeState) ProtoMessage() {}
+
+// ProtoReflect returns the reflective view of the message.
+func (x *VolumeState) ProtoReflect() protoreflect.Message {
+	mi := &file_metrics_v1_ingest_proto_msgTypes[6]
+	if x != nil {
+		ms := protoimpl.X.MessageStateOf(protoimpl.Pointer(x))
+		if ms.LoadMessageInfo() == nil {
+			ms.StoreMessageInfo(mi)
+		}
+		return ms
+	}
+	return mi.MessageOf(x)
+}
+
+// Descriptor returns the legacy message descriptor. Deprecated: Use VolumeState.ProtoReflect.Descriptor instead.
+func (*VolumeState) Descriptor() ([]byte, []int) {
+	return file_metrics_v1_ingest_proto_rawDescGZIP(), []int{6}
+}
+
+// GetRaidId returns the RaidId field.
+func (x *VolumeState) GetRaidId() string {
+	if x != nil {
+		return x.RaidId
+	}
+	return ""
+}
+
+// GetCapacityBytes returns the CapacityBytes field.
+func (x *VolumeState) GetCapacityBytes() uint64 {
+	if x != nil {
+		return x.CapacityBytes
+	}
+	return 0
+}
+
+// GetUsedBytes returns the UsedBytes field.
+func (x *VolumeState) GetUsedBytes() uint64 {
+	if x != nil {
+		return x.UsedBytes
+	}
+	return 0
+}
+
+// GetFreeBytes returns the FreeBytes field.
+func (x *VolumeState) GetFreeBytes() uint64 {
+	if x != nil {
+		return x.FreeBytes
+	}
+	return 0
+}
+
+// GetFileCount returns the FileCount field.
+func (x *VolumeState) GetFileCount() uint64 {
+	if x != nil {
+		return x.FileCount
+	}
+	return 0
+}
+
+// GetDirtyBytes returns the DirtyBytes field.
+func (x *VolumeState) GetDirtyBytes() uint64 {
+	if x != nil {
+		return x.DirtyBytes
+	}
+	return 0
+}
+
+// GetSnapshotUsedBytes returns the SnapshotUsedBytes field.
+func (x *VolumeState) GetSnapshotUsedBytes() uint64 {
+	if x != nil {
+		return x.SnapshotUsedBytes
+	}
+	return 0
+}
+
+// GetSnapshotPoolBytes returns the SnapshotPoolBytes field.
+func (x *VolumeState) GetSnapshotPoolBytes() uint64 {
+	if x != nil {
+		return x.SnapshotPoolBytes
+	}
+	return 0
+}
+
 // ----- PROCESS -----
 // ProcessSample records process-level metrics for the simulator.
 type ProcessSample struct {
-	state               protoimpl.MessageState `protogen:"open.v1"`
-	CpuSeconds          float64                `protobuf:"fixed64,1,opt,name=cpu_seconds,json=cpuSeconds,proto3" json:"cpu_seconds,omitempty"`                             // gauge
-	ResidentMemoryBytes uint64                 `protobuf:"varint,2,opt,name=resident_memory_bytes,json=residentMemoryBytes,proto3" json:"resident_memory_bytes,omitempty"` // gauge
-	unknownFields       protoimpl.UnknownFields
-	sizeCache           protoimpl.SizeCache
+	state                 protoimpl.MessageState `protogen:"open.v1"`
+	CpuSeconds            float64                `protobuf:"fixed64,1,opt,name=cpu_seconds,json=cpuSeconds,proto3" json:"cpu_seconds,omitempty"`                                    // gauge
+	ResidentMemoryBytes   uint64                 `protobuf:"varint,2,opt,name=resident_memory_bytes,json=residentMemoryBytes,proto3" json:"resident_memory_bytes,omitempty"`        // gauge
+	CompressionRatio      float64                `protobuf:"fixed64,3,opt,name=compression_ratio,json=compressionRatio,proto3" json:"compression_ratio,omitempty"`                  // logical bytes per stored byte across compressed volumes
+	CompressionCpuSeconds float64                `protobuf:"fixed64,4,opt,name=compression_cpu_seconds,json=compressionCpuSeconds,proto3" json:"compression_cpu_seconds,omitempty"` // time spent compressing and decompressing blocks
+	unknownFields         protoimpl.UnknownFields
+	sizeCache             protoimpl.SizeCache
 }
 
 // Reset resets the message to its zero value.
 func (x *ProcessSample) Reset() {
 	*x = ProcessSample{}
-	mi := &file_metrics_v1_ingest_proto_msgTypes[6]
+	mi := &file_metrics_v1_ingest_proto_msgTypes[7]
 	ms := protoimpl.X.MessageStateOf(protoimpl.Pointer(x))
 	ms.StoreMessageInfo(mi)
 }
@@ -725,7 +1191,7 @@ func (*ProcessSample) ProtoMessage() {}
 
 // ProtoReflect returns the reflective view of the message.
 func (x *ProcessSample) ProtoReflect() protoreflect.Message {
-	mi := &file_metrics_v1_ingest_proto_msgTypes[6]
+	mi := &file_metrics_v1_ingest_proto_msgTypes[7]
 	if x != nil {
 		ms := protoimpl.X.MessageStateOf(protoimpl.Pointer(x))
 		if ms.LoadMessageInfo() == nil {
@@ -738,7 +1204,7 @@ func (x *ProcessSample) ProtoReflect() protoreflect.Message {
 
 // Descriptor returns the legacy message descriptor. Deprecated: Use ProcessSample.ProtoReflect.Descriptor instead.
 func (*ProcessSample) Descriptor() ([]byte, []int) {
-	return file_metrics_v1_ingest_proto_rawDescGZIP(), []int{6}
+	return file_metrics_v1_ingest_proto_rawDescGZIP(), []int{7}
 }
 
 // GetCpuSeconds returns the CpuSeconds field.
@@ -757,6 +1223,22 @@ func (x *ProcessSample) GetResidentMemoryBytes() uint64 {
 	return 0
 }
 
+// GetCompressionRatio returns the CompressionRatio field.
+func (x *ProcessSample) GetCompressionRatio() float64 {
+	if x != nil {
+		return x.CompressionRatio
+	}
+	return 0
+}
+
+// GetCompressionCpuSeconds returns the CompressionCpuSeconds field.
+func (x *ProcessSample) GetCompressionCpuSeconds() float64 {
+	if x != nil {
+		return x.CompressionCpuSeconds
+	}
+	return 0
+}
+
 // PushResponse summarizes the ingestion results for a streamed push.
 type PushResponse struct {
 	state           protoimpl.MessageState `protogen:"open.v1"`
@@ -770,7 +1252,7 @@ type PushResponse struct {
 // Reset resets the message to its zero value.
 func (x *PushResponse) Reset() {
 	*x = PushResponse{}
-	mi := &file_metrics_v1_ingest_proto_msgTypes[7]
+	mi := &file_metrics_v1_ingest_proto_msgTypes[8]
 	ms := protoimpl.X.MessageStateOf(protoimpl.Pointer(x))
 	ms.StoreMessageInfo(mi)
 }
@@ -785,7 +1267,7 @@ func (*PushResponse) ProtoMessage() {}
 
 // ProtoReflect returns the reflective view of the message.
 func (x *PushResponse) ProtoReflect() protoreflect.Message {
-	mi := &file_metrics_v1_ingest_proto_msgTypes[7]
+	mi := &file_metrics_v1_ingest_proto_msgTypes[8]
 	if x != nil {
 		ms := protoimpl.X.MessageStateOf(protoimpl.Pointer(x))
 		if ms.LoadMessageInfo() == nil {
@@ -798,7 +1280,7 @@ func (x *PushResponse) ProtoReflect() protoreflect.Message {
 
 // Descriptor returns the legacy message descriptor. Deprecated: Use PushResponse.ProtoReflect.Descriptor instead.
 func (*PushResponse) Descriptor() ([]byte, []int) {
-	return file_metrics_v1_ingest_proto_rawDescGZIP(), []int{7}
+	return file_metrics_v1_ingest_proto_rawDescGZIP(), []int{8}
 }
 
 // GetAcceptedBatches returns the AcceptedBatches field.
@@ -830,7 +1312,7 @@ var File_metrics_v1_ingest_proto protoreflect.FileDescriptor
 const file_metrics_v1_ingest_proto_rawDesc = "" +
 	"\n" +
 	"\x17metrics/v1/ingest.proto\x12\n" +
-	"metrics.v1\x1a\x1fgoogle/protobuf/timestamp.proto\"\xae\x03\n" +
+	"metrics.v1\x1a\x1fgoogle/protobuf/timestamp.proto\"\xec\x03\n" +
 	"\fMetricsBatch\x12\x1b\n" +
 	"\tsource_id\x18\x01 \x01(\tR\bsourceId\x12\x15\n" +
 	"\x06seq_no\x18\x02 \x01(\x04R\x05seqNo\x128\n" +
@@ -842,18 +1324,26 @@ const file_metrics_v1_ingest_proto_rawDesc = "" +
 	"\braid_ops\x18\x14 \x03(\v2\x12.metrics.v1.RaidOpR\araidOps\x126\n" +
 	"\vraid_states\x18\x15 \x03(\v2\x15.metrics.v1.RaidStateR\n" +
 	"raidStates\x12-\n" +
-	"\bfuse_ops\x18\x1e \x03(\v2\x12.metrics.v1.FuseOpR\afuseOps\x123\n" +
+	"\bfuse_ops\x18\x1e \x03(\v2\x12.metrics.v1.FuseOpR\afuseOps\x12<\n" +
+	"\rvolume_states\x18\x1f \x03(\v2\x17.metrics.v1.VolumeStateR\fvolumeStates\x123\n" +
 	"\aprocess\x18( \x01(\v2\x19.metrics.v1.ProcessSampleR\aprocess\"\x9c\x01\n" +
 	"\x06DiskOp\x12\x17\n" +
 	"\adisk_id\x18\x01 \x01(\tR\x06diskId\x12$\n" +
 	"\x02op\x18\x02 \x01(\x0e2\x14.metrics.v1.IoOpTypeR\x02op\x12\x14\n" +
 	"\x05bytes\x18\x03 \x01(\x04R\x05bytes\x12'\n" +
 	"\x0flatency_seconds\x18\x04 \x01(\x01R\x0elatencySeconds\x12\x14\n" +
-	"\x05error\x18\x05 \x01(\bR\x05error\"E\n" +
+	"\x05error\x18\x05 \x01(\bR\x05error\"\xda\x02\n" +
 	"\tDiskState\x12\x17\n" +
 	"\adisk_id\x18\x01 \x01(\tR\x06diskId\x12\x1f\n" +
 	"\vqueue_depth\x18\x02 \x01(\x01R\n" +
-	"queueDepth\"\xe2\x02\n" +
+	"queueDepth\x12\x18\n" +
+	"\amissing\x18\x03 \x01(\bR\amissing\x12#\n" +
+	"\rneeds_rebuild\x18\x04 \x01(\bR\fneedsRebuild\x12#\n" +
+	"\rgrown_defects\x18\x05 \x01(\x04R\fgrownDefects\x12/\n" +
+	"\x13reallocated_sectors\x18\x06 \x01(\x04R\x12reallocatedSectors\x12/\n" +
+	"\x13temperature_celsius\x18\a \x01(\x01R\x12temperatureCelsius\x12\x19\n" +
+	"\bpre_fail\x18\b \x01(\bR\apreFail\x122\n" +
+	"\x15rebuild_source_chunks\x18\t \x01(\x04R\x13rebuildSourceChunks\"\x96\x05\n" +
 	"\x06RaidOp\x12\x17\n" +
 	"\araid_id\x18\x01 \x01(\tR\x06raidId\x12$\n" +
 	"\x02op\x18\x02 \x01(\x0e2\x14.metrics.v1.IoOpTypeR\x02op\x12\x14\n" +
@@ -864,23 +1354,73 @@ const file_metrics_v1_ingest_proto_rawDesc = "" +
 	" \x01(\tR\x10servedFromDiskId\x12*\n" +
 	"\x11raid3_parity_read\x18\x14 \x01(\bR\x0fraid3ParityRead\x12,\n" +
 	"\x12raid3_parity_write\x18\x15 \x01(\bR\x10raid3ParityWrite\x12;\n" +
-	"\x1araid3_partial_stripe_write\x18\x16 \x01(\bR\x17raid3PartialStripeWrite\"\xc7\x01\n" +
+	"\x1araid3_partial_stripe_write\x18\x16 \x01(\bR\x17raid3PartialStripeWrite\x12\x1a\n" +
+	"\bdegraded\x18\x1e \x01(\bR\bdegraded\x12\x16\n" +
+	"\x06region\x18( \x01(\rR\x06region\x123\n" +
+	"\x16read_ahead_hit_stripes\x182 \x01(\x04R\x13readAheadHitStripes\x125\n" +
+	"\x17read_ahead_miss_stripes\x183 \x01(\x04R\x14readAheadMissStripes\x127\n" +
+	"\x18parity_cache_hit_stripes\x184 \x01(\x04R\x15parityCacheHitStripes\x129\n" +
+	"\x19parity_cache_miss_stripes\x185 \x01(\x04R\x16parityCacheMissStripes\x12\x1e\n" +
+	"\n" +
+	"background\x18< \x01(\bR\n" +
+	"background\"\x80\n" +
+	"\n" +
 	"\tRaidState\x12\x17\n" +
 	"\araid_id\x18\x01 \x01(\tR\x06raidId\x122\n" +
 	"\x15raid1_resync_progress\x18\n" +
 	" \x01(\x01R\x13raid1ResyncProgress\x12\x1a\n" +
 	"\bdegraded\x18\x14 \x01(\bR\bdegraded\x12!\n" +
 	"\ffailed_disks\x18\x15 \x01(\rR\vfailedDisks\x12.\n" +
-	"\x13rebuild_in_progress\x18\x16 \x01(\bR\x11rebuildInProgress\"\x85\x01\n" +
+	"\x13rebuild_in_progress\x18\x16 \x01(\bR\x11rebuildInProgress\x12&\n" +
+	"\x0fpool_used_bytes\x18\x1e \x01(\x04R\rpoolUsedBytes\x12.\n" +
+	"\x13pool_capacity_bytes\x18\x1f \x01(\x04R\x11poolCapacityBytes\x122\n" +
+	"\x15check_stripes_checked\x18( \x01(\x04R\x13checkStripesChecked\x128\n" +
+	"\x18check_repairable_stripes\x18) \x01(\x04R\x16checkRepairableStripes\x12>\n" +
+	"\x1bcheck_uncorrectable_stripes\x18* \x01(\x04R\x19checkUncorrectableStripes\x123\n" +
+	"\x15uncorrectable_stripes\x18+ \x01(\x04R\x14uncorrectableStripes\x12\x1b\n" +
+	"\tread_only\x18, \x01(\bR\breadOnly\x122\n" +
+	"\x15rebuild_stripes_total\x182 \x01(\x04R\x13rebuildStripesTotal\x120\n" +
+	"\x14rebuild_stripes_done\x183 \x01(\x04R\x12rebuildStripesDone\x127\n" +
+	"\x18rebuild_bytes_per_second\x184 \x01(\x01R\x15rebuildBytesPerSecond\x12.\n" +
+	"\x13rebuild_eta_seconds\x185 \x01(\x01R\x11rebuildEtaSeconds\x12.\n" +
+	"\x13scrub_stripes_total\x18< \x01(\x04R\x11scrubStripesTotal\x12,\n" +
+	"\x12scrub_stripes_done\x18= \x01(\x04R\x10scrubStripesDone\x12)\n" +
+	"\x10scrub_mismatches\x18> \x01(\x04R\x0fscrubMismatches\x12#\n" +
+	"\rstale_members\x18F \x01(\x04R\fstaleMembers\x12.\n" +
+	"\x13balance_bytes_total\x18P \x01(\x04R\x11balanceBytesTotal\x12,\n" +
+	"\x12balance_bytes_done\x18Q \x01(\x04R\x10balanceBytesDone\x127\n" +
+	"\x18balance_bytes_per_second\x18R \x01(\x01R\x15balanceBytesPerSecond\x12*\n" +
+	"\x11write_hole_policy\x18Z \x01(\tR\x0fwriteHolePolicy\x12!\n" +
+	"\ftorn_stripes\x18[ \x01(\x04R\vtornStripes\x12=\n" +
+	"\x1bwrite_hole_replayed_stripes\x18\\ \x01(\x04R\x18writeHoleReplayedStripes\x12=\n" +
+	"\x1bfull_stripe_rejected_writes\x18] \x01(\x04R\x18fullStripeRejectedWrites\"\xb8\x01\n" +
 	"\x06FuseOp\x12&\n" +
 	"\x02op\x18\x01 \x01(\x0e2\x16.metrics.v1.FuseOpTypeR\x02op\x12\x14\n" +
 	"\x05bytes\x18\x02 \x01(\x04R\x05bytes\x12'\n" +
 	"\x0flatency_seconds\x18\x03 \x01(\x01R\x0elatencySeconds\x12\x14\n" +
-	"\x05error\x18\x04 \x01(\bR\x05error\"d\n" +
+	"\x05error\x18\x04 \x01(\bR\x05error\x12\x14\n" +
+	"\x05inode\x18\n" +
+	" \x01(\x04R\x05inode\x12\x1b\n" +
+	"\tname_hash\x18\v \x01(\x04R\bnameHash\"\xab\x02\n" +
+	"\vVolumeState\x12\x17\n" +
+	"\araid_id\x18\x01 \x01(\tR\x06raidId\x12%\n" +
+	"\x0ecapacity_bytes\x18\x02 \x01(\x04R\rcapacityBytes\x12\x1d\n" +
+	"\n" +
+	"used_bytes\x18\x03 \x01(\x04R\tusedBytes\x12\x1d\n" +
+	"\n" +
+	"free_bytes\x18\x04 \x01(\x04R\tfreeBytes\x12\x1d\n" +
+	"\n" +
+	"file_count\x18\x05 \x01(\x04R\tfileCount\x12\x1f\n" +
+	"\vdirty_bytes\x18\x06 \x01(\x04R\n" +
+	"dirtyBytes\x12.\n" +
+	"\x13snapshot_used_bytes\x18\a \x01(\x04R\x11snapshotUsedBytes\x12.\n" +
+	"\x13snapshot_pool_bytes\x18\b \x01(\x04R\x11snapshotPoolBytes\"\xc9\x01\n" +
 	"\rProcessSample\x12\x1f\n" +
 	"\vcpu_seconds\x18\x01 \x01(\x01R\n" +
 	"cpuSeconds\x122\n" +
-	"\x15resident_memory_bytes\x18\x02 \x01(\x04R\x13residentMemoryBytes\"\x8f\x01\n" +
+	"\x15resident_memory_bytes\x18\x02 \x01(\x04R\x13residentMemoryBytes\x12+\n" +
+	"\x11compression_ratio\x18\x03 \x01(\x01R\x10compressionRatio\x126\n" +
+	"\x17compression_cpu_seconds\x18\x04 \x01(\x01R\x15compressionCpuSeconds\"\x8f\x01\n" +
 	"\fPushResponse\x12)\n" +
 	"\x10accepted_batches\x18\x01 \x01(\x04R\x0facceptedBatches\x12)\n" +
 	"\x10accepted_samples\x18\x02 \x01(\x04R\x0facceptedSamples\x12)\n" +
@@ -913,7 +1453,7 @@ func file_metrics_v1_ingest_proto_rawDescGZIP() []byte {
 }
 
 var file_metrics_v1_ingest_proto_enumTypes = make([]protoimpl.EnumInfo, 2)
-var file_metrics_v1_ingest_proto_msgTypes = make([]protoimpl.MessageInfo, 8)
+var file_metrics_v1_ingest_proto_msgTypes = make([]protoimpl.MessageInfo, 9)
 var file_metrics_v1_ingest_proto_goTypes = []any{
 	(IoOpType)(0),                 // 0: metrics.v1.IoOpType
 	(FuseOpType)(0),               // 1: metrics.v1.FuseOpType
@@ -923,28 +1463,30 @@ var file_metrics_v1_ingest_proto_goTypes = []any{
 	(*RaidOp)(nil),                // 5: metrics.v1.RaidOp
 	(*RaidState)(nil),             // 6: metrics.v1.RaidState
 	(*FuseOp)(nil),                // 7: metrics.v1.FuseOp
-	(*ProcessSample)(nil),         // 8: metrics.v1.ProcessSample
-	(*PushResponse)(nil),          // 9: metrics.v1.PushResponse
-	(*timestamppb.Timestamp)(nil), // 10: google.protobuf.Timestamp
+	(*VolumeState)(nil),           // 8: metrics.v1.VolumeState
+	(*ProcessSample)(nil),         // 9: metrics.v1.ProcessSample
+	(*PushResponse)(nil),          // 10: metrics.v1.PushResponse
+	(*timestamppb.Timestamp)(nil), // 11: google.protobuf.Timestamp
 }
 var file_metrics_v1_ingest_proto_depIdxs = []int32{
-	10, // 0: metrics.v1.MetricsBatch.timestamp:type_name -> google.protobuf.Timestamp
+	11, // 0: metrics.v1.MetricsBatch.timestamp:type_name -> google.protobuf.Timestamp
 	3,  // 1: metrics.v1.MetricsBatch.disk_ops:type_name -> metrics.v1.DiskOp
 	4,  // 2: metrics.v1.MetricsBatch.disk_states:type_name -> metrics.v1.DiskState
 	5,  // 3: metrics.v1.MetricsBatch.raid_ops:type_name -> metrics.v1.RaidOp
 	6,  // 4: metrics.v1.MetricsBatch.raid_states:type_name -> metrics.v1.RaidState
 	7,  // 5: metrics.v1.MetricsBatch.fuse_ops:type_name -> metrics.v1.FuseOp
-	8,  // 6: metrics.v1.MetricsBatch.process:type_name -> metrics.v1.ProcessSample
-	0,  // 7: metrics.v1.DiskOp.op:type_name -> metrics.v1.IoOpType
-	0,  // 8: metrics.v1.RaidOp.op:type_name -> metrics.v1.IoOpType
-	1,  // 9: metrics.v1.FuseOp.op:type_name -> metrics.v1.FuseOpType
-	2,  // 10: metrics.v1.MetricsIngestor.Push:input_type -> metrics.v1.MetricsBatch
-	9,  // 11: metrics.v1.MetricsIngestor.Push:output_type -> metrics.v1.PushResponse
-	11, // [11:12] is the sub-list for method output_type
-	10, // [10:11] is the sub-list for method input_type
-	10, // [10:10] is the sub-list for extension type_name
-	10, // [10:10] is the sub-list for extension extendee
-	0,  // [0:10] is the sub-list for field type_name
+	8,  // 6: metrics.v1.MetricsBatch.volume_states:type_name -> metrics.v1.VolumeState
+	9,  // 7: metrics.v1.MetricsBatch.process:type_name -> metrics.v1.ProcessSample
+	0,  // 8: metrics.v1.DiskOp.op:type_name -> metrics.v1.IoOpType
+	0,  // 9: metrics.v1.RaidOp.op:type_name -> metrics.v1.IoOpType
+	1,  // 10: metrics.v1.FuseOp.op:type_name -> metrics.v1.FuseOpType
+	2,  // 11: metrics.v1.MetricsIngestor.Push:input_type -> metrics.v1.MetricsBatch
+	10, // 12: metrics.v1.MetricsIngestor.Push:output_type -> metrics.v1.PushResponse
+	12, // [12:13] is the sub-list for method output_type
+	11, // [11:12] is the sub-list for method input_type
+	11, // [11:11] is the sub-list for extension type_name
+	11, // [11:11] is the sub-list for extension extendee
+	0,  // [0:11] is the sub-list for field type_name
 }
 
 func init() { file_metrics_v1_ingest_proto_init() }
@@ -958,7 +1500,7 @@ func file_metrics_v1_ingest_proto_init() {
 			GoPackagePath: reflect.TypeOf(x{}).PkgPath(),
 			RawDescriptor: unsafe.Slice(unsafe.StringData(file_metrics_v1_ingest_proto_rawDesc), len(file_metrics_v1_ingest_proto_rawDesc)),
 			NumEnums:      2,
-			NumMessages:   8,
+			NumMessages:   9,
 			NumExtensions: 0,
 			NumServices:   1,
 		},
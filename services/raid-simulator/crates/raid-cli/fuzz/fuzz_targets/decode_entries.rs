@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raid_cli_fuzz::constants::{HEADER_SIZE, MAX_FILES};
+use raid_cli_fuzz::metadata::{Header, decode_entries};
+
+fuzz_target!(|data: &[u8]| {
+    let Some(header) = Header::from_bytes(data) else {
+        return;
+    };
+    if let Ok(entries) = decode_entries(&data[HEADER_SIZE..], &header) {
+        assert_eq!(entries.len(), MAX_FILES);
+        for entry in entries.iter().filter(|entry| entry.used) {
+            assert!(entry.offset.saturating_add(entry.size.max(1)) <= header.next_free);
+        }
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raid_cli_fuzz::metadata::Entry;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(entry) = Entry::from_bytes(data) {
+        let decoded = Entry::from_bytes(&entry.to_bytes()).expect("re-encoded entry decodes");
+        assert_eq!(
+            (decoded.used, decoded.offset, decoded.size),
+            (entry.used, entry.offset, entry.size)
+        );
+    }
+});
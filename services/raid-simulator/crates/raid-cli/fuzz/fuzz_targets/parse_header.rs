@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raid_cli_fuzz::metadata::Header;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(header) = Header::from_bytes(data) {
+        assert_eq!(Header::from_bytes(&header.to_bytes()), Some(header));
+    }
+});
@@ -0,0 +1,9 @@
+//! On-disk metadata parsers of `raid-cli`, built standalone for fuzzing.
+//!
+//! `raid-cli` only ships a binary, so the format modules are compiled here by
+//! path. Run a target with `cargo +nightly fuzz run <target>` from this directory.
+
+#[path = "../../src/fs/constants.rs"]
+pub mod constants;
+#[path = "../../src/fs/metadata.rs"]
+pub mod metadata;
@@ -0,0 +1,118 @@
+//! Skewed access patterns for the bench and synthetic workloads.
+//!
+//! Offsets are drawn block by block from a Zipf distribution: block `k` of the
+//! volume is picked with probability proportional to `1 / (k + 1)^skew`, so the
+//! hottest blocks sit at the start of the volume. A skew of `0` is uniform.
+//! Heat is reported over `HEAT_REGIONS` equal slices of the volume.
+
+use anyhow::Result;
+use rand::Rng;
+use rand_distr::{Distribution, Zipf};
+
+/// `HEAT_REGIONS` is the number of equal logical regions heat is reported for.
+pub const HEAT_REGIONS: usize = 16;
+
+/// `ZipfOffsets` draws block-aligned offsets with Zipf-distributed popularity.
+#[derive(Clone, Debug)]
+pub struct ZipfOffsets {
+    dist: Zipf<f64>,
+    block: u64,
+}
+
+impl ZipfOffsets {
+    #[allow(clippy::cast_precision_loss)]
+    /// `new` builds a generator over the whole blocks of `capacity`.
+    ///
+    /// # Arguments
+    /// * `capacity` - Logical size of the volume in bytes.
+    /// * `block` - Size of each access in bytes.
+    /// * `skew` - Zipf exponent; `0` is uniform, larger values are hotter.
+    ///
+    /// # Errors
+    /// Returns an error if the skew is negative or not finite.
+    pub fn new(capacity: u64, block: u64, skew: f64) -> Result<Self> {
+        if !skew.is_finite() || skew < 0.0 {
+            anyhow::bail!("zipf skew must be a non-negative number, got {skew}");
+        }
+        let block = block.max(1);
+        let blocks = (capacity / block).max(1);
+        let dist = Zipf::new(blocks as f64, skew)
+            .map_err(|err| anyhow::anyhow!("invalid zipf skew {skew}: {err}"))?;
+        Ok(Self { dist, block })
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    /// `sample` returns the offset of the next access.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u64 {
+        let rank = self.dist.sample(rng) as u64;
+        rank.saturating_sub(1) * self.block
+    }
+}
+
+#[must_use]
+/// `region` returns the 0-based heat region of `offset` within `capacity`.
+pub fn region(offset: u64, capacity: u64) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    let slice = u128::from(offset.min(capacity - 1)) * HEAT_REGIONS as u128;
+    usize::try_from(slice / u128::from(capacity)).unwrap_or(HEAT_REGIONS - 1)
+}
+
+/// `RegionHeat` counts accesses per heat region.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegionHeat(pub [u64; HEAT_REGIONS]);
+
+impl RegionHeat {
+    /// `record` counts one access at `offset`.
+    pub fn record(&mut self, offset: u64, capacity: u64) {
+        self.0[region(offset, capacity)] += 1;
+    }
+
+    #[must_use]
+    /// `total` returns the number of recorded accesses.
+    pub fn total(&self) -> u64 {
+        self.0.iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn skew_concentrates_accesses_in_the_first_region() {
+        let capacity = 1 << 20;
+        let mut rng = StdRng::seed_from_u64(1);
+        let heat = |skew: f64, rng: &mut StdRng| {
+            let offsets = ZipfOffsets::new(capacity, 4096, skew).expect("offsets");
+            let mut heat = RegionHeat::default();
+            for _ in 0..4096 {
+                let offset = offsets.sample(rng);
+                assert_eq!(offset % 4096, 0);
+                assert!(offset < capacity);
+                heat.record(offset, capacity);
+            }
+            heat
+        };
+
+        let uniform = heat(0.0, &mut rng);
+        let skewed = heat(1.2, &mut rng);
+
+        assert!(uniform.0.iter().all(|&n| n > 128));
+        assert!(skewed.0[0] > skewed.total() / 2);
+        assert!(ZipfOffsets::new(capacity, 4096, -1.0).is_err());
+    }
+
+    #[test]
+    fn region_splits_the_volume_evenly() {
+        assert_eq!(region(0, 1600), 0);
+        assert_eq!(region(99, 1600), 0);
+        assert_eq!(region(100, 1600), 1);
+        assert_eq!(region(1599, 1600), HEAT_REGIONS - 1);
+        assert_eq!(region(5000, 1600), HEAT_REGIONS - 1);
+        assert_eq!(region(7, 0), 0);
+    }
+}
@@ -0,0 +1,169 @@
+//! Several arrays mounted by one `fuse` process.
+//!
+//! `--arrays` names a `.toml`, `.yaml` or `.yml` file with one `array` entry per
+//! mount, simulating a host with several md devices:
+//!
+//! ```toml
+//! [[array]]
+//! name = "md0"
+//! mount_point = "/mnt/md0"
+//! disk_dir = "/var/lib/raid/md0"
+//! raid = "raid1"
+//! disks = 2
+//!
+//! [[array]]
+//! name = "md1"
+//! mount_point = "/mnt/md1"
+//! disk_dir = "/var/lib/raid/md1"
+//! raid = "raid3"
+//! disks = 4
+//! ```
+//!
+//! Each array reports its metrics under its name, with disk identifiers such as
+//! `md1/disk2`, and takes the settings it leaves out from the command line.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::cli::RaidMode;
+
+/// `ArraySpec` describes one array and where it is mounted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArraySpec {
+    /// RAID identifier of the array's metrics.
+    pub name: String,
+    pub mount_point: PathBuf,
+    pub disk_dir: PathBuf,
+    pub raid: Option<RaidMode>,
+    pub disks: Option<usize>,
+    pub disk_size: Option<u64>,
+    pub thin_size: Option<u64>,
+    pub compress_size: Option<u64>,
+    /// Timed disk failures of this array, instead of `--failure-schedule`.
+    pub failure_schedule: Option<PathBuf>,
+    pub read_only: Option<bool>,
+    /// Source name of the mount; defaults to the array name.
+    pub fsname: Option<String>,
+}
+
+/// `ArraySet` lists the arrays of an `--arrays` file.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArraySet {
+    #[serde(rename = "array")]
+    pub arrays: Vec<ArraySpec>,
+}
+
+impl ArraySet {
+    /// `load` reads the arrays from a `.toml`, `.yaml` or `.yml` file.
+    ///
+    /// # Arguments
+    /// * `path` - Arrays file; the extension selects the format.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, lists no array, or
+    /// two arrays share a name, mount point or disk directory.
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read arrays file {}", path.display()))?;
+        let set: Self = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&raw).map_err(anyhow::Error::from),
+            Some("yaml" | "yml") => serde_yaml::from_str(&raw).map_err(anyhow::Error::from),
+            _ => Err(anyhow::anyhow!(
+                "arrays file must be a .toml, .yaml or .yml file"
+            )),
+        }
+        .with_context(|| format!("invalid arrays file {}", path.display()))?;
+        set.validate()
+            .with_context(|| format!("invalid arrays file {}", path.display()))?;
+        Ok(set)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.arrays.is_empty() {
+            anyhow::bail!("no array listed");
+        }
+        let mut names = HashSet::new();
+        let mut mount_points = HashSet::new();
+        let mut disk_dirs = HashSet::new();
+        for array in &self.arrays {
+            if array.name.is_empty() || array.name.contains('/') {
+                anyhow::bail!("array name {:?} must be non-empty without '/'", array.name);
+            }
+            if !names.insert(&array.name) {
+                anyhow::bail!("array name {:?} is used twice", array.name);
+            }
+            if !mount_points.insert(&array.mount_point) {
+                anyhow::bail!("mount point {} is used twice", array.mount_point.display());
+            }
+            if !disk_dirs.insert(&array.disk_dir) {
+                anyhow::bail!("disk directory {} is used twice", array.disk_dir.display());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+
+    const TWO_ARRAYS: &str = r#"
+[[array]]
+name = "md0"
+mount_point = "/mnt/md0"
+disk_dir = "/var/raid/md0"
+raid = "raid1"
+disks = 2
+
+[[array]]
+name = "md1"
+mount_point = "/mnt/md1"
+disk_dir = "/var/raid/md1"
+read_only = true
+"#;
+
+    #[test]
+    fn loads_toml_and_yaml_arrays() {
+        let dir = temp_dir("raid-cli-arrays");
+        let path = dir.join("arrays.toml");
+        std::fs::write(&path, TWO_ARRAYS).expect("write arrays");
+        let set = ArraySet::load(&path).expect("load toml");
+        assert_eq!(set.arrays.len(), 2);
+        assert_eq!(set.arrays[0].raid, Some(RaidMode::Raid1));
+        assert_eq!(set.arrays[0].disks, Some(2));
+        assert_eq!(set.arrays[1].raid, None);
+        assert_eq!(set.arrays[1].read_only, Some(true));
+
+        let path = dir.join("arrays.yaml");
+        std::fs::write(
+            &path,
+            "array:\n  - name: md0\n    mount_point: /mnt/md0\n    disk_dir: /var/raid/md0\n    raid: raid3\n",
+        )
+        .expect("write arrays");
+        let set = ArraySet::load(&path).expect("load yaml");
+        assert_eq!(set.arrays[0].raid, Some(RaidMode::Raid3));
+    }
+
+    #[test]
+    fn rejects_conflicting_arrays() {
+        let dir = temp_dir("raid-cli-arrays-invalid");
+        let path = dir.join("arrays.toml");
+        for raw in [
+            "array = []\n",
+            &TWO_ARRAYS.replace("md1\"", "md0\""),
+            &TWO_ARRAYS.replace("/mnt/md1", "/mnt/md0"),
+            &TWO_ARRAYS.replace("/var/raid/md1", "/var/raid/md0"),
+            &TWO_ARRAYS.replace("name = \"md1\"", "name = \"md/1\""),
+            &TWO_ARRAYS.replace("read_only", "raid_level"),
+        ] {
+            std::fs::write(&path, raw).expect("write arrays");
+            assert!(ArraySet::load(&path).is_err(), "{raw}");
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Command-line argument definitions for the RAID simulator CLI.
 
+use std::net::SocketAddr;
 use std::path::PathBuf;
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
 
 use crate::fs::DEFAULT_DISK_LEN;
 
@@ -12,24 +14,84 @@ use crate::fs::DEFAULT_DISK_LEN;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+
+    /// Format of the log output.
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "LOG_FORMAT",
+        default_value = "text"
+    )]
+    pub log_format: LogFormat,
+
+    /// Log a timed span for every stripe read/write, rebuild batch and FUSE operation.
+    #[arg(long, global = true, default_value_t = false)]
+    pub trace_io: bool,
+
+    /// Seed every random number generator so runs are reproducible.
+    #[arg(long, global = true, env = "SIM_SEED")]
+    pub seed: Option<u64>,
 }
 
 /// Command enumerates the supported CLI subcommands.
 #[derive(Subcommand)]
 pub enum Command {
-    Fuse(FuseArgs),
+    Fuse(Box<FuseArgs>),
+
+    Metrics(Box<MetricsCommandArgs>),
+
+    Migrate(MigrateArgs),
+
+    Grow(GrowArgs),
+
+    Shrink(ShrinkArgs),
+
+    Inspect(InspectArgs),
+
+    Visualize(VisualizeArgs),
+
+    Check(CheckArgs),
+
+    Status(StatusArgs),
 
-    Metrics(MetricsArgs),
+    Export(ExportArgs),
+
+    Import(ImportArgs),
+
+    Snapshot(SnapshotArgs),
+
+    Backup(BackupArgs),
+
+    Restore(RestoreArgs),
+
+    Bench(BenchArgs),
+
+    Replay(ReplayArgs),
+
+    Scenario(ScenarioArgs),
+
+    Torture(TortureArgs),
+
+    Assemble(AssembleArgs),
+
+    Config(ConfigArgs),
 }
 
 /// `FuseArgs` configures the FUSE mount command.
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Args)]
 pub struct FuseArgs {
-    #[arg(long)]
-    pub mount_point: PathBuf,
+    #[arg(long, required_unless_present = "arrays")]
+    pub mount_point: Option<PathBuf>,
 
-    #[arg(long)]
-    pub disk_dir: PathBuf,
+    #[arg(long, required_unless_present = "arrays")]
+    pub disk_dir: Option<PathBuf>,
+
+    /// TOML or YAML file listing several arrays to mount, instead of
+    /// `--mount-point` and `--disk-dir`.
+    #[arg(long, conflicts_with_all = ["mount_point", "disk_dir"])]
+    pub arrays: Option<PathBuf>,
 
     #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
     pub raid: RaidMode,
@@ -40,11 +102,113 @@ pub struct FuseArgs {
     #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
     pub disk_size: u64,
 
+    /// How disk images are accessed; `direct` bypasses the page cache.
+    #[arg(long, value_enum, default_value_t = DiskIoMode::Mmap)]
+    pub disk_io: DiskIoMode,
+
+    /// Virtual size in bytes; converts the array to a thin volume on first mount.
+    #[arg(long)]
+    pub thin_size: Option<u64>,
+
+    /// Virtual size in bytes; stores blocks zstd-compressed from the first mount on.
+    #[arg(long, conflicts_with = "thin_size")]
+    pub compress_size: Option<u64>,
+
+    /// Stripes to prefetch ahead of sequential reads; 0 disables read-ahead.
+    #[arg(long, default_value_t = 0)]
+    pub read_ahead: u64,
+
+    /// Stripes whose parity is cached for partial-stripe writes; 0 disables the cache.
+    #[arg(long, default_value_t = 0)]
+    pub parity_cache: usize,
+
+    /// How IO behaves while members are missing or awaiting rebuild.
+    #[arg(long, value_enum, default_value_t = DegradedMode::FailFast)]
+    pub degraded: DegradedMode,
+
+    /// Refuse writes once this many stripes could not be reconstructed.
+    #[arg(long)]
+    pub uncorrectable_limit: Option<u64>,
+
+    /// How stripe writes are protected against a crash between data and parity.
+    #[arg(long, value_enum, default_value_t = WriteHoleMode::Unprotected)]
+    pub write_hole: WriteHoleMode,
+
+    /// TOML or JSON file of timed disk failures to inject after mounting.
+    #[arg(long)]
+    pub failure_schedule: Option<PathBuf>,
+
+    /// Mean defects each disk grows per GiB of IO; disks wear out only when set.
+    #[arg(long)]
+    pub wear_rate: Option<f64>,
+
+    /// Bytes of writes each disk acknowledges from a drive cache before they reach its image; 0 holds them until the next flush.
+    #[arg(long)]
+    pub write_cache: Option<usize>,
+
+    /// Chance that a cached write is lost on power failure, from 0 (battery-backed) to 1.
+    #[arg(long, default_value_t = 0.5)]
+    pub cache_volatility: f64,
+
     #[command(flatten)]
     pub metrics: MetricsArgs,
 
-    #[arg(long, default_value_t = false)]
+    /// Let other users access the mount (required for NFS export); needs
+    /// `user_allow_other` in /etc/fuse.conf unless run as root.
+    #[arg(long, default_value_t = false, conflicts_with = "allow_root")]
     pub allow_other: bool,
+
+    /// Let root access the mount besides the mounting user.
+    #[arg(long)]
+    pub allow_root: bool,
+
+    /// Have the kernel unmount the filesystem if the process exits without unmounting.
+    #[arg(long)]
+    pub auto_unmount: bool,
+
+    /// Have the kernel check file permissions against the reported modes.
+    #[arg(long)]
+    pub default_permissions: bool,
+
+    /// Source name shown for the mount, as in /proc/mounts.
+    #[arg(long, default_value = "raid-fuse")]
+    pub fsname: String,
+
+    /// Filesystem subtype shown for the mount, as in `fuse.<subtype>`.
+    #[arg(long)]
+    pub subtype: Option<String>,
+
+    /// Mount read-only: mutating operations fail with EROFS and a pending rebuild is not written back.
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// File reads and writes each class may serve per second; unlimited when unset.
+    #[arg(long)]
+    pub qos_iops: Option<u64>,
+
+    /// Megabytes of file reads and writes each class may move per second; unlimited when unset.
+    #[arg(long)]
+    pub qos_mbps: Option<f64>,
+
+    /// Apply the `QoS` limits to every user separately instead of to all users together.
+    #[arg(long)]
+    pub qos_per_uid: bool,
+
+    /// Percent of the array's time rebuilds may take while files are in use, from 1 to 100; unlimited when unset.
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub background_share: Option<u8>,
+
+    /// Bytes of adjacent writes each file collects before they reach the array; writes go straight through when unset.
+    #[arg(long)]
+    pub write_buffer: Option<usize>,
+
+    /// Milliseconds buffered writes may wait for neighbours before they are flushed.
+    #[arg(long, default_value_t = 50)]
+    pub write_buffer_ms: u64,
+
+    /// Expose the whole RAID volume as `volume.raw` and each member as a read-only `disks/disk-N.raw` in the mount root; writes to `volume.raw` bypass and can overwrite the file table.
+    #[arg(long)]
+    pub raw_volume: bool,
 }
 
 /// `MetricsArgs` configures metrics streaming options.
@@ -57,6 +221,11 @@ pub struct MetricsArgs {
     )]
     pub socket_path: String,
 
+    /// Stream to the gateway over TCP (`http://host:port`, or `https://` for TLS)
+    /// instead of the unix socket.
+    #[arg(long, env = "METRICS_ENDPOINT")]
+    pub metrics_endpoint: Option<String>,
+
     #[arg(long, env = "METRICS_SOURCE_ID", default_value = "raid-simulator")]
     pub source_id: String,
 
@@ -66,40 +235,760 @@ pub struct MetricsArgs {
     #[arg(long, env = "METRICS_OPS_PER_TICK", default_value_t = 200)]
     pub ops_per_tick: u32,
 
-    #[arg(long, env = "METRICS_QUEUE_CAP", default_value_t = 2048)]
-    pub queue_cap: usize,
+    /// Operation mix of the synthetic generator.
+    #[arg(long, value_enum, env = "METRICS_WORKLOAD", default_value_t = WorkloadProfile::MixedVm)]
+    pub workload: WorkloadProfile,
+
+    /// TOML file overriding settings of the `--workload` profile; re-read on SIGHUP.
+    #[arg(long, env = "METRICS_WORKLOAD_FILE")]
+    pub workload_file: Option<PathBuf>,
+
+    /// TOML or YAML file setting any of these flags by name, plus `log_level`;
+    /// re-read on SIGHUP.
+    #[arg(long, env = "METRICS_CONFIG_FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Fraction of synthetic operations that are reads.
+    #[arg(long)]
+    pub read_ratio: Option<f64>,
+
+    /// How strongly synthetic operations cluster into bursts, from 0 (even) to below 1.
+    #[arg(long)]
+    pub burstiness: Option<f64>,
+
+    /// Probability that a synthetic operation fails.
+    #[arg(long)]
+    pub error_probability: Option<f64>,
+
+    /// Zipf skew of the synthetic RAID operation offsets; 0 spreads them evenly.
+    #[arg(long)]
+    pub zipf_skew: Option<f64>,
+
+    #[arg(long, env = "METRICS_QUEUE_CAP", default_value_t = 2048)]
+    pub queue_cap: usize,
+
+    #[arg(long, env = "METRICS_CONN_BUFFER", default_value_t = 512)]
+    pub conn_buffer: usize,
+
+    #[arg(long, env = "METRICS_CONNECT_TIMEOUT_MS", default_value_t = 2000)]
+    pub connect_timeout_ms: u64,
+
+    /// Deadline of one RPC or OTLP export; 0 disables it for the gateway stream and
+    /// limits OTLP exports to one `--interval-ms`.
+    #[arg(long, env = "METRICS_RPC_TIMEOUT_MS", default_value_t = 0)]
+    pub rpc_timeout_ms: u64,
+
+    #[arg(long, env = "METRICS_BACKOFF_INITIAL_MS", default_value_t = 250)]
+    pub backoff_initial_ms: u64,
+
+    #[arg(long, env = "METRICS_BACKOFF_MAX_MS", default_value_t = 10_000)]
+    pub backoff_max_ms: u64,
+
+    #[arg(long, env = "METRICS_JITTER_RATIO", default_value_t = 0.2)]
+    pub jitter_ratio: f64,
+
+    #[arg(long, env = "METRICS_SHUTDOWN_GRACE_MS", default_value_t = 1500)]
+    pub shutdown_grace_ms: u64,
+
+    #[arg(long, env = "GRPC_AUTH_TOKEN", default_value = "")]
+    pub auth_token: String,
+
+    /// Read the auth token from this file on every connect; send SIGHUP to
+    /// reconnect with a rotated token.
+    #[arg(long, env = "GRPC_AUTH_TOKEN_FILE")]
+    pub auth_token_file: Option<PathBuf>,
+
+    /// PEM CA certificate trusted for `https` metrics endpoints.
+    #[arg(long, env = "METRICS_TLS_CA_CERT")]
+    pub tls_ca_cert: Option<PathBuf>,
+
+    /// PEM client certificate for mutual TLS; re-read on SIGHUP.
+    #[arg(long, env = "METRICS_TLS_CLIENT_CERT", requires = "tls_client_key")]
+    pub tls_client_cert: Option<PathBuf>,
+
+    /// PEM private key of the client certificate.
+    #[arg(long, env = "METRICS_TLS_CLIENT_KEY", requires = "tls_client_cert")]
+    pub tls_client_key: Option<PathBuf>,
+
+    /// Persist batches in this directory while the gateway is unreachable and
+    /// replay them on reconnect.
+    #[arg(long, env = "METRICS_SPOOL_DIR")]
+    pub spool_dir: Option<PathBuf>,
+
+    #[arg(long, env = "METRICS_SPOOL_MAX_BYTES", default_value_t = 64 * 1024 * 1024)]
+    pub spool_max_bytes: u64,
+
+    /// Serve the collected metrics for Prometheus scrapes on this address.
+    #[arg(long, env = "METRICS_PROMETHEUS_LISTEN")]
+    pub prometheus_listen: Option<SocketAddr>,
+
+    /// Serve `/healthz` and `/readyz` for container healthchecks on this address.
+    #[arg(long, env = "HEALTH_LISTEN")]
+    pub health_listen: Option<SocketAddr>,
+
+    #[arg(long, env = "METRICS_EXPORTER", value_enum, default_value_t = MetricsExporter::Gateway)]
+    pub metrics_exporter: MetricsExporter,
+
+    #[arg(
+        long,
+        env = "OTEL_EXPORTER_OTLP_ENDPOINT",
+        default_value = "http://localhost:4317"
+    )]
+    pub otlp_endpoint: String,
+
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_PROTOCOL", value_enum, default_value_t = OtlpProtocol::Grpc)]
+    pub otlp_protocol: OtlpProtocol,
+
+    /// Append every disk and RAID operation to this file for offline analysis.
+    #[arg(long, env = "METRICS_FILE")]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Record format of `--metrics-file`; inferred from its extension when unset.
+    #[arg(
+        long,
+        env = "METRICS_FILE_FORMAT",
+        value_enum,
+        requires = "metrics_file"
+    )]
+    pub metrics_file_format: Option<MetricsFileFormat>,
+}
+
+/// `MetricsCommandArgs` runs the synthetic metrics generator, or one of the
+/// metrics tools when a subcommand is given.
+#[derive(Args, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct MetricsCommandArgs {
+    #[command(subcommand)]
+    pub action: Option<MetricsAction>,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+/// `MetricsAction` enumerates the metrics tools.
+#[derive(Subcommand, Debug, Clone)]
+pub enum MetricsAction {
+    /// Re-stream operations captured with `--metrics-file` to the gateway.
+    Replay(MetricsReplayArgs),
+}
+
+/// `MetricsReplayArgs` configures re-streaming a recorded metrics file.
+#[derive(Args, Debug, Clone)]
+pub struct MetricsReplayArgs {
+    /// JSONL file written with `--metrics-file`.
+    #[arg(long)]
+    pub file: PathBuf,
+
+    /// Playback speed relative to the recorded timestamps, such as `2x` or `0.5x`;
+    /// `0` replays without pauses.
+    #[arg(long, default_value = "1x")]
+    pub speed: Speed,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+/// `Speed` is a playback speed factor, written as `2`, `2x` or `0.5x`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Speed(pub f64);
+
+impl std::str::FromStr for Speed {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let factor = s.trim();
+        let factor = factor
+            .strip_suffix(['x', 'X'])
+            .unwrap_or(factor)
+            .parse::<f64>()
+            .map_err(|err| format!("invalid speed {s:?}: {err}"))?;
+        if !factor.is_finite() || factor < 0.0 {
+            return Err(format!("speed {s:?} must be a non-negative number"));
+        }
+        Ok(Self(factor))
+    }
+}
+
+/// `MigrateArgs` configures an offline conversion between two arrays.
+#[derive(Args, Debug, Clone)]
+pub struct MigrateArgs {
+    #[arg(long)]
+    pub from_disk_dir: PathBuf,
+
+    #[arg(long, value_enum)]
+    pub from_raid: RaidMode,
+
+    #[arg(long)]
+    pub from_disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub from_disk_size: u64,
+
+    #[arg(long)]
+    pub to_disk_dir: PathBuf,
+
+    #[arg(long, value_enum)]
+    pub to_raid: RaidMode,
+
+    #[arg(long)]
+    pub to_disks: usize,
+
+    /// Size of each target disk image; defaults to the source disk size.
+    #[arg(long)]
+    pub to_disk_size: Option<u64>,
+
+    /// Copy the whole logical capacity instead of only the filesystem's used extent.
+    #[arg(long, default_value_t = false)]
+    pub full: bool,
+
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+/// `GrowArgs` configures adding member disks to an existing array.
+#[derive(Args, Debug, Clone)]
+pub struct GrowArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    /// Current number of member disks.
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Number of disks to append to the array.
+    #[arg(long, default_value_t = 1)]
+    pub add: usize,
+
+    /// Leave the data in place and move it while the grown array is mounted.
+    #[arg(long)]
+    pub background: bool,
+
+    /// Grow the array mounted here online, restriping it while it stays mounted.
+    #[arg(long, conflicts_with = "background")]
+    pub mount_point: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+/// `ShrinkArgs` configures removing member disks from an existing array.
+#[derive(Args, Debug, Clone)]
+pub struct ShrinkArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    /// Current number of member disks.
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Number of trailing disks to remove from the array.
+    #[arg(long, default_value_t = 1)]
+    pub remove: usize,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+/// `InspectArgs` configures the stripe/offset debugger.
+#[derive(Args, Debug, Clone)]
+pub struct InspectArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Logical byte offset to locate.
+    #[arg(long)]
+    pub offset: u64,
+
+    /// Number of consecutive stripes to dump, starting at the located stripe.
+    #[arg(long, default_value_t = 1)]
+    pub stripes: u64,
+}
+
+/// `VisualizeArgs` configures the stripe heatmap export.
+#[derive(Args, Debug, Clone)]
+pub struct VisualizeArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Logical byte range to draw, as `START..END` or a length from offset 0.
+    #[arg(long)]
+    pub range: ByteRange,
+
+    #[arg(long, value_enum, default_value_t = VisualizeFormat::Svg)]
+    pub format: VisualizeFormat,
+
+    /// Destination file, or `-` for stdout.
+    #[arg(long, default_value = "-")]
+    pub output: PathBuf,
+}
+
+/// `VisualizeFormat` selects the document written by `visualize`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum VisualizeFormat {
+    Svg,
+    Html,
+}
+
+/// `ByteRange` is a half-open range of logical bytes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl std::str::FromStr for ByteRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<u64>()
+                .map_err(|err| format!("invalid byte offset {v:?}: {err}"))
+        };
+        let (start, end) = match s.split_once("..") {
+            Some((start, end)) => (parse(start)?, parse(end)?),
+            None => (0, parse(s)?),
+        };
+        if start >= end {
+            return Err(format!("range {s:?} is empty"));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// `CheckArgs` configures the read-only consistency check.
+#[derive(Args, Debug, Clone)]
+pub struct CheckArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+/// `StatusArgs` configures printing the array status of offline images.
+#[derive(Args, Debug, Clone)]
+pub struct StatusArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Print the status as JSON instead of the mdstat-style view.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+/// `AssembleArgs` configures assembling an array from the superblocks of its images.
+#[derive(Args, Debug, Clone)]
+pub struct AssembleArgs {
+    /// Directory to scan for member images.
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+}
+
+/// `BenchArgs` configures the disk backend throughput benchmark.
+#[derive(Args, Debug, Clone)]
+pub struct BenchArgs {
+    /// Directory for scratch disk images; must not contain an array yet.
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    #[arg(long, value_enum, default_value_t = DiskIoMode::Mmap)]
+    pub disk_io: DiskIoMode,
+
+    /// Size of each logical read and write in bytes.
+    #[arg(long, default_value_t = 4096)]
+    pub block_size: usize,
+
+    /// Access blocks at Zipf-distributed offsets with this skew instead of sequentially.
+    #[arg(long)]
+    pub zipf_skew: Option<f64>,
+
+    /// How stripe writes are protected against a crash between data and parity.
+    #[arg(long, value_enum, default_value_t = WriteHoleMode::Unprotected)]
+    pub write_hole: WriteHoleMode,
+}
+
+/// `ReplayArgs` configures replaying a block IO trace.
+#[derive(Args, Debug, Clone)]
+pub struct ReplayArgs {
+    /// CSV (`timestamp,op,offset,size`) or `blkparse` trace to replay.
+    #[arg(long)]
+    pub trace: PathBuf,
+
+    /// Replay against scratch disk images in this directory; without it the trace
+    /// drives synthetic metrics.
+    #[arg(long)]
+    pub disk_dir: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    #[arg(long, value_enum, default_value_t = DiskIoMode::Mmap)]
+    pub disk_io: DiskIoMode,
+
+    /// Playback speed relative to the trace timestamps; `0` replays without pauses.
+    #[arg(long, default_value_t = 1.0)]
+    pub speed: f64,
+
+    #[command(flatten)]
+    pub metrics: MetricsArgs,
+}
+
+/// `ScenarioArgs` configures running a scripted failure and repair scenario.
+#[derive(Args, Debug, Clone)]
+pub struct ScenarioArgs {
+    #[arg(value_enum)]
+    pub action: ScenarioAction,
+
+    /// YAML scenario file.
+    pub file: PathBuf,
+
+    /// Directory for the scenario's disk images; a temporary one is used and removed when omitted.
+    #[arg(long)]
+    pub disk_dir: Option<PathBuf>,
+}
+
+/// `TortureArgs` configures the fsync durability matrix.
+#[derive(Args, Debug, Clone)]
+pub struct TortureArgs {
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = 65536)]
+    pub disk_size: u64,
+
+    /// Bytes each file may buffer before writing to the volume, as with `fuse --write-buffer`.
+    #[arg(long)]
+    pub write_buffer: Option<usize>,
+
+    /// Power failures per pattern, each losing a different part of the cached writes.
+    #[arg(long, default_value_t = 8)]
+    pub crashes: u64,
+
+    /// Directory for the disk images; a temporary one is used and removed when omitted.
+    #[arg(long)]
+    pub disk_dir: Option<PathBuf>,
+}
+
+/// `ConfigArgs` configures the configuration file helper.
+#[derive(Args, Debug, Clone)]
+pub struct ConfigArgs {
+    #[arg(value_enum)]
+    pub action: ConfigAction,
+}
+
+/// `ExportArgs` configures streaming the logical volume into a flat image.
+#[derive(Args, Debug, Clone)]
+pub struct ExportArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Destination file, or `-` for stdout.
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+/// `ImportArgs` configures seeding a volume from a flat image.
+#[derive(Args, Debug, Clone)]
+pub struct ImportArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Source file, or `-` for stdin.
+    #[arg(long)]
+    pub input: PathBuf,
+}
+
+/// `BackupArgs` configures streaming volume contents into a backup.
+#[derive(Args, Debug, Clone)]
+pub struct BackupArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Destination file, or `-` for stdout.
+    #[arg(long)]
+    pub output: PathBuf,
+
+    /// Only stream the blocks written since this snapshot.
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Snapshot to take once the backup is written, as the base of the next one.
+    #[arg(long)]
+    pub snapshot: Option<String>,
+}
+
+/// `RestoreArgs` configures applying a backup to a volume.
+#[derive(Args, Debug, Clone)]
+pub struct RestoreArgs {
+    #[arg(long)]
+    pub disk_dir: PathBuf,
+
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
+
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
+
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
+
+    /// Source file, or `-` for stdin.
+    #[arg(long)]
+    pub input: PathBuf,
+}
+
+/// `SnapshotArgs` configures the snapshot management command.
+#[derive(Args, Debug, Clone)]
+pub struct SnapshotArgs {
+    #[arg(value_enum)]
+    pub action: SnapshotAction,
+
+    #[arg(long)]
+    pub disk_dir: PathBuf,
 
-    #[arg(long, env = "METRICS_CONN_BUFFER", default_value_t = 512)]
-    pub conn_buffer: usize,
+    #[arg(long, value_enum, default_value_t = RaidMode::Raid0)]
+    pub raid: RaidMode,
 
-    #[arg(long, env = "METRICS_CONNECT_TIMEOUT_MS", default_value_t = 2000)]
-    pub connect_timeout_ms: u64,
+    #[arg(long, default_value_t = 3)]
+    pub disks: usize,
 
-    #[arg(long, env = "METRICS_RPC_TIMEOUT_MS", default_value_t = 0)]
-    pub rpc_timeout_ms: u64,
+    #[arg(long, default_value_t = DEFAULT_DISK_LEN)]
+    pub disk_size: u64,
 
-    #[arg(long, env = "METRICS_BACKOFF_INITIAL_MS", default_value_t = 250)]
-    pub backoff_initial_ms: u64,
+    /// Snapshot name; required by `create`, `delete`, and `rollback`.
+    #[arg(long)]
+    pub name: Option<String>,
 
-    #[arg(long, env = "METRICS_BACKOFF_MAX_MS", default_value_t = 10_000)]
-    pub backoff_max_ms: u64,
+    /// Share of the volume reserved for snapshots when the first one is created.
+    #[arg(long, default_value_t = 25)]
+    pub reserve_percent: u8,
+}
 
-    #[arg(long, env = "METRICS_JITTER_RATIO", default_value_t = 0.2)]
-    pub jitter_ratio: f64,
+/// `SnapshotAction` selects the snapshot operation to perform.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SnapshotAction {
+    Create,
+    List,
+    Delete,
+    Rollback,
+    Disable,
+}
 
-    #[arg(long, env = "METRICS_SHUTDOWN_GRACE_MS", default_value_t = 1500)]
-    pub shutdown_grace_ms: u64,
+/// `ScenarioAction` selects what to do with a scenario file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ScenarioAction {
+    /// Run the steps and report every assertion.
+    Run,
+    /// Only parse and validate the file.
+    Validate,
+}
 
-    #[arg(long, env = "GRPC_AUTH_TOKEN", default_value = "")]
-    pub auth_token: String,
+/// `ConfigAction` selects what to do with configuration files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ConfigAction {
+    /// Print a configuration file listing every setting with its default.
+    PrintDefault,
 }
 
 /// `RaidMode` selects the RAID layout for the simulation.
-#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum RaidMode {
     Raid0,
     Raid1,
     Raid3,
+    /// Parity groups of three scattered over the whole pool; rebuilds read from every disk.
+    Declustered,
+}
+
+impl RaidMode {
+    #[must_use]
+    /// `id` returns the lower-case name used on the command line and in metrics.
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::Raid0 => "raid0",
+            Self::Raid1 => "raid1",
+            Self::Raid3 => "raid3",
+            Self::Declustered => "declustered",
+        }
+    }
+}
+
+/// `DiskIoMode` selects how disk images are accessed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DiskIoMode {
+    Mmap,
+    /// Requires a build with the `io-uring` feature.
+    IoUring,
+    /// Bypass the page cache with `O_DIRECT`; Linux only.
+    Direct,
+}
+
+/// `WriteHoleMode` selects how stripe writes are protected against a torn update.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WriteHoleMode {
+    /// Write stripes in place.
+    Unprotected,
+    /// Journal whole stripes before writing them.
+    Journal,
+    /// Reject writes that do not cover whole stripes.
+    FullStripe,
+    /// Log the partial parity of each write, like md's PPL.
+    Ppl,
+}
+
+/// `MetricsExporter` selects where metrics batches are shipped.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MetricsExporter {
+    /// Stream batches to the metrics gateway.
+    Gateway,
+    /// Export aggregated metrics to an OpenTelemetry collector.
+    Otlp,
+}
+
+/// `MetricsFileFormat` selects the record format of the metrics file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MetricsFileFormat {
+    /// One JSON object per line.
+    Jsonl,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+/// `WorkloadProfile` names a preset operation mix of the synthetic generator.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorkloadProfile {
+    /// Small random IO, read-heavy, with moderate bursts.
+    Oltp,
+    /// Large sequential writes at a steady rate.
+    SequentialBackup,
+    /// Even mix of reads and writes across block sizes.
+    MixedVm,
+    /// Rare small bursts.
+    Idle,
+}
+
+/// `LogFormat` selects how log lines are written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per line, including the fields of the enclosing spans.
+    Json,
+}
+
+/// `OtlpProtocol` selects the OTLP transport.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OtlpProtocol {
+    Grpc,
+    #[value(name = "http/protobuf")]
+    HttpProtobuf,
+}
+
+/// `DegradedMode` selects the degraded-mode policy of a mounted volume.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DegradedMode {
+    /// Fail IO with EIO when data cannot be reconstructed.
+    FailFast,
+    /// Return zeros for data that cannot be reconstructed.
+    BestEffort,
+    /// Refuse writes with EROFS while the array is degraded.
+    ReadOnly,
 }
 
 #[cfg(test)]
@@ -150,6 +1039,7 @@ mod tests {
     fn parses_fuse_defaults() {
         let _lock = ENV_LOCK.lock().unwrap();
         let _socket = EnvGuard::clear("METRICS_SOCKET_PATH");
+        let _endpoint = EnvGuard::clear("METRICS_ENDPOINT");
         let _source = EnvGuard::clear("METRICS_SOURCE_ID");
         let _interval = EnvGuard::clear("METRICS_INTERVAL_MS");
         let _ops = EnvGuard::clear("METRICS_OPS_PER_TICK");
@@ -162,6 +1052,13 @@ mod tests {
         let _jitter = EnvGuard::clear("METRICS_JITTER_RATIO");
         let _shutdown = EnvGuard::clear("METRICS_SHUTDOWN_GRACE_MS");
         let _auth = EnvGuard::clear("GRPC_AUTH_TOKEN");
+        let _auth_file = EnvGuard::clear("GRPC_AUTH_TOKEN_FILE");
+        let _tls_ca = EnvGuard::clear("METRICS_TLS_CA_CERT");
+        let _tls_cert = EnvGuard::clear("METRICS_TLS_CLIENT_CERT");
+        let _tls_key = EnvGuard::clear("METRICS_TLS_CLIENT_KEY");
+        let _spool = EnvGuard::clear("METRICS_SPOOL_DIR");
+        let _workload = EnvGuard::clear("METRICS_WORKLOAD");
+        let _prometheus = EnvGuard::clear("METRICS_PROMETHEUS_LISTEN");
 
         let cli = Cli::parse_from([
             "raid-cli",
@@ -179,15 +1076,103 @@ mod tests {
         assert_eq!(args.raid, RaidMode::Raid0);
         assert_eq!(args.disks, 3);
         assert_eq!(args.disk_size, DEFAULT_DISK_LEN);
+        assert_eq!(args.thin_size, None);
+        assert_eq!(args.compress_size, None);
+        assert_eq!(args.read_ahead, 0);
+        assert_eq!(args.parity_cache, 0);
+        assert_eq!(args.background_share, None);
+        assert_eq!(args.degraded, DegradedMode::FailFast);
+        assert_eq!(args.uncorrectable_limit, None);
+        assert_eq!(args.write_hole, WriteHoleMode::Unprotected);
         assert_eq!(args.metrics.interval_ms, 1000);
         assert_eq!(args.metrics.ops_per_tick, 200);
+        assert_eq!(args.metrics.workload, WorkloadProfile::MixedVm);
         assert_eq!(args.metrics.queue_cap, 2048);
+        assert_eq!(args.metrics.prometheus_listen, None);
+        assert_eq!(args.metrics.health_listen, None);
+        assert_eq!(args.metrics.metrics_endpoint, None);
+        assert_eq!(args.metrics.spool_dir, None);
+    }
+
+    #[test]
+    fn parses_otlp_exporter_flags() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let _exporter = EnvGuard::clear("METRICS_EXPORTER");
+        let _endpoint = EnvGuard::clear("OTEL_EXPORTER_OTLP_ENDPOINT");
+        let _protocol = EnvGuard::set("OTEL_EXPORTER_OTLP_PROTOCOL", "http/protobuf");
+
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "metrics",
+            "--metrics-exporter",
+            "otlp",
+            "--otlp-endpoint",
+            "http://collector:4318",
+        ]);
+        let Command::Metrics(args) = cli.command else {
+            panic!("expected metrics command");
+        };
+        let args = args.metrics;
+
+        assert_eq!(args.metrics_exporter, MetricsExporter::Otlp);
+        assert_eq!(args.otlp_endpoint, "http://collector:4318");
+        assert_eq!(args.otlp_protocol, OtlpProtocol::HttpProtobuf);
+    }
+
+    #[test]
+    fn parses_global_flags() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let _format = EnvGuard::clear("LOG_FORMAT");
+        let _seed = EnvGuard::clear("SIM_SEED");
+
+        let cli = Cli::parse_from(["raid-cli", "metrics"]);
+        assert_eq!(cli.log_format, LogFormat::Text);
+        assert!(!cli.trace_io);
+
+        let cli = Cli::parse_from(["raid-cli", "metrics", "--log-format", "json", "--trace-io"]);
+        assert_eq!(cli.log_format, LogFormat::Json);
+        assert!(cli.trace_io);
+        assert_eq!(cli.seed, None);
+
+        let cli = Cli::parse_from(["raid-cli", "replay", "--trace", "t.csv", "--seed", "42"]);
+        assert_eq!(cli.seed, Some(42));
+    }
+
+    #[test]
+    fn parses_metrics_file_flags() {
+        let _lock = ENV_LOCK.lock().unwrap();
+        let _file = EnvGuard::clear("METRICS_FILE");
+        let _format = EnvGuard::clear("METRICS_FILE_FORMAT");
+
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "metrics",
+            "--metrics-file",
+            "/tmp/run.log",
+            "--metrics-file-format",
+            "csv",
+        ]);
+        let Command::Metrics(args) = cli.command else {
+            panic!("expected metrics command");
+        };
+        let args = args.metrics;
+
+        assert_eq!(args.metrics_file, Some(PathBuf::from("/tmp/run.log")));
+        assert_eq!(args.metrics_file_format, Some(MetricsFileFormat::Csv));
+
+        let Err(err) =
+            Cli::try_parse_from(["raid-cli", "metrics", "--metrics-file-format", "jsonl"])
+        else {
+            panic!("format without a file must fail");
+        };
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
     }
 
     #[test]
     fn parses_metrics_with_env_overrides() {
         let _lock = ENV_LOCK.lock().unwrap();
         let _socket = EnvGuard::set("METRICS_SOCKET_PATH", "/tmp/metrics.sock");
+        let _endpoint = EnvGuard::set("METRICS_ENDPOINT", "https://gateway:443");
         let _source = EnvGuard::set("METRICS_SOURCE_ID", "raid-test");
         let _interval = EnvGuard::set("METRICS_INTERVAL_MS", "150");
         let _ops = EnvGuard::set("METRICS_OPS_PER_TICK", "42");
@@ -200,13 +1185,25 @@ mod tests {
         let _jitter = EnvGuard::set("METRICS_JITTER_RATIO", "0.7");
         let _shutdown = EnvGuard::set("METRICS_SHUTDOWN_GRACE_MS", "800");
         let _auth = EnvGuard::set("GRPC_AUTH_TOKEN", "token");
+        let _auth_file = EnvGuard::set("GRPC_AUTH_TOKEN_FILE", "/run/secrets/token");
+        let _tls_ca = EnvGuard::set("METRICS_TLS_CA_CERT", "/tls/ca.pem");
+        let _tls_cert = EnvGuard::set("METRICS_TLS_CLIENT_CERT", "/tls/client.pem");
+        let _tls_key = EnvGuard::set("METRICS_TLS_CLIENT_KEY", "/tls/client.key");
+        let _spool = EnvGuard::set("METRICS_SPOOL_DIR", "/var/spool/raid");
+        let _spool_max = EnvGuard::set("METRICS_SPOOL_MAX_BYTES", "4096");
+        let _prometheus = EnvGuard::set("METRICS_PROMETHEUS_LISTEN", "0.0.0.0:9300");
 
         let cli = Cli::parse_from(["raid-cli", "metrics"]);
         let Command::Metrics(args) = cli.command else {
             panic!("expected metrics command");
         };
+        let args = args.metrics;
 
         assert_eq!(args.socket_path, "/tmp/metrics.sock");
+        assert_eq!(
+            args.metrics_endpoint.as_deref(),
+            Some("https://gateway:443")
+        );
         assert_eq!(args.source_id, "raid-test");
         assert_eq!(args.interval_ms, 150);
         assert_eq!(args.ops_per_tick, 42);
@@ -219,6 +1216,48 @@ mod tests {
         assert!((args.jitter_ratio - 0.7).abs() < f64::EPSILON);
         assert_eq!(args.shutdown_grace_ms, 800);
         assert_eq!(args.auth_token, "token");
+        assert_eq!(
+            args.auth_token_file,
+            Some(PathBuf::from("/run/secrets/token"))
+        );
+        assert_eq!(args.tls_ca_cert, Some(PathBuf::from("/tls/ca.pem")));
+        assert_eq!(args.tls_client_cert, Some(PathBuf::from("/tls/client.pem")));
+        assert_eq!(args.tls_client_key, Some(PathBuf::from("/tls/client.key")));
+        assert_eq!(args.spool_dir, Some(PathBuf::from("/var/spool/raid")));
+        assert_eq!(args.spool_max_bytes, 4096);
+        assert_eq!(
+            args.prometheus_listen,
+            Some("0.0.0.0:9300".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn parses_metrics_replay_with_a_speed_factor() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "metrics",
+            "replay",
+            "--file",
+            "out.jsonl",
+            "--speed",
+            "2x",
+        ]);
+        let Command::Metrics(args) = cli.command else {
+            panic!("expected metrics command");
+        };
+        let Some(MetricsAction::Replay(args)) = args.action else {
+            panic!("expected metrics replay command");
+        };
+        assert_eq!(args.file, PathBuf::from("out.jsonl"));
+        assert_eq!(args.speed, Speed(2.0));
+
+        assert_eq!("0.5X".parse::<Speed>(), Ok(Speed(0.5)));
+        assert_eq!("0".parse::<Speed>(), Ok(Speed(0.0)));
+        assert!("-1x".parse::<Speed>().is_err());
+        assert!("fast".parse::<Speed>().is_err());
+        assert!(
+            Cli::try_parse_from(["raid-cli", "metrics", "--interval-ms", "5", "replay"]).is_err()
+        );
     }
 
     #[test]
@@ -236,6 +1275,24 @@ mod tests {
             "2",
             "--disk-size",
             "2048",
+            "--disk-io",
+            "direct",
+            "--thin-size",
+            "65536",
+            "--degraded",
+            "read-only",
+            "--uncorrectable-limit",
+            "16",
+            "--write-hole",
+            "full-stripe",
+            "--read-ahead",
+            "8",
+            "--parity-cache",
+            "32",
+            "--background-share",
+            "25",
+            "--failure-schedule",
+            "/etc/raid/schedule.toml",
         ]);
 
         let Command::Fuse(args) = cli.command else {
@@ -245,5 +1302,316 @@ mod tests {
         assert_eq!(args.raid, RaidMode::Raid1);
         assert_eq!(args.disks, 2);
         assert_eq!(args.disk_size, 2048);
+        assert_eq!(args.disk_io, DiskIoMode::Direct);
+        assert_eq!(args.thin_size, Some(65536));
+        assert_eq!(args.read_ahead, 8);
+        assert_eq!(args.parity_cache, 32);
+        assert_eq!(args.background_share, Some(25));
+        assert_eq!(args.degraded, DegradedMode::ReadOnly);
+        assert_eq!(args.uncorrectable_limit, Some(16));
+        assert_eq!(args.write_hole, WriteHoleMode::FullStripe);
+        assert_eq!(
+            args.failure_schedule,
+            Some(PathBuf::from("/etc/raid/schedule.toml"))
+        );
+    }
+
+    #[test]
+    fn compress_size_excludes_thin_size() {
+        let base = [
+            "raid-cli",
+            "fuse",
+            "--mount-point",
+            "/mnt/raid",
+            "--disk-dir",
+            "/var/raid",
+            "--compress-size",
+            "1048576",
+        ];
+        let Command::Fuse(args) = Cli::parse_from(base).command else {
+            panic!("expected fuse command");
+        };
+        assert_eq!(args.compress_size, Some(1_048_576));
+
+        let both = [base.as_slice(), &["--thin-size", "65536"]].concat();
+        assert!(Cli::try_parse_from(both).is_err());
+    }
+
+    #[test]
+    fn parses_migrate_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "migrate",
+            "--from-disk-dir",
+            "/var/raid0",
+            "--from-raid",
+            "raid0",
+            "--from-disks",
+            "3",
+            "--to-disk-dir",
+            "/var/raid3",
+            "--to-raid",
+            "raid3",
+            "--to-disks",
+            "4",
+            "--dry-run",
+        ]);
+
+        let Command::Migrate(args) = cli.command else {
+            panic!("expected migrate command");
+        };
+
+        assert_eq!(args.from_raid, RaidMode::Raid0);
+        assert_eq!(args.from_disks, 3);
+        assert_eq!(args.from_disk_size, DEFAULT_DISK_LEN);
+        assert_eq!(args.to_raid, RaidMode::Raid3);
+        assert_eq!(args.to_disks, 4);
+        assert_eq!(args.to_disk_size, None);
+        assert!(args.dry_run);
+        assert!(!args.full);
+    }
+
+    #[test]
+    fn parses_grow_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "grow",
+            "--disk-dir",
+            "/var/raid",
+            "--raid",
+            "raid3",
+            "--disks",
+            "3",
+        ]);
+
+        let Command::Grow(args) = cli.command else {
+            panic!("expected grow command");
+        };
+
+        assert_eq!(args.raid, RaidMode::Raid3);
+        assert_eq!(args.disks, 3);
+        assert_eq!(args.add, 1);
+        assert_eq!(args.disk_size, DEFAULT_DISK_LEN);
+        assert_eq!(args.mount_point, None);
+
+        assert!(
+            Cli::try_parse_from([
+                "raid-cli",
+                "grow",
+                "--disk-dir",
+                "/var/raid",
+                "--mount-point",
+                "/mnt/raid",
+                "--background",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_shrink_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "shrink",
+            "--disk-dir",
+            "/var/raid",
+            "--disks",
+            "4",
+            "--remove",
+            "2",
+        ]);
+
+        let Command::Shrink(args) = cli.command else {
+            panic!("expected shrink command");
+        };
+
+        assert_eq!(args.raid, RaidMode::Raid0);
+        assert_eq!(args.disks, 4);
+        assert_eq!(args.remove, 2);
+    }
+
+    #[test]
+    fn parses_inspect_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "inspect",
+            "--disk-dir",
+            "/var/raid",
+            "--raid",
+            "raid3",
+            "--offset",
+            "4096",
+        ]);
+
+        let Command::Inspect(args) = cli.command else {
+            panic!("expected inspect command");
+        };
+
+        assert_eq!(args.offset, 4096);
+        assert_eq!(args.stripes, 1);
+    }
+
+    #[test]
+    fn parses_check_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "check",
+            "--disk-dir",
+            "/var/raid",
+            "--raid",
+            "raid1",
+            "--disks",
+            "2",
+        ]);
+
+        let Command::Check(args) = cli.command else {
+            panic!("expected check command");
+        };
+
+        assert_eq!(args.disk_dir, PathBuf::from("/var/raid"));
+        assert_eq!(args.raid, RaidMode::Raid1);
+        assert_eq!(args.disks, 2);
+    }
+
+    #[test]
+    fn parses_status_args() {
+        let cli = Cli::parse_from(["raid-cli", "status", "--disk-dir", "/var/raid", "--json"]);
+
+        let Command::Status(args) = cli.command else {
+            panic!("expected status command");
+        };
+
+        assert_eq!(args.disk_dir, PathBuf::from("/var/raid"));
+        assert!(args.json);
+    }
+
+    #[test]
+    fn parses_assemble_args() {
+        let cli = Cli::parse_from(["raid-cli", "assemble", "--disk-dir", "/var/raid"]);
+
+        let Command::Assemble(args) = cli.command else {
+            panic!("expected assemble command");
+        };
+
+        assert_eq!(args.disk_dir, PathBuf::from("/var/raid"));
+    }
+
+    #[test]
+    fn parses_bench_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "bench",
+            "--disk-dir",
+            "/tmp/bench",
+            "--disk-io",
+            "io-uring",
+            "--block-size",
+            "65536",
+        ]);
+
+        let Command::Bench(args) = cli.command else {
+            panic!("expected bench command");
+        };
+
+        assert_eq!(args.disk_io, DiskIoMode::IoUring);
+        assert_eq!(args.block_size, 65536);
+        assert_eq!(args.raid, RaidMode::Raid0);
+    }
+
+    #[test]
+    fn parses_export_and_import_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "export",
+            "--disk-dir",
+            "/var/raid",
+            "--output",
+            "-",
+        ]);
+        let Command::Export(args) = cli.command else {
+            panic!("expected export command");
+        };
+        assert_eq!(args.output, PathBuf::from("-"));
+
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "import",
+            "--disk-dir",
+            "/var/raid",
+            "--raid",
+            "raid3",
+            "--input",
+            "image.bin",
+        ]);
+        let Command::Import(args) = cli.command else {
+            panic!("expected import command");
+        };
+        assert_eq!(args.raid, RaidMode::Raid3);
+        assert_eq!(args.input, PathBuf::from("image.bin"));
+    }
+
+    #[test]
+    fn parses_snapshot_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "snapshot",
+            "create",
+            "--disk-dir",
+            "/var/raid",
+            "--name",
+            "nightly",
+        ]);
+        let Command::Snapshot(args) = cli.command else {
+            panic!("expected snapshot command");
+        };
+
+        assert_eq!(args.action, SnapshotAction::Create);
+        assert_eq!(args.name.as_deref(), Some("nightly"));
+        assert_eq!(args.reserve_percent, 25);
+    }
+
+    #[test]
+    fn parses_backup_and_restore_args() {
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "backup",
+            "--disk-dir",
+            "/var/raid",
+            "--output",
+            "-",
+            "--since",
+            "monday",
+            "--snapshot",
+            "tuesday",
+        ]);
+        let Command::Backup(args) = cli.command else {
+            panic!("expected backup command");
+        };
+        assert_eq!(args.output, PathBuf::from("-"));
+        assert_eq!(args.since.as_deref(), Some("monday"));
+        assert_eq!(args.snapshot.as_deref(), Some("tuesday"));
+
+        let cli = Cli::parse_from([
+            "raid-cli",
+            "restore",
+            "--disk-dir",
+            "/var/copy",
+            "--input",
+            "monday.bak",
+        ]);
+        let Command::Restore(args) = cli.command else {
+            panic!("expected restore command");
+        };
+        assert_eq!(args.input, PathBuf::from("monday.bak"));
+    }
+
+    #[test]
+    fn parses_config_args() {
+        let cli = Cli::parse_from(["raid-cli", "config", "print-default"]);
+        let Command::Config(args) = cli.command else {
+            panic!("expected config command");
+        };
+
+        assert_eq!(args.action, ConfigAction::PrintDefault);
     }
 }
@@ -0,0 +1,319 @@
+//! Assembly of an array from the superblocks of its member images.
+//!
+//! Like `mdadm --assemble --scan`, the disk directory is searched for member
+//! images and their superblocks tell which array each belongs to and in which role.
+//! Members found under other names are moved back to `disk-N.img`, so the array
+//! can be mounted with the geometry the report prints.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::AssembleArgs;
+use crate::commands::disk_image_path;
+use crate::fs::DEFAULT_CHUNK_SIZE;
+use crate::superblock::{self, Standing, Superblock};
+use crate::volume::{data_disks, validate_geometry};
+
+/// `Assembly` is an array recognised from the superblocks in a disk directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assembly {
+    /// Superblock of the newest member, describing the array.
+    pub array: Superblock,
+    /// Image found for each role; `None` for missing members.
+    pub members: Vec<Option<PathBuf>>,
+}
+
+/// `run` assembles an array and prints the detected layout.
+///
+/// # Arguments
+/// * `args` - Assemble arguments.
+///
+/// # Errors
+/// Returns an error if no array is found or its members do not agree.
+pub fn run(args: &AssembleArgs) -> Result<()> {
+    println!("{}", render(args)?.trim_end());
+    Ok(())
+}
+
+/// `render` assembles an array and builds the report without printing it.
+///
+/// # Arguments
+/// * `args` - Assemble arguments.
+///
+/// # Errors
+/// Returns an error if no array is found, its members do not agree, or a member
+/// cannot be moved to its canonical name.
+pub fn render(args: &AssembleArgs) -> Result<String> {
+    let assembly = scan(&args.disk_dir)?;
+    place(&assembly, &args.disk_dir)?;
+
+    let array = &assembly.array;
+    let mut out = format!(
+        "array {}: {}, {} disks, generation {}\n",
+        array.uuid(),
+        array.layout.id(),
+        array.disks,
+        array.generation
+    );
+    for (role, found) in assembly.members.iter().enumerate() {
+        let canonical = disk_image_path(&args.disk_dir, role);
+        let _ = match found {
+            None => writeln!(out, "  [{role}] missing"),
+            Some(path) if *path == canonical => writeln!(out, "  [{role}] {}", canonical.display()),
+            Some(path) => writeln!(
+                out,
+                "  [{role}] {} (was {})",
+                canonical.display(),
+                path.display()
+            ),
+        };
+    }
+    let _ = writeln!(
+        out,
+        "mount with --raid {} --disks {} --disk-size {}",
+        array.layout.id(),
+        array.disks,
+        array.disk_size
+    );
+    Ok(out)
+}
+
+/// `scan` reads the superblocks of a disk directory and checks that they form one array.
+///
+/// Members whose superblock has an older generation missed a membership change and
+/// are refused as stale, as are members that split from the rest of the array. Missing members are allowed up to the layout's redundancy.
+///
+/// # Arguments
+/// * `disk_dir` - Directory containing member images.
+///
+/// # Errors
+/// Returns an error if no superblock is found, members belong to different arrays or
+/// layouts, a member is stale, two members claim one role, or too many are missing.
+pub fn scan(disk_dir: &Path) -> Result<Assembly> {
+    let found = read_superblocks(disk_dir)?;
+    let Some(array) = found
+        .iter()
+        .map(|(_, sb)| *sb)
+        .max_by_key(|sb| sb.generation)
+    else {
+        anyhow::bail!("no member superblocks found in {}", disk_dir.display());
+    };
+    validate_geometry(array.layout, array.disks)?;
+    if array.chunk_size != DEFAULT_CHUNK_SIZE {
+        anyhow::bail!(
+            "array {} uses {}-byte chunks; this build supports {DEFAULT_CHUNK_SIZE}",
+            array.uuid(),
+            array.chunk_size
+        );
+    }
+
+    let mut members: Vec<Option<PathBuf>> = vec![None; array.disks];
+    for (image, sb) in found {
+        match sb.standing(&array) {
+            Standing::Current => {}
+            Standing::Foreign => anyhow::bail!(
+                "{} belongs to array {}, not {}",
+                image.display(),
+                sb.uuid(),
+                array.uuid()
+            ),
+            Standing::Stale => anyhow::bail!(
+                "{} is stale (generation {}, array at {})",
+                image.display(),
+                sb.generation,
+                array.generation
+            ),
+            Standing::SplitBrain => anyhow::bail!(
+                "{} was used without role {} of the array, which carried on without it",
+                image.display(),
+                array.role
+            ),
+        }
+        if !sb.same_geometry(&array) || sb.role >= array.disks {
+            anyhow::bail!(
+                "{} disagrees with the other members about the array layout",
+                image.display()
+            );
+        }
+        if let Some(other) = &members[sb.role] {
+            anyhow::bail!(
+                "{} and {} both claim role {}",
+                other.display(),
+                image.display(),
+                sb.role
+            );
+        }
+        members[sb.role] = Some(image);
+    }
+
+    let present = members.iter().flatten().count();
+    let redundancy = array.disks - data_disks(array.layout, array.disks);
+    if array.disks - present > redundancy {
+        anyhow::bail!(
+            "only {present} of {} members of array {} found",
+            array.disks,
+            array.uuid()
+        );
+    }
+    Ok(Assembly {
+        array: array.for_role(0),
+        members,
+    })
+}
+
+/// `read_superblocks` returns every `.img` image in a directory that has a superblock.
+fn read_superblocks(disk_dir: &Path) -> Result<Vec<(PathBuf, Superblock)>> {
+    let mut found = Vec::new();
+    let entries =
+        std::fs::read_dir(disk_dir).with_context(|| format!("read {}", disk_dir.display()))?;
+    for entry in entries {
+        let image = entry?.path();
+        if image.extension().is_none_or(|ext| ext != "img") || !image.is_file() {
+            continue;
+        }
+        if let Some(sb) = superblock::read(&image)? {
+            found.push((image, sb));
+        }
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(found)
+}
+
+/// `place` moves every member to the canonical image name of its role.
+///
+/// Members are first moved aside, so members that swapped names do not overwrite
+/// each other.
+fn place(assembly: &Assembly, disk_dir: &Path) -> Result<()> {
+    let moves: Vec<(&PathBuf, PathBuf)> = assembly
+        .members
+        .iter()
+        .enumerate()
+        .filter_map(|(role, found)| {
+            let found = found.as_ref()?;
+            let canonical = disk_image_path(disk_dir, role);
+            (*found != canonical).then_some((found, canonical))
+        })
+        .collect();
+    for (_, canonical) in &moves {
+        if canonical.exists() && !assembly.members.iter().flatten().any(|m| m == canonical) {
+            anyhow::bail!(
+                "{} is in the way and is not a member of the array",
+                canonical.display()
+            );
+        }
+    }
+
+    let mut staged = Vec::with_capacity(moves.len());
+    for (found, canonical) in moves {
+        let mut aside = canonical.clone().into_os_string();
+        aside.push(".assemble");
+        let aside = PathBuf::from(aside);
+        move_member(found, &aside)?;
+        staged.push((aside, canonical));
+    }
+    for (aside, canonical) in staged {
+        move_member(&aside, &canonical)?;
+    }
+    Ok(())
+}
+
+/// `move_member` renames a member image; its superblock moves with it.
+fn move_member(from: &Path, to: &Path) -> Result<()> {
+    std::fs::rename(from, to)
+        .with_context(|| format!("move {} to {}", from.display(), to.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RaidMode;
+    use crate::fs::test_utils::temp_dir;
+    use crate::volume::open_volume;
+    use raid_rs::retention::array::{MemberState, MemberStatus};
+    use raid_rs::retention::disk::{DiskHealth, DiskStats};
+
+    fn args(dir: &Path) -> AssembleArgs {
+        AssembleArgs {
+            disk_dir: dir.to_path_buf(),
+        }
+    }
+
+    fn create(dir: &Path, mode: RaidMode, disks: usize) {
+        let mut volume = open_volume(mode, dir, disks, 256).expect("open volume");
+        volume.write_bytes(0, b"assembled");
+    }
+
+    #[test]
+    fn assemble_restores_member_order() {
+        let dir = temp_dir("raid-cli-assemble-order");
+        create(&dir, RaidMode::Raid3, 3);
+        let spare = dir.join("spare.img");
+        move_member(&disk_image_path(&dir, 0), &spare).expect("move");
+        move_member(&disk_image_path(&dir, 2), &disk_image_path(&dir, 0)).expect("move");
+        move_member(&spare, &disk_image_path(&dir, 2)).expect("move");
+
+        let report = render(&args(&dir)).expect("assemble");
+
+        assert!(report.contains(": raid3, 3 disks, generation 1"));
+        assert!(report.contains("(was "));
+        assert!(report.ends_with("--raid raid3 --disks 3 --disk-size 256\n"));
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 256).expect("reopen");
+        let mut out = [0u8; 9];
+        volume.read_bytes(0, &mut out);
+        assert_eq!(&out, b"assembled");
+    }
+
+    #[test]
+    fn assemble_allows_missing_members_up_to_redundancy() {
+        let dir = temp_dir("raid-cli-assemble-degraded");
+        create(&dir, RaidMode::Raid3, 3);
+        std::fs::remove_file(disk_image_path(&dir, 1)).expect("remove image");
+
+        let report = render(&args(&dir)).expect("assemble");
+        assert!(report.contains("[1] missing"));
+
+        std::fs::remove_file(disk_image_path(&dir, 2)).expect("remove image");
+        let err = scan(&dir).expect_err("too few members");
+        assert!(err.to_string().contains("only 1 of 3 members"));
+    }
+
+    #[test]
+    fn assemble_refuses_members_of_another_array() {
+        let dir = temp_dir("raid-cli-assemble-foreign");
+        let other = temp_dir("raid-cli-assemble-other");
+        create(&dir, RaidMode::Raid1, 2);
+        create(&other, RaidMode::Raid1, 2);
+        move_member(&disk_image_path(&other, 1), &dir.join("extra.img")).expect("move");
+
+        let err = scan(&dir).expect_err("foreign member");
+
+        assert!(err.to_string().contains("belongs to array"));
+    }
+
+    #[test]
+    fn assemble_refuses_stale_members() {
+        let dir = temp_dir("raid-cli-assemble-stale");
+        create(&dir, RaidMode::Raid3, 3);
+        let members: Vec<MemberStatus> = (0..3)
+            .map(|index| MemberStatus {
+                index,
+                state: if index == 1 {
+                    MemberState::Failed
+                } else {
+                    MemberState::Ok
+                },
+                path: disk_image_path(&dir, index).display().to_string(),
+                image_exists: true,
+                io: DiskStats::default(),
+                health: DiskHealth::default(),
+            })
+            .collect();
+        superblock::advance_generation(&members).expect("advance");
+
+        let err = scan(&dir).expect_err("stale member");
+
+        assert!(err.to_string().contains("disk-1.img is stale"));
+    }
+}
@@ -0,0 +1,287 @@
+//! Backups of the logical volume contents, full or incremental since a snapshot.
+//!
+//! A backup stream starts with a header holding the capacity of the source and
+//! the snapshot the backup is relative to, followed by extents of logical bytes
+//! and an end marker carrying the number of extents. A full backup covers the
+//! whole volume. An incremental backup holds only the blocks written since its
+//! base snapshot, found through the snapshot's copy-on-write map, so it must be
+//! restored on top of a volume that already holds the base.
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::cli::BackupArgs;
+use crate::commands::{COPY_CHUNK, Progress, check_all_members_present, check_existing_images};
+use crate::volume::{open_volume, validate_geometry};
+
+/// `BACKUP_MAGIC` identifies a backup stream.
+pub const BACKUP_MAGIC: [u8; 8] = *b"RAIDBKP1";
+/// `END_MARKER` takes the place of an extent offset after the last extent.
+pub const END_MARKER: u64 = u64::MAX;
+
+/// `BackupHeader` describes the source of a backup stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupHeader {
+    /// Logical capacity of the source volume.
+    pub capacity: u64,
+    /// Snapshot the backup is relative to; `None` for a full backup.
+    pub since: Option<String>,
+}
+
+impl BackupHeader {
+    /// `write_to` encodes the header at the start of a backup stream.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn write_to(&self, out: &mut dyn Write) -> Result<()> {
+        let since = self.since.as_deref().unwrap_or_default().as_bytes();
+        let since_len = u16::try_from(since.len()).context("snapshot name too long")?;
+        out.write_all(&BACKUP_MAGIC)?;
+        out.write_all(&self.capacity.to_le_bytes())?;
+        out.write_all(&since_len.to_le_bytes())?;
+        out.write_all(since)?;
+        Ok(())
+    }
+
+    /// `read_from` decodes the header at the start of a backup stream.
+    ///
+    /// # Errors
+    /// Returns an error if the stream is not a backup or ends early.
+    pub fn read_from(input: &mut dyn Read) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic).context("read backup header")?;
+        if magic != BACKUP_MAGIC {
+            anyhow::bail!("input is not a raid-cli backup");
+        }
+        let capacity = read_u64(input)?;
+        let mut since_len = [0u8; 2];
+        input
+            .read_exact(&mut since_len)
+            .context("read backup header")?;
+        let mut since = vec![0u8; usize::from(u16::from_le_bytes(since_len))];
+        input.read_exact(&mut since).context("read backup header")?;
+        let since = String::from_utf8(since).context("backup base snapshot name")?;
+        Ok(Self {
+            capacity,
+            since: (!since.is_empty()).then_some(since),
+        })
+    }
+}
+
+/// `BackupSummary` counts the extents and bytes a backup or restore moved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BackupSummary {
+    pub extents: u64,
+    pub bytes: u64,
+}
+
+/// `read_u64` reads a little-endian `u64` from a backup stream.
+///
+/// # Errors
+/// Returns an error if the stream ends early.
+pub fn read_u64(input: &mut dyn Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    input
+        .read_exact(&mut buf)
+        .context("backup stream ends early")?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// `run` writes a backup of an array to a file or stdout.
+///
+/// # Arguments
+/// * `args` - Backup arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, the base
+/// snapshot cannot be used, or the output cannot be written.
+pub fn run(args: &BackupArgs) -> Result<()> {
+    let summary = if args.output == Path::new("-") {
+        backup_to(args, &mut std::io::stdout().lock())?
+    } else {
+        let file = File::create(&args.output)
+            .with_context(|| format!("create {}", args.output.display()))?;
+        backup_to(args, &mut BufWriter::new(file))?
+    };
+    let kind = args
+        .since
+        .as_deref()
+        .map_or_else(|| "full".to_string(), |base| format!("since {base}"));
+    eprintln!(
+        "backup: {} bytes in {} extents ({kind})",
+        summary.bytes, summary.extents
+    );
+    if let Some(name) = &args.snapshot {
+        eprintln!("backup: took snapshot {name} as the base of the next backup");
+    }
+    Ok(())
+}
+
+/// `backup_to` streams a backup of the logical volume into a writer.
+///
+/// # Arguments
+/// * `args` - Backup arguments.
+/// * `out` - Destination of the backup stream.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, the base
+/// snapshot does not exist or is invalid, the new snapshot cannot be taken, or
+/// writing fails.
+pub fn backup_to(args: &BackupArgs, out: &mut dyn Write) -> Result<BackupSummary> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    if let Some(name) = &args.snapshot {
+        if !volume.snapshots_enabled() {
+            anyhow::bail!(
+                "--snapshot needs snapshots enabled; create one with `raid-cli snapshot create` first"
+            );
+        }
+        if volume.snapshot_list().iter().any(|info| info.name == *name) {
+            anyhow::bail!("snapshot {name} already exists");
+        }
+    }
+    let capacity = volume.logical_capacity_bytes();
+    let ranges = match &args.since {
+        Some(base) => volume.snapshot_changed_ranges(base)?,
+        None => std::iter::once(0..capacity).collect(),
+    };
+
+    BackupHeader {
+        capacity,
+        since: args.since.clone(),
+    }
+    .write_to(out)
+    .context("write backup")?;
+    let total = ranges.iter().map(|range| range.end - range.start).sum();
+    let mut progress = Progress::new("backup", total);
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut summary = BackupSummary::default();
+    for range in ranges {
+        let mut at = range.start;
+        while at < range.end {
+            let take = usize::try_from(range.end - at)
+                .unwrap_or(usize::MAX)
+                .min(COPY_CHUNK);
+            volume.read_bytes(at, &mut buf[..take]);
+            let len = u32::try_from(take).context("extent too long")?;
+            out.write_all(&at.to_le_bytes()).context("write backup")?;
+            out.write_all(&len.to_le_bytes()).context("write backup")?;
+            out.write_all(&buf[..take]).context("write backup")?;
+            at += take as u64;
+            summary.extents += 1;
+            summary.bytes += take as u64;
+            progress.update(summary.bytes);
+        }
+    }
+    out.write_all(&END_MARKER.to_le_bytes())
+        .context("write backup")?;
+    out.write_all(&summary.extents.to_le_bytes())
+        .context("write backup")?;
+    out.flush().context("flush backup")?;
+
+    if let Some(name) = &args.snapshot {
+        volume.snapshot_create(name)?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{RaidMode, RestoreArgs};
+    use crate::commands::restore::restore_from;
+    use crate::fs::test_utils::temp_dir;
+    use raid_rs::retention::volume::DynVolume;
+
+    const DISK_SIZE: u64 = 8192;
+
+    fn args(dir: &Path, since: Option<&str>, snapshot: Option<&str>) -> BackupArgs {
+        BackupArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: DISK_SIZE,
+            output: "-".into(),
+            since: since.map(str::to_string),
+            snapshot: snapshot.map(str::to_string),
+        }
+    }
+
+    fn restore_args(dir: &Path) -> RestoreArgs {
+        RestoreArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: DISK_SIZE,
+            input: "-".into(),
+        }
+    }
+
+    fn open(dir: &Path) -> Box<dyn DynVolume> {
+        open_volume(RaidMode::Raid3, dir, 3, DISK_SIZE).expect("open volume")
+    }
+
+    fn read(dir: &Path, offset: u64, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        open(dir).read_bytes(offset, &mut out);
+        out
+    }
+
+    fn backup(args: &BackupArgs) -> (BackupSummary, Vec<u8>) {
+        let mut stream = Vec::new();
+        let summary = backup_to(args, &mut stream).expect("backup");
+        (summary, stream)
+    }
+
+    #[test]
+    fn incremental_backup_streams_only_blocks_changed_since_the_snapshot() {
+        let source = temp_dir("raid-cli-backup-source");
+        let copy = temp_dir("raid-cli-backup-copy");
+        let mut volume = open(&source);
+        volume.init_snapshots(4096, 0).expect("init snapshots");
+        volume.write_bytes(0, b"monday");
+        volume.write_bytes(3000, b"stays");
+        let capacity = volume.logical_capacity_bytes();
+        drop(volume);
+
+        let (full, stream) = backup(&args(&source, None, Some("monday")));
+        assert_eq!(full.bytes, capacity);
+        restore_from(&restore_args(&copy), &mut stream.as_slice()).expect("restore full");
+        assert_eq!(read(&copy, 0, 6), b"monday");
+
+        open(&source).write_bytes(2, b"ND");
+        let (incremental, stream) = backup(&args(&source, Some("monday"), Some("tuesday")));
+        assert_eq!(incremental.extents, 1);
+        assert!(incremental.bytes < capacity / 10);
+
+        let restored = restore_from(&restore_args(&copy), &mut stream.as_slice()).expect("restore");
+        assert_eq!(restored, incremental);
+        assert_eq!(read(&copy, 0, 6), b"moNDay");
+        assert_eq!(read(&copy, 3000, 5), b"stays");
+
+        let (unchanged, _) = backup(&args(&source, Some("tuesday"), None));
+        assert_eq!(unchanged.extents, 0);
+    }
+
+    #[test]
+    fn backup_rejects_unusable_snapshots() {
+        let dir = temp_dir("raid-cli-backup-snapshots");
+        drop(open(&dir));
+
+        let err = backup_to(&args(&dir, None, Some("base")), &mut Vec::new())
+            .expect_err("snapshots disabled");
+        assert!(err.to_string().contains("snapshot create"));
+
+        open(&dir).init_snapshots(4096, 0).expect("init snapshots");
+        let err = backup_to(&args(&dir, Some("missing"), None), &mut Vec::new())
+            .expect_err("unknown base");
+        assert!(err.to_string().contains("missing"));
+    }
+}
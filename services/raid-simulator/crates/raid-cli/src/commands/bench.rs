@@ -0,0 +1,303 @@
+//! Throughput benchmark of the disk access backends.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use raid_rs::retention::volume::{WriteHolePolicy, WriteHoleStats};
+
+use crate::access::{RegionHeat, ZipfOffsets};
+use crate::cli::{BenchArgs, WriteHoleMode};
+use crate::commands::ensure_scratch_dir;
+use crate::seed::{self, Component};
+use crate::volume::{disk_io, open_volume_with, validate_geometry};
+
+/// `BenchReport` holds the timings of one write pass and two read passes.
+#[derive(Clone, Debug)]
+pub struct BenchReport {
+    pub bytes: u64,
+    pub ops: u64,
+    pub write: Duration,
+    pub read: Duration,
+    /// The read pass repeated through the borrowing read path.
+    pub read_borrowed: Duration,
+    /// Accesses per region of the volume over both passes, for Zipf runs.
+    pub heat: Option<RegionHeat>,
+    /// Cost of the write-hole policy over the write pass.
+    pub write_hole: WriteHoleStats,
+}
+
+impl BenchReport {
+    /// `mib_per_second` converts a pass duration into throughput.
+    #[allow(clippy::cast_precision_loss)]
+    fn mib_per_second(&self, elapsed: Duration) -> f64 {
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.bytes as f64 / (1024.0 * 1024.0) / secs
+        } else {
+            0.0
+        }
+    }
+}
+
+/// `run` benchmarks sequential logical writes and reads on scratch disk images.
+///
+/// # Arguments
+/// * `args` - Bench arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the directory already holds an
+/// array, or the selected backend is not available.
+pub fn run(args: &BenchArgs) -> Result<()> {
+    let report = bench(args)?;
+    println!(
+        "bench: {:?} {} bytes in {}-byte blocks ({} ops per pass)",
+        args.disk_io, report.bytes, args.block_size, report.ops
+    );
+    println!(
+        "  write: {:.1} MiB/s ({:.3}s)",
+        report.mib_per_second(report.write),
+        report.write.as_secs_f64()
+    );
+    println!(
+        "  read:  {:.1} MiB/s ({:.3}s)",
+        report.mib_per_second(report.read),
+        report.read.as_secs_f64()
+    );
+    println!(
+        "  read (borrowed): {:.1} MiB/s ({:.3}s, {:.1}x)",
+        report.mib_per_second(report.read_borrowed),
+        report.read_borrowed.as_secs_f64(),
+        report.read.as_secs_f64() / report.read_borrowed.as_secs_f64().max(f64::EPSILON)
+    );
+    if let Some(line) = write_hole_line(args.write_hole, &report) {
+        println!("  write hole: {line}");
+    }
+    if let Some(heat) = report.heat {
+        print_heat(&heat);
+    }
+    Ok(())
+}
+
+impl From<WriteHoleMode> for WriteHolePolicy {
+    fn from(mode: WriteHoleMode) -> Self {
+        match mode {
+            WriteHoleMode::Unprotected => Self::Unprotected,
+            WriteHoleMode::Journal => Self::Journal,
+            WriteHoleMode::FullStripe => Self::FullStripe,
+            WriteHoleMode::Ppl => Self::Ppl,
+        }
+    }
+}
+
+/// `write_hole_line` summarizes what the write-hole policy cost the write pass.
+#[allow(clippy::cast_precision_loss)]
+fn write_hole_line(mode: WriteHoleMode, report: &BenchReport) -> Option<String> {
+    let stats = &report.write_hole;
+    let share = |logged: u64| logged as f64 * 100.0 / report.bytes.max(1) as f64;
+    match mode {
+        WriteHoleMode::Unprotected => None,
+        WriteHoleMode::Journal => Some(format!(
+            "journal, {} stripes logged ({} bytes, {:.1}% of written)",
+            stats.journaled_stripes,
+            stats.journal_bytes,
+            share(stats.journal_bytes)
+        )),
+        WriteHoleMode::FullStripe => Some(format!(
+            "full-stripe, {} partial writes rejected",
+            stats.rejected_writes
+        )),
+        WriteHoleMode::Ppl => Some(format!(
+            "ppl, {} stripes logged ({} bytes, {:.1}% of written), {} flushes",
+            stats.ppl_stripes,
+            stats.ppl_bytes,
+            share(stats.ppl_bytes),
+            stats.ppl_flushes
+        )),
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn print_heat(heat: &RegionHeat) {
+    let total = heat.total().max(1) as f64;
+    println!("  heat per region:");
+    for (i, &n) in heat.0.iter().enumerate() {
+        println!("    {i:>2}: {:5.1}% ({n} ops)", n as f64 * 100.0 / total);
+    }
+}
+
+/// `bench` writes the whole logical volume once and reads it back twice, first
+/// into a buffer and then through the borrowing read path.
+///
+/// With `--zipf-skew` each pass issues the same number of block accesses at
+/// Zipf-distributed offsets instead of sweeping the volume, and the report
+/// counts the accesses per region.
+///
+/// # Arguments
+/// * `args` - Bench arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the directory already holds an
+/// array, or the selected backend is not available.
+pub fn bench(args: &BenchArgs) -> Result<BenchReport> {
+    validate_geometry(args.raid, args.disks)?;
+    if args.block_size == 0 {
+        anyhow::bail!("--block-size must be greater than zero");
+    }
+    let io = disk_io(args.disk_io)?;
+    ensure_scratch_dir("bench", &args.disk_dir, args.disks)?;
+
+    let mut volume = open_volume_with(args.raid, &args.disk_dir, args.disks, args.disk_size, io)?;
+    volume.clear_needs_rebuild_all();
+    volume.set_write_hole_policy(args.write_hole.into());
+    let capacity = volume.logical_capacity_bytes();
+    let block = args.block_size as u64;
+
+    let mut buf: Vec<u8> = (0..=u8::MAX).cycle().take(args.block_size).collect();
+    let write_offsets = offsets(args, capacity, block)?;
+    let read_offsets = offsets(args, capacity, block)?;
+    let span =
+        |offset: u64| usize::try_from((capacity - offset).min(block)).unwrap_or(args.block_size);
+
+    let mut bytes = 0u64;
+    let start = Instant::now();
+    for &offset in &write_offsets {
+        let take = span(offset);
+        volume.write_bytes(offset, &buf[..take]);
+        bytes += take as u64;
+    }
+    let write = start.elapsed();
+
+    let start = Instant::now();
+    for &offset in &read_offsets {
+        let take = span(offset);
+        volume.read_bytes(offset, &mut buf[..take]);
+    }
+    let read = start.elapsed();
+
+    let start = Instant::now();
+    for &offset in &read_offsets {
+        volume.try_read_with(offset, span(offset), &mut |data| {
+            std::hint::black_box(data);
+        })?;
+    }
+    let read_borrowed = start.elapsed();
+
+    let heat = args.zipf_skew.map(|_| {
+        let mut heat = RegionHeat::default();
+        for &offset in write_offsets.iter().chain(&read_offsets) {
+            heat.record(offset, capacity);
+        }
+        heat
+    });
+    Ok(BenchReport {
+        bytes,
+        ops: write_offsets.len() as u64,
+        write,
+        read,
+        read_borrowed,
+        heat,
+        write_hole: volume.write_hole_stats(),
+    })
+}
+
+/// `offsets` lists the access offsets of one pass, drawn before timing starts.
+fn offsets(args: &BenchArgs, capacity: u64, block: u64) -> Result<Vec<u64>> {
+    let Some(skew) = args.zipf_skew else {
+        return Ok((0..capacity).step_by(usize::try_from(block)?).collect());
+    };
+    let zipf = ZipfOffsets::new(capacity, block, skew)?;
+    let mut rng = seed::rng(Component::Bench);
+    Ok((0..capacity.div_ceil(block))
+        .map(|_| zipf.sample(&mut rng))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::HEAT_REGIONS;
+    use crate::cli::{DiskIoMode, RaidMode};
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(dir: &std::path::Path) -> BenchArgs {
+        BenchArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64 * 1024,
+            disk_io: DiskIoMode::Mmap,
+            block_size: 4096,
+            zipf_skew: None,
+            write_hole: WriteHoleMode::Unprotected,
+        }
+    }
+
+    #[test]
+    fn bench_covers_the_whole_volume() {
+        let dir = temp_dir("raid-cli-bench");
+
+        let report = bench(&args(&dir)).expect("bench");
+
+        assert_eq!(report.bytes, 128 * 1024);
+        assert_eq!(report.ops, 32);
+        assert!(report.read_borrowed > Duration::ZERO);
+        assert!(report.heat.is_none());
+    }
+
+    #[test]
+    fn zipf_bench_reports_region_heat() {
+        let dir = temp_dir("raid-cli-bench-zipf");
+
+        let report = bench(&BenchArgs {
+            zipf_skew: Some(1.5),
+            ..args(&dir)
+        })
+        .expect("bench");
+
+        assert_eq!(report.ops, 32);
+        let heat = report.heat.expect("heat");
+        assert_eq!(heat.total(), 64);
+        assert!(heat.0[0] > heat.0[HEAT_REGIONS - 1]);
+    }
+
+    #[test]
+    fn bench_refuses_existing_arrays() {
+        let dir = temp_dir("raid-cli-bench-existing");
+        drop(crate::volume::open_volume(RaidMode::Raid3, &dir, 3, 64 * 1024).expect("open"));
+
+        let err = bench(&args(&dir)).expect_err("existing images");
+
+        assert!(err.to_string().contains("pick an empty --disk-dir"));
+    }
+
+    #[test]
+    fn ppl_logs_less_than_a_journal() {
+        let run = |mode: WriteHoleMode| {
+            let dir = temp_dir("raid-cli-bench-write-hole");
+            let report = bench(&BenchArgs {
+                write_hole: mode,
+                block_size: 6,
+                ..args(&dir)
+            })
+            .expect("bench");
+            (write_hole_line(mode, &report), report.write_hole)
+        };
+
+        let (line, unprotected) = run(WriteHoleMode::Unprotected);
+        assert_eq!(line, None);
+        assert_eq!(unprotected, WriteHoleStats::default());
+
+        let (_, journal) = run(WriteHoleMode::Journal);
+        let (line, ppl) = run(WriteHoleMode::Ppl);
+        assert!(line.expect("ppl line").starts_with("ppl, "));
+        assert_eq!(ppl.ppl_stripes, journal.journaled_stripes);
+        assert!(ppl.ppl_bytes < journal.journal_bytes);
+
+        let (line, full_stripe) = run(WriteHoleMode::FullStripe);
+        assert!(full_stripe.rejected_writes > 0);
+        assert!(
+            line.expect("full-stripe line")
+                .contains("partial writes rejected")
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Read-only consistency check of mirror copies and parity on offline images.
+
+use anyhow::Result;
+use raid_rs::retention::volume::CheckReport;
+
+use crate::cli::CheckArgs;
+use crate::commands::{Progress, check_all_members_present, check_existing_images};
+use crate::metrics_runtime::MetricsEmitter;
+use crate::volume::{open_volume, validate_geometry};
+
+/// `run` scans every stripe of an array and reports inconsistent ones without repairing them.
+///
+/// # Arguments
+/// * `args` - Check arguments.
+/// * `metrics` - Emitter used to publish progress and the final counts.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, or any stripe is uncorrectable.
+pub fn run(args: &CheckArgs, metrics: &MetricsEmitter) -> Result<()> {
+    let report = check(args, metrics)?;
+    println!(
+        "check: {} stripes, {} repairable ({} chunks), {} uncorrectable, {} unverified",
+        report.stripes_checked,
+        report.repairable_stripes,
+        report.mismatched_chunks,
+        report.uncorrectable_stripes,
+        report.unverified_stripes
+    );
+    if report.uncorrectable_stripes > 0 {
+        anyhow::bail!(
+            "{} stripes cannot be restored from redundancy",
+            report.uncorrectable_stripes
+        );
+    }
+    Ok(())
+}
+
+/// `check` runs the scan and returns the report without printing it.
+///
+/// # Arguments
+/// * `args` - Check arguments.
+/// * `metrics` - Emitter used to publish progress and the final counts.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid or images are missing.
+pub fn check(args: &CheckArgs, metrics: &MetricsEmitter) -> Result<CheckReport> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    let failed = volume.failed_disks();
+    let stripes = volume.physical_stripes();
+    let mut progress = Progress::new("check", stripes);
+    let mut report = CheckReport::default();
+    for s in 0..stripes {
+        report.add(volume.check_stripe(s));
+        if progress.update(s + 1) {
+            metrics.record_scrub_progress(failed, s + 1, stripes, mismatches(&report));
+        }
+    }
+
+    metrics.record_check_report(report);
+    metrics.record_scrub_progress(failed, stripes, stripes, mismatches(&report));
+    Ok(report)
+}
+
+const fn mismatches(report: &CheckReport) -> u64 {
+    report.repairable_stripes + report.uncorrectable_stripes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command, RaidMode};
+    use crate::commands::disk_image_path;
+    use crate::fs::test_utils::temp_dir;
+    use crate::metrics_runtime::MetricsEvent;
+    use clap::Parser;
+    use tokio::sync::mpsc;
+
+    const DISK_SIZE: u64 = 256;
+
+    fn args(dir: &std::path::Path, raid: RaidMode, disks: usize) -> CheckArgs {
+        let cli = Cli::parse_from([
+            "raid-cli".to_string(),
+            "check".to_string(),
+            "--disk-dir".to_string(),
+            dir.display().to_string(),
+            "--disks".to_string(),
+            disks.to_string(),
+            "--disk-size".to_string(),
+            DISK_SIZE.to_string(),
+        ]);
+        let Command::Check(mut args) = cli.command else {
+            panic!("expected check command");
+        };
+        args.raid = raid;
+        args
+    }
+
+    fn emitter() -> (std::sync::Arc<MetricsEmitter>, mpsc::Receiver<MetricsEvent>) {
+        let (tx, rx) = mpsc::channel(1024);
+        (MetricsEmitter::new("raid-test".to_string(), tx), rx)
+    }
+
+    fn seed(dir: &std::path::Path, raid: RaidMode, disks: usize) {
+        let mut volume = open_volume(raid, dir, disks, DISK_SIZE).expect("open volume");
+        let payload: Vec<u8> = (0..volume.logical_capacity_bytes())
+            .map(|i| u8::try_from(i % 251).expect("fits in u8"))
+            .collect();
+        volume.write_bytes(0, &payload);
+    }
+
+    fn corrupt(dir: &std::path::Path, disk: usize, offset: usize) {
+        let path = disk_image_path(dir, disk);
+        let mut image = std::fs::read(&path).expect("read image");
+        image[offset] ^= 0xff;
+        std::fs::write(&path, image).expect("write image");
+    }
+
+    #[test]
+    fn check_reports_parity_mismatch_and_publishes_counts() {
+        let dir = temp_dir("raid-cli-check-raid3");
+        seed(&dir, RaidMode::Raid3, 3);
+        corrupt(&dir, 2, 0);
+        let before = std::fs::read(disk_image_path(&dir, 2)).expect("read image");
+        let (metrics, mut rx) = emitter();
+
+        run(&args(&dir, RaidMode::Raid3, 3), &metrics).expect("check");
+
+        let after = std::fs::read(disk_image_path(&dir, 2)).expect("read image");
+        assert_eq!(before, after);
+        let mut last = None;
+        while let Ok(event) = rx.try_recv() {
+            if let MetricsEvent::RaidState(state) = event {
+                last = Some((
+                    state.check_stripes_checked,
+                    state.check_repairable_stripes,
+                    state.check_uncorrectable_stripes,
+                ));
+            }
+        }
+        assert_eq!(last, Some((DISK_SIZE / 4, 1, 0)));
+    }
+
+    #[test]
+    fn check_fails_on_split_mirror() {
+        let dir = temp_dir("raid-cli-check-raid1");
+        seed(&dir, RaidMode::Raid1, 2);
+        corrupt(&dir, 1, 5);
+        let (metrics, _rx) = emitter();
+
+        let err = run(&args(&dir, RaidMode::Raid1, 2), &metrics).expect_err("expected error");
+        assert!(err.to_string().contains("cannot be restored"));
+    }
+
+    #[test]
+    fn check_rejects_missing_member() {
+        let dir = temp_dir("raid-cli-check-missing");
+        seed(&dir, RaidMode::Raid3, 3);
+        std::fs::remove_file(disk_image_path(&dir, 1)).expect("remove image");
+        let (metrics, _rx) = emitter();
+
+        let err = check(&args(&dir, RaidMode::Raid3, 3), &metrics).expect_err("expected error");
+        assert!(err.to_string().contains("missing"));
+    }
+}
@@ -0,0 +1,14 @@
+//! Configuration file helper.
+
+use crate::cli::{ConfigAction, ConfigArgs};
+use crate::config::default_config;
+
+/// `run` executes a configuration file action.
+///
+/// # Arguments
+/// * `args` - Config arguments.
+pub fn run(args: &ConfigArgs) {
+    match args.action {
+        ConfigAction::PrintDefault => print!("{}", default_config()),
+    }
+}
@@ -0,0 +1,361 @@
+//! Capacity expansion by appending member disks to an existing array.
+//!
+//! With `--mount-point` the array stays mounted: the grow is requested through the
+//! control file, and the mount reshapes its data onto the appended members in the
+//! background, saving a checkpoint after every batch. The filesystem reports the
+//! larger capacity once the reshape finishes; later mounts use the new `--disks`
+//! value. Without it the reshape runs against unmounted images, or with
+//! `--background` is left to a balance that runs while the grown array is mounted.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use raid_rs::retention::volume::{BATCH_STRIPES, BalanceCheckpoint, DynVolume, ReshapeCheckpoint};
+
+use crate::cli::{GrowArgs, RaidMode};
+use crate::commands::{
+    Progress, check_all_members_present, check_existing_images, disk_image_path, rewrite_layout,
+};
+use crate::fs::DEFAULT_CHUNK_SIZE;
+use crate::fs::constants::CTL_NAME;
+use crate::metrics_runtime::MetricsEmitter;
+use crate::volume::{data_disks, open_reshaped_volume, open_volume, validate_geometry};
+
+/// `run` appends member disks to an array and reshapes its contents onto the new geometry.
+///
+/// RAID0, RAID3 and declustered arrays are restriped in place, or by a balance on the next mount
+/// with `--background`; RAID1 arrays resync the new mirrors. With `--mount-point` the mounted
+/// array is asked to reshape itself instead.
+///
+/// # Arguments
+/// * `args` - Grow arguments.
+/// * `metrics` - Emitter used to publish reshape progress.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, member images are missing, new images
+/// already exist, an earlier balance or online grow has not finished, or the mount
+/// refuses the grow.
+pub fn run(args: &GrowArgs, metrics: &MetricsEmitter) -> Result<()> {
+    if args.add == 0 {
+        anyhow::bail!("--add must be at least 1");
+    }
+    if let Some(checkpoint) = ReshapeCheckpoint::load(&args.disk_dir)? {
+        anyhow::bail!(
+            "an online grow to {} members has not finished; mount with --disks {} first",
+            checkpoint.target_disks,
+            checkpoint.source_disks
+        );
+    }
+    if let Some(mount_point) = &args.mount_point {
+        return request_online(args, mount_point);
+    }
+    if args.background && args.raid == RaidMode::Raid1 {
+        anyhow::bail!("--background applies to RAID0, RAID3 and declustered arrays");
+    }
+    if BalanceCheckpoint::load(&args.disk_dir)?.is_some() {
+        anyhow::bail!("a balance from an earlier grow has not finished; mount the array first");
+    }
+    let new_disks = args.disks + args.add;
+    validate_geometry(args.raid, args.disks)?;
+    validate_geometry(args.raid, new_disks)?;
+
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+    for i in args.disks..new_disks {
+        let path = disk_image_path(&args.disk_dir, i);
+        if path.exists() {
+            anyhow::bail!("image {} already exists", path.display());
+        }
+    }
+
+    let mut old = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    if old.snapshots_enabled() {
+        anyhow::bail!("cannot grow an array with snapshots; run `snapshot disable` first");
+    }
+    if old.is_thin() {
+        anyhow::bail!("cannot grow a thin-provisioned array");
+    }
+    if old.is_compressed() {
+        anyhow::bail!("cannot grow a compressed array");
+    }
+    let mut new = open_reshaped_volume(args.raid, &args.disk_dir, new_disks, disk_size)?;
+    let old_capacity = old.logical_capacity_bytes();
+    let new_capacity = new.logical_capacity_bytes();
+
+    println!(
+        "grow: {:?} x{} ({old_capacity} bytes) -> x{new_disks} ({new_capacity} bytes)",
+        args.raid, args.disks
+    );
+
+    if args.background {
+        drop(old);
+        new.clear_needs_rebuild_all();
+        BalanceCheckpoint {
+            source_disks: args.disks,
+            position: 0,
+        }
+        .save(&args.disk_dir)?;
+        println!(
+            "grow: mount with --disks {new_disks}; the data moves in the background and the \
+             added capacity appears on the first mount after the balance finishes"
+        );
+        return Ok(());
+    }
+
+    match args.raid {
+        RaidMode::Raid1 => resync_mirrors(new.as_mut(), metrics),
+        RaidMode::Raid0 | RaidMode::Raid3 | RaidMode::Declustered => {
+            // New stripe `k` only overwrites disk offset `k * N`, which the forward copy
+            // has already consumed, so the old layout is read before it is clobbered.
+            let stripe_bytes = data_disks(args.raid, new_disks) * DEFAULT_CHUNK_SIZE;
+            let failed = new.failed_disks();
+            let mut progress = Progress::new("grow", new_capacity);
+            rewrite_layout(
+                &mut |off, buf| {
+                    old.read_bytes(off, buf);
+                    Ok(())
+                },
+                old_capacity,
+                new.as_mut(),
+                stripe_bytes,
+                &mut |done| {
+                    if progress.update(done) {
+                        metrics.record_raid_state(failed, true, progress.fraction(done));
+                    }
+                },
+            )?;
+        }
+    }
+    drop(old);
+    new.clear_needs_rebuild_all();
+    metrics.record_raid_state(new.failed_disks(), false, 1.0);
+
+    println!("grow: done; mount with --disks {new_disks}");
+    Ok(())
+}
+
+/// `request_online` asks the filesystem mounted at `mount_point` to grow onto the
+/// appended members through its control file.
+fn request_online(args: &GrowArgs, mount_point: &Path) -> Result<()> {
+    let new_disks = args.disks + args.add;
+    validate_geometry(args.raid, args.disks)?;
+    validate_geometry(args.raid, new_disks)?;
+    let ctl = mount_point.join(CTL_NAME);
+    std::fs::write(&ctl, format!("grow {new_disks}")).with_context(|| {
+        format!(
+            "{} refused to grow to {new_disks} members; the array must be mounted with --disks {}, \
+             in sync and idle",
+            ctl.display(),
+            args.disks
+        )
+    })?;
+    println!(
+        "grow: {:?} x{} -> x{new_disks} reshaping in the background; progress is in {}",
+        args.raid,
+        args.disks,
+        ctl.display()
+    );
+    Ok(())
+}
+
+/// `resync_mirrors` copies the existing mirror contents onto the appended disks.
+fn resync_mirrors(volume: &mut dyn DynVolume, metrics: &MetricsEmitter) {
+    let stripes = volume.stripes_needed_for_logical_end(volume.logical_capacity_bytes());
+    let mut progress = Progress::new("grow", stripes);
+    for first in (0..stripes).step_by(BATCH_STRIPES) {
+        let end = stripes.min(first + BATCH_STRIPES as u64);
+        volume.repair_stripes(&(first..end).collect::<Vec<_>>());
+        if progress.update(end) {
+            metrics.record_raid_state(volume.failed_disks(), true, progress.fraction(end));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use crate::fs::test_utils::temp_dir;
+    use crate::metrics_runtime::MetricsEvent;
+    use clap::Parser;
+    use raid_rs::retention::disk::DiskIo;
+    use tokio::sync::mpsc;
+
+    const DISK_SIZE: u64 = 256;
+
+    fn args(dir: &std::path::Path, raid: RaidMode, disks: usize) -> GrowArgs {
+        let cli = Cli::parse_from([
+            "raid-cli".to_string(),
+            "grow".to_string(),
+            "--disk-dir".to_string(),
+            dir.display().to_string(),
+            "--disks".to_string(),
+            disks.to_string(),
+            "--disk-size".to_string(),
+            DISK_SIZE.to_string(),
+        ]);
+        let Command::Grow(mut args) = cli.command else {
+            panic!("expected grow command");
+        };
+        args.raid = raid;
+        args
+    }
+
+    fn emitter() -> (std::sync::Arc<MetricsEmitter>, mpsc::Receiver<MetricsEvent>) {
+        let (tx, rx) = mpsc::channel(1024);
+        (MetricsEmitter::new("raid-test".to_string(), tx), rx)
+    }
+
+    fn seed(dir: &std::path::Path, raid: RaidMode, disks: usize) -> Vec<u8> {
+        let mut volume = open_volume(raid, dir, disks, DISK_SIZE).expect("open volume");
+        let payload: Vec<u8> = (0..volume.logical_capacity_bytes())
+            .map(|i| u8::try_from(i % 251).expect("fits in u8"))
+            .collect();
+        volume.write_bytes(0, &payload);
+        payload
+    }
+
+    fn assert_grown(dir: &std::path::Path, raid: RaidMode, disks: usize, payload: &[u8]) {
+        let mut volume = open_volume(raid, dir, disks, DISK_SIZE).expect("open grown");
+        let capacity = usize::try_from(volume.logical_capacity_bytes()).expect("capacity");
+        let mut out = vec![0u8; capacity];
+        volume.read_bytes(0, &mut out);
+        assert_eq!(&out[..payload.len()], payload);
+        assert!(out[payload.len()..].iter().all(|&b| b == 0));
+        assert!(volume.disk_statuses().iter().all(|s| !s.needs_rebuild));
+    }
+
+    #[test]
+    fn grow_restripes_raid0() {
+        let dir = temp_dir("raid-cli-grow-raid0");
+        let payload = seed(&dir, RaidMode::Raid0, 2);
+        let (metrics, _rx) = emitter();
+
+        run(&args(&dir, RaidMode::Raid0, 2), &metrics).expect("grow");
+
+        assert_grown(&dir, RaidMode::Raid0, 3, &payload);
+    }
+
+    #[test]
+    fn grow_restripes_declustered_arrays() {
+        let dir = temp_dir("raid-cli-grow-declustered");
+        let payload = seed(&dir, RaidMode::Declustered, 3);
+        let (metrics, _rx) = emitter();
+
+        run(&args(&dir, RaidMode::Declustered, 3), &metrics).expect("grow");
+
+        assert_grown(&dir, RaidMode::Declustered, 4, &payload);
+    }
+
+    #[test]
+    fn grow_restripes_raid3_and_reports_progress() {
+        let dir = temp_dir("raid-cli-grow-raid3");
+        let payload = seed(&dir, RaidMode::Raid3, 3);
+        let (metrics, mut rx) = emitter();
+
+        run(&args(&dir, RaidMode::Raid3, 3), &metrics).expect("grow");
+
+        assert_grown(&dir, RaidMode::Raid3, 4, &payload);
+        let mut last = None;
+        while let Ok(event) = rx.try_recv() {
+            if let MetricsEvent::RaidState(state) = event {
+                last = Some((state.rebuild_in_progress, state.raid1_resync_progress));
+            }
+        }
+        assert_eq!(last, Some((false, 1.0)));
+    }
+
+    #[test]
+    fn grow_resyncs_raid1_mirror() {
+        let dir = temp_dir("raid-cli-grow-raid1");
+        let payload = seed(&dir, RaidMode::Raid1, 2);
+        let (metrics, _rx) = emitter();
+
+        run(&args(&dir, RaidMode::Raid1, 2), &metrics).expect("grow");
+
+        let image = std::fs::read(disk_image_path(&dir, 2)).expect("read new mirror");
+        assert_eq!(image[..payload.len()], payload);
+    }
+
+    #[test]
+    fn grow_background_leaves_a_balance_checkpoint() {
+        let dir = temp_dir("raid-cli-grow-background");
+        let payload = seed(&dir, RaidMode::Raid3, 3);
+        let (metrics, _rx) = emitter();
+        let mut grow = args(&dir, RaidMode::Raid3, 3);
+        grow.background = true;
+
+        run(&grow, &metrics).expect("grow");
+
+        let checkpoint = BalanceCheckpoint::load(&dir).expect("load checkpoint");
+        assert_eq!(
+            checkpoint,
+            Some(BalanceCheckpoint {
+                source_disks: 3,
+                position: 0
+            })
+        );
+        let err = run(&args(&dir, RaidMode::Raid3, 4), &metrics).expect_err("pending balance");
+        assert!(err.to_string().contains("has not finished"));
+
+        let source =
+            crate::volume::open_balance_source(RaidMode::Raid3, &dir, 3, DISK_SIZE, DiskIo::Mmap)
+                .expect("open source");
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 4, DISK_SIZE).expect("open grown");
+        volume.begin_balance(source, 3, 0).expect("begin balance");
+        while volume.balance_step(BATCH_STRIPES).expect("step") > 0 {}
+        assert_eq!(
+            BalanceCheckpoint::load(&dir).expect("load checkpoint"),
+            None
+        );
+        drop(volume);
+
+        assert_grown(&dir, RaidMode::Raid3, 4, &payload);
+    }
+
+    #[test]
+    fn grow_online_writes_the_control_file() {
+        let dir = temp_dir("raid-cli-grow-online");
+        let mount_point = dir.join("mnt");
+        std::fs::create_dir_all(&mount_point).expect("mount point");
+        let (metrics, _rx) = emitter();
+        let mut grow = args(&dir, RaidMode::Raid3, 3);
+        grow.mount_point = Some(mount_point.clone());
+
+        run(&grow, &metrics).expect("grow");
+
+        let ctl = std::fs::read_to_string(mount_point.join(CTL_NAME)).expect("read ctl");
+        assert_eq!(ctl, "grow 4");
+        grow.mount_point = Some(dir.join("missing"));
+        assert!(run(&grow, &metrics).is_err());
+    }
+
+    #[test]
+    fn grow_refuses_while_an_online_grow_is_unfinished() {
+        let dir = temp_dir("raid-cli-grow-reshaping");
+        seed(&dir, RaidMode::Raid0, 2);
+        ReshapeCheckpoint {
+            source_disks: 2,
+            target_disks: 3,
+            position: 0,
+        }
+        .save(&dir)
+        .expect("save checkpoint");
+        let (metrics, _rx) = emitter();
+
+        let err = run(&args(&dir, RaidMode::Raid0, 2), &metrics).expect_err("pending reshape");
+        assert!(err.to_string().contains("mount with --disks 2"));
+    }
+
+    #[test]
+    fn grow_rejects_existing_target_image() {
+        let dir = temp_dir("raid-cli-grow-exists");
+        seed(&dir, RaidMode::Raid0, 2);
+        std::fs::write(disk_image_path(&dir, 2), vec![0u8; 8]).expect("write image");
+        let (metrics, _rx) = emitter();
+
+        let err = run(&args(&dir, RaidMode::Raid0, 2), &metrics).expect_err("expected error");
+        assert!(err.to_string().contains("already exists"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Seeding of a volume from a flat image of its logical contents.
+
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::cli::ImportArgs;
+use crate::commands::{
+    COPY_CHUNK, Progress, check_all_members_present, check_existing_images, disk_image_path,
+};
+use crate::volume::{logical_capacity, open_volume, validate_geometry};
+
+/// `run` writes a flat image from a file or stdin into the logical volume.
+///
+/// # Arguments
+/// * `args` - Import arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the image does not fit, or reading fails.
+pub fn run(args: &ImportArgs) -> Result<()> {
+    let read = if args.input == Path::new("-") {
+        import_from(args, &mut std::io::stdin().lock())?
+    } else {
+        let file =
+            File::open(&args.input).with_context(|| format!("open {}", args.input.display()))?;
+        let len = file.metadata().map_or(0, |m| m.len());
+        let capacity = logical_capacity(args.raid, args.disks, args.disk_size.max(1));
+        if len > capacity {
+            anyhow::bail!(
+                "{} is {len} bytes but the volume holds only {capacity}",
+                args.input.display()
+            );
+        }
+        import_from(args, &mut BufReader::new(file))?
+    };
+    eprintln!("import: {read} bytes written");
+    Ok(())
+}
+
+/// `import_from` streams bytes from a reader into the logical volume starting at offset zero.
+///
+/// Missing arrays are created; existing arrays must have all members present.
+///
+/// # Arguments
+/// * `args` - Import arguments.
+/// * `input` - Source of the logical bytes.
+///
+/// # Returns
+/// The number of bytes written.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the input exceeds the capacity, or reading fails.
+pub fn import_from(args: &ImportArgs, input: &mut dyn Read) -> Result<u64> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    let existing = (0..args.disks).any(|i| disk_image_path(&args.disk_dir, i).exists());
+    if existing {
+        check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+        check_all_members_present(&args.disk_dir, args.disks)?;
+    }
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    let capacity = volume.logical_capacity_bytes();
+    let mut progress = Progress::new("import", capacity);
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut copied = 0u64;
+    loop {
+        let n = read_full(input, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        if copied + n as u64 > capacity {
+            anyhow::bail!("input exceeds the volume capacity of {capacity} bytes");
+        }
+        volume.write_bytes(copied, &buf[..n]);
+        copied += n as u64;
+        progress.update(copied);
+    }
+    volume.clear_needs_rebuild_all();
+    Ok(copied)
+}
+
+fn read_full(input: &mut dyn Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e).context("read import"),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{ExportArgs, RaidMode};
+    use crate::commands::export::export_to;
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(dir: &Path) -> ImportArgs {
+        ImportArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64,
+            input: "-".into(),
+        }
+    }
+
+    #[test]
+    fn import_then_export_roundtrips() {
+        let dir = temp_dir("raid-cli-import");
+        let payload: Vec<u8> = (0..100u8).collect();
+
+        let written = import_from(&args(&dir), &mut payload.as_slice()).expect("import");
+        assert_eq!(written, 100);
+
+        let export = ExportArgs {
+            disk_dir: dir,
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64,
+            output: "-".into(),
+        };
+        let mut out = Vec::new();
+        export_to(&export, &mut out).expect("export");
+        assert_eq!(&out[..100], payload.as_slice());
+        assert!(out[100..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn import_rejects_oversized_input() {
+        let dir = temp_dir("raid-cli-import-big");
+        let payload = vec![7u8; 129];
+
+        let err = import_from(&args(&dir), &mut payload.as_slice()).expect_err("too big");
+        assert!(err.to_string().contains("exceeds"));
+    }
+}
@@ -0,0 +1,170 @@
+//! Stripe/offset debugger that shows how logical bytes are laid out on disk.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use raid_rs::layout::stripe::traits::stripe::ChunkRole;
+use raid_rs::retention::volume::StripeInspection;
+
+use crate::cli::InspectArgs;
+use crate::commands::{check_all_members_present, check_existing_images};
+use crate::volume::{open_volume, validate_geometry};
+
+/// `run` prints the location of a logical offset and dumps the stripes around it.
+///
+/// # Arguments
+/// * `args` - Inspect arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, or the offset is out of range.
+pub fn run(args: &InspectArgs) -> Result<()> {
+    print!("{}", render(args)?);
+    Ok(())
+}
+
+/// `render` builds the inspect report without printing it.
+///
+/// # Arguments
+/// * `args` - Inspect arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, or the offset is out of range.
+pub fn render(args: &InspectArgs) -> Result<String> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    if volume.is_thin() {
+        anyhow::bail!("inspect does not follow thin allocation maps");
+    }
+    let capacity = volume.logical_capacity_bytes();
+    if args.offset >= capacity {
+        anyhow::bail!(
+            "offset {} is outside the logical capacity of {capacity} bytes",
+            args.offset
+        );
+    }
+
+    let loc = volume.locate(args.offset);
+    let roles: Vec<ChunkRole> = volume
+        .map_logical(args.offset)
+        .iter()
+        .map(|chunk| chunk.role)
+        .collect();
+    let total_stripes = volume.stripes_needed_for_logical_end(capacity);
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "offset {}: stripe {}, chunk {}, byte {} -> disk {} @ {}",
+        args.offset,
+        loc.stripe_index,
+        loc.chunk_index,
+        loc.byte_in_chunk,
+        loc.disk_index,
+        loc.disk_offset
+    );
+
+    let end = loc
+        .stripe_index
+        .saturating_add(args.stripes.max(1))
+        .min(total_stripes);
+    for stripe_index in loc.stripe_index..end {
+        let inspection = volume.inspect_stripe(stripe_index);
+        render_stripe(&mut out, &roles, &inspection);
+    }
+    Ok(out)
+}
+
+fn render_stripe(out: &mut String, roles: &[ChunkRole], inspection: &StripeInspection) {
+    let _ = writeln!(
+        out,
+        "stripe {} (disk offset {}):",
+        inspection.stripe_index, inspection.disk_offset
+    );
+    for (i, stored) in inspection.stored.iter().enumerate() {
+        let role = roles[i];
+        let Some(stored) = stored else {
+            let _ = writeln!(out, "  disk {i} [{role:<8}] missing");
+            continue;
+        };
+        if inspection.mismatched.contains(&i) {
+            let _ = writeln!(
+                out,
+                "  disk {i} [{role:<8}] {}  expected {}  MISMATCH",
+                hex(stored),
+                hex(&inspection.expected[i])
+            );
+        } else {
+            let _ = writeln!(out, "  disk {i} [{role:<8}] {}", hex(stored));
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RaidMode;
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(dir: &std::path::Path, offset: u64) -> InspectArgs {
+        InspectArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64,
+            offset,
+            stripes: 1,
+        }
+    }
+
+    #[test]
+    fn inspect_reports_location_and_parity() {
+        let dir = temp_dir("raid-cli-inspect");
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume");
+        volume.write_bytes(8, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        drop(volume);
+
+        let report = render(&args(&dir, 13)).expect("inspect");
+
+        assert!(report.contains("offset 13: stripe 1, chunk 1, byte 1 -> disk 1 @ 5"));
+        assert!(report.contains("disk 0 [data 0  ] 01 02 03 04"));
+        assert!(report.contains("disk 2 [parity  ] 04 04 04 0c"));
+        assert!(!report.contains("MISMATCH"));
+    }
+
+    #[test]
+    fn inspect_flags_parity_mismatch() {
+        let dir = temp_dir("raid-cli-inspect-bad");
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume");
+        volume.write_bytes(0, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        drop(volume);
+        let parity = crate::commands::disk_image_path(&dir, 2);
+        let mut image = std::fs::read(&parity).expect("read parity");
+        image[0] ^= 0xFF;
+        std::fs::write(&parity, image).expect("write parity");
+
+        let report = render(&args(&dir, 0)).expect("inspect");
+
+        assert!(report.contains("MISMATCH"));
+        let image = std::fs::read(&parity).expect("read parity");
+        assert_eq!(image[0], 0xFF ^ (1 ^ 5), "inspect must not repair");
+    }
+
+    #[test]
+    fn inspect_rejects_offset_past_capacity() {
+        let dir = temp_dir("raid-cli-inspect-range");
+        drop(open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume"));
+
+        assert!(render(&args(&dir, 128)).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Offline conversion of a volume from one RAID layout to another.
+
+use anyhow::Result;
+
+use crate::cli::MigrateArgs;
+use crate::commands::{COPY_CHUNK, Progress, check_existing_images, disk_image_path};
+use crate::volume::{
+    logical_capacity, open_reshaped_volume, open_volume, used_extent, validate_geometry,
+};
+
+/// `run` copies the logical contents of the source array into a freshly created target array.
+///
+/// # Arguments
+/// * `args` - Migration arguments.
+///
+/// # Errors
+/// Returns an error if either geometry is invalid, the target is too small, or would be overwritten.
+pub fn run(args: &MigrateArgs) -> Result<()> {
+    validate_geometry(args.from_raid, args.from_disks)?;
+    validate_geometry(args.to_raid, args.to_disks)?;
+
+    let from_disk_size = args.from_disk_size.max(1);
+    let to_disk_size = args.to_disk_size.unwrap_or(from_disk_size).max(1);
+
+    if same_dir(&args.from_disk_dir, &args.to_disk_dir) {
+        anyhow::bail!("source and target disk directories must differ");
+    }
+    check_existing_images(&args.from_disk_dir, args.from_disks, from_disk_size)?;
+    for i in 0..args.to_disks {
+        let path = disk_image_path(&args.to_disk_dir, i);
+        if path.exists() {
+            anyhow::bail!("target image {} already exists", path.display());
+        }
+    }
+
+    let mut source = open_volume(
+        args.from_raid,
+        &args.from_disk_dir,
+        args.from_disks,
+        from_disk_size,
+    )?;
+    let source_capacity = source.logical_capacity_bytes();
+    let extent = if args.full {
+        source_capacity
+    } else {
+        used_extent(source.as_mut()).unwrap_or(source_capacity)
+    }
+    .min(source_capacity);
+    let target_capacity = logical_capacity(args.to_raid, args.to_disks, to_disk_size);
+
+    println!(
+        "migrate: {:?} x{} ({source_capacity} bytes) -> {:?} x{} ({target_capacity} bytes)",
+        args.from_raid, args.from_disks, args.to_raid, args.to_disks
+    );
+    println!("migrate: {extent} bytes to copy");
+
+    if extent > target_capacity {
+        anyhow::bail!(
+            "target capacity {target_capacity} is smaller than the {extent} bytes to migrate"
+        );
+    }
+    if args.dry_run {
+        println!("migrate: dry run, nothing written");
+        return Ok(());
+    }
+
+    let mut target =
+        open_reshaped_volume(args.to_raid, &args.to_disk_dir, args.to_disks, to_disk_size)?;
+    let mut progress = Progress::new("migrate", extent);
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut copied = 0u64;
+    while copied < extent {
+        let take = usize::try_from(extent - copied)
+            .unwrap_or(usize::MAX)
+            .min(COPY_CHUNK);
+        source.read_bytes(copied, &mut buf[..take]);
+        target.write_bytes(copied, &buf[..take]);
+        copied += take as u64;
+        progress.update(copied);
+    }
+    target.clear_needs_rebuild_all();
+
+    println!("migrate: done, {copied} bytes copied");
+    Ok(())
+}
+
+fn same_dir(a: &std::path::Path, b: &std::path::Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RaidMode;
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(from: &std::path::Path, to: &std::path::Path) -> MigrateArgs {
+        MigrateArgs {
+            from_disk_dir: from.to_path_buf(),
+            from_raid: RaidMode::Raid0,
+            from_disks: 2,
+            from_disk_size: 256,
+            to_disk_dir: to.to_path_buf(),
+            to_raid: RaidMode::Raid3,
+            to_disks: 3,
+            to_disk_size: None,
+            full: true,
+            dry_run: false,
+        }
+    }
+
+    fn seed_source(dir: &std::path::Path) -> Vec<u8> {
+        let payload: Vec<u8> = (0..512u32)
+            .map(|i| u8::try_from(i % 251).expect("fits in u8"))
+            .collect();
+        let mut volume = open_volume(RaidMode::Raid0, dir, 2, 256).expect("open source");
+        volume.write_bytes(0, &payload);
+        payload
+    }
+
+    #[test]
+    fn migrate_copies_logical_bytes_into_new_layout() {
+        let from = temp_dir("raid-cli-migrate-from");
+        let to = temp_dir("raid-cli-migrate-to");
+        let payload = seed_source(&from);
+
+        run(&args(&from, &to)).expect("migrate");
+
+        let mut target = open_volume(RaidMode::Raid3, &to, 3, 256).expect("open target");
+        let mut out = vec![0u8; payload.len()];
+        target.read_bytes(0, &mut out);
+        assert_eq!(out, payload);
+    }
+
+    #[test]
+    fn migrate_dry_run_leaves_target_untouched() {
+        let from = temp_dir("raid-cli-migrate-dry-from");
+        let to = temp_dir("raid-cli-migrate-dry-to");
+        seed_source(&from);
+
+        let mut dry = args(&from, &to);
+        dry.dry_run = true;
+        run(&dry).expect("dry run");
+
+        assert!(!disk_image_path(&to, 0).exists());
+    }
+
+    #[test]
+    fn migrate_rejects_target_that_is_too_small() {
+        let from = temp_dir("raid-cli-migrate-small-from");
+        let to = temp_dir("raid-cli-migrate-small-to");
+        seed_source(&from);
+
+        let mut small = args(&from, &to);
+        small.to_raid = RaidMode::Raid1;
+        let err = run(&small).expect_err("expected error");
+        assert!(err.to_string().contains("smaller than"));
+    }
+
+    #[test]
+    fn migrate_rejects_same_directory() {
+        let dir = temp_dir("raid-cli-migrate-same");
+        seed_source(&dir);
+        let err = run(&args(&dir, &dir)).expect_err("expected error");
+        assert!(err.to_string().contains("must differ"));
+    }
+}
@@ -0,0 +1,306 @@
+//! Offline subcommands that operate directly on disk images.
+
+pub mod assemble;
+pub mod backup;
+pub mod bench;
+pub mod check;
+pub mod config;
+pub mod export;
+pub mod grow;
+pub mod import;
+pub mod inspect;
+pub mod metrics_replay;
+pub mod migrate;
+pub mod replay;
+pub mod restore;
+pub mod scenario;
+pub mod shrink;
+pub mod snapshot;
+pub mod status;
+pub mod torture;
+pub mod visualize;
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use raid_rs::retention::disk::METADATA_BYTES;
+use raid_rs::retention::volume::DynVolume;
+use tracing::info;
+
+use crate::superblock;
+
+/// `COPY_CHUNK` is the buffer size used when streaming logical bytes between volumes.
+pub const COPY_CHUNK: usize = 64 * 1024;
+
+/// `disk_image_path` returns the image path of a member disk inside a disk directory.
+///
+/// # Arguments
+/// * `disk_dir` - Directory containing disk images.
+/// * `index` - Member index.
+pub fn disk_image_path(disk_dir: &Path, index: usize) -> std::path::PathBuf {
+    disk_dir.join(format!("disk-{index}.img"))
+}
+
+/// `check_existing_images` verifies that the images of an existing array match the expected size.
+///
+/// Opening an array resizes its images, so a size mismatch would silently truncate data.
+/// Images written before members kept a metadata tail hold `disk_size` bytes of data
+/// alone; they gain a blank tail here, and opening the array stamps the superblock into it.
+///
+/// # Arguments
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Number of member disks.
+/// * `disk_size` - Expected size of each disk image in bytes.
+///
+/// # Errors
+/// Returns an error if no member image exists, an image has an unexpected size, or a
+/// tail-less image cannot be extended.
+pub fn check_existing_images(disk_dir: &Path, disks: usize, disk_size: u64) -> Result<()> {
+    let mut found = 0usize;
+    for i in 0..disks {
+        let path = disk_image_path(disk_dir, i);
+        let Ok(meta) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if meta.len() == disk_size && superblock::read(&path)?.is_none() {
+            upgrade_tailless_image(&path, disk_size)?;
+            found += 1;
+            continue;
+        }
+        let data = meta.len().saturating_sub(METADATA_BYTES);
+        if data != disk_size {
+            anyhow::bail!(
+                "{} holds {data} bytes but --disk-size is {disk_size}",
+                path.display()
+            );
+        }
+        found += 1;
+    }
+    if found == 0 {
+        anyhow::bail!("no disk images found in {}", disk_dir.display());
+    }
+    Ok(())
+}
+
+/// `upgrade_tailless_image` appends a blank metadata tail to an image that holds
+/// `disk_size` bytes of data alone.
+///
+/// # Errors
+/// Returns an error if the image cannot be extended.
+fn upgrade_tailless_image(path: &Path, disk_size: u64) -> Result<()> {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_len(disk_size.saturating_add(METADATA_BYTES)))
+        .with_context(|| {
+            format!(
+                "{} has no metadata tail and cannot be extended by {METADATA_BYTES} bytes; \
+                 copy it somewhere writable to upgrade it",
+                path.display()
+            )
+        })?;
+    info!(
+        "upgraded {}: appended the {METADATA_BYTES} byte metadata tail",
+        path.display()
+    );
+    Ok(())
+}
+
+/// `ensure_scratch_dir` verifies that a command about to overwrite disks finds no array.
+///
+/// # Arguments
+/// * `command` - Command name used in the error message.
+/// * `disk_dir` - Directory for the scratch disk images.
+/// * `disks` - Number of member disks.
+///
+/// # Errors
+/// Returns an error naming the first member image that already exists.
+pub fn ensure_scratch_dir(command: &str, disk_dir: &Path, disks: usize) -> Result<()> {
+    if let Some(path) = (0..disks)
+        .map(|i| disk_image_path(disk_dir, i))
+        .find(|path| path.exists())
+    {
+        anyhow::bail!(
+            "{} already exists; {command} overwrites its disks, pick an empty --disk-dir",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// `rewrite_layout` writes the whole logical range of a reshaped volume from a source.
+///
+/// Bytes past `source_len` are zero-filled so parity stays consistent across the new layout.
+/// The target is written front to back in whole stripes.
+///
+/// # Arguments
+/// * `read_source` - Reads source bytes at a logical offset into a buffer.
+/// * `source_len` - Number of logical bytes provided by the source.
+/// * `target` - Volume laid out with the new geometry.
+/// * `stripe_bytes` - Logical bytes per stripe of the target layout.
+/// * `on_progress` - Called with the number of target bytes written so far.
+///
+/// # Errors
+/// Returns an error if reading from the source fails.
+pub fn rewrite_layout(
+    read_source: &mut dyn FnMut(u64, &mut [u8]) -> Result<()>,
+    source_len: u64,
+    target: &mut dyn DynVolume,
+    stripe_bytes: usize,
+    on_progress: &mut dyn FnMut(u64),
+) -> Result<()> {
+    let capacity = target.logical_capacity_bytes();
+    let stripe_bytes = stripe_bytes.max(1);
+    let chunk = (COPY_CHUNK / stripe_bytes).max(1) * stripe_bytes;
+
+    let mut buf = vec![0u8; chunk];
+    let mut done = 0u64;
+    while done < capacity {
+        let take = usize::try_from(capacity - done)
+            .unwrap_or(usize::MAX)
+            .min(chunk);
+        let from_source = usize::try_from(source_len.saturating_sub(done))
+            .unwrap_or(usize::MAX)
+            .min(take);
+        read_source(done, &mut buf[..from_source])?;
+        buf[from_source..take].fill(0);
+        target.write_bytes(done, &buf[..take]);
+        done += take as u64;
+        on_progress(done);
+    }
+    Ok(())
+}
+
+/// `check_all_members_present` verifies that every member image of an array exists.
+///
+/// # Arguments
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Number of member disks.
+///
+/// # Errors
+/// Returns an error naming the first missing image.
+pub fn check_all_members_present(disk_dir: &Path, disks: usize) -> Result<()> {
+    for i in 0..disks {
+        let path = disk_image_path(disk_dir, i);
+        if !path.exists() {
+            anyhow::bail!(
+                "member image {} is missing; rebuild it first",
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// `Progress` logs coarse percentage updates for long-running offline commands.
+pub struct Progress {
+    label: &'static str,
+    total: u64,
+    step: u64,
+    next_report: u64,
+}
+
+impl Progress {
+    /// `new` creates a progress reporter that logs roughly every ten percent.
+    ///
+    /// # Arguments
+    /// * `label` - Prefix used for log lines.
+    /// * `total` - Total amount of work units.
+    #[must_use]
+    pub fn new(label: &'static str, total: u64) -> Self {
+        let step = (total / 10).max(1);
+        Self {
+            label,
+            total,
+            step,
+            next_report: step,
+        }
+    }
+
+    /// `fraction` returns the completed fraction for the given amount of finished work.
+    ///
+    /// # Arguments
+    /// * `done` - Completed work units.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn fraction(&self, done: u64) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (done.min(self.total) as f64) / (self.total as f64)
+        }
+    }
+
+    /// `update` records finished work and logs when a reporting step is crossed.
+    ///
+    /// # Arguments
+    /// * `done` - Completed work units.
+    ///
+    /// # Returns
+    /// `true` if a progress line was logged.
+    pub fn update(&mut self, done: u64) -> bool {
+        if done < self.next_report && done < self.total {
+            return false;
+        }
+        info!(
+            "{}: {:.0}% ({done}/{})",
+            self.label,
+            self.fraction(done) * 100.0,
+            self.total
+        );
+        self.next_report = done.saturating_add(self.step);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+
+    #[test]
+    fn check_existing_images_rejects_missing_and_mismatched() {
+        let dir = temp_dir("raid-cli-check-images");
+        assert!(check_existing_images(&dir, 2, 64).is_err());
+
+        let image = vec![0u8; 64 + usize::try_from(METADATA_BYTES).expect("tail fits")];
+        std::fs::write(disk_image_path(&dir, 0), image).expect("write image");
+        assert!(check_existing_images(&dir, 2, 64).is_ok());
+        assert!(check_existing_images(&dir, 2, 128).is_err());
+    }
+
+    #[test]
+    fn check_existing_images_upgrades_tailless_images() {
+        let dir = temp_dir("raid-cli-check-baseline");
+        let disk_size = 2 * METADATA_BYTES;
+        let data: Vec<u8> = (0..disk_size).map(|i| i.to_le_bytes()[0]).collect();
+        for i in 0..2 {
+            std::fs::write(disk_image_path(&dir, i), &data).expect("write image");
+        }
+        check_existing_images(&dir, 2, disk_size).expect("upgrade images");
+        for i in 0..2 {
+            let image = std::fs::read(disk_image_path(&dir, i)).expect("read image");
+            assert_eq!(image.len() as u64, disk_size + METADATA_BYTES);
+            assert_eq!(image[..data.len()], data[..]);
+        }
+
+        let stamp =
+            superblock::stamp(crate::cli::RaidMode::Raid0, &dir, 2, disk_size).expect("stamp");
+        stamp.write_pending(&dir).expect("write superblocks");
+        assert!(
+            check_existing_images(&dir, 2, disk_size + METADATA_BYTES).is_err(),
+            "a stamped image is never taken for a tail-less one"
+        );
+        check_existing_images(&dir, 2, disk_size).expect("upgraded images match");
+    }
+
+    #[test]
+    fn progress_reports_at_steps_and_completion() {
+        let mut progress = Progress::new("test", 100);
+        assert!(!progress.update(5));
+        assert!(progress.update(10));
+        assert!(!progress.update(15));
+        assert!(progress.update(100));
+        assert!((progress.fraction(50) - 0.5).abs() < f64::EPSILON);
+    }
+}
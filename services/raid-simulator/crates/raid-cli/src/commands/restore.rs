@@ -0,0 +1,189 @@
+//! Restoring the logical volume contents from a backup stream.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::cli::RestoreArgs;
+use crate::commands::backup::{BackupHeader, BackupSummary, END_MARKER, read_u64};
+use crate::commands::{
+    COPY_CHUNK, Progress, check_all_members_present, check_existing_images, disk_image_path,
+};
+use crate::volume::{open_volume, validate_geometry};
+
+/// `run` applies a backup from a file or stdin to an array.
+///
+/// # Arguments
+/// * `args` - Restore arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the backup does not fit, or the
+/// stream is not a complete backup.
+pub fn run(args: &RestoreArgs) -> Result<()> {
+    let summary = if args.input == Path::new("-") {
+        restore_from(args, &mut std::io::stdin().lock())?
+    } else {
+        let file =
+            File::open(&args.input).with_context(|| format!("open {}", args.input.display()))?;
+        restore_from(args, &mut BufReader::new(file))?
+    };
+    eprintln!(
+        "restore: {} bytes in {} extents",
+        summary.bytes, summary.extents
+    );
+    Ok(())
+}
+
+/// `restore_from` writes the extents of a backup stream into the logical volume.
+///
+/// Full backups create missing arrays. Incremental backups need the array that
+/// holds their base, so they refuse an empty directory.
+///
+/// # Arguments
+/// * `args` - Restore arguments.
+/// * `input` - Source of the backup stream.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the backup is larger than the
+/// volume, an incremental backup has no array to apply to, or the stream is
+/// corrupt or cut short.
+pub fn restore_from(args: &RestoreArgs, input: &mut dyn Read) -> Result<BackupSummary> {
+    validate_geometry(args.raid, args.disks)?;
+    let header = BackupHeader::read_from(input)?;
+    let disk_size = args.disk_size.max(1);
+    let existing = (0..args.disks).any(|i| disk_image_path(&args.disk_dir, i).exists());
+    if existing {
+        check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+        check_all_members_present(&args.disk_dir, args.disks)?;
+    } else if let Some(base) = &header.since {
+        anyhow::bail!(
+            "the backup only holds changes since snapshot {base}; restore the backups it builds on into {} first",
+            args.disk_dir.display()
+        );
+    }
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    let capacity = volume.logical_capacity_bytes();
+    if header.capacity > capacity {
+        anyhow::bail!(
+            "the backup covers {} bytes but the volume holds only {capacity}",
+            header.capacity
+        );
+    }
+    let mut progress = Progress::new("restore", header.capacity);
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut summary = BackupSummary::default();
+    loop {
+        let offset = read_u64(input)?;
+        if offset == END_MARKER {
+            let extents = read_u64(input)?;
+            if extents != summary.extents {
+                anyhow::bail!(
+                    "backup stream lists {extents} extents but holds {}",
+                    summary.extents
+                );
+            }
+            break;
+        }
+        let mut len = [0u8; 4];
+        input
+            .read_exact(&mut len)
+            .context("backup stream ends early")?;
+        let len = usize::try_from(u32::from_le_bytes(len)).unwrap_or(usize::MAX);
+        if len > COPY_CHUNK || offset.saturating_add(len as u64) > header.capacity {
+            anyhow::bail!("backup extent at {offset} of {len} bytes is out of range");
+        }
+        input
+            .read_exact(&mut buf[..len])
+            .context("backup stream ends early")?;
+        volume.write_bytes(offset, &buf[..len]);
+        summary.extents += 1;
+        summary.bytes += len as u64;
+        progress.update(offset + len as u64);
+    }
+    volume.clear_needs_rebuild_all();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RaidMode;
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(dir: &Path) -> RestoreArgs {
+        RestoreArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64,
+            input: "-".into(),
+        }
+    }
+
+    fn stream(since: Option<&str>, capacity: u64, extents: &[(u64, &[u8])], count: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        BackupHeader {
+            capacity,
+            since: since.map(str::to_string),
+        }
+        .write_to(&mut out)
+        .expect("header");
+        for (offset, data) in extents {
+            out.extend_from_slice(&offset.to_le_bytes());
+            out.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+            out.extend_from_slice(data);
+        }
+        out.extend_from_slice(&END_MARKER.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn restore_writes_extents_into_a_new_array() {
+        let dir = temp_dir("raid-cli-restore");
+        let input = stream(None, 128, &[(10, b"stripe")], 1);
+
+        let summary = restore_from(&args(&dir), &mut input.as_slice()).expect("restore");
+
+        assert_eq!(
+            summary,
+            BackupSummary {
+                extents: 1,
+                bytes: 6
+            }
+        );
+        let mut out = [0u8; 6];
+        open_volume(RaidMode::Raid3, &dir, 3, 64)
+            .expect("open")
+            .read_bytes(10, &mut out);
+        assert_eq!(&out, b"stripe");
+    }
+
+    #[test]
+    fn restore_rejects_incremental_backups_without_a_base() {
+        let dir = temp_dir("raid-cli-restore-base");
+        let input = stream(Some("monday"), 128, &[], 0);
+
+        let err = restore_from(&args(&dir), &mut input.as_slice()).expect_err("no base");
+
+        assert!(err.to_string().contains("snapshot monday"));
+        assert!(!disk_image_path(&dir, 0).exists());
+    }
+
+    #[test]
+    fn restore_rejects_damaged_streams() {
+        let dir = temp_dir("raid-cli-restore-damaged");
+        let restore = |input: Vec<u8>| restore_from(&args(&dir), &mut input.as_slice());
+
+        assert!(restore(b"not a backup".to_vec()).is_err());
+        assert!(restore(stream(None, 4096, &[], 0)).is_err(), "too large");
+        assert!(restore(stream(None, 128, &[(126, b"abc")], 1)).is_err());
+        assert!(restore(stream(None, 128, &[(0, b"abc")], 2)).is_err());
+        let mut cut = stream(None, 128, &[(0, b"abc")], 1);
+        cut.truncate(cut.len() - 12);
+        assert!(restore(cut).is_err());
+    }
+}
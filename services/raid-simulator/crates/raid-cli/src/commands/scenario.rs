@@ -0,0 +1,682 @@
+//! Scripted failure and repair scenarios with a pass/fail report.
+
+use std::fmt::Write as _;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use raid_rs::retention::volume::{CrashPlan, DynVolume, StripeCheck};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use crate::cli::{ScenarioAction, ScenarioArgs};
+use crate::commands::{COPY_CHUNK, disk_image_path, ensure_scratch_dir};
+use crate::scenario::{Action, Assertion, Scenario};
+use crate::volume::open_volume;
+
+/// `Outcome` is the result of one step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// An action completed, with a short summary.
+    Done(String),
+    Passed,
+    /// An assertion did not hold, with the expectations that failed.
+    Failed(String),
+    /// An action failed; the remaining steps are skipped.
+    Error(String),
+}
+
+/// `StepReport` records when a step ran and how it ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepReport {
+    pub elapsed: Duration,
+    pub step: String,
+    pub outcome: Outcome,
+}
+
+/// `ScenarioReport` collects the steps that ran.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScenarioReport {
+    pub steps: Vec<StepReport>,
+    /// Number of steps in the scenario, including skipped ones.
+    pub total_steps: usize,
+}
+
+impl ScenarioReport {
+    #[must_use]
+    /// `passed` reports whether every step ran and every assertion held.
+    pub fn passed(&self) -> bool {
+        self.steps.len() == self.total_steps
+            && self
+                .steps
+                .iter()
+                .all(|s| matches!(s.outcome, Outcome::Done(_) | Outcome::Passed))
+    }
+
+    fn assertions(&self) -> (usize, usize) {
+        let passed = self
+            .steps
+            .iter()
+            .filter(|s| s.outcome == Outcome::Passed)
+            .count();
+        let failed = self
+            .steps
+            .iter()
+            .filter(|s| matches!(s.outcome, Outcome::Failed(_)))
+            .count();
+        (passed, passed + failed)
+    }
+}
+
+/// `run` runs or validates a scenario file and prints its report.
+///
+/// # Arguments
+/// * `args` - Scenario arguments.
+///
+/// # Errors
+/// Returns an error if the scenario is invalid, its disk directory already holds
+/// an array, or the scenario does not pass.
+pub fn run(args: &ScenarioArgs) -> Result<()> {
+    let scenario = Scenario::load(&args.file)?;
+    let title = if scenario.name.is_empty() {
+        args.file.display().to_string()
+    } else {
+        scenario.name.clone()
+    };
+    if args.action == ScenarioAction::Validate {
+        println!("scenario {title}: {} steps, valid", scenario.steps.len());
+        return Ok(());
+    }
+
+    let (disk_dir, temporary) = args
+        .disk_dir
+        .clone()
+        .map_or_else(|| (scratch_dir(), true), |dir| (dir, false));
+    let report = execute(&scenario, &disk_dir);
+    if temporary {
+        let _ = std::fs::remove_dir_all(&disk_dir);
+    }
+    let report = report?;
+
+    println!(
+        "scenario {title}: {:?} with {} disks of {} bytes",
+        scenario.raid, scenario.disks, scenario.disk_size
+    );
+    print!("{}", render(&report));
+    if !report.passed() {
+        anyhow::bail!("scenario {title} failed");
+    }
+    Ok(())
+}
+
+/// `execute` runs the steps of a scenario on fresh disk images.
+///
+/// # Arguments
+/// * `scenario` - Scenario to run.
+/// * `disk_dir` - Directory for the disk images; must not hold an array yet.
+///
+/// # Errors
+/// Returns an error if the directory already holds an array or the volume cannot
+/// be opened. Failing steps are reported, not returned.
+pub fn execute(scenario: &Scenario, disk_dir: &Path) -> Result<ScenarioReport> {
+    ensure_scratch_dir("scenario", disk_dir, scenario.disks)?;
+    let volume = open(scenario, disk_dir)?;
+    let capacity = usize::try_from(volume.logical_capacity_bytes())?;
+    let mut runner = Runner {
+        volume,
+        scenario,
+        disk_dir,
+        expected: vec![0; capacity],
+    };
+
+    let start = Instant::now();
+    let mut report = ScenarioReport {
+        steps: Vec::new(),
+        total_steps: scenario.steps.len(),
+    };
+    for step in &scenario.steps {
+        if let Some(wait) = step.at.and_then(|at| at.checked_sub(start.elapsed())) {
+            std::thread::sleep(wait);
+        }
+        let elapsed = start.elapsed();
+        let outcome = runner
+            .apply(&step.action)
+            .unwrap_or_else(|err| Outcome::Error(format!("{err:#}")));
+        let stop = matches!(outcome, Outcome::Error(_));
+        report.steps.push(StepReport {
+            elapsed,
+            step: describe(&step.action),
+            outcome,
+        });
+        if stop {
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// `open` opens the volume of a scenario with all members in sync.
+fn open(scenario: &Scenario, disk_dir: &Path) -> Result<Box<dyn DynVolume>> {
+    let mut volume = open_volume(scenario.raid, disk_dir, scenario.disks, scenario.disk_size)?;
+    volume.clear_needs_rebuild_all();
+    volume.set_write_hole_policy(scenario.write_hole);
+    if let Some(cache) = scenario.write_cache {
+        volume.enable_write_cache(cache.plan());
+    }
+    Ok(volume)
+}
+
+/// `Runner` holds the volume under test and the content it should hold.
+struct Runner<'a> {
+    volume: Box<dyn DynVolume>,
+    scenario: &'a Scenario,
+    disk_dir: &'a Path,
+    expected: Vec<u8>,
+}
+
+impl Runner<'_> {
+    fn apply(&mut self, action: &Action) -> Result<Outcome> {
+        if let Some((failure, disk)) = action.failure() {
+            let end = self.volume.logical_capacity_bytes();
+            failure.apply(self.volume.as_mut(), disk, end)?;
+            let failed = self.volume.failed_disks();
+            return Ok(Outcome::Done(format!("{failed} failed disks")));
+        }
+        match *action {
+            Action::Write { seed } => {
+                StdRng::seed_from_u64(seed).fill_bytes(&mut self.expected);
+                // Whole stripes per write, so the full-stripe policy accepts them.
+                let stripe = self.volume.geometry().bytes_per_stripe;
+                let block = COPY_CHUNK - COPY_CHUNK % stripe;
+                for (i, chunk) in self.expected.chunks(block).enumerate() {
+                    self.volume.try_write_bytes((i * block) as u64, chunk)?;
+                }
+                Ok(Outcome::Done(format!("{} bytes", self.expected.len())))
+            }
+            Action::Corrupt {
+                disk,
+                offset,
+                bytes,
+            } => {
+                corrupt(&disk_image_path(self.disk_dir, disk), offset, bytes)?;
+                Ok(Outcome::Done(format!("{bytes} bytes inverted")))
+            }
+            Action::Scrub { repair } => Ok(Outcome::Done(self.scrub(repair))),
+            Action::Bench { block_size } => Ok(Outcome::Done(self.bench(block_size)?)),
+            Action::Flush => {
+                let pending = self.volume.write_cache_stats();
+                self.volume.flush();
+                let written = self.volume.write_cache_stats().written_back - pending.written_back;
+                Ok(Outcome::Done(format!("{written} cached writes stored")))
+            }
+            Action::PowerFail => self.power_fail().map(Outcome::Done),
+            Action::TornWrite {
+                offset,
+                bytes,
+                seed,
+            } => self.torn_write(offset, bytes, seed).map(Outcome::Done),
+            Action::Assert(ref assertion) => Ok(self.assert(assertion)),
+            _ => unreachable!("disk actions are handled above"),
+        }
+    }
+
+    /// `power_fail` crashes every member, then restarts the array from its images.
+    ///
+    /// Members that had failed stay failed; the rest are resynced the way an array
+    /// recovers from an unclean shutdown.
+    fn power_fail(&mut self) -> Result<String> {
+        self.volume.crash();
+        let lost = self.volume.write_cache_stats();
+        self.restart()?;
+        self.volume.resync();
+        Ok(format!(
+            "{} cached writes ({} bytes) lost",
+            lost.lost_writes, lost.lost_bytes
+        ))
+    }
+
+    /// `torn_write` writes seeded bytes and loses power between the data and the
+    /// redundancy writes of the first stripe they touch.
+    ///
+    /// Earlier writes are flushed first so only the torn stripe is at stake. The
+    /// array restarts without a resync; a journal is replayed on the way up.
+    fn torn_write(&mut self, offset: u64, bytes: u64, seed: u64) -> Result<String> {
+        let start = usize::try_from(offset)?;
+        let end = usize::try_from(bytes)
+            .ok()
+            .and_then(|bytes| start.checked_add(bytes))
+            .filter(|&end| end <= self.expected.len())
+            .with_context(|| format!("torn write {offset}+{bytes} is outside the volume"))?;
+        let mut data = vec![0u8; end - start];
+        StdRng::seed_from_u64(seed).fill_bytes(&mut data);
+
+        self.volume.flush();
+        let before = self.volume.write_hole_stats();
+        self.volume.simulate_crashes(CrashPlan {
+            seed,
+            crash_after_writes: Some(1),
+            write_hole: true,
+        });
+        let written = self.volume.try_write_bytes(offset, &data);
+        let stats = self.volume.write_hole_stats();
+        let torn = stats.torn_stripes - before.torn_stripes;
+        if torn > 0 {
+            // The data chunks of the first stripe are all that reached the disks.
+            let stripe = self.volume.geometry().bytes_per_stripe;
+            let torn_end = end.min((start / stripe + 1) * stripe);
+            self.expected[start..torn_end].copy_from_slice(&data[..torn_end - start]);
+        }
+        self.volume.crash();
+        self.restart()?;
+
+        let replayed = self.volume.write_hole_stats().replayed_stripes;
+        Ok(match written {
+            Ok(()) => format!(
+                "{torn} stripes torn, {} journaled, {replayed} replayed",
+                stats.journaled_stripes - before.journaled_stripes
+            ),
+            Err(err) => format!("write rejected ({err}), nothing torn"),
+        })
+    }
+
+    /// `restart` reopens the array from its images after a power failure.
+    ///
+    /// Members that had failed stay failed.
+    fn restart(&mut self) -> Result<()> {
+        let missing: Vec<usize> = (0..)
+            .zip(self.volume.disk_statuses())
+            .filter(|(_, status)| status.missing)
+            .map(|(i, _)| i)
+            .collect();
+
+        self.volume = open(self.scenario, self.disk_dir)?;
+        for &i in &missing {
+            self.volume.fail_disk(i)?;
+        }
+        Ok(())
+    }
+
+    fn scrub(&mut self, repair: bool) -> String {
+        let (mut repairable, mut uncorrectable) = (Vec::new(), 0u64);
+        for stripe in 0..self.volume.physical_stripes() {
+            match self.volume.check_stripe(stripe) {
+                StripeCheck::Repairable { .. } => repairable.push(stripe),
+                StripeCheck::Uncorrectable => uncorrectable += 1,
+                _ => {}
+            }
+        }
+        if repair {
+            self.volume.repair_stripes(&repairable);
+        }
+        let repairable = repairable.len();
+        let verb = if repair { "repaired" } else { "found" };
+        format!("{repairable} repairable stripes {verb}, {uncorrectable} uncorrectable")
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn bench(&mut self, block_size: usize) -> Result<String> {
+        let mib_per_second = |elapsed: Duration| {
+            self.expected.len() as f64 / (1024.0 * 1024.0) / elapsed.as_secs_f64().max(f64::EPSILON)
+        };
+
+        let mut buf = vec![0u8; block_size];
+        let start = Instant::now();
+        for offset in (0..self.expected.len()).step_by(block_size) {
+            let take = block_size.min(self.expected.len() - offset);
+            self.volume.read_bytes(offset as u64, &mut buf[..take]);
+        }
+        let read = start.elapsed();
+
+        let start = Instant::now();
+        for (i, chunk) in self.expected.chunks(block_size).enumerate() {
+            self.volume
+                .try_write_bytes((i * block_size) as u64, chunk)?;
+        }
+        let write = start.elapsed();
+
+        Ok(format!(
+            "read {:.1} MiB/s, write {:.1} MiB/s",
+            mib_per_second(read),
+            mib_per_second(write)
+        ))
+    }
+
+    fn assert(&mut self, assertion: &Assertion) -> Outcome {
+        let mut failures = Vec::new();
+        if let Some(want) = assertion.data_intact {
+            let intact = self.data_mismatch();
+            if intact.is_none() != want {
+                failures.push(intact.unwrap_or_else(|| "data is intact".to_string()));
+            }
+        }
+        if let Some(want) = assertion.failed_disks {
+            let got = self.volume.failed_disks();
+            if got != want {
+                failures.push(format!("{got} failed disks"));
+            }
+        }
+        if let Some(want) = assertion.degraded {
+            let got = self
+                .volume
+                .disk_statuses()
+                .iter()
+                .any(|d| d.missing || d.needs_rebuild);
+            if got != want {
+                failures.push(format!("degraded is {got}"));
+            }
+        }
+        if assertion.repairable_stripes.is_some() || assertion.uncorrectable_stripes.is_some() {
+            let check = self.volume.check();
+            if let Some(want) = assertion
+                .repairable_stripes
+                .filter(|&n| n != check.repairable_stripes)
+            {
+                failures.push(format!(
+                    "{} repairable stripes, want {want}",
+                    check.repairable_stripes
+                ));
+            }
+            if let Some(want) = assertion
+                .uncorrectable_stripes
+                .filter(|&n| n != check.uncorrectable_stripes)
+            {
+                failures.push(format!(
+                    "{} uncorrectable stripes, want {want}",
+                    check.uncorrectable_stripes
+                ));
+            }
+        }
+        if failures.is_empty() {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(failures.join("; "))
+        }
+    }
+
+    /// `data_mismatch` describes where the volume first differs from the expected content.
+    fn data_mismatch(&mut self) -> Option<String> {
+        let mut buf = vec![0u8; COPY_CHUNK];
+        for (i, want) in self.expected.chunks(COPY_CHUNK).enumerate() {
+            let offset = i * COPY_CHUNK;
+            let got = &mut buf[..want.len()];
+            if let Err(err) = self.volume.try_read_bytes(offset as u64, got) {
+                return Some(format!("read at {offset} failed: {err:#}"));
+            }
+            if let Some(pos) = got.iter().zip(want).position(|(a, b)| a != b) {
+                return Some(format!("data differs at byte {}", offset + pos));
+            }
+        }
+        None
+    }
+}
+
+/// `corrupt` inverts bytes of a disk image in place.
+fn corrupt(path: &Path, offset: u64, bytes: u64) -> Result<()> {
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    let mut buf = vec![0u8; usize::try_from(bytes)?];
+    file.read_exact_at(&mut buf, offset)?;
+    for b in &mut buf {
+        *b = !*b;
+    }
+    file.write_all_at(&buf, offset)?;
+    Ok(())
+}
+
+fn describe(action: &Action) -> String {
+    if let Some((failure, disk)) = action.failure() {
+        return format!("{} disk {disk}", format!("{failure:?}").to_lowercase());
+    }
+    match action {
+        Action::Write { seed } => format!("write seed {seed}"),
+        Action::Corrupt {
+            disk,
+            offset,
+            bytes,
+        } => format!("corrupt disk {disk} bytes {offset}..{}", offset + bytes),
+        Action::Scrub { repair: true } => "scrub".to_string(),
+        Action::Scrub { repair: false } => "scrub without repair".to_string(),
+        Action::Bench { block_size } => format!("bench {block_size}-byte blocks"),
+        Action::Flush => "flush".to_string(),
+        Action::PowerFail => "power failure".to_string(),
+        Action::TornWrite { offset, bytes, .. } => {
+            format!("torn write bytes {offset}..{}", offset + bytes)
+        }
+        Action::Assert(assertion) => {
+            let mut txt = "assert".to_string();
+            let mut expect = |name: &str, value: Option<String>| {
+                if let Some(value) = value {
+                    let _ = write!(txt, " {name}={value}");
+                }
+            };
+            expect("data_intact", assertion.data_intact.map(|v| v.to_string()));
+            expect(
+                "failed_disks",
+                assertion.failed_disks.map(|v| v.to_string()),
+            );
+            expect("degraded", assertion.degraded.map(|v| v.to_string()));
+            expect(
+                "repairable_stripes",
+                assertion.repairable_stripes.map(|v| v.to_string()),
+            );
+            expect(
+                "uncorrectable_stripes",
+                assertion.uncorrectable_stripes.map(|v| v.to_string()),
+            );
+            txt
+        }
+        _ => unreachable!("disk actions are described above"),
+    }
+}
+
+/// `render` formats the report, one line per step and a verdict.
+fn render(report: &ScenarioReport) -> String {
+    let mut txt = String::new();
+    for step in &report.steps {
+        let outcome = match &step.outcome {
+            Outcome::Done(summary) => format!("ok ({summary})"),
+            Outcome::Passed => "PASS".to_string(),
+            Outcome::Failed(why) => format!("FAIL ({why})"),
+            Outcome::Error(err) => format!("ERROR ({err})"),
+        };
+        let _ = writeln!(
+            txt,
+            "  [{:>8.3}s] {}: {outcome}",
+            step.elapsed.as_secs_f64(),
+            step.step
+        );
+    }
+    let (passed, assertions) = report.assertions();
+    let _ = writeln!(
+        txt,
+        "result: {} ({passed} of {assertions} assertions passed, {} of {} steps run)",
+        if report.passed() { "PASS" } else { "FAIL" },
+        report.steps.len(),
+        report.total_steps
+    );
+    txt
+}
+
+fn scratch_dir() -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("raid-cli-scenario-{}-{nanos}", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+
+    const RAID3_SCENARIO: &str = "
+name: raid3 lab
+raid: raid3
+disks: 3
+disk_size: 65536
+steps:
+  - action: write
+    seed: 7
+  - action: fail
+    disk: 1
+  - action: assert
+    data_intact: true
+    failed_disks: 1
+    degraded: true
+  - action: replace
+    disk: 1
+  - action: corrupt
+    disk: 2
+  - action: assert
+    repairable_stripes: 1
+  - action: scrub
+  - action: bench
+    block_size: 8192
+  - action: assert
+    data_intact: true
+    degraded: false
+    repairable_stripes: 0
+    uncorrectable_stripes: 0
+";
+
+    #[test]
+    fn raid3_scenario_survives_failure_and_parity_corruption() {
+        let scenario = Scenario::from_yaml(RAID3_SCENARIO).expect("scenario");
+
+        let report = execute(&scenario, &temp_dir("raid-cli-scenario-raid3")).expect("execute");
+
+        assert!(report.passed(), "{}", render(&report));
+        assert_eq!(report.assertions(), (3, 3));
+        assert!(
+            render(&report)
+                .ends_with("result: PASS (3 of 3 assertions passed, 9 of 9 steps run)\n")
+        );
+    }
+
+    #[test]
+    fn failed_assertions_and_errors_fail_the_scenario() {
+        let scenario = Scenario::from_yaml(
+            "
+raid: raid0
+disks: 2
+disk_size: 4096
+steps:
+  - action: write
+  - action: corrupt
+    disk: 0
+  - action: assert
+    data_intact: true
+  - action: readd
+    disk: 0
+  - action: assert
+    failed_disks: 0
+",
+        )
+        .expect("scenario");
+
+        let report = execute(&scenario, &temp_dir("raid-cli-scenario-raid0")).expect("execute");
+
+        assert!(!report.passed());
+        assert!(
+            matches!(&report.steps[2].outcome, Outcome::Failed(why) if why.contains("differs at byte 0"))
+        );
+        assert!(matches!(report.steps[3].outcome, Outcome::Error(_)));
+        assert_eq!(report.steps.len(), 4);
+    }
+
+    #[test]
+    fn power_failure_loses_only_unflushed_writes() {
+        let run = |volatility: f64, barrier: bool| {
+            let scenario = Scenario::from_yaml(&format!(
+                "
+raid: raid1
+disks: 2
+disk_size: 16384
+write_cache:
+  volatility: {volatility}
+steps:
+  - action: write
+    seed: 1
+  - action: {}
+  - action: write
+    seed: 2
+  - action: power_fail
+  - action: assert
+    data_intact: true
+    failed_disks: 0
+",
+                if barrier { "flush" } else { "scrub" }
+            ))
+            .expect("scenario");
+            execute(&scenario, &temp_dir("raid-cli-scenario-power-fail")).expect("execute")
+        };
+
+        let volatile = run(1.0, true);
+        assert!(
+            matches!(&volatile.steps[3].outcome, Outcome::Done(lost) if !lost.starts_with("0 ")),
+            "{}",
+            render(&volatile)
+        );
+        assert!(matches!(volatile.steps[4].outcome, Outcome::Failed(_)));
+
+        let battery_backed = run(0.0, false);
+        assert!(battery_backed.passed(), "{}", render(&battery_backed));
+        assert!(
+            render(&battery_backed).contains("power failure: ok (0 cached writes (0 bytes) lost)")
+        );
+    }
+
+    #[test]
+    fn journal_closes_the_write_hole_that_breaks_rebuild() {
+        let run = |policy: &str| {
+            let scenario = Scenario::from_yaml(&format!(
+                "
+raid: raid3
+disks: 3
+disk_size: 4096
+write_hole: {policy}
+steps:
+  - action: write
+    seed: 4
+  - action: torn_write
+    offset: 16
+    bytes: 4
+    seed: 5
+  - action: assert
+    repairable_stripes: 0
+  - action: fail
+    disk: 1
+  - action: assert
+    data_intact: true
+"
+            ))
+            .expect("scenario");
+            execute(&scenario, &temp_dir("raid-cli-scenario-write-hole")).expect("execute")
+        };
+
+        let unprotected = run("unprotected");
+        assert!(
+            render(&unprotected)
+                .contains("torn write bytes 16..20: ok (1 stripes torn, 0 journaled, 0 replayed)"),
+            "{}",
+            render(&unprotected)
+        );
+        assert!(
+            matches!(&unprotected.steps[2].outcome, Outcome::Failed(why) if why == "1 repairable stripes, want 0")
+        );
+        assert!(matches!(unprotected.steps[4].outcome, Outcome::Failed(_)));
+
+        let journal = run("journal");
+        assert!(journal.passed(), "{}", render(&journal));
+        assert!(render(&journal).contains("(1 stripes torn, 1 journaled, 1 replayed)"));
+
+        let full_stripe = run("full_stripe");
+        assert!(full_stripe.passed(), "{}", render(&full_stripe));
+        assert!(render(&full_stripe).contains("write rejected"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Offline removal of trailing member disks from an existing array.
+//!
+//! Shrinking moves each stripe to a lower logical density, so data is evacuated into a
+//! staging file before the remaining members are restriped and the removed images deleted.
+
+use std::fs::File;
+use std::io::Write;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use raid_rs::retention::volume::{BalanceCheckpoint, DynVolume, ReshapeCheckpoint};
+
+use crate::cli::{RaidMode, ShrinkArgs};
+use crate::commands::{
+    COPY_CHUNK, Progress, check_all_members_present, check_existing_images, disk_image_path,
+    rewrite_layout,
+};
+use crate::fs::DEFAULT_CHUNK_SIZE;
+use crate::metrics_runtime::MetricsEmitter;
+use crate::superblock;
+use crate::volume::{
+    data_disks, logical_capacity, open_reshaped_volume, open_volume, used_extent, validate_geometry,
+};
+
+const STAGING_FILE: &str = "shrink.staging";
+
+/// `run` removes trailing member disks and restripes the remaining members.
+///
+/// RAID0 and RAID3 arrays are evacuated and restriped; RAID1 arrays drop the extra mirrors.
+///
+/// # Arguments
+/// * `args` - Shrink arguments.
+/// * `metrics` - Emitter used to publish reshape progress.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, member images are missing, live data
+/// would not fit on the remaining members, or a balance or online grow has not finished.
+pub fn run(args: &ShrinkArgs, metrics: &MetricsEmitter) -> Result<()> {
+    if args.remove == 0 {
+        anyhow::bail!("--remove must be at least 1");
+    }
+    if BalanceCheckpoint::load(&args.disk_dir)?.is_some() {
+        anyhow::bail!("a balance from an earlier grow has not finished; mount the array first");
+    }
+    if ReshapeCheckpoint::load(&args.disk_dir)?.is_some() {
+        anyhow::bail!("an online grow has not finished; mount the array first");
+    }
+    let new_disks = args
+        .disks
+        .checked_sub(args.remove)
+        .ok_or_else(|| anyhow::anyhow!("cannot remove {} of {} disks", args.remove, args.disks))?;
+    validate_geometry(args.raid, args.disks)?;
+    validate_geometry(args.raid, new_disks)?;
+
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let mut old = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    if old.snapshots_enabled() {
+        anyhow::bail!("cannot shrink an array with snapshots; run `snapshot disable` first");
+    }
+    if old.is_thin() {
+        anyhow::bail!("cannot shrink a thin-provisioned array");
+    }
+    let old_capacity = old.logical_capacity_bytes();
+    let new_capacity = logical_capacity(args.raid, new_disks, disk_size);
+    let extent = used_extent(old.as_mut())
+        .unwrap_or(old_capacity)
+        .min(old_capacity);
+
+    println!(
+        "shrink: {:?} x{} ({old_capacity} bytes) -> x{new_disks} ({new_capacity} bytes)",
+        args.raid, args.disks
+    );
+    println!("shrink: {extent} bytes of live data");
+
+    if extent > new_capacity {
+        anyhow::bail!(
+            "live data ({extent} bytes) does not fit in the shrunk capacity of {new_capacity} bytes"
+        );
+    }
+
+    if args.raid != RaidMode::Raid1 {
+        let staging_path = args.disk_dir.join(STAGING_FILE);
+        stage(old.as_mut(), extent, &staging_path)?;
+        drop(old);
+
+        let staging = File::open(&staging_path)
+            .with_context(|| format!("open {}", staging_path.display()))?;
+        let mut new = open_reshaped_volume(args.raid, &args.disk_dir, new_disks, disk_size)?;
+        let stripe_bytes = data_disks(args.raid, new_disks) * DEFAULT_CHUNK_SIZE;
+        let failed = new.failed_disks();
+        let mut progress = Progress::new("shrink", new_capacity);
+        rewrite_layout(
+            &mut |off, buf| {
+                staging
+                    .read_exact_at(buf, off)
+                    .with_context(|| format!("read {}", staging_path.display()))
+            },
+            extent,
+            new.as_mut(),
+            stripe_bytes,
+            &mut |done| {
+                if progress.update(done) {
+                    metrics.record_raid_state(failed, true, progress.fraction(done));
+                }
+            },
+        )?;
+        new.clear_needs_rebuild_all();
+        drop(staging);
+        std::fs::remove_file(&staging_path)
+            .with_context(|| format!("remove {}", staging_path.display()))?;
+    }
+
+    for i in new_disks..args.disks {
+        let path = disk_image_path(&args.disk_dir, i);
+        std::fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+    }
+    // RAID1 keeps its mirrors in place, so only the superblocks learn the new count.
+    superblock::restamp(args.raid, &args.disk_dir, new_disks, disk_size)?
+        .write_pending(&args.disk_dir)?;
+    metrics.record_raid_state(0, false, 1.0);
+
+    println!("shrink: done; mount with --disks {new_disks}");
+    Ok(())
+}
+
+/// `stage` copies the live logical range of a volume into a staging file.
+fn stage(volume: &mut dyn DynVolume, extent: u64, path: &Path) -> Result<()> {
+    let mut file = File::create(path).with_context(|| format!("create {}", path.display()))?;
+    let mut progress = Progress::new("shrink: evacuate", extent);
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut copied = 0u64;
+    while copied < extent {
+        let take = usize::try_from(extent - copied)
+            .unwrap_or(usize::MAX)
+            .min(COPY_CHUNK);
+        volume.read_bytes(copied, &mut buf[..take]);
+        file.write_all(&buf[..take])
+            .with_context(|| format!("write {}", path.display()))?;
+        copied += take as u64;
+        progress.update(copied);
+    }
+    file.sync_all()
+        .with_context(|| format!("sync {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use crate::fs::metadata::Header;
+    use crate::fs::test_utils::temp_dir;
+    use crate::fs::{HEADER_SIZE, RaidFs};
+    use clap::Parser;
+    use raid_rs::layout::stripe::raid0::RAID0;
+    use tokio::sync::mpsc;
+
+    type HeaderFs = RaidFs<1, DEFAULT_CHUNK_SIZE, RAID0<1, DEFAULT_CHUNK_SIZE>>;
+
+    const DISK_SIZE: u64 = 8192;
+
+    fn args(dir: &Path, raid: RaidMode, disks: usize) -> ShrinkArgs {
+        let cli = Cli::parse_from([
+            "raid-cli".to_string(),
+            "shrink".to_string(),
+            "--disk-dir".to_string(),
+            dir.display().to_string(),
+            "--disks".to_string(),
+            disks.to_string(),
+            "--disk-size".to_string(),
+            DISK_SIZE.to_string(),
+        ]);
+        let Command::Shrink(mut args) = cli.command else {
+            panic!("expected shrink command");
+        };
+        args.raid = raid;
+        args
+    }
+
+    fn emitter() -> std::sync::Arc<MetricsEmitter> {
+        let (tx, _rx) = mpsc::channel(1024);
+        MetricsEmitter::new("raid-test".to_string(), tx)
+    }
+
+    /// Writes a filesystem header claiming `used` bytes plus a matching payload.
+    fn seed(dir: &Path, raid: RaidMode, disks: usize, used: u64) -> Vec<u8> {
+        let mut volume = open_volume(raid, dir, disks, DISK_SIZE).expect("open volume");
+        let mut payload: Vec<u8> = (0..used)
+            .map(|i| u8::try_from(i % 251).expect("fits in u8"))
+            .collect();
+        let header = HeaderFs::header_bytes(&Header {
+            next_free: used,
+            quotas: false,
+        });
+        payload[..HEADER_SIZE].copy_from_slice(&header);
+        volume.write_bytes(0, &payload);
+        payload
+    }
+
+    fn read_back(dir: &Path, raid: RaidMode, disks: usize, len: usize) -> Vec<u8> {
+        let mut volume = open_volume(raid, dir, disks, DISK_SIZE).expect("open shrunk");
+        let mut out = vec![0u8; len];
+        volume.read_bytes(0, &mut out);
+        out
+    }
+
+    #[test]
+    fn shrink_restripes_raid0_and_removes_image() {
+        let dir = temp_dir("raid-cli-shrink-raid0");
+        let payload = seed(&dir, RaidMode::Raid0, 3, 12_000);
+
+        run(&args(&dir, RaidMode::Raid0, 3), &emitter()).expect("shrink");
+
+        assert!(!disk_image_path(&dir, 2).exists());
+        assert!(!dir.join(STAGING_FILE).exists());
+        assert_eq!(read_back(&dir, RaidMode::Raid0, 2, payload.len()), payload);
+    }
+
+    #[test]
+    fn shrink_restripes_raid3() {
+        let dir = temp_dir("raid-cli-shrink-raid3");
+        let payload = seed(&dir, RaidMode::Raid3, 4, 12_000);
+
+        run(&args(&dir, RaidMode::Raid3, 4), &emitter()).expect("shrink");
+
+        assert!(!disk_image_path(&dir, 3).exists());
+        assert_eq!(read_back(&dir, RaidMode::Raid3, 3, payload.len()), payload);
+    }
+
+    #[test]
+    fn shrink_drops_raid1_mirror() {
+        let dir = temp_dir("raid-cli-shrink-raid1");
+        let payload = seed(&dir, RaidMode::Raid1, 3, 8_000);
+
+        run(&args(&dir, RaidMode::Raid1, 3), &emitter()).expect("shrink");
+
+        assert!(!disk_image_path(&dir, 2).exists());
+        assert_eq!(read_back(&dir, RaidMode::Raid1, 2, payload.len()), payload);
+    }
+
+    #[test]
+    fn shrink_refuses_when_live_data_does_not_fit() {
+        let dir = temp_dir("raid-cli-shrink-full");
+        seed(&dir, RaidMode::Raid0, 2, 12_000);
+
+        let err = run(&args(&dir, RaidMode::Raid0, 2), &emitter()).expect_err("expected error");
+        assert!(err.to_string().contains("does not fit"));
+        assert!(disk_image_path(&dir, 1).exists());
+    }
+}
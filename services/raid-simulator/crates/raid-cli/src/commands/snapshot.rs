@@ -0,0 +1,147 @@
+//! Offline management of copy-on-write volume snapshots.
+//!
+//! The first `create` reserves the tail of the volume for snapshot storage, which
+//! shrinks the capacity the filesystem sees on its next mount.
+
+use anyhow::Result;
+use raid_rs::retention::volume::{DynVolume, SnapshotInfo};
+
+use crate::cli::{SnapshotAction, SnapshotArgs};
+use crate::commands::{check_all_members_present, check_existing_images};
+use crate::volume::{open_volume, used_extent, validate_geometry};
+
+/// `run` executes a snapshot action against the disk images of an array.
+///
+/// # Arguments
+/// * `args` - Snapshot arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, or the action fails.
+pub fn run(args: &SnapshotArgs) -> Result<()> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    match args.action {
+        SnapshotAction::Create => {
+            let name = required_name(args)?;
+            if !volume.snapshots_enabled() {
+                enable(volume.as_mut(), args.reserve_percent)?;
+            }
+            let info = volume.snapshot_create(name)?;
+            println!("snapshot: created {} (id {})", info.name, info.id);
+        }
+        SnapshotAction::List => {
+            for info in volume.snapshot_list() {
+                println!("{}", describe(&info));
+            }
+        }
+        SnapshotAction::Delete => {
+            let name = required_name(args)?;
+            volume.snapshot_delete(name)?;
+            println!("snapshot: deleted {name}");
+        }
+        SnapshotAction::Rollback => {
+            let name = required_name(args)?;
+            volume.snapshot_rollback(name)?;
+            println!("snapshot: rolled back to {name}");
+        }
+        SnapshotAction::Disable => {
+            volume.disable_snapshots()?;
+            println!(
+                "snapshot: reserve released; logical capacity is now {} bytes",
+                volume.logical_capacity_bytes()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn required_name(args: &SnapshotArgs) -> Result<&str> {
+    args.name
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--name is required for {:?}", args.action))
+}
+
+/// `enable` reserves a percentage of the volume for snapshot storage.
+fn enable(volume: &mut dyn DynVolume, reserve_percent: u8) -> Result<()> {
+    if reserve_percent == 0 || reserve_percent >= 100 {
+        anyhow::bail!("--reserve-percent must be between 1 and 99");
+    }
+    let capacity = volume.logical_capacity_bytes();
+    let reserve = capacity / 100 * u64::from(reserve_percent);
+    let data_end = used_extent(volume).unwrap_or(0);
+    volume.init_snapshots(reserve, data_end)?;
+    println!(
+        "snapshot: reserved {} bytes; logical capacity is now {} bytes",
+        capacity - volume.logical_capacity_bytes(),
+        volume.logical_capacity_bytes()
+    );
+    Ok(())
+}
+
+fn describe(info: &SnapshotInfo) -> String {
+    let state = if info.valid { "" } else { " INVALID" };
+    format!(
+        "{}\tid={}\tcreated={}\tpreserved_blocks={}{state}",
+        info.name, info.id, info.created_unix, info.preserved_blocks
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RaidMode;
+    use crate::fs::test_utils::temp_dir;
+
+    const DISK_SIZE: u64 = 8192;
+
+    fn args(dir: &std::path::Path, action: SnapshotAction, name: Option<&str>) -> SnapshotArgs {
+        SnapshotArgs {
+            action,
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: DISK_SIZE,
+            name: name.map(str::to_string),
+            reserve_percent: 25,
+        }
+    }
+
+    fn open(dir: &std::path::Path) -> Box<dyn DynVolume> {
+        open_volume(RaidMode::Raid3, dir, 3, DISK_SIZE).expect("open volume")
+    }
+
+    #[test]
+    fn create_reserves_region_and_rollback_restores() {
+        let dir = temp_dir("raid-cli-snapshot");
+        let mut volume = open(&dir);
+        let raw = volume.logical_capacity_bytes();
+        volume.write_bytes(0, b"before");
+        drop(volume);
+
+        run(&args(&dir, SnapshotAction::Create, Some("s1"))).expect("create");
+        let mut volume = open(&dir);
+        assert!(volume.logical_capacity_bytes() < raw);
+        volume.write_bytes(0, b"after!");
+        drop(volume);
+
+        run(&args(&dir, SnapshotAction::Rollback, Some("s1"))).expect("rollback");
+        let mut volume = open(&dir);
+        let mut out = [0u8; 6];
+        volume.read_bytes(0, &mut out);
+        assert_eq!(&out, b"before");
+        assert_eq!(volume.snapshot_list().len(), 1);
+    }
+
+    #[test]
+    fn delete_requires_name() {
+        let dir = temp_dir("raid-cli-snapshot-name");
+        drop(open(&dir));
+
+        let err = run(&args(&dir, SnapshotAction::Delete, None)).expect_err("expected error");
+        assert!(err.to_string().contains("--name"));
+    }
+}
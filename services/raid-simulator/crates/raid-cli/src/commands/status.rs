@@ -0,0 +1,83 @@
+//! Array status report for offline disk images.
+
+use anyhow::Result;
+
+use crate::cli::StatusArgs;
+use crate::commands::{check_all_members_present, check_existing_images};
+use crate::volume::{open_volume, validate_geometry};
+
+/// `run` prints the status of an array as an mdstat-style view or as JSON.
+///
+/// # Arguments
+/// * `args` - Status arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid or images are missing.
+pub fn run(args: &StatusArgs) -> Result<()> {
+    println!("{}", render(args)?.trim_end());
+    Ok(())
+}
+
+/// `render` builds the status report without printing it.
+///
+/// # Arguments
+/// * `args` - Status arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, or serialization fails.
+pub fn render(args: &StatusArgs) -> Result<String> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    let status = volume.status();
+    if args.json {
+        return Ok(serde_json::to_string_pretty(&status)?);
+    }
+    Ok(status.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::RaidMode;
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(dir: &std::path::Path, json: bool) -> StatusArgs {
+        StatusArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64,
+            json,
+        }
+    }
+
+    #[test]
+    fn status_renders_mdstat_view() {
+        let dir = temp_dir("raid-cli-status-text");
+        drop(open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume"));
+
+        let report = render(&args(&dir, false)).expect("status");
+
+        assert!(report.starts_with("array : 3 disks, 2 data"));
+        assert!(report.contains("[3/3] [UUU]"));
+    }
+
+    #[test]
+    fn status_renders_json() {
+        let dir = temp_dir("raid-cli-status-json");
+        drop(open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume"));
+
+        let report = render(&args(&dir, true)).expect("status");
+        let value: serde_json::Value = serde_json::from_str(&report).expect("valid json");
+
+        assert_eq!(value["disks"], 3);
+        assert_eq!(value["members"][2]["state"], "ok");
+        assert_eq!(value["members"][0]["read_errors"], 0);
+        assert!(value["members"][0]["reads"].as_u64().is_some());
+        assert!(value["rebuild"].is_null());
+    }
+}
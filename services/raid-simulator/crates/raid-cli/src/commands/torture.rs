@@ -0,0 +1,736 @@
+//! Fsync durability matrix of the filesystem served by a FUSE mount.
+//!
+//! Each pattern is a short sequence of file operations in the style of the
+//! `CrashMonkey` corpus: create, write, fsync, unlink, link an unnamed temp file
+//! into place. Patterns run against the same `RaidFs` the mount serves, on member
+//! disks that cache their writes; right after the last operation the power fails.
+//! The images are then reopened, resynced and mounted again, and the files found
+//! are compared with what the pattern was promised. Running every pattern under
+//! several crash seeds shows which fsync guarantees the configuration provides.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use raid_rs::layout::stripe::raid0::RAID0;
+use raid_rs::layout::stripe::raid1::RAID1;
+use raid_rs::layout::stripe::raid3::RAID3;
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::volume::CrashPlan;
+use raid_rs::simulator::SimulatorBuilder;
+use rand::Rng;
+
+use crate::cli::{RaidMode, TortureArgs};
+use crate::commands::ensure_scratch_dir;
+use crate::fs::{
+    DEFAULT_CHUNK_SIZE, DEFAULT_MAX_AGE, FsState, GrowState, OpenFiles, ROOT_ID, RaidFs, Scrub,
+    WriteBuffer,
+};
+use crate::mount::load_filesystem;
+use crate::seed::{self, Component};
+use crate::volume::{DeclusteredLayout, validate_geometry};
+
+/// `Op` is one file operation of a pattern. Files are named by the name they
+/// were created under, even after they are unlinked or linked elsewhere.
+#[derive(Copy, Clone, Debug)]
+enum Op {
+    /// Create a file and keep a handle open.
+    Create(&'static str),
+    /// Create an unnamed file with `O_TMPFILE` and keep a handle open; the label
+    /// only identifies it within the pattern.
+    Tmpfile(&'static str),
+    /// Write `len` bytes at `offset` of a file.
+    Write {
+        file: &'static str,
+        offset: u64,
+        len: u64,
+    },
+    /// Remove the name of a file; an open file lives on unnamed.
+    Unlink(&'static str),
+    /// Give an unnamed open file a new name.
+    Link {
+        file: &'static str,
+        name: &'static str,
+    },
+    /// Release the handle of a file.
+    Close(&'static str),
+    /// Sync the filesystem, as `fsync` on any of its files does.
+    Fsync,
+}
+
+/// `Expect` is what a name must hold after the crash.
+#[derive(Copy, Clone, Debug)]
+enum Expect {
+    /// No file goes by the name.
+    Absent,
+    /// The file as it was at the last fsync.
+    Synced,
+    /// The file as it was when the power failed.
+    Final,
+}
+
+/// `Pattern` is an operation sequence and the guarantee it checks.
+struct Pattern {
+    name: &'static str,
+    /// Whether POSIX requires the guarantee of a file system.
+    posix: bool,
+    guarantee: &'static str,
+    ops: &'static [Op],
+    expect: &'static [(&'static str, Expect)],
+}
+
+const PATTERNS: &[Pattern] = &[
+    Pattern {
+        name: "create-write-fsync",
+        posix: true,
+        guarantee: "fsync makes a new file and its data durable",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 3000,
+            },
+            Op::Fsync,
+        ],
+        expect: &[("a", Expect::Synced)],
+    },
+    Pattern {
+        name: "append-fsync",
+        posix: true,
+        guarantee: "fsync makes an append durable",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 2000,
+            },
+            Op::Fsync,
+            Op::Write {
+                file: "a",
+                offset: 2000,
+                len: 2500,
+            },
+            Op::Fsync,
+        ],
+        expect: &[("a", Expect::Synced)],
+    },
+    Pattern {
+        name: "overwrite-fsync",
+        posix: true,
+        guarantee: "fsync makes an overwrite durable without tearing the rest",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 4096,
+            },
+            Op::Fsync,
+            Op::Write {
+                file: "a",
+                offset: 1000,
+                len: 700,
+            },
+            Op::Fsync,
+        ],
+        expect: &[("a", Expect::Synced)],
+    },
+    Pattern {
+        name: "unlink-fsync",
+        posix: true,
+        guarantee: "fsync makes an unlink durable",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 1500,
+            },
+            Op::Close("a"),
+            Op::Fsync,
+            Op::Unlink("a"),
+            Op::Fsync,
+        ],
+        expect: &[("a", Expect::Absent)],
+    },
+    Pattern {
+        name: "tmpfile-link-fsync",
+        posix: true,
+        guarantee: "fsync makes a temp file linked into place durable",
+        ops: &[
+            Op::Tmpfile("a.tmp"),
+            Op::Write {
+                file: "a.tmp",
+                offset: 0,
+                len: 2048,
+            },
+            Op::Link {
+                file: "a.tmp",
+                name: "a",
+            },
+            Op::Fsync,
+        ],
+        expect: &[("a", Expect::Synced), ("a.tmp", Expect::Absent)],
+    },
+    Pattern {
+        name: "unlinked-open-crash",
+        posix: true,
+        guarantee: "a file unlinked while open does not come back",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 1024,
+            },
+            Op::Fsync,
+            Op::Unlink("a"),
+            Op::Fsync,
+        ],
+        expect: &[("a", Expect::Absent)],
+    },
+    Pattern {
+        name: "write-no-fsync",
+        posix: false,
+        guarantee: "a new file survives a crash without fsync",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 3000,
+            },
+        ],
+        expect: &[("a", Expect::Final)],
+    },
+    Pattern {
+        name: "append-no-fsync",
+        posix: false,
+        guarantee: "an append survives a crash without fsync",
+        ops: &[
+            Op::Create("a"),
+            Op::Write {
+                file: "a",
+                offset: 0,
+                len: 2000,
+            },
+            Op::Fsync,
+            Op::Write {
+                file: "a",
+                offset: 2000,
+                len: 2000,
+            },
+        ],
+        expect: &[("a", Expect::Final)],
+    },
+];
+
+/// `PatternReport` counts how often the guarantee of a pattern held.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternReport {
+    pub name: &'static str,
+    pub posix: bool,
+    pub guarantee: &'static str,
+    pub runs: u64,
+    pub held: u64,
+    /// What the first crash that broke the guarantee left behind.
+    pub first_break: Option<String>,
+}
+
+impl PatternReport {
+    #[must_use]
+    /// `provided` reports whether the guarantee held after every crash.
+    pub const fn provided(&self) -> bool {
+        self.held == self.runs
+    }
+}
+
+/// `TortureReport` is the durability matrix of one configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TortureReport {
+    pub patterns: Vec<PatternReport>,
+    /// Cached disk writes the crashes dropped.
+    pub lost_writes: u64,
+}
+
+impl TortureReport {
+    /// `broken` returns the POSIX guarantees that did not always hold.
+    pub fn broken(&self) -> impl Iterator<Item = &PatternReport> {
+        self.patterns
+            .iter()
+            .filter(|pattern| pattern.posix && !pattern.provided())
+    }
+}
+
+/// `run` builds the durability matrix of a configuration and prints it.
+///
+/// # Arguments
+/// * `args` - Torture arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the disk directory already holds
+/// an array, a pattern cannot run, or a guarantee POSIX requires was broken.
+pub fn run(args: &TortureArgs) -> Result<()> {
+    let seed = seed::rng(Component::WriteCache).random();
+    let (disk_dir, temporary) = args
+        .disk_dir
+        .clone()
+        .map_or_else(|| (scratch_dir(), true), |dir| (dir, false));
+    let report = execute(args, &disk_dir, seed);
+    if temporary {
+        let _ = std::fs::remove_dir_all(&disk_dir);
+    }
+    let report = report?;
+
+    let buffer = args
+        .write_buffer
+        .map_or_else(|| "off".to_string(), |bytes| format!("{bytes} bytes"));
+    println!(
+        "torture: {:?} with {} disks of {} bytes, write buffer {buffer}, {} crashes per pattern",
+        args.raid, args.disks, args.disk_size, args.crashes
+    );
+    print!("{}", render(&report));
+    let broken = report.broken().count();
+    if broken > 0 {
+        anyhow::bail!("the configuration breaks {broken} fsync guarantees POSIX requires");
+    }
+    Ok(())
+}
+
+/// `execute` runs every pattern under `args.crashes` crashes.
+///
+/// Every run gets fresh disk images in a directory of its own below `disk_dir`.
+/// The images of runs that broke a guarantee are kept for inspection.
+///
+/// # Arguments
+/// * `args` - Torture arguments.
+/// * `disk_dir` - Directory for the disk images; must not hold an array yet.
+/// * `seed` - Seed of the first crash; later crashes count up from it.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, the directory already holds an
+/// array, or a pattern cannot run. Broken guarantees are reported, not returned.
+pub fn execute(args: &TortureArgs, disk_dir: &Path, seed: u64) -> Result<TortureReport> {
+    validate_geometry(args.raid, args.disks)?;
+    ensure_scratch_dir("torture", disk_dir, args.disks)?;
+    match args.disks {
+        1 => execute_with::<1>(args, disk_dir, seed),
+        2 => execute_with::<2>(args, disk_dir, seed),
+        3 => execute_with::<3>(args, disk_dir, seed),
+        4 => execute_with::<4>(args, disk_dir, seed),
+        5 => execute_with::<5>(args, disk_dir, seed),
+        6 => execute_with::<6>(args, disk_dir, seed),
+        7 => execute_with::<7>(args, disk_dir, seed),
+        8 => execute_with::<8>(args, disk_dir, seed),
+        disks => Err(anyhow::anyhow!(
+            "unsupported disk count {disks}; supported range is 1-8"
+        )),
+    }
+}
+
+fn execute_with<const D: usize>(
+    args: &TortureArgs,
+    disk_dir: &Path,
+    seed: u64,
+) -> Result<TortureReport> {
+    match args.raid {
+        RaidMode::Raid0 => matrix::<D, DEFAULT_CHUNK_SIZE, _>(args, disk_dir, seed, RAID0::zero),
+        RaidMode::Raid1 => matrix::<D, DEFAULT_CHUNK_SIZE, _>(args, disk_dir, seed, RAID1::zero),
+        RaidMode::Raid3 => matrix::<D, DEFAULT_CHUNK_SIZE, _>(args, disk_dir, seed, RAID3::zero),
+        RaidMode::Declustered => {
+            matrix::<D, DEFAULT_CHUNK_SIZE, _>(args, disk_dir, seed, DeclusteredLayout::zero)
+        }
+    }
+}
+
+fn matrix<const D: usize, const N: usize, T: Stripe<D, N>>(
+    args: &TortureArgs,
+    disk_dir: &Path,
+    seed: u64,
+    layout: fn() -> T,
+) -> Result<TortureReport> {
+    let mut report = TortureReport::default();
+    for pattern in PATTERNS {
+        let mut result = PatternReport {
+            name: pattern.name,
+            posix: pattern.posix,
+            guarantee: pattern.guarantee,
+            runs: args.crashes,
+            held: 0,
+            first_break: None,
+        };
+        for crash in 0..args.crashes {
+            let dir = disk_dir.join(format!("{}-{crash}", pattern.name));
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+            let outcome =
+                crash_pattern::<D, N, T>(pattern, &dir, args, seed.wrapping_add(crash), layout)
+                    .with_context(|| format!("pattern {} failed to run", pattern.name))?;
+            report.lost_writes += outcome.lost_writes;
+            match outcome.broken {
+                None => {
+                    result.held += 1;
+                    let _ = std::fs::remove_dir_all(&dir);
+                }
+                Some(why) => {
+                    result.first_break.get_or_insert(why);
+                }
+            }
+        }
+        report.patterns.push(result);
+    }
+    Ok(report)
+}
+
+/// `Outcome` is what one crash of a pattern left behind.
+struct Outcome {
+    /// Why the guarantee did not hold, if it did not.
+    broken: Option<String>,
+    lost_writes: u64,
+}
+
+/// `File` is the expected state of a file the pattern created.
+struct File {
+    index: usize,
+    name: Option<&'static str>,
+    open: bool,
+    data: Vec<u8>,
+}
+
+/// `named` returns the contents of every file that has a name.
+fn named(files: &BTreeMap<&'static str, File>) -> BTreeMap<&'static str, Vec<u8>> {
+    files
+        .values()
+        .filter_map(|file| file.name.map(|name| (name, file.data.clone())))
+        .collect()
+}
+
+fn crash_pattern<const D: usize, const N: usize, T: Stripe<D, N>>(
+    pattern: &Pattern,
+    dir: &Path,
+    args: &TortureArgs,
+    seed: u64,
+    layout: fn() -> T,
+) -> Result<Outcome> {
+    let open = || {
+        SimulatorBuilder::<D, N>::new(dir)
+            .disk_size(args.disk_size.max(1))
+            .build(layout())
+    };
+    let mut volume = open()?;
+    let (header, entries, quotas) = load_filesystem(&mut volume, false)?;
+    volume.simulate_crashes(CrashPlan {
+        seed,
+        ..CrashPlan::default()
+    });
+    let fs = RaidFs {
+        state: Arc::new(Mutex::new(FsState {
+            volume,
+            header,
+            entries,
+            write_buffer: WriteBuffer::new(args.write_buffer, DEFAULT_MAX_AGE),
+            scrub: Scrub::default(),
+            grow: GrowState::default(),
+            hot_add: None,
+            quotas,
+            open_files: OpenFiles::default(),
+        })),
+        metrics: None,
+        file_io: Mutex::default(),
+        read_only: false,
+        throttle: Mutex::default(),
+        raw_volume: false,
+    };
+
+    let mut files = BTreeMap::new();
+    let mut synced = BTreeMap::new();
+    for (step, op) in pattern.ops.iter().enumerate() {
+        apply(&fs, &mut files, *op, seed, step)
+            .map_err(std::io::Error::from_raw_os_error)
+            .with_context(|| format!("{op:?}"))?;
+        if matches!(op, Op::Fsync) {
+            synced = named(&files);
+        }
+    }
+    let last = named(&files);
+
+    let lost_writes = {
+        let mut state = fs
+            .state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("filesystem state lock poisoned"))?;
+        state.volume.crash();
+        state.volume.write_cache_stats().lost_writes
+    };
+    drop(fs);
+
+    let mut volume = open()?;
+    volume.resync();
+    let entries = match load_filesystem(&mut volume, false) {
+        Ok((_, entries, _)) => entries,
+        Err(err) => {
+            return Ok(Outcome {
+                broken: Some(format!("filesystem did not mount: {err:#}")),
+                lost_writes,
+            });
+        }
+    };
+    let mut found = BTreeMap::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.used && !entry.is_orphan())
+    {
+        let mut data = vec![0u8; usize::try_from(entry.size)?];
+        volume.read_bytes(entry.offset, &mut data);
+        found.insert(entry.name.clone(), data);
+    }
+
+    let broken = pattern
+        .expect
+        .iter()
+        .find_map(|&(name, expect)| {
+            let want = match expect {
+                Expect::Absent => None,
+                Expect::Synced => synced.get(name),
+                Expect::Final => last.get(name),
+            };
+            mismatch(name, want, found.get(name))
+        })
+        .or_else(|| {
+            found
+                .keys()
+                .find(|name| {
+                    !synced.contains_key(name.as_str()) && !last.contains_key(name.as_str())
+                })
+                .map(|name| format!("{name}: appeared from nowhere"))
+        });
+    Ok(Outcome {
+        broken,
+        lost_writes,
+    })
+}
+
+/// `apply` runs one operation of a pattern and updates the expected files.
+fn apply<const D: usize, const N: usize, T: Stripe<D, N>>(
+    fs: &RaidFs<D, N, T>,
+    files: &mut BTreeMap<&'static str, File>,
+    op: Op,
+    seed: u64,
+    step: usize,
+) -> Result<(), i32> {
+    match op {
+        Op::Create(name) => {
+            let index = fs.create_regular_entry(ROOT_ID, OsStr::new(name), 0)?;
+            fs.state
+                .lock()
+                .map_err(|_| libc::EIO)?
+                .open_files
+                .open(index);
+            files.insert(
+                name,
+                File {
+                    index,
+                    name: Some(name),
+                    open: true,
+                    data: Vec::new(),
+                },
+            );
+        }
+        Op::Tmpfile(label) => {
+            let index = fs.create_tmpfile(ROOT_ID, 0)?;
+            files.insert(
+                label,
+                File {
+                    index,
+                    name: None,
+                    open: true,
+                    data: Vec::new(),
+                },
+            );
+        }
+        Op::Write { file, offset, len } => {
+            let file = files.get_mut(file).ok_or(libc::EBADF)?;
+            let data = fill(seed, step, len);
+            let mut state = fs.state.lock().map_err(|_| libc::EIO)?;
+            RaidFs::<D, N, T>::write_file(&mut state, file.index, offset, &data)?;
+            let start = usize::try_from(offset).map_err(|_| libc::EFBIG)?;
+            let end = start + data.len();
+            if file.data.len() < end {
+                file.data.resize(end, 0);
+            }
+            file.data[start..end].copy_from_slice(&data);
+        }
+        Op::Unlink(file) => {
+            let name = files.get(file).and_then(|f| f.name).ok_or(libc::ENOENT)?;
+            fs.unlink_entry(ROOT_ID, OsStr::new(name))?;
+            let open = files.get_mut(file).is_some_and(|f| {
+                f.name = None;
+                f.open
+            });
+            if !open {
+                files.remove(file);
+            }
+        }
+        Op::Link { file, name } => {
+            let file = files.get_mut(file).ok_or(libc::EBADF)?;
+            let ino = RaidFs::<D, N, T>::inode_for(file.index);
+            fs.link_entry(ino, ROOT_ID, OsStr::new(name))?;
+            file.name = Some(name);
+        }
+        Op::Close(file) => {
+            let handle = files.get_mut(file).ok_or(libc::EBADF)?;
+            let ino = RaidFs::<D, N, T>::inode_for(handle.index);
+            fs.flush_buffered(ino)?;
+            fs.release_handle(ino);
+            handle.open = false;
+            if handle.name.is_none() {
+                files.remove(file);
+            }
+        }
+        Op::Fsync => {
+            if !fs.sync_volume() {
+                return Err(libc::EIO);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `fill` returns the bytes a write of a pattern stores; they differ per crash
+/// seed, per step and along the write so torn and misplaced data shows up.
+fn fill(seed: u64, step: usize, len: u64) -> Vec<u8> {
+    (0..len)
+        .map(|i| {
+            let value = seed.wrapping_add(step as u64 * 0x9e37).wrapping_add(i) % 251;
+            u8::try_from(value + 1).unwrap_or(u8::MAX)
+        })
+        .collect()
+}
+
+/// `mismatch` describes how a recovered file differs from the expected one.
+fn mismatch(name: &str, want: Option<&Vec<u8>>, got: Option<&Vec<u8>>) -> Option<String> {
+    match (want, got) {
+        (None, None) => None,
+        (None, Some(got)) => Some(format!("{name}: should be gone, found {} bytes", got.len())),
+        (Some(want), None) => Some(format!("{name}: {} bytes lost with the file", want.len())),
+        (Some(want), Some(got)) if want.len() != got.len() => Some(format!(
+            "{name}: expected {} bytes, found {}",
+            want.len(),
+            got.len()
+        )),
+        (Some(want), Some(got)) => want
+            .iter()
+            .zip(got)
+            .position(|(a, b)| a != b)
+            .map(|at| format!("{name}: data differs from byte {at}")),
+    }
+}
+
+fn render(report: &TortureReport) -> String {
+    let mut txt = String::new();
+    for pattern in &report.patterns {
+        let verdict = if pattern.provided() {
+            "PROVIDED"
+        } else {
+            "MISSING"
+        };
+        let scope = if pattern.posix { "posix" } else { "extra" };
+        let _ = writeln!(
+            txt,
+            "  {verdict:<8}  {:<20} {scope:<5}  held {}/{}  {}",
+            pattern.name, pattern.held, pattern.runs, pattern.guarantee
+        );
+        if let Some(why) = &pattern.first_break {
+            let _ = writeln!(txt, "            first break: {why}");
+        }
+    }
+    let _ = writeln!(
+        txt,
+        "crashes dropped {} cached disk writes",
+        report.lost_writes
+    );
+    txt
+}
+
+fn scratch_dir() -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("raid-cli-torture-{}-{nanos}", std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(raid: RaidMode, disks: usize, write_buffer: Option<usize>) -> TortureArgs {
+        TortureArgs {
+            raid,
+            disks,
+            disk_size: 65536,
+            write_buffer,
+            crashes: 3,
+            disk_dir: None,
+        }
+    }
+
+    #[test]
+    fn fsync_guarantees_hold_on_every_layout() {
+        for (raid, disks) in [
+            (RaidMode::Raid0, 2),
+            (RaidMode::Raid1, 2),
+            (RaidMode::Raid3, 3),
+        ] {
+            let dir = temp_dir("raid-cli-torture");
+            let report = execute(&args(raid, disks, None), &dir, 11).expect("torture");
+            assert_eq!(report.patterns.len(), PATTERNS.len());
+            assert_eq!(report.broken().count(), 0, "{raid:?}: {report:?}");
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn unsynced_writes_are_lost_with_a_volatile_cache() {
+        let dir = temp_dir("raid-cli-torture");
+        let mut args = args(RaidMode::Raid1, 2, Some(1 << 16));
+        args.crashes = 6;
+        let report = execute(&args, &dir, 5).expect("torture");
+        let unsynced = report
+            .patterns
+            .iter()
+            .find(|pattern| pattern.name == "write-no-fsync")
+            .expect("write-no-fsync pattern");
+        assert!(!unsynced.provided());
+        assert!(unsynced.first_break.is_some());
+        assert!(report.lost_writes > 0);
+        assert!(render(&report).contains("MISSING"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn existing_array_is_not_overwritten() {
+        let dir = temp_dir("raid-cli-torture");
+        std::fs::write(dir.join("disk-0.img"), b"data").expect("write image");
+        assert!(execute(&args(RaidMode::Raid0, 2, None), &dir, 0).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn mismatch_describes_the_difference() {
+        let data = vec![1, 2, 3];
+        assert_eq!(mismatch("a", None, None), None);
+        assert_eq!(mismatch("a", Some(&data), Some(&data)), None);
+        assert!(mismatch("a", Some(&data), None).is_some_and(|why| why.contains("lost")));
+        assert!(
+            mismatch("a", Some(&data), Some(&vec![1, 9, 3]))
+                .is_some_and(|why| why.ends_with("byte 1"))
+        );
+    }
+}
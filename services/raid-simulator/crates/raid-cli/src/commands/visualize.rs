@@ -0,0 +1,358 @@
+//! Heatmap export showing which disk holds which chunk of a logical range.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use raid_rs::layout::stripe::traits::stripe::ChunkRole;
+
+use crate::cli::{VisualizeArgs, VisualizeFormat};
+use crate::commands::{check_all_members_present, check_existing_images};
+use crate::volume::{open_volume, validate_geometry};
+
+/// `MAX_STRIPES` bounds the rows of one heatmap so a typo cannot produce a huge document.
+pub const MAX_STRIPES: u64 = 4096;
+
+const CELL_W: u64 = 72;
+const CELL_H: u64 = 20;
+const LEFT: u64 = 96;
+const TOP: u64 = 32;
+const LEGEND_H: u64 = 56;
+
+/// `CellState` is the health of one chunk as seen on disk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CellState {
+    Healthy,
+    /// The member is stale and waits for a rebuild.
+    Stale,
+    /// The stored chunk does not match the re-encoded stripe.
+    Corrupt,
+    Missing,
+}
+
+impl CellState {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Stale => "stale",
+            Self::Corrupt => "corrupt",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+struct Cell {
+    role: ChunkRole,
+    state: CellState,
+}
+
+struct Row {
+    stripe_index: u64,
+    disk_offset: u64,
+    cells: Vec<Cell>,
+}
+
+/// `run` writes the heatmap of a logical range to a file or stdout.
+///
+/// # Arguments
+/// * `args` - Visualize arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, the range is out of
+/// bounds, or the output cannot be written.
+pub fn run(args: &VisualizeArgs) -> Result<()> {
+    let doc = render(args)?;
+    if args.output == Path::new("-") {
+        print!("{doc}");
+    } else {
+        std::fs::write(&args.output, doc)
+            .with_context(|| format!("write {}", args.output.display()))?;
+    }
+    Ok(())
+}
+
+/// `render` builds the heatmap document without writing it.
+///
+/// # Arguments
+/// * `args` - Visualize arguments.
+///
+/// # Errors
+/// Returns an error if the geometry is invalid, images are missing, or the range is out of
+/// bounds.
+pub fn render(args: &VisualizeArgs) -> Result<String> {
+    validate_geometry(args.raid, args.disks)?;
+    let disk_size = args.disk_size.max(1);
+    check_existing_images(&args.disk_dir, args.disks, disk_size)?;
+    check_all_members_present(&args.disk_dir, args.disks)?;
+
+    let mut volume = open_volume(args.raid, &args.disk_dir, args.disks, disk_size)?;
+    if volume.is_thin() {
+        anyhow::bail!("visualize does not follow thin allocation maps");
+    }
+    let capacity = volume.logical_capacity_bytes();
+    if args.range.start >= capacity {
+        anyhow::bail!(
+            "range start {} is outside the logical capacity of {capacity} bytes",
+            args.range.start
+        );
+    }
+    let end = args.range.end.min(capacity);
+    let stripe_bytes = (volume.geometry().bytes_per_stripe as u64).max(1);
+    let first = args.range.start / stripe_bytes;
+    let last = (end - 1) / stripe_bytes;
+    if last - first >= MAX_STRIPES {
+        anyhow::bail!(
+            "range covers {} stripes; narrow it to at most {MAX_STRIPES}",
+            last - first + 1
+        );
+    }
+
+    let statuses = volume.disk_statuses();
+    let mut rows = Vec::new();
+    for stripe_index in first..=last {
+        let roles = volume.map_logical(stripe_index * stripe_bytes);
+        let inspection = volume.inspect_stripe(stripe_index);
+        let cells = roles
+            .iter()
+            .map(|chunk| {
+                let disk = chunk.disk;
+                let state = if statuses[disk].missing || inspection.stored[disk].is_none() {
+                    CellState::Missing
+                } else if inspection.mismatched.contains(&disk) {
+                    CellState::Corrupt
+                } else if statuses[disk].needs_rebuild {
+                    CellState::Stale
+                } else {
+                    CellState::Healthy
+                };
+                Cell {
+                    role: chunk.role,
+                    state,
+                }
+            })
+            .collect();
+        rows.push(Row {
+            stripe_index,
+            disk_offset: inspection.disk_offset,
+            cells,
+        });
+    }
+
+    let title = format!("{:?} bytes {}..{end}", args.raid, args.range.start);
+    let svg = render_svg(&title, args.disks, &rows);
+    Ok(match args.format {
+        VisualizeFormat::Svg => svg,
+        VisualizeFormat::Html => format!(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n{svg}</body>\n</html>\n"
+        ),
+    })
+}
+
+fn render_svg(title: &str, disks: usize, rows: &[Row]) -> String {
+    let width = LEFT + CELL_W * disks as u64 + 8;
+    let height = TOP + CELL_H * rows.len() as u64 + LEGEND_H;
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" font-family=\"monospace\" font-size=\"11\">"
+    );
+    let _ = writeln!(out, "<title>{title}</title>");
+    let _ = writeln!(
+        out,
+        "<defs><pattern id=\"stale\" width=\"6\" height=\"6\" patternUnits=\"userSpaceOnUse\" patternTransform=\"rotate(45)\"><rect width=\"3\" height=\"6\" fill=\"#ffffff\" fill-opacity=\"0.5\"/></pattern></defs>"
+    );
+    for disk in 0..disks as u64 {
+        let x = LEFT + disk * CELL_W + CELL_W / 2;
+        let _ = writeln!(
+            out,
+            "<text x=\"{x}\" y=\"{}\" text-anchor=\"middle\">disk {disk}</text>",
+            TOP - 10
+        );
+    }
+    for (row_index, row) in rows.iter().enumerate() {
+        let y = TOP + row_index as u64 * CELL_H;
+        let _ = writeln!(
+            out,
+            "<text x=\"4\" y=\"{}\">stripe {}</text>",
+            y + CELL_H - 6,
+            row.stripe_index
+        );
+        for (disk, cell) in row.cells.iter().enumerate() {
+            let x = LEFT + disk as u64 * CELL_W;
+            let tooltip = format!(
+                "stripe {}, disk {disk} @ {}: {} ({})",
+                row.stripe_index,
+                row.disk_offset,
+                cell.role,
+                cell.state.label()
+            );
+            render_cell(&mut out, x, y, cell, &tooltip);
+        }
+    }
+    render_legend(&mut out, TOP + CELL_H * rows.len() as u64 + 16);
+    out.push_str("</svg>\n");
+    out
+}
+
+fn render_cell(out: &mut String, x: u64, y: u64, cell: &Cell, tooltip: &str) {
+    let fill = if cell.state == CellState::Missing {
+        "#bab0ac"
+    } else {
+        role_color(cell.role)
+    };
+    let (stroke, stroke_width) = if cell.state == CellState::Corrupt {
+        ("#e15759", 3)
+    } else {
+        ("#ffffff", 1)
+    };
+    let _ = writeln!(
+        out,
+        "<g class=\"chunk {}\"><title>{tooltip}</title>",
+        cell.state.label()
+    );
+    let _ = writeln!(
+        out,
+        "<rect x=\"{x}\" y=\"{y}\" width=\"{CELL_W}\" height=\"{CELL_H}\" fill=\"{fill}\" stroke=\"{stroke}\" stroke-width=\"{stroke_width}\"/>"
+    );
+    if cell.state == CellState::Stale {
+        let _ = writeln!(
+            out,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"{CELL_W}\" height=\"{CELL_H}\" fill=\"url(#stale)\"/>"
+        );
+    }
+    let _ = writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#ffffff\">{}</text></g>",
+        x + CELL_W / 2,
+        y + CELL_H - 6,
+        short_role(cell.role)
+    );
+}
+
+fn render_legend(out: &mut String, y: u64) {
+    let entries = [
+        ("#4e79a7", "data", ""),
+        ("#59a14f", "mirror", ""),
+        ("#f28e2b", "parity", ""),
+        ("#bab0ac", "missing", ""),
+        ("#4e79a7", "stale", "url(#stale)"),
+        ("#ffffff", "corrupt", ""),
+    ];
+    for (i, (fill, label, overlay)) in entries.iter().enumerate() {
+        let x = 4 + (i as u64 % 3) * 96;
+        let y = y + (i as u64 / 3) * 18;
+        let stroke = if *label == "corrupt" {
+            "#e15759\" stroke-width=\"3"
+        } else {
+            "#ffffff"
+        };
+        let _ = writeln!(
+            out,
+            "<rect x=\"{x}\" y=\"{y}\" width=\"14\" height=\"12\" fill=\"{fill}\" stroke=\"{stroke}\"/>"
+        );
+        if !overlay.is_empty() {
+            let _ = writeln!(
+                out,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"14\" height=\"12\" fill=\"{overlay}\"/>"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{label}</text>",
+            x + 20,
+            y + 10
+        );
+    }
+}
+
+const fn role_color(role: ChunkRole) -> &'static str {
+    match role {
+        ChunkRole::Data(_) => "#4e79a7",
+        ChunkRole::Mirror(_) => "#59a14f",
+        ChunkRole::Parity => "#f28e2b",
+    }
+}
+
+fn short_role(role: ChunkRole) -> String {
+    match role {
+        ChunkRole::Data(i) => format!("D{i}"),
+        ChunkRole::Mirror(i) => format!("M{i}"),
+        ChunkRole::Parity => "P".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{ByteRange, RaidMode};
+    use crate::fs::test_utils::temp_dir;
+
+    fn args(dir: &std::path::Path, range: &str, format: VisualizeFormat) -> VisualizeArgs {
+        VisualizeArgs {
+            disk_dir: dir.to_path_buf(),
+            raid: RaidMode::Raid3,
+            disks: 3,
+            disk_size: 64,
+            range: range.parse().expect("range"),
+            format,
+            output: "-".into(),
+        }
+    }
+
+    #[test]
+    fn range_accepts_bounds_or_length() {
+        assert_eq!(
+            "8..24".parse::<ByteRange>(),
+            Ok(ByteRange { start: 8, end: 24 })
+        );
+        assert_eq!(
+            "16".parse::<ByteRange>(),
+            Ok(ByteRange { start: 0, end: 16 })
+        );
+        assert!("24..8".parse::<ByteRange>().is_err());
+        assert!("x..8".parse::<ByteRange>().is_err());
+    }
+
+    #[test]
+    fn heatmap_draws_one_row_per_stripe() {
+        let dir = temp_dir("raid-cli-visualize");
+        drop(open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume"));
+
+        let svg = render(&args(&dir, "4..20", VisualizeFormat::Svg)).expect("visualize");
+
+        assert!(svg.starts_with("<svg"));
+        for stripe in 0..3 {
+            assert!(svg.contains(&format!(">stripe {stripe}</text>")));
+        }
+        assert!(!svg.contains(">stripe 3</text>"));
+        assert!(svg.contains("stripe 1, disk 2 @ 4: parity (healthy)"));
+        assert_eq!(svg.matches("class=\"chunk healthy\"").count(), 9);
+    }
+
+    #[test]
+    fn heatmap_marks_corrupt_chunks() {
+        let dir = temp_dir("raid-cli-visualize-bad");
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume");
+        volume.write_bytes(0, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        drop(volume);
+        let parity = crate::commands::disk_image_path(&dir, 2);
+        let mut image = std::fs::read(&parity).expect("read parity");
+        image[0] ^= 0xFF;
+        std::fs::write(&parity, image).expect("write parity");
+
+        let html = render(&args(&dir, "8", VisualizeFormat::Html)).expect("visualize");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<svg"));
+        assert!(html.contains("stripe 0, disk 2 @ 0: parity (corrupt)"));
+        assert_eq!(html.matches("class=\"chunk corrupt\"").count(), 1);
+    }
+
+    #[test]
+    fn heatmap_rejects_range_past_capacity() {
+        let dir = temp_dir("raid-cli-visualize-range");
+        drop(open_volume(RaidMode::Raid3, &dir, 3, 64).expect("open volume"));
+
+        assert!(render(&args(&dir, "128..256", VisualizeFormat::Svg)).is_err());
+    }
+}
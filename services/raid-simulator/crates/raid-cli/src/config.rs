@@ -0,0 +1,365 @@
+//! Configuration files setting the flags of the RAID simulator commands.
+//!
+//! `--config` names a `.toml`, `.yaml` or `.yml` file whose keys are the flag names
+//! of the `fuse` and `metrics` commands in snake case:
+//!
+//! ```toml
+//! disk_dir = "/var/lib/raid"
+//! raid = "raid3"
+//! disks = 4
+//! read_only = true
+//! interval_ms = 500
+//! log_level = "info,raid_cli=debug"
+//! ```
+//!
+//! Flags given on the command line override the file, and the file overrides
+//! environment variables. Keys of flags the running command does not take are
+//! ignored, so one file can serve every command; unknown keys are rejected.
+//! `raid-cli config print-default` prints a file listing every key.
+
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, CommandFactory, FromArgMatches};
+use toml::{Table, Value};
+
+use crate::cli::{Cli, Command, MetricsAction, MetricsArgs};
+
+/// `CONFIG` is the flag naming the configuration file.
+const CONFIG: &str = "config";
+/// `LOG_LEVEL` is the key holding `RUST_LOG`-style filter directives.
+pub const LOG_LEVEL: &str = "log_level";
+/// `TEMPLATE` is the command whose flags the configuration file may set.
+const TEMPLATE: &str = "fuse";
+
+/// `parse` parses the process arguments and applies the `--config` file.
+///
+/// Usage errors, `--help` and `--version` exit the process as `Cli::parse` does.
+///
+/// # Errors
+/// Returns an error if the configuration file cannot be loaded or has invalid values.
+pub fn parse() -> Result<Cli> {
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    append_file_args(&mut args)?;
+    let matches = Cli::command().get_matches_from(&args);
+    Ok(Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit()))
+}
+
+/// `try_parse_from` parses `args` and applies the `--config` file.
+///
+/// # Arguments
+/// * `args` - Command line, starting with the binary name.
+///
+/// # Errors
+/// Returns an error if the arguments are invalid, or the configuration file cannot
+/// be loaded or has invalid values.
+pub fn try_parse_from<I, T>(args: I) -> Result<Cli>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+    append_file_args(&mut args)?;
+    let matches = Cli::command().try_get_matches_from(&args)?;
+    Ok(Cli::from_arg_matches(&matches)?)
+}
+
+/// `append_file_args` appends the settings of the `--config` file to `args` as
+/// flags, skipping flags already given on the command line.
+///
+/// The arguments are parsed leniently first, so settings the file provides, such
+/// as required flags, may be missing from the command line; errors are left to
+/// the final parse.
+fn append_file_args(args: &mut Vec<OsString>) -> Result<()> {
+    let Ok(matches) = Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(&*args)
+    else {
+        return Ok(());
+    };
+    let Some((name, sub)) = matches.subcommand() else {
+        return Ok(());
+    };
+    let Some(path) = sub.try_get_one::<PathBuf>(CONFIG).ok().flatten() else {
+        return Ok(());
+    };
+    let table = load(path)?;
+    args.extend(file_args(name, sub, &table)?);
+    Ok(())
+}
+
+/// `reparse_metrics_args` parses the process arguments again with the current
+/// contents of the `--config` file.
+///
+/// # Errors
+/// Returns an error if the configuration file cannot be loaded, sets invalid
+/// values, or the command takes no metrics arguments.
+pub fn reparse_metrics_args() -> Result<MetricsArgs> {
+    metrics_args(try_parse_from(std::env::args_os())?.command)
+        .context("command takes no metrics arguments")
+}
+
+/// `metrics_args` returns the metrics arguments of a command, if it takes any.
+pub fn metrics_args(command: Command) -> Option<MetricsArgs> {
+    match command {
+        Command::Fuse(args) => Some(args.metrics),
+        Command::Metrics(args) => Some(match args.action {
+            Some(MetricsAction::Replay(replay)) => replay.metrics,
+            None => args.metrics,
+        }),
+        Command::Grow(args) => Some(args.metrics),
+        Command::Shrink(args) => Some(args.metrics),
+        Command::Check(args) => Some(args.metrics),
+        Command::Replay(args) => Some(args.metrics),
+        _ => None,
+    }
+}
+
+/// `load` reads a configuration file and checks that every key is known.
+///
+/// # Arguments
+/// * `path` - Configuration file; the extension selects the format.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or parsed, or has an unknown key.
+pub fn load(path: &Path) -> Result<Table> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read config {}", path.display()))?;
+    let table = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str::<Table>(&raw).map_err(anyhow::Error::from),
+        Some("yaml" | "yml") => serde_yaml::from_str::<Table>(&raw).map_err(anyhow::Error::from),
+        _ => Err(anyhow::anyhow!(
+            "config must be a .toml, .yaml or .yml file"
+        )),
+    }
+    .with_context(|| format!("invalid config {}", path.display()))?;
+
+    let template = template();
+    if let Some(key) = table
+        .keys()
+        .find(|key| *key != LOG_LEVEL && settable(&template, key).is_none())
+    {
+        anyhow::bail!("invalid config {}: unknown key {key:?}", path.display());
+    }
+    Ok(table)
+}
+
+/// `log_level` returns the log filter directives set by a configuration file.
+///
+/// # Arguments
+/// * `path` - Configuration file.
+///
+/// # Errors
+/// Returns an error if the file cannot be loaded or `log_level` is not a string.
+pub fn log_level(path: &Path) -> Result<Option<String>> {
+    match load(path)?.remove(LOG_LEVEL) {
+        None => Ok(None),
+        Some(Value::String(level)) => Ok(Some(level)),
+        Some(_) => anyhow::bail!(
+            "invalid config {}: log_level must be a string",
+            path.display()
+        ),
+    }
+}
+
+#[must_use]
+/// `default_config` renders a configuration file listing every key, commented
+/// out and set to its default.
+pub fn default_config() -> String {
+    let mut out = String::from(
+        "# raid-cli configuration, passed with --config.\n\
+         # Command-line flags override these settings, which override environment variables.\n",
+    );
+    for arg in template().get_arguments().filter(|arg| is_settable(arg)) {
+        out.push('\n');
+        if let Some(help) = arg.get_help() {
+            let _ = writeln!(out, "# {help}");
+        }
+        let values: Vec<_> = arg
+            .get_possible_values()
+            .iter()
+            .map(|value| value.get_name().to_string())
+            .collect();
+        if !values.is_empty() && !is_flag(arg) {
+            let _ = writeln!(out, "# One of: {}", values.join(", "));
+        }
+        if let Some(env) = arg.get_env() {
+            let _ = writeln!(out, "# Environment: {}", env.to_string_lossy());
+        }
+        match arg.get_default_values().first() {
+            Some(value) => {
+                let value = literal(&value.to_string_lossy());
+                let _ = writeln!(out, "# {} = {value}", arg.get_id());
+            }
+            None => {
+                let _ = writeln!(out, "# {} =", arg.get_id());
+            }
+        }
+    }
+    out.push_str(
+        "\n# Log filter directives, as in RUST_LOG; re-applied on SIGHUP.\n# log_level = \"info\"\n",
+    );
+    out
+}
+
+/// `template` returns the command whose flags the configuration file may set.
+fn template() -> clap::Command {
+    subcommand(TEMPLATE)
+}
+
+/// `subcommand` returns the subcommand `name` with the global flags propagated.
+fn subcommand(name: &str) -> clap::Command {
+    let mut root = Cli::command();
+    root.build();
+    root.find_subcommand(name)
+        .cloned()
+        .expect("known subcommand")
+}
+
+/// `settable` returns the flag `key` names, unless it is the `--config` flag, a
+/// global flag or `--help`.
+fn settable<'a>(command: &'a clap::Command, key: &str) -> Option<&'a Arg> {
+    command
+        .get_arguments()
+        .find(|arg| arg.get_id() == key)
+        .filter(|arg| is_settable(arg))
+}
+
+fn is_settable(arg: &Arg) -> bool {
+    arg.get_id() != CONFIG
+        && !arg.is_global_set()
+        && arg.get_long().is_some_and(|long| long != "help")
+}
+
+fn is_flag(arg: &Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::SetTrue)
+}
+
+/// `file_args` turns the keys of a configuration file into flags of the command
+/// `name`, skipping flags already given on the command line.
+fn file_args(name: &str, matches: &ArgMatches, table: &Table) -> Result<Vec<OsString>> {
+    let command = subcommand(name);
+    let mut args = Vec::new();
+    for (key, value) in table {
+        let Some(arg) = settable(&command, key) else {
+            continue;
+        };
+        if matches.value_source(key) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let flag = format!("--{}", arg.get_long().unwrap_or(key));
+        match value {
+            Value::Boolean(set) if is_flag(arg) => {
+                if *set {
+                    args.push(flag.into());
+                }
+            }
+            Value::String(value) => args.extend([flag, value.clone()].map(OsString::from)),
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => {
+                args.extend([flag, value.to_string()].map(OsString::from));
+            }
+            _ => anyhow::bail!("config key {key:?} must be a string, number or boolean"),
+        }
+    }
+    Ok(args)
+}
+
+/// `literal` renders a default value as a TOML literal.
+fn literal(value: &str) -> String {
+    if value.parse::<f64>().is_ok() || value == "true" || value == "false" {
+        value.to_string()
+    } else {
+        Value::String(value.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{DegradedMode, RaidMode};
+    use crate::fs::test_utils::temp_dir;
+
+    fn fuse_args(config: &Path, extra: &[&str]) -> Result<crate::cli::FuseArgs> {
+        let mut args = vec!["raid-cli", "fuse", "--mount-point", "/mnt/raid"];
+        args.extend(extra);
+        let config = config.display().to_string();
+        args.extend(["--config", &config]);
+        match try_parse_from(args)?.command {
+            Command::Fuse(args) => Ok(*args),
+            _ => panic!("expected fuse command"),
+        }
+    }
+
+    #[test]
+    fn command_line_overrides_config_file() {
+        let dir = temp_dir("raid-cli-config");
+        let path = dir.join("raid.toml");
+        std::fs::write(
+            &path,
+            "disk_dir = \"/var/raid\"\nraid = \"raid3\"\ndisks = 4\nread_only = true\n\
+             interval_ms = 250\njitter_ratio = 0.5\nlog_level = \"debug\"\n",
+        )
+        .expect("write config");
+
+        let args = fuse_args(&path, &["--disks", "5"]).expect("parse");
+        assert_eq!(args.disk_dir, Some(PathBuf::from("/var/raid")));
+        assert_eq!(args.raid, RaidMode::Raid3);
+        assert_eq!(args.disks, 5);
+        assert!(args.read_only);
+        assert_eq!(args.metrics.interval_ms, 250);
+        assert!((args.metrics.jitter_ratio - 0.5).abs() < f64::EPSILON);
+        assert_eq!(
+            log_level(&path).expect("log level").as_deref(),
+            Some("debug")
+        );
+
+        let yaml = dir.join("raid.yaml");
+        std::fs::write(&yaml, "disk_dir: /var/raid\ndegraded: read-only\n").expect("write config");
+        let args = fuse_args(&yaml, &[]).expect("parse yaml");
+        assert_eq!(args.degraded, DegradedMode::ReadOnly);
+    }
+
+    #[test]
+    fn invalid_config_files_are_rejected() {
+        let dir = temp_dir("raid-cli-config-invalid");
+        let path = dir.join("raid.toml");
+        for raw in [
+            "disk_dir = \"/var/raid\"\nlatency = 1\n",
+            "disk_dir = \"/var/raid\"\nconfig = \"other.toml\"\n",
+            "disk_dir = \"/var/raid\"\ndisks = \"many\"\n",
+            "disk_dir = \"/var/raid\"\nraid = \"raid5\"\n",
+            "disk_dir = [\"/var/raid\"]\n",
+        ] {
+            std::fs::write(&path, raw).expect("write config");
+            assert!(fuse_args(&path, &[]).is_err(), "{raw}");
+        }
+        let json = dir.join("raid.json");
+        std::fs::write(&json, "{}").expect("write config");
+        assert!(fuse_args(&json, &["--disk-dir", "/var/raid"]).is_err());
+    }
+
+    #[test]
+    fn default_config_lists_every_setting() {
+        let config = default_config();
+        assert!(config.contains("# interval_ms = 1000\n"));
+        assert!(config.contains("# raid = \"raid0\"\n"));
+        assert!(config.contains("# read_only = false\n"));
+        assert!(config.contains("# Environment: METRICS_SOCKET_PATH\n"));
+        assert!(!config.contains("# config ="));
+
+        let uncommented: Vec<_> = config
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.contains(" = "))
+            .collect();
+        let dir = temp_dir("raid-cli-config-default");
+        let path = dir.join("raid.toml");
+        std::fs::write(&path, uncommented.join("\n")).expect("write config");
+        let args = fuse_args(&path, &["--disk-dir", "/var/raid"]).expect("parse defaults");
+        assert_eq!(args.raid, RaidMode::Raid0);
+        assert_eq!(args.metrics.interval_ms, 1000);
+    }
+}
@@ -0,0 +1,376 @@
+//! File sink that records simulator operations for offline analysis.
+//!
+//! Every disk and RAID operation becomes one record appended to a local file,
+//! either as newline-delimited JSON or as CSV with a header row. Records carry a
+//! wall-clock timestamp so runs can be loaded straight into a data frame, and
+//! JSONL files can be read back with `load` to replay them.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use raid_rs::metrics::{DiskOp, IoClass, IoOpType, MetricsSink, RaidOp};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::MetricsFileFormat;
+
+const CSV_HEADER: &str = "timestamp,kind,id,op,bytes,latency_seconds,error,degraded\n";
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: f64,
+    kind: &'static str,
+    id: &'a str,
+    op: &'static str,
+    bytes: u64,
+    latency_seconds: f64,
+    error: bool,
+    degraded: bool,
+}
+
+#[derive(Deserialize)]
+struct OwnedRecord {
+    timestamp: f64,
+    kind: String,
+    id: String,
+    op: String,
+    bytes: u64,
+    latency_seconds: f64,
+    error: bool,
+    degraded: bool,
+}
+
+/// `RecordedOp` is an operation read back from a metrics file.
+#[derive(Clone, Debug)]
+pub enum RecordedOp {
+    Disk(DiskOp),
+    Raid(RaidOp),
+}
+
+/// `Recorded` is a timestamped operation read back from a metrics file.
+#[derive(Clone, Debug)]
+pub struct Recorded {
+    /// Wall-clock time the operation was recorded at, in seconds.
+    pub timestamp: f64,
+    /// Array the operation belongs to; `None` for disks of an unlabelled array.
+    pub raid_id: Option<String>,
+    pub op: RecordedOp,
+}
+
+/// `FileSink` appends simulator operations to a JSONL or CSV file.
+pub struct FileSink {
+    raid_id: String,
+    format: MetricsFileFormat,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl FileSink {
+    /// `create` opens `path` for appending and writes the CSV header to new files.
+    ///
+    /// # Arguments
+    /// * `path` - File receiving the records.
+    /// * `format` - Record format; inferred from the extension when `None`.
+    /// * `raid_id` - Identifier recorded for RAID operations, unless the recording
+    ///   thread is labelled with another array.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened or written.
+    pub fn create(path: &Path, format: Option<MetricsFileFormat>, raid_id: &str) -> Result<Self> {
+        let format = format.unwrap_or_else(|| infer_format(path));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open metrics file {}", path.display()))?;
+        let is_empty = file.metadata()?.len() == 0;
+        let mut writer = BufWriter::new(file);
+        if format == MetricsFileFormat::Csv && is_empty {
+            writer.write_all(CSV_HEADER.as_bytes())?;
+        }
+        Ok(Self {
+            raid_id: raid_id.to_string(),
+            format,
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// `flush` writes buffered records to the file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&self) -> Result<()> {
+        if let Ok(mut writer) = self.writer.lock() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, record: &Record<'_>) {
+        let line = match self.format {
+            MetricsFileFormat::Jsonl => match serde_json::to_string(record) {
+                Ok(json) => json + "\n",
+                Err(_) => return,
+            },
+            MetricsFileFormat::Csv => format!(
+                "{},{},{},{},{},{},{},{}\n",
+                record.timestamp,
+                record.kind,
+                csv_field(record.id),
+                record.op,
+                record.bytes,
+                record.latency_seconds,
+                record.error,
+                record.degraded
+            ),
+        };
+        if let Ok(mut writer) = self.writer.lock() {
+            let _ = writer.write_all(line.as_bytes());
+        }
+    }
+}
+
+impl MetricsSink for FileSink {
+    fn record_disk_op(&self, op: DiskOp) {
+        let id = match raid_rs::metrics::thread_scope() {
+            Some(raid_id) => format!("{raid_id}/{}", op.disk_id),
+            None => op.disk_id,
+        };
+        self.write(&Record {
+            timestamp: unix_seconds(),
+            kind: "disk",
+            id: &id,
+            op: op_name(op.op),
+            bytes: op.bytes,
+            latency_seconds: op.latency_seconds,
+            error: op.error,
+            degraded: false,
+        });
+    }
+
+    fn record_raid_op(&self, op: RaidOp) {
+        let scope = raid_rs::metrics::thread_scope();
+        self.write(&Record {
+            timestamp: unix_seconds(),
+            kind: "raid",
+            id: scope.as_deref().unwrap_or(&self.raid_id),
+            op: op_name(op.op),
+            bytes: op.bytes,
+            latency_seconds: op.latency_seconds,
+            error: op.error,
+            degraded: op.degraded,
+        });
+    }
+}
+
+/// `load` reads the operations of a JSONL metrics file.
+///
+/// Disk ids qualified with an array (`raid3/disk1`) are split into the array and
+/// the disk. RAID operations come back as foreground IO without cache counters,
+/// which the file does not record.
+///
+/// # Arguments
+/// * `path` - JSONL file written by a `FileSink`.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or a line is not a record.
+pub fn load(path: &Path) -> Result<Vec<Recorded>> {
+    let file = File::open(path).with_context(|| format!("open metrics file {}", path.display()))?;
+    let mut records = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("read metrics file {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: OwnedRecord = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid record", path.display(), index + 1))?;
+        records.push(
+            decode(record)
+                .with_context(|| format!("{}:{}: invalid record", path.display(), index + 1))?,
+        );
+    }
+    Ok(records)
+}
+
+fn decode(record: OwnedRecord) -> Result<Recorded> {
+    let op = match record.op.as_str() {
+        "read" => IoOpType::Read,
+        "write" => IoOpType::Write,
+        other => anyhow::bail!("unknown op {other:?}"),
+    };
+    let (raid_id, op) = match record.kind.as_str() {
+        "disk" => {
+            let (raid_id, disk_id) = match record.id.rsplit_once('/') {
+                Some((raid_id, disk_id)) => (Some(raid_id.to_string()), disk_id.to_string()),
+                None => (None, record.id),
+            };
+            let op = DiskOp {
+                disk_id,
+                op,
+                bytes: record.bytes,
+                latency_seconds: record.latency_seconds,
+                error: record.error,
+            };
+            (raid_id, RecordedOp::Disk(op))
+        }
+        "raid" => {
+            let op = RaidOp {
+                op,
+                bytes: record.bytes,
+                latency_seconds: record.latency_seconds,
+                error: record.error,
+                degraded: record.degraded,
+                read_ahead_hits: 0,
+                read_ahead_misses: 0,
+                parity_cache_hits: 0,
+                parity_cache_misses: 0,
+                class: IoClass::Foreground,
+            };
+            (Some(record.id), RecordedOp::Raid(op))
+        }
+        other => anyhow::bail!("unknown kind {other:?}"),
+    };
+    Ok(Recorded {
+        timestamp: record.timestamp,
+        raid_id,
+        op,
+    })
+}
+
+fn infer_format(path: &Path) -> MetricsFileFormat {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+    {
+        MetricsFileFormat::Csv
+    } else {
+        MetricsFileFormat::Jsonl
+    }
+}
+
+const fn op_name(op: IoOpType) -> &'static str {
+    match op {
+        IoOpType::Read => "read",
+        IoOpType::Write => "write",
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn unix_seconds() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+    use raid_rs::metrics::IoClass;
+
+    fn disk_op() -> DiskOp {
+        DiskOp {
+            disk_id: "disk1".to_string(),
+            op: IoOpType::Write,
+            bytes: 4096,
+            latency_seconds: 0.002,
+            error: false,
+        }
+    }
+
+    fn raid_op() -> RaidOp {
+        RaidOp {
+            op: IoOpType::Read,
+            bytes: 512,
+            latency_seconds: 0.01,
+            error: true,
+            degraded: true,
+            read_ahead_hits: 0,
+            read_ahead_misses: 0,
+            parity_cache_hits: 0,
+            parity_cache_misses: 0,
+            class: IoClass::Foreground,
+        }
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        let path = temp_dir("raid-cli-file-sink-jsonl").join("ops.jsonl");
+        let sink = FileSink::create(&path, None, "raid3").unwrap();
+        sink.record_disk_op(disk_op());
+        sink.record_raid_op(raid_op());
+        sink.flush().unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["kind"], "disk");
+        assert_eq!(records[0]["id"], "disk1");
+        assert_eq!(records[0]["op"], "write");
+        assert_eq!(records[0]["bytes"], 4096);
+        assert_eq!(records[1]["kind"], "raid");
+        assert_eq!(records[1]["id"], "raid3");
+        assert_eq!(records[1]["error"], true);
+        assert_eq!(records[1]["degraded"], true);
+    }
+
+    #[test]
+    fn load_reads_json_lines_back() {
+        let path = temp_dir("raid-cli-file-sink-load").join("ops.jsonl");
+        let sink = FileSink::create(&path, None, "raid3").unwrap();
+        sink.record_disk_op(disk_op());
+        sink.record_raid_op(raid_op());
+        sink.flush().unwrap();
+        std::fs::write(
+            path.with_extension("bad"),
+            "{\"timestamp\":0,\"kind\":\"fuse\",\"id\":\"x\",\"op\":\"read\",\"bytes\":0,\
+             \"latency_seconds\":0,\"error\":false,\"degraded\":false}\n",
+        )
+        .unwrap();
+
+        let records = load(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].raid_id, None);
+        let RecordedOp::Disk(op) = &records[0].op else {
+            panic!("expected disk op");
+        };
+        assert_eq!((op.disk_id.as_str(), op.bytes), ("disk1", 4096));
+        assert_eq!(records[1].raid_id.as_deref(), Some("raid3"));
+        let RecordedOp::Raid(op) = &records[1].op else {
+            panic!("expected raid op");
+        };
+        assert!(op.error && op.degraded);
+        assert!(records[1].timestamp >= records[0].timestamp);
+
+        let err = load(&path.with_extension("bad")).unwrap_err();
+        assert!(format!("{err:#}").contains("unknown kind"));
+    }
+
+    #[test]
+    fn appends_csv_with_a_single_header() {
+        let path = temp_dir("raid-cli-file-sink-csv").join("ops.csv");
+        for _ in 0..2 {
+            let sink = FileSink::create(&path, None, "raid1").unwrap();
+            sink.record_raid_op(raid_op());
+            sink.flush().unwrap();
+        }
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER.trim_end());
+        assert!(lines[1].ends_with(",raid,raid1,read,512,0.01,true,true"));
+    }
+}
@@ -0,0 +1,107 @@
+//! Placement of file data relative to the volume's stripes.
+//!
+//! A write that covers whole stripes is encoded straight from the payload, while
+//! any other write reads the stripes it touches first. Files start on a stripe
+//! boundary, so when the stripe size divides `FS_BLOCK_SIZE` every block-aligned
+//! block of a file covers whole stripes and is written without that read.
+
+use raid_rs::retention::volume::Geometry;
+
+use super::constants::FS_BLOCK_SIZE;
+
+/// `Alignment` rounds file placement to the stripe unit of a volume.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Alignment {
+    stripe_bytes: u64,
+}
+
+impl Alignment {
+    #[must_use]
+    /// `new` derives the alignment from the volume geometry.
+    ///
+    /// # Arguments
+    /// * `geom` - Geometry of the volume holding the filesystem.
+    pub fn new(geom: &Geometry) -> Self {
+        Self {
+            stripe_bytes: (geom.bytes_per_stripe as u64).max(1),
+        }
+    }
+
+    #[must_use]
+    /// `stripe_bytes` returns the logical bytes per stripe.
+    pub const fn stripe_bytes(self) -> u64 {
+        self.stripe_bytes
+    }
+
+    #[must_use]
+    /// `align_up` rounds an offset up to the next stripe boundary.
+    ///
+    /// # Arguments
+    /// * `offset` - Logical byte offset within the volume.
+    pub const fn align_up(self, offset: u64) -> u64 {
+        offset
+            .div_ceil(self.stripe_bytes)
+            .saturating_mul(self.stripe_bytes)
+    }
+
+    #[must_use]
+    /// `fits_blocks` reports whether every filesystem block covers whole stripes.
+    pub const fn fits_blocks(self) -> bool {
+        FS_BLOCK_SIZE.is_multiple_of(self.stripe_bytes)
+    }
+
+    #[must_use]
+    /// `warning` describes a geometry whose stripes straddle filesystem blocks.
+    ///
+    /// # Returns
+    /// `None` if block writes cover whole stripes.
+    pub fn warning(self) -> Option<String> {
+        (!self.fits_blocks()).then(|| {
+            format!(
+                "stripe size {} bytes does not divide the {FS_BLOCK_SIZE}-byte filesystem block; \
+                 block writes will read and rewrite partial stripes",
+                self.stripe_bytes
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(bytes_per_chunk: usize, data_disks: usize) -> Geometry {
+        Geometry {
+            bytes_per_chunk,
+            bytes_per_stripe: bytes_per_chunk * data_disks,
+            disks: data_disks + 1,
+            data_disks,
+            redundant_disks: 1,
+        }
+    }
+
+    #[test]
+    fn align_up_rounds_to_stripe_boundary() {
+        let align = Alignment::new(&geometry(4, 3));
+        assert_eq!(align.stripe_bytes(), 12);
+        assert_eq!(align.align_up(0), 0);
+        assert_eq!(align.align_up(1), 12);
+        assert_eq!(align.align_up(24), 24);
+        assert_eq!(align.align_up(25), 36);
+    }
+
+    #[test]
+    fn stripes_dividing_the_block_do_not_warn() {
+        let align = Alignment::new(&geometry(4, 2));
+        assert!(align.fits_blocks());
+        assert_eq!(align.warning(), None);
+    }
+
+    #[test]
+    fn stripes_straddling_blocks_warn() {
+        let align = Alignment::new(&geometry(4, 3));
+        assert!(!align.fits_blocks());
+        let warning = align.warning().expect("expected warning");
+        assert!(warning.contains("stripe size 12 bytes"));
+    }
+}
@@ -2,6 +2,8 @@
 
 use std::time::Duration;
 
+use raid_rs::retention::volume::MAX_SNAPSHOTS;
+
 /// `ROOT_ID` is the inode ID for the filesystem root.
 pub const ROOT_ID: u64 = 1;
 /// `FILE_ID_BASE` is the starting inode ID for regular files.
@@ -28,9 +30,22 @@ pub const ENTRY_SIZE: usize = 88;
 pub const TABLE_SIZE: usize = HEADER_SIZE + (ENTRY_SIZE * MAX_FILES);
 /// `OPEN_DIRECT_IO` toggles direct I/O for FUSE file handles.
 pub const OPEN_DIRECT_IO: u32 = 1;
+/// `FS_BLOCK_SIZE` is the size of the blocks the kernel writes file data in.
+pub const FS_BLOCK_SIZE: u64 = 4096;
 /// `STATFS_BLOCK_SIZE` is the block size reported by statfs.
 pub const STATFS_BLOCK_SIZE: u32 = 512;
 
+/// `QUOTA_MAGIC` identifies the quota table at the tail of the volume.
+pub const QUOTA_MAGIC: [u8; 8] = *b"RAIDQTA1";
+/// `QUOTA_SIZE` is the byte size of the region reserved for the quota table.
+pub const QUOTA_SIZE: u64 = FS_BLOCK_SIZE;
+/// `QUOTA_HEADER_SIZE` is the byte size of the quota table header.
+pub const QUOTA_HEADER_SIZE: usize = 16;
+/// `QUOTA_RECORD_SIZE` is the byte size of each quota record.
+pub const QUOTA_RECORD_SIZE: usize = 24;
+/// `MAX_QUOTAS` is the maximum number of users with a quota.
+pub const MAX_QUOTAS: usize = 64;
+
 /// `CTL_NAME` is the control file name exposed in the root directory.
 pub const CTL_NAME: &str = ".raidctl";
 /// `CTL_INO` is the inode number for the control file.
@@ -38,6 +53,28 @@ pub const CTL_INO: u64 = FILE_ID_BASE + (MAX_FILES as u64) + 1;
 /// `CTL_SIZE` is the fixed size of the control file in bytes.
 pub const CTL_SIZE: u64 = 4096;
 
+/// `RAW_NAME` is the passthrough file exposing the whole volume in the root directory.
+pub const RAW_NAME: &str = "volume.raw";
+/// `RAW_INO` is the inode number for the raw volume file.
+pub const RAW_INO: u64 = CTL_INO + 1;
+/// `DISKS_DIR_NAME` is the read-only directory exposing the raw bytes of each member disk.
+pub const DISKS_DIR_NAME: &str = "disks";
+/// `DISKS_INO` is the inode number for the member disk directory.
+pub const DISKS_INO: u64 = RAW_INO + 1;
+/// `DISK_FILE_BASE` is the starting inode ID for the `disk-N.raw` files.
+pub const DISK_FILE_BASE: u64 = DISKS_INO + 1;
+
+/// `SNAP_DIR_NAME` is the read-only directory exposing volume snapshots.
+pub const SNAP_DIR_NAME: &str = ".snapshots";
+/// `SNAP_ROOT_INO` is the inode number for the snapshot directory.
+///
+/// Snapshot inodes live far above the file table so they never collide with live files.
+pub const SNAP_ROOT_INO: u64 = 1 << 32;
+/// `SNAP_DIR_BASE` is the starting inode ID for per-snapshot directories.
+pub const SNAP_DIR_BASE: u64 = SNAP_ROOT_INO + 1;
+/// `SNAP_FILE_BASE` is the starting inode ID for files inside snapshots.
+pub const SNAP_FILE_BASE: u64 = SNAP_DIR_BASE + MAX_SNAPSHOTS as u64;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,8 +84,25 @@ mod tests {
         assert_eq!(TABLE_SIZE, HEADER_SIZE + (ENTRY_SIZE * MAX_FILES));
     }
 
+    #[test]
+    fn quota_table_fits_its_region() {
+        assert!((QUOTA_HEADER_SIZE + MAX_QUOTAS * QUOTA_RECORD_SIZE) as u64 <= QUOTA_SIZE);
+    }
+
     #[test]
     fn ctl_inode_is_after_file_range() {
         assert_eq!(CTL_INO, FILE_ID_BASE + (MAX_FILES as u64) + 1);
     }
+
+    #[test]
+    fn raw_inodes_follow_ctl_inode() {
+        assert_eq!(RAW_INO, CTL_INO + 1);
+        assert_eq!(DISK_FILE_BASE, CTL_INO + 3);
+    }
+
+    #[test]
+    fn snapshot_inodes_sit_above_file_range() {
+        assert!(SNAP_ROOT_INO > FILE_ID_BASE + MAX_FILES as u64);
+        assert_eq!(SNAP_FILE_BASE - SNAP_DIR_BASE, MAX_SNAPSHOTS as u64);
+    }
 }
@@ -1,11 +1,69 @@
 //! Metadata structures for the RAID filesystem table.
 
-use super::constants::{ENTRY_SIZE, NAME_LEN};
+use std::fmt;
+
+use super::constants::{
+    ENTRY_SIZE, HEADER_SIZE, MAGIC, MAX_FILES, MAX_QUOTAS, NAME_LEN, QUOTA_HEADER_SIZE,
+    QUOTA_MAGIC, QUOTA_RECORD_SIZE, QUOTA_SIZE, TABLE_SIZE, VERSION,
+};
 
 /// Header stores the filesystem metadata header fields.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Header {
     pub next_free: u64,
+    /// The tail of the volume holds a quota table.
+    pub quotas: bool,
+}
+
+/// `Corrupt` reports on-disk metadata that cannot be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Corrupt(pub String);
+
+impl fmt::Display for Corrupt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "corrupt filesystem metadata: {}", self.0)
+    }
+}
+
+impl std::error::Error for Corrupt {}
+
+impl Header {
+    #[must_use]
+    /// `to_bytes` serializes the header into a fixed-size buffer.
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut buf = [0u8; HEADER_SIZE];
+        buf[0..8].copy_from_slice(&MAGIC);
+        buf[8] = VERSION;
+        buf[9] = u8::from(self.quotas);
+        buf[16..24].copy_from_slice(&self.next_free.to_le_bytes());
+        let max_files = u32::try_from(MAX_FILES).unwrap_or(u32::MAX);
+        buf[24..28].copy_from_slice(&max_files.to_le_bytes());
+        buf
+    }
+
+    #[must_use]
+    /// `from_bytes` attempts to parse a header from a buffer.
+    ///
+    /// # Arguments
+    /// * `buf` - Buffer containing header data.
+    ///
+    /// # Returns
+    /// `Some(Header)` if the buffer holds a header of this format, otherwise `None`.
+    pub fn from_bytes(buf: &[u8]) -> Option<Self> {
+        let buf = buf.get(..HEADER_SIZE)?;
+        if buf[0..8] != MAGIC || buf[8] != VERSION {
+            return None;
+        }
+        let max_files = u32::from_le_bytes(buf[24..28].try_into().ok()?);
+        if usize::try_from(max_files).ok()? != MAX_FILES {
+            return None;
+        }
+        let next_free = u64::from_le_bytes(buf[16..24].try_into().ok()?);
+        Some(Self {
+            next_free,
+            quotas: buf[9] != 0,
+        })
+    }
 }
 
 /// Entry stores a directory table entry for a file.
@@ -15,6 +73,8 @@ pub struct Entry {
     pub offset: u64,
     pub size: u64,
     pub used: bool,
+    /// User the file's bytes are charged to.
+    pub owner: u32,
 }
 
 #[allow(clippy::missing_const_for_fn)]
@@ -27,6 +87,7 @@ impl Entry {
             offset: 0,
             size: 0,
             used: false,
+            owner: 0,
         }
     }
 
@@ -38,6 +99,7 @@ impl Entry {
     pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
         let mut buf = [0u8; ENTRY_SIZE];
         buf[0] = u8::from(self.used);
+        buf[4..8].copy_from_slice(&self.owner.to_le_bytes());
         buf[8..16].copy_from_slice(&self.offset.to_le_bytes());
         buf[16..24].copy_from_slice(&self.size.to_le_bytes());
         let name_bytes = self.name.as_bytes();
@@ -46,28 +108,150 @@ impl Entry {
         buf
     }
 
-    #[must_use]
     /// `from_bytes` deserializes an entry from a fixed-size buffer.
     ///
     /// # Arguments
     /// * `buf` - Buffer containing serialized entry data.
     ///
-    /// # Panics
-    /// Panics if `buf` is shorter than `ENTRY_SIZE`.
-    pub fn from_bytes(buf: &[u8]) -> Self {
-        let used = buf.first().copied().unwrap_or(0) == 1;
-        let offset = u64::from_le_bytes(buf[8..16].try_into().unwrap());
-        let size = u64::from_le_bytes(buf[16..24].try_into().unwrap());
+    /// # Errors
+    /// Returns `Corrupt` if `buf` is shorter than `ENTRY_SIZE`, the used flag is
+    /// neither 0 nor 1, or a used entry's extent overflows.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, Corrupt> {
+        let Some(buf) = buf.get(..ENTRY_SIZE) else {
+            return Err(Corrupt(format!(
+                "entry needs {ENTRY_SIZE} bytes, got {}",
+                buf.len()
+            )));
+        };
+        let used = match buf[0] {
+            0 => false,
+            1 => true,
+            flag => return Err(Corrupt(format!("entry used flag is {flag}"))),
+        };
+        let owner = u32::from_le_bytes(buf[4..8].try_into().unwrap_or_default());
+        let offset = u64::from_le_bytes(buf[8..16].try_into().unwrap_or_default());
+        let size = u64::from_le_bytes(buf[16..24].try_into().unwrap_or_default());
+        if used && offset.checked_add(size.max(1)).is_none() {
+            return Err(Corrupt(format!("entry extent {offset}+{size} overflows")));
+        }
         let name_bytes = &buf[24..24 + NAME_LEN];
         let end = name_bytes.iter().position(|b| *b == 0).unwrap_or(NAME_LEN);
         let name = String::from_utf8_lossy(&name_bytes[..end]).into_owned();
-        Self {
+        Ok(Self {
             name,
             offset,
             size,
             used,
-        }
+            owner,
+        })
+    }
+
+    #[must_use]
+    /// `is_orphan` reports whether the entry holds a file that was unlinked while open.
+    pub fn is_orphan(&self) -> bool {
+        self.used && self.name.is_empty()
+    }
+
+    /// `end` returns the logical end of the space allocated to the entry.
+    const fn end(&self) -> u64 {
+        self.offset
+            .saturating_add(if self.size == 0 { 1 } else { self.size })
+    }
+}
+
+/// `decode_entries` decodes the file table that follows the header.
+///
+/// Used entries must lie between the end of the table and `header.next_free`,
+/// which is where the allocator places them.
+///
+/// # Arguments
+/// * `buf` - Buffer holding `MAX_FILES` serialized entries.
+/// * `header` - Header the table was stored with.
+///
+/// # Errors
+/// Returns `Corrupt` if the buffer is too short or any entry is malformed.
+pub fn decode_entries(buf: &[u8], header: &Header) -> Result<Vec<Entry>, Corrupt> {
+    let table_len = ENTRY_SIZE * MAX_FILES;
+    let Some(buf) = buf.get(..table_len) else {
+        return Err(Corrupt(format!(
+            "file table needs {table_len} bytes, got {}",
+            buf.len()
+        )));
+    };
+    buf.chunks_exact(ENTRY_SIZE)
+        .enumerate()
+        .map(|(index, bytes)| {
+            let entry = Entry::from_bytes(bytes)
+                .map_err(|err| Corrupt(format!("{} at index {index}", err.0)))?;
+            if entry.used && (entry.offset < TABLE_SIZE as u64 || entry.end() > header.next_free) {
+                return Err(Corrupt(format!(
+                    "entry {index} extent {}+{} lies outside the data area ending at {}",
+                    entry.offset, entry.size, header.next_free
+                )));
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+/// Quota caps the bytes and files charged to one user; `None` leaves a limit unset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub uid: u32,
+    pub bytes: Option<u64>,
+    pub files: Option<u64>,
+}
+
+/// `encode_quotas` serializes a quota table into its reserved region.
+///
+/// Unset limits are stored as `u64::MAX`; at most `MAX_QUOTAS` records are kept.
+#[must_use]
+pub fn encode_quotas(quotas: &[Quota]) -> Vec<u8> {
+    let quotas = &quotas[..quotas.len().min(MAX_QUOTAS)];
+    let mut buf = vec![0u8; usize::try_from(QUOTA_SIZE).unwrap_or(usize::MAX)];
+    buf[0..8].copy_from_slice(&QUOTA_MAGIC);
+    let count = u32::try_from(quotas.len()).unwrap_or(u32::MAX);
+    buf[8..12].copy_from_slice(&count.to_le_bytes());
+    for (quota, record) in quotas
+        .iter()
+        .zip(buf[QUOTA_HEADER_SIZE..].chunks_exact_mut(QUOTA_RECORD_SIZE))
+    {
+        record[0..4].copy_from_slice(&quota.uid.to_le_bytes());
+        record[8..16].copy_from_slice(&quota.bytes.unwrap_or(u64::MAX).to_le_bytes());
+        record[16..24].copy_from_slice(&quota.files.unwrap_or(u64::MAX).to_le_bytes());
     }
+    buf
+}
+
+/// `decode_quotas` decodes the quota table stored at the tail of the volume.
+///
+/// # Errors
+/// Returns `Corrupt` if the region lacks the quota magic or holds too many records.
+pub fn decode_quotas(buf: &[u8]) -> Result<Vec<Quota>, Corrupt> {
+    if buf.get(0..8) != Some(&QUOTA_MAGIC[..]) {
+        return Err(Corrupt("quota table magic is missing".into()));
+    }
+    let count = buf
+        .get(8..12)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map_or(0, u32::from_le_bytes) as usize;
+    let records = buf.get(QUOTA_HEADER_SIZE..).unwrap_or_default();
+    if count > MAX_QUOTAS || count * QUOTA_RECORD_SIZE > records.len() {
+        return Err(Corrupt(format!("quota table holds {count} records")));
+    }
+    let limit = |bytes: &[u8]| {
+        let value = u64::from_le_bytes(bytes.try_into().unwrap_or_default());
+        (value != u64::MAX).then_some(value)
+    };
+    Ok(records
+        .chunks_exact(QUOTA_RECORD_SIZE)
+        .take(count)
+        .map(|record| Quota {
+            uid: u32::from_le_bytes(record[0..4].try_into().unwrap_or_default()),
+            bytes: limit(&record[8..16]),
+            files: limit(&record[16..24]),
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -81,15 +265,17 @@ mod tests {
             offset: 10,
             size: 20,
             used: true,
+            owner: 1000,
         };
 
         let bytes = entry.to_bytes();
-        let decoded = Entry::from_bytes(&bytes);
+        let decoded = Entry::from_bytes(&bytes).expect("decode entry");
 
         assert_eq!(decoded.name, "alpha");
         assert_eq!(decoded.offset, 10);
         assert_eq!(decoded.size, 20);
         assert!(decoded.used);
+        assert_eq!(decoded.owner, 1000);
     }
 
     #[test]
@@ -99,9 +285,102 @@ mod tests {
             offset: 0,
             size: 0,
             used: true,
+            owner: 0,
         };
         let bytes = entry.to_bytes();
-        let decoded = Entry::from_bytes(&bytes);
+        let decoded = Entry::from_bytes(&bytes).expect("decode entry");
         assert_eq!(decoded.name.len(), NAME_LEN);
     }
+
+    #[test]
+    fn header_round_trip_preserves_next_free() {
+        let header = Header {
+            next_free: 4096,
+            quotas: false,
+        };
+        assert_eq!(Header::from_bytes(&header.to_bytes()), Some(header));
+        let quotas = Header {
+            next_free: 4096,
+            quotas: true,
+        };
+        assert_eq!(Header::from_bytes(&quotas.to_bytes()), Some(quotas));
+        assert_eq!(Header::from_bytes(&[0u8; HEADER_SIZE]), None);
+    }
+
+    #[test]
+    fn quota_table_round_trip_keeps_unset_limits() {
+        let quotas = vec![
+            Quota {
+                uid: 1000,
+                bytes: Some(4096),
+                files: None,
+            },
+            Quota {
+                uid: 1001,
+                bytes: None,
+                files: Some(0),
+            },
+        ];
+        let buf = encode_quotas(&quotas);
+        assert_eq!(buf.len() as u64, QUOTA_SIZE);
+        assert_eq!(decode_quotas(&buf), Ok(quotas));
+
+        assert!(decode_quotas(&[0u8; 64]).is_err());
+        let mut oversized = buf;
+        oversized[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_quotas(&oversized).is_err());
+    }
+
+    #[test]
+    fn entry_rejects_malformed_bytes() {
+        let used = Entry {
+            name: "alpha".to_string(),
+            offset: 1,
+            size: 1,
+            used: true,
+            owner: 0,
+        };
+        assert!(Entry::from_bytes(&used.to_bytes()[..ENTRY_SIZE - 1]).is_err());
+
+        let mut bytes = used.to_bytes();
+        bytes[0] = 2;
+        assert!(Entry::from_bytes(&bytes).is_err());
+
+        let overflowing = Entry {
+            offset: u64::MAX,
+            ..used
+        };
+        let err = Entry::from_bytes(&overflowing.to_bytes()).expect_err("extent overflows");
+        assert!(err.to_string().contains("overflows"));
+    }
+
+    #[test]
+    fn decode_entries_checks_extents_against_header() {
+        let header = Header {
+            next_free: TABLE_SIZE as u64 + 10,
+            quotas: false,
+        };
+        let mut table = vec![0u8; ENTRY_SIZE * MAX_FILES];
+        let entry = Entry {
+            name: "alpha".to_string(),
+            offset: TABLE_SIZE as u64,
+            size: 10,
+            used: true,
+            owner: 0,
+        };
+        table[ENTRY_SIZE..2 * ENTRY_SIZE].copy_from_slice(&entry.to_bytes());
+
+        let entries = decode_entries(&table, &header).expect("decode table");
+        assert_eq!(entries.len(), MAX_FILES);
+        assert!(!entries[0].used);
+        assert_eq!(entries[1].name, "alpha");
+
+        let short = Header {
+            next_free: TABLE_SIZE as u64 + 9,
+            quotas: false,
+        };
+        let err = decode_entries(&table, &short).expect_err("extent past next_free");
+        assert!(err.to_string().contains("entry 1"));
+        assert!(decode_entries(&table[..ENTRY_SIZE], &header).is_err());
+    }
 }
@@ -1,13 +1,18 @@
 //! Filesystem building blocks for the RAID-backed FUSE implementation.
 
+pub mod align;
 pub mod constants;
 pub mod metadata;
 pub mod persist;
 pub mod raidfs;
 
+pub use align::Alignment;
 pub use constants::*;
-pub use metadata::{Entry, Header};
-pub use raidfs::{FsState, RaidFs};
+pub use metadata::{Corrupt, Entry, Header, Quota, decode_entries};
+pub use raidfs::{
+    DEFAULT_MAX_AGE, FsState, GrowState, OpenFiles, QosLimits, RaidFs, Scrub, ScrubState, Throttle,
+    WriteBuffer, read_quotas, reclaim_orphans,
+};
 
 #[cfg(test)]
 pub(crate) mod test_utils {
@@ -21,7 +26,7 @@ pub(crate) mod test_utils {
 
     use super::constants::{DEFAULT_CHUNK_SIZE, MAX_FILES};
     use super::metadata::{Entry, Header};
-    use super::raidfs::{FsState, RaidFs};
+    use super::raidfs::{FsState, GrowState, OpenFiles, RaidFs, Scrub, WriteBuffer};
 
     /// `TestStripe` is the RAID0 stripe used by filesystem tests.
     pub type TestStripe = RAID0<1, { DEFAULT_CHUNK_SIZE }>;
@@ -46,27 +51,37 @@ pub(crate) mod test_utils {
     pub fn create_test_state() -> TestState {
         let dir = temp_dir("raid-cli");
         let paths = [dir.join("disk-0.img").to_string_lossy().into_owned()];
-        let array = Array::<1, { DEFAULT_CHUNK_SIZE }>::init_array(&paths, 20_000);
+        let array =
+            Array::<1, { DEFAULT_CHUNK_SIZE }>::init_array(&paths, 20_000).expect("init array");
         let volume = Volume::new(array, TestStripe::zero());
         let header = Header {
             next_free: RaidFs::<1, { DEFAULT_CHUNK_SIZE }, TestStripe>::data_start(),
+            quotas: false,
         };
         let entries = vec![Entry::empty(); MAX_FILES];
         TestState {
             volume,
             header,
             entries,
+            write_buffer: WriteBuffer::default(),
+            scrub: Scrub::default(),
+            grow: GrowState::default(),
+            hot_add: None,
+            quotas: Vec::new(),
+            open_files: OpenFiles::default(),
         }
     }
 
     /// `create_test_fs` builds a ready-to-use filesystem wrapper for tests.
     pub fn create_test_fs() -> TestFs {
         let state = create_test_state();
-        let capacity = state.volume.logical_capacity_bytes();
         TestFs {
             state: Arc::new(Mutex::new(state)),
-            capacity,
             metrics: None,
+            file_io: Mutex::default(),
+            read_only: false,
+            throttle: Mutex::default(),
+            raw_volume: false,
         }
     }
 }
@@ -38,6 +38,7 @@ mod tests {
             offset: 200,
             size: 12,
             used: true,
+            owner: 0,
         };
 
         save_header_and_entry(&mut state, 0);
@@ -51,7 +52,7 @@ mod tests {
         let mut entry_buf = [0u8; ENTRY_SIZE];
         let entry_offset = HEADER_SIZE as u64;
         state.volume.read_bytes(entry_offset, &mut entry_buf);
-        let parsed_entry = Entry::from_bytes(&entry_buf);
+        let parsed_entry = Entry::from_bytes(&entry_buf).expect("entry parsed");
         assert_eq!(parsed_entry.name, "file.txt");
         assert_eq!(parsed_entry.offset, 200);
         assert_eq!(parsed_entry.size, 12);
@@ -0,0 +1,407 @@
+//! Operator control of background work on a mounted volume.
+//!
+//! Rebuild speed presets name common background shares, so operators need not
+//! pick a percentage. A scrub checks every physical stripe and repairs those whose
+//! redundancy no longer matches their data. The control file only starts and stops
+//! it; the scrub thread of the mount checks the stripes in batches, pausing like a
+//! rebuild to leave foreground IO its share of the array. Progress is kept in the
+//! filesystem state, where the control file reports it.
+//!
+//! An online grow is requested the same way: the control file records the member
+//! count to grow to, and the reshape thread of the mount opens the grown array and
+//! moves the data onto it in batches while the filesystem stays mounted. Hot-adding
+//! a disk image requests a grow by one member: the reshape thread makes the image
+//! the next member, which counts as rebuilding until the reshape has filled it.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::volume::{BATCH_STRIPES, StripeCheck};
+
+use super::types::FsState;
+
+/// `RebuildSpeed` is a named background share for rebuild and scrub work.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RebuildSpeed {
+    Min,
+    Low,
+    Normal,
+    Max,
+}
+
+impl RebuildSpeed {
+    const ALL: [Self; 4] = [Self::Min, Self::Low, Self::Normal, Self::Max];
+
+    #[must_use]
+    /// `parse` reads a preset name as used by the control file.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|speed| speed.name() == name)
+    }
+
+    #[must_use]
+    /// `from_share` returns the preset with the given background share, if any.
+    pub fn from_share(share: Option<u8>) -> Option<Self> {
+        Self::ALL.into_iter().find(|speed| speed.share() == share)
+    }
+
+    #[must_use]
+    /// `name` returns the preset name used by the control file.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Min => "min",
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Max => "max",
+        }
+    }
+
+    #[must_use]
+    /// `share` returns the background share of the preset; `max` leaves it unlimited.
+    pub const fn share(self) -> Option<u8> {
+        match self {
+            Self::Min => Some(5),
+            Self::Low => Some(20),
+            Self::Normal => Some(50),
+            Self::Max => None,
+        }
+    }
+}
+
+/// `ScrubState` is where the last scrub of a mount stands.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ScrubState {
+    #[default]
+    Idle,
+    Running,
+    Stopped,
+    Finished,
+}
+
+/// `Scrub` tracks the scrub of a mounted volume.
+#[derive(Clone, Debug, Default)]
+pub struct Scrub {
+    pub state: ScrubState,
+    /// Next physical stripe to check.
+    pub next: u64,
+    pub total: u64,
+    pub repaired: u64,
+    pub uncorrectable: u64,
+}
+
+impl Scrub {
+    /// `start` begins a new scrub over `total` stripes and reports whether it did.
+    pub fn start(&mut self, total: u64) -> bool {
+        if self.state == ScrubState::Running {
+            return false;
+        }
+        *self = Self {
+            state: ScrubState::Running,
+            total,
+            ..Self::default()
+        };
+        true
+    }
+
+    /// `stop` ends the running scrub and reports whether one was running.
+    pub fn stop(&mut self) -> bool {
+        let running = self.state == ScrubState::Running;
+        if running {
+            self.state = ScrubState::Stopped;
+        }
+        running
+    }
+
+    #[must_use]
+    /// `status_string` reports the scrub for the control file.
+    pub fn status_string(&self) -> String {
+        let mut txt = String::from("\nscrub:\n");
+        let state = match self.state {
+            ScrubState::Idle => {
+                txt.push_str("  idle\n");
+                return txt;
+            }
+            ScrubState::Running => "running",
+            ScrubState::Stopped => "stopped",
+            ScrubState::Finished => "finished",
+        };
+        let _ = writeln!(
+            txt,
+            "  {state}: {}/{} stripes, {} repaired, {} uncorrectable",
+            self.next, self.total, self.repaired, self.uncorrectable
+        );
+        txt
+    }
+}
+
+/// `GrowState` is where the online grow of a mount stands; each state but `Idle`
+/// carries the member count grown to.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GrowState {
+    #[default]
+    Idle,
+    /// Requested from the control file, not yet picked up by the reshape thread.
+    Requested(usize),
+    Running(usize),
+    Finished(usize),
+    Failed(usize),
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> FsState<D, N, T> {
+    /// `start_scrub` schedules a scrub of every physical stripe.
+    ///
+    /// A volume that has been reshaped no longer keeps its data in the stripes of
+    /// its own geometry, so it is not scrubbed until it is mounted again.
+    ///
+    /// # Returns
+    /// `false` if a scrub is already running, disks await a rebuild or the volume
+    /// is being or has been reshaped.
+    pub fn start_scrub(&mut self) -> bool {
+        !self.grow_pending()
+            && !self.volume.any_needs_rebuild()
+            && self.scrub.start(self.volume.physical_stripes())
+    }
+
+    /// `request_grow` asks the reshape thread to restripe the volume onto `disks`
+    /// members while it stays mounted.
+    ///
+    /// # Returns
+    /// `false` if `disks` adds no member, a grow was already requested, a scrub or
+    /// balance is running, disks are missing or await a rebuild, or the volume is
+    /// thin, compressed or has snapshots.
+    pub fn request_grow(&mut self, disks: usize) -> bool {
+        let volume = &self.volume;
+        if disks <= D
+            || self.grow_pending()
+            || self.scrub.state == ScrubState::Running
+            || volume.is_balancing()
+            || volume.is_degraded()
+            || volume.any_needs_rebuild()
+            || volume.is_thin()
+            || volume.is_compressed()
+            || volume.snapshots_enabled()
+        {
+            return false;
+        }
+        self.grow = GrowState::Requested(disks);
+        true
+    }
+
+    /// `request_add_disk` hot-adds the disk image at `image` as the next member,
+    /// growing the volume onto it while it stays mounted.
+    ///
+    /// The image is moved into place when the grow starts; whatever it held is
+    /// discarded.
+    ///
+    /// # Returns
+    /// `false` if `image` is not a file or a grow by one member cannot be requested.
+    pub fn request_add_disk(&mut self, image: PathBuf) -> bool {
+        if !image.is_file() || !self.request_grow(D + 1) {
+            return false;
+        }
+        self.hot_add = Some(image);
+        true
+    }
+
+    /// `grow_pending` reports whether a grow was requested or has begun moving data;
+    /// a failed reshape resumes from its checkpoint on the next mount.
+    fn grow_pending(&self) -> bool {
+        matches!(self.grow, GrowState::Requested(_)) || self.volume.reshape_progress().is_some()
+    }
+
+    #[must_use]
+    /// `grow_status_string` reports the online grow for the control file.
+    pub fn grow_status_string(&self) -> String {
+        let mut txt = String::from("\ngrow:\n");
+        let (state, disks) = match self.grow {
+            GrowState::Idle => {
+                txt.push_str("  idle\n");
+                return txt;
+            }
+            GrowState::Requested(disks) => ("requested", disks),
+            GrowState::Running(disks) => ("running", disks),
+            GrowState::Finished(disks) => ("finished", disks),
+            GrowState::Failed(disks) => ("failed", disks),
+        };
+        let _ = write!(txt, "  {state}: {D} -> {disks} members");
+        if let Some(progress) = self.volume.reshape_progress() {
+            let _ = write!(
+                txt,
+                ", {}/{} bytes moved",
+                progress.moved_bytes, progress.total_bytes
+            );
+        }
+        if matches!(self.grow, GrowState::Finished(_)) {
+            let _ = write!(txt, "; mount with --disks {disks} next time");
+        }
+        txt.push('\n');
+        if let Some(image) = &self.hot_add {
+            let member = match self.grow {
+                GrowState::Idle | GrowState::Requested(_) => "added",
+                GrowState::Running(_) => "rebuilding",
+                GrowState::Finished(_) => "in sync",
+                GrowState::Failed(_) => "failed",
+            };
+            let _ = writeln!(txt, "  disk {D}: {member} ({})", image.display());
+        }
+        txt
+    }
+
+    /// `scrub_batch` checks the next batch of stripes of a running scrub and repairs
+    /// the ones that can be restored from redundancy.
+    ///
+    /// # Returns
+    /// Whether the scrub has stripes left.
+    pub fn scrub_batch(&mut self) -> bool {
+        if self.scrub.state != ScrubState::Running {
+            return false;
+        }
+        let end = (self.scrub.next + BATCH_STRIPES as u64).min(self.scrub.total);
+        let mut repairable = Vec::new();
+        for stripe in self.scrub.next..end {
+            match self.volume.check_stripe(stripe) {
+                StripeCheck::Repairable { .. } => repairable.push(stripe),
+                StripeCheck::Uncorrectable => self.scrub.uncorrectable += 1,
+                _ => {}
+            }
+        }
+        self.volume.repair_stripes(&repairable);
+        self.scrub.repaired += repairable.len() as u64;
+        self.scrub.next = end;
+        if end < self.scrub.total {
+            return true;
+        }
+        self.scrub.state = ScrubState::Finished;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::{create_test_state, temp_dir};
+    use crate::fs::{DEFAULT_CHUNK_SIZE, Entry, Header, MAX_FILES, OpenFiles, WriteBuffer};
+    use raid_rs::layout::stripe::raid1::RAID1;
+    use raid_rs::retention::array::Array;
+    use raid_rs::retention::volume::Volume;
+
+    type MirrorState = FsState<3, DEFAULT_CHUNK_SIZE, RAID1<3, DEFAULT_CHUNK_SIZE>>;
+
+    fn mirror_state(dir: &std::path::Path) -> MirrorState {
+        let paths: [String; 3] =
+            std::array::from_fn(|i| dir.join(format!("disk-{i}.img")).display().to_string());
+        let mut volume = Volume::new(
+            Array::init_array(&paths, 4096).expect("init array"),
+            RAID1::zero(),
+        );
+        volume.clear_needs_rebuild_all();
+        MirrorState {
+            volume,
+            header: Header {
+                next_free: 0,
+                quotas: false,
+            },
+            entries: vec![Entry::empty(); MAX_FILES],
+            write_buffer: WriteBuffer::default(),
+            scrub: Scrub::default(),
+            grow: GrowState::default(),
+            hot_add: None,
+            quotas: Vec::new(),
+            open_files: OpenFiles::default(),
+        }
+    }
+
+    #[test]
+    fn rebuild_speed_presets_round_trip() {
+        for speed in RebuildSpeed::ALL {
+            assert_eq!(RebuildSpeed::parse(speed.name()), Some(speed));
+            assert_eq!(RebuildSpeed::from_share(speed.share()), Some(speed));
+        }
+        assert_eq!(RebuildSpeed::parse("fast"), None);
+        assert_eq!(RebuildSpeed::from_share(Some(33)), None);
+    }
+
+    #[test]
+    fn scrub_runs_once_at_a_time_and_can_stop() {
+        let mut state = create_test_state();
+        assert!(!state.start_scrub(), "disks await a rebuild");
+        state.volume.clear_needs_rebuild_all();
+        assert!(state.scrub.status_string().contains("idle"));
+        assert!(!state.scrub.stop());
+
+        assert!(state.start_scrub());
+        assert!(!state.start_scrub(), "already running");
+        assert!(state.scrub_batch());
+        assert!(state.scrub.stop());
+        assert!(!state.scrub_batch());
+        let stopped = state.scrub.next;
+        assert!(stopped > 0 && stopped < state.scrub.total);
+        assert!(state.scrub.status_string().contains("stopped"));
+
+        assert!(state.start_scrub());
+        assert_eq!(state.scrub.next, 0);
+        while state.scrub_batch() {}
+        assert_eq!(state.scrub.state, ScrubState::Finished);
+        assert_eq!(state.scrub.next, state.scrub.total);
+    }
+
+    #[test]
+    fn grow_requests_need_more_members_and_a_quiet_volume() {
+        let dir = temp_dir("raid-cli-grow-request");
+        let mut state = mirror_state(&dir);
+        assert!(state.grow_status_string().contains("idle"));
+        assert!(!state.request_grow(3), "no member added");
+        assert!(state.start_scrub());
+        assert!(!state.request_grow(4), "scrub running");
+        assert!(state.scrub.stop());
+
+        assert!(state.request_grow(4));
+        assert_eq!(state.grow, GrowState::Requested(4));
+        assert!(!state.request_grow(5), "already requested");
+        assert!(!state.start_scrub(), "grow requested");
+        assert!(
+            state
+                .grow_status_string()
+                .contains("requested: 3 -> 4 members")
+        );
+
+        let mut failed = mirror_state(&dir);
+        failed.volume.fail_disk(2).expect("fail disk");
+        assert!(!failed.request_grow(4), "member missing");
+    }
+
+    #[test]
+    fn add_disk_requests_a_grow_by_one_member() {
+        let dir = temp_dir("raid-cli-add-disk");
+        let image = dir.join("spare.img");
+        let mut state = mirror_state(&dir);
+        assert!(!state.request_add_disk(image.clone()), "no image");
+
+        std::fs::write(&image, b"").expect("write image");
+        assert!(state.request_add_disk(image.clone()));
+        assert_eq!(state.grow, GrowState::Requested(4));
+        assert!(!state.request_add_disk(image), "grow requested");
+        let status = state.grow_status_string();
+        assert!(status.contains("requested: 3 -> 4 members"));
+        assert!(status.contains("disk 3: added"));
+    }
+
+    #[test]
+    fn scrub_repairs_mismatched_mirror_copies() {
+        let dir = temp_dir("raid-cli-scrub");
+        let mut state = mirror_state(&dir);
+        state.volume.write_bytes(0, b"mirror");
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(dir.join("disk-1.img"))
+            .and_then(|mut image| std::io::Write::write_all(&mut image, &[0xff; 8]))
+            .expect("corrupt copy");
+
+        assert!(state.start_scrub());
+        while state.scrub_batch() {}
+
+        assert!(state.scrub.repaired > 0);
+        assert_eq!(state.scrub.uncorrectable, 0);
+        assert!(state.volume.check().is_clean());
+    }
+}
@@ -0,0 +1,401 @@
+//! Write coalescing for the FUSE layer.
+//!
+//! Every FUSE write of a few KiB costs the volume a read-modify-write of a whole
+//! stripe. With a write buffer, the data of each file collects in one pending
+//! extent that grows while writes land next to or inside it, and reaches the
+//! volume in a single write. An extent is flushed once it reaches the buffer
+//! limit, when a write lands elsewhere in the file, before the file is read or
+//! resized, on fsync and close, before a snapshot, and after it has waited
+//! `max_age`. File sizes change in memory at once; the entry table is written
+//! together with the data.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+
+use super::types::FsState;
+use crate::fs::persist::save_header_and_entry;
+
+/// `DEFAULT_MAX_AGE` is how long buffered data waits for neighbours by default.
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_millis(50);
+
+/// `Extent` is the buffered data of one file at a volume offset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Extent {
+    pub offset: u64,
+    pub data: Vec<u8>,
+    /// When the oldest write in the extent arrived.
+    since: Instant,
+}
+
+impl Extent {
+    const fn end(&self) -> u64 {
+        self.offset + self.data.len() as u64
+    }
+
+    /// `merge` lays `data` at `offset` over the extent if the two touch or overlap.
+    ///
+    /// # Returns
+    /// `false` if there is a hole between them.
+    fn merge(&mut self, offset: u64, data: &[u8]) -> bool {
+        let end = offset + data.len() as u64;
+        if offset > self.end() || end < self.offset {
+            return false;
+        }
+        if offset < self.offset {
+            let front = usize::try_from(self.offset - offset).unwrap_or(usize::MAX);
+            let mut merged = vec![0u8; front];
+            merged.append(&mut self.data);
+            self.data = merged;
+            self.offset = offset;
+        }
+        let at = usize::try_from(offset - self.offset).unwrap_or(usize::MAX);
+        let overlap = data.len().min(self.data.len().saturating_sub(at));
+        self.data[at..at + overlap].copy_from_slice(&data[..overlap]);
+        self.data.extend_from_slice(&data[overlap..]);
+        true
+    }
+}
+
+/// `WriteBufferStats` counts the work of a write buffer since mount.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WriteBufferStats {
+    /// Writes that were held in the buffer.
+    pub buffered_writes: u64,
+    /// Extents written to the volume.
+    pub flushes: u64,
+    pub flushed_bytes: u64,
+}
+
+/// `WriteBuffer` holds the unwritten data of each file, keyed by entry index.
+#[derive(Clone, Debug)]
+pub struct WriteBuffer {
+    /// Bytes an extent may hold before it is flushed; `None` turns buffering off.
+    limit: Option<usize>,
+    max_age: Duration,
+    pending: HashMap<usize, Extent>,
+    stats: WriteBufferStats,
+}
+
+impl Default for WriteBuffer {
+    fn default() -> Self {
+        Self::new(None, DEFAULT_MAX_AGE)
+    }
+}
+
+impl WriteBuffer {
+    #[must_use]
+    /// `new` builds an empty buffer.
+    ///
+    /// # Arguments
+    /// * `limit` - Bytes each file may buffer; `None` or 0 writes through.
+    /// * `max_age` - How long buffered data may wait before it is flushed.
+    pub fn new(limit: Option<usize>, max_age: Duration) -> Self {
+        Self {
+            limit: limit.filter(|&limit| limit > 0),
+            max_age,
+            pending: HashMap::new(),
+            stats: WriteBufferStats::default(),
+        }
+    }
+
+    #[must_use]
+    /// `is_enabled` reports whether writes are buffered at all.
+    pub const fn is_enabled(&self) -> bool {
+        self.limit.is_some()
+    }
+
+    #[must_use]
+    /// `max_age` returns how long buffered data may wait before it is flushed.
+    pub const fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    #[must_use]
+    /// `holds` reports whether file `index` has buffered data.
+    pub fn holds(&self, index: usize) -> bool {
+        self.pending.contains_key(&index)
+    }
+
+    #[must_use]
+    /// `dirty_bytes` returns the bytes buffered across all files.
+    pub fn dirty_bytes(&self) -> u64 {
+        self.pending
+            .values()
+            .map(|extent| extent.data.len() as u64)
+            .sum()
+    }
+
+    #[must_use]
+    /// `stats` returns the counters of the buffer.
+    pub const fn stats(&self) -> WriteBufferStats {
+        self.stats
+    }
+
+    /// `add` buffers a write of `data` at volume offset `offset` for file `index`.
+    ///
+    /// # Returns
+    /// The extents that must be written to the volume now, in order: a displaced
+    /// extent the write does not touch, and the write's own extent once it has
+    /// reached the limit. With buffering off, the write itself.
+    pub fn add(&mut self, index: usize, offset: u64, data: &[u8], now: Instant) -> Vec<Extent> {
+        let mut ready = Vec::new();
+        let Some(limit) = self.limit else {
+            ready.push(Extent {
+                offset,
+                data: data.to_vec(),
+                since: now,
+            });
+            return ready;
+        };
+        let merged = self
+            .pending
+            .get_mut(&index)
+            .is_some_and(|extent| extent.merge(offset, data));
+        if !merged {
+            if let Some(displaced) = self.pending.remove(&index) {
+                ready.push(displaced);
+            }
+            self.pending.insert(
+                index,
+                Extent {
+                    offset,
+                    data: data.to_vec(),
+                    since: now,
+                },
+            );
+        }
+        self.stats.buffered_writes += 1;
+        if self.pending[&index].data.len() >= limit {
+            ready.extend(self.pending.remove(&index));
+        }
+        ready
+    }
+
+    /// `take` removes the buffered data of file `index`.
+    pub fn take(&mut self, index: usize) -> Option<Extent> {
+        self.pending.remove(&index)
+    }
+
+    #[must_use]
+    /// `expired` returns the files whose data has waited `max_age` by `now`.
+    pub fn expired(&self, now: Instant) -> Vec<usize> {
+        self.pending
+            .iter()
+            .filter(|(_, extent)| now.saturating_duration_since(extent.since) >= self.max_age)
+            .map(|(&index, _)| index)
+            .collect()
+    }
+
+    #[must_use]
+    /// `files` returns every file with buffered data.
+    pub fn files(&self) -> Vec<usize> {
+        self.pending.keys().copied().collect()
+    }
+
+    #[must_use]
+    /// `status_string` reports the buffer for the control file.
+    pub fn status_string(&self) -> String {
+        let Some(limit) = self.limit else {
+            return "\nwrite buffer: off\n".to_string();
+        };
+        let mut txt = format!(
+            "\nwrite buffer:\n  limit: {limit} B per file, max age {} ms\n",
+            self.max_age.as_millis()
+        );
+        let _ = writeln!(
+            txt,
+            "  dirty: {} B in {} files",
+            self.dirty_bytes(),
+            self.pending.len()
+        );
+        let _ = writeln!(
+            txt,
+            "  flushed: {} B in {} flushes of {} writes",
+            self.stats.flushed_bytes, self.stats.flushes, self.stats.buffered_writes
+        );
+        txt
+    }
+
+    const fn count_flush(&mut self, extent: &Extent) {
+        self.stats.flushes += 1;
+        self.stats.flushed_bytes += extent.data.len() as u64;
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> FsState<D, N, T> {
+    /// `write_data` writes `data` at volume offset `offset` for file `index`
+    /// through the write buffer.
+    ///
+    /// # Errors
+    /// Returns the volume error of an extent that could not be written; that
+    /// extent is dropped.
+    pub fn write_data(&mut self, index: usize, offset: u64, data: &[u8]) -> raid_rs::Result<()> {
+        if !self.write_buffer.is_enabled() {
+            return self.volume.try_write_bytes(offset, data);
+        }
+        for extent in self.write_buffer.add(index, offset, data, Instant::now()) {
+            self.write_extent(&extent)?;
+        }
+        Ok(())
+    }
+
+    /// `flush_file` writes the buffered data of file `index` and its entry.
+    ///
+    /// # Errors
+    /// Returns the volume error if the data could not be written; it is dropped.
+    pub fn flush_file(&mut self, index: usize) -> raid_rs::Result<()> {
+        let Some(extent) = self.write_buffer.take(index) else {
+            return Ok(());
+        };
+        self.write_extent(&extent)?;
+        if self.entries.get(index).is_some_and(|entry| entry.used) {
+            save_header_and_entry(self, index);
+        }
+        Ok(())
+    }
+
+    /// `flush_files` flushes every file in `indices`, continuing past failures.
+    ///
+    /// # Errors
+    /// Returns the first volume error.
+    pub fn flush_files(&mut self, indices: Vec<usize>) -> raid_rs::Result<()> {
+        let mut result = Ok(());
+        for index in indices {
+            if let Err(err) = self.flush_file(index) {
+                tracing::warn!("failed to flush buffered writes of entry {index}: {err}");
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+
+    /// `flush_all` writes the buffered data of every file.
+    ///
+    /// # Errors
+    /// Returns the first volume error.
+    pub fn flush_all(&mut self) -> raid_rs::Result<()> {
+        let files = self.write_buffer.files();
+        self.flush_files(files)
+    }
+
+    /// `flush_expired` writes the buffered data that has waited `max_age` by `now`.
+    ///
+    /// # Errors
+    /// Returns the first volume error.
+    pub fn flush_expired(&mut self, now: Instant) -> raid_rs::Result<()> {
+        let files = self.write_buffer.expired(now);
+        self.flush_files(files)
+    }
+
+    fn write_extent(&mut self, extent: &Extent) -> raid_rs::Result<()> {
+        self.volume.try_write_bytes(extent.offset, &extent.data)?;
+        self.write_buffer.count_flush(extent);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::create_test_state;
+
+    fn buffer(limit: usize) -> WriteBuffer {
+        WriteBuffer::new(Some(limit), Duration::from_millis(10))
+    }
+
+    #[test]
+    fn adjacent_and_overlapping_writes_merge() {
+        let mut buffer = buffer(1024);
+        let now = Instant::now();
+        assert!(buffer.add(0, 100, b"abcd", now).is_empty());
+        assert!(buffer.add(0, 104, b"efgh", now).is_empty());
+        assert!(buffer.add(0, 102, b"XY", now).is_empty());
+        assert!(buffer.add(0, 98, b"01", now).is_empty());
+
+        assert_eq!(buffer.dirty_bytes(), 10);
+        let extent = buffer.take(0).expect("buffered extent");
+        assert_eq!(extent.offset, 98);
+        assert_eq!(extent.data, b"01abXYefgh");
+        assert_eq!(buffer.stats().buffered_writes, 4);
+    }
+
+    #[test]
+    fn distant_write_displaces_the_pending_extent() {
+        let mut buffer = buffer(1024);
+        let now = Instant::now();
+        buffer.add(0, 0, b"head", now);
+        buffer.add(1, 8, b"other file", now);
+        let ready = buffer.add(0, 64, b"tail", now);
+
+        assert_eq!(ready.len(), 1);
+        assert_eq!(
+            (ready[0].offset, ready[0].data.as_slice()),
+            (0, &b"head"[..])
+        );
+        assert_eq!(buffer.take(0).map(|extent| extent.offset), Some(64));
+        assert!(buffer.holds(1));
+    }
+
+    #[test]
+    fn full_extents_and_disabled_buffers_write_through() {
+        let mut buffer = buffer(8);
+        let now = Instant::now();
+        assert!(buffer.add(0, 0, b"1234", now).is_empty());
+        let ready = buffer.add(0, 4, b"5678", now);
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].data, b"12345678");
+        assert_eq!(buffer.dirty_bytes(), 0);
+
+        let mut off = WriteBuffer::new(Some(0), DEFAULT_MAX_AGE);
+        assert!(!off.is_enabled());
+        assert_eq!(off.add(0, 0, b"x", now).len(), 1);
+        assert_eq!(off.status_string(), "\nwrite buffer: off\n");
+    }
+
+    #[test]
+    fn only_old_extents_expire() {
+        let mut buffer = buffer(1024);
+        let now = Instant::now();
+        buffer.add(0, 0, b"old", now);
+        buffer.add(1, 0, b"new", now + Duration::from_millis(8));
+        assert_eq!(buffer.expired(now + Duration::from_millis(12)), vec![0]);
+    }
+
+    #[test]
+    fn flushing_writes_data_and_entry_to_the_volume() {
+        let mut state = create_test_state();
+        state.write_buffer = buffer(1024);
+        state.entries[0].used = true;
+        state.entries[0].offset = 4096;
+        state.entries[0].size = 8;
+
+        state.write_data(0, 4096, b"abcd").expect("buffer");
+        state.write_data(0, 4100, b"efgh").expect("buffer");
+        let mut out = [0u8; 8];
+        state.volume.read_bytes(4096, &mut out);
+        assert_eq!(out, [0; 8]);
+
+        state.flush_all().expect("flush");
+        state.volume.read_bytes(4096, &mut out);
+        assert_eq!(&out, b"abcdefgh");
+        assert_eq!(
+            state.write_buffer.stats(),
+            WriteBufferStats {
+                buffered_writes: 2,
+                flushes: 1,
+                flushed_bytes: 8,
+            }
+        );
+        assert!(
+            state
+                .write_buffer
+                .status_string()
+                .contains("dirty: 0 B in 0 files")
+        );
+    }
+}
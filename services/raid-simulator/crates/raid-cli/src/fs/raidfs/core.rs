@@ -7,7 +7,7 @@ use fuser::{FileAttr, FileType};
 use raid_rs::layout::stripe::traits::stripe::Stripe;
 
 use crate::fs::constants::{
-    CTL_INO, CTL_SIZE, FILE_ID_BASE, HEADER_SIZE, MAGIC, MAX_FILES, ROOT_ID, TABLE_SIZE, VERSION,
+    CTL_INO, CTL_SIZE, FILE_ID_BASE, HEADER_SIZE, MAX_FILES, RAW_INO, ROOT_ID, TABLE_SIZE,
 };
 use crate::fs::metadata::Header;
 
@@ -40,6 +40,13 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         Self::file_attr(CTL_INO, CTL_SIZE)
     }
 
+    #[must_use]
+    /// `raw_attr` returns file attributes for the raw volume file.
+    pub fn raw_attr(&self) -> FileAttr {
+        let capacity = self.state.lock().map_or(0, |state| state.capacity());
+        Self::file_attr(RAW_INO, capacity)
+    }
+
     #[must_use]
     /// `data_start` returns the byte offset where file data begins.
     pub const fn data_start() -> u64 {
@@ -52,13 +59,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     /// # Arguments
     /// * `header` - Header to serialize.
     pub fn header_bytes(header: &Header) -> [u8; HEADER_SIZE] {
-        let mut buf = [0u8; HEADER_SIZE];
-        buf[0..8].copy_from_slice(&MAGIC);
-        buf[8] = VERSION;
-        buf[16..24].copy_from_slice(&header.next_free.to_le_bytes());
-        let max_files = u32::try_from(MAX_FILES).unwrap_or(u32::MAX);
-        buf[24..28].copy_from_slice(&max_files.to_le_bytes());
-        buf
+        header.to_bytes()
     }
 
     #[must_use]
@@ -70,21 +71,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     /// # Returns
     /// `Some(Header)` if the header is valid, otherwise `None`.
     pub fn parse_header(buf: &[u8]) -> Option<Header> {
-        if buf.len() < HEADER_SIZE {
-            return None;
-        }
-        if buf[0..8] != MAGIC {
-            return None;
-        }
-        if buf[8] != VERSION {
-            return None;
-        }
-        let max_files = u32::from_le_bytes(buf[24..28].try_into().ok()?) as usize;
-        if max_files != MAX_FILES {
-            return None;
-        }
-        let next_free = u64::from_le_bytes(buf[16..24].try_into().ok()?);
-        Some(Header { next_free })
+        Header::from_bytes(buf)
     }
 
     #[must_use]
@@ -110,6 +97,13 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         }
     }
 
+    #[must_use]
+    /// `is_read_only` reports whether `ino` refuses changes: every node of a
+    /// read-only mount, and snapshot nodes on any mount.
+    pub fn is_read_only(&self, ino: u64) -> bool {
+        self.read_only || Self::snapshot_node(ino).is_some() || self.raw_disk(ino).is_some()
+    }
+
     #[must_use]
     /// `is_valid_name` validates a filename for directory entries.
     ///
@@ -158,13 +152,17 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fs::constants::VERSION;
     use crate::fs::test_utils::TestStripe;
 
     type TestFs = RaidFs<1, { crate::fs::DEFAULT_CHUNK_SIZE }, TestStripe>;
 
     #[test]
     fn header_bytes_round_trip() {
-        let header = Header { next_free: 123 };
+        let header = Header {
+            next_free: 123,
+            quotas: false,
+        };
         let bytes = TestFs::header_bytes(&header);
         let parsed = TestFs::parse_header(&bytes).expect("parse header");
         assert_eq!(parsed.next_free, 123);
@@ -179,14 +177,20 @@ mod tests {
 
     #[test]
     fn header_parse_rejects_bad_version() {
-        let mut bytes = TestFs::header_bytes(&Header { next_free: 0 });
+        let mut bytes = TestFs::header_bytes(&Header {
+            next_free: 0,
+            quotas: false,
+        });
         bytes[8] = VERSION.saturating_add(1);
         assert!(TestFs::parse_header(&bytes).is_none());
     }
 
     #[test]
     fn header_parse_rejects_bad_max_files() {
-        let mut bytes = TestFs::header_bytes(&Header { next_free: 0 });
+        let mut bytes = TestFs::header_bytes(&Header {
+            next_free: 0,
+            quotas: false,
+        });
         let max_files = u32::try_from(MAX_FILES).unwrap_or(u32::MAX);
         bytes[24..28].copy_from_slice(&max_files.saturating_add(1).to_le_bytes());
         assert!(TestFs::parse_header(&bytes).is_none());
@@ -0,0 +1,110 @@
+//! Per-file I/O accounting for the FUSE layer.
+
+use std::collections::HashMap;
+
+/// `FileIo` accumulates the data operations served for one file.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileIo {
+    pub reads: u64,
+    pub writes: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+impl FileIo {
+    #[must_use]
+    /// `total_bytes` returns the bytes read and written for the file.
+    pub const fn total_bytes(&self) -> u64 {
+        self.read_bytes.saturating_add(self.write_bytes)
+    }
+}
+
+/// `FileIoTable` tracks per-inode I/O counters for files that saw traffic.
+#[derive(Debug, Default)]
+pub struct FileIoTable {
+    files: HashMap<u64, FileIo>,
+}
+
+impl FileIoTable {
+    /// `record_read` accounts a read served for `ino`.
+    pub fn record_read(&mut self, ino: u64, bytes: u64) {
+        let io = self.files.entry(ino).or_default();
+        io.reads += 1;
+        io.read_bytes = io.read_bytes.saturating_add(bytes);
+    }
+
+    /// `record_write` accounts a write applied to `ino`.
+    pub fn record_write(&mut self, ino: u64, bytes: u64) {
+        let io = self.files.entry(ino).or_default();
+        io.writes += 1;
+        io.write_bytes = io.write_bytes.saturating_add(bytes);
+    }
+
+    /// `forget` drops the counters of a removed file so its inode starts fresh.
+    pub fn forget(&mut self, ino: u64) {
+        self.files.remove(&ino);
+    }
+
+    #[must_use]
+    /// `top` returns up to `n` files ordered by total bytes, busiest first.
+    pub fn top(&self, n: usize) -> Vec<(u64, FileIo)> {
+        let mut files: Vec<(u64, FileIo)> =
+            self.files.iter().map(|(&ino, &io)| (ino, io)).collect();
+        files.sort_unstable_by(|a, b| {
+            b.1.total_bytes()
+                .cmp(&a.1.total_bytes())
+                .then(a.0.cmp(&b.0))
+        });
+        files.truncate(n);
+        files
+    }
+}
+
+#[must_use]
+/// `name_hash` returns the FNV-1a hash of a file name.
+///
+/// Metrics carry the hash instead of the name so file names never leave the host.
+pub fn name_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_orders_files_by_total_bytes() {
+        let mut table = FileIoTable::default();
+        table.record_read(10, 100);
+        table.record_write(11, 500);
+        table.record_read(12, 50);
+        table.record_write(12, 60);
+        table.record_read(10, 100);
+
+        let top = table.top(2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, 11);
+        assert_eq!(top[1].0, 10);
+        assert_eq!(
+            top[1].1,
+            FileIo {
+                reads: 2,
+                writes: 0,
+                read_bytes: 200,
+                write_bytes: 0,
+            }
+        );
+
+        table.forget(11);
+        assert_eq!(table.top(1)[0].0, 10);
+    }
+
+    #[test]
+    fn name_hash_is_fnv1a() {
+        assert_eq!(name_hash(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(name_hash("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(name_hash("foo"), name_hash("bar"));
+    }
+}
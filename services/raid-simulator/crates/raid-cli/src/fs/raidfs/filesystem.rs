@@ -1,24 +1,41 @@
 use std::ffi::OsStr;
+use std::os::raw::c_int;
 use std::time::SystemTime;
 
 use fuser::{
-    Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry,
-    ReplyOpen, ReplyStatfs, ReplyWrite, ReplyXattr, Request, TimeOrNow,
+    Filesystem, KernelConfig, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory, ReplyEmpty,
+    ReplyEntry, ReplyOpen, ReplyStatfs, ReplyWrite, ReplyXattr, Request, TimeOrNow,
 };
 use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::metrics::IO_TRACE_TARGET;
+use tracing::span::EnteredSpan;
+use tracing::trace_span;
 
 use super::types::RaidFs;
 
 impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N, T> {
+    fn init(&mut self, _req: &Request<'_>, _config: &mut KernelConfig) -> Result<(), c_int> {
+        crate::health::set_mounted(true);
+        Ok(())
+    }
+
+    fn destroy(&mut self) {
+        self.sync_volume();
+        crate::health::set_mounted(false);
+    }
+
     fn lookup(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let _span = io_span("lookup", parent);
         self.op_lookup(req, parent, name, reply);
     }
 
     fn getattr(&mut self, req: &Request<'_>, ino: u64, fh: Option<u64>, reply: ReplyAttr) {
+        let _span = io_span("getattr", ino);
         self.op_getattr(req, ino, fh, reply);
     }
 
     fn access(&mut self, req: &Request<'_>, ino: u64, mask: i32, reply: ReplyEmpty) {
+        let _span = io_span("access", ino);
         self.op_access(req, ino, mask, reply);
     }
 
@@ -30,6 +47,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         size: u32,
         reply: ReplyXattr,
     ) {
+        let _span = io_span("getxattr", ino);
         Self::op_getxattr(req, ino, name, size, reply);
     }
 
@@ -52,6 +70,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         flags: Option<u32>,
         reply: ReplyAttr,
     ) {
+        let _span = io_span("setattr", ino);
         self.op_setattr(
             req, ino, mode, uid, gid, size, atime, mtime, ctime, fh, crtime, chgtime, bkuptime,
             flags, reply,
@@ -68,14 +87,29 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         rdev: u32,
         reply: ReplyEntry,
     ) {
+        let _span = io_span("mknod", parent);
         self.op_mknod(req, parent, name, mode, umask, rdev, reply);
     }
 
     fn unlink(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        let _span = io_span("unlink", parent);
         self.op_unlink(req, parent, name, reply);
     }
 
+    fn link(
+        &mut self,
+        req: &Request<'_>,
+        ino: u64,
+        newparent: u64,
+        newname: &OsStr,
+        reply: ReplyEntry,
+    ) {
+        let _span = io_span("link", ino);
+        self.op_link(req, ino, newparent, newname, reply);
+    }
+
     fn open(&mut self, req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        let _span = io_span("open", ino);
         self.op_open(req, ino, flags, reply);
     }
 
@@ -90,6 +124,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         lock_owner: Option<u64>,
         reply: ReplyData,
     ) {
+        let _span = io_span("read", ino);
         self.op_read(req, ino, fh, offset, size, flags, lock_owner, reply);
     }
 
@@ -105,6 +140,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         lock_owner: Option<u64>,
         reply: ReplyWrite,
     ) {
+        let _span = io_span("write", ino);
         self.op_write(
             req,
             ino,
@@ -119,7 +155,8 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
     }
 
     fn flush(&mut self, req: &Request<'_>, ino: u64, fh: u64, lock_owner: u64, reply: ReplyEmpty) {
-        Self::op_flush(req, ino, fh, lock_owner, reply);
+        let _span = io_span("flush", ino);
+        self.op_flush(req, ino, fh, lock_owner, reply);
     }
 
     fn release(
@@ -132,10 +169,12 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         flush: bool,
         reply: ReplyEmpty,
     ) {
-        Self::op_release(req, ino, fh, flags, lock_owner, flush, reply);
+        let _span = io_span("release", ino);
+        self.op_release(req, ino, fh, flags, lock_owner, flush, reply);
     }
 
     fn fsync(&mut self, req: &Request<'_>, ino: u64, fh: u64, datasync: bool, reply: ReplyEmpty) {
+        let _span = io_span("fsync", ino);
         self.op_fsync(req, ino, fh, datasync, reply);
     }
 
@@ -147,6 +186,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         offset: i64,
         reply: ReplyDirectory,
     ) {
+        let _span = io_span("readdir", ino);
         self.op_readdir(req, ino, fh, offset, reply);
     }
 
@@ -160,14 +200,21 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Filesystem for RaidFs<D, N
         flags: i32,
         reply: ReplyCreate,
     ) {
+        let _span = io_span("create", parent);
         self.op_create(req, parent, name, mode, umask, flags, reply);
     }
 
     fn statfs(&mut self, req: &Request<'_>, ino: u64, reply: ReplyStatfs) {
+        let _span = io_span("statfs", ino);
         self.op_statfs(req, ino, reply);
     }
 }
 
+/// `io_span` opens the trace span of one FUSE operation on `ino`.
+fn io_span(op: &'static str, ino: u64) -> EnteredSpan {
+    trace_span!(target: IO_TRACE_TARGET, "fuse_op", op, ino).entered()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
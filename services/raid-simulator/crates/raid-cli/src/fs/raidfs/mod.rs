@@ -1,14 +1,28 @@
 //! RAID-backed filesystem implementation for the FUSE layer.
 
+mod background;
+mod coalesce;
 mod core;
+mod file_io;
 mod filesystem;
 mod ops_attr;
 mod ops_create;
 mod ops_dir;
 mod ops_io;
+mod ops_raw;
+mod ops_snapshot;
 mod ops_sync;
+mod quota;
+mod throttle;
+mod tmpfile;
 mod types;
 
+pub use background::{GrowState, RebuildSpeed, Scrub, ScrubState};
+pub use coalesce::{DEFAULT_MAX_AGE, WriteBuffer, WriteBufferStats};
+pub use file_io::{FileIo, FileIoTable};
+pub use quota::{QuotaUsage, read_quotas};
+pub use throttle::{QosClass, QosLimits, Throttle};
+pub use tmpfile::{OpenFiles, reclaim_orphans};
 pub use types::{FsState, RaidFs};
 
 #[cfg(test)]
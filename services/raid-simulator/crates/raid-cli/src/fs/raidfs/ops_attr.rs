@@ -2,16 +2,35 @@ use std::time::SystemTime;
 
 use fuser::{ReplyAttr, ReplyEmpty, ReplyStatfs, ReplyXattr, Request, TimeOrNow};
 use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::volume::MAX_SNAPSHOTS;
 
-use crate::fs::constants::{CTL_INO, MAX_FILES, NAME_LEN, ROOT_ID, STATFS_BLOCK_SIZE, TTL};
+use crate::fs::constants::{
+    CTL_INO, DISK_FILE_BASE, DISKS_INO, MAX_FILES, NAME_LEN, RAW_INO, ROOT_ID, STATFS_BLOCK_SIZE,
+    TTL,
+};
 use crate::fs::persist::save_header_and_entry;
+use crate::metrics_runtime::SpaceUsage;
 
-use super::types::RaidFs;
+use super::ops_snapshot::SnapshotNode;
+use super::types::{FsState, RaidFs};
 
 enum InodeTarget {
     Root,
     Control,
+    Raw,
+    DisksDir,
+    Disk(usize),
     Entry(usize),
+    Snapshot(SnapshotNode),
+}
+
+/// `StatfsCounts` holds the block and inode counts reported by `statfs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatfsCounts {
+    pub blocks: u64,
+    pub bfree: u64,
+    pub files: u64,
+    pub ffree: u64,
 }
 
 impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
@@ -51,6 +70,16 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         match self.resolve_inode(ino) {
             Ok(InodeTarget::Root) => reply.attr(&TTL, &self.root_attr()),
             Ok(InodeTarget::Control) => reply.attr(&TTL, &self.ctl_attr()),
+            Ok(InodeTarget::Raw) => reply.attr(&TTL, &self.raw_attr()),
+            Ok(InodeTarget::DisksDir) => reply.attr(&TTL, &self.disks_dir_attr()),
+            Ok(InodeTarget::Disk(disk)) => match self.raw_disk_attr(disk) {
+                Ok(attr) => reply.attr(&TTL, &attr),
+                Err(code) => reply.error(code),
+            },
+            Ok(InodeTarget::Snapshot(node)) => match self.snapshot_attr(node) {
+                Ok(attr) => reply.attr(&TTL, &attr),
+                Err(code) => reply.error(code),
+            },
             Ok(InodeTarget::Entry(index)) => {
                 let Ok(state) = self.state.lock() else {
                     reply.error(libc::EIO);
@@ -89,6 +118,14 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
             reply.attr(&TTL, &self.ctl_attr());
             return;
         }
+        if self.is_raw(ino) {
+            reply.attr(&TTL, &self.raw_attr());
+            return;
+        }
+        if self.is_read_only(ino) {
+            reply.error(libc::EROFS);
+            return;
+        }
 
         let Some(index) = Self::index_for_inode(ino) else {
             reply.error(libc::ENOENT);
@@ -105,17 +142,26 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         };
         let entry_offset = entry.offset;
         let mut entry_size = entry.size;
+        let owner = entry.owner;
 
         if let Some(new_size) = size {
+            if let Err(err) = state.flush_file(index) {
+                reply.error(Self::errno_for(&err));
+                return;
+            }
             if new_size > entry_size {
                 let allocated = entry_size.max(1);
                 let is_last = entry_offset + allocated == header_next_free;
                 let new_allocated = new_size.max(1);
                 let new_end = entry_offset.saturating_add(new_allocated);
-                if !is_last || new_end > self.capacity {
+                if !is_last || new_end > state.data_end() {
                     reply.error(libc::ENOSPC);
                     return;
                 }
+                if let Err(code) = state.check_quota(owner, new_size - entry_size, 0) {
+                    reply.error(code);
+                    return;
+                }
                 state.header.next_free = new_end;
             }
             entry_size = new_size;
@@ -128,34 +174,81 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         reply.attr(&TTL, &self.entry_attr(index, entry_size));
     }
 
-    pub(crate) fn op_statfs(&self, _req: &Request<'_>, _ino: u64, reply: ReplyStatfs) {
+    pub(crate) fn op_statfs(&self, _req: &Request<'_>, ino: u64, reply: ReplyStatfs) {
         let Ok(state) = self.state.lock() else {
             reply.error(libc::EIO);
             return;
         };
 
-        let used_bytes = state.header.next_free.max(Self::data_start());
-        let available_bytes = self.capacity.saturating_sub(used_bytes);
-        let block_size = u64::from(STATFS_BLOCK_SIZE);
-        let blocks = self.capacity / block_size;
-        let bfree = available_bytes / block_size;
-        let bavail = bfree;
-        let files = MAX_FILES as u64;
-        let used_files = state.entries.iter().filter(|entry| entry.used).count() as u64;
-        let ffree = files.saturating_sub(used_files);
-
+        let counts = Self::statfs_counts(&state, ino);
+        drop(state);
         reply.statfs(
-            blocks,
-            bfree,
-            bavail,
-            files,
-            ffree,
+            counts.blocks,
+            counts.bfree,
+            counts.bfree,
+            counts.files,
+            counts.ffree,
             STATFS_BLOCK_SIZE,
             u32::try_from(NAME_LEN).unwrap_or(u32::MAX),
             STATFS_BLOCK_SIZE,
         );
     }
 
+    /// `statfs_counts` returns the `statfs` counts for the filesystem holding `ino`.
+    ///
+    /// Inside `.snapshots` the counts describe the copy-on-write pool: its blocks,
+    /// the blocks not yet holding preserved data, and the free snapshot slots.
+    /// Everywhere else they describe the live filesystem.
+    ///
+    /// # Arguments
+    /// * `state` - Filesystem state to inspect.
+    /// * `ino` - Inode the caller asked about.
+    pub(crate) fn statfs_counts(state: &FsState<D, N, T>, ino: u64) -> StatfsCounts {
+        let block_size = u64::from(STATFS_BLOCK_SIZE);
+        if Self::snapshot_node(ino).is_some()
+            && let Some(usage) = state.volume.snapshot_usage()
+        {
+            let files = MAX_SNAPSHOTS as u64;
+            return StatfsCounts {
+                blocks: usage.pool_bytes() / block_size,
+                bfree: usage.free_bytes() / block_size,
+                files,
+                ffree: files.saturating_sub(usage.snapshots),
+            };
+        }
+        let usage = Self::space_usage(state);
+        let files = MAX_FILES as u64;
+        StatfsCounts {
+            blocks: usage.capacity_bytes / block_size,
+            bfree: usage.free_bytes / block_size,
+            files,
+            ffree: files.saturating_sub(usage.file_count),
+        }
+    }
+
+    /// `space_usage` summarizes capacity, used and free bytes, and the file count.
+    ///
+    /// # Arguments
+    /// * `state` - Filesystem state to inspect.
+    pub(crate) fn space_usage(state: &FsState<D, N, T>) -> SpaceUsage {
+        let capacity = state.capacity();
+        let used_bytes = state.header.next_free.max(Self::data_start());
+        let mut free_bytes = state.data_end().saturating_sub(used_bytes);
+        if let Some(usage) = state.volume.thin_usage() {
+            free_bytes = free_bytes.min(usage.free_bytes());
+        }
+        let snapshots = state.volume.snapshot_usage();
+        SpaceUsage {
+            capacity_bytes: capacity,
+            used_bytes: used_bytes.min(capacity),
+            free_bytes,
+            file_count: state.entries.iter().filter(|entry| entry.used).count() as u64,
+            dirty_bytes: state.write_buffer.dirty_bytes(),
+            snapshot_used_bytes: snapshots.map_or(0, |usage| usage.used_bytes()),
+            snapshot_pool_bytes: snapshots.map_or(0, |usage| usage.pool_bytes()),
+        }
+    }
+
     fn resolve_inode(&self, ino: u64) -> Result<InodeTarget, i32> {
         if ino == ROOT_ID {
             return Ok(InodeTarget::Root);
@@ -163,6 +256,20 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         if ino == CTL_INO {
             return Ok(InodeTarget::Control);
         }
+        if self.is_raw(ino) {
+            return Ok(InodeTarget::Raw);
+        }
+        if self.raw_volume && ino == DISKS_INO {
+            return Ok(InodeTarget::DisksDir);
+        }
+        if let Some(disk) = self.raw_disk(ino) {
+            return Ok(InodeTarget::Disk(disk));
+        }
+        if let Some(node) = Self::snapshot_node(ino) {
+            return self
+                .snapshot_attr(node)
+                .map(|_| InodeTarget::Snapshot(node));
+        }
 
         let Some(index) = Self::index_for_inode(ino) else {
             return Err(libc::ENOENT);
@@ -180,7 +287,12 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     }
 
     fn is_inode_in_range(ino: u64) -> bool {
-        ino == ROOT_ID || ino == CTL_INO || Self::index_for_inode(ino).is_some()
+        ino == ROOT_ID
+            || ino == CTL_INO
+            || ino == RAW_INO
+            || (DISKS_INO..DISK_FILE_BASE + D as u64).contains(&ino)
+            || Self::index_for_inode(ino).is_some()
+            || Self::snapshot_node(ino).is_some()
     }
 }
 
@@ -214,6 +326,28 @@ mod tests {
         assert!(fs.resolve_inode(999_999).is_err());
     }
 
+    #[test]
+    fn space_usage_counts_files_and_free_bytes() {
+        let fs = create_test_fs();
+        let data_start = RaidFs::<1, { DEFAULT_CHUNK_SIZE }, TestStripe>::data_start();
+        {
+            let mut state = fs.state.lock().expect("state lock");
+            state.entries[0].used = true;
+            state.entries[2].used = true;
+            state.header.next_free = data_start + 100;
+        }
+
+        let state = fs.state.lock().expect("state lock");
+        let capacity = state.capacity();
+        let usage = RaidFs::<1, { DEFAULT_CHUNK_SIZE }, TestStripe>::space_usage(&state);
+        drop(state);
+
+        assert_eq!(usage.capacity_bytes, capacity);
+        assert_eq!(usage.used_bytes, data_start + 100);
+        assert_eq!(usage.free_bytes, capacity - data_start - 100);
+        assert_eq!(usage.file_count, 2);
+    }
+
     #[test]
     fn resolve_inode_rejects_unused_entries() {
         let fs = create_test_fs();
@@ -3,11 +3,15 @@ use std::ffi::OsStr;
 use fuser::{ReplyCreate, ReplyEmpty, ReplyEntry, Request};
 use raid_rs::layout::stripe::traits::stripe::Stripe;
 
-use crate::fs::constants::{CTL_INO, CTL_NAME, NAME_LEN, OPEN_DIRECT_IO, ROOT_ID, TTL};
+use crate::fs::align::Alignment;
+use crate::fs::constants::{
+    CTL_INO, CTL_NAME, DISKS_DIR_NAME, NAME_LEN, OPEN_DIRECT_IO, RAW_NAME, ROOT_ID, SNAP_DIR_NAME,
+    TTL,
+};
 use crate::fs::metadata::Entry;
 use crate::fs::persist::save_header_and_entry;
 
-use super::types::RaidFs;
+use super::types::{FsState, RaidFs};
 
 enum CreateTarget {
     Control,
@@ -18,15 +22,21 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn op_create(
         &self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &OsStr,
         _mode: u32,
         _umask: u32,
-        _flags: i32,
+        flags: i32,
         reply: ReplyCreate,
     ) {
-        match self.create_target(parent, name) {
+        let target = if flags & libc::O_TMPFILE == libc::O_TMPFILE {
+            self.create_tmpfile(parent, req.uid())
+                .map(CreateTarget::Entry)
+        } else {
+            self.create_target(parent, name, req.uid())
+        };
+        match target {
             Ok(CreateTarget::Control) => {
                 let attr = self.ctl_attr();
                 reply.created(&TTL, &attr, 0, CTL_INO, OPEN_DIRECT_IO);
@@ -42,7 +52,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn op_mknod(
         &self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         parent: u64,
         name: &OsStr,
         _mode: u32,
@@ -50,7 +60,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         _rdev: u32,
         reply: ReplyEntry,
     ) {
-        match self.create_regular_entry(parent, name) {
+        match self.create_regular_entry(parent, name, req.uid()) {
             Ok(index) => {
                 let attr = self.entry_attr(index, 0);
                 reply.entry(&TTL, &attr, 0);
@@ -72,7 +82,24 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         }
     }
 
-    fn create_target(&self, parent: u64, name: &OsStr) -> Result<CreateTarget, i32> {
+    pub(crate) fn op_link(
+        &self,
+        _req: &Request<'_>,
+        ino: u64,
+        newparent: u64,
+        newname: &OsStr,
+        reply: ReplyEntry,
+    ) {
+        match self.link_entry(ino, newparent, newname) {
+            Ok((index, size)) => reply.entry(&TTL, &self.entry_attr(index, size), 0),
+            Err(code) => reply.error(code),
+        }
+    }
+
+    fn create_target(&self, parent: u64, name: &OsStr, uid: u32) -> Result<CreateTarget, i32> {
+        if self.is_read_only(parent) {
+            return Err(libc::EROFS);
+        }
         if parent != ROOT_ID || !Self::is_valid_name(name) {
             return Err(libc::EINVAL);
         }
@@ -81,20 +108,42 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
             return Ok(CreateTarget::Control);
         }
 
-        let index = self.create_regular_entry(parent, name)?;
+        let index = self.create_regular_entry(parent, name, uid)?;
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        state.open_files.open(index);
         Ok(CreateTarget::Entry(index))
     }
 
-    fn create_regular_entry(&self, parent: u64, name: &OsStr) -> Result<usize, i32> {
+    /// `new_name` checks that `name` may be given to a file in `parent`.
+    fn new_name(&self, parent: u64, name: &OsStr) -> Result<String, i32> {
+        if self.is_read_only(parent) {
+            return Err(libc::EROFS);
+        }
         if parent != ROOT_ID || !Self::is_valid_name(name) {
             return Err(libc::EINVAL);
         }
+        let raw_name = name == OsStr::new(RAW_NAME) || name == OsStr::new(DISKS_DIR_NAME);
+        let reserved = name == OsStr::new(CTL_NAME) || name == OsStr::new(SNAP_DIR_NAME);
+        if reserved || (self.raw_volume && raw_name) {
+            return Err(libc::EEXIST);
+        }
 
         let name_str = name.to_string_lossy().into_owned();
         if name_str.len() > NAME_LEN {
             return Err(libc::ENAMETOOLONG);
         }
+        Ok(name_str)
+    }
 
+    pub(crate) fn create_regular_entry(
+        &self,
+        parent: u64,
+        name: &OsStr,
+        uid: u32,
+    ) -> Result<usize, i32> {
+        let name_str = self.new_name(parent, name)?;
         let Ok(mut state) = self.state.lock() else {
             return Err(libc::EIO);
         };
@@ -106,31 +155,99 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         {
             return Err(libc::EEXIST);
         }
+        Self::allocate_entry(&mut state, name_str, uid)
+    }
+
+    /// `create_tmpfile` creates an unnamed file in `parent` with one open handle,
+    /// as `open` with `O_TMPFILE` does.
+    ///
+    /// The file is listed nowhere. `link_entry` puts it into place; otherwise it is
+    /// freed with its last handle, or at the next mount after a crash.
+    pub(crate) fn create_tmpfile(&self, parent: u64, uid: u32) -> Result<usize, i32> {
+        if self.is_read_only(parent) {
+            return Err(libc::EROFS);
+        }
+        if parent != ROOT_ID {
+            return Err(libc::EINVAL);
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        let index = Self::allocate_entry(&mut state, String::new(), uid)?;
+        state.open_files.open(index);
+        Ok(index)
+    }
 
+    /// `allocate_entry` stores a new empty file named `name` in a free table entry.
+    fn allocate_entry(state: &mut FsState<D, N, T>, name: String, uid: u32) -> Result<usize, i32> {
         let Some(index) = state.entries.iter().position(|entry| !entry.used) else {
             return Err(libc::ENOSPC);
         };
+        state.check_quota(uid, 0, 1)?;
 
-        let offset = state.header.next_free;
+        let offset = Alignment::new(&state.volume.geometry()).align_up(state.header.next_free);
         let new_end = offset.saturating_add(1);
-        if new_end > self.capacity {
+        if new_end > state.data_end() {
             return Err(libc::ENOSPC);
         }
 
         let entry = Entry {
-            name: name_str,
+            name,
             offset,
             size: 0,
             used: true,
+            owner: uid,
         };
         state.entries[index] = entry;
         state.header.next_free = new_end;
-        save_header_and_entry(&mut state, index);
+        save_header_and_entry(state, index);
 
         Ok(index)
     }
 
-    fn unlink_entry(&self, parent: u64, name: &OsStr) -> Result<(), i32> {
+    /// `link_entry` names an unnamed file, created with `O_TMPFILE` or unlinked while
+    /// open, putting it into place.
+    ///
+    /// Every file has a single name in the flat table, so named files cannot be linked.
+    ///
+    /// # Returns
+    /// The table index and size of the file.
+    pub(crate) fn link_entry(
+        &self,
+        ino: u64,
+        parent: u64,
+        name: &OsStr,
+    ) -> Result<(usize, u64), i32> {
+        let name_str = self.new_name(parent, name)?;
+        let Some(index) = Self::index_for_inode(ino) else {
+            return Err(libc::EPERM);
+        };
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        if state
+            .entries
+            .iter()
+            .any(|entry| entry.used && entry.name == name_str)
+        {
+            return Err(libc::EEXIST);
+        }
+        let Some(entry) = state.entries.get_mut(index).filter(|entry| entry.used) else {
+            return Err(libc::ENOENT);
+        };
+        if !entry.is_orphan() {
+            return Err(libc::EMLINK);
+        }
+        entry.name = name_str;
+        let size = entry.size;
+        save_header_and_entry(&mut state, index);
+        Ok((index, size))
+    }
+
+    pub(crate) fn unlink_entry(&self, parent: u64, name: &OsStr) -> Result<(), i32> {
+        if self.is_read_only(parent) {
+            return Err(libc::EROFS);
+        }
         if parent != ROOT_ID {
             return Err(libc::ENOENT);
         }
@@ -145,8 +262,11 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
             .enumerate()
             .find(|(_, entry)| entry.used && entry.name == name.to_string_lossy())
         {
-            state.entries[index] = Entry::empty();
-            save_header_and_entry(&mut state, index);
+            if state.unlink_file(index)
+                && let Ok(mut file_io) = self.file_io.lock()
+            {
+                file_io.forget(Self::inode_for(index));
+            }
             Ok(())
         } else {
             Err(libc::ENOENT)
@@ -157,13 +277,13 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::fs::test_utils::create_test_fs;
+    use crate::fs::test_utils::{TestFs, create_test_fs};
 
     #[test]
     fn create_target_handles_control_name() {
         let fs = create_test_fs();
         let target = fs
-            .create_target(ROOT_ID, OsStr::new(CTL_NAME))
+            .create_target(ROOT_ID, OsStr::new(CTL_NAME), 0)
             .expect("control target");
         assert!(matches!(target, CreateTarget::Control));
     }
@@ -172,18 +292,33 @@ mod tests {
     fn create_regular_entry_creates_entry() {
         let fs = create_test_fs();
         let index = fs
-            .create_regular_entry(ROOT_ID, OsStr::new("file.txt"))
+            .create_regular_entry(ROOT_ID, OsStr::new("file.txt"), 0)
             .expect("create entry");
         let state = fs.state.lock().expect("lock state");
         assert!(state.entries[index].used);
         drop(state);
     }
 
+    #[test]
+    fn create_regular_entry_starts_on_a_stripe_boundary() {
+        let fs = create_test_fs();
+        let data_start = TestFs::data_start();
+        fs.state.lock().expect("lock state").header.next_free = data_start + 1;
+        let index = fs
+            .create_regular_entry(ROOT_ID, OsStr::new("aligned"), 0)
+            .expect("create entry");
+        let state = fs.state.lock().expect("lock state");
+        let stripe = state.volume.geometry().bytes_per_stripe as u64;
+        assert_eq!(state.entries[index].offset, data_start + stripe);
+        assert_eq!(state.header.next_free, data_start + stripe + 1);
+        drop(state);
+    }
+
     #[test]
     fn unlink_entry_removes_existing_entry() {
         let fs = create_test_fs();
         let index = fs
-            .create_regular_entry(ROOT_ID, OsStr::new("deleteme"))
+            .create_regular_entry(ROOT_ID, OsStr::new("deleteme"), 0)
             .expect("create entry");
         assert!(fs.unlink_entry(ROOT_ID, OsStr::new("deleteme")).is_ok());
         let state = fs.state.lock().expect("lock state");
@@ -191,11 +326,34 @@ mod tests {
         drop(state);
     }
 
+    #[test]
+    fn read_only_mount_refuses_creates_and_unlinks() {
+        let mut fs = create_test_fs();
+        fs.create_regular_entry(ROOT_ID, OsStr::new("kept"), 0)
+            .expect("create entry");
+        fs.read_only = true;
+
+        assert_eq!(
+            fs.create_regular_entry(ROOT_ID, OsStr::new("new"), 0)
+                .expect_err("read-only"),
+            libc::EROFS
+        );
+        assert!(matches!(
+            fs.create_target(ROOT_ID, OsStr::new(CTL_NAME), 0),
+            Err(libc::EROFS)
+        ));
+        assert_eq!(
+            fs.unlink_entry(ROOT_ID, OsStr::new("kept")),
+            Err(libc::EROFS)
+        );
+        assert!(fs.is_read_only(TestFs::inode_for(0)));
+    }
+
     #[test]
     fn create_regular_entry_rejects_invalid_parent() {
         let fs = create_test_fs();
         let err = fs
-            .create_regular_entry(999, OsStr::new("file.txt"))
+            .create_regular_entry(999, OsStr::new("file.txt"), 0)
             .expect_err("expected error");
         assert_eq!(err, libc::EINVAL);
     }
@@ -205,7 +363,7 @@ mod tests {
         let fs = create_test_fs();
         let long_name = "a".repeat(NAME_LEN + 1);
         let err = fs
-            .create_regular_entry(ROOT_ID, OsStr::new(&long_name))
+            .create_regular_entry(ROOT_ID, OsStr::new(&long_name), 0)
             .expect_err("expected error");
         assert_eq!(err, libc::ENAMETOOLONG);
     }
@@ -214,10 +372,10 @@ mod tests {
     fn create_regular_entry_rejects_duplicates() {
         let fs = create_test_fs();
         let _ = fs
-            .create_regular_entry(ROOT_ID, OsStr::new("dupe"))
+            .create_regular_entry(ROOT_ID, OsStr::new("dupe"), 0)
             .expect("create entry");
         let err = fs
-            .create_regular_entry(ROOT_ID, OsStr::new("dupe"))
+            .create_regular_entry(ROOT_ID, OsStr::new("dupe"), 0)
             .expect_err("expected error");
         assert_eq!(err, libc::EEXIST);
     }
@@ -232,11 +390,104 @@ mod tests {
             }
         }
         let err = fs
-            .create_regular_entry(ROOT_ID, OsStr::new("full"))
+            .create_regular_entry(ROOT_ID, OsStr::new("full"), 0)
             .expect_err("expected error");
         assert_eq!(err, libc::ENOSPC);
     }
 
+    #[test]
+    fn create_regular_entry_charges_the_file_quota() {
+        let fs = create_test_fs();
+        assert!(
+            fs.state
+                .lock()
+                .expect("lock state")
+                .apply_quota_command("1000 files 1")
+        );
+        let index = fs
+            .create_regular_entry(ROOT_ID, OsStr::new("mine"), 1000)
+            .expect("within quota");
+        assert_eq!(
+            fs.state.lock().expect("lock state").entries[index].owner,
+            1000
+        );
+
+        let err = fs
+            .create_regular_entry(ROOT_ID, OsStr::new("more"), 1000)
+            .expect_err("over quota");
+        assert_eq!(err, libc::EDQUOT);
+        fs.create_regular_entry(ROOT_ID, OsStr::new("theirs"), 1001)
+            .expect("other users are unlimited");
+    }
+
+    #[test]
+    fn unlinked_open_file_can_be_linked_into_place() {
+        let fs = create_test_fs();
+        let Ok(CreateTarget::Entry(index)) = fs.create_target(ROOT_ID, OsStr::new(".tmp"), 0)
+        else {
+            panic!("expected a file entry");
+        };
+        let ino = TestFs::inode_for(index);
+        assert_eq!(
+            fs.link_entry(ino, ROOT_ID, OsStr::new("final")),
+            Err(libc::EMLINK)
+        );
+
+        fs.unlink_entry(ROOT_ID, OsStr::new(".tmp"))
+            .expect("unlink open file");
+        assert!(fs.state.lock().expect("lock state").entries[index].is_orphan());
+
+        assert_eq!(
+            fs.link_entry(ino, ROOT_ID, OsStr::new(CTL_NAME)),
+            Err(libc::EEXIST)
+        );
+        assert_eq!(
+            fs.link_entry(ino, ROOT_ID, OsStr::new("final")),
+            Ok((index, 0))
+        );
+        fs.release_handle(ino);
+        let state = fs.state.lock().expect("lock state");
+        assert!(state.entries[index].used);
+        assert_eq!(state.entries[index].name, "final");
+        drop(state);
+    }
+
+    #[test]
+    fn tmpfile_is_written_then_linked_into_place() {
+        let fs = create_test_fs();
+        let index = fs.create_tmpfile(ROOT_ID, 0).expect("create tmpfile");
+        let ino = TestFs::inode_for(index);
+        let mut state = fs.state.lock().expect("lock state");
+        assert!(state.entries[index].is_orphan());
+        TestFs::write_file(&mut state, index, 0, b"finished").expect("write tmpfile");
+        drop(state);
+
+        assert_eq!(
+            fs.link_entry(ino, ROOT_ID, OsStr::new("final")),
+            Ok((index, 8))
+        );
+        fs.release_handle(ino);
+        let mut state = fs.state.lock().expect("lock state");
+        assert_eq!(state.entries[index].name, "final");
+        let offset = state.entries[index].offset;
+        let mut out = [0u8; 8];
+        state.volume.read_bytes(offset, &mut out);
+        assert_eq!(&out, b"finished");
+        drop(state);
+    }
+
+    #[test]
+    fn tmpfile_is_freed_with_its_last_handle() {
+        let fs = create_test_fs();
+        let index = fs.create_tmpfile(ROOT_ID, 0).expect("create tmpfile");
+        fs.release_handle(TestFs::inode_for(index));
+        assert!(!fs.state.lock().expect("lock state").entries[index].used);
+
+        let mut fs = create_test_fs();
+        fs.read_only = true;
+        assert_eq!(fs.create_tmpfile(ROOT_ID, 0), Err(libc::EROFS));
+    }
+
     #[test]
     fn unlink_entry_returns_not_found() {
         let fs = create_test_fs();
@@ -3,13 +3,21 @@ use std::ffi::OsStr;
 use fuser::{FileType, ReplyDirectory, ReplyEntry, Request};
 use raid_rs::layout::stripe::traits::stripe::Stripe;
 
-use crate::fs::constants::{CTL_INO, CTL_NAME, ROOT_ID, TTL};
+use crate::fs::constants::{
+    CTL_INO, CTL_NAME, DISKS_DIR_NAME, DISKS_INO, RAW_INO, RAW_NAME, ROOT_ID, SNAP_DIR_NAME,
+    SNAP_ROOT_INO, TTL,
+};
 
+use super::ops_snapshot::SnapshotNode;
 use super::types::RaidFs;
 
 enum LookupTarget {
     Control,
+    Raw,
+    DisksDir,
+    Disk(usize),
     Entry(usize),
+    Snapshot(SnapshotNode),
 }
 
 impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
@@ -22,6 +30,16 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     ) {
         match self.lookup_target(parent, name) {
             Ok(LookupTarget::Control) => reply.entry(&TTL, &self.ctl_attr(), 0),
+            Ok(LookupTarget::Raw) => reply.entry(&TTL, &self.raw_attr(), 0),
+            Ok(LookupTarget::DisksDir) => reply.entry(&TTL, &self.disks_dir_attr(), 0),
+            Ok(LookupTarget::Disk(disk)) => match self.raw_disk_attr(disk) {
+                Ok(attr) => reply.entry(&TTL, &attr, 0),
+                Err(code) => reply.error(code),
+            },
+            Ok(LookupTarget::Snapshot(node)) => match self.snapshot_attr(node) {
+                Ok(attr) => reply.entry(&TTL, &attr, 0),
+                Err(code) => reply.error(code),
+            },
             Ok(LookupTarget::Entry(index)) => {
                 let Ok(state) = self.state.lock() else {
                     reply.error(libc::EIO);
@@ -57,6 +75,16 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     }
 
     fn lookup_target(&self, parent: u64, name: &OsStr) -> Result<LookupTarget, i32> {
+        if let Some(node) = Self::snapshot_node(parent) {
+            return self
+                .snapshot_lookup(node, &name.to_string_lossy())
+                .map(LookupTarget::Snapshot);
+        }
+        if self.raw_volume && parent == DISKS_INO {
+            return Self::raw_disk_lookup(&name.to_string_lossy())
+                .map(LookupTarget::Disk)
+                .ok_or(libc::ENOENT);
+        }
         if parent != ROOT_ID {
             return Err(libc::ENOENT);
         }
@@ -64,11 +92,21 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         if name == OsStr::new(CTL_NAME) {
             return Ok(LookupTarget::Control);
         }
+        if self.raw_volume && name == OsStr::new(RAW_NAME) {
+            return Ok(LookupTarget::Raw);
+        }
+        if self.raw_volume && name == OsStr::new(DISKS_DIR_NAME) {
+            return Ok(LookupTarget::DisksDir);
+        }
 
         let Ok(state) = self.state.lock() else {
             return Err(libc::EIO);
         };
 
+        if name == OsStr::new(SNAP_DIR_NAME) && state.volume.snapshots_enabled() {
+            return Ok(LookupTarget::Snapshot(SnapshotNode::Root));
+        }
+
         if let Some((index, _)) = state
             .entries
             .iter()
@@ -82,6 +120,12 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     }
 
     fn list_dir_entries(&self, ino: u64) -> Result<Vec<(u64, FileType, String)>, i32> {
+        if let Some(node) = Self::snapshot_node(ino) {
+            return self.snapshot_list(node);
+        }
+        if self.raw_volume && ino == DISKS_INO {
+            return Ok(Self::raw_disk_list());
+        }
         if ino != ROOT_ID {
             return Err(libc::ENOENT);
         }
@@ -94,8 +138,19 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         entries.push((ROOT_ID, FileType::Directory, ".".to_string()));
         entries.push((ROOT_ID, FileType::Directory, "..".to_string()));
         entries.push((CTL_INO, FileType::RegularFile, CTL_NAME.to_string()));
+        if self.raw_volume {
+            entries.push((RAW_INO, FileType::RegularFile, RAW_NAME.to_string()));
+            entries.push((DISKS_INO, FileType::Directory, DISKS_DIR_NAME.to_string()));
+        }
+        if state.volume.snapshots_enabled() {
+            entries.push((
+                SNAP_ROOT_INO,
+                FileType::Directory,
+                SNAP_DIR_NAME.to_string(),
+            ));
+        }
         for (index, entry) in state.entries.iter().enumerate() {
-            if entry.used {
+            if entry.used && !entry.is_orphan() {
                 entries.push((
                     Self::inode_for(index),
                     FileType::RegularFile,
@@ -146,6 +201,53 @@ mod tests {
         assert!(entries.iter().any(|entry| entry.2 == "data.bin"));
     }
 
+    #[test]
+    fn snapshot_dir_appears_once_snapshots_are_enabled() {
+        let fs = create_test_fs();
+        assert!(
+            fs.lookup_target(ROOT_ID, OsStr::new(SNAP_DIR_NAME))
+                .is_err()
+        );
+
+        fs.state
+            .lock()
+            .expect("lock state")
+            .volume
+            .init_snapshots(5000, 0)
+            .expect("init snapshots");
+
+        assert!(matches!(
+            fs.lookup_target(ROOT_ID, OsStr::new(SNAP_DIR_NAME)),
+            Ok(LookupTarget::Snapshot(SnapshotNode::Root))
+        ));
+        let entries = fs.list_dir_entries(ROOT_ID).expect("entries");
+        assert!(entries.iter().any(|entry| entry.2 == SNAP_DIR_NAME));
+    }
+
+    #[test]
+    fn raw_files_are_listed_only_when_enabled() {
+        let mut fs = create_test_fs();
+        assert!(fs.lookup_target(ROOT_ID, OsStr::new(RAW_NAME)).is_err());
+        let entries = fs.list_dir_entries(ROOT_ID).expect("entries");
+        assert!(!entries.iter().any(|entry| entry.0 == RAW_INO));
+
+        fs.raw_volume = true;
+        assert!(matches!(
+            fs.lookup_target(ROOT_ID, OsStr::new(RAW_NAME)),
+            Ok(LookupTarget::Raw)
+        ));
+        let entries = fs.list_dir_entries(ROOT_ID).expect("entries");
+        assert!(entries.iter().any(|entry| entry.2 == RAW_NAME));
+        assert!(entries.iter().any(|entry| entry.2 == DISKS_DIR_NAME));
+        let capacity = fs.state.lock().expect("lock state").capacity();
+        assert_eq!(fs.raw_attr().size, capacity);
+        assert!(matches!(
+            fs.lookup_target(DISKS_INO, OsStr::new("disk-0.raw")),
+            Ok(LookupTarget::Disk(0))
+        ));
+        assert_eq!(fs.list_dir_entries(DISKS_INO).expect("disks").len(), 3);
+    }
+
     #[test]
     fn list_dir_entries_rejects_non_root() {
         let fs = create_test_fs();
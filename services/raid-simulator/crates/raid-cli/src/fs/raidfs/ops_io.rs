@@ -1,48 +1,93 @@
 use fuser::{ReplyData, ReplyOpen, ReplyWrite, Request};
 use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::IoError;
 use raid_rs::retention::volume::Volume;
-use std::time::Instant;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::fs::constants::{CTL_INO, OPEN_DIRECT_IO};
+use crate::fs::constants::{CTL_INO, OPEN_DIRECT_IO, RAW_INO};
+use crate::fs::metadata::Entry;
 use crate::fs::persist::save_header_and_entry;
 use crate::metrics_runtime::{FuseOp, FuseOpType};
 
-use super::types::RaidFs;
+use super::background::RebuildSpeed;
+use super::file_io::name_hash;
+use super::ops_snapshot::SnapshotNode;
+use super::throttle::QosClass;
+use super::types::{FsState, RaidFs};
+
+/// Number of files listed under `hot files` in the control file.
+const HOT_FILES: usize = 5;
 
 impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
-    pub(crate) fn op_open(&self, _req: &Request<'_>, ino: u64, _flags: i32, reply: ReplyOpen) {
+    pub(crate) fn op_open(&self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
         let start = Instant::now();
         let mut error = false;
+        if self.read_only && flags & libc::O_ACCMODE != libc::O_RDONLY {
+            reply.error(libc::EROFS);
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, true);
+            return;
+        }
         if ino == CTL_INO {
             reply.opened(CTL_INO, OPEN_DIRECT_IO);
-            self.record_fuse_op(FuseOpType::Open, 0, start, error);
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
+            return;
+        }
+        if self.is_raw(ino) {
+            reply.opened(RAW_INO, OPEN_DIRECT_IO);
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
+            return;
+        }
+        if self.raw_disk(ino).is_some() {
+            if flags & libc::O_ACCMODE == libc::O_RDONLY {
+                reply.opened(ino, OPEN_DIRECT_IO);
+            } else {
+                reply.error(libc::EROFS);
+                error = true;
+            }
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
+            return;
+        }
+        if let Some(node) = Self::snapshot_node(ino) {
+            if flags & libc::O_ACCMODE != libc::O_RDONLY {
+                reply.error(libc::EROFS);
+                error = true;
+            } else if self.snapshot_attr(node).is_ok() {
+                reply.opened(ino, OPEN_DIRECT_IO);
+            } else {
+                reply.error(libc::ENOENT);
+                error = true;
+            }
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
             return;
         }
         let Some(index) = Self::index_for_inode(ino) else {
             reply.error(libc::ENOENT);
             error = true;
-            self.record_fuse_op(FuseOpType::Open, 0, start, error);
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
             return;
         };
-        let Ok(state) = self.state.lock() else {
+        let Ok(mut state) = self.state.lock() else {
             reply.error(libc::EIO);
             error = true;
-            self.record_fuse_op(FuseOpType::Open, 0, start, error);
+            self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
             return;
         };
         if state.entries.get(index).is_some_and(|entry| entry.used) {
+            state.open_files.open(index);
             reply.opened(ino, OPEN_DIRECT_IO);
         } else {
             reply.error(libc::ENOENT);
             error = true;
         }
-        self.record_fuse_op(FuseOpType::Open, 0, start, error);
+        self.record_fuse_op(FuseOpType::Open, ino, 0, start, error);
     }
 
-    #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments, clippy::too_many_lines)]
     pub(crate) fn op_read(
         &self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         ino: u64,
         _fh: u64,
         offset: i64,
@@ -58,17 +103,43 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
             let Ok(state) = self.state.lock() else {
                 reply.error(libc::EIO);
                 error = true;
-                self.record_fuse_op(FuseOpType::Read, 0, start, error);
+                self.record_fuse_op(FuseOpType::Read, ino, 0, start, error);
                 return;
             };
             let mut txt = String::new();
             txt.push_str("raidctl commands:\n");
             txt.push_str("  <n>           - fail disk n (hot-remove)\n");
             txt.push_str("  swap <n>      - fail + replace + rebuild disk n\n");
-            txt.push_str("  replace <n>   - replace + rebuild disk n\n");
-            txt.push_str("  rebuild <n>   - rebuild disk n\n\n");
-            txt.push_str("disk status:\n");
+            txt.push_str("  replace <n> [p] - replace + rebuild disk n, at path p if given\n");
+            txt.push_str("  readd <n>     - reattach failed disk n + resync dirty regions\n");
+            txt.push_str("  split <n>     - split mirror n off as a standalone image\n");
+            txt.push_str("  re-add <n>    - re-add split mirror n + resync dirty regions\n");
+            txt.push_str("  rebuild <n>   - rebuild disk n\n");
+            txt.push_str("  snapshot <s>  - create snapshot s (see .snapshots)\n");
+            txt.push_str("  metrics <sink> on|off - resume or pause a metrics sink\n");
+            txt.push_str("  qos <read|write|all> <iops|mbps> <n|off> - set a rate limit\n");
+            txt.push_str(
+                "  priority <percent|off> - cap rebuild and scrub IO while files are in use\n",
+            );
+            txt.push_str("  rebuild-speed <min|low|normal|max> - preset the priority cap\n");
+            txt.push_str("  scrub <start|stop|status> - check and repair every stripe\n");
+            txt.push_str("  grow <n>      - restripe onto n members while mounted\n");
+            txt.push_str("  add-disk <p>  - hot-add image p as the next member and grow onto it\n");
+            txt.push_str(
+                "  quota <uid> <bytes|files> <n|off> - limit what a user's files take up\n\n",
+            );
+            txt.push_str("array status:\n");
             txt.push_str(&state.volume.disk_status_string());
+            txt.push_str(&Self::sink_status_string());
+            if let Ok(throttle) = self.throttle.lock() {
+                txt.push_str(&throttle.status_string());
+            }
+            txt.push_str(&Self::priority_status_string(&state.volume));
+            txt.push_str(&state.scrub.status_string());
+            txt.push_str(&state.grow_status_string());
+            txt.push_str(&state.quota_status_string());
+            txt.push_str(&state.write_buffer.status_string());
+            txt.push_str(&self.hot_files_string(&state.entries));
 
             let bytes = txt.as_bytes();
             let off = usize::try_from(offset.max(0)).unwrap_or(0);
@@ -79,14 +150,58 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
                 reply.data(&bytes[off..end]);
                 bytes_sent = u64::try_from(end.saturating_sub(off)).unwrap_or(0);
             }
-            self.record_fuse_op(FuseOpType::Read, bytes_sent, start, error);
+            self.record_fuse_op(FuseOpType::Read, ino, bytes_sent, start, error);
+            return;
+        }
+
+        self.throttle_io(QosClass::Read, req.uid(), u64::from(size));
+
+        let raw = self.raw_disk(ino);
+        if self.is_raw(ino) || raw.is_some() {
+            let offset = u64::try_from(offset.max(0)).unwrap_or(0);
+            let read = raw.map_or_else(
+                || self.raw_read(offset, size),
+                |disk| self.raw_disk_read(disk, offset, size),
+            );
+            match read {
+                Ok(buf) => {
+                    reply.data(&buf);
+                    bytes_sent = buf.len() as u64;
+                }
+                Err(code) => {
+                    reply.error(code);
+                    error = true;
+                }
+            }
+            self.record_fuse_op(FuseOpType::Read, ino, bytes_sent, start, error);
+            return;
+        }
+
+        if let Some(node) = Self::snapshot_node(ino) {
+            let SnapshotNode::File(snap, index) = node else {
+                reply.error(libc::EISDIR);
+                self.record_fuse_op(FuseOpType::Read, ino, 0, start, true);
+                return;
+            };
+            let offset = u64::try_from(offset.max(0)).unwrap_or(0);
+            match self.snapshot_read(snap, index, offset, size) {
+                Ok(buf) => {
+                    reply.data(&buf);
+                    bytes_sent = u64::try_from(buf.len()).unwrap_or(0);
+                }
+                Err(code) => {
+                    reply.error(code);
+                    error = true;
+                }
+            }
+            self.record_fuse_op(FuseOpType::Read, ino, bytes_sent, start, error);
             return;
         }
 
         let Some(index) = Self::index_for_inode(ino) else {
             reply.error(libc::ENOENT);
             error = true;
-            self.record_fuse_op(FuseOpType::Read, 0, start, error);
+            self.record_fuse_op(FuseOpType::Read, ino, 0, start, error);
             return;
         };
 
@@ -94,37 +209,53 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         let Ok(mut state) = self.state.lock() else {
             reply.error(libc::EIO);
             error = true;
-            self.record_fuse_op(FuseOpType::Read, 0, start, error);
+            self.record_fuse_op(FuseOpType::Read, ino, 0, start, error);
             return;
         };
         let Some(entry) = state.entries.get(index).filter(|entry| entry.used) else {
             reply.error(libc::ENOENT);
             error = true;
-            self.record_fuse_op(FuseOpType::Read, 0, start, error);
+            self.record_fuse_op(FuseOpType::Read, ino, 0, start, error);
             return;
         };
 
         let (file_offset, file_size) = (entry.offset, entry.size);
+        let file_hash = name_hash(&entry.name);
+        if let Err(err) = state.flush_file(index) {
+            reply.error(Self::errno_for(&err));
+            self.record_fuse_op(FuseOpType::Read, ino, 0, start, true);
+            return;
+        }
         if offset >= file_size {
             reply.data(&[]);
-            self.record_fuse_op(FuseOpType::Read, 0, start, error);
+            self.record_fuse_op(FuseOpType::Read, ino, 0, start, error);
             return;
         }
 
         let available = file_size - offset;
         let to_read = usize::try_from(u64::from(size).min(available)).unwrap_or(0);
-        let mut buf = vec![0u8; to_read];
         let abs_offset = file_offset + offset;
-        state.volume.read_bytes(abs_offset, &mut buf);
-        reply.data(&buf);
-        bytes_sent = u64::try_from(buf.len()).unwrap_or(0);
-        self.record_fuse_op(FuseOpType::Read, bytes_sent, start, error);
+        let mut reply = Some(reply);
+        let read = state.volume.try_read_with(abs_offset, to_read, |data| {
+            if let Some(reply) = reply.take() {
+                reply.data(data);
+            }
+        });
+        if let Err(err) = read {
+            if let Some(reply) = reply.take() {
+                reply.error(Self::errno_for(&err));
+            }
+            self.record_fuse_op(FuseOpType::Read, ino, 0, start, true);
+            return;
+        }
+        bytes_sent = u64::try_from(to_read).unwrap_or(0);
+        self.record_file_io(FuseOpType::Read, ino, file_hash, bytes_sent, start);
     }
 
     #[allow(clippy::too_many_arguments, clippy::too_many_lines)]
     pub(crate) fn op_write(
         &self,
-        _req: &Request<'_>,
+        req: &Request<'_>,
         ino: u64,
         _fh: u64,
         offset: i64,
@@ -136,13 +267,18 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     ) {
         let start = Instant::now();
         let mut error = false;
+        if ino == CTL_INO && self.read_only {
+            reply.error(libc::EROFS);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, true);
+            return;
+        }
         if ino == CTL_INO {
             let cmd = std::str::from_utf8(data).unwrap_or("").trim();
 
             let Ok(mut state) = self.state.lock() else {
                 reply.error(libc::EIO);
                 error = true;
-                self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                 return;
             };
 
@@ -152,13 +288,13 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
                 if state.volume.fail_disk(i).is_err() {
                     reply.error(libc::EINVAL);
                     error = true;
-                    self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                     return;
                 }
                 let write_len = Self::write_len(data.len());
                 let bytes_written = u64::from(write_len);
                 reply.written(write_len);
-                self.record_fuse_op(FuseOpType::Write, bytes_written, start, error);
+                self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
                 self.record_disk_and_raid_states(&state.volume, 0.0);
                 return;
             }
@@ -170,43 +306,119 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
                     if state.volume.replace_disk(i).is_err() {
                         reply.error(libc::EINVAL);
                         error = true;
-                        self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                         return;
                     }
                     if state.volume.rebuild_disk_upto(i, end).is_err() {
                         reply.error(libc::EIO);
                         error = true;
-                        self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                         return;
                     }
                     let write_len = Self::write_len(data.len());
                     let bytes_written = u64::from(write_len);
                     reply.written(write_len);
-                    self.record_fuse_op(FuseOpType::Write, bytes_written, start, error);
+                    self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
                     self.record_disk_and_raid_states(&state.volume, 0.0);
                     return;
                 }
             }
 
             if let Some(rest) = cmd.strip_prefix("replace") {
+                let rest = rest.trim();
+                let (index, image) = rest
+                    .split_once(char::is_whitespace)
+                    .map_or((rest, None), |(index, image)| (index, Some(image.trim())));
+                if let Ok(i) = index.parse::<usize>() {
+                    let replaced = match image {
+                        Some(image) => {
+                            let len = state.volume.status().disk_bytes;
+                            state.volume.replace_disk_with(i, Path::new(image), len)
+                        }
+                        None => state.volume.replace_disk(i),
+                    };
+                    if replaced.is_err() {
+                        reply.error(libc::EINVAL);
+                        error = true;
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                        return;
+                    }
+                    if state.volume.rebuild_disk_upto(i, end).is_err() {
+                        reply.error(libc::EIO);
+                        error = true;
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                        return;
+                    }
+                    let write_len = Self::write_len(data.len());
+                    let bytes_written = u64::from(write_len);
+                    reply.written(write_len);
+                    self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
+                    self.record_disk_and_raid_states(&state.volume, 0.0);
+                    return;
+                }
+            }
+
+            if let Some(rest) = cmd.strip_prefix("readd") {
                 let rest = rest.trim();
                 if let Ok(i) = rest.parse::<usize>() {
-                    if state.volume.replace_disk(i).is_err() {
+                    if state.volume.readd_disk(i).is_err() {
                         reply.error(libc::EINVAL);
                         error = true;
-                        self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                         return;
                     }
                     if state.volume.rebuild_disk_upto(i, end).is_err() {
                         reply.error(libc::EIO);
                         error = true;
-                        self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                         return;
                     }
                     let write_len = Self::write_len(data.len());
                     let bytes_written = u64::from(write_len);
                     reply.written(write_len);
-                    self.record_fuse_op(FuseOpType::Write, bytes_written, start, error);
+                    self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
+                    self.record_disk_and_raid_states(&state.volume, 0.0);
+                    return;
+                }
+            }
+
+            if let Some(rest) = cmd.strip_prefix("split") {
+                let rest = rest.trim();
+                if let Ok(i) = rest.parse::<usize>() {
+                    if state.volume.split_mirror(i).is_err() {
+                        reply.error(libc::EINVAL);
+                        error = true;
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                        return;
+                    }
+                    let write_len = Self::write_len(data.len());
+                    let bytes_written = u64::from(write_len);
+                    reply.written(write_len);
+                    self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
+                    self.record_disk_and_raid_states(&state.volume, 0.0);
+                    return;
+                }
+            }
+
+            if let Some(rest) = cmd.strip_prefix("re-add") {
+                let rest = rest.trim();
+                if let Ok(i) = rest.parse::<usize>() {
+                    if state.volume.re_add(i).is_err() {
+                        reply.error(libc::EINVAL);
+                        error = true;
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                        return;
+                    }
+                    if state.volume.rebuild_disk_upto(i, end).is_err() {
+                        reply.error(libc::EIO);
+                        error = true;
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                        return;
+                    }
+                    let write_len = Self::write_len(data.len());
+                    let bytes_written = u64::from(write_len);
+                    reply.written(write_len);
+                    self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
                     self.record_disk_and_raid_states(&state.volume, 0.0);
                     return;
                 }
@@ -218,28 +430,178 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
                     if state.volume.rebuild_disk_upto(i, end).is_err() {
                         reply.error(libc::EIO);
                         error = true;
-                        self.record_fuse_op(FuseOpType::Write, 0, start, error);
+                        self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
                         return;
                     }
                     let write_len = Self::write_len(data.len());
                     let bytes_written = u64::from(write_len);
                     reply.written(write_len);
-                    self.record_fuse_op(FuseOpType::Write, bytes_written, start, error);
+                    self.record_fuse_op(FuseOpType::Write, ino, bytes_written, start, error);
                     self.record_disk_and_raid_states(&state.volume, 0.0);
                     return;
                 }
             }
 
+            if let Some(rest) = cmd.strip_prefix("metrics ") {
+                if !Self::toggle_sink(rest) {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("qos ") {
+                let applied = self
+                    .throttle
+                    .lock()
+                    .is_ok_and(|mut throttle| throttle.apply_command(rest));
+                if !applied {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("priority ") {
+                if !Self::apply_priority(&mut state.volume, rest) {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("rebuild-speed ") {
+                if !Self::apply_rebuild_speed(&mut state.volume, rest) {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("scrub ") {
+                if !Self::apply_scrub(&mut state, rest) {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("grow ") {
+                if !rest
+                    .trim()
+                    .parse()
+                    .is_ok_and(|disks| state.request_grow(disks))
+                {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("add-disk ") {
+                if !state.request_add_disk(PathBuf::from(rest.trim())) {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(rest) = cmd.strip_prefix("quota ") {
+                if !state.apply_quota_command(rest) {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
+            if let Some(name) = cmd.strip_prefix("snapshot ") {
+                if state.flush_all().is_err() || state.volume.snapshot_create(name.trim()).is_err()
+                {
+                    reply.error(libc::EINVAL);
+                    error = true;
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+                    return;
+                }
+                let write_len = Self::write_len(data.len());
+                reply.written(write_len);
+                self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                return;
+            }
+
             reply.error(libc::EINVAL);
             error = true;
-            self.record_fuse_op(FuseOpType::Write, 0, start, error);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
+            return;
+        }
+
+        if self.is_read_only(ino) {
+            reply.error(libc::EROFS);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, true);
+            return;
+        }
+
+        self.throttle_io(QosClass::Write, req.uid(), data.len() as u64);
+
+        if self.is_raw(ino) {
+            let offset = u64::try_from(offset.max(0)).unwrap_or(0);
+            match self.raw_write(offset, data) {
+                Ok(len) => {
+                    let write_len = Self::write_len(len);
+                    reply.written(write_len);
+                    self.record_fuse_op(FuseOpType::Write, ino, u64::from(write_len), start, error);
+                }
+                Err(code) => {
+                    reply.error(code);
+                    self.record_fuse_op(FuseOpType::Write, ino, 0, start, true);
+                }
+            }
             return;
         }
 
         let Some(index) = Self::index_for_inode(ino) else {
             reply.error(libc::ENOENT);
             error = true;
-            self.record_fuse_op(FuseOpType::Write, 0, start, error);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
             return;
         };
 
@@ -247,18 +609,58 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         let Ok(mut state) = self.state.lock() else {
             reply.error(libc::EIO);
             error = true;
-            self.record_fuse_op(FuseOpType::Write, 0, start, error);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
             return;
         };
-        let header_next_free = state.header.next_free;
-        let Some(entry) = state.entries.get(index).filter(|entry| entry.used) else {
+        let Some(file_hash) = state
+            .entries
+            .get(index)
+            .filter(|entry| entry.used)
+            .map(|entry| name_hash(&entry.name))
+        else {
             reply.error(libc::ENOENT);
             error = true;
-            self.record_fuse_op(FuseOpType::Write, 0, start, error);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, error);
             return;
         };
+
+        let pool_before = state.volume.thin_usage();
+        if let Err(code) = Self::write_file(&mut state, index, offset, data) {
+            reply.error(code);
+            self.record_fuse_op(FuseOpType::Write, ino, 0, start, true);
+            return;
+        }
+        let write_len = Self::write_len(data.len());
+        reply.written(write_len);
+        let bytes_written = u64::from(write_len);
+        self.record_file_io(FuseOpType::Write, ino, file_hash, bytes_written, start);
+        if let Some(usage) = state.volume.thin_usage()
+            && pool_before != Some(usage)
+            && let Some(metrics) = self.metrics.as_ref()
+        {
+            metrics.record_pool_usage(usage);
+        }
+    }
+
+    /// `write_file` writes `data` at `offset` of file entry `index`, growing the file
+    /// when the write ends past it.
+    ///
+    /// # Errors
+    /// Returns `ENOENT` for a free entry, `ENOSPC` if the file cannot grow, `EDQUOT`
+    /// if its owner is over quota, or the errno of a failed volume write.
+    pub(crate) fn write_file(
+        state: &mut FsState<D, N, T>,
+        index: usize,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<(), i32> {
+        let header_next_free = state.header.next_free;
+        let Some(entry) = state.entries.get(index).filter(|entry| entry.used) else {
+            return Err(libc::ENOENT);
+        };
         let entry_offset = entry.offset;
         let entry_size = entry.size;
+        let owner = entry.owner;
 
         let end_offset = offset.saturating_add(data.len() as u64);
         let new_size = entry_size.max(end_offset);
@@ -267,45 +669,197 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         let new_allocated = new_size.max(1);
         let new_end = entry_offset.saturating_add(new_allocated);
 
-        if new_end > self.capacity || (!is_last && new_size > entry.size) {
-            reply.error(libc::ENOSPC);
-            error = true;
-            self.record_fuse_op(FuseOpType::Write, 0, start, error);
-            return;
-        }
-
-        if offset > entry_size {
-            let gap = usize::try_from(offset - entry_size).unwrap_or(0);
-            if gap > 0 {
-                let zeros = vec![0u8; gap];
-                let gap_offset = entry_offset + entry_size;
-                state.volume.write_bytes(gap_offset, &zeros);
-            }
+        if new_end > state.data_end() || (!is_last && new_size > entry_size) {
+            return Err(libc::ENOSPC);
         }
+        state.check_quota(owner, new_size - entry_size, 0)?;
 
-        let abs_offset = entry_offset + offset;
-        state.volume.write_bytes(abs_offset, data);
+        let gap = usize::try_from(offset.saturating_sub(entry_size)).unwrap_or(0);
+        let written = if gap > 0 {
+            let mut payload = vec![0u8; gap];
+            payload.extend_from_slice(data);
+            state.write_data(index, entry_offset + entry_size, &payload)
+        } else {
+            state.write_data(index, entry_offset + offset, data)
+        };
+        written.map_err(|err| Self::errno_for(&err))?;
         if let Some(entry) = state.entries.get_mut(index) {
             entry.size = new_size;
         }
         if is_last {
             state.header.next_free = new_end;
         }
-        save_header_and_entry(&mut state, index);
-        let write_len = Self::write_len(data.len());
-        reply.written(write_len);
-        let bytes_written = u64::from(write_len);
-        self.record_fuse_op(FuseOpType::Write, bytes_written, start, error);
+        if !state.write_buffer.holds(index) {
+            save_header_and_entry(state, index);
+        }
+        Ok(())
+    }
+
+    /// `toggle_sink` applies a `<sink> on|off` control command to the metrics registry.
+    fn toggle_sink(args: &str) -> bool {
+        let mut parts = args.split_whitespace();
+        let (Some(name), Some(state), None) = (parts.next(), parts.next(), parts.next()) else {
+            return false;
+        };
+        let enabled = match state {
+            "on" => true,
+            "off" => false,
+            _ => return false,
+        };
+        raid_rs::metrics::set_sink_enabled(name, enabled)
+    }
+
+    fn sink_status_string() -> String {
+        let sinks = raid_rs::metrics::registered_sinks();
+        if sinks.is_empty() {
+            return String::new();
+        }
+        let mut txt = String::from("\nmetrics sinks:\n");
+        for (name, enabled) in sinks {
+            let state = if enabled { "on" } else { "off" };
+            let _ = writeln!(txt, "  {name}: {state}");
+        }
+        txt
+    }
+
+    /// `apply_priority` handles a `priority <percent|off>` control command.
+    fn apply_priority(volume: &mut Volume<D, N, T>, args: &str) -> bool {
+        let share = match args.trim() {
+            "off" => None,
+            value => match value.parse::<u8>() {
+                Ok(percent) if (1..=100).contains(&percent) => Some(percent),
+                _ => return false,
+            },
+        };
+        volume.set_background_share(share);
+        true
+    }
+
+    /// `apply_rebuild_speed` handles a `rebuild-speed <preset>` control command.
+    fn apply_rebuild_speed(volume: &mut Volume<D, N, T>, args: &str) -> bool {
+        let Some(speed) = RebuildSpeed::parse(args.trim()) else {
+            return false;
+        };
+        volume.set_background_share(speed.share());
+        true
+    }
+
+    /// `apply_scrub` handles a `scrub <start|stop|status>` control command.
+    ///
+    /// Starting fails while a scrub runs or disks await a rebuild, and stopping
+    /// fails when no scrub runs. `status` logs the progress also shown in the
+    /// control file.
+    fn apply_scrub(state: &mut FsState<D, N, T>, args: &str) -> bool {
+        match args.trim() {
+            "start" => state.start_scrub(),
+            "stop" => state.scrub.stop(),
+            "status" => {
+                tracing::info!("{}", state.scrub.status_string().trim());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// `priority_status_string` reports the background share and the bytes each IO
+    /// class moved for the control file.
+    fn priority_status_string(volume: &Volume<D, N, T>) -> String {
+        let mut share = volume
+            .background_share()
+            .map_or_else(|| "off".to_string(), |percent| format!("{percent}%"));
+        if let Some(speed) = RebuildSpeed::from_share(volume.background_share()) {
+            let _ = write!(share, " (rebuild-speed {})", speed.name());
+        }
+        let stats = volume.io_class_stats();
+        format!(
+            "\nio priority:\n  background share: {share}\n  foreground: {} B\n  background: {} B, {} pauses\n",
+            stats.foreground_bytes, stats.background_bytes, stats.background_waits
+        )
+    }
+
+    /// `hot_files_string` lists the files with the most I/O for the control file.
+    fn hot_files_string(&self, entries: &[Entry]) -> String {
+        let Ok(file_io) = self.file_io.lock() else {
+            return String::new();
+        };
+        let top = file_io.top(HOT_FILES);
+        drop(file_io);
+        if top.is_empty() {
+            return String::new();
+        }
+        let mut txt = String::from("\nhot files:\n");
+        for (ino, io) in top {
+            let name = Self::index_for_inode(ino)
+                .and_then(|index| entries.get(index))
+                .filter(|entry| entry.used && !entry.is_orphan())
+                .map_or("?", |entry| entry.name.as_str());
+            let _ = writeln!(
+                txt,
+                "  {name}: {} reads ({} B), {} writes ({} B)",
+                io.reads, io.read_bytes, io.writes, io.write_bytes
+            );
+        }
+        txt
+    }
+
+    /// `throttle_io` waits until the `QoS` limits admit an operation of `bytes` by `uid`.
+    ///
+    /// The wait happens before the filesystem lock is taken, so throttled clients
+    /// leave the volume to the rebuild thread and to other users meanwhile.
+    fn throttle_io(&self, class: QosClass, uid: u32, bytes: u64) {
+        let wait = self.throttle.lock().map_or(Duration::ZERO, |mut throttle| {
+            throttle.reserve(class, uid, bytes, Instant::now())
+        });
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
     }
 
     fn write_len(len: usize) -> u32 {
         u32::try_from(len).unwrap_or(u32::MAX)
     }
 
-    fn record_fuse_op(&self, op: FuseOpType, bytes: u64, start: Instant, error: bool) {
+    /// `errno_for` maps a volume IO failure to the errno reported to the kernel.
+    pub(crate) const fn errno_for(err: &raid_rs::Error) -> i32 {
+        match err {
+            raid_rs::Error::OutOfSpace(_) => libc::ENOSPC,
+            raid_rs::Error::Geometry(_) => libc::EINVAL,
+            raid_rs::Error::Degraded(IoError::DiskMissing { .. }) => libc::ENXIO,
+            raid_rs::Error::Degraded(IoError::ReadOnly) => libc::EROFS,
+            _ => libc::EIO,
+        }
+    }
+
+    fn record_fuse_op(&self, op: FuseOpType, ino: u64, bytes: u64, start: Instant, error: bool) {
+        self.emit_fuse_op(op, ino, 0, bytes, start, error);
+    }
+
+    /// `record_file_io` accounts a successful data operation on a regular file.
+    fn record_file_io(&self, op: FuseOpType, ino: u64, file_hash: u64, bytes: u64, start: Instant) {
+        if let Ok(mut file_io) = self.file_io.lock() {
+            match op {
+                FuseOpType::Read => file_io.record_read(ino, bytes),
+                FuseOpType::Write => file_io.record_write(ino, bytes),
+                FuseOpType::Open | FuseOpType::Fsync => {}
+            }
+        }
+        self.emit_fuse_op(op, ino, file_hash, bytes, start, false);
+    }
+
+    fn emit_fuse_op(
+        &self,
+        op: FuseOpType,
+        ino: u64,
+        name_hash: u64,
+        bytes: u64,
+        start: Instant,
+        error: bool,
+    ) {
         if let Some(metrics) = self.metrics.as_ref() {
             metrics.record_fuse_op(FuseOp {
                 op,
+                inode: ino,
+                name_hash,
                 bytes,
                 latency_seconds: start.elapsed().as_secs_f64(),
                 error,
@@ -320,6 +874,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         for status in volume.disk_statuses() {
             metrics.record_disk_status(status);
         }
+        metrics.record_disk_healths(&volume.disk_healths());
         let failed_disks = volume.failed_disks();
         let rebuild_in_progress = volume.any_needs_rebuild();
         metrics.record_raid_state(failed_disks, rebuild_in_progress, progress);
@@ -330,7 +885,7 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
 mod tests {
     use super::*;
     use crate::fs::DEFAULT_CHUNK_SIZE;
-    use crate::fs::test_utils::TestStripe;
+    use crate::fs::test_utils::{TestStripe, create_test_fs};
 
     type TestFs = RaidFs<1, { DEFAULT_CHUNK_SIZE }, TestStripe>;
 
@@ -341,4 +896,119 @@ mod tests {
         assert_eq!(TestFs::write_len(u32::MAX as usize), u32::MAX);
         assert_eq!(TestFs::write_len((u32::MAX as usize) + 10), u32::MAX);
     }
+
+    #[test]
+    fn errno_for_maps_volume_errors() {
+        let range = IoError::OutOfRange {
+            offset: 8,
+            len: 8,
+            limit: 10,
+        };
+        let missing = IoError::DiskMissing {
+            path: "disk-0.img".into(),
+        };
+        let lost = IoError::Uncorrectable { stripe_index: 3 };
+
+        assert_eq!(
+            TestFs::errno_for(&raid_rs::Error::OutOfSpace("pool".into())),
+            libc::ENOSPC
+        );
+        assert_eq!(TestFs::errno_for(&range.into()), libc::EINVAL);
+        assert_eq!(TestFs::errno_for(&missing.into()), libc::ENXIO);
+        assert_eq!(TestFs::errno_for(&lost.into()), libc::EIO);
+        assert_eq!(TestFs::errno_for(&IoError::ReadOnly.into()), libc::EROFS);
+        assert_eq!(
+            TestFs::errno_for(&raid_rs::Error::Corrupt("other".into())),
+            libc::EIO
+        );
+    }
+
+    #[test]
+    fn priority_command_sets_the_background_share() {
+        let fs = create_test_fs();
+        let mut state = fs.state.lock().expect("lock state");
+        assert!(TestFs::apply_priority(&mut state.volume, "25"));
+        assert_eq!(state.volume.background_share(), Some(25));
+        assert!(TestFs::priority_status_string(&state.volume).contains("background share: 25%"));
+
+        for bad in ["", "0", "101", "half"] {
+            assert!(!TestFs::apply_priority(&mut state.volume, bad), "{bad}");
+        }
+        assert!(TestFs::apply_priority(&mut state.volume, "off"));
+        assert_eq!(state.volume.background_share(), None);
+        drop(state);
+    }
+
+    #[test]
+    fn rebuild_speed_and_scrub_commands() {
+        let fs = create_test_fs();
+        let mut state = fs.state.lock().expect("lock state");
+        assert!(TestFs::apply_rebuild_speed(&mut state.volume, "low"));
+        assert_eq!(state.volume.background_share(), Some(20));
+        assert!(TestFs::priority_status_string(&state.volume).contains("20% (rebuild-speed low)"));
+        assert!(!TestFs::apply_rebuild_speed(&mut state.volume, "ludicrous"));
+
+        state.volume.clear_needs_rebuild_all();
+        assert!(TestFs::apply_scrub(&mut state, "status"));
+        assert!(!TestFs::apply_scrub(&mut state, "stop"));
+        assert!(TestFs::apply_scrub(&mut state, "start"));
+        assert!(!TestFs::apply_scrub(&mut state, "start"));
+        assert!(TestFs::apply_scrub(&mut state, "stop"));
+        assert!(!TestFs::apply_scrub(&mut state, "pause"));
+        drop(state);
+    }
+
+    #[test]
+    fn hot_files_string_names_busiest_files() {
+        let fs = create_test_fs();
+        let index = 0;
+        fs.state.lock().expect("lock state").entries[index] = Entry {
+            name: "hot.bin".to_string(),
+            offset: 0,
+            size: 4096,
+            used: true,
+            owner: 0,
+        };
+        let ino = TestFs::inode_for(index);
+        fs.record_file_io(FuseOpType::Write, ino, 0, 4096, Instant::now());
+        fs.record_file_io(FuseOpType::Read, ino, 0, 512, Instant::now());
+        fs.record_file_io(
+            FuseOpType::Read,
+            TestFs::inode_for(index + 1),
+            0,
+            1,
+            Instant::now(),
+        );
+
+        let state = fs.state.lock().expect("lock state");
+        let txt = fs.hot_files_string(&state.entries);
+        drop(state);
+        let lines: Vec<&str> = txt.lines().collect();
+        assert_eq!(lines[1], "hot files:");
+        assert_eq!(lines[2], "  hot.bin: 1 reads (512 B), 1 writes (4096 B)");
+        assert_eq!(lines[3], "  ?: 1 reads (1 B), 0 writes (0 B)");
+    }
+
+    #[test]
+    fn toggle_sink_parses_control_commands() {
+        struct NullSink;
+        impl raid_rs::metrics::MetricsSink for NullSink {
+            fn record_disk_op(&self, _op: raid_rs::metrics::DiskOp) {}
+            fn record_raid_op(&self, _op: raid_rs::metrics::RaidOp) {}
+        }
+        assert!(raid_rs::metrics::register_sink(
+            "ctl-test",
+            std::sync::Arc::new(NullSink)
+        ));
+
+        assert!(TestFs::toggle_sink("ctl-test off"));
+        assert!(TestFs::sink_status_string().contains("  ctl-test: off\n"));
+        assert!(TestFs::toggle_sink(" ctl-test   on "));
+        assert!(TestFs::sink_status_string().contains("  ctl-test: on\n"));
+        assert!(!TestFs::toggle_sink("ctl-test maybe"));
+        assert!(!TestFs::toggle_sink("ctl-test"));
+        assert!(!TestFs::toggle_sink("ctl-missing on"));
+
+        assert!(raid_rs::metrics::unregister_sink("ctl-test"));
+    }
 }
@@ -0,0 +1,242 @@
+//! Passthrough view of the whole volume as `volume.raw` in the mount root.
+//!
+//! Reads and writes of the file reach the volume at the same byte offsets and
+//! skip the file table, so block tools such as `dd`, `mkfs` or `hexdump` can work
+//! on the RAID volume itself. Writes can overwrite the file table too, which is
+//! why the file only appears when the mount asks for it.
+//!
+//! Next to it, the read-only `disks` directory holds a `disk-N.raw` file per
+//! member with the bytes stored on that disk, so striping, mirrors and parity can
+//! be seen member by member.
+
+use fuser::{FileAttr, FileType};
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+
+use crate::fs::constants::{DISK_FILE_BASE, DISKS_INO, RAW_INO, ROOT_ID};
+
+use super::types::RaidFs;
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
+    #[must_use]
+    /// `is_raw` reports whether `ino` is the exposed raw volume file.
+    pub const fn is_raw(&self, ino: u64) -> bool {
+        self.raw_volume && ino == RAW_INO
+    }
+
+    #[must_use]
+    /// `raw_disk` maps an inode onto the member disk whose raw file it is.
+    pub fn raw_disk(&self, ino: u64) -> Option<usize> {
+        if !self.raw_volume {
+            return None;
+        }
+        let disk = usize::try_from(ino.checked_sub(DISK_FILE_BASE)?).ok()?;
+        (disk < D).then_some(disk)
+    }
+
+    #[must_use]
+    /// `raw_disk_inode` returns the inode of the raw file of member `disk`.
+    pub const fn raw_disk_inode(disk: usize) -> u64 {
+        DISK_FILE_BASE + disk as u64
+    }
+
+    #[must_use]
+    /// `raw_disk_name` returns the file name of member `disk` in the `disks` directory.
+    pub fn raw_disk_name(disk: usize) -> String {
+        format!("disk-{disk}.raw")
+    }
+
+    #[must_use]
+    /// `disks_dir_attr` returns attributes for the member disk directory.
+    pub fn disks_dir_attr(&self) -> FileAttr {
+        FileAttr {
+            ino: DISKS_INO,
+            perm: 0o555,
+            ..self.root_attr()
+        }
+    }
+
+    /// `raw_disk_attr` returns attributes for the raw file of member `disk`.
+    ///
+    /// # Errors
+    /// Returns `EIO` if the filesystem state cannot be locked.
+    pub(crate) fn raw_disk_attr(&self, disk: usize) -> Result<FileAttr, i32> {
+        let len = self.raw_disk_len()?;
+        let mut attr = self.raw_attr();
+        attr.ino = Self::raw_disk_inode(disk);
+        attr.size = len;
+        attr.blocks = len.div_ceil(512);
+        attr.perm = 0o444;
+        Ok(attr)
+    }
+
+    #[must_use]
+    /// `raw_disk_lookup` finds the member disk named `name` in the `disks` directory.
+    pub fn raw_disk_lookup(name: &str) -> Option<usize> {
+        (0..D).find(|&disk| Self::raw_disk_name(disk) == name)
+    }
+
+    #[must_use]
+    /// `raw_disk_list` returns the listing of the `disks` directory.
+    pub fn raw_disk_list() -> Vec<(u64, FileType, String)> {
+        let mut out = vec![
+            (DISKS_INO, FileType::Directory, ".".to_string()),
+            (ROOT_ID, FileType::Directory, "..".to_string()),
+        ];
+        for disk in 0..D {
+            out.push((
+                Self::raw_disk_inode(disk),
+                FileType::RegularFile,
+                Self::raw_disk_name(disk),
+            ));
+        }
+        out
+    }
+
+    /// `raw_disk_read` reads up to `size` bytes stored on member `disk` at `offset`.
+    ///
+    /// # Errors
+    /// Returns `ENXIO` if the disk is missing, or the errno of another failed read.
+    pub(crate) fn raw_disk_read(
+        &self,
+        disk: usize,
+        offset: u64,
+        size: u32,
+    ) -> Result<Vec<u8>, i32> {
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        state.flush_all().map_err(|err| Self::errno_for(&err))?;
+        let len = u64::from(size).min(state.volume.status().disk_bytes.saturating_sub(offset));
+        let mut buf = vec![0u8; usize::try_from(len).map_err(|_| libc::EINVAL)?];
+        if !buf.is_empty() {
+            state
+                .volume
+                .peek_disk(disk, offset, &mut buf)
+                .map_err(|err| Self::errno_for(&err))?;
+        }
+        Ok(buf)
+    }
+
+    fn raw_disk_len(&self) -> Result<u64, i32> {
+        let Ok(state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        Ok(state.volume.status().disk_bytes)
+    }
+
+    /// `raw_read` reads up to `size` bytes of the volume at `offset`.
+    ///
+    /// # Errors
+    /// Returns the errno of a failed volume read.
+    pub(crate) fn raw_read(&self, offset: u64, size: u32) -> Result<Vec<u8>, i32> {
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        let len = u64::from(size).min(state.capacity().saturating_sub(offset));
+        let mut buf = vec![0u8; usize::try_from(len).map_err(|_| libc::EINVAL)?];
+        if buf.is_empty() {
+            return Ok(buf);
+        }
+        state
+            .flush_all()
+            .and_then(|()| state.volume.try_read_bytes(offset, &mut buf))
+            .map_err(|err| Self::errno_for(&err))?;
+        Ok(buf)
+    }
+
+    /// `raw_write` writes `data` to the volume at `offset`, cut at its end.
+    ///
+    /// # Returns
+    /// The number of bytes written.
+    ///
+    /// # Errors
+    /// Returns `ENOSPC` at or past the end of the volume, or the errno of a failed
+    /// volume write.
+    pub(crate) fn raw_write(&self, offset: u64, data: &[u8]) -> Result<usize, i32> {
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        let room = state.capacity().saturating_sub(offset);
+        let len = usize::try_from(room).map_or(data.len(), |room| room.min(data.len()));
+        if len == 0 && !data.is_empty() {
+            return Err(libc::ENOSPC);
+        }
+        state
+            .flush_all()
+            .and_then(|()| state.volume.try_write_bytes(offset, &data[..len]))
+            .map_err(|err| Self::errno_for(&err))?;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fs::constants::{CTL_INO, DISK_FILE_BASE, RAW_INO};
+    use crate::fs::test_utils::{TestFs, create_test_fs};
+
+    #[test]
+    fn raw_file_exists_only_when_enabled() {
+        let mut fs = create_test_fs();
+        assert!(!fs.is_raw(RAW_INO));
+        fs.raw_volume = true;
+        assert!(fs.is_raw(RAW_INO));
+        assert!(!fs.is_raw(CTL_INO));
+    }
+
+    #[test]
+    fn disk_files_map_onto_members() {
+        let mut fs = create_test_fs();
+        assert_eq!(fs.raw_disk(DISK_FILE_BASE), None);
+        fs.raw_volume = true;
+        assert_eq!(fs.raw_disk(DISK_FILE_BASE), Some(0));
+        assert_eq!(fs.raw_disk(DISK_FILE_BASE + 1), None);
+        assert_eq!(TestFs::raw_disk_lookup("disk-0.raw"), Some(0));
+        assert_eq!(TestFs::raw_disk_lookup("disk-1.raw"), None);
+        let names: Vec<String> = TestFs::raw_disk_list()
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect();
+        assert_eq!(names, [".", "..", "disk-0.raw"]);
+    }
+
+    #[test]
+    fn disk_reads_show_member_bytes() {
+        let mut fs = create_test_fs();
+        fs.raw_volume = true;
+        fs.raw_write(0, b"stripe").expect("raw write");
+
+        let len = fs.raw_disk_attr(0).expect("disk attr").size;
+        assert!(len >= fs.state.lock().expect("lock state").capacity());
+        assert_eq!(fs.raw_disk_read(0, 0, 6).as_deref(), Ok(&b"stripe"[..]));
+        assert_eq!(fs.raw_disk_read(0, len - 1, 8).map(|buf| buf.len()), Ok(1));
+        assert_eq!(fs.raw_disk_read(0, len, 8), Ok(Vec::new()));
+
+        fs.state
+            .lock()
+            .expect("lock state")
+            .volume
+            .fail_disk(0)
+            .expect("fail disk");
+        assert_eq!(fs.raw_disk_read(0, 0, 6), Err(libc::ENXIO));
+    }
+
+    #[test]
+    fn raw_io_reaches_volume_offsets() {
+        let mut fs = create_test_fs();
+        fs.raw_volume = true;
+        let end = fs.state.lock().expect("lock state").capacity();
+
+        assert_eq!(fs.raw_write(0, b"RAW!"), Ok(4));
+        let mut state = fs.state.lock().expect("lock state");
+        let mut out = [0u8; 4];
+        state.volume.read_bytes(0, &mut out);
+        drop(state);
+        assert_eq!(&out, b"RAW!");
+        assert_eq!(fs.raw_read(1, 2).as_deref(), Ok(&b"AW"[..]));
+
+        assert_eq!(fs.raw_write(end - 2, b"tail"), Ok(2));
+        assert_eq!(fs.raw_read(end - 2, 16).map(|buf| buf.len()), Ok(2));
+        assert_eq!(fs.raw_read(end, 16), Ok(Vec::new()));
+        assert_eq!(fs.raw_write(end, b"x"), Err(libc::ENOSPC));
+    }
+}
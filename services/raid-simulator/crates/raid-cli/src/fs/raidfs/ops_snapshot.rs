@@ -0,0 +1,329 @@
+//! Read-only view of volume snapshots under the `.snapshots` directory.
+
+use fuser::{FileAttr, FileType};
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::volume::MAX_SNAPSHOTS;
+
+use crate::fs::constants::{
+    HEADER_SIZE, MAX_FILES, ROOT_ID, SNAP_DIR_BASE, SNAP_FILE_BASE, SNAP_ROOT_INO, TABLE_SIZE,
+};
+use crate::fs::metadata::{Entry, decode_entries};
+
+use super::types::RaidFs;
+
+/// `SnapshotNode` identifies an inode inside the snapshot tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotNode {
+    Root,
+    Dir(usize),
+    File(usize, usize),
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
+    #[must_use]
+    /// `snapshot_node` maps an inode number onto the snapshot tree.
+    ///
+    /// # Returns
+    /// `Some(node)` when the inode belongs to the `.snapshots` directory.
+    pub fn snapshot_node(ino: u64) -> Option<SnapshotNode> {
+        if ino == SNAP_ROOT_INO {
+            return Some(SnapshotNode::Root);
+        }
+        if (SNAP_DIR_BASE..SNAP_FILE_BASE).contains(&ino) {
+            return usize::try_from(ino - SNAP_DIR_BASE)
+                .ok()
+                .map(SnapshotNode::Dir);
+        }
+        let rel = usize::try_from(ino.checked_sub(SNAP_FILE_BASE)?).ok()?;
+        let (snap, entry) = (rel / MAX_FILES, rel % MAX_FILES);
+        (snap < MAX_SNAPSHOTS).then_some(SnapshotNode::File(snap, entry))
+    }
+
+    #[must_use]
+    /// `snapshot_inode` converts a snapshot tree node into an inode number.
+    pub const fn snapshot_inode(node: SnapshotNode) -> u64 {
+        match node {
+            SnapshotNode::Root => SNAP_ROOT_INO,
+            SnapshotNode::Dir(snap) => SNAP_DIR_BASE + snap as u64,
+            SnapshotNode::File(snap, entry) => SNAP_FILE_BASE + (snap * MAX_FILES + entry) as u64,
+        }
+    }
+
+    /// `snapshot_attr` returns attributes for a node of the snapshot tree.
+    ///
+    /// # Errors
+    /// Returns `ENOENT` if the snapshot or file does not exist.
+    pub(crate) fn snapshot_attr(&self, node: SnapshotNode) -> Result<FileAttr, i32> {
+        let ino = Self::snapshot_inode(node);
+        match node {
+            SnapshotNode::Root => Ok(self.snapshot_dir_attr(ino)),
+            SnapshotNode::Dir(snap) => {
+                self.snapshot_name(snap)?;
+                Ok(self.snapshot_dir_attr(ino))
+            }
+            SnapshotNode::File(snap, index) => {
+                let entry = self.snapshot_entry(snap, index)?;
+                Ok(FileAttr {
+                    ino,
+                    perm: 0o444,
+                    ..self.entry_attr(index, entry.size)
+                })
+            }
+        }
+    }
+
+    /// `snapshot_lookup` resolves a name inside a snapshot tree directory.
+    ///
+    /// # Errors
+    /// Returns `ENOENT` if the parent is not a snapshot directory or the name is unknown.
+    pub(crate) fn snapshot_lookup(
+        &self,
+        parent: SnapshotNode,
+        name: &str,
+    ) -> Result<SnapshotNode, i32> {
+        match parent {
+            SnapshotNode::Root => self
+                .snapshot_dirs()?
+                .into_iter()
+                .find(|(_, snap_name)| snap_name == name)
+                .map(|(snap, _)| SnapshotNode::Dir(snap))
+                .ok_or(libc::ENOENT),
+            SnapshotNode::Dir(snap) => self
+                .snapshot_entries(snap)?
+                .into_iter()
+                .enumerate()
+                .find(|(_, entry)| entry.used && entry.name == name)
+                .map(|(index, _)| SnapshotNode::File(snap, index))
+                .ok_or(libc::ENOENT),
+            SnapshotNode::File(..) => Err(libc::ENOTDIR),
+        }
+    }
+
+    /// `snapshot_list` returns the directory listing of a snapshot tree directory.
+    ///
+    /// # Errors
+    /// Returns `ENOENT` if the directory does not exist and `ENOTDIR` for files.
+    pub(crate) fn snapshot_list(
+        &self,
+        node: SnapshotNode,
+    ) -> Result<Vec<(u64, FileType, String)>, i32> {
+        let ino = Self::snapshot_inode(node);
+        let mut out = vec![(ino, FileType::Directory, ".".to_string())];
+        match node {
+            SnapshotNode::Root => {
+                out.push((ROOT_ID, FileType::Directory, "..".to_string()));
+                for (snap, name) in self.snapshot_dirs()? {
+                    out.push((
+                        Self::snapshot_inode(SnapshotNode::Dir(snap)),
+                        FileType::Directory,
+                        name,
+                    ));
+                }
+            }
+            SnapshotNode::Dir(snap) => {
+                out.push((SNAP_ROOT_INO, FileType::Directory, "..".to_string()));
+                for (index, entry) in self.snapshot_entries(snap)?.into_iter().enumerate() {
+                    if entry.used && !entry.is_orphan() {
+                        out.push((
+                            Self::snapshot_inode(SnapshotNode::File(snap, index)),
+                            FileType::RegularFile,
+                            entry.name,
+                        ));
+                    }
+                }
+            }
+            SnapshotNode::File(..) => return Err(libc::ENOTDIR),
+        }
+        Ok(out)
+    }
+
+    /// `snapshot_read` reads file bytes as they were when the snapshot was taken.
+    ///
+    /// # Errors
+    /// Returns `ENOENT` if the file does not exist and `EIO` if the snapshot cannot be read.
+    pub(crate) fn snapshot_read(
+        &self,
+        snap: usize,
+        index: usize,
+        offset: u64,
+        size: u32,
+    ) -> Result<Vec<u8>, i32> {
+        let entry = self.snapshot_entry(snap, index)?;
+        if offset >= entry.size {
+            return Ok(Vec::new());
+        }
+        let len = usize::try_from(u64::from(size).min(entry.size - offset)).unwrap_or(0);
+        let mut buf = vec![0u8; len];
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        state
+            .volume
+            .read_snapshot_bytes(snap, entry.offset + offset, &mut buf)
+            .map_err(|_| libc::EIO)?;
+        Ok(buf)
+    }
+
+    fn snapshot_dir_attr(&self, ino: u64) -> FileAttr {
+        FileAttr {
+            ino,
+            perm: 0o555,
+            ..self.root_attr()
+        }
+    }
+
+    fn snapshot_dirs(&self) -> Result<Vec<(usize, String)>, i32> {
+        let Ok(state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        Ok(state
+            .volume
+            .snapshot_list()
+            .into_iter()
+            .filter(|info| info.valid)
+            .map(|info| (info.index, info.name))
+            .collect())
+    }
+
+    fn snapshot_name(&self, snap: usize) -> Result<String, i32> {
+        self.snapshot_dirs()?
+            .into_iter()
+            .find(|(index, _)| *index == snap)
+            .map(|(_, name)| name)
+            .ok_or(libc::ENOENT)
+    }
+
+    fn snapshot_entry(&self, snap: usize, index: usize) -> Result<Entry, i32> {
+        self.snapshot_entries(snap)?
+            .into_iter()
+            .nth(index)
+            .filter(|entry| entry.used)
+            .ok_or(libc::ENOENT)
+    }
+
+    /// `snapshot_entries` reads the file table stored in a snapshot.
+    fn snapshot_entries(&self, snap: usize) -> Result<Vec<Entry>, i32> {
+        self.snapshot_name(snap)?;
+        let mut table = vec![0u8; TABLE_SIZE];
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        state
+            .volume
+            .read_snapshot_bytes(snap, 0, &mut table)
+            .map_err(|_| libc::EIO)?;
+        drop(state);
+        let Some(header) = Self::parse_header(&table) else {
+            return Ok(Vec::new());
+        };
+        decode_entries(&table[HEADER_SIZE..], &header).map_err(|_| libc::EIO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::metadata::Header;
+    use crate::fs::test_utils::{TestFs, create_test_fs};
+
+    fn seeded_fs() -> TestFs {
+        let fs = create_test_fs();
+        {
+            let mut state = fs.state.lock().expect("lock state");
+            state
+                .volume
+                .init_snapshots(5000, TestFs::data_start())
+                .expect("init snapshots");
+            let offset = TestFs::data_start();
+            let header = TestFs::header_bytes(&Header {
+                next_free: offset + 5,
+                quotas: false,
+            });
+            let entry = Entry {
+                name: "file.txt".to_string(),
+                offset,
+                size: 5,
+                used: true,
+                owner: 0,
+            };
+            state.volume.write_bytes(0, &header);
+            state
+                .volume
+                .write_bytes(HEADER_SIZE as u64, &entry.to_bytes());
+            state.volume.write_bytes(offset, b"hello");
+            state
+                .volume
+                .snapshot_create("daily")
+                .expect("create snapshot");
+            state.volume.write_bytes(offset, b"HELLO");
+        }
+        fs
+    }
+
+    #[test]
+    fn snapshot_inodes_round_trip() {
+        for node in [
+            SnapshotNode::Root,
+            SnapshotNode::Dir(3),
+            SnapshotNode::File(2, 17),
+        ] {
+            assert_eq!(
+                TestFs::snapshot_node(TestFs::snapshot_inode(node)),
+                Some(node)
+            );
+        }
+        assert_eq!(TestFs::snapshot_node(ROOT_ID), None);
+    }
+
+    #[test]
+    fn snapshot_tree_lists_and_reads_preserved_file() {
+        let fs = seeded_fs();
+
+        let dir = fs
+            .snapshot_lookup(SnapshotNode::Root, "daily")
+            .expect("lookup dir");
+        let listing = fs.snapshot_list(dir).expect("list dir");
+        assert!(listing.iter().any(|entry| entry.2 == "file.txt"));
+
+        let file = fs.snapshot_lookup(dir, "file.txt").expect("lookup file");
+        let SnapshotNode::File(snap, index) = file else {
+            panic!("expected file node");
+        };
+        assert_eq!(fs.snapshot_attr(file).expect("attr").perm, 0o444);
+        assert_eq!(
+            fs.snapshot_read(snap, index, 0, 64).expect("read"),
+            b"hello"
+        );
+    }
+
+    #[test]
+    fn snapshot_lookup_rejects_unknown_names() {
+        let fs = seeded_fs();
+        assert_eq!(
+            fs.snapshot_lookup(SnapshotNode::Root, "missing"),
+            Err(libc::ENOENT)
+        );
+    }
+
+    #[test]
+    fn statfs_inside_snapshots_reports_the_cow_pool() {
+        let fs = seeded_fs();
+        let state = fs.state.lock().expect("lock state");
+        let usage = state.volume.snapshot_usage().expect("snapshot usage");
+        assert_eq!(usage.used_slots, 1);
+
+        let pool = TestFs::statfs_counts(&state, SNAP_ROOT_INO);
+        let block = u64::from(crate::fs::constants::STATFS_BLOCK_SIZE);
+        assert_eq!(pool.blocks, usage.pool_bytes() / block);
+        assert_eq!(pool.bfree, usage.free_bytes() / block);
+        assert_eq!(pool.ffree, MAX_SNAPSHOTS as u64 - 1);
+
+        let live = TestFs::statfs_counts(&state, ROOT_ID);
+        assert_eq!(live.blocks, state.capacity() / block);
+        assert_eq!(live.files, MAX_FILES as u64);
+        let space = TestFs::space_usage(&state);
+        assert_eq!(space.snapshot_used_bytes, usage.block_bytes);
+        assert_eq!(space.snapshot_pool_bytes, usage.pool_bytes());
+        drop(state);
+    }
+}
@@ -2,28 +2,29 @@ use fuser::{ReplyEmpty, Request};
 use raid_rs::layout::stripe::traits::stripe::Stripe;
 use std::time::Instant;
 
-use crate::fs::constants::CTL_INO;
+use crate::fs::constants::{CTL_INO, DISK_FILE_BASE, RAW_INO};
 use crate::metrics_runtime::{FuseOp, FuseOpType};
 
 use super::types::RaidFs;
 
 impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     pub(crate) fn op_flush(
+        &self,
         _req: &Request<'_>,
         ino: u64,
         _fh: u64,
         _lock_owner: u64,
         reply: ReplyEmpty,
     ) {
-        if Self::is_known_inode(ino) {
-            reply.ok();
-        } else {
-            reply.error(libc::ENOENT);
+        match self.flush_buffered(ino) {
+            Ok(()) => reply.ok(),
+            Err(code) => reply.error(code),
         }
     }
 
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn op_release(
+        &self,
         _req: &Request<'_>,
         ino: u64,
         _fh: u64,
@@ -32,10 +33,27 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         _flush: bool,
         reply: ReplyEmpty,
     ) {
-        if Self::is_known_inode(ino) {
-            reply.ok();
-        } else {
-            reply.error(libc::ENOENT);
+        let flushed = self.flush_buffered(ino);
+        self.release_handle(ino);
+        match flushed {
+            Ok(()) => reply.ok(),
+            Err(code) => reply.error(code),
+        }
+    }
+
+    /// `release_handle` drops an open handle of a file, freeing the file if it was
+    /// unlinked and this was its last handle.
+    pub(crate) fn release_handle(&self, ino: u64) {
+        let Some(index) = Self::index_for_inode(ino) else {
+            return;
+        };
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.release_file(index)
+            && let Ok(mut file_io) = self.file_io.lock()
+        {
+            file_io.forget(ino);
         }
     }
 
@@ -49,15 +67,20 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
     ) {
         let start = Instant::now();
         let mut error = false;
-        if Self::is_known_inode(ino) {
+        if !Self::is_known_inode(ino) {
+            reply.error(libc::ENOENT);
+            error = true;
+        } else if self.sync_volume() {
             reply.ok();
         } else {
-            reply.error(libc::ENOENT);
+            reply.error(libc::EIO);
             error = true;
         }
         if let Some(metrics) = self.metrics.as_ref() {
             metrics.record_fuse_op(FuseOp {
                 op: FuseOpType::Fsync,
+                inode: ino,
+                name_hash: 0,
                 bytes: 0,
                 latency_seconds: start.elapsed().as_secs_f64(),
                 error,
@@ -65,8 +88,44 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
         }
     }
 
+    /// `flush_buffered` writes the buffered data of file `ino` to the volume.
+    pub(crate) fn flush_buffered(&self, ino: u64) -> Result<(), i32> {
+        if !Self::is_known_inode(ino) {
+            return Err(libc::ENOENT);
+        }
+        let Some(index) = Self::index_for_inode(ino) else {
+            return Ok(());
+        };
+        let Ok(mut state) = self.state.lock() else {
+            return Err(libc::EIO);
+        };
+        state.flush_file(index).map_err(|err| Self::errno_for(&err))
+    }
+
+    /// `sync_volume` writes out buffered file data and flushes the write caches of
+    /// the member disks to their images.
+    ///
+    /// Returns `false` if the volume could not be synced.
+    pub(crate) fn sync_volume(&self) -> bool {
+        let Ok(mut state) = self.state.lock() else {
+            return false;
+        };
+        if state.flush_all().is_err() {
+            return false;
+        }
+        match state.volume.sync() {
+            Ok(()) => true,
+            Err(err) => {
+                tracing::warn!("failed to sync volume: {err:#}");
+                false
+            }
+        }
+    }
+
     fn is_known_inode(ino: u64) -> bool {
-        ino == CTL_INO || Self::index_for_inode(ino).is_some()
+        ino == CTL_INO
+            || (RAW_INO..DISK_FILE_BASE + D as u64).contains(&ino)
+            || Self::index_for_inode(ino).is_some()
     }
 }
 
@@ -74,7 +133,9 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> RaidFs<D, N, T> {
 mod tests {
     use super::*;
     use crate::fs::DEFAULT_CHUNK_SIZE;
-    use crate::fs::test_utils::TestStripe;
+    use crate::fs::raidfs::{DEFAULT_MAX_AGE, WriteBuffer};
+    use crate::fs::test_utils::{TestStripe, create_test_fs};
+    use raid_rs::retention::disk::WriteCachePlan;
 
     type TestFs = RaidFs<1, { DEFAULT_CHUNK_SIZE }, TestStripe>;
 
@@ -84,4 +145,50 @@ mod tests {
         assert!(TestFs::is_known_inode(TestFs::inode_for(0)));
         assert!(!TestFs::is_known_inode(999_999));
     }
+
+    #[test]
+    fn sync_volume_flushes_write_caches() {
+        let fs = create_test_fs();
+        let mut state = fs.state.lock().expect("lock state");
+        state.volume.enable_write_cache(WriteCachePlan {
+            volatility: 1.0,
+            ..WriteCachePlan::default()
+        });
+        state.volume.write_bytes(0, b"durable");
+        drop(state);
+
+        assert!(fs.sync_volume());
+
+        let mut state = fs.state.lock().expect("lock state");
+        state.volume.crash();
+        assert_eq!(state.volume.write_cache_stats().lost_writes, 0);
+        let mut out = [0u8; 7];
+        state.volume.read_bytes(0, &mut out);
+        drop(state);
+        assert_eq!(&out, b"durable");
+    }
+
+    #[test]
+    fn flush_and_sync_write_out_buffered_data() {
+        let fs = create_test_fs();
+        let mut state = fs.state.lock().expect("lock state");
+        state.write_buffer = WriteBuffer::new(Some(1024), DEFAULT_MAX_AGE);
+        state.write_data(0, 8192, b"first").expect("buffer");
+        state.write_data(1, 9216, b"second").expect("buffer");
+        drop(state);
+
+        assert_eq!(fs.flush_buffered(TestFs::inode_for(0)), Ok(()));
+        assert_eq!(fs.flush_buffered(999_999), Err(libc::ENOENT));
+        let state = fs.state.lock().expect("lock state");
+        assert_eq!(state.write_buffer.dirty_bytes(), 6);
+        drop(state);
+
+        assert!(fs.sync_volume());
+        let mut state = fs.state.lock().expect("lock state");
+        assert_eq!(state.write_buffer.dirty_bytes(), 0);
+        let mut out = [0u8; 6];
+        state.volume.read_bytes(9216, &mut out);
+        drop(state);
+        assert_eq!(&out, b"second");
+    }
 }
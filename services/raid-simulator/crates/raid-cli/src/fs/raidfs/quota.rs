@@ -0,0 +1,275 @@
+//! Per-user byte and file-count quotas.
+//!
+//! Every file is charged to the user that created it. Limits live in a quota
+//! table at the tail of the volume, reserved when the first quota is set and
+//! released again when the last one is lifted; while it is reserved, files cannot
+//! grow into it. Usage is not stored but summed from the file table, so it always
+//! matches the files on the volume. Creating a file or growing one past a limit
+//! fails with `EDQUOT`.
+
+use std::fmt::Write as _;
+
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::volume::Volume;
+
+use super::types::FsState;
+use crate::fs::constants::{MAX_QUOTAS, QUOTA_SIZE};
+use crate::fs::metadata::{Corrupt, Header, Quota, decode_quotas, encode_quotas};
+
+/// `QuotaUsage` is what the files of one user take up.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// `quota_offset` returns where the quota table of a volume starts.
+fn quota_offset<const D: usize, const N: usize, T: Stripe<D, N>>(volume: &Volume<D, N, T>) -> u64 {
+    volume.logical_capacity_bytes().saturating_sub(QUOTA_SIZE)
+}
+
+/// `read_quotas` loads the quota table of a volume whose header reserves one.
+///
+/// # Errors
+/// Returns `Corrupt` if the reserved region does not hold a quota table.
+pub fn read_quotas<const D: usize, const N: usize, T: Stripe<D, N>>(
+    volume: &mut Volume<D, N, T>,
+    header: &Header,
+) -> Result<Vec<Quota>, Corrupt> {
+    if !header.quotas {
+        return Ok(Vec::new());
+    }
+    let mut buf = vec![0u8; usize::try_from(QUOTA_SIZE).unwrap_or(usize::MAX)];
+    let offset = quota_offset(volume);
+    volume.read_bytes(offset, &mut buf);
+    decode_quotas(&buf)
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> FsState<D, N, T> {
+    #[must_use]
+    /// `capacity` returns the logical capacity of the filesystem in bytes.
+    ///
+    /// It follows the volume, so an online grow enlarges it once the reshape ends.
+    pub fn capacity(&self) -> u64 {
+        self.volume.logical_capacity_bytes()
+    }
+
+    #[must_use]
+    /// `data_end` returns the end of the space files may be allocated in.
+    pub fn data_end(&self) -> u64 {
+        let capacity = self.capacity();
+        if self.header.quotas {
+            capacity.min(quota_offset(&self.volume))
+        } else {
+            capacity
+        }
+    }
+
+    #[must_use]
+    /// `quota_usage` sums the bytes and files charged to a user.
+    pub fn quota_usage(&self, uid: u32) -> QuotaUsage {
+        self.entries
+            .iter()
+            .filter(|entry| entry.used && entry.owner == uid)
+            .fold(QuotaUsage::default(), |usage, entry| QuotaUsage {
+                bytes: usage.bytes + entry.size,
+                files: usage.files + 1,
+            })
+    }
+
+    /// `check_quota` tests whether a user may take up more bytes and files.
+    ///
+    /// # Errors
+    /// Returns `EDQUOT` if either would exceed the user's quota.
+    pub fn check_quota(&self, uid: u32, bytes: u64, files: u64) -> Result<(), i32> {
+        let Some(quota) = self.quotas.iter().find(|quota| quota.uid == uid) else {
+            return Ok(());
+        };
+        let usage = self.quota_usage(uid);
+        let over = |used: u64, extra: u64, limit: Option<u64>| {
+            extra > 0 && limit.is_some_and(|limit| used.saturating_add(extra) > limit)
+        };
+        if over(usage.bytes, bytes, quota.bytes) || over(usage.files, files, quota.files) {
+            return Err(libc::EDQUOT);
+        }
+        Ok(())
+    }
+
+    /// `set_quota` replaces the limits of a user and persists the quota table.
+    ///
+    /// Setting the first quota reserves the table at the tail of the volume;
+    /// lifting the last one releases it.
+    ///
+    /// # Errors
+    /// Returns `ENOSPC` if files already reach into the tail of the volume or the
+    /// table has no room for another user.
+    pub fn set_quota(&mut self, quota: Quota) -> Result<(), i32> {
+        let mut quotas = self.quotas.clone();
+        quotas.retain(|other| other.uid != quota.uid);
+        if quota.bytes.is_some() || quota.files.is_some() {
+            quotas.push(quota);
+            quotas.sort_by_key(|quota| quota.uid);
+        }
+        if quotas.len() > MAX_QUOTAS {
+            return Err(libc::ENOSPC);
+        }
+        let reserved = !quotas.is_empty();
+        if reserved && !self.header.quotas && self.header.next_free > quota_offset(&self.volume) {
+            return Err(libc::ENOSPC);
+        }
+        if reserved {
+            let offset = quota_offset(&self.volume);
+            self.volume.write_bytes(offset, &encode_quotas(&quotas));
+        }
+        if self.header.quotas != reserved {
+            self.header.quotas = reserved;
+            let header = self.header.to_bytes();
+            self.volume.write_bytes(0, &header);
+        }
+        self.quotas = quotas;
+        Ok(())
+    }
+
+    /// `move_quota_table` rewrites a reserved quota table at the tail of the volume
+    /// after the volume grew under the mounted filesystem.
+    pub fn move_quota_table(&mut self) {
+        if self.header.quotas {
+            let offset = quota_offset(&self.volume);
+            self.volume
+                .write_bytes(offset, &encode_quotas(&self.quotas));
+        }
+    }
+
+    /// `apply_quota_command` handles a `quota <uid> <bytes|files> <n|off>` control
+    /// command.
+    ///
+    /// # Returns
+    /// `false` if the command is malformed or the quota cannot be stored.
+    pub fn apply_quota_command(&mut self, args: &str) -> bool {
+        let mut parts = args.split_whitespace();
+        let (Some(uid), Some(kind), Some(value), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return false;
+        };
+        let Ok(uid) = uid.parse::<u32>() else {
+            return false;
+        };
+        let limit = match value {
+            "off" => None,
+            value => match value.parse::<u64>() {
+                Ok(limit) => Some(limit),
+                Err(_) => return false,
+            },
+        };
+        let mut quota = self
+            .quotas
+            .iter()
+            .find(|quota| quota.uid == uid)
+            .copied()
+            .unwrap_or(Quota {
+                uid,
+                bytes: None,
+                files: None,
+            });
+        match kind {
+            "bytes" => quota.bytes = limit,
+            "files" => quota.files = limit,
+            _ => return false,
+        }
+        self.set_quota(quota).is_ok()
+    }
+
+    #[must_use]
+    /// `quota_status_string` lists the quota and usage of every limited user.
+    pub fn quota_status_string(&self) -> String {
+        let mut txt = String::from("\nquotas:\n");
+        if self.quotas.is_empty() {
+            txt.push_str("  none\n");
+            return txt;
+        }
+        let limit = |limit: Option<u64>| limit.map_or_else(|| "off".to_string(), |v| v.to_string());
+        for quota in &self.quotas {
+            let usage = self.quota_usage(quota.uid);
+            let _ = writeln!(
+                txt,
+                "  uid {}: bytes {}/{}, files {}/{}",
+                quota.uid,
+                usage.bytes,
+                limit(quota.bytes),
+                usage.files,
+                limit(quota.files)
+            );
+        }
+        txt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::create_test_state;
+
+    #[test]
+    fn quota_commands_reserve_and_release_the_table() {
+        let mut state = create_test_state();
+        let capacity = state.volume.logical_capacity_bytes();
+        assert_eq!(state.data_end(), capacity);
+        assert!(state.quota_status_string().contains("none"));
+
+        assert!(state.apply_quota_command("1000 bytes 4096"));
+        assert!(state.apply_quota_command("1000 files 2"));
+        assert!(!state.apply_quota_command("1000 inodes 2"));
+        assert!(!state.apply_quota_command("root bytes 1"));
+        assert!(state.header.quotas);
+        assert_eq!(state.data_end(), capacity - QUOTA_SIZE);
+        assert_eq!(
+            read_quotas(&mut state.volume, &state.header),
+            Ok(state.quotas.clone())
+        );
+        assert!(
+            state
+                .quota_status_string()
+                .contains("uid 1000: bytes 0/4096, files 0/2")
+        );
+
+        assert!(state.apply_quota_command("1000 bytes off"));
+        assert!(state.apply_quota_command("1000 files off"));
+        assert!(!state.header.quotas);
+        assert!(state.quotas.is_empty());
+        assert_eq!(state.data_end(), capacity);
+    }
+
+    #[test]
+    fn quota_needs_a_free_tail() {
+        let mut state = create_test_state();
+        state.header.next_free = state.volume.logical_capacity_bytes();
+        assert!(!state.apply_quota_command("1000 files 1"));
+        assert!(!state.header.quotas);
+    }
+
+    #[test]
+    fn check_quota_counts_files_of_the_owner() {
+        let mut state = create_test_state();
+        state.entries[0].used = true;
+        state.entries[0].owner = 1000;
+        state.entries[0].size = 100;
+        state.entries[1].used = true;
+        state.entries[1].owner = 1001;
+        state.entries[1].size = 500;
+        assert!(state.apply_quota_command("1000 bytes 150"));
+        assert!(state.apply_quota_command("1000 files 1"));
+
+        assert_eq!(
+            state.quota_usage(1000),
+            QuotaUsage {
+                bytes: 100,
+                files: 1
+            }
+        );
+        assert_eq!(state.check_quota(1000, 50, 0), Ok(()));
+        assert_eq!(state.check_quota(1000, 51, 0), Err(libc::EDQUOT));
+        assert_eq!(state.check_quota(1000, 0, 1), Err(libc::EDQUOT));
+        assert_eq!(state.check_quota(1001, 1 << 20, 1), Ok(()));
+    }
+}
@@ -0,0 +1,319 @@
+//! Token-bucket throttling of file I/O for the FUSE layer.
+//!
+//! Reads and writes are separate classes, each optionally limited in operations
+//! and megabytes per second. A request takes tokens from the buckets of its class
+//! and waits out any shortfall before it touches the volume, so a runaway workload
+//! cannot keep the filesystem lock away from the rebuild thread. With `per_uid`,
+//! every user draws from buckets of its own.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// Bytes in the megabyte of `mbps` limits.
+const MB: f64 = 1_000_000.0;
+
+/// `QosClass` is the kind of file operation a limit applies to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum QosClass {
+    Read,
+    Write,
+}
+
+impl QosClass {
+    /// `ALL` lists every class in display order.
+    pub const ALL: [Self; 2] = [Self::Read, Self::Write];
+
+    #[must_use]
+    /// `label` returns the name used by the control file.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            Self::Read => 0,
+            Self::Write => 1,
+        }
+    }
+}
+
+/// `QosLimits` caps the rate of one class of operations; `None` leaves it unlimited.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct QosLimits {
+    pub iops: Option<u64>,
+    pub mbps: Option<f64>,
+}
+
+/// `TokenBucket` refills at `rate` tokens per second up to one second's worth.
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    const fn new(rate: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate,
+            updated: now,
+        }
+    }
+
+    /// `take` withdraws `amount` tokens and returns how long the caller must wait
+    /// for the bucket to cover them; later requests wait behind the debt.
+    fn take(&mut self, amount: f64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = elapsed.mul_add(self.rate, self.tokens).min(self.rate);
+        self.updated = now;
+        self.tokens -= amount;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+/// `Buckets` holds the operation and byte buckets of one class and user.
+#[derive(Clone, Debug)]
+struct Buckets {
+    ops: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+impl Buckets {
+    fn new(limits: QosLimits, now: Instant) -> Self {
+        #[allow(clippy::cast_precision_loss)]
+        let ops = limits
+            .iops
+            .filter(|&iops| iops > 0)
+            .map(|iops| TokenBucket::new(iops as f64, now));
+        let bytes = limits
+            .mbps
+            .filter(|&mbps| mbps > 0.0)
+            .map(|mbps| TokenBucket::new(mbps * MB, now));
+        Self { ops, bytes }
+    }
+}
+
+/// `Throttle` delays file operations that exceed the limits of their class.
+#[derive(Clone, Debug, Default)]
+pub struct Throttle {
+    limits: [QosLimits; 2],
+    per_uid: bool,
+    buckets: HashMap<(QosClass, Option<u32>), Buckets>,
+}
+
+impl Throttle {
+    #[must_use]
+    /// `new` applies `limits` to both reads and writes.
+    ///
+    /// # Arguments
+    /// * `limits` - Rate limits of each class.
+    /// * `per_uid` - Give every user its own buckets instead of one shared set.
+    pub fn new(limits: QosLimits, per_uid: bool) -> Self {
+        Self {
+            limits: [limits; 2],
+            per_uid,
+            buckets: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    /// `limits` returns the current limits of `class`.
+    pub const fn limits(&self, class: QosClass) -> QosLimits {
+        self.limits[class.index()]
+    }
+
+    /// `set_limits` replaces the limits of `class`; its buckets start full.
+    pub fn set_limits(&mut self, class: QosClass, limits: QosLimits) {
+        self.limits[class.index()] = limits;
+        self.buckets.retain(|(c, _), _| *c != class);
+    }
+
+    /// `reserve` accounts one operation of `bytes` and returns how long it must wait.
+    ///
+    /// # Arguments
+    /// * `class` - Kind of operation.
+    /// * `uid` - User issuing the operation.
+    /// * `bytes` - Payload size of the operation.
+    /// * `now` - Time the operation arrived.
+    pub fn reserve(&mut self, class: QosClass, uid: u32, bytes: u64, now: Instant) -> Duration {
+        let limits = self.limits(class);
+        if limits.iops.is_none() && limits.mbps.is_none() {
+            return Duration::ZERO;
+        }
+        let key = (class, self.per_uid.then_some(uid));
+        let buckets = self
+            .buckets
+            .entry(key)
+            .or_insert_with(|| Buckets::new(limits, now));
+        let ops = buckets
+            .ops
+            .as_mut()
+            .map_or(Duration::ZERO, |b| b.take(1.0, now));
+        #[allow(clippy::cast_precision_loss)]
+        let data = buckets
+            .bytes
+            .as_mut()
+            .map_or(Duration::ZERO, |b| b.take(bytes as f64, now));
+        ops.max(data)
+    }
+
+    /// `apply_command` handles a `qos <read|write|all> <iops|mbps> <n|off>` control
+    /// command.
+    ///
+    /// # Returns
+    /// `false` if the command is malformed.
+    pub fn apply_command(&mut self, args: &str) -> bool {
+        let mut parts = args.split_whitespace();
+        let (Some(class), Some(kind), Some(value), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return false;
+        };
+        let classes: &[QosClass] = match class {
+            "read" => &[QosClass::Read],
+            "write" => &[QosClass::Write],
+            "all" => &QosClass::ALL,
+            _ => return false,
+        };
+        let off = value == "off";
+        for &class in classes {
+            let mut limits = self.limits(class);
+            match kind {
+                "iops" if off => limits.iops = None,
+                "iops" => match value.parse::<u64>() {
+                    Ok(iops) if iops > 0 => limits.iops = Some(iops),
+                    _ => return false,
+                },
+                "mbps" if off => limits.mbps = None,
+                "mbps" => match value.parse::<f64>() {
+                    Ok(mbps) if mbps.is_finite() && mbps > 0.0 => limits.mbps = Some(mbps),
+                    _ => return false,
+                },
+                _ => return false,
+            }
+            self.set_limits(class, limits);
+        }
+        true
+    }
+
+    #[must_use]
+    /// `status_string` lists the limits of every class for the control file.
+    pub fn status_string(&self) -> String {
+        let scope = if self.per_uid { " (per uid)" } else { "" };
+        let mut txt = format!("\nqos{scope}:\n");
+        for class in QosClass::ALL {
+            let limits = self.limits(class);
+            let iops = limits
+                .iops
+                .map_or_else(|| "off".to_string(), |v| v.to_string());
+            let mbps = limits
+                .mbps
+                .map_or_else(|| "off".to_string(), |v| v.to_string());
+            let _ = writeln!(txt, "  {}: iops {iops}, mbps {mbps}", class.label());
+        }
+        txt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(iops: Option<u64>, mbps: Option<f64>) -> Throttle {
+        Throttle::new(QosLimits { iops, mbps }, false)
+    }
+
+    #[test]
+    fn unlimited_throttle_never_waits() {
+        let mut throttle = Throttle::default();
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(
+                throttle.reserve(QosClass::Write, 0, 1 << 20, now),
+                Duration::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn iops_limit_delays_operations_past_the_burst() {
+        let mut throttle = limited(Some(10), None);
+        let now = Instant::now();
+        for _ in 0..10 {
+            assert_eq!(throttle.reserve(QosClass::Read, 0, 1, now), Duration::ZERO);
+        }
+        let wait = throttle.reserve(QosClass::Read, 0, 1, now);
+        assert!((wait.as_secs_f64() - 0.1).abs() < 1e-9, "{wait:?}");
+
+        let later = now + Duration::from_secs(1);
+        assert_eq!(
+            throttle.reserve(QosClass::Read, 0, 1, later),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn bandwidth_limit_waits_for_large_requests() {
+        let mut throttle = limited(None, Some(1.0));
+        let now = Instant::now();
+        let wait = throttle.reserve(QosClass::Write, 0, 3_000_000, now);
+        assert!((wait.as_secs_f64() - 2.0).abs() < 1e-9, "{wait:?}");
+        assert_eq!(throttle.reserve(QosClass::Read, 0, 1, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn per_uid_buckets_are_independent() {
+        let mut throttle = Throttle::new(
+            QosLimits {
+                iops: Some(1),
+                mbps: None,
+            },
+            true,
+        );
+        let now = Instant::now();
+        assert_eq!(throttle.reserve(QosClass::Read, 1, 0, now), Duration::ZERO);
+        assert!(throttle.reserve(QosClass::Read, 1, 0, now) > Duration::ZERO);
+        assert_eq!(throttle.reserve(QosClass::Read, 2, 0, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn control_commands_adjust_limits() {
+        let mut throttle = Throttle::default();
+        assert!(throttle.apply_command("write iops 50"));
+        assert!(throttle.apply_command("all mbps 2.5"));
+        assert_eq!(
+            throttle.limits(QosClass::Write),
+            QosLimits {
+                iops: Some(50),
+                mbps: Some(2.5),
+            }
+        );
+        assert_eq!(throttle.limits(QosClass::Read).iops, None);
+        assert!(throttle.apply_command("write iops off"));
+        assert_eq!(throttle.limits(QosClass::Write).iops, None);
+
+        for bad in [
+            "",
+            "read iops",
+            "read iops 0",
+            "both iops 1",
+            "read mbps -1",
+            "read iops 1 2",
+        ] {
+            assert!(!throttle.apply_command(bad), "{bad}");
+        }
+        assert_eq!(
+            throttle.status_string(),
+            "\nqos:\n  read: iops off, mbps 2.5\n  write: iops off, mbps 2.5\n"
+        );
+    }
+}
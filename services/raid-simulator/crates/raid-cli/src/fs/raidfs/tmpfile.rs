@@ -0,0 +1,164 @@
+//! Unnamed temporary files in the flat file table.
+//!
+//! A file created with `O_TMPFILE` has an entry but no name, and a file unlinked
+//! while it is still open loses its name but keeps its entry, so its handles can
+//! read and write it. Linking it gives it a name, which puts a finished temp file
+//! into place; otherwise its entry is freed when the last handle is released. Entries orphaned by a crash
+//! are reclaimed at mount, so temp files never leak into the table.
+
+use std::collections::HashMap;
+
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+use raid_rs::retention::volume::Volume;
+
+use super::types::FsState;
+use crate::fs::constants::{ENTRY_SIZE, HEADER_SIZE};
+use crate::fs::metadata::Entry;
+use crate::fs::persist::save_header_and_entry;
+
+/// `OpenFiles` counts the open handles of every file entry.
+#[derive(Clone, Debug, Default)]
+pub struct OpenFiles {
+    handles: HashMap<usize, u32>,
+}
+
+impl OpenFiles {
+    /// `open` records a new handle of entry `index`.
+    pub fn open(&mut self, index: usize) {
+        *self.handles.entry(index).or_default() += 1;
+    }
+
+    /// `release` drops a handle of entry `index` and reports whether it was the last.
+    pub fn release(&mut self, index: usize) -> bool {
+        let Some(count) = self.handles.get_mut(&index) else {
+            return false;
+        };
+        *count -= 1;
+        if *count > 0 {
+            return false;
+        }
+        self.handles.remove(&index);
+        true
+    }
+
+    #[must_use]
+    /// `is_open` reports whether entry `index` has open handles.
+    pub fn is_open(&self, index: usize) -> bool {
+        self.handles.contains_key(&index)
+    }
+}
+
+/// `reclaim_orphans` frees the entries of files that were unlinked while open when
+/// the filesystem last stopped.
+///
+/// # Returns
+/// The number of entries freed.
+pub fn reclaim_orphans<const D: usize, const N: usize, T: Stripe<D, N>>(
+    volume: &mut Volume<D, N, T>,
+    entries: &mut [Entry],
+) -> usize {
+    let mut freed = 0;
+    for (index, entry) in entries.iter_mut().enumerate() {
+        if entry.is_orphan() {
+            *entry = Entry::empty();
+            let offset = HEADER_SIZE as u64 + (index as u64 * ENTRY_SIZE as u64);
+            volume.write_bytes(offset, &entry.to_bytes());
+            freed += 1;
+        }
+    }
+    freed
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> FsState<D, N, T> {
+    /// `unlink_file` removes the name of entry `index`, freeing the entry unless it
+    /// is still open.
+    ///
+    /// # Returns
+    /// Whether the entry was freed.
+    pub fn unlink_file(&mut self, index: usize) -> bool {
+        if self.open_files.is_open(index) {
+            self.entries[index].name.clear();
+            save_header_and_entry(self, index);
+            return false;
+        }
+        self.free_entry(index);
+        true
+    }
+
+    /// `release_file` drops a handle of entry `index`, freeing the entry if it was
+    /// the last handle of an unlinked file.
+    ///
+    /// # Returns
+    /// Whether the entry was freed.
+    pub fn release_file(&mut self, index: usize) -> bool {
+        let last = self.open_files.release(index);
+        if last && self.entries.get(index).is_some_and(Entry::is_orphan) {
+            self.free_entry(index);
+            return true;
+        }
+        false
+    }
+
+    fn free_entry(&mut self, index: usize) {
+        self.entries[index] = Entry::empty();
+        self.write_buffer.take(index);
+        save_header_and_entry(self, index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::create_test_state;
+
+    #[test]
+    fn open_files_count_handles() {
+        let mut open = OpenFiles::default();
+        assert!(!open.release(3));
+        open.open(3);
+        open.open(3);
+        assert!(!open.release(3));
+        assert!(open.is_open(3));
+        assert!(open.release(3));
+        assert!(!open.is_open(3));
+    }
+
+    #[test]
+    fn unlinked_open_file_is_freed_on_last_release() {
+        let mut state = create_test_state();
+        state.entries[0] = Entry {
+            name: "scratch".to_string(),
+            offset: 0,
+            size: 8,
+            used: true,
+            owner: 0,
+        };
+        state.open_files.open(0);
+        state.open_files.open(0);
+
+        assert!(!state.unlink_file(0));
+        assert!(state.entries[0].is_orphan());
+        assert!(!state.release_file(0));
+        assert!(state.entries[0].used);
+        assert!(state.release_file(0));
+        assert!(!state.entries[0].used);
+    }
+
+    #[test]
+    fn orphans_are_reclaimed_at_mount() {
+        let mut state = create_test_state();
+        let mut entries = vec![Entry::empty(); 3];
+        entries[1].used = true;
+        entries[2].used = true;
+        entries[2].name = "kept".to_string();
+
+        assert_eq!(reclaim_orphans(&mut state.volume, &mut entries), 1);
+        assert!(!entries[1].used);
+        assert!(entries[2].used);
+        let mut buf = [0u8; ENTRY_SIZE];
+        state
+            .volume
+            .read_bytes(HEADER_SIZE as u64 + ENTRY_SIZE as u64, &mut buf);
+        assert!(!Entry::from_bytes(&buf).expect("decode entry").used);
+    }
+}
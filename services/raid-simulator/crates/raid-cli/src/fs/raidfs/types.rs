@@ -1,11 +1,17 @@
 //! Core filesystem state types for the RAID-backed FUSE layer.
 
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use raid_rs::layout::stripe::traits::stripe::Stripe;
 use raid_rs::retention::volume::Volume;
 
-use crate::fs::metadata::{Entry, Header};
+use super::background::{GrowState, Scrub};
+use super::coalesce::WriteBuffer;
+use super::file_io::FileIoTable;
+use super::throttle::Throttle;
+use super::tmpfile::OpenFiles;
+use crate::fs::metadata::{Entry, Header, Quota};
 use crate::metrics_runtime::MetricsEmitter;
 
 /// `FsState` holds the mutable on-disk state for the filesystem.
@@ -13,13 +19,33 @@ pub struct FsState<const D: usize, const N: usize, T: Stripe<D, N>> {
     pub volume: Volume<D, N, T>,
     pub header: Header,
     pub entries: Vec<Entry>,
+    /// File writes not yet on the volume.
+    pub write_buffer: WriteBuffer,
+    /// Progress of the scrub started from the control file.
+    pub scrub: Scrub,
+    /// Progress of the online grow requested from the control file.
+    pub grow: GrowState,
+    /// Image hot-added from the control file, which the grow makes the next member.
+    pub hot_add: Option<PathBuf>,
+    /// Per-user limits, stored at the tail of the volume while `header.quotas` is set.
+    pub quotas: Vec<Quota>,
+    /// Open handles of every file, which keep unlinked files alive.
+    pub open_files: OpenFiles,
 }
 
-/// `RaidFs` wraps shared state and capacity metadata for FUSE operations.
+/// `RaidFs` wraps shared state and mount options for FUSE operations.
 pub struct RaidFs<const D: usize, const N: usize, T: Stripe<D, N>> {
     pub state: Arc<Mutex<FsState<D, N, T>>>,
-    pub capacity: u64,
     pub metrics: Option<Arc<MetricsEmitter>>,
+    /// Per-file I/O counters; lock after `state` when both are needed.
+    pub file_io: Mutex<FileIoTable>,
+    /// Every mutating operation fails with `EROFS`.
+    pub read_only: bool,
+    /// Rate limits of file reads and writes; lock on its own, never while waiting.
+    pub throttle: Mutex<Throttle>,
+    /// Expose the whole volume as `volume.raw` and each member under `disks` in the
+    /// root directory.
+    pub raw_volume: bool,
 }
 
 #[cfg(test)]
@@ -37,7 +63,7 @@ mod tests {
     #[test]
     fn raidfs_has_expected_capacity() {
         let fs = create_test_fs();
-        let capacity = fs.capacity;
+        let capacity = fs.raw_attr().size;
         let state = fs.state.lock().expect("state lock");
         assert_eq!(capacity, state.volume.logical_capacity_bytes());
         drop(state);
@@ -48,8 +74,11 @@ mod tests {
         let state = create_test_state();
         let fs = RaidFs::<1, { DEFAULT_CHUNK_SIZE }, TestStripe> {
             state: Arc::new(Mutex::new(state)),
-            capacity: 1,
             metrics: None,
+            file_io: Mutex::default(),
+            read_only: false,
+            throttle: Mutex::default(),
+            raw_volume: false,
         };
         assert!(fs.metrics.is_none());
     }
@@ -0,0 +1,231 @@
+//! Liveness and readiness endpoints for container healthchecks.
+//!
+//! `/healthz` answers as long as the process serves requests. `/readyz` answers
+//! `200` only when the filesystem is mounted (for commands that mount one), the
+//! metrics sender holds a connection and no rebuild is running; degraded arrays
+//! stay ready because they keep serving IO. Both bodies list every check.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::{Context, Result};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::prometheus::{get_path, http_response};
+
+/// Filesystems the process mounts, and those the kernel has mounted.
+static MOUNTS_REQUIRED: AtomicUsize = AtomicUsize::new(0);
+static MOUNTED: AtomicUsize = AtomicUsize::new(0);
+static SENDER_CONNECTED: AtomicBool = AtomicBool::new(false);
+/// Degraded and rebuilding state of each array, by RAID identifier.
+static ARRAYS: Mutex<BTreeMap<String, (bool, bool)>> = Mutex::new(BTreeMap::new());
+
+/// `require_mount` makes readiness wait for one more filesystem to be mounted.
+pub fn require_mount() {
+    MOUNTS_REQUIRED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// `set_mounted` records that the kernel mounted or unmounted a filesystem.
+pub fn set_mounted(mounted: bool) {
+    if mounted {
+        MOUNTED.fetch_add(1, Ordering::Relaxed);
+    } else {
+        let _ = MOUNTED.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+    }
+}
+
+/// `set_sender_connected` records whether metrics reach their destination.
+pub fn set_sender_connected(connected: bool) {
+    SENDER_CONNECTED.store(connected, Ordering::Relaxed);
+}
+
+/// `set_array_state` records the degraded and rebuilding state of an array.
+pub fn set_array_state(raid_id: &str, degraded: bool, rebuilding: bool) {
+    ARRAYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(raid_id.to_string(), (degraded, rebuilding));
+}
+
+/// `Status` is a snapshot of every check reported by the endpoints.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    pub mount_required: bool,
+    pub mounted: bool,
+    pub sender_connected: bool,
+    pub degraded: bool,
+    pub rebuilding: bool,
+}
+
+impl Status {
+    #[must_use]
+    /// `current` returns the checks as last recorded by the process.
+    pub fn current() -> Self {
+        let required = MOUNTS_REQUIRED.load(Ordering::Relaxed);
+        let arrays = ARRAYS.lock().unwrap_or_else(PoisonError::into_inner);
+        Self {
+            mount_required: required > 0,
+            mounted: required > 0 && MOUNTED.load(Ordering::Relaxed) >= required,
+            sender_connected: SENDER_CONNECTED.load(Ordering::Relaxed),
+            degraded: arrays.values().any(|&(degraded, _)| degraded),
+            rebuilding: arrays.values().any(|&(_, rebuilding)| rebuilding),
+        }
+    }
+
+    #[must_use]
+    /// `ready` reports whether the simulator should receive traffic.
+    pub const fn ready(self) -> bool {
+        (self.mounted || !self.mount_required) && self.sender_connected && !self.rebuilding
+    }
+
+    fn render(self) -> String {
+        let mut txt = String::new();
+        let _ = writeln!(txt, "ready: {}", self.ready());
+        if self.mount_required {
+            let _ = writeln!(txt, "mounted: {}", self.mounted);
+        }
+        let _ = writeln!(txt, "sender_connected: {}", self.sender_connected);
+        let _ = writeln!(txt, "degraded: {}", self.degraded);
+        let _ = writeln!(txt, "rebuilding: {}", self.rebuilding);
+        txt
+    }
+}
+
+/// `spawn` serves `/healthz` and `/readyz` on `listen` until shutdown.
+///
+/// # Arguments
+/// * `listen` - Address to serve on; nothing is started when `None`.
+/// * `shutdown` - Watch channel signaling shutdown.
+///
+/// # Errors
+/// Returns an error if the address cannot be bound.
+pub async fn spawn(listen: Option<SocketAddr>, shutdown: watch::Receiver<bool>) -> Result<()> {
+    let Some(addr) = listen else {
+        return Ok(());
+    };
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind health endpoint {addr}"))?;
+    info!(
+        "health: serving /healthz and /readyz on {}",
+        listener.local_addr()?
+    );
+    tokio::spawn(serve(listener, shutdown));
+    Ok(())
+}
+
+async fn serve(listener: TcpListener, mut shutdown: watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tokio::spawn(async move {
+                        if let Err(err) = handle(stream).await {
+                            warn!("health: request failed: {err:#}");
+                        }
+                    });
+                }
+                Err(err) => warn!("health: accept failed: {err}"),
+            },
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    break;
+                }
+            },
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream) -> Result<()> {
+    let path = get_path(&mut stream).await?;
+    let response = respond(path.as_deref(), Status::current());
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn respond(path: Option<&[u8]>, status: Status) -> String {
+    match path {
+        Some(b"/healthz") => http_response("200 OK", "text/plain", &status.render()),
+        Some(b"/readyz") if status.ready() => {
+            http_response("200 OK", "text/plain", &status.render())
+        }
+        Some(b"/readyz") => {
+            http_response("503 Service Unavailable", "text/plain", &status.render())
+        }
+        _ => http_response("404 Not Found", "text/plain", ""),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ready_requires_mount_connection_and_no_rebuild() {
+        let ready = Status {
+            mount_required: true,
+            mounted: true,
+            sender_connected: true,
+            degraded: true,
+            rebuilding: false,
+        };
+        assert!(ready.ready());
+        assert!(
+            Status {
+                mount_required: false,
+                mounted: false,
+                ..ready
+            }
+            .ready()
+        );
+        assert!(
+            !Status {
+                mounted: false,
+                ..ready
+            }
+            .ready()
+        );
+        assert!(
+            !Status {
+                sender_connected: false,
+                ..ready
+            }
+            .ready()
+        );
+        assert!(
+            !Status {
+                rebuilding: true,
+                ..ready
+            }
+            .ready()
+        );
+    }
+
+    #[test]
+    fn respond_maps_paths_and_readiness() {
+        let status = Status {
+            sender_connected: true,
+            ..Status::default()
+        };
+
+        let live = respond(Some(b"/healthz"), Status::default());
+        assert!(live.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(live.ends_with(
+            "ready: false\nsender_connected: false\ndegraded: false\nrebuilding: false\n"
+        ));
+
+        assert!(respond(Some(b"/readyz"), status).starts_with("HTTP/1.1 200 OK\r\n"));
+        let not_ready = respond(Some(b"/readyz"), Status::default());
+        assert!(not_ready.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(respond(Some(b"/metrics"), status).starts_with("HTTP/1.1 404"));
+        assert!(respond(None, status).starts_with("HTTP/1.1 404"));
+    }
+}
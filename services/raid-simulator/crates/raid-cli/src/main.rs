@@ -2,79 +2,208 @@
 #![allow(clippy::cargo_common_metadata)]
 //! CLI entry point for the RAID simulator and metrics streamer.
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result};
 
+mod access;
+mod arrays;
 mod cli;
+mod commands;
+mod config;
+mod file_sink;
 /// fs exposes filesystem helpers for the RAID-backed FUSE implementation.
 pub mod fs;
+mod health;
 mod mount;
 
 mod metrics_runtime;
+mod otlp;
 mod pb;
+mod prometheus;
+mod reload;
+mod scenario;
+mod schedule;
+mod seed;
 mod sender;
 mod simulator;
+mod spool;
+mod superblock;
+mod tcp;
+mod trace;
 mod uds;
+mod volume;
+mod workload;
 
-use cli::{Cli, Command, RaidMode};
+use arrays::{ArraySet, ArraySpec};
+use cli::{Command, LogFormat, RaidMode};
 use fs::DEFAULT_CHUNK_SIZE;
-use mount::run_fuse;
-
-use std::time::Duration;
+use mount::{FaultPolicy, MountFlags, run_fuse};
+use raid_rs::metrics::IO_TRACE_TARGET;
+use raid_rs::retention::disk::{WearPlan, WriteCachePlan};
+use raid_rs::retention::volume::DegradedPolicy;
+use rand::Rng;
+use rand::rngs::StdRng;
+use schedule::FailureSchedule;
+use seed::Component;
 
 use tokio::sync::{mpsc, watch};
 use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
-use crate::metrics_runtime::{MetricsEmitter, run_event_metrics_loop};
+use crate::file_sink::FileSink;
+use crate::fs::QosLimits;
+use crate::metrics_runtime::{MetricsEmitter, run_event_metrics_loop, spawn_sink};
 use crate::pb::metrics;
-use crate::sender::{SenderConfig, SenderStats, run_sender};
+use crate::reload::Settings;
+use crate::sender::SenderStats;
 use crate::simulator::SyntheticSimulator;
 
 fn main() -> Result<()> {
-    init_tracing();
-
-    let cli = Cli::parse();
+    let cli = config::parse()?;
+    init_tracing(cli.log_format, cli.trace_io);
+    seed::set(cli.seed);
 
     match cli.command {
-        Command::Fuse(args) => run_fuse_with_synthetic_metrics(args),
-        Command::Metrics(args) => run_metrics_only(args),
+        Command::Fuse(args) => run_fuse_with_synthetic_metrics(*args),
+        Command::Metrics(args) => match args.action {
+            Some(cli::MetricsAction::Replay(args)) => {
+                let metrics_args = args.metrics.clone();
+                // Replayed operations carry the arrays they were recorded under.
+                run_with_event_metrics(metrics_args, RaidMode::Raid0, move |emitter| {
+                    commands::metrics_replay::run(&args, &emitter)
+                })
+            }
+            None => run_metrics_only(args.metrics),
+        },
+        Command::Migrate(args) => commands::migrate::run(&args),
+        Command::Inspect(args) => commands::inspect::run(&args),
+        Command::Visualize(args) => commands::visualize::run(&args),
+        Command::Status(args) => commands::status::run(&args),
+        Command::Export(args) => commands::export::run(&args),
+        Command::Import(args) => commands::import::run(&args),
+        Command::Snapshot(args) => commands::snapshot::run(&args),
+        Command::Backup(args) => commands::backup::run(&args),
+        Command::Restore(args) => commands::restore::run(&args),
+        Command::Bench(args) => commands::bench::run(&args),
+        Command::Scenario(args) => commands::scenario::run(&args),
+        Command::Torture(args) => commands::torture::run(&args),
+        Command::Assemble(args) => commands::assemble::run(&args),
+        Command::Config(args) => {
+            commands::config::run(&args);
+            Ok(())
+        }
+        Command::Grow(args) => {
+            let metrics_args = args.metrics.clone();
+            let raid = args.raid;
+            run_with_event_metrics(metrics_args, raid, move |emitter| {
+                commands::grow::run(&args, &emitter)
+            })
+        }
+        Command::Shrink(args) => {
+            let metrics_args = args.metrics.clone();
+            let raid = args.raid;
+            run_with_event_metrics(metrics_args, raid, move |emitter| {
+                commands::shrink::run(&args, &emitter)
+            })
+        }
+        Command::Check(args) => {
+            let metrics_args = args.metrics.clone();
+            let raid = args.raid;
+            run_with_event_metrics(metrics_args, raid, move |emitter| {
+                commands::check::run(&args, &emitter)
+            })
+        }
+        Command::Replay(args) => {
+            let metrics_args = args.metrics.clone();
+            let raid = args.raid;
+            run_with_event_metrics(metrics_args, raid, move |emitter| {
+                commands::replay::run(&args, &emitter)
+            })
+        }
     }
 }
 
-fn init_tracing() {
+fn init_tracing(format: LogFormat, trace_io: bool) {
     if tracing::dispatcher::has_been_set() {
         return;
     }
 
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive("info".parse().unwrap()))
-        .init();
+    let filter = EnvFilter::from_default_env().add_directive("info".parse().unwrap());
+    let io = trace_io.then(|| format!("{IO_TRACE_TARGET}=trace"));
+    let (filter, span_events) = match &io {
+        Some(io) => (filter.add_directive(io.parse().unwrap()), FmtSpan::CLOSE),
+        None => (filter, FmtSpan::NONE),
+    };
+
+    let (filter, handle) = tracing_subscriber::reload::Layer::new(filter);
+    reload::init_log_filter(handle, io);
+    let layer = tracing_subscriber::fmt::layer().with_span_events(span_events);
+    let registry = tracing_subscriber::registry().with(filter);
+    match format {
+        LogFormat::Text => registry.with(layer).init(),
+        LogFormat::Json => registry.with(layer.json().flatten_event(true)).init(),
+    }
 }
 
 fn run_fuse_with_synthetic_metrics(args: cli::FuseArgs) -> Result<()> {
+    let metrics_args = args.metrics.clone();
+    let raid = args.raid;
+    run_with_event_metrics(metrics_args, raid, move |emitter| {
+        run_fuse_command(&args, emitter)
+    })
+}
+
+/// Registry name of the sink feeding the exporter pipeline.
+const STREAM_SINK: &str = "stream";
+/// Registry name of the `--metrics-file` sink.
+const FILE_SINK: &str = "file";
+
+fn run_with_event_metrics<F>(metrics_args: cli::MetricsArgs, raid: RaidMode, run: F) -> Result<()>
+where
+    F: FnOnce(std::sync::Arc<MetricsEmitter>) -> Result<()>,
+{
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
 
-    let metrics_args = args.metrics.clone();
     let (event_tx, event_rx) = mpsc::channel(metrics_args.queue_cap);
-    let raid_id = match args.raid {
-        RaidMode::Raid0 => "raid0",
-        RaidMode::Raid1 => "raid1",
-        RaidMode::Raid3 => "raid3",
+    let emitter = MetricsEmitter::new(raid.id().to_string(), event_tx);
+    let file_sink = match &metrics_args.metrics_file {
+        Some(path) => Some(std::sync::Arc::new(FileSink::create(
+            path,
+            metrics_args.metrics_file_format,
+            raid.id(),
+        )?)),
+        None => None,
     };
-    let emitter = MetricsEmitter::new(raid_id.to_string(), event_tx);
-    let _ = raid_rs::metrics::install_metrics_sink(emitter.clone());
+    let _ = raid_rs::metrics::register_sink(STREAM_SINK, emitter.clone());
+    if let Some(sink) = &file_sink {
+        let _ = raid_rs::metrics::register_sink(FILE_SINK, sink.clone());
+    }
     let metrics_thread = start_event_metrics_thread(metrics_args, shutdown_rx, event_rx);
 
-    let fuse_res = run_fuse_command(args, emitter);
+    let run_res = run(emitter.clone());
 
+    let _ = raid_rs::metrics::unregister_sink(STREAM_SINK);
+    let _ = raid_rs::metrics::unregister_sink(FILE_SINK);
     let _ = shutdown_tx.send(true);
+    if let Some(sink) = &file_sink
+        && let Err(e) = sink.flush()
+    {
+        warn!("metrics: failed to flush metrics file: {:#}", e);
+    }
 
     match metrics_thread.join() {
-        Ok(Ok(stats)) => {
+        Ok(Ok(mut stats)) => {
+            stats.dropped_events = emitter.dropped();
+            let dropped = stats.dropped_events;
+            info!(
+                "metrics: exit: reconnects={}, send_errors={}, dropped_batches={}, spool_depth={}",
+                stats.reconnects, stats.send_errors, stats.dropped_batches, stats.spool_depth
+            );
             info!(
-                "metrics: exit: reconnects={}, send_errors={}, dropped_batches={}",
-                stats.reconnects, stats.send_errors, stats.dropped_batches
+                "metrics: dropped events: disk_ops={}, raid_ops={}, fuse_ops={}, states={}",
+                dropped.disk_ops, dropped.raid_ops, dropped.fuse_ops, dropped.states
             );
         }
         Ok(Err(e)) => {
@@ -85,87 +214,233 @@ fn run_fuse_with_synthetic_metrics(args: cli::FuseArgs) -> Result<()> {
         }
     }
 
-    fuse_res
+    run_res
+}
+
+fn run_fuse_command(args: &cli::FuseArgs, metrics: std::sync::Arc<MetricsEmitter>) -> Result<()> {
+    let mut wear = seed::rng(Component::Wear);
+    let mut cache = seed::rng(Component::WriteCache);
+    let Some(path) = &args.arrays else {
+        let spec = ArraySpec {
+            name: args.raid.id().to_string(),
+            mount_point: args
+                .mount_point
+                .clone()
+                .context("--mount-point is required")?,
+            disk_dir: args.disk_dir.clone().context("--disk-dir is required")?,
+            fsname: Some(args.fsname.clone()),
+            ..ArraySpec::default()
+        };
+        let faults = fault_policy(args, &mut wear, &mut cache);
+        return mount_array(args, &spec, faults, metrics);
+    };
+
+    let arrays = ArraySet::load(path)?.arrays;
+    let results: Vec<Result<()>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = arrays
+            .iter()
+            .map(|spec| {
+                let faults = fault_policy(args, &mut wear, &mut cache);
+                let metrics = metrics.for_array(&spec.name);
+                scope.spawn(move || {
+                    raid_rs::metrics::set_thread_scope(Some(&spec.name));
+                    let res = mount_array(args, spec, faults, metrics)
+                        .with_context(|| format!("array {}", spec.name));
+                    if let Err(err) = &res {
+                        warn!("{err:#}");
+                    }
+                    res
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("array thread panicked")))
+            })
+            .collect()
+    });
+    results.into_iter().collect()
 }
 
-fn run_fuse_command(args: cli::FuseArgs, metrics: std::sync::Arc<MetricsEmitter>) -> Result<()> {
-    let cli::FuseArgs {
-        mount_point,
-        disk_dir,
-        raid,
-        disks,
-        disk_size,
-        metrics: _,
-        allow_other,
-    } = args;
+/// `fault_policy` builds the fault policy of one array, drawing the seeds of its
+/// wear model and write cache from the given generators.
+fn fault_policy(args: &cli::FuseArgs, wear: &mut StdRng, cache: &mut StdRng) -> FaultPolicy {
+    FaultPolicy {
+        degraded: DegradedPolicy::from(args.degraded),
+        uncorrectable_limit: args.uncorrectable_limit,
+        wear: args.wear_rate.map(|defects_per_gib| WearPlan {
+            seed: wear.random(),
+            defects_per_gib,
+            ..WearPlan::default()
+        }),
+        write_cache: args.write_cache.map(|capacity| WriteCachePlan {
+            seed: cache.random(),
+            capacity,
+            volatility: args.cache_volatility,
+        }),
+    }
+}
 
-    let disk_size = disk_size.max(1);
+/// `mount_array` mounts one array and serves it until it is unmounted; settings the
+/// spec leaves out come from the command line.
+#[allow(clippy::too_many_lines)]
+fn mount_array(
+    args: &cli::FuseArgs,
+    spec: &ArraySpec,
+    faults: FaultPolicy,
+    metrics: std::sync::Arc<MetricsEmitter>,
+) -> Result<()> {
+    let raid = spec.raid.unwrap_or(args.raid);
+    let disks = spec.disks.unwrap_or(args.disks);
+    volume::validate_geometry(raid, disks)?;
+    let disk_size = spec.disk_size.unwrap_or(args.disk_size).max(1);
+    let thin_size = spec.thin_size.or(args.thin_size);
+    let flags = MountFlags {
+        allow_other: args.allow_other,
+        allow_root: args.allow_root,
+        auto_unmount: args.auto_unmount,
+        default_permissions: args.default_permissions,
+        fsname: spec.fsname.clone().unwrap_or_else(|| spec.name.clone()),
+        subtype: args.subtype.clone(),
+        read_only: spec.read_only.unwrap_or(args.read_only),
+        qos: QosLimits {
+            iops: args.qos_iops,
+            mbps: args.qos_mbps,
+        },
+        qos_per_uid: args.qos_per_uid,
+        background_share: args.background_share,
+        write_buffer: args.write_buffer,
+        write_buffer_age: std::time::Duration::from_millis(args.write_buffer_ms),
+        raw_volume: args.raw_volume,
+        compress_size: spec.compress_size.or(args.compress_size),
+        write_hole: args.write_hole.into(),
+    };
+    let io = volume::disk_io(args.disk_io)?;
+    let schedule = spec
+        .failure_schedule
+        .as_ref()
+        .or(args.failure_schedule.as_ref())
+        .map(|path| FailureSchedule::load(path, disks))
+        .transpose()?
+        .unwrap_or_default();
+    health::require_mount();
 
     match (raid, disks) {
         (RaidMode::Raid0, 1) => run_fuse::<1, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 1) => Err(anyhow::anyhow!("raid mode requires at least 2 disks")),
         (_, 2) => run_fuse::<2, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 3) => run_fuse::<3, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 4) => run_fuse::<4, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 5) => run_fuse::<5, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 6) => run_fuse::<6, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 7) => run_fuse::<7, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         (_, 8) => run_fuse::<8, DEFAULT_CHUNK_SIZE>(
             raid,
-            &mount_point,
-            &disk_dir,
+            &spec.mount_point,
+            &spec.disk_dir,
             disk_size,
+            io,
+            thin_size,
+            args.read_ahead,
+            args.parity_cache,
+            faults,
+            schedule,
             metrics,
-            allow_other,
+            &flags,
         ),
         _ => Err(anyhow::anyhow!(
             "unsupported disk count {disks}; supported range is 1-8"
@@ -210,8 +485,8 @@ fn run_metrics_only(args: cli::MetricsArgs) -> Result<()> {
         let stats = metrics_task.await??;
 
         info!(
-            "metrics: exit: reconnects={}, send_errors={}, dropped_batches={}",
-            stats.reconnects, stats.send_errors, stats.dropped_batches
+            "metrics: exit: reconnects={}, send_errors={}, dropped_batches={}, spool_depth={}",
+            stats.reconnects, stats.send_errors, stats.dropped_batches, stats.spool_depth
         );
 
         Ok::<(), anyhow::Error>(())
@@ -237,42 +512,26 @@ async fn run_metrics_loop(
     args: cli::MetricsArgs,
     shutdown_rx: watch::Receiver<bool>,
 ) -> Result<SenderStats> {
-    let (tx, rx) = mpsc::channel::<metrics::MetricsBatch>(args.queue_cap);
+    let settings = reload::spawn(&args, shutdown_rx.clone())?;
+    let (sender_tx, rx) = mpsc::channel::<metrics::MetricsBatch>(args.queue_cap);
+    let tx = prometheus::attach(
+        args.prometheus_listen,
+        sender_tx,
+        args.queue_cap,
+        shutdown_rx.clone(),
+    )
+    .await?;
+    health::spawn(args.health_listen, shutdown_rx.clone()).await?;
 
     let generator = tokio::spawn(run_generator(
         tx,
         shutdown_rx.clone(),
         args.source_id.clone(),
-        Duration::from_millis(args.interval_ms),
         args.ops_per_tick,
+        settings,
     ));
 
-    let auth_token = args.auth_token.trim().to_string();
-    let auth_token = if auth_token.is_empty() {
-        None
-    } else {
-        Some(auth_token)
-    };
-
-    let rpc_timeout = if args.rpc_timeout_ms == 0 {
-        None
-    } else {
-        Some(Duration::from_millis(args.rpc_timeout_ms))
-    };
-
-    let sender_cfg = SenderConfig {
-        socket_path: args.socket_path.clone(),
-        connect_timeout: Duration::from_millis(args.connect_timeout_ms),
-        rpc_timeout,
-        backoff_initial: Duration::from_millis(args.backoff_initial_ms),
-        backoff_max: Duration::from_millis(args.backoff_max_ms),
-        jitter_ratio: args.jitter_ratio,
-        conn_buffer: args.conn_buffer,
-        shutdown_grace: Duration::from_millis(args.shutdown_grace_ms),
-        auth_token,
-    };
-
-    let mut sender_task = tokio::spawn(run_sender(rx, shutdown_rx.clone(), sender_cfg));
+    let mut sender_task = spawn_sink(&args, rx, shutdown_rx.clone());
 
     tokio::select! {
         res = &mut sender_task => {
@@ -303,8 +562,8 @@ async fn run_generator(
     tx: mpsc::Sender<metrics::MetricsBatch>,
     mut shutdown: watch::Receiver<bool>,
     source_id: String,
-    interval: Duration,
     ops_per_tick: u32,
+    mut settings: watch::Receiver<Settings>,
 ) {
     let disk_ids = vec!["disk0", "disk1", "disk2", "disk3"]
         .into_iter()
@@ -316,11 +575,12 @@ async fn run_generator(
         .map(ToString::to_string)
         .collect::<Vec<_>>();
 
-    let mut sim = SyntheticSimulator::new(disk_ids, raid_ids);
+    let Settings { interval, workload } = settings.borrow_and_update().clone();
+    let mut sim = SyntheticSimulator::new(disk_ids, raid_ids, workload);
 
     let mut seq_no: u64 = 1;
-    let mut ticker = tokio::time::interval(interval);
-    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut ticker = reload::ticker(interval);
+    let mut reloadable = true;
 
     let mut dropped: u64 = 0;
 
@@ -340,6 +600,15 @@ async fn run_generator(
                     }
                 }
             },
+            changed = settings.changed(), if reloadable => {
+                if changed.is_ok() {
+                    let Settings { interval, workload } = settings.borrow_and_update().clone();
+                    ticker = reload::ticker(interval);
+                    sim.set_workload(workload);
+                } else {
+                    reloadable = false;
+                }
+            },
             changed = shutdown.changed() => {
                 if changed.is_err() {
                     break;
@@ -363,15 +632,26 @@ async fn sigterm() {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::{FuseArgs, MetricsArgs, RaidMode};
+    use crate::cli::{
+        DegradedMode, DiskIoMode, FuseArgs, MetricsArgs, MetricsExporter, OtlpProtocol, RaidMode,
+        WriteHoleMode,
+    };
     use std::path::PathBuf;
 
     fn test_metrics_args() -> MetricsArgs {
         MetricsArgs {
             socket_path: "/tmp/metrics.sock".to_string(),
+            metrics_endpoint: None,
             source_id: "raid-cli-test".to_string(),
             interval_ms: 1000,
             ops_per_tick: 1,
+            workload: cli::WorkloadProfile::MixedVm,
+            workload_file: None,
+            config: None,
+            read_ratio: None,
+            burstiness: None,
+            error_probability: None,
+            zipf_skew: None,
             queue_cap: 1,
             conn_buffer: 1,
             connect_timeout_ms: 1,
@@ -381,6 +661,19 @@ mod tests {
             jitter_ratio: 0.0,
             shutdown_grace_ms: 1,
             auth_token: String::new(),
+            auth_token_file: None,
+            tls_ca_cert: None,
+            tls_client_cert: None,
+            tls_client_key: None,
+            spool_dir: None,
+            spool_max_bytes: 1,
+            prometheus_listen: None,
+            health_listen: None,
+            metrics_exporter: MetricsExporter::Gateway,
+            otlp_endpoint: "http://localhost:4317".to_string(),
+            otlp_protocol: OtlpProtocol::Grpc,
+            metrics_file: None,
+            metrics_file_format: None,
         }
     }
 
@@ -389,16 +682,42 @@ mod tests {
         let (tx, _rx) = mpsc::channel(1);
         let metrics = MetricsEmitter::new("raid1".to_string(), tx);
         let args = FuseArgs {
-            mount_point: PathBuf::from("/tmp/mount"),
-            disk_dir: PathBuf::from("/tmp/disks"),
+            mount_point: Some(PathBuf::from("/tmp/mount")),
+            disk_dir: Some(PathBuf::from("/tmp/disks")),
+            arrays: None,
             raid: RaidMode::Raid1,
             disks: 1,
             disk_size: 10,
+            disk_io: DiskIoMode::Mmap,
+            thin_size: None,
+            compress_size: None,
+            read_ahead: 0,
+            parity_cache: 0,
+            degraded: DegradedMode::FailFast,
+            uncorrectable_limit: None,
+            write_hole: WriteHoleMode::Unprotected,
+            failure_schedule: None,
+            wear_rate: None,
+            write_cache: None,
+            cache_volatility: 0.5,
             metrics: test_metrics_args(),
             allow_other: false,
+            allow_root: false,
+            auto_unmount: false,
+            default_permissions: false,
+            fsname: "raid-fuse".to_string(),
+            subtype: None,
+            read_only: false,
+            qos_iops: None,
+            qos_mbps: None,
+            qos_per_uid: false,
+            background_share: None,
+            write_buffer: None,
+            write_buffer_ms: 50,
+            raw_volume: false,
         };
 
-        let err = run_fuse_command(args, metrics).expect_err("expected error");
+        let err = run_fuse_command(&args, metrics).expect_err("expected error");
         assert!(
             err.to_string()
                 .contains("raid mode requires at least 2 disks")
@@ -410,16 +729,42 @@ mod tests {
         let (tx, _rx) = mpsc::channel(1);
         let metrics = MetricsEmitter::new("raid0".to_string(), tx);
         let args = FuseArgs {
-            mount_point: PathBuf::from("/tmp/mount"),
-            disk_dir: PathBuf::from("/tmp/disks"),
+            mount_point: Some(PathBuf::from("/tmp/mount")),
+            disk_dir: Some(PathBuf::from("/tmp/disks")),
+            arrays: None,
             raid: RaidMode::Raid0,
             disks: 9,
             disk_size: 10,
+            disk_io: DiskIoMode::Mmap,
+            thin_size: None,
+            compress_size: None,
+            read_ahead: 0,
+            parity_cache: 0,
+            degraded: DegradedMode::FailFast,
+            uncorrectable_limit: None,
+            write_hole: WriteHoleMode::Unprotected,
+            failure_schedule: None,
+            wear_rate: None,
+            write_cache: None,
+            cache_volatility: 0.5,
             metrics: test_metrics_args(),
             allow_other: false,
+            allow_root: false,
+            auto_unmount: false,
+            default_permissions: false,
+            fsname: "raid-fuse".to_string(),
+            subtype: None,
+            read_only: false,
+            qos_iops: None,
+            qos_mbps: None,
+            qos_per_uid: false,
+            background_share: None,
+            write_buffer: None,
+            write_buffer_ms: 50,
+            raw_volume: false,
         };
 
-        let err = run_fuse_command(args, metrics).expect_err("expected error");
+        let err = run_fuse_command(&args, metrics).expect_err("expected error");
         assert!(err.to_string().contains("unsupported disk count 9"));
     }
 }
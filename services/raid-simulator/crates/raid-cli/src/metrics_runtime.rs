@@ -1,23 +1,40 @@
 //! Runtime wiring for translating simulator events into metrics batches.
 
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 use prost_types::Timestamp;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
 use tracing::warn;
 
-use raid_rs::metrics::{DiskOp, IoOpType, MetricsSink, RaidOp};
-use raid_rs::retention::volume::DiskStatus;
+use raid_rs::metrics::{DiskOp, IoClass, IoOpType, MetricsSink, RaidOp, compression_totals};
+use raid_rs::retention::array::RebuildStatus;
+use raid_rs::retention::disk::DiskHealth;
+use raid_rs::retention::volume::{
+    BalanceProgress, CheckReport, DiskChange, DiskStatus, StripeCheck, ThinUsage, VolumeEvent,
+    WriteHolePolicy, WriteHoleStats,
+};
 
-use crate::cli::MetricsArgs;
+use crate::cli::{MetricsArgs, MetricsExporter};
+use crate::health;
+use crate::otlp::{OtlpConfig, run_otlp_exporter};
 use crate::pb::metrics;
-use crate::sender::{SenderConfig, SenderStats, run_sender};
+use crate::prometheus;
+use crate::reload::{self, Settings};
+use crate::sender::{DroppedEvents, SenderConfig, SenderStats, run_sender};
+use crate::tcp::TlsFiles;
+
+/// Fraction of the event channel, as a divisor, reserved for state events.
+const STATE_RESERVE_DIVISOR: usize = 4;
 
 /// `FuseOpType` identifies the kind of FUSE operation.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FuseOpType {
     Read,
     Write,
@@ -26,29 +43,104 @@ pub enum FuseOpType {
 }
 
 /// `FuseOp` captures a FUSE-level operation sample.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FuseOp {
     pub op: FuseOpType,
+    pub inode: u64,
+    pub name_hash: u64,
     pub bytes: u64,
     pub latency_seconds: f64,
     pub error: bool,
 }
 
+/// `SpaceUsage` captures a capacity and space-usage sample of a mounted filesystem.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpaceUsage {
+    pub capacity_bytes: u64,
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+    pub file_count: u64,
+    /// File writes held in the write buffer, not yet on the array.
+    pub dirty_bytes: u64,
+    /// Blocks preserved by copy-on-write for snapshots, outside `capacity_bytes`.
+    pub snapshot_used_bytes: u64,
+    pub snapshot_pool_bytes: u64,
+}
+
 /// `MetricsEvent` describes events emitted by the simulator for batching.
 #[derive(Clone, Debug)]
 pub enum MetricsEvent {
     DiskOp(DiskOp),
-    RaidOp { raid_id: String, op: RaidOp },
+    RaidOp {
+        raid_id: String,
+        op: RaidOp,
+    },
     FuseOp(FuseOp),
-    DiskState(metrics::DiskState),
+    VolumeState(metrics::VolumeState),
+    DiskHealth {
+        disk_id: String,
+        missing: bool,
+        needs_rebuild: bool,
+    },
+    QueueDepth {
+        disk_id: String,
+        queue_depth: u64,
+    },
+    DiskWear {
+        disk_id: String,
+        health: DiskHealth,
+    },
+    RebuildSources {
+        disk_id: String,
+        chunks: u64,
+    },
     RaidState(metrics::RaidState),
+    PoolUsage {
+        raid_id: String,
+        used_bytes: u64,
+        capacity_bytes: u64,
+    },
 }
 
 /// `MetricsEmitter` forwards simulator events into an async channel.
 #[derive(Clone)]
 pub struct MetricsEmitter {
     raid_id: String,
+    /// Disk identifiers are prefixed with the RAID identifier, as when the process
+    /// runs several arrays.
+    qualify_disks: bool,
+    /// Emitters of the arrays added by `for_array`, by RAID identifier.
+    arrays: Arc<RwLock<HashMap<String, Arc<Self>>>>,
     tx: mpsc::Sender<MetricsEvent>,
+    pool_used: Arc<AtomicU64>,
+    pool_capacity: Arc<AtomicU64>,
+    check_stripes: Arc<AtomicU64>,
+    check_repairable: Arc<AtomicU64>,
+    check_uncorrectable: Arc<AtomicU64>,
+    missing_disks: Arc<AtomicU64>,
+    pre_fail_disks: Arc<AtomicU64>,
+    rebuilding: Arc<AtomicBool>,
+    uncorrectable: Arc<AtomicU64>,
+    read_only: Arc<AtomicBool>,
+    rebuild_total: Arc<AtomicU64>,
+    rebuild_done: Arc<AtomicU64>,
+    rebuild_rate: Arc<AtomicU64>,
+    rebuild_eta: Arc<AtomicU64>,
+    scrub_total: Arc<AtomicU64>,
+    scrub_done: Arc<AtomicU64>,
+    scrub_mismatches: Arc<AtomicU64>,
+    stale_members: Arc<AtomicU64>,
+    balance_total: Arc<AtomicU64>,
+    balance_done: Arc<AtomicU64>,
+    balance_rate: Arc<AtomicU64>,
+    /// Write-hole policy of the volume and its counters, once reported.
+    write_hole: Arc<Mutex<Option<(WriteHolePolicy, WriteHoleStats)>>>,
+    /// Channel slots kept free of op samples for state events.
+    state_reserve: usize,
+    dropped_disk_ops: Arc<AtomicU64>,
+    dropped_raid_ops: Arc<AtomicU64>,
+    dropped_fuse_ops: Arc<AtomicU64>,
+    dropped_states: Arc<AtomicU64>,
 }
 
 impl MetricsEmitter {
@@ -58,7 +150,114 @@ impl MetricsEmitter {
     /// * `raid_id` - Identifier of the RAID volume.
     /// * `tx` - Channel sender for metrics events.
     pub fn new(raid_id: String, tx: mpsc::Sender<MetricsEvent>) -> Arc<Self> {
-        Arc::new(Self { raid_id, tx })
+        Arc::new(Self {
+            raid_id,
+            qualify_disks: false,
+            arrays: Arc::default(),
+            state_reserve: tx.max_capacity() / STATE_RESERVE_DIVISOR,
+            tx,
+            pool_used: Arc::new(AtomicU64::new(0)),
+            pool_capacity: Arc::new(AtomicU64::new(0)),
+            check_stripes: Arc::new(AtomicU64::new(0)),
+            check_repairable: Arc::new(AtomicU64::new(0)),
+            check_uncorrectable: Arc::new(AtomicU64::new(0)),
+            missing_disks: Arc::new(AtomicU64::new(0)),
+            pre_fail_disks: Arc::new(AtomicU64::new(0)),
+            rebuilding: Arc::new(AtomicBool::new(false)),
+            uncorrectable: Arc::new(AtomicU64::new(0)),
+            read_only: Arc::new(AtomicBool::new(false)),
+            rebuild_total: Arc::new(AtomicU64::new(0)),
+            rebuild_done: Arc::new(AtomicU64::new(0)),
+            rebuild_rate: Arc::new(AtomicU64::new(0)),
+            rebuild_eta: Arc::new(AtomicU64::new(0)),
+            scrub_total: Arc::new(AtomicU64::new(0)),
+            scrub_done: Arc::new(AtomicU64::new(0)),
+            scrub_mismatches: Arc::new(AtomicU64::new(0)),
+            stale_members: Arc::new(AtomicU64::new(0)),
+            balance_total: Arc::new(AtomicU64::new(0)),
+            balance_done: Arc::new(AtomicU64::new(0)),
+            balance_rate: Arc::new(AtomicU64::new(0)),
+            write_hole: Arc::default(),
+            dropped_disk_ops: Arc::new(AtomicU64::new(0)),
+            dropped_raid_ops: Arc::new(AtomicU64::new(0)),
+            dropped_fuse_ops: Arc::new(AtomicU64::new(0)),
+            dropped_states: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// `for_array` returns the emitter of another array sharing this emitter's channel.
+    ///
+    /// The new emitter qualifies its disk identifiers with `raid_id`, and operations
+    /// recorded on threads labelled `raid_id` with `raid_rs::metrics::set_thread_scope`
+    /// are routed to it.
+    ///
+    /// # Arguments
+    /// * `raid_id` - Identifier of the array.
+    pub fn for_array(&self, raid_id: &str) -> Arc<Self> {
+        let mut array = Arc::unwrap_or_clone(Self::new(raid_id.to_string(), self.tx.clone()));
+        array.qualify_disks = true;
+        array.dropped_disk_ops = self.dropped_disk_ops.clone();
+        array.dropped_raid_ops = self.dropped_raid_ops.clone();
+        array.dropped_fuse_ops = self.dropped_fuse_ops.clone();
+        array.dropped_states = self.dropped_states.clone();
+        let array = Arc::new(array);
+        self.arrays
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(raid_id.to_string(), array.clone());
+        array
+    }
+
+    /// `scoped` returns the emitter of the array labelling the current thread, if any.
+    fn scoped(&self) -> Option<Arc<Self>> {
+        let scope = raid_rs::metrics::thread_scope()?;
+        self.arrays
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&*scope)
+            .cloned()
+    }
+
+    fn disk_id(&self, disk: &str) -> String {
+        if self.qualify_disks {
+            format!("{}/{disk}", self.raid_id)
+        } else {
+            disk.to_string()
+        }
+    }
+
+    /// `dropped` returns the events shed so far because the channel was full.
+    pub fn dropped(&self) -> DroppedEvents {
+        DroppedEvents {
+            disk_ops: self.dropped_disk_ops.load(Ordering::Relaxed),
+            raid_ops: self.dropped_raid_ops.load(Ordering::Relaxed),
+            fuse_ops: self.dropped_fuse_ops.load(Ordering::Relaxed),
+            states: self.dropped_states.load(Ordering::Relaxed),
+        }
+    }
+
+    /// `enqueue` sends an event, shedding per-op samples first when the channel fills up.
+    ///
+    /// Op samples stop short of the reserved slots, so state changes still get through
+    /// while a burst of IO saturates the channel.
+    fn enqueue(&self, event: MetricsEvent) {
+        let op_counter = match &event {
+            MetricsEvent::DiskOp(_) => Some(&self.dropped_disk_ops),
+            MetricsEvent::RaidOp { .. } => Some(&self.dropped_raid_ops),
+            MetricsEvent::FuseOp(_) => Some(&self.dropped_fuse_ops),
+            _ => None,
+        };
+        if let Some(counter) = op_counter
+            && self.tx.capacity() <= self.state_reserve
+        {
+            counter.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.tx.try_send(event) {
+            op_counter
+                .unwrap_or(&self.dropped_states)
+                .fetch_add(1, Ordering::Relaxed);
+        }
     }
 
     /// `record_fuse_op` enqueues a FUSE operation event.
@@ -66,7 +265,24 @@ impl MetricsEmitter {
     /// # Arguments
     /// * `op` - FUSE operation to record.
     pub fn record_fuse_op(&self, op: FuseOp) {
-        let _ = self.tx.try_send(MetricsEvent::FuseOp(op));
+        self.enqueue(MetricsEvent::FuseOp(op));
+    }
+
+    /// `record_space_usage` enqueues a capacity and space-usage sample.
+    ///
+    /// # Arguments
+    /// * `usage` - Space usage of the mounted filesystem.
+    pub fn record_space_usage(&self, usage: SpaceUsage) {
+        self.enqueue(MetricsEvent::VolumeState(metrics::VolumeState {
+            raid_id: self.raid_id.clone(),
+            capacity_bytes: usage.capacity_bytes,
+            used_bytes: usage.used_bytes,
+            free_bytes: usage.free_bytes,
+            file_count: usage.file_count,
+            dirty_bytes: usage.dirty_bytes,
+            snapshot_used_bytes: usage.snapshot_used_bytes,
+            snapshot_pool_bytes: usage.snapshot_pool_bytes,
+        }));
     }
 
     /// `record_disk_status` enqueues a disk status update.
@@ -74,20 +290,63 @@ impl MetricsEmitter {
     /// # Arguments
     /// * `status` - Disk status summary.
     pub fn record_disk_status(&self, status: DiskStatus) {
-        let disk_id = format!("disk{}", status.index);
-        let queue_depth = if status.missing {
-            -1.0
-        } else if status.needs_rebuild {
-            1.0
-        } else {
-            0.0
-        };
-        let _ = self
-            .tx
-            .try_send(MetricsEvent::DiskState(metrics::DiskState {
-                disk_id,
+        let bit = 1u64.checked_shl(u32::try_from(status.index).unwrap_or(u32::MAX));
+        if let Some(bit) = bit {
+            if status.missing {
+                self.missing_disks.fetch_or(bit, Ordering::Relaxed);
+            } else {
+                self.missing_disks.fetch_and(!bit, Ordering::Relaxed);
+            }
+        }
+        self.enqueue(MetricsEvent::DiskHealth {
+            disk_id: self.disk_id(&format!("disk{}", status.index)),
+            missing: status.missing,
+            needs_rebuild: status.needs_rebuild,
+        });
+    }
+
+    /// `record_queue_depths` enqueues the number of operations each disk is serving.
+    ///
+    /// # Arguments
+    /// * `depths` - In-flight operations per disk, indexed like the array.
+    pub fn record_queue_depths(&self, depths: &[u64]) {
+        for (index, &queue_depth) in depths.iter().enumerate() {
+            self.enqueue(MetricsEvent::QueueDepth {
+                disk_id: self.disk_id(&format!("disk{index}")),
                 queue_depth,
-            }));
+            });
+        }
+    }
+
+    /// `record_disk_healths` enqueues the SMART-like attributes of every disk.
+    ///
+    /// A warning is logged when a disk starts predicting its own failure.
+    ///
+    /// # Arguments
+    /// * `healths` - Health of each disk, indexed like the array.
+    pub fn record_disk_healths(&self, healths: &[DiskHealth]) {
+        for (index, &health) in healths.iter().enumerate() {
+            let bit = 1u64.checked_shl(u32::try_from(index).unwrap_or(u32::MAX));
+            if let Some(bit) = bit {
+                let before = if health.pre_fail {
+                    self.pre_fail_disks.fetch_or(bit, Ordering::Relaxed)
+                } else {
+                    self.pre_fail_disks.fetch_and(!bit, Ordering::Relaxed)
+                };
+                if health.pre_fail && before & bit == 0 {
+                    warn!(
+                        disk = index,
+                        grown_defects = health.grown_defects,
+                        reallocated_sectors = health.reallocated_sectors,
+                        "disk predicts its own failure; replace it before it fails"
+                    );
+                }
+            }
+            self.enqueue(MetricsEvent::DiskWear {
+                disk_id: self.disk_id(&format!("disk{index}")),
+                health,
+            });
+        }
     }
 
     /// `record_raid_state` enqueues a RAID status update.
@@ -97,48 +356,292 @@ impl MetricsEmitter {
     /// * `rebuild_in_progress` - Whether rebuild is ongoing.
     /// * `progress` - RAID1 resync progress value.
     pub fn record_raid_state(&self, failed_disks: u32, rebuild_in_progress: bool, progress: f64) {
+        health::set_array_state(&self.raid_id, failed_disks > 0, rebuild_in_progress);
+        let write_hole = *self
+            .write_hole
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let (write_hole_policy, write_hole) = write_hole.map_or_else(
+            || (String::new(), WriteHoleStats::default()),
+            |(policy, stats)| (policy.name().to_string(), stats),
+        );
         let state = metrics::RaidState {
             raid_id: self.raid_id.clone(),
             raid1_resync_progress: progress,
             degraded: failed_disks > 0,
             failed_disks,
             rebuild_in_progress,
+            pool_used_bytes: self.pool_used.load(Ordering::Relaxed),
+            pool_capacity_bytes: self.pool_capacity.load(Ordering::Relaxed),
+            check_stripes_checked: self.check_stripes.load(Ordering::Relaxed),
+            check_repairable_stripes: self.check_repairable.load(Ordering::Relaxed),
+            check_uncorrectable_stripes: self.check_uncorrectable.load(Ordering::Relaxed),
+            uncorrectable_stripes: self.uncorrectable.load(Ordering::Relaxed),
+            read_only: self.read_only.load(Ordering::Relaxed),
+            rebuild_stripes_total: self.rebuild_total.load(Ordering::Relaxed),
+            rebuild_stripes_done: self.rebuild_done.load(Ordering::Relaxed),
+            rebuild_bytes_per_second: f64::from_bits(self.rebuild_rate.load(Ordering::Relaxed)),
+            rebuild_eta_seconds: f64::from_bits(self.rebuild_eta.load(Ordering::Relaxed)),
+            scrub_stripes_total: self.scrub_total.load(Ordering::Relaxed),
+            scrub_stripes_done: self.scrub_done.load(Ordering::Relaxed),
+            scrub_mismatches: self.scrub_mismatches.load(Ordering::Relaxed),
+            stale_members: self.stale_members.load(Ordering::Relaxed),
+            balance_bytes_total: self.balance_total.load(Ordering::Relaxed),
+            balance_bytes_done: self.balance_done.load(Ordering::Relaxed),
+            balance_bytes_per_second: f64::from_bits(self.balance_rate.load(Ordering::Relaxed)),
+            write_hole_policy,
+            torn_stripes: write_hole.torn_stripes,
+            write_hole_replayed_stripes: write_hole.replayed_stripes,
+            full_stripe_rejected_writes: write_hole.rejected_writes,
         };
-        let _ = self.tx.try_send(MetricsEvent::RaidState(state));
+        self.enqueue(MetricsEvent::RaidState(state));
+    }
+
+    /// `record_rebuild_progress` enqueues a RAID state carrying rebuild progress and ETA,
+    /// followed by the chunks each disk has supplied to the rebuild.
+    ///
+    /// # Arguments
+    /// * `failed_disks` - Count of failed disks.
+    /// * `status` - Progress of the running rebuild pass.
+    /// * `stripe_bytes` - Bytes repaired per stripe, used for the byte rate.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn record_rebuild_progress(
+        &self,
+        failed_disks: u32,
+        status: &RebuildStatus,
+        stripe_bytes: u64,
+    ) {
+        let remaining = status.total_stripes.saturating_sub(status.done_stripes);
+        let eta = if status.stripes_per_second > 0.0 {
+            remaining as f64 / status.stripes_per_second
+        } else {
+            0.0
+        };
+        let rate = status.stripes_per_second * stripe_bytes as f64;
+        self.rebuild_total
+            .store(status.total_stripes, Ordering::Relaxed);
+        self.rebuild_done
+            .store(status.done_stripes, Ordering::Relaxed);
+        self.rebuild_rate.store(rate.to_bits(), Ordering::Relaxed);
+        self.rebuild_eta.store(eta.to_bits(), Ordering::Relaxed);
+        self.rebuilding.store(true, Ordering::Relaxed);
+        self.record_raid_state(failed_disks, true, status.percent / 100.0);
+        for (index, &chunks) in status.source_chunks.iter().enumerate() {
+            self.enqueue(MetricsEvent::RebuildSources {
+                disk_id: self.disk_id(&format!("disk{index}")),
+                chunks,
+            });
+        }
+    }
+
+    /// `record_scrub_progress` enqueues a RAID state carrying scrub progress.
+    ///
+    /// # Arguments
+    /// * `failed_disks` - Count of failed disks.
+    /// * `done` - Stripes verified so far.
+    /// * `total` - Stripes the scrub covers.
+    /// * `mismatches` - Inconsistent stripes found so far.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn record_scrub_progress(&self, failed_disks: u32, done: u64, total: u64, mismatches: u64) {
+        self.scrub_total.store(total, Ordering::Relaxed);
+        self.scrub_done.store(done, Ordering::Relaxed);
+        self.scrub_mismatches.store(mismatches, Ordering::Relaxed);
+        let progress = if total == 0 {
+            1.0
+        } else {
+            done as f64 / total as f64
+        };
+        let rebuilding = self.rebuilding.load(Ordering::Relaxed);
+        self.record_raid_state(failed_disks, rebuilding, progress);
+    }
+
+    /// `record_balance_progress` enqueues a RAID state carrying balance progress.
+    ///
+    /// # Arguments
+    /// * `failed_disks` - Count of failed disks.
+    /// * `progress` - Data moved by the running balance.
+    /// * `bytes_per_second` - Average balance rate so far.
+    pub fn record_balance_progress(
+        &self,
+        failed_disks: u32,
+        progress: BalanceProgress,
+        bytes_per_second: f64,
+    ) {
+        self.balance_total
+            .store(progress.total_bytes, Ordering::Relaxed);
+        self.balance_done
+            .store(progress.moved_bytes, Ordering::Relaxed);
+        self.balance_rate
+            .store(bytes_per_second.to_bits(), Ordering::Relaxed);
+        let rebuilding = self.rebuilding.load(Ordering::Relaxed);
+        self.record_raid_state(failed_disks, rebuilding, progress.fraction());
+    }
+
+    /// `record_member_rebuild` enqueues a RAID state showing a hot-added member as
+    /// rebuilding until the reshape onto it is `done`.
+    ///
+    /// # Arguments
+    /// * `failed_disks` - Count of failed disks.
+    /// * `progress` - Data moved onto the grown array.
+    /// * `done` - Whether the reshape has finished.
+    pub fn record_member_rebuild(&self, failed_disks: u32, progress: BalanceProgress, done: bool) {
+        self.rebuilding.store(!done, Ordering::Relaxed);
+        self.record_raid_state(failed_disks, !done, progress.fraction());
+    }
+
+    /// `record_write_hole` enqueues a RAID state carrying the write-hole counters.
+    ///
+    /// # Arguments
+    /// * `failed_disks` - Count of failed disks.
+    /// * `policy` - How the volume protects stripe writes.
+    /// * `stats` - Write-hole counters since the volume was opened.
+    pub fn record_write_hole(
+        &self,
+        failed_disks: u32,
+        policy: WriteHolePolicy,
+        stats: WriteHoleStats,
+    ) {
+        *self
+            .write_hole
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some((policy, stats));
+        let rebuilding = self.rebuilding.load(Ordering::Relaxed);
+        self.record_raid_state(failed_disks, rebuilding, 0.0);
+    }
+
+    /// `record_pool_usage` enqueues thin pool usage and attaches it to later RAID states.
+    ///
+    /// # Arguments
+    /// * `usage` - Pool usage of a thin volume.
+    pub fn record_pool_usage(&self, usage: ThinUsage) {
+        let used_bytes = usage.used_bytes();
+        let capacity_bytes = usage.pool_bytes();
+        self.pool_used.store(used_bytes, Ordering::Relaxed);
+        self.pool_capacity.store(capacity_bytes, Ordering::Relaxed);
+        self.enqueue(MetricsEvent::PoolUsage {
+            raid_id: self.raid_id.clone(),
+            used_bytes,
+            capacity_bytes,
+        });
+    }
+
+    /// `record_volume_event` translates a volume event into disk and RAID state updates.
+    ///
+    /// The failed disk count comes from the disk statuses recorded so far.
+    ///
+    /// # Arguments
+    /// * `event` - Event received from a volume subscription.
+    pub fn record_volume_event(&self, event: &VolumeEvent) {
+        let failed = self.missing_disks.load(Ordering::Relaxed).count_ones();
+        match event {
+            VolumeEvent::Disk {
+                change: DiskChange::Stale,
+                status,
+            } => {
+                self.record_disk_status(*status);
+                self.stale_members.fetch_add(1, Ordering::Relaxed);
+                let rebuilding = self.rebuilding.load(Ordering::Relaxed);
+                self.record_raid_state(failed, rebuilding, 0.0);
+            }
+            VolumeEvent::Disk { status, .. } => self.record_disk_status(*status),
+            VolumeEvent::RebuildStarted { stripes } => {
+                self.rebuilding.store(true, Ordering::Relaxed);
+                self.rebuild_total.store(*stripes, Ordering::Relaxed);
+                self.rebuild_done.store(0, Ordering::Relaxed);
+                self.rebuild_rate.store(0f64.to_bits(), Ordering::Relaxed);
+                self.rebuild_eta.store(0f64.to_bits(), Ordering::Relaxed);
+                self.record_raid_state(failed, true, 0.0);
+            }
+            VolumeEvent::RebuildFinished { .. } => {
+                self.rebuilding.store(false, Ordering::Relaxed);
+                self.rebuild_done.store(
+                    self.rebuild_total.load(Ordering::Relaxed),
+                    Ordering::Relaxed,
+                );
+                self.rebuild_eta.store(0f64.to_bits(), Ordering::Relaxed);
+                self.record_raid_state(failed, false, 1.0);
+            }
+            VolumeEvent::CheckFinding { verdict, .. } => {
+                if matches!(
+                    verdict,
+                    StripeCheck::Repairable { .. } | StripeCheck::Uncorrectable
+                ) {
+                    self.scrub_mismatches.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            VolumeEvent::Uncorrectable { stripes, read_only } => {
+                self.uncorrectable.store(*stripes, Ordering::Relaxed);
+                self.read_only.store(*read_only, Ordering::Relaxed);
+                let rebuilding = self.rebuilding.load(Ordering::Relaxed);
+                self.record_raid_state(failed, rebuilding, 0.0);
+            }
+        }
+    }
+
+    /// `record_check_report` attaches the result of a consistency check to later RAID states.
+    ///
+    /// # Arguments
+    /// * `report` - Summary of the finished check.
+    pub fn record_check_report(&self, report: CheckReport) {
+        self.check_stripes
+            .store(report.stripes_checked, Ordering::Relaxed);
+        self.check_repairable
+            .store(report.repairable_stripes, Ordering::Relaxed);
+        self.check_uncorrectable
+            .store(report.uncorrectable_stripes, Ordering::Relaxed);
     }
 }
 
 impl MetricsSink for MetricsEmitter {
-    fn record_disk_op(&self, op: DiskOp) {
-        let _ = self.tx.try_send(MetricsEvent::DiskOp(op));
+    fn record_disk_op(&self, mut op: DiskOp) {
+        if let Some(array) = self.scoped() {
+            return array.record_disk_op(op);
+        }
+        op.disk_id = self.disk_id(&op.disk_id);
+        self.enqueue(MetricsEvent::DiskOp(op));
     }
 
     fn record_raid_op(&self, op: RaidOp) {
-        let _ = self.tx.try_send(MetricsEvent::RaidOp {
+        if let Some(array) = self.scoped() {
+            return array.record_raid_op(op);
+        }
+        self.enqueue(MetricsEvent::RaidOp {
             raid_id: self.raid_id.clone(),
             op,
         });
     }
 }
 
-/// `run_event_metrics_loop` batches events and streams them to the metrics gateway.
+/// `spawn_sink` starts the task shipping metrics batches to the selected exporter.
 ///
 /// # Arguments
 /// * `args` - Metrics configuration arguments.
+/// * `rx` - Receiver for metrics batches.
 /// * `shutdown_rx` - Watch channel signaling shutdown.
-/// * `event_rx` - Event receiver for metrics events.
 ///
 /// # Returns
-/// Sender statistics from the run.
-///
-/// # Errors
-/// Returns an error if the sender task fails.
-pub async fn run_event_metrics_loop(
-    args: MetricsArgs,
+/// Handle resolving to the sink statistics.
+pub fn spawn_sink(
+    args: &MetricsArgs,
+    rx: mpsc::Receiver<metrics::MetricsBatch>,
     shutdown_rx: watch::Receiver<bool>,
-    event_rx: mpsc::Receiver<MetricsEvent>,
-) -> Result<SenderStats> {
-    let (tx, rx) = mpsc::channel::<metrics::MetricsBatch>(args.queue_cap);
+) -> JoinHandle<SenderStats> {
+    if args.metrics_exporter == MetricsExporter::Otlp {
+        let cfg = OtlpConfig {
+            endpoint: args.otlp_endpoint.clone(),
+            protocol: args.otlp_protocol,
+            source_id: args.source_id.clone(),
+            interval: Duration::from_millis(args.interval_ms),
+            connect_timeout: Duration::from_millis(args.connect_timeout_ms),
+            request_timeout: Duration::from_millis(if args.rpc_timeout_ms > 0 {
+                args.rpc_timeout_ms
+            } else {
+                args.interval_ms
+            }),
+            shutdown_grace: Duration::from_millis(args.shutdown_grace_ms),
+        };
+        return tokio::spawn(run_otlp_exporter(rx, shutdown_rx, cfg));
+    }
 
     let auth_token = args.auth_token.trim().to_string();
     let auth_token = if auth_token.is_empty() {
@@ -155,6 +658,7 @@ pub async fn run_event_metrics_loop(
 
     let sender_cfg = SenderConfig {
         socket_path: args.socket_path.clone(),
+        endpoint: args.metrics_endpoint.clone(),
         connect_timeout: Duration::from_millis(args.connect_timeout_ms),
         rpc_timeout,
         backoff_initial: Duration::from_millis(args.backoff_initial_ms),
@@ -163,15 +667,54 @@ pub async fn run_event_metrics_loop(
         conn_buffer: args.conn_buffer,
         shutdown_grace: Duration::from_millis(args.shutdown_grace_ms),
         auth_token,
+        auth_token_file: args.auth_token_file.clone(),
+        tls: TlsFiles {
+            ca_cert: args.tls_ca_cert.clone(),
+            client_cert: args.tls_client_cert.clone(),
+            client_key: args.tls_client_key.clone(),
+        },
+        spool_dir: args.spool_dir.clone(),
+        spool_max_bytes: args.spool_max_bytes,
     };
 
-    let mut sender_task = tokio::spawn(run_sender(rx, shutdown_rx.clone(), sender_cfg));
+    tokio::spawn(run_sender(rx, shutdown_rx, sender_cfg))
+}
+
+/// `run_event_metrics_loop` batches events and streams them to the metrics gateway.
+///
+/// # Arguments
+/// * `args` - Metrics configuration arguments.
+/// * `shutdown_rx` - Watch channel signaling shutdown.
+/// * `event_rx` - Event receiver for metrics events.
+///
+/// # Returns
+/// Sender statistics from the run.
+///
+/// # Errors
+/// Returns an error if the sender task fails.
+pub async fn run_event_metrics_loop(
+    args: MetricsArgs,
+    shutdown_rx: watch::Receiver<bool>,
+    event_rx: mpsc::Receiver<MetricsEvent>,
+) -> Result<SenderStats> {
+    let (sender_tx, rx) = mpsc::channel::<metrics::MetricsBatch>(args.queue_cap);
+    let tx = prometheus::attach(
+        args.prometheus_listen,
+        sender_tx,
+        args.queue_cap,
+        shutdown_rx.clone(),
+    )
+    .await?;
+    health::spawn(args.health_listen, shutdown_rx.clone()).await?;
+
+    let settings = reload::spawn(&args, shutdown_rx.clone())?;
+    let mut sender_task = spawn_sink(&args, rx, shutdown_rx.clone());
     let generator_task = tokio::spawn(run_event_generator(
         tx,
         shutdown_rx.clone(),
         event_rx,
         args.source_id.clone(),
-        Duration::from_millis(args.interval_ms),
+        settings,
     ));
 
     tokio::select! {
@@ -188,20 +731,22 @@ pub async fn run_event_metrics_loop(
     }
 }
 
+#[allow(clippy::too_many_lines)]
 async fn run_event_generator(
     tx: mpsc::Sender<metrics::MetricsBatch>,
     mut shutdown: watch::Receiver<bool>,
     mut event_rx: mpsc::Receiver<MetricsEvent>,
     source_id: String,
-    interval: Duration,
+    mut settings: watch::Receiver<Settings>,
 ) {
     let mut seq_no: u64 = 1;
-    let mut ticker = tokio::time::interval(interval);
-    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut ticker = reload::ticker(settings.borrow_and_update().interval);
+    let mut reloadable = true;
 
     let mut dropped: u64 = 0;
     let mut disk_state_cache: HashMap<String, metrics::DiskState> = HashMap::new();
     let mut raid_state_cache: HashMap<String, metrics::RaidState> = HashMap::new();
+    let mut volume_state_cache: HashMap<String, metrics::VolumeState> = HashMap::new();
 
     loop {
         tokio::select! {
@@ -223,12 +768,27 @@ async fn run_event_generator(
                         MetricsEvent::FuseOp(op) => {
                             fuse_ops.push(to_fuse_op(&op));
                         }
-                        MetricsEvent::DiskState(state) => {
-                            disk_state_cache.insert(state.disk_id.clone(), state);
+                        MetricsEvent::VolumeState(state) => {
+                            volume_state_cache.insert(state.raid_id.clone(), state);
+                        }
+                        MetricsEvent::DiskHealth { disk_id, missing, needs_rebuild } => {
+                            merge_disk_health(&mut disk_state_cache, disk_id, missing, needs_rebuild);
+                        }
+                        MetricsEvent::QueueDepth { disk_id, queue_depth } => {
+                            merge_queue_depth(&mut disk_state_cache, disk_id, queue_depth);
+                        }
+                        MetricsEvent::DiskWear { disk_id, health } => {
+                            merge_disk_wear(&mut disk_state_cache, disk_id, health);
+                        }
+                        MetricsEvent::RebuildSources { disk_id, chunks } => {
+                            disk_state_entry(&mut disk_state_cache, disk_id).rebuild_source_chunks = chunks;
                         }
                         MetricsEvent::RaidState(state) => {
                             raid_state_cache.insert(state.raid_id.clone(), state);
                         }
+                        MetricsEvent::PoolUsage { raid_id, used_bytes, capacity_bytes } => {
+                            merge_pool_usage(&mut raid_state_cache, raid_id, used_bytes, capacity_bytes);
+                        }
                     }
                 }
 
@@ -237,12 +797,13 @@ async fn run_event_generator(
                     if !disk_state_cache.contains_key(&disk_id) {
                         disk_states.push(metrics::DiskState {
                             disk_id,
-                            queue_depth: 0.0,
+                            ..Default::default()
                         });
                     }
                 }
 
                 let raid_states = raid_state_cache.values().cloned().collect::<Vec<_>>();
+                let volume_states = volume_state_cache.values().cloned().collect::<Vec<_>>();
 
                 let process = process_sample();
 
@@ -251,6 +812,7 @@ async fn run_event_generator(
                     && fuse_ops.is_empty()
                     && disk_states.is_empty()
                     && raid_states.is_empty()
+                    && volume_states.is_empty()
                     && process.is_none()
                 {
                     continue;
@@ -265,6 +827,7 @@ async fn run_event_generator(
                     raid_ops,
                     raid_states,
                     fuse_ops,
+                    volume_states,
                     process,
                 };
                 seq_no = seq_no.wrapping_add(1);
@@ -279,6 +842,13 @@ async fn run_event_generator(
                     }
                 }
             },
+            changed = settings.changed(), if reloadable => {
+                if changed.is_ok() {
+                    ticker = reload::ticker(settings.borrow_and_update().interval);
+                } else {
+                    reloadable = false;
+                }
+            },
             changed = shutdown.changed() => {
                 if changed.is_err() {
                     break;
@@ -323,9 +893,76 @@ fn to_raid_op(raid_id: &str, op: RaidOp) -> metrics::RaidOp {
         raid3_parity_read: false,
         raid3_parity_write: false,
         raid3_partial_stripe_write: false,
+        degraded: op.degraded,
+        region: 0,
+        read_ahead_hit_stripes: op.read_ahead_hits,
+        read_ahead_miss_stripes: op.read_ahead_misses,
+        parity_cache_hit_stripes: op.parity_cache_hits,
+        parity_cache_miss_stripes: op.parity_cache_misses,
+        background: op.class == IoClass::Background,
     }
 }
 
+fn merge_pool_usage(
+    cache: &mut HashMap<String, metrics::RaidState>,
+    raid_id: String,
+    used_bytes: u64,
+    capacity_bytes: u64,
+) {
+    let state = cache
+        .entry(raid_id.clone())
+        .or_insert_with(|| metrics::RaidState {
+            raid_id,
+            ..Default::default()
+        });
+    state.pool_used_bytes = used_bytes;
+    state.pool_capacity_bytes = capacity_bytes;
+}
+
+fn merge_disk_health(
+    cache: &mut HashMap<String, metrics::DiskState>,
+    disk_id: String,
+    missing: bool,
+    needs_rebuild: bool,
+) {
+    let state = disk_state_entry(cache, disk_id);
+    state.missing = missing;
+    state.needs_rebuild = needs_rebuild;
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn merge_queue_depth(
+    cache: &mut HashMap<String, metrics::DiskState>,
+    disk_id: String,
+    queue_depth: u64,
+) {
+    disk_state_entry(cache, disk_id).queue_depth = queue_depth as f64;
+}
+
+fn merge_disk_wear(
+    cache: &mut HashMap<String, metrics::DiskState>,
+    disk_id: String,
+    health: DiskHealth,
+) {
+    let state = disk_state_entry(cache, disk_id);
+    state.grown_defects = health.grown_defects;
+    state.reallocated_sectors = health.reallocated_sectors;
+    state.temperature_celsius = f64::from(health.temperature_celsius);
+    state.pre_fail = health.pre_fail;
+}
+
+fn disk_state_entry(
+    cache: &mut HashMap<String, metrics::DiskState>,
+    disk_id: String,
+) -> &mut metrics::DiskState {
+    cache
+        .entry(disk_id.clone())
+        .or_insert_with(|| metrics::DiskState {
+            disk_id,
+            ..Default::default()
+        })
+}
+
 const fn to_fuse_op(op: &FuseOp) -> metrics::FuseOp {
     let op_type = match op.op {
         FuseOpType::Read => metrics::FuseOpType::FuseOpRead,
@@ -336,6 +973,8 @@ const fn to_fuse_op(op: &FuseOp) -> metrics::FuseOp {
     metrics::FuseOp {
         op: op_type as i32,
         bytes: op.bytes,
+        inode: op.inode,
+        name_hash: op.name_hash,
         latency_seconds: op.latency_seconds,
         error: op.error,
     }
@@ -372,10 +1011,13 @@ fn process_sample() -> Option<metrics::ProcessSample> {
     let resident_memory_bytes = u64::try_from(usage.ru_maxrss)
         .unwrap_or(0)
         .saturating_mul(1024);
+    let compression = compression_totals();
 
     Some(metrics::ProcessSample {
         cpu_seconds,
         resident_memory_bytes,
+        compression_ratio: compression.ratio(),
+        compression_cpu_seconds: compression.cpu_seconds,
     })
 }
 
@@ -394,11 +1036,12 @@ fn timeval_to_secs(tv: libc::timeval) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::workload::Workload;
     use std::collections::HashMap;
     use tokio::time::timeout;
 
     #[tokio::test]
-    async fn metrics_emitter_records_disk_status_queue_depths() {
+    async fn metrics_emitter_records_disk_health() {
         let (tx, mut rx) = mpsc::channel(10);
         let emitter = MetricsEmitter::new("raid1".to_string(), tx);
 
@@ -418,25 +1061,336 @@ mod tests {
             needs_rebuild: false,
         });
 
-        let mut states = Vec::new();
+        let mut health = HashMap::new();
         for _ in 0..3 {
             match rx.recv().await {
-                Some(MetricsEvent::DiskState(state)) => states.push(state),
-                other => panic!("expected DiskState event, got {other:?}"),
+                Some(MetricsEvent::DiskHealth {
+                    disk_id,
+                    missing,
+                    needs_rebuild,
+                }) => {
+                    health.insert(disk_id, (missing, needs_rebuild));
+                }
+                other => panic!("expected DiskHealth event, got {other:?}"),
             }
         }
 
-        let mut queue_depths = HashMap::new();
-        for state in states {
-            queue_depths.insert(state.disk_id, state.queue_depth);
+        assert_eq!(health.get("disk0"), Some(&(true, false)));
+        assert_eq!(health.get("disk1"), Some(&(false, true)));
+        assert_eq!(health.get("disk2"), Some(&(false, false)));
+    }
+
+    #[test]
+    fn metrics_emitter_sheds_op_samples_before_state_events() {
+        let (tx, mut rx) = mpsc::channel(8);
+        let emitter = MetricsEmitter::new("raid1".to_string(), tx);
+
+        for _ in 0..10 {
+            emitter.record_disk_op(DiskOp {
+                disk_id: "disk0".to_string(),
+                op: IoOpType::Write,
+                bytes: 1,
+                latency_seconds: 0.0,
+                error: false,
+            });
+        }
+        emitter.record_fuse_op(FuseOp {
+            op: FuseOpType::Open,
+            inode: 0,
+            name_hash: 0,
+            bytes: 0,
+            latency_seconds: 0.0,
+            error: false,
+        });
+        for _ in 0..3 {
+            emitter.record_raid_state(1, true, 0.5);
         }
 
-        assert_eq!(queue_depths.get("disk0"), Some(&-1.0));
-        assert_eq!(queue_depths.get("disk1"), Some(&1.0));
-        assert_eq!(queue_depths.get("disk2"), Some(&0.0));
+        assert_eq!(
+            emitter.dropped(),
+            DroppedEvents {
+                disk_ops: 4,
+                raid_ops: 0,
+                fuse_ops: 1,
+                states: 1,
+            }
+        );
+        let mut states = 0;
+        while let Ok(event) = rx.try_recv() {
+            if matches!(event, MetricsEvent::RaidState(_)) {
+                states += 1;
+            }
+        }
+        assert_eq!(states, 2);
     }
 
     #[tokio::test]
+    async fn metrics_emitter_records_queue_depths() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid1".to_string(), tx);
+
+        emitter.record_queue_depths(&[0, 3]);
+
+        for expected in [("disk0", 0), ("disk1", 3)] {
+            match rx.recv().await {
+                Some(MetricsEvent::QueueDepth {
+                    disk_id,
+                    queue_depth,
+                }) => assert_eq!((disk_id.as_str(), queue_depth), expected),
+                other => panic!("expected QueueDepth event, got {other:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_emitter_records_disk_healths() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid1".to_string(), tx);
+        let worn = DiskHealth {
+            grown_defects: 5,
+            reallocated_sectors: 4,
+            temperature_celsius: 38,
+            pre_fail: true,
+        };
+
+        emitter.record_disk_healths(&[DiskHealth::default(), worn]);
+
+        for expected in [("disk0", DiskHealth::default()), ("disk1", worn)] {
+            match rx.recv().await {
+                Some(MetricsEvent::DiskWear { disk_id, health }) => {
+                    assert_eq!((disk_id.as_str(), health), expected);
+                }
+                other => panic!("expected DiskWear event, got {other:?}"),
+            }
+        }
+        assert_eq!(emitter.pre_fail_disks.load(Ordering::Relaxed), 0b10);
+    }
+
+    #[tokio::test]
+    async fn metrics_emitter_attaches_pool_usage_to_raid_state() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid3".to_string(), tx);
+
+        emitter.record_pool_usage(ThinUsage {
+            virtual_bytes: 8192,
+            stripe_bytes: 8,
+            allocated_stripes: 3,
+            pool_stripes: 100,
+        });
+        emitter.record_raid_state(0, false, 1.0);
+
+        match rx.recv().await {
+            Some(MetricsEvent::PoolUsage {
+                raid_id,
+                used_bytes,
+                capacity_bytes,
+            }) => {
+                assert_eq!(raid_id, "raid3");
+                assert_eq!(used_bytes, 24);
+                assert_eq!(capacity_bytes, 800);
+            }
+            other => panic!("expected PoolUsage event, got {other:?}"),
+        }
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert_eq!(state.pool_used_bytes, 24);
+                assert_eq!(state.pool_capacity_bytes, 800);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_emitter_translates_volume_events() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid3".to_string(), tx);
+
+        emitter.record_volume_event(&VolumeEvent::Disk {
+            change: DiskChange::Failed,
+            status: DiskStatus {
+                index: 2,
+                missing: true,
+                needs_rebuild: false,
+            },
+        });
+        emitter.record_volume_event(&VolumeEvent::RebuildStarted { stripes: 8 });
+
+        match rx.recv().await {
+            Some(MetricsEvent::DiskHealth {
+                disk_id, missing, ..
+            }) => {
+                assert_eq!(disk_id, "disk2");
+                assert!(missing);
+            }
+            other => panic!("expected DiskHealth event, got {other:?}"),
+        }
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert_eq!(state.failed_disks, 1);
+                assert!(state.rebuild_in_progress);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+
+        emitter.record_volume_event(&VolumeEvent::Uncorrectable {
+            stripes: 3,
+            read_only: true,
+        });
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert_eq!(state.uncorrectable_stripes, 3);
+                assert!(state.read_only);
+                assert!(state.rebuild_in_progress);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+
+        emitter.record_volume_event(&VolumeEvent::Disk {
+            change: DiskChange::Stale,
+            status: DiskStatus {
+                index: 0,
+                missing: false,
+                needs_rebuild: true,
+            },
+        });
+        assert!(matches!(
+            rx.recv().await,
+            Some(MetricsEvent::DiskHealth {
+                needs_rebuild: true,
+                ..
+            })
+        ));
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => assert_eq!(state.stale_members, 1),
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_emitter_reports_rebuild_and_scrub_progress() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid5".to_string(), tx);
+
+        emitter.record_rebuild_progress(
+            1,
+            &RebuildStatus {
+                done_stripes: 25,
+                total_stripes: 100,
+                percent: 25.0,
+                stripes_per_second: 5.0,
+                source_chunks: vec![25, 0, 25],
+            },
+            4096,
+        );
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert!(state.rebuild_in_progress);
+                assert_eq!(state.rebuild_stripes_total, 100);
+                assert_eq!(state.rebuild_stripes_done, 25);
+                assert!((state.rebuild_bytes_per_second - 20_480.0).abs() < f64::EPSILON);
+                assert!((state.rebuild_eta_seconds - 15.0).abs() < f64::EPSILON);
+                assert!((state.raid1_resync_progress - 0.25).abs() < f64::EPSILON);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+        for expected in [("disk0", 25), ("disk1", 0), ("disk2", 25)] {
+            match rx.recv().await {
+                Some(MetricsEvent::RebuildSources { disk_id, chunks }) => {
+                    assert_eq!((disk_id.as_str(), chunks), expected);
+                }
+                other => panic!("expected RebuildSources event, got {other:?}"),
+            }
+        }
+
+        emitter.record_volume_event(&VolumeEvent::RebuildFinished { disks: vec![1] });
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert!(!state.rebuild_in_progress);
+                assert_eq!(state.rebuild_stripes_done, 100);
+                assert!(state.rebuild_eta_seconds.abs() < f64::EPSILON);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+
+        emitter.record_volume_event(&VolumeEvent::CheckFinding {
+            stripe_index: 3,
+            verdict: StripeCheck::Uncorrectable,
+        });
+        emitter.record_scrub_progress(0, 10, 40, 2);
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert_eq!(state.scrub_stripes_total, 40);
+                assert_eq!(state.scrub_stripes_done, 10);
+                assert_eq!(state.scrub_mismatches, 2);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_emitter_reports_write_hole_counters_per_policy() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid3".to_string(), tx);
+
+        emitter.record_raid_state(0, false, 0.0);
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => assert!(state.write_hole_policy.is_empty()),
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+
+        emitter.record_write_hole(
+            0,
+            WriteHolePolicy::FullStripe,
+            WriteHoleStats {
+                torn_stripes: 1,
+                replayed_stripes: 2,
+                rejected_writes: 3,
+                ..WriteHoleStats::default()
+            },
+        );
+        match rx.recv().await {
+            Some(MetricsEvent::RaidState(state)) => {
+                assert_eq!(state.write_hole_policy, "full_stripe");
+                assert_eq!(state.torn_stripes, 1);
+                assert_eq!(state.write_hole_replayed_stripes, 2);
+                assert_eq!(state.full_stripe_rejected_writes, 3);
+            }
+            other => panic!("expected RaidState event, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn metrics_emitter_records_space_usage() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let emitter = MetricsEmitter::new("raid0".to_string(), tx);
+
+        emitter.record_space_usage(SpaceUsage {
+            capacity_bytes: 1 << 20,
+            used_bytes: 4096,
+            free_bytes: (1 << 20) - 4096,
+            file_count: 3,
+            dirty_bytes: 512,
+            snapshot_used_bytes: 256,
+            snapshot_pool_bytes: 4096,
+        });
+
+        match rx.recv().await {
+            Some(MetricsEvent::VolumeState(state)) => {
+                assert_eq!(state.raid_id, "raid0");
+                assert_eq!(state.capacity_bytes, 1 << 20);
+                assert_eq!(state.used_bytes, 4096);
+                assert_eq!(state.free_bytes, (1 << 20) - 4096);
+                assert_eq!(state.file_count, 3);
+                assert_eq!(state.dirty_bytes, 512);
+                assert_eq!(state.snapshot_used_bytes, 256);
+                assert_eq!(state.snapshot_pool_bytes, 4096);
+            }
+            other => panic!("expected VolumeState event, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    #[allow(clippy::too_many_lines)]
     async fn run_event_generator_batches_ops_and_states() {
         let (batch_tx, mut batch_rx) = mpsc::channel(1);
         let (shutdown_tx, shutdown_rx) = watch::channel(false);
@@ -447,7 +1401,11 @@ mod tests {
             shutdown_rx,
             event_rx,
             "source-1".to_string(),
-            Duration::from_millis(5),
+            watch::channel(Settings {
+                interval: Duration::from_millis(5),
+                workload: Workload::default(),
+            })
+            .1,
         ));
 
         event_tx
@@ -461,10 +1419,30 @@ mod tests {
             .await
             .unwrap();
         event_tx
-            .send(MetricsEvent::DiskState(metrics::DiskState {
+            .send(MetricsEvent::QueueDepth {
                 disk_id: "disk1".to_string(),
-                queue_depth: 1.0,
-            }))
+                queue_depth: 1,
+            })
+            .await
+            .unwrap();
+        event_tx
+            .send(MetricsEvent::DiskHealth {
+                disk_id: "disk1".to_string(),
+                missing: false,
+                needs_rebuild: true,
+            })
+            .await
+            .unwrap();
+        event_tx
+            .send(MetricsEvent::DiskWear {
+                disk_id: "disk1".to_string(),
+                health: DiskHealth {
+                    grown_defects: 2,
+                    reallocated_sectors: 2,
+                    temperature_celsius: 33,
+                    pre_fail: false,
+                },
+            })
             .await
             .unwrap();
         event_tx
@@ -475,6 +1453,12 @@ mod tests {
                     bytes: 12,
                     latency_seconds: 0.25,
                     error: false,
+                    degraded: true,
+                    read_ahead_hits: 3,
+                    read_ahead_misses: 1,
+                    parity_cache_hits: 0,
+                    parity_cache_misses: 0,
+                    class: IoClass::Foreground,
                 },
             })
             .await
@@ -482,6 +1466,8 @@ mod tests {
         event_tx
             .send(MetricsEvent::FuseOp(FuseOp {
                 op: FuseOpType::Write,
+                inode: 7,
+                name_hash: 0xab,
                 bytes: 128,
                 latency_seconds: 0.04,
                 error: true,
@@ -502,10 +1488,14 @@ mod tests {
 
         let mut disk_states = HashMap::new();
         for state in batch.disk_states {
-            disk_states.insert(state.disk_id, state.queue_depth);
+            disk_states.insert(state.disk_id.clone(), state);
         }
-        assert_eq!(disk_states.get("disk0"), Some(&0.0));
-        assert_eq!(disk_states.get("disk1"), Some(&1.0));
+        assert!(disk_states["disk0"].queue_depth.abs() < f64::EPSILON);
+        assert!((disk_states["disk1"].queue_depth - 1.0).abs() < f64::EPSILON);
+        assert!(disk_states["disk1"].needs_rebuild);
+        assert!(!disk_states["disk1"].missing);
+        assert_eq!(disk_states["disk1"].reallocated_sectors, 2);
+        assert!((disk_states["disk1"].temperature_celsius - 33.0).abs() < f64::EPSILON);
 
         let raid_op = &batch.raid_ops[0];
         assert_eq!(raid_op.raid_id, "raid1");
@@ -517,10 +1507,17 @@ mod tests {
         assert!(!raid_op.raid3_parity_read);
         assert!(!raid_op.raid3_parity_write);
         assert!(!raid_op.raid3_partial_stripe_write);
+        assert!(raid_op.degraded);
+        assert!(!raid_op.background);
+        assert_eq!(raid_op.read_ahead_hit_stripes, 3);
+        assert_eq!(raid_op.read_ahead_miss_stripes, 1);
+        assert_eq!(raid_op.parity_cache_hit_stripes, 0);
 
         let fuse_op = &batch.fuse_ops[0];
         assert_eq!(fuse_op.op, metrics::FuseOpType::FuseOpWrite as i32);
         assert_eq!(fuse_op.bytes, 128);
+        assert_eq!(fuse_op.inode, 7);
+        assert_eq!(fuse_op.name_hash, 0xab);
         assert!((fuse_op.latency_seconds - 0.04).abs() < f64::EPSILON);
         assert!(fuse_op.error);
 
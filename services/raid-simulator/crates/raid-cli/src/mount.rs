@@ -1,92 +1,381 @@
 //! FUSE mount helpers for RAID-backed filesystems.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use fuser::MountOption;
+use fuser::{MountOption, SessionUnmounter};
 use raid_rs::layout::stripe::raid0::RAID0;
 use raid_rs::layout::stripe::raid1::RAID1;
 use raid_rs::layout::stripe::raid3::RAID3;
 use raid_rs::layout::stripe::traits::stripe::Stripe;
-use raid_rs::retention::array::Array;
-use raid_rs::retention::volume::Volume;
+use raid_rs::metrics::IO_TRACE_TARGET;
+use raid_rs::retention::array::InFlight;
+use raid_rs::retention::disk::{DiskIo, WearPlan, WriteCachePlan};
+use raid_rs::retention::volume::{
+    BATCH_STRIPES, BalanceCheckpoint, BalanceProgress, DegradedPolicy, DiskChange, DynVolume,
+    ReshapeCheckpoint, Volume, VolumeEvent, WriteHolePolicy,
+};
+use raid_rs::simulator::SimulatorBuilder;
+use tokio::signal::unix::{SignalKind, signal};
+use tracing::trace_span;
 
-use crate::cli::RaidMode;
-use crate::fs::{ENTRY_SIZE, Entry, FsState, HEADER_SIZE, Header, MAX_FILES, RaidFs};
+use crate::cli::{DegradedMode, RaidMode};
+use crate::commands::disk_image_path;
+use crate::fs::{
+    Alignment, ENTRY_SIZE, Entry, FsState, GrowState, HEADER_SIZE, Header, MAX_FILES, OpenFiles,
+    QosLimits, Quota, RaidFs, Scrub, ScrubState, Throttle, WriteBuffer, decode_entries,
+    read_quotas, reclaim_orphans,
+};
 use crate::metrics_runtime::MetricsEmitter;
+use crate::schedule::FailureSchedule;
+use crate::superblock;
+use crate::volume::{DeclusteredLayout, open_balance_source, open_reshape_target};
 
-fn disk_paths<const D: usize>(disk_dir: &Path) -> Result<[String; D]> {
-    std::fs::create_dir_all(disk_dir)
-        .with_context(|| format!("failed to create disk directory {}", disk_dir.display()))?;
-    Ok(std::array::from_fn(|i| {
-        disk_dir
-            .join(format!("disk-{i}.img"))
-            .to_string_lossy()
-            .into_owned()
-    }))
+/// Interval between capacity and space-usage samples of a mounted filesystem.
+const SPACE_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Shortest interval between checks for buffered writes that have waited too long.
+const MIN_FLUSH_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Interval between checks for a scrub or grow started from the control file.
+const SCRUB_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Interval between queue-depth samples of the disks.
+const QUEUE_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// `FaultPolicy` bundles how members of a mounted volume wear out, how they cache
+/// writes and how the volume reacts to failing members.
+#[derive(Copy, Clone, Debug)]
+pub struct FaultPolicy {
+    pub degraded: DegradedPolicy,
+    /// Lost stripes after which writes are refused, if any.
+    pub uncorrectable_limit: Option<u64>,
+    /// Wear model of the members, if they wear out.
+    pub wear: Option<WearPlan>,
+    /// Drive write cache of the members, if they cache writes.
+    pub write_cache: Option<WriteCachePlan>,
 }
 
-#[allow(clippy::too_many_lines)]
-fn mount_volume<const D: usize, const N: usize, T>(
-    mount_point: &Path,
-    disk_dir: &Path,
-    disk_size: u64,
-    layout: T,
-    metrics: std::sync::Arc<MetricsEmitter>,
-    allow_other: bool,
-) -> Result<()>
-where
-    T: Stripe<D, N> + Send + 'static,
-{
-    std::fs::create_dir_all(mount_point)
-        .with_context(|| format!("failed to create mount point {}", mount_point.display()))?;
-    let paths = disk_paths::<D>(disk_dir)?;
-    let array = Array::<D, N>::init_array(&paths, disk_size);
-    let capacity = array.disk_len().saturating_mul(T::DATA as u64);
+/// `MountFlags` are the FUSE mount options of a mounted volume.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug, Default)]
+pub struct MountFlags {
+    /// Allow other users to access the mount (required for NFS export).
+    pub allow_other: bool,
+    /// Allow root to access the mount besides the mounting user.
+    pub allow_root: bool,
+    /// Unmount when the process exits without unmounting.
+    pub auto_unmount: bool,
+    /// Let the kernel check permissions against the reported file modes.
+    pub default_permissions: bool,
+    pub fsname: String,
+    pub subtype: Option<String>,
+    /// Refuse every change and leave a pending rebuild unwritten, so a suspect
+    /// array can be inspected without touching its disks.
+    pub read_only: bool,
+    /// Rate limits of file reads and writes.
+    pub qos: QosLimits,
+    /// Give every user its own rate limits instead of one shared budget.
+    pub qos_per_uid: bool,
+    /// Percent of the array's time rebuild work may take while files are in use.
+    pub background_share: Option<u8>,
+    /// Bytes of adjacent writes each file collects before they reach the array;
+    /// writes go straight through when unset.
+    pub write_buffer: Option<usize>,
+    /// How long buffered writes may wait for neighbours before they are flushed.
+    pub write_buffer_age: Duration,
+    /// Expose the whole volume as `volume.raw` and each member under `disks`.
+    pub raw_volume: bool,
+    /// Virtual size of a volume storing its blocks compressed, if it does.
+    pub compress_size: Option<u64>,
+    /// How stripe writes are protected against a crash between data and parity.
+    pub write_hole: WriteHolePolicy,
+}
+
+impl MountFlags {
+    #[must_use]
+    /// `options` returns the FUSE mount options the flags ask for.
+    pub fn options(&self) -> Vec<MountOption> {
+        let mut options = vec![
+            if self.read_only {
+                MountOption::RO
+            } else {
+                MountOption::RW
+            },
+            MountOption::FSName(self.fsname.clone()),
+        ];
+        if let Some(subtype) = &self.subtype {
+            options.push(MountOption::Subtype(subtype.clone()));
+        }
+        for (set, option) in [
+            (self.allow_other, MountOption::AllowOther),
+            (self.allow_root, MountOption::AllowRoot),
+            (self.auto_unmount, MountOption::AutoUnmount),
+            (self.default_permissions, MountOption::DefaultPermissions),
+        ] {
+            if set {
+                options.push(option);
+            }
+        }
+        options
+    }
+}
+
+impl From<DegradedMode> for DegradedPolicy {
+    fn from(mode: DegradedMode) -> Self {
+        match mode {
+            DegradedMode::FailFast => Self::FailFast,
+            DegradedMode::BestEffort => Self::BestEffort,
+            DegradedMode::ReadOnly => Self::ReadOnly,
+        }
+    }
+}
+
+/// `load_filesystem` reads the header, file table and quota table of a volume,
+/// formatting it first if it holds no filesystem yet.
+///
+/// Unless `read_only` is set, files left unlinked while open are freed.
+///
+/// # Errors
+/// Returns an error if the volume is too small for the metadata or its tables are corrupt.
+pub fn load_filesystem<const D: usize, const N: usize, T: Stripe<D, N>>(
+    volume: &mut Volume<D, N, T>,
+    read_only: bool,
+) -> Result<(Header, Vec<Entry>, Vec<Quota>)> {
+    let capacity = volume.logical_capacity_bytes();
     if capacity < RaidFs::<D, N, T>::data_start() + 1 {
         return Err(anyhow::anyhow!(
             "disk size too small for filesystem metadata"
         ));
     }
-    let mut volume = Volume::new(array, layout);
     let mut header_buf = [0u8; HEADER_SIZE];
     volume.read_bytes(0, &mut header_buf);
     let parsed_header = RaidFs::<D, N, T>::parse_header(&header_buf);
     let is_new_header = parsed_header.is_none();
     let mut header = parsed_header.unwrap_or_else(|| Header {
         next_free: RaidFs::<D, N, T>::data_start(),
+        quotas: false,
     });
     if header.next_free < RaidFs::<D, N, T>::data_start() {
         header.next_free = RaidFs::<D, N, T>::data_start();
     }
 
-    let mut entries = vec![Entry::empty(); MAX_FILES];
-    for (i, entry) in entries.iter_mut().enumerate().take(MAX_FILES) {
-        let mut buf = [0u8; ENTRY_SIZE];
-        let entry_offset = HEADER_SIZE as u64 + (i as u64 * ENTRY_SIZE as u64);
-        volume.read_bytes(entry_offset, &mut buf);
-        *entry = Entry::from_bytes(&buf);
-    }
-
-    if is_new_header {
+    let entries = if is_new_header && read_only {
+        vec![Entry::empty(); MAX_FILES]
+    } else if is_new_header {
         let header_bytes = RaidFs::<D, N, T>::header_bytes(&header);
         volume.write_bytes(0, &header_bytes);
-        for (i, entry) in entries.iter_mut().enumerate().take(MAX_FILES) {
+        let empty = Entry::empty().to_bytes();
+        for i in 0..MAX_FILES {
             let entry_offset = HEADER_SIZE as u64 + (i as u64 * ENTRY_SIZE as u64);
-            let empty = Entry::empty().to_bytes();
             volume.write_bytes(entry_offset, &empty);
-            *entry = Entry::empty();
         }
 
         volume.clear_needs_rebuild_all();
+        vec![Entry::empty(); MAX_FILES]
+    } else {
+        let mut table = vec![0u8; ENTRY_SIZE * MAX_FILES];
+        volume.read_bytes(HEADER_SIZE as u64, &mut table);
+        let mut entries = decode_entries(&table, &header).context("failed to load file table")?;
+        if !read_only {
+            let freed = reclaim_orphans(volume, &mut entries);
+            if freed > 0 {
+                tracing::info!("freed {freed} files unlinked while open");
+            }
+        }
+        entries
+    };
+    let quotas = read_quotas(volume, &header).context("failed to load quota table")?;
+    Ok((header, entries, quotas))
+}
+
+/// `open_state` opens the volume of a mount and loads its filesystem.
+///
+/// A read-only mount writes nothing to the members: the superblocks are left as
+/// `stamp` found them and the write-hole logs are not replayed.
+///
+/// # Returns
+/// The filesystem state and the events of the volume, subscribed before any
+/// member was marked stale.
+///
+/// # Errors
+/// Returns an error if the volume cannot be opened, its filesystem cannot be
+/// loaded, or an interrupted balance or grow cannot be resumed.
+#[allow(clippy::too_many_arguments)]
+fn open_state<const D: usize, const N: usize, T: Stripe<D, N>>(
+    disk_dir: &Path,
+    disk_size: u64,
+    io: DiskIo,
+    thin_size: Option<u64>,
+    read_ahead: u64,
+    parity_cache: usize,
+    faults: FaultPolicy,
+    stamp: &superblock::Stamp,
+    balance: Option<(Box<dyn DynVolume>, BalanceCheckpoint)>,
+    reshape: Option<(Box<dyn DynVolume>, ReshapeCheckpoint)>,
+    layout: T,
+    metrics: &MetricsEmitter,
+    flags: &MountFlags,
+) -> Result<(FsState<D, N, T>, std::sync::mpsc::Receiver<VolumeEvent>)> {
+    let mut builder = SimulatorBuilder::<D, N>::new(disk_dir)
+        .disk_size(disk_size)
+        .disk_io(io)
+        .degraded_policy(faults.degraded)
+        .uncorrectable_limit(faults.uncorrectable_limit)
+        .read_ahead(read_ahead)
+        .parity_cache(parity_cache)
+        .background_share(flags.background_share)
+        .read_only(flags.read_only);
+    if let Some(thin_size) = thin_size {
+        builder = builder.thin(thin_size);
+    }
+    if let Some(compress_size) = flags.compress_size {
+        builder = builder.compress(compress_size);
+    }
+    if let Some(plan) = faults.wear {
+        builder = builder.wear(plan);
+    }
+    if let Some(plan) = faults.write_cache {
+        builder = builder.write_cache(plan);
+    }
+    let mut volume = builder.build_with(layout, |volume| {
+        let mut header_buf = [0u8; HEADER_SIZE];
+        volume.read_bytes(0, &mut header_buf);
+        RaidFs::<D, N, T>::parse_header(&header_buf).map_or(0, |h| h.next_free)
+    })?;
+    if !flags.read_only {
+        stamp.write_pending(disk_dir)?;
     }
+    volume.set_write_hole_policy(flags.write_hole);
+    if let Some(usage) = volume.thin_usage() {
+        metrics.record_pool_usage(usage);
+    }
+    if let Some((source, checkpoint)) = balance {
+        volume.begin_balance(source, checkpoint.source_disks, checkpoint.position)?;
+        tracing::info!(
+            "balancing data from {} members, {} bytes moved so far",
+            checkpoint.source_disks,
+            checkpoint.position
+        );
+    }
+    if let Some(warning) = Alignment::new(&volume.geometry()).warning() {
+        tracing::warn!("{warning}");
+    }
+    let (header, entries, quotas) = load_filesystem(&mut volume, flags.read_only)?;
+
+    let events = volume.subscribe();
+    for &i in &stamp.stale {
+        tracing::warn!("disk {i} holds stale data; rebuilding it");
+        volume.mark_stale(i)?;
+    }
+    for &i in &stamp.rejoined {
+        tracing::warn!("disk {i} rejoined; resyncing the regions written while it was away");
+        volume.mark_rejoined(i)?;
+    }
+    let grow = match reshape {
+        Some((target, checkpoint)) => {
+            volume
+                .begin_reshape(target, checkpoint.target_disks, checkpoint.position)
+                .with_context(|| {
+                    format!(
+                        "cannot resume the online grow to {} members",
+                        checkpoint.target_disks
+                    )
+                })?;
+            tracing::info!(
+                "growing onto {} members, {} bytes moved so far",
+                checkpoint.target_disks,
+                checkpoint.position
+            );
+            GrowState::Running(checkpoint.target_disks)
+        }
+        None => GrowState::Idle,
+    };
 
-    let state = Arc::new(Mutex::new(FsState {
+    let state = FsState {
         volume,
         header,
         entries,
-    }));
+        write_buffer: WriteBuffer::new(flags.write_buffer, flags.write_buffer_age),
+        scrub: Scrub::default(),
+        grow,
+        hot_add: None,
+        quotas,
+        open_files: OpenFiles::default(),
+    };
+    Ok((state, events))
+}
+
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+fn mount_volume<const D: usize, const N: usize, T>(
+    mount_point: &Path,
+    disk_dir: &Path,
+    disk_size: u64,
+    io: DiskIo,
+    thin_size: Option<u64>,
+    read_ahead: u64,
+    parity_cache: usize,
+    faults: FaultPolicy,
+    schedule: FailureSchedule,
+    stamp: &superblock::Stamp,
+    balance: Option<(Box<dyn DynVolume>, BalanceCheckpoint)>,
+    reshape: Option<(Box<dyn DynVolume>, ReshapeCheckpoint)>,
+    layout: T,
+    metrics: std::sync::Arc<MetricsEmitter>,
+    flags: &MountFlags,
+) -> Result<()>
+where
+    T: Stripe<D, N> + Send + 'static,
+{
+    std::fs::create_dir_all(mount_point)
+        .with_context(|| format!("failed to create mount point {}", mount_point.display()))?;
+    let (state, events) = open_state(
+        disk_dir,
+        disk_size,
+        io,
+        thin_size,
+        read_ahead,
+        parity_cache,
+        faults,
+        stamp,
+        balance,
+        reshape,
+        layout,
+        &metrics,
+        flags,
+    )?;
+    spawn_queue_sampler(state.volume.in_flight(), metrics.clone());
+    let state = Arc::new(Mutex::new(state));
+
+    let metrics_events = metrics.clone();
+    let state_events = state.clone();
+    let read_only = flags.read_only;
+    spawn_in_scope(move || {
+        for event in events {
+            metrics_events.record_volume_event(&event);
+            let VolumeEvent::Disk { change, status } = event else {
+                continue;
+            };
+            if change == DiskChange::Stale || read_only {
+                continue;
+            }
+            let Ok(st) = state_events.lock() else {
+                return;
+            };
+            let members = st.volume.status().members;
+            drop(st);
+            let updated = if change == DiskChange::Rebuilt {
+                superblock::record_rebuilt(&members, status.index)
+            } else {
+                superblock::advance_generation(&members).map(drop)
+            };
+            if let Err(err) = updated {
+                tracing::warn!("failed to update superblocks: {err:#}");
+            }
+        }
+    });
 
     {
         let state_clone = state.clone();
@@ -97,77 +386,152 @@ where
         );
 
         let metrics_clone = metrics.clone();
-        std::thread::spawn(move || {
+        let read_only = flags.read_only;
+        spawn_in_scope(move || {
             let stripes = {
-                let Ok(st) = state_clone.lock() else {
+                let Ok(mut st) = state_clone.lock() else {
                     return;
                 };
                 if st.volume.logical_capacity_bytes() == 0 {
                     return;
                 }
-                if st.volume.any_needs_rebuild() {
-                    st.volume.stripes_needed_for_logical_end(rebuild_end)
+                if st.volume.any_needs_rebuild() && !read_only {
+                    st.volume.begin_rebuild(rebuild_end)
                 } else {
-                    0
+                    Vec::new()
                 }
             };
+            let total_stripes = stripes.len() as u64;
 
-            if stripes == 0 {
+            if total_stripes == 0 {
                 if let Ok(st) = state_clone.lock() {
                     record_status_snapshot(&metrics_clone, &st);
                 }
                 return;
             }
 
-            let mut last_reported = 0;
-            let report_every = (stripes / 100).max(1);
-
-            for s in 0..stripes {
-                if let Ok(mut st) = state_clone.lock() {
-                    st.volume.repair_stripe(s);
-                    if s + 1 >= last_reported + report_every || s + 1 == stripes {
-                        let completed = u32::try_from(s + 1).unwrap_or(u32::MAX);
-                        let total = u32::try_from(stripes).unwrap_or(u32::MAX).max(1);
-                        let progress = f64::from(completed) / f64::from(total);
-                        metrics_clone.record_raid_state(st.volume.failed_disks(), true, progress);
-                        for status in st.volume.disk_statuses() {
-                            metrics_clone.record_disk_status(status);
-                        }
-                        last_reported = s + 1;
+            let report_every = usize::try_from(total_stripes / 100).unwrap_or(usize::MAX);
+
+            'rebuild: for batch in stripes.chunks(report_every.max(1)) {
+                let _span = trace_span!(
+                    target: IO_TRACE_TARGET,
+                    "rebuild_batch",
+                    first_stripe = batch[0],
+                    stripes = batch.len()
+                )
+                .entered();
+                for run in batch.chunks(BATCH_STRIPES) {
+                    let Ok(mut st) = state_clone.lock() else {
+                        break 'rebuild;
+                    };
+                    let wait = st.volume.background_wait();
+                    if !wait.is_zero() {
+                        drop(st);
+                        std::thread::sleep(wait);
+                        let Ok(next) = state_clone.lock() else {
+                            break 'rebuild;
+                        };
+                        st = next;
                     }
-                } else {
-                    break;
+                    st.volume.repair_stripes(run);
+                }
+                if let Ok(st) = state_clone.lock()
+                    && let Some(rebuild) = st.volume.status().rebuild
+                {
+                    metrics_clone.record_rebuild_progress(
+                        st.volume.failed_disks(),
+                        &rebuild,
+                        st.volume.geometry().bytes_per_stripe as u64,
+                    );
                 }
             }
 
             if let Ok(mut st) = state_clone.lock() {
                 st.volume.clear_needs_rebuild_all();
-                metrics_clone.record_raid_state(st.volume.failed_disks(), false, 1.0);
-                for status in st.volume.disk_statuses() {
-                    metrics_clone.record_disk_status(status);
-                }
             }
         });
     }
 
+    if !schedule.is_empty() {
+        spawn_failure_schedule(schedule, state.clone(), metrics.clone());
+    }
+    spawn_space_sampler(state.clone(), metrics.clone());
+    if !flags.read_only {
+        spawn_scrubber(state.clone(), metrics.clone());
+        spawn_balancer(state.clone(), metrics.clone());
+        let target = GrowTarget {
+            mode: stamp.array.layout,
+            disk_dir: disk_dir.to_path_buf(),
+            disk_size,
+            io,
+        };
+        spawn_reshaper(state.clone(), target, metrics.clone());
+    }
+    if flags.write_buffer.is_some() {
+        spawn_write_flusher(state.clone(), flags.write_buffer_age);
+    }
+
     let fs = RaidFs {
         state,
-        capacity,
         metrics: Some(metrics),
+        file_io: Mutex::default(),
+        read_only: flags.read_only,
+        throttle: Mutex::new(Throttle::new(flags.qos, flags.qos_per_uid)),
+        raw_volume: flags.raw_volume,
     };
 
-    let mut options = vec![MountOption::RW, MountOption::FSName("raid-fuse".into())];
+    let mut session = fuser::Session::new(fs, mount_point, &flags.options())
+        .with_context(|| format!("failed to mount filesystem at {}", mount_point.display()))?;
+    unmount_on_signal(session.unmount_callable());
+    session
+        .run()
+        .with_context(|| format!("failed to serve filesystem at {}", mount_point.display()))
+}
 
-    if allow_other {
-        if allow_other_enabled() {
-            options.push(MountOption::AllowOther);
-        } else {
-            tracing::warn!("allow_other requested but not enabled in /etc/fuse.conf");
-        }
-    }
+/// `spawn_in_scope` runs `f` on a new thread that records its operations under
+/// the array label of the calling thread.
+fn spawn_in_scope<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let scope = raid_rs::metrics::thread_scope();
+    std::thread::spawn(move || {
+        raid_rs::metrics::set_thread_scope(scope.as_deref());
+        f();
+    });
+}
 
-    fuser::mount2(fs, mount_point, &options)
-        .with_context(|| format!("failed to mount filesystem at {}", mount_point.display()))
+/// `unmount_on_signal` unmounts the filesystem once the process receives SIGTERM or
+/// SIGINT.
+///
+/// The session then returns from `run` and the filesystem flushes its volume, so
+/// stopping the container does not leave the mount point hanging.
+fn unmount_on_signal(mut unmounter: SessionUnmounter) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            tracing::warn!("cannot watch for termination signals: {err}");
+            return;
+        }
+    };
+    std::thread::spawn(move || {
+        runtime.block_on(async {
+            let Ok(mut sigterm) = signal(SignalKind::terminate()) else {
+                tracing::warn!("cannot install SIGTERM handler");
+                return;
+            };
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => tracing::info!("shutdown: ctrl-c"),
+                _ = sigterm.recv() => tracing::info!("shutdown: SIGTERM"),
+            }
+        });
+        if let Err(err) = unmounter.unmount() {
+            tracing::warn!("failed to unmount: {err}");
+        }
+    });
 }
 
 fn record_status_snapshot<const D: usize, const N: usize, T>(
@@ -179,6 +543,7 @@ fn record_status_snapshot<const D: usize, const N: usize, T>(
     for status in state.volume.disk_statuses() {
         metrics.record_disk_status(status);
     }
+    metrics.record_disk_healths(&state.volume.disk_healths());
     metrics.record_raid_state(
         state.volume.failed_disks(),
         state.volume.any_needs_rebuild(),
@@ -186,14 +551,385 @@ fn record_status_snapshot<const D: usize, const N: usize, T>(
     );
 }
 
-fn allow_other_enabled() -> bool {
-    let Ok(conf) = std::fs::read_to_string("/etc/fuse.conf") else {
-        return false;
-    };
-    conf.lines()
-        .map(str::trim)
-        .filter(|line| !line.is_empty() && !line.starts_with('#'))
-        .any(|line| line == "user_allow_other")
+/// `spawn_space_sampler` periodically reports capacity and space usage of the filesystem,
+/// and the write-hole counters of its volume.
+fn spawn_space_sampler<const D: usize, const N: usize, T>(
+    state: Arc<Mutex<FsState<D, N, T>>>,
+    metrics: Arc<MetricsEmitter>,
+) where
+    T: Stripe<D, N> + Send + 'static,
+{
+    spawn_in_scope(move || {
+        loop {
+            let (usage, failed, policy, write_hole) = {
+                let Ok(st) = state.lock() else {
+                    return;
+                };
+                (
+                    RaidFs::<D, N, T>::space_usage(&st),
+                    st.volume.failed_disks(),
+                    st.volume.write_hole_policy(),
+                    st.volume.write_hole_stats(),
+                )
+            };
+            metrics.record_space_usage(usage);
+            metrics.record_write_hole(failed, policy, write_hole);
+            std::thread::sleep(SPACE_SAMPLE_INTERVAL);
+        }
+    });
+}
+
+/// `spawn_write_flusher` flushes buffered file writes once they have waited `max_age`.
+fn spawn_write_flusher<const D: usize, const N: usize, T>(
+    state: Arc<Mutex<FsState<D, N, T>>>,
+    max_age: Duration,
+) where
+    T: Stripe<D, N> + Send + 'static,
+{
+    let interval = (max_age / 2).max(MIN_FLUSH_INTERVAL);
+    spawn_in_scope(move || {
+        loop {
+            std::thread::sleep(interval);
+            let Ok(mut st) = state.lock() else {
+                return;
+            };
+            let _ = st.flush_expired(std::time::Instant::now());
+        }
+    });
+}
+
+/// `spawn_scrubber` works through scrubs started from the control file.
+///
+/// Each batch of stripes takes the filesystem lock on its own, and the thread
+/// waits outside the lock whenever the background share asks it to.
+fn spawn_scrubber<const D: usize, const N: usize, T>(
+    state: Arc<Mutex<FsState<D, N, T>>>,
+    metrics: Arc<MetricsEmitter>,
+) where
+    T: Stripe<D, N> + Send + 'static,
+{
+    spawn_in_scope(move || {
+        let mut reported = None;
+        loop {
+            let Ok(mut st) = state.lock() else {
+                return;
+            };
+            if st.scrub.state != ScrubState::Running {
+                drop(st);
+                std::thread::sleep(SCRUB_POLL_INTERVAL);
+                continue;
+            }
+            let wait = st.volume.background_wait();
+            if !wait.is_zero() {
+                drop(st);
+                std::thread::sleep(wait);
+                continue;
+            }
+            st.scrub_batch();
+            let scrub = st.scrub.clone();
+            let failed = st.volume.failed_disks();
+            drop(st);
+
+            let percent = scrub.next * 100 / scrub.total.max(1);
+            if scrub.state != ScrubState::Running || reported != Some(percent) {
+                reported = Some(percent);
+                metrics.record_scrub_progress(
+                    failed,
+                    scrub.next,
+                    scrub.total,
+                    scrub.repaired + scrub.uncorrectable,
+                );
+            }
+        }
+    });
+}
+
+/// `spawn_balancer` moves the data of a grown array onto its new geometry.
+///
+/// Like the scrubber, each batch takes the filesystem lock on its own and the
+/// thread waits outside the lock whenever the background share asks it to. The
+/// thread ends once the balance finishes, or right away if none is running.
+fn spawn_balancer<const D: usize, const N: usize, T>(
+    state: Arc<Mutex<FsState<D, N, T>>>,
+    metrics: Arc<MetricsEmitter>,
+) where
+    T: Stripe<D, N> + Send + 'static,
+{
+    spawn_in_scope(move || {
+        let started = std::time::Instant::now();
+        let Some(first) = state
+            .lock()
+            .ok()
+            .and_then(|st| st.volume.balance_progress())
+        else {
+            return;
+        };
+        let mut reported = None;
+        loop {
+            let Ok(mut st) = state.lock() else {
+                return;
+            };
+            let wait = st.volume.background_wait();
+            if !wait.is_zero() {
+                drop(st);
+                std::thread::sleep(wait);
+                continue;
+            }
+            if let Err(err) = st.volume.balance_step(BATCH_STRIPES) {
+                tracing::warn!("balance stopped: {err}");
+                return;
+            }
+            let progress = st.volume.balance_progress();
+            if progress.is_none() {
+                st.move_quota_table();
+            }
+            let failed = st.volume.failed_disks();
+            drop(st);
+
+            let progress = progress.unwrap_or(BalanceProgress {
+                moved_bytes: first.total_bytes,
+                total_bytes: first.total_bytes,
+            });
+            let moved = progress.moved_bytes - first.moved_bytes;
+            #[allow(clippy::cast_precision_loss)]
+            let rate = moved as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON);
+            let done = progress.moved_bytes >= progress.total_bytes;
+            let percent = progress.moved_bytes * 100 / progress.total_bytes.max(1);
+            if done || reported != Some(percent) {
+                reported = Some(percent);
+                metrics.record_balance_progress(failed, progress, rate);
+            }
+            if done {
+                tracing::info!("balance finished; the added capacity is available");
+                return;
+            }
+        }
+    });
+}
+
+/// `GrowTarget` locates the images an online grow opens the grown array on.
+struct GrowTarget {
+    mode: RaidMode,
+    disk_dir: PathBuf,
+    disk_size: u64,
+    io: DiskIo,
+}
+
+/// `grow_step` advances the online grow of a mounted filesystem.
+///
+/// A requested grow moves a hot-added image into place as the next member, opens
+/// the grown array and starts reshaping onto it; a running one moves the next batch
+/// of stripes. After the last batch the quota table
+/// moves to the new tail and the member superblocks describe the grown array, so
+/// the next mount has to use its member count.
+///
+/// # Errors
+/// Returns an error if the hot-added image cannot be moved into place, the grown
+/// array cannot be opened, the reshape cannot start or move its next batch, or the
+/// superblocks cannot be rewritten.
+fn grow_step<const D: usize, const N: usize, T: Stripe<D, N>>(
+    st: &mut FsState<D, N, T>,
+    target: &GrowTarget,
+) -> Result<()> {
+    match st.grow {
+        GrowState::Requested(disks) => {
+            st.flush_all()?;
+            if let Some(image) = &st.hot_add {
+                place_member(image, &disk_image_path(&target.disk_dir, D))?;
+            }
+            let volume = open_reshape_target(
+                target.mode,
+                &target.disk_dir,
+                disks,
+                target.disk_size,
+                target.io,
+            )?;
+            st.volume.begin_reshape(volume, disks, 0)?;
+            st.grow = GrowState::Running(disks);
+        }
+        GrowState::Running(disks) => {
+            st.volume.reshape_step(BATCH_STRIPES)?;
+            if !st.volume.is_reshaping() {
+                st.move_quota_table();
+                superblock::restamp(target.mode, &target.disk_dir, disks, target.disk_size)?
+                    .write_pending(&target.disk_dir)?;
+                st.grow = GrowState::Finished(disks);
+            }
+        }
+        GrowState::Idle | GrowState::Finished(_) | GrowState::Failed(_) => {}
+    }
+    Ok(())
+}
+
+/// `place_member` moves a hot-added image to the path of the member it becomes and
+/// empties it, so the grown array opens it as a blank disk.
+///
+/// # Errors
+/// Returns an error if a member image already exists at `member`, or the image
+/// cannot be moved or emptied.
+fn place_member(image: &Path, member: &Path) -> Result<()> {
+    if member.exists() {
+        anyhow::bail!("member image {} already exists", member.display());
+    }
+    std::fs::rename(image, member).with_context(|| {
+        format!(
+            "cannot move {} to {}; hot-added images must be on the same filesystem",
+            image.display(),
+            member.display()
+        )
+    })?;
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(member)
+        .and_then(|file| file.set_len(0))
+        .with_context(|| format!("cannot empty {}", member.display()))
+}
+
+/// `spawn_reshaper` runs the online grows requested from the control file.
+///
+/// Like the balancer, each batch takes the filesystem lock on its own and the
+/// thread waits outside the lock whenever the background share asks it to. The
+/// thread ends once a grow finishes; a failed grow leaves its checkpoint behind
+/// for the next mount to resume from.
+fn spawn_reshaper<const D: usize, const N: usize, T>(
+    state: Arc<Mutex<FsState<D, N, T>>>,
+    target: GrowTarget,
+    metrics: Arc<MetricsEmitter>,
+) where
+    T: Stripe<D, N> + Send + 'static,
+{
+    spawn_in_scope(move || {
+        let mut started: Option<(std::time::Instant, u64)> = None;
+        let mut reported = None;
+        loop {
+            let Ok(mut st) = state.lock() else {
+                return;
+            };
+            let disks = match st.grow {
+                GrowState::Requested(disks) => disks,
+                GrowState::Running(disks) => {
+                    let wait = st.volume.background_wait();
+                    if !wait.is_zero() {
+                        drop(st);
+                        std::thread::sleep(wait);
+                        continue;
+                    }
+                    disks
+                }
+                GrowState::Idle | GrowState::Finished(_) | GrowState::Failed(_) => {
+                    drop(st);
+                    std::thread::sleep(SCRUB_POLL_INTERVAL);
+                    continue;
+                }
+            };
+            if let Err(err) = grow_step(&mut st, &target) {
+                tracing::warn!("grow to {disks} members stopped: {err:#}");
+                st.grow = GrowState::Failed(disks);
+                continue;
+            }
+            let progress = st.volume.reshape_progress();
+            let failed = st.volume.failed_disks();
+            let done = st.grow == GrowState::Finished(disks);
+            let hot_added = st.hot_add.is_some();
+            drop(st);
+
+            let Some(progress) = progress else {
+                continue;
+            };
+            let (since, first) =
+                *started.get_or_insert_with(|| (std::time::Instant::now(), progress.moved_bytes));
+            #[allow(clippy::cast_precision_loss)]
+            let rate = (progress.moved_bytes - first) as f64
+                / since.elapsed().as_secs_f64().max(f64::EPSILON);
+            let percent = progress.moved_bytes * 100 / progress.total_bytes.max(1);
+            if done || reported != Some(percent) {
+                reported = Some(percent);
+                metrics.record_balance_progress(failed, progress, rate);
+                if hot_added {
+                    metrics.record_member_rebuild(failed, progress, done);
+                }
+            }
+            if done {
+                tracing::info!("grow finished; mount with --disks {disks} from now on");
+                return;
+            }
+        }
+    });
+}
+
+/// `spawn_queue_sampler` periodically reports the operations each disk is serving.
+///
+/// The counters are read without taking the filesystem lock, so IO that holds the
+/// lock shows up in the samples.
+fn spawn_queue_sampler(in_flight: InFlight, metrics: Arc<MetricsEmitter>) {
+    spawn_in_scope(move || {
+        loop {
+            metrics.record_queue_depths(&in_flight.depths());
+            std::thread::sleep(QUEUE_SAMPLE_INTERVAL);
+        }
+    });
+}
+
+/// `spawn_failure_schedule` runs the steps of a schedule at their offsets from now.
+fn spawn_failure_schedule<const D: usize, const N: usize, T>(
+    schedule: FailureSchedule,
+    state: Arc<Mutex<FsState<D, N, T>>>,
+    metrics: Arc<MetricsEmitter>,
+) where
+    T: Stripe<D, N> + Send + 'static,
+{
+    let started = std::time::Instant::now();
+    spawn_in_scope(move || {
+        for step in schedule.steps {
+            std::thread::sleep(step.at.saturating_sub(started.elapsed()));
+            let Ok(mut st) = state.lock() else {
+                return;
+            };
+            let end = st.header.next_free.max(RaidFs::<D, N, T>::data_start());
+            match step.apply(&mut st.volume, end) {
+                Ok(()) => tracing::info!(?step, "applied scheduled failure step"),
+                Err(err) => tracing::warn!(?step, "scheduled failure step failed: {err:#}"),
+            }
+            record_status_snapshot(&metrics, &st);
+        }
+    });
+}
+
+/// `load_reshape` opens the target of an online grow left unfinished by an
+/// earlier mount.
+///
+/// # Errors
+/// Returns an error if the grow started from another member count, the mount is
+/// read-only, or the grown array cannot be opened.
+fn load_reshape<const D: usize>(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disk_size: u64,
+    io: DiskIo,
+    read_only: bool,
+) -> Result<Option<(Box<dyn DynVolume>, ReshapeCheckpoint)>> {
+    let reshape = ReshapeCheckpoint::load(disk_dir)?;
+    if let Some(checkpoint) = reshape
+        && checkpoint.source_disks != D
+    {
+        anyhow::bail!(
+            "an online grow to {} members is unfinished; mount with --disks {} to finish it",
+            checkpoint.target_disks,
+            checkpoint.source_disks
+        );
+    }
+    if reshape.is_some() && read_only {
+        anyhow::bail!(
+            "an online grow is unfinished and resuming it writes to the members; mount \
+             read-write to finish it first"
+        );
+    }
+    reshape
+        .map(|checkpoint| {
+            open_reshape_target(mode, disk_dir, checkpoint.target_disks, disk_size, io)
+                .map(|target| (target, checkpoint))
+        })
+        .transpose()
 }
 
 /// `run_fuse` mounts the RAID-backed filesystem using the selected mode.
@@ -203,43 +939,113 @@ fn allow_other_enabled() -> bool {
 /// * `mount_point` - Filesystem mount point.
 /// * `disk_dir` - Directory containing disk images.
 /// * `disk_size` - Size of each disk image in bytes.
+/// * `io` - Access method for the disk images.
+/// * `thin_size` - Virtual size to provision thinly, if any.
+/// * `read_ahead` - Stripes to prefetch ahead of sequential reads; 0 disables it.
+/// * `parity_cache` - Stripes whose parity is cached for partial-stripe writes; 0 disables it.
+/// * `faults` - How the volume reacts to failing members.
+/// * `schedule` - Timed disk failures to inject after mounting.
 /// * `metrics` - Metrics emitter for runtime status updates.
-/// * `allow_other` - Whether to allow other users (required for NFS export).
+/// * `flags` - Whether other users may access the mount and whether it is read-only.
 ///
 /// # Errors
-/// Returns an error if the mount cannot be initialized.
+/// Returns an error if the member superblocks do not match the array or the mount
+/// cannot be initialized.
+#[allow(clippy::too_many_arguments)]
 pub fn run_fuse<const D: usize, const N: usize>(
     mode: RaidMode,
     mount_point: &Path,
     disk_dir: &Path,
     disk_size: u64,
+    io: DiskIo,
+    thin_size: Option<u64>,
+    read_ahead: u64,
+    parity_cache: usize,
+    faults: FaultPolicy,
+    schedule: FailureSchedule,
     metrics: std::sync::Arc<MetricsEmitter>,
-    allow_other: bool,
+    flags: &MountFlags,
 ) -> Result<()> {
+    let reshape = load_reshape::<D>(mode, disk_dir, disk_size, io, flags.read_only)?;
+    let stamp = if flags.read_only {
+        superblock::check(mode, disk_dir, D, disk_size)?
+    } else {
+        superblock::stamp(mode, disk_dir, D, disk_size)?
+    };
+    let balance = BalanceCheckpoint::load(disk_dir)?
+        .map(|checkpoint| {
+            open_balance_source(mode, disk_dir, checkpoint.source_disks, disk_size, io)
+                .map(|source| (source, checkpoint))
+        })
+        .transpose()?;
     match mode {
         RaidMode::Raid0 => mount_volume::<D, N, RAID0<D, N>>(
             mount_point,
             disk_dir,
             disk_size,
+            io,
+            thin_size,
+            read_ahead,
+            parity_cache,
+            faults,
+            schedule,
+            &stamp,
+            balance,
+            reshape,
             RAID0::<D, N>::zero(),
             metrics,
-            allow_other,
+            flags,
         ),
         RaidMode::Raid1 => mount_volume::<D, N, RAID1<D, N>>(
             mount_point,
             disk_dir,
             disk_size,
+            io,
+            thin_size,
+            read_ahead,
+            parity_cache,
+            faults,
+            schedule,
+            &stamp,
+            balance,
+            reshape,
             RAID1::<D, N>::zero(),
             metrics,
-            allow_other,
+            flags,
         ),
         RaidMode::Raid3 => mount_volume::<D, N, RAID3<D, N>>(
             mount_point,
             disk_dir,
             disk_size,
+            io,
+            thin_size,
+            read_ahead,
+            parity_cache,
+            faults,
+            schedule,
+            &stamp,
+            balance,
+            reshape,
             RAID3::<D, N>::zero(),
             metrics,
-            allow_other,
+            flags,
+        ),
+        RaidMode::Declustered => mount_volume::<D, N, DeclusteredLayout<D, N>>(
+            mount_point,
+            disk_dir,
+            disk_size,
+            io,
+            thin_size,
+            read_ahead,
+            parity_cache,
+            faults,
+            schedule,
+            &stamp,
+            balance,
+            reshape,
+            DeclusteredLayout::<D, N>::zero(),
+            metrics,
+            flags,
         ),
     }
 }
@@ -247,24 +1053,226 @@ pub fn run_fuse<const D: usize, const N: usize>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    fn temp_dir(prefix: &str) -> std::path::PathBuf {
-        let mut dir = std::env::temp_dir();
-        let nanos = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        dir.push(format!("{prefix}-{}-{}", std::process::id(), nanos));
-        dir
+    use crate::fs::test_utils::temp_dir;
+    use crate::fs::{DEFAULT_CHUNK_SIZE, Quota};
+    use crate::volume::open_volume;
+
+    const DISK_SIZE: u64 = 1 << 20;
+
+    type Raid0State = FsState<2, DEFAULT_CHUNK_SIZE, RAID0<2, DEFAULT_CHUNK_SIZE>>;
+
+    fn raid0_state(dir: &Path) -> Raid0State {
+        let stamp = superblock::stamp(RaidMode::Raid0, dir, 2, DISK_SIZE).expect("stamp");
+        let mut volume = SimulatorBuilder::<2, DEFAULT_CHUNK_SIZE>::new(dir)
+            .disk_size(DISK_SIZE)
+            .build(RAID0::zero())
+            .expect("build volume");
+        stamp.write_pending(dir).expect("write superblocks");
+        volume.clear_needs_rebuild_all();
+        let (header, entries, quotas) = load_filesystem(&mut volume, false).expect("format");
+        FsState {
+            volume,
+            header,
+            entries,
+            write_buffer: WriteBuffer::default(),
+            scrub: Scrub::default(),
+            grow: GrowState::default(),
+            hot_add: None,
+            quotas,
+            open_files: OpenFiles::default(),
+        }
+    }
+
+    #[test]
+    fn grow_step_reshapes_a_mounted_volume_onto_added_members() {
+        let dir = temp_dir("raid-cli-online-grow");
+        let mut st = raid0_state(&dir);
+        let quota = Quota {
+            uid: 1000,
+            bytes: Some(4096),
+            files: None,
+        };
+        st.set_quota(quota).expect("set quota");
+        let data_start =
+            RaidFs::<2, DEFAULT_CHUNK_SIZE, RAID0<2, DEFAULT_CHUNK_SIZE>>::data_start();
+        st.volume.write_bytes(data_start, b"online");
+        let old_capacity = st.capacity();
+
+        let target = GrowTarget {
+            mode: RaidMode::Raid0,
+            disk_dir: dir.clone(),
+            disk_size: DISK_SIZE,
+            io: DiskIo::default(),
+        };
+        assert!(st.request_grow(3));
+        grow_step(&mut st, &target).expect("start grow");
+        assert_eq!(st.grow, GrowState::Running(3));
+        assert!(ReshapeCheckpoint::load(&dir).expect("load").is_some());
+        let mut steps = 0;
+        while st.grow == GrowState::Running(3) {
+            assert_eq!(st.capacity(), old_capacity);
+            grow_step(&mut st, &target).expect("grow step");
+            steps += 1;
+        }
+        assert!(steps > 1, "the reshape moves data in batches");
+        assert_eq!(st.grow, GrowState::Finished(3));
+        assert!(st.grow_status_string().contains("mount with --disks 3"));
+        assert!(!st.start_scrub());
+        assert_eq!(st.capacity(), old_capacity / 2 * 3);
+        assert_eq!(read_quotas(&mut st.volume, &st.header), Ok(vec![quota]));
+        drop(st);
+
+        assert_eq!(ReshapeCheckpoint::load(&dir).expect("load"), None);
+        let sb = superblock::read(&disk_image_path(&dir, 2))
+            .expect("read superblock")
+            .expect("superblock");
+        assert_eq!(sb.disks, 3);
+        let mut grown = open_volume(RaidMode::Raid0, &dir, 3, DISK_SIZE).expect("reopen");
+        let mut out = [0u8; 6];
+        grown.read_bytes(data_start, &mut out);
+        assert_eq!(&out, b"online");
+    }
+
+    #[test]
+    fn grow_step_makes_a_hot_added_image_the_next_member() {
+        let dir = temp_dir("raid-cli-hot-add");
+        let mut st = raid0_state(&dir);
+        let data_start =
+            RaidFs::<2, DEFAULT_CHUNK_SIZE, RAID0<2, DEFAULT_CHUNK_SIZE>>::data_start();
+        st.volume.write_bytes(data_start, b"hot-add");
+        let spare = dir.join("spare.img");
+        std::fs::write(&spare, vec![0xA5; 4096]).expect("write spare");
+
+        let target = GrowTarget {
+            mode: RaidMode::Raid0,
+            disk_dir: dir.clone(),
+            disk_size: DISK_SIZE,
+            io: DiskIo::default(),
+        };
+        assert!(st.request_add_disk(spare.clone()));
+        grow_step(&mut st, &target).expect("start grow");
+        assert!(!spare.exists());
+        assert!(st.grow_status_string().contains("disk 2: rebuilding"));
+        while st.grow == GrowState::Running(3) {
+            grow_step(&mut st, &target).expect("grow step");
+        }
+        assert_eq!(st.grow, GrowState::Finished(3));
+        assert!(st.grow_status_string().contains("disk 2: in sync"));
+        drop(st);
+
+        let sb = superblock::read(&disk_image_path(&dir, 2))
+            .expect("read superblock")
+            .expect("superblock");
+        assert_eq!(sb.disks, 3);
+        let mut grown = open_volume(RaidMode::Raid0, &dir, 3, DISK_SIZE).expect("reopen");
+        let mut out = [0u8; 7];
+        grown.read_bytes(data_start, &mut out);
+        assert_eq!(&out, b"hot-add");
+    }
+
+    #[test]
+    fn read_only_mount_leaves_the_images_untouched() {
+        type Raid3 = RAID3<3, DEFAULT_CHUNK_SIZE>;
+        let dir = temp_dir("raid-cli-read-only-mount");
+        let stamp = superblock::stamp(RaidMode::Raid3, &dir, 3, DISK_SIZE).expect("stamp");
+        let mut volume = SimulatorBuilder::<3, DEFAULT_CHUNK_SIZE>::new(&dir)
+            .disk_size(DISK_SIZE)
+            .build(Raid3::zero())
+            .expect("build volume");
+        stamp.write_pending(&dir).expect("write superblocks");
+        volume.clear_needs_rebuild_all();
+        load_filesystem(&mut volume, false).expect("format");
+        volume.sync().expect("sync");
+        // A torn write leaves a partial parity log a read-write mount would replay.
+        volume.set_write_hole_policy(WriteHolePolicy::Ppl);
+        volume.simulate_crashes(raid_rs::retention::volume::CrashPlan {
+            seed: 1,
+            crash_after_writes: Some(1),
+            write_hole: true,
+        });
+        volume.write_bytes(
+            RaidFs::<3, DEFAULT_CHUNK_SIZE, Raid3>::data_start(),
+            b"torn",
+        );
+        drop(volume);
+        assert!(dir.join("partial-parity.log").exists());
+
+        let images = || {
+            (0..3)
+                .map(|i| std::fs::read(disk_image_path(&dir, i)).expect("read"))
+                .collect::<Vec<_>>()
+        };
+        let before = images();
+        let flags = MountFlags {
+            read_only: true,
+            write_hole: WriteHolePolicy::Ppl,
+            ..MountFlags::default()
+        };
+        let faults = FaultPolicy {
+            degraded: DegradedPolicy::default(),
+            uncorrectable_limit: None,
+            wear: None,
+            write_cache: None,
+        };
+        let (tx, _rx) = tokio::sync::mpsc::channel(1024);
+        let metrics = MetricsEmitter::new("raid-test".to_string(), tx);
+        let stamp = superblock::check(RaidMode::Raid3, &dir, 3, DISK_SIZE).expect("check");
+        let (st, _events) = open_state(
+            &dir,
+            DISK_SIZE,
+            DiskIo::default(),
+            None,
+            0,
+            0,
+            faults,
+            &stamp,
+            None,
+            None,
+            Raid3::zero(),
+            &metrics,
+            &flags,
+        )
+        .expect("open read-only");
+        assert_eq!(st.volume.write_hole_stats().replayed_stripes, 0);
+        drop(st);
+
+        assert_eq!(images(), before);
+        assert!(dir.join("partial-parity.log").exists());
     }
 
     #[test]
-    fn disk_paths_build_expected_names() {
-        let dir = temp_dir("raid-cli-disks");
-        let paths = disk_paths::<3>(&dir).expect("paths");
-        assert!(paths[0].ends_with("disk-0.img"));
-        assert!(paths[1].ends_with("disk-1.img"));
-        assert!(paths[2].ends_with("disk-2.img"));
+    fn mount_flags_map_to_fuse_options() {
+        let flags = MountFlags {
+            fsname: "raid-fuse".to_string(),
+            ..MountFlags::default()
+        };
+        assert_eq!(
+            flags.options(),
+            vec![
+                MountOption::RW,
+                MountOption::FSName("raid-fuse".to_string())
+            ]
+        );
+
+        let flags = MountFlags {
+            allow_root: true,
+            auto_unmount: true,
+            default_permissions: true,
+            fsname: "md0".to_string(),
+            subtype: Some("raid3".to_string()),
+            read_only: true,
+            ..MountFlags::default()
+        };
+        assert_eq!(
+            flags.options(),
+            vec![
+                MountOption::RO,
+                MountOption::FSName("md0".to_string()),
+                MountOption::Subtype("raid3".to_string()),
+                MountOption::AllowRoot,
+                MountOption::AutoUnmount,
+                MountOption::DefaultPermissions,
+            ]
+        );
     }
 }
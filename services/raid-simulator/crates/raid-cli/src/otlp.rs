@@ -0,0 +1,544 @@
+//! OpenTelemetry export of simulator metrics.
+//!
+//! Instead of streaming raw batches to the gateway, batches are aggregated with the
+//! same families the Prometheus endpoint exposes and pushed periodically to an OTLP
+//! collector as cumulative sums, gauges and histograms.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper_util::client::legacy::Client as HttpClient;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::rt::TokioExecutor;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_client::MetricsServiceClient;
+use opentelemetry_proto::tonic::common::v1::{AnyValue, InstrumentationScope, KeyValue, any_value};
+use opentelemetry_proto::tonic::metrics::v1::{
+    AggregationTemporality, Gauge, Histogram, HistogramDataPoint, Metric, NumberDataPoint,
+    ResourceMetrics, ScopeMetrics, Sum, metric, number_data_point,
+};
+use opentelemetry_proto::tonic::resource::v1::Resource;
+use prost::Message;
+use tokio::sync::{mpsc, watch};
+use tonic::transport::Channel;
+use tracing::{debug, info, warn};
+
+use crate::cli::OtlpProtocol;
+use crate::health;
+use crate::pb::metrics as pb;
+use crate::prometheus::{Exporter, Family, Kind, LATENCY_BUCKETS, Labels};
+use crate::sender::{DroppedEvents, SenderStats};
+
+/// Path appended to a base endpoint for OTLP/HTTP metrics.
+const HTTP_METRICS_PATH: &str = "/v1/metrics";
+
+/// `OtlpConfig` captures collector and export settings.
+pub struct OtlpConfig {
+    pub endpoint: String,
+    pub protocol: OtlpProtocol,
+    pub source_id: String,
+    pub interval: Duration,
+    pub connect_timeout: Duration,
+    /// Deadline of one export request, from sending it to the collector's reply.
+    pub request_timeout: Duration,
+    pub shutdown_grace: Duration,
+}
+
+/// `run_otlp_exporter` aggregates metrics batches and exports them until shutdown.
+///
+/// # Arguments
+/// * `rx` - Receiver for metrics batches.
+/// * `shutdown` - Watch channel signaling shutdown.
+/// * `cfg` - Collector configuration settings.
+///
+/// # Returns
+/// Export statistics collected during execution.
+pub async fn run_otlp_exporter(
+    mut rx: mpsc::Receiver<pb::MetricsBatch>,
+    mut shutdown: watch::Receiver<bool>,
+    cfg: OtlpConfig,
+) -> SenderStats {
+    let mut stats = SenderStats {
+        dropped_batches: 0,
+        dropped_events: DroppedEvents::default(),
+        reconnects: 0,
+        send_errors: 0,
+        spool_depth: 0,
+    };
+    let exporter = Exporter::default();
+    let start = unix_nanos();
+    let mut client = Client::new(&cfg);
+
+    let mut ticker = tokio::time::interval(cfg.interval.max(Duration::from_millis(1)));
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    ticker.tick().await;
+
+    loop {
+        tokio::select! {
+            batch = rx.recv() => match batch {
+                Some(batch) => exporter.observe_batch(&batch),
+                None => break,
+            },
+            _ = ticker.tick() => {
+                let request = export_request(&exporter, &cfg.source_id, start, unix_nanos());
+                let outcome = client.export(request, &mut stats).await;
+                health::set_sender_connected(outcome.is_ok());
+                if let Err(err) = outcome {
+                    stats.send_errors += 1;
+                    warn!("otlp: export failed: {err:#}");
+                }
+            }
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    info!("otlp: shutdown requested");
+                    break;
+                }
+            }
+        }
+    }
+
+    while let Ok(batch) = rx.try_recv() {
+        exporter.observe_batch(&batch);
+    }
+    let request = export_request(&exporter, &cfg.source_id, start, unix_nanos());
+    match tokio::time::timeout(cfg.shutdown_grace, client.export(request, &mut stats)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => {
+            stats.send_errors += 1;
+            warn!("otlp: final export failed: {err:#}");
+        }
+        Err(_) => {
+            stats.send_errors += 1;
+            warn!("otlp: final export timed out");
+        }
+    }
+    stats
+}
+
+/// `Client` delivers export requests over the configured transport.
+struct Client<'a> {
+    cfg: &'a OtlpConfig,
+    grpc: Option<MetricsServiceClient<Channel>>,
+    http: HttpClient<HttpConnector, Full<Bytes>>,
+    connected_once: bool,
+}
+
+impl<'a> Client<'a> {
+    fn new(cfg: &'a OtlpConfig) -> Self {
+        let mut connector = HttpConnector::new();
+        connector.set_connect_timeout(Some(cfg.connect_timeout));
+        let http = HttpClient::builder(TokioExecutor::new())
+            .http1_title_case_headers(true)
+            .build(connector);
+        Self {
+            cfg,
+            grpc: None,
+            http,
+            connected_once: false,
+        }
+    }
+
+    async fn export(
+        &mut self,
+        request: ExportMetricsServiceRequest,
+        stats: &mut SenderStats,
+    ) -> Result<()> {
+        if request.resource_metrics.iter().all(|rm| {
+            rm.scope_metrics
+                .iter()
+                .all(|scope| scope.metrics.is_empty())
+        }) {
+            return Ok(());
+        }
+        match self.cfg.protocol {
+            OtlpProtocol::Grpc => self.export_grpc(request, stats).await,
+            OtlpProtocol::HttpProtobuf => {
+                post_http(
+                    &self.http,
+                    &self.cfg.endpoint,
+                    request.encode_to_vec(),
+                    self.cfg.request_timeout,
+                )
+                .await
+            }
+        }
+    }
+
+    async fn export_grpc(
+        &mut self,
+        request: ExportMetricsServiceRequest,
+        stats: &mut SenderStats,
+    ) -> Result<()> {
+        if self.grpc.is_none() {
+            let channel = Channel::from_shared(self.cfg.endpoint.clone())
+                .context("invalid OTLP endpoint")?
+                .connect_timeout(self.cfg.connect_timeout)
+                .connect()
+                .await
+                .with_context(|| format!("failed to connect to {}", self.cfg.endpoint))?;
+            if self.connected_once {
+                stats.reconnects += 1;
+            }
+            self.connected_once = true;
+            debug!("otlp: connected to {}", self.cfg.endpoint);
+            self.grpc = Some(MetricsServiceClient::new(channel));
+        }
+        let Some(client) = self.grpc.as_mut() else {
+            return Ok(());
+        };
+        let mut request = tonic::Request::new(request);
+        request.set_timeout(self.cfg.request_timeout);
+        match client.export(request).await {
+            Ok(response) => {
+                if let Some(partial) = response.into_inner().partial_success
+                    && partial.rejected_data_points > 0
+                {
+                    warn!(
+                        "otlp: collector rejected {} data points: {}",
+                        partial.rejected_data_points, partial.error_message
+                    );
+                }
+                Ok(())
+            }
+            Err(err) => {
+                self.grpc = None;
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// `post_http` sends one OTLP/HTTP protobuf request and checks for a 2xx reply.
+///
+/// The whole exchange, from connecting to the response head, must finish within
+/// `request_timeout`, so a collector that stops answering cannot stall the export loop.
+async fn post_http(
+    client: &HttpClient<HttpConnector, Full<Bytes>>,
+    endpoint: &str,
+    body: Vec<u8>,
+    request_timeout: Duration,
+) -> Result<()> {
+    let uri: http::Uri = endpoint.parse().context("invalid OTLP endpoint")?;
+    if uri.scheme_str() != Some("http") {
+        bail!("OTLP/HTTP endpoint must use http://");
+    }
+    let path = match uri.path() {
+        "" | "/" => HTTP_METRICS_PATH,
+        path => path,
+    }
+    .parse()
+    .context("invalid OTLP endpoint path")?;
+    let mut parts = uri.into_parts();
+    parts.path_and_query = Some(path);
+    let uri = http::Uri::from_parts(parts).context("invalid OTLP endpoint")?;
+
+    let request = http::Request::post(uri)
+        .header(http::header::CONTENT_TYPE, "application/x-protobuf")
+        .body(Full::new(Bytes::from(body)))
+        .context("failed to build OTLP request")?;
+    let response = tokio::time::timeout(request_timeout, client.request(request))
+        .await
+        .context("timed out waiting for collector")?
+        .context("failed to reach collector")?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("collector answered {status}");
+    }
+    Ok(())
+}
+
+/// `export_request` converts the aggregated families into one OTLP request.
+///
+/// # Arguments
+/// * `exporter` - Aggregated metrics.
+/// * `source_id` - Reported as the `service.name` resource attribute.
+/// * `start` - Start of the cumulative window, in Unix nanoseconds.
+/// * `now` - Timestamp of the data points, in Unix nanoseconds.
+fn export_request(
+    exporter: &Exporter,
+    source_id: &str,
+    start: u64,
+    now: u64,
+) -> ExportMetricsServiceRequest {
+    let mut metrics = Vec::new();
+    exporter.visit(|name, family| metrics.push(convert_family(name, family, start, now)));
+
+    ExportMetricsServiceRequest {
+        resource_metrics: vec![ResourceMetrics {
+            resource: Some(Resource {
+                attributes: vec![key_value("service.name", source_id)],
+                ..Default::default()
+            }),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: env!("CARGO_PKG_NAME").to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    ..Default::default()
+                }),
+                metrics,
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        }],
+    }
+}
+
+fn convert_family(name: &str, family: &Family, start: u64, now: u64) -> Metric {
+    let number_points = || {
+        family
+            .series
+            .iter()
+            .map(|(labels, series)| NumberDataPoint {
+                attributes: attributes(labels),
+                start_time_unix_nano: start,
+                time_unix_nano: now,
+                value: Some(number_data_point::Value::AsDouble(series.value)),
+                ..Default::default()
+            })
+            .collect()
+    };
+    let data = match family.kind {
+        Kind::Counter => metric::Data::Sum(Sum {
+            data_points: number_points(),
+            aggregation_temporality: AggregationTemporality::Cumulative as i32,
+            is_monotonic: true,
+        }),
+        Kind::Gauge => metric::Data::Gauge(Gauge {
+            data_points: number_points(),
+        }),
+        Kind::Histogram => metric::Data::Histogram(Histogram {
+            data_points: family
+                .series
+                .iter()
+                .map(|(labels, series)| {
+                    let mut bucket_counts = Vec::with_capacity(LATENCY_BUCKETS.len() + 1);
+                    let mut below = 0;
+                    for &cumulative in &series.buckets {
+                        bucket_counts.push(cumulative - below);
+                        below = cumulative;
+                    }
+                    bucket_counts.resize(LATENCY_BUCKETS.len(), 0);
+                    bucket_counts.push(series.count - below);
+                    HistogramDataPoint {
+                        attributes: attributes(labels),
+                        start_time_unix_nano: start,
+                        time_unix_nano: now,
+                        count: series.count,
+                        sum: Some(series.value),
+                        bucket_counts,
+                        explicit_bounds: LATENCY_BUCKETS.to_vec(),
+                        ..Default::default()
+                    }
+                })
+                .collect(),
+            aggregation_temporality: AggregationTemporality::Cumulative as i32,
+        }),
+    };
+    Metric {
+        name: name.to_string(),
+        description: family.help.to_string(),
+        unit: if family.kind == Kind::Histogram {
+            "s".to_string()
+        } else {
+            String::new()
+        },
+        data: Some(data),
+        ..Default::default()
+    }
+}
+
+fn attributes(labels: &Labels) -> Vec<KeyValue> {
+    labels.iter().map(|(k, v)| key_value(k, v)).collect()
+}
+
+fn key_value(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.to_string())),
+        }),
+        ..Default::default()
+    }
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn batch() -> pb::MetricsBatch {
+        pb::MetricsBatch {
+            disk_ops: vec![pb::DiskOp {
+                disk_id: "disk0".to_string(),
+                op: pb::IoOpType::IoOpWrite as i32,
+                bytes: 512,
+                latency_seconds: 0.003,
+                error: false,
+            }],
+            raid_states: vec![pb::RaidState {
+                raid_id: "raid0".to_string(),
+                failed_disks: 2,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn find<'a>(request: &'a ExportMetricsServiceRequest, name: &str) -> &'a Metric {
+        request.resource_metrics[0].scope_metrics[0]
+            .metrics
+            .iter()
+            .find(|m| m.name == name)
+            .unwrap_or_else(|| panic!("missing metric {name}"))
+    }
+
+    #[test]
+    fn converts_families_to_cumulative_otlp_metrics() {
+        let exporter = Exporter::default();
+        exporter.observe_batch(&batch());
+        exporter.observe_batch(&batch());
+
+        let request = export_request(&exporter, "sim-a", 1, 2);
+
+        let resource = request.resource_metrics[0].resource.as_ref().unwrap();
+        assert_eq!(resource.attributes[0], key_value("service.name", "sim-a"));
+
+        let Some(metric::Data::Sum(sum)) = &find(&request, "disk_write_bytes").data else {
+            panic!("disk_write_bytes should be a sum");
+        };
+        assert!(sum.is_monotonic);
+        assert_eq!(
+            sum.aggregation_temporality,
+            AggregationTemporality::Cumulative as i32
+        );
+        assert_eq!(
+            sum.data_points[0].attributes,
+            vec![key_value("disk_id", "disk0")]
+        );
+        assert_eq!(
+            sum.data_points[0].value,
+            Some(number_data_point::Value::AsDouble(1024.0))
+        );
+
+        let Some(metric::Data::Gauge(gauge)) = &find(&request, "raid_failed_disks").data else {
+            panic!("raid_failed_disks should be a gauge");
+        };
+        assert_eq!(
+            gauge.data_points[0].value,
+            Some(number_data_point::Value::AsDouble(2.0))
+        );
+
+        let Some(metric::Data::Histogram(histogram)) =
+            &find(&request, "disk_write_latency_seconds").data
+        else {
+            panic!("disk_write_latency_seconds should be a histogram");
+        };
+        let point = &histogram.data_points[0];
+        assert_eq!(point.count, 2);
+        assert_eq!(point.bucket_counts.len(), point.explicit_bounds.len() + 1);
+        assert_eq!(point.bucket_counts.iter().sum::<u64>(), 2);
+        let bucket = LATENCY_BUCKETS.iter().position(|&b| b >= 0.003).unwrap();
+        assert_eq!(point.bucket_counts[bucket], 2);
+    }
+
+    #[tokio::test]
+    async fn http_exporter_posts_protobuf_to_the_metrics_path() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let collector = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+            let body_start = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&raw[..body_start]).to_string();
+            let len: usize = head
+                .lines()
+                .find_map(|l| l.strip_prefix("Content-Length: "))
+                .unwrap()
+                .parse()
+                .unwrap();
+            while raw.len() < body_start + len {
+                let n = stream.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            (head, raw[body_start..].to_vec())
+        });
+
+        let (tx, rx) = mpsc::channel(4);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let cfg = OtlpConfig {
+            endpoint: format!("http://{addr}"),
+            protocol: OtlpProtocol::HttpProtobuf,
+            source_id: "sim-b".to_string(),
+            interval: Duration::from_hours(1),
+            connect_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_secs(1),
+            shutdown_grace: Duration::from_secs(5),
+        };
+        let task = tokio::spawn(run_otlp_exporter(rx, shutdown_rx, cfg));
+        tx.send(batch()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown_tx.send(true).unwrap();
+
+        let stats = task.await.unwrap();
+        let (head, body) = collector.await.unwrap();
+        assert_eq!(stats.send_errors, 0);
+        assert!(head.starts_with("POST /v1/metrics HTTP/1.1\r\n"));
+        assert!(head.contains("Content-Type: application/x-protobuf\r\n"));
+        let request = ExportMetricsServiceRequest::decode(body.as_slice()).unwrap();
+        assert_eq!(find(&request, "disk_write_ops").name, "disk_write_ops");
+    }
+
+    #[tokio::test]
+    async fn http_export_times_out_on_a_silent_collector() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let collector = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(stream);
+        });
+        let cfg = OtlpConfig {
+            endpoint: format!("http://{addr}"),
+            protocol: OtlpProtocol::HttpProtobuf,
+            source_id: "sim-c".to_string(),
+            interval: Duration::from_hours(1),
+            connect_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_millis(100),
+            shutdown_grace: Duration::from_secs(5),
+        };
+        let client = Client::new(&cfg);
+
+        let started = std::time::Instant::now();
+        let err = post_http(
+            &client.http,
+            &cfg.endpoint,
+            vec![1, 2, 3],
+            cfg.request_timeout,
+        )
+        .await
+        .expect_err("silent collector");
+        assert!(format!("{err:#}").contains("timed out"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        collector.abort();
+    }
+}
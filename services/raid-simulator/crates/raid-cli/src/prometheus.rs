@@ -0,0 +1,1165 @@
+//! Prometheus text exposition of the metrics batches produced by the simulator.
+//!
+//! Batches are observed on their way to the gRPC sender and folded into the same
+//! counters, gauges and latency histograms the metrics gateway registers, so a
+//! dashboard can scrape the simulator directly and keep its queries.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+
+use crate::pb::metrics;
+
+/// Upper bounds of the latency histogram buckets, matching the gateway.
+pub const LATENCY_BUCKETS: [f64; 17] = [
+    0.00005, 0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+    2.5, 5.0, 10.0,
+];
+
+/// Largest request head read from a scrape connection.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// `Kind` is the Prometheus type of a metric family.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+/// `Labels` are the label pairs identifying one series of a family.
+pub type Labels = Vec<(&'static str, String)>;
+
+/// `Series` is the accumulated state of one labelled series.
+///
+/// `value` holds the counter or gauge value, or the sum of histogram observations.
+/// `buckets` are cumulative counts per entry of `LATENCY_BUCKETS`.
+#[derive(Clone, Debug, Default)]
+pub struct Series {
+    pub value: f64,
+    pub buckets: Vec<u64>,
+    pub count: u64,
+}
+
+/// `Family` is a named metric and all of its series.
+pub struct Family {
+    pub help: &'static str,
+    pub kind: Kind,
+    pub series: BTreeMap<Labels, Series>,
+}
+
+/// `Exporter` accumulates metrics from batches and renders them for scraping.
+#[derive(Default)]
+pub struct Exporter {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+impl Exporter {
+    /// `observe_batch` folds one metrics batch into the exported series.
+    ///
+    /// # Arguments
+    /// * `batch` - Batch produced by a generator.
+    pub fn observe_batch(&self, batch: &metrics::MetricsBatch) {
+        for op in &batch.disk_ops {
+            self.observe_disk_op(op);
+        }
+        for state in &batch.disk_states {
+            self.observe_disk_state(state);
+        }
+        for op in &batch.raid_ops {
+            self.observe_raid_op(op);
+        }
+        for state in &batch.raid_states {
+            self.observe_raid_state(state);
+        }
+        for op in &batch.fuse_ops {
+            self.observe_fuse_op(op);
+        }
+        for st in &batch.volume_states {
+            self.observe_volume_state(st);
+        }
+        if let Some(process) = &batch.process {
+            self.set(
+                "process_cpu_seconds",
+                "Simulated CPU seconds used by the RAID simulator",
+                &[],
+                process.cpu_seconds,
+            );
+            #[allow(clippy::cast_precision_loss)]
+            self.set(
+                "process_resident_memory",
+                "Simulated resident memory (bytes)",
+                &[],
+                process.resident_memory_bytes as f64,
+            );
+            self.set(
+                "process_compression_ratio",
+                "Logical bytes per stored byte across compressed volumes",
+                &[],
+                process.compression_ratio,
+            );
+            self.set(
+                "process_compression_cpu_seconds",
+                "Seconds spent compressing and decompressing volume blocks",
+                &[],
+                process.compression_cpu_seconds,
+            );
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_disk_op(&self, op: &metrics::DiskOp) {
+        let labels = [("disk_id", op.disk_id.as_str())];
+        let bytes = op.bytes as f64;
+        match metrics::IoOpType::try_from(op.op) {
+            Ok(metrics::IoOpType::IoOpRead) => {
+                self.inc(
+                    "disk_read_ops",
+                    "Number of disk read operations",
+                    &labels,
+                    1.0,
+                );
+                self.inc("disk_read_bytes", "Bytes read from disk", &labels, bytes);
+                self.observe(
+                    "disk_read_latency_seconds",
+                    "Disk read latency (seconds)",
+                    &labels,
+                    op.latency_seconds,
+                );
+            }
+            Ok(metrics::IoOpType::IoOpWrite) => {
+                self.inc(
+                    "disk_write_ops",
+                    "Number of disk write operations",
+                    &labels,
+                    1.0,
+                );
+                self.inc("disk_write_bytes", "Bytes written to disk", &labels, bytes);
+                self.observe(
+                    "disk_write_latency_seconds",
+                    "Disk write latency (seconds)",
+                    &labels,
+                    op.latency_seconds,
+                );
+            }
+            _ => return,
+        }
+        if op.error {
+            self.inc("disk_errors", "Total disk errors", &labels, 1.0);
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss, clippy::too_many_lines)]
+    fn observe_raid_op(&self, op: &metrics::RaidOp) {
+        let labels = [("raid", op.raid_id.as_str())];
+        let bytes = op.bytes as f64;
+        let class = if op.background {
+            "background"
+        } else {
+            "foreground"
+        };
+        self.inc(
+            "raid_io_class_bytes",
+            "RAID bytes moved by user (foreground) and rebuild or scrub (background) IO",
+            &[("raid", op.raid_id.as_str()), ("class", class)],
+            bytes,
+        );
+        if op.background {
+            return;
+        }
+        match metrics::IoOpType::try_from(op.op) {
+            Ok(metrics::IoOpType::IoOpRead) => {
+                self.inc("raid_read_ops", "Total RAID read operations", &labels, 1.0);
+                self.inc("raid_read_bytes", "Total RAID read bytes", &labels, bytes);
+                self.observe(
+                    "raid_read_latency_seconds",
+                    "RAID read latency (seconds)",
+                    &labels,
+                    op.latency_seconds,
+                );
+                if !op.served_from_disk_id.is_empty() {
+                    self.inc(
+                        "raid1_reads_from_disk",
+                        "Reads served from a given disk in RAID1",
+                        &[
+                            ("raid", op.raid_id.as_str()),
+                            ("disk_id", op.served_from_disk_id.as_str()),
+                        ],
+                        1.0,
+                    );
+                }
+                if op.raid3_parity_read {
+                    self.inc(
+                        "raid3_parity_reads",
+                        "RAID3 parity read operations",
+                        &labels,
+                        1.0,
+                    );
+                }
+                if op.degraded {
+                    self.inc(
+                        "raid_degraded_reads",
+                        "RAID reads served while members were missing or rebuilding",
+                        &labels,
+                        1.0,
+                    );
+                }
+                for (name, help, stripes) in [
+                    (
+                        "raid_read_ahead_hit_stripes",
+                        "Stripes served from the read-ahead cache",
+                        op.read_ahead_hit_stripes,
+                    ),
+                    (
+                        "raid_read_ahead_miss_stripes",
+                        "Stripes read from disk while read-ahead was active",
+                        op.read_ahead_miss_stripes,
+                    ),
+                ] {
+                    if stripes > 0 {
+                        self.inc(name, help, &labels, stripes as f64);
+                    }
+                }
+            }
+            Ok(metrics::IoOpType::IoOpWrite) => {
+                self.inc(
+                    "raid_write_ops",
+                    "Total RAID write operations",
+                    &labels,
+                    1.0,
+                );
+                self.inc("raid_write_bytes", "Total RAID write bytes", &labels, bytes);
+                self.observe(
+                    "raid_write_latency_seconds",
+                    "RAID write latency (seconds)",
+                    &labels,
+                    op.latency_seconds,
+                );
+                if op.raid3_parity_write {
+                    self.inc(
+                        "raid3_parity_writes",
+                        "RAID3 parity write operations",
+                        &labels,
+                        1.0,
+                    );
+                }
+                if op.raid3_partial_stripe_write {
+                    self.inc(
+                        "raid3_partial_stripe_writes",
+                        "RAID3 partial stripe writes",
+                        &labels,
+                        1.0,
+                    );
+                }
+                for (name, help, stripes) in [
+                    (
+                        "raid_parity_cache_hit_stripes",
+                        "Partial-stripe writes served from cached parity",
+                        op.parity_cache_hit_stripes,
+                    ),
+                    (
+                        "raid_parity_cache_miss_stripes",
+                        "Partial-stripe writes that found no cached parity",
+                        op.parity_cache_miss_stripes,
+                    ),
+                ] {
+                    if stripes > 0 {
+                        self.inc(name, help, &labels, stripes as f64);
+                    }
+                }
+            }
+            _ => return,
+        }
+        if op.region != 0 {
+            let region = op.region.to_string();
+            self.inc(
+                "raid_region_ops",
+                "RAID operations per logical region of the volume",
+                &[("raid", op.raid_id.as_str()), ("region", region.as_str())],
+                1.0,
+            );
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_disk_state(&self, st: &metrics::DiskState) {
+        let labels = [("disk_id", st.disk_id.as_str())];
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        self.set(
+            "disk_queue_depth",
+            "Current disk queue depth",
+            &labels,
+            st.queue_depth,
+        );
+        self.set(
+            "disk_missing",
+            "Disk is missing from the array (0/1)",
+            &labels,
+            flag(st.missing),
+        );
+        self.set(
+            "disk_needs_rebuild",
+            "Disk holds stale data awaiting rebuild (0/1)",
+            &labels,
+            flag(st.needs_rebuild),
+        );
+        self.set(
+            "disk_grown_defects",
+            "Defects grown since the disk was installed",
+            &labels,
+            st.grown_defects as f64,
+        );
+        self.set(
+            "disk_reallocated_sectors",
+            "Defects remapped to spare sectors",
+            &labels,
+            st.reallocated_sectors as f64,
+        );
+        self.set(
+            "disk_temperature_celsius",
+            "Disk temperature (degrees Celsius)",
+            &labels,
+            st.temperature_celsius,
+        );
+        self.set(
+            "disk_pre_fail",
+            "Disk predicts its own failure (0/1)",
+            &labels,
+            flag(st.pre_fail),
+        );
+        self.set(
+            "disk_rebuild_source_chunks",
+            "Chunks the latest rebuild has read from this disk",
+            &labels,
+            st.rebuild_source_chunks as f64,
+        );
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_raid_state(&self, st: &metrics::RaidState) {
+        let labels = [("raid", st.raid_id.as_str())];
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        self.set(
+            "raid1_resync_progress",
+            "RAID1 resync progress (0-1)",
+            &labels,
+            st.raid1_resync_progress,
+        );
+        self.set(
+            "raid_degraded_state",
+            "RAID degraded state (0/1)",
+            &labels,
+            flag(st.degraded),
+        );
+        self.set(
+            "raid_failed_disks",
+            "Number of failed disks in RAID",
+            &labels,
+            f64::from(st.failed_disks),
+        );
+        self.set(
+            "raid_rebuild_in_progress",
+            "RAID rebuild in progress (0/1)",
+            &labels,
+            flag(st.rebuild_in_progress),
+        );
+        self.set(
+            "raid_uncorrectable_stripes",
+            "Logical stripes that could not be reconstructed on read",
+            &labels,
+            st.uncorrectable_stripes as f64,
+        );
+        self.set(
+            "raid_read_only",
+            "RAID volume refuses writes after too many uncorrectable errors (0/1)",
+            &labels,
+            flag(st.read_only),
+        );
+        if st.pool_capacity_bytes > 0 {
+            self.set(
+                "raid_pool_used_bytes",
+                "Physical bytes allocated from a thin pool",
+                &labels,
+                st.pool_used_bytes as f64,
+            );
+            self.set(
+                "raid_pool_capacity_bytes",
+                "Physical bytes available to a thin pool",
+                &labels,
+                st.pool_capacity_bytes as f64,
+            );
+        }
+        if st.check_stripes_checked > 0 {
+            self.set(
+                "raid_check_repairable_stripes",
+                "Inconsistent stripes found by the last check that redundancy can repair",
+                &labels,
+                st.check_repairable_stripes as f64,
+            );
+            self.set(
+                "raid_check_uncorrectable_stripes",
+                "Stripes found by the last check that redundancy cannot repair",
+                &labels,
+                st.check_uncorrectable_stripes as f64,
+            );
+        }
+        self.observe_progress(st, &labels);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_progress(&self, st: &metrics::RaidState, labels: &[(&'static str, &str)]) {
+        if st.rebuild_stripes_total > 0 {
+            self.set(
+                "raid_rebuild_stripes_total",
+                "Stripes to repair in the current rebuild pass",
+                labels,
+                st.rebuild_stripes_total as f64,
+            );
+            self.set(
+                "raid_rebuild_stripes_done",
+                "Stripes repaired in the current rebuild pass",
+                labels,
+                st.rebuild_stripes_done as f64,
+            );
+            self.set(
+                "raid_rebuild_bytes_per_second",
+                "Average rebuild rate since the pass started (bytes/s)",
+                labels,
+                st.rebuild_bytes_per_second,
+            );
+            self.set(
+                "raid_rebuild_eta_seconds",
+                "Estimated seconds until the rebuild pass completes",
+                labels,
+                st.rebuild_eta_seconds,
+            );
+        }
+        if st.scrub_stripes_total > 0 {
+            self.set(
+                "raid_scrub_stripes_total",
+                "Stripes to verify in the current scrub",
+                labels,
+                st.scrub_stripes_total as f64,
+            );
+            self.set(
+                "raid_scrub_stripes_done",
+                "Stripes verified in the current scrub",
+                labels,
+                st.scrub_stripes_done as f64,
+            );
+            self.set(
+                "raid_scrub_mismatches",
+                "Inconsistent stripes found by the current scrub",
+                labels,
+                st.scrub_mismatches as f64,
+            );
+        }
+        if st.balance_bytes_total > 0 {
+            self.set(
+                "raid_balance_bytes_total",
+                "Bytes to move onto the members added by a grow",
+                labels,
+                st.balance_bytes_total as f64,
+            );
+            self.set(
+                "raid_balance_bytes_done",
+                "Bytes moved so far by the running balance",
+                labels,
+                st.balance_bytes_done as f64,
+            );
+            self.set(
+                "raid_balance_bytes_per_second",
+                "Average balance rate since the mount (bytes/s)",
+                labels,
+                st.balance_bytes_per_second,
+            );
+        }
+        self.set(
+            "raid_stale_members",
+            "Members that rejoined with stale data and were marked for rebuild",
+            labels,
+            st.stale_members as f64,
+        );
+        if !st.write_hole_policy.is_empty() {
+            let labels = [
+                ("raid", st.raid_id.as_str()),
+                ("policy", st.write_hole_policy.as_str()),
+            ];
+            self.set(
+                "raid_write_hole_torn_stripes",
+                "Stripes whose data reached the members without their redundancy",
+                &labels,
+                st.torn_stripes as f64,
+            );
+            self.set(
+                "raid_write_hole_replayed_stripes",
+                "Stripes rewritten from the journal or partial parity log on open",
+                &labels,
+                st.write_hole_replayed_stripes as f64,
+            );
+            self.set(
+                "raid_write_hole_rejected_writes",
+                "Partial-stripe writes refused by the full-stripe policy",
+                &labels,
+                st.full_stripe_rejected_writes as f64,
+            );
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_volume_state(&self, st: &metrics::VolumeState) {
+        let labels = [("raid", st.raid_id.as_str())];
+        self.set(
+            "volume_capacity_bytes",
+            "Logical capacity of the mounted filesystem (bytes)",
+            &labels,
+            st.capacity_bytes as f64,
+        );
+        self.set(
+            "volume_used_bytes",
+            "Bytes used by files and metadata on the mounted filesystem",
+            &labels,
+            st.used_bytes as f64,
+        );
+        self.set(
+            "volume_free_bytes",
+            "Bytes still available for new data on the mounted filesystem",
+            &labels,
+            st.free_bytes as f64,
+        );
+        self.set(
+            "volume_files",
+            "Number of files stored on the mounted filesystem",
+            &labels,
+            st.file_count as f64,
+        );
+        self.set(
+            "volume_dirty_bytes",
+            "File writes held in the write buffer and not yet on the array (bytes)",
+            &labels,
+            st.dirty_bytes as f64,
+        );
+        if st.snapshot_pool_bytes > 0 {
+            self.set(
+                "volume_snapshot_used_bytes",
+                "Bytes preserved by copy-on-write for snapshots of the mounted filesystem",
+                &labels,
+                st.snapshot_used_bytes as f64,
+            );
+            self.set(
+                "volume_snapshot_pool_bytes",
+                "Bytes reserved for copy-on-write snapshot data on the mounted filesystem",
+                &labels,
+                st.snapshot_pool_bytes as f64,
+            );
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_fuse_op(&self, op: &metrics::FuseOp) {
+        let bytes = op.bytes as f64;
+        match metrics::FuseOpType::try_from(op.op) {
+            Ok(metrics::FuseOpType::FuseOpRead) => {
+                self.inc("fuse_read_ops", "Number of FUSE read operations", &[], 1.0);
+                self.inc("fuse_read_bytes", "Bytes read via FUSE", &[], bytes);
+                self.observe_file_bytes("fuse_file_read_bytes", "Bytes read via FUSE per file", op);
+                self.observe(
+                    "fuse_read_latency_seconds",
+                    "FUSE read latency (seconds)",
+                    &[],
+                    op.latency_seconds,
+                );
+            }
+            Ok(metrics::FuseOpType::FuseOpWrite) => {
+                self.inc(
+                    "fuse_write_ops",
+                    "Number of FUSE write operations",
+                    &[],
+                    1.0,
+                );
+                self.inc("fuse_write_bytes", "Bytes written via FUSE", &[], bytes);
+                self.observe_file_bytes(
+                    "fuse_file_write_bytes",
+                    "Bytes written via FUSE per file",
+                    op,
+                );
+                self.observe(
+                    "fuse_write_latency_seconds",
+                    "FUSE write latency (seconds)",
+                    &[],
+                    op.latency_seconds,
+                );
+            }
+            Ok(metrics::FuseOpType::FuseOpOpen) => {
+                self.inc("fuse_open_ops", "Number of FUSE open operations", &[], 1.0);
+            }
+            Ok(metrics::FuseOpType::FuseOpFsync) => {
+                self.inc(
+                    "fuse_fsync_ops",
+                    "Number of FUSE fsync operations",
+                    &[],
+                    1.0,
+                );
+            }
+            _ => return,
+        }
+        if op.error {
+            self.inc("fuse_errors", "Total FUSE errors", &[], 1.0);
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn observe_file_bytes(&self, name: &'static str, help: &'static str, op: &metrics::FuseOp) {
+        if op.inode == 0 || op.bytes == 0 {
+            return;
+        }
+        let inode = op.inode.to_string();
+        let name_hash = format!("{:016x}", op.name_hash);
+        let labels = [("inode", inode.as_str()), ("name_hash", name_hash.as_str())];
+        self.inc(name, help, &labels, op.bytes as f64);
+    }
+
+    fn inc(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&'static str, &str)],
+        by: f64,
+    ) {
+        self.update(name, help, Kind::Counter, labels, |s| s.value += by);
+    }
+
+    fn set(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&'static str, &str)],
+        value: f64,
+    ) {
+        self.update(name, help, Kind::Gauge, labels, |s| s.value = value);
+    }
+
+    fn observe(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&'static str, &str)],
+        value: f64,
+    ) {
+        if !value.is_finite() || value < 0.0 {
+            return;
+        }
+        self.update(name, help, Kind::Histogram, labels, |s| {
+            s.buckets.resize(LATENCY_BUCKETS.len(), 0);
+            for (bucket, bound) in s.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+                if value <= bound {
+                    *bucket += 1;
+                }
+            }
+            s.value += value;
+            s.count += 1;
+        });
+    }
+
+    fn update(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        kind: Kind,
+        labels: &[(&'static str, &str)],
+        apply: impl FnOnce(&mut Series),
+    ) {
+        let Ok(mut families) = self.families.lock() else {
+            return;
+        };
+        let family = families.entry(name).or_insert_with(|| Family {
+            help,
+            kind,
+            series: BTreeMap::new(),
+        });
+        let labels = labels.iter().map(|&(k, v)| (k, v.to_string())).collect();
+        apply(family.series.entry(labels).or_default());
+    }
+
+    /// `visit` calls `f` with every family under the exporter lock.
+    ///
+    /// # Arguments
+    /// * `f` - Callback receiving the family name and its state.
+    pub fn visit(&self, mut f: impl FnMut(&str, &Family)) {
+        let Ok(families) = self.families.lock() else {
+            return;
+        };
+        for (name, family) in families.iter() {
+            f(name, family);
+        }
+    }
+
+    #[must_use]
+    /// `render` returns every series in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let Ok(families) = self.families.lock() else {
+            return String::new();
+        };
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {name} {}", family.help);
+            let _ = writeln!(out, "# TYPE {name} {}", family.kind.as_str());
+            for (labels, series) in &family.series {
+                let labels = label_set(labels);
+                let labels = labels.as_str();
+                if family.kind != Kind::Histogram {
+                    let _ = writeln!(out, "{name}{} {}", braces(labels), series.value);
+                    continue;
+                }
+                for (count, bound) in series.buckets.iter().zip(LATENCY_BUCKETS) {
+                    let le = with_label(labels, &format!("le=\"{bound}\""));
+                    let _ = writeln!(out, "{name}_bucket{le} {count}");
+                }
+                let inf = with_label(labels, "le=\"+Inf\"");
+                let _ = writeln!(out, "{name}_bucket{inf} {}", series.count);
+                let _ = writeln!(out, "{name}_sum{} {}", braces(labels), series.value);
+                let _ = writeln!(out, "{name}_count{} {}", braces(labels), series.count);
+            }
+        }
+        out
+    }
+}
+
+/// `label_set` renders label pairs as `k="v",...` with exposition-format escaping.
+fn label_set(labels: &[(&'static str, String)]) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{k}=\"{v}\"")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn braces(labels: &str) -> String {
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{labels}}}")
+    }
+}
+
+fn with_label(labels: &str, extra: &str) -> String {
+    if labels.is_empty() {
+        format!("{{{extra}}}")
+    } else {
+        format!("{{{labels},{extra}}}")
+    }
+}
+
+/// `attach` starts the scrape endpoint when `listen` is set.
+///
+/// Generators send their batches to the returned channel. With an endpoint, every
+/// batch is observed before it is forwarded to `sender_tx`; without one,
+/// `sender_tx` is returned unchanged. Forwarding never waits on the sender: a
+/// batch it has no room for is counted in `metrics_forward_dropped_batches_total`
+/// and dropped, so `/metrics` keeps updating while the gateway is unreachable.
+///
+/// # Arguments
+/// * `listen` - Address of the scrape endpoint, if enabled.
+/// * `sender_tx` - Channel feeding the gRPC sender.
+/// * `queue_cap` - Capacity of the channel in front of the exporter.
+/// * `shutdown` - Watch channel signaling shutdown.
+///
+/// # Errors
+/// Returns an error if the listen address cannot be bound.
+pub async fn attach(
+    listen: Option<SocketAddr>,
+    sender_tx: mpsc::Sender<metrics::MetricsBatch>,
+    queue_cap: usize,
+    shutdown: watch::Receiver<bool>,
+) -> Result<mpsc::Sender<metrics::MetricsBatch>> {
+    let Some(addr) = listen else {
+        return Ok(sender_tx);
+    };
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("failed to bind prometheus endpoint {addr}"))?;
+    info!("prometheus: serving /metrics on {}", listener.local_addr()?);
+
+    let exporter = Arc::new(Exporter::default());
+    tokio::spawn(serve(listener, exporter.clone(), shutdown));
+
+    let (tx, mut rx) = mpsc::channel::<metrics::MetricsBatch>(queue_cap.max(1));
+    tokio::spawn(async move {
+        let mut sender_tx = Some(sender_tx);
+        while let Some(batch) = rx.recv().await {
+            exporter.observe_batch(&batch);
+            let Some(forward) = &sender_tx else {
+                continue;
+            };
+            match forward.try_send(batch) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => exporter.inc(
+                    "metrics_forward_dropped_batches_total",
+                    "Batches the exporter dropped because the sender had no room",
+                    &[],
+                    1.0,
+                ),
+                Err(TrySendError::Closed(_)) => sender_tx = None,
+            }
+        }
+    });
+    Ok(tx)
+}
+
+/// `serve` answers scrapes of `/metrics` until shutdown.
+async fn serve(
+    listener: TcpListener,
+    exporter: Arc<Exporter>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let exporter = exporter.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = handle(stream, &exporter).await {
+                            warn!("prometheus: scrape failed: {err:#}");
+                        }
+                    });
+                }
+                Err(err) => warn!("prometheus: accept failed: {err}"),
+            },
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    break;
+                }
+            },
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream, exporter: &Exporter) -> Result<()> {
+    let response = match get_path(&mut stream).await?.as_deref() {
+        Some(path) if path == b"/metrics" || path.starts_with(b"/metrics?") => {
+            http_response("200 OK", "text/plain; version=0.0.4", &exporter.render())
+        }
+        _ => http_response("404 Not Found", "text/plain", ""),
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// `get_path` reads a request head and returns the target of a `GET` request.
+///
+/// # Errors
+/// Returns an error if the connection cannot be read.
+pub async fn get_path(stream: &mut TcpStream) -> Result<Option<Vec<u8>>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let request_line = head.split(|&b| b == b'\r').next().unwrap_or_default();
+    Ok(
+        match request_line.split(|&b| b == b' ').collect::<Vec<_>>()[..] {
+            [b"GET", path, ..] => Some(path.to_vec()),
+            _ => None,
+        },
+    )
+}
+
+/// `http_response` formats a complete HTTP/1.1 response that closes the connection.
+pub fn http_response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn sample_batch() -> metrics::MetricsBatch {
+        metrics::MetricsBatch {
+            disk_ops: vec![metrics::DiskOp {
+                disk_id: "disk0".to_string(),
+                op: metrics::IoOpType::IoOpRead as i32,
+                bytes: 4096,
+                latency_seconds: 0.0003,
+                error: true,
+            }],
+            raid_ops: vec![metrics::RaidOp {
+                raid_id: "raid1".to_string(),
+                op: metrics::IoOpType::IoOpRead as i32,
+                bytes: 512,
+                latency_seconds: 0.002,
+                served_from_disk_id: "disk1".to_string(),
+                degraded: true,
+                region: 2,
+                read_ahead_hit_stripes: 6,
+                ..Default::default()
+            }],
+            disk_states: vec![metrics::DiskState {
+                disk_id: "disk2".to_string(),
+                queue_depth: 3.0,
+                missing: true,
+                needs_rebuild: false,
+                reallocated_sectors: 12,
+                temperature_celsius: 41.0,
+                pre_fail: true,
+                rebuild_source_chunks: 25,
+                ..Default::default()
+            }],
+            raid_states: vec![metrics::RaidState {
+                raid_id: "raid1".to_string(),
+                failed_disks: 1,
+                degraded: true,
+                ..Default::default()
+            }],
+            fuse_ops: vec![
+                metrics::FuseOp {
+                    op: metrics::FuseOpType::FuseOpFsync as i32,
+                    ..Default::default()
+                },
+                metrics::FuseOp {
+                    op: metrics::FuseOpType::FuseOpWrite as i32,
+                    bytes: 100,
+                    inode: 7,
+                    name_hash: 0xab,
+                    ..Default::default()
+                },
+            ],
+            volume_states: vec![metrics::VolumeState {
+                raid_id: "raid1".to_string(),
+                capacity_bytes: 8192,
+                used_bytes: 1024,
+                free_bytes: 7168,
+                file_count: 2,
+                dirty_bytes: 4096,
+                snapshot_used_bytes: 512,
+                snapshot_pool_bytes: 16384,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn render_matches_gateway_metric_names() {
+        let exporter = Exporter::default();
+        exporter.observe_batch(&sample_batch());
+        exporter.observe_batch(&sample_batch());
+
+        let text = exporter.render();
+
+        assert!(text.contains("# TYPE disk_read_ops counter\n"));
+        assert!(text.contains("disk_read_ops{disk_id=\"disk0\"} 2\n"));
+        assert!(text.contains("disk_read_bytes{disk_id=\"disk0\"} 8192\n"));
+        assert!(text.contains("disk_errors{disk_id=\"disk0\"} 2\n"));
+        assert!(text.contains("raid1_reads_from_disk{raid=\"raid1\",disk_id=\"disk1\"} 2\n"));
+        assert!(text.contains("raid_degraded_reads{raid=\"raid1\"} 2\n"));
+        assert!(text.contains("raid_region_ops{raid=\"raid1\",region=\"2\"} 2\n"));
+        assert!(text.contains("raid_read_ahead_hit_stripes{raid=\"raid1\"} 12\n"));
+        assert!(!text.contains("raid_read_ahead_miss_stripes"));
+        assert!(text.contains("# TYPE raid_failed_disks gauge\n"));
+        assert!(text.contains("raid_failed_disks{raid=\"raid1\"} 1\n"));
+        assert!(text.contains("raid_stale_members{raid=\"raid1\"} 0\n"));
+        assert!(text.contains("disk_queue_depth{disk_id=\"disk2\"} 3\n"));
+        assert!(text.contains("disk_missing{disk_id=\"disk2\"} 1\n"));
+        assert!(text.contains("disk_needs_rebuild{disk_id=\"disk2\"} 0\n"));
+        assert!(text.contains("disk_reallocated_sectors{disk_id=\"disk2\"} 12\n"));
+        assert!(text.contains("disk_temperature_celsius{disk_id=\"disk2\"} 41\n"));
+        assert!(text.contains("disk_pre_fail{disk_id=\"disk2\"} 1\n"));
+        assert!(text.contains("disk_rebuild_source_chunks{disk_id=\"disk2\"} 25\n"));
+        assert!(text.contains("fuse_fsync_ops 2\n"));
+        assert!(
+            text.contains(
+                "fuse_file_write_bytes{inode=\"7\",name_hash=\"00000000000000ab\"} 200\n"
+            )
+        );
+        assert!(text.contains("volume_used_bytes{raid=\"raid1\"} 1024\n"));
+        assert!(text.contains("volume_files{raid=\"raid1\"} 2\n"));
+        assert!(text.contains("volume_dirty_bytes{raid=\"raid1\"} 4096\n"));
+        assert!(text.contains("volume_snapshot_used_bytes{raid=\"raid1\"} 512\n"));
+        assert!(text.contains("volume_snapshot_pool_bytes{raid=\"raid1\"} 16384\n"));
+        assert!(!text.contains("raid_pool_used_bytes"));
+    }
+
+    #[test]
+    fn background_ops_only_count_toward_their_class() {
+        let exporter = Exporter::default();
+        let mut batch = sample_batch();
+        batch.raid_ops.push(metrics::RaidOp {
+            raid_id: "raid1".to_string(),
+            op: metrics::IoOpType::IoOpRead as i32,
+            bytes: 2048,
+            background: true,
+            ..Default::default()
+        });
+        exporter.observe_batch(&batch);
+
+        let text = exporter.render();
+
+        assert!(text.contains("raid_io_class_bytes{raid=\"raid1\",class=\"foreground\"} 512\n"));
+        assert!(text.contains("raid_io_class_bytes{raid=\"raid1\",class=\"background\"} 2048\n"));
+        assert!(text.contains("raid_read_ops{raid=\"raid1\"} 1\n"));
+        assert!(text.contains("raid_read_bytes{raid=\"raid1\"} 512\n"));
+    }
+
+    #[test]
+    fn write_hole_counters_are_labelled_by_policy() {
+        let exporter = Exporter::default();
+        let mut batch = sample_batch();
+        batch.raid_states.push(metrics::RaidState {
+            raid_id: "raid3".to_string(),
+            write_hole_policy: "journal".to_string(),
+            torn_stripes: 1,
+            write_hole_replayed_stripes: 4,
+            ..Default::default()
+        });
+        exporter.observe_batch(&batch);
+
+        let text = exporter.render();
+
+        assert!(
+            text.contains("raid_write_hole_torn_stripes{raid=\"raid3\",policy=\"journal\"} 1\n")
+        );
+        assert!(
+            text.contains(
+                "raid_write_hole_replayed_stripes{raid=\"raid3\",policy=\"journal\"} 4\n"
+            )
+        );
+        assert!(!text.contains("raid_write_hole_torn_stripes{raid=\"raid1\""));
+    }
+
+    #[test]
+    fn histograms_are_cumulative() {
+        let exporter = Exporter::default();
+        exporter.observe_batch(&sample_batch());
+
+        let text = exporter.render();
+
+        assert!(text.contains("# TYPE disk_read_latency_seconds histogram\n"));
+        assert!(
+            text.contains("disk_read_latency_seconds_bucket{disk_id=\"disk0\",le=\"0.00025\"} 0\n")
+        );
+        assert!(
+            text.contains("disk_read_latency_seconds_bucket{disk_id=\"disk0\",le=\"0.0005\"} 1\n")
+        );
+        assert!(
+            text.contains("disk_read_latency_seconds_bucket{disk_id=\"disk0\",le=\"+Inf\"} 1\n")
+        );
+        assert!(text.contains("disk_read_latency_seconds_count{disk_id=\"disk0\"} 1\n"));
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(
+            label_set(&[("disk_id", "a\"b\\c\nd".to_string())]),
+            "disk_id=\"a\\\"b\\\\c\\nd\""
+        );
+    }
+
+    #[tokio::test]
+    async fn endpoint_serves_metrics_and_forwards_batches() {
+        let (sender_tx, mut sender_rx) = mpsc::channel(4);
+        let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+        let probe = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = probe.local_addr().unwrap();
+        drop(probe);
+
+        let tx = attach(Some(addr), sender_tx, 4, shutdown_rx)
+            .await
+            .expect("attach");
+        tx.send(sample_batch()).await.unwrap();
+        let forwarded = sender_rx.recv().await.expect("forwarded batch");
+        assert_eq!(forwarded.disk_ops.len(), 1);
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: test\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("disk_read_ops{disk_id=\"disk0\"} 1\n"));
+
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+
+    async fn scrape(addr: SocketAddr) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: test\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn endpoint_keeps_updating_while_the_sender_is_stalled() {
+        // Nobody drains the sender channel, as when the gateway is down without a spool.
+        let (sender_tx, _sender_rx) = mpsc::channel(1);
+        let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+        let probe = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = probe.local_addr().unwrap();
+        drop(probe);
+
+        let tx = attach(Some(addr), sender_tx, 1, shutdown_rx)
+            .await
+            .expect("attach");
+        for _ in 0..5 {
+            tokio::time::timeout(Duration::from_secs(5), tx.send(sample_batch()))
+                .await
+                .expect("exporter keeps accepting batches")
+                .unwrap();
+        }
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        loop {
+            let response = scrape(addr).await;
+            if response.contains("disk_read_ops{disk_id=\"disk0\"} 5\n") {
+                assert!(response.contains("metrics_forward_dropped_batches_total 4\n"));
+                break;
+            }
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "scrape never saw all batches: {response}"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+}
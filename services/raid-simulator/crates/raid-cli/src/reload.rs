@@ -0,0 +1,208 @@
+//! Settings reloaded at runtime on SIGHUP, without remounting the filesystem.
+//!
+//! On SIGHUP the command line is parsed again with the current contents of the
+//! `--config` file, `--workload-file` is read again, and the new metrics interval
+//! and workload take effect from the next metrics tick; `log_level` from the config
+//! file replaces the log filter. Flags given on the command line keep overriding
+//! the file. A file that fails to load or validate is reported and the settings in
+//! effect are kept.
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::watch;
+use tracing::{info, warn};
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+use crate::cli::MetricsArgs;
+use crate::config;
+use crate::workload::Workload;
+
+/// Log filter handle and the directive always added to reloaded filters.
+static LOG_FILTER: OnceLock<(reload::Handle<EnvFilter, Registry>, Option<String>)> =
+    OnceLock::new();
+
+/// `Settings` are the reloadable settings in effect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Time between metrics batches.
+    pub interval: Duration,
+    /// Operation mix of the synthetic generator.
+    pub workload: Workload,
+}
+
+impl Settings {
+    /// `resolve` builds the settings from the metrics arguments.
+    ///
+    /// # Arguments
+    /// * `args` - Metrics configuration arguments.
+    ///
+    /// # Errors
+    /// Returns an error if the interval is zero or the workload is invalid.
+    pub fn resolve(args: &MetricsArgs) -> Result<Self> {
+        if args.interval_ms == 0 {
+            anyhow::bail!("interval_ms must be greater than zero");
+        }
+        Ok(Self {
+            interval: Duration::from_millis(args.interval_ms),
+            workload: Workload::resolve(args)?,
+        })
+    }
+}
+
+#[must_use]
+/// `ticker` returns the timer of metrics batches sent every `interval`; ticks
+/// missed while busy are delayed rather than sent in a burst.
+pub fn ticker(interval: Duration) -> tokio::time::Interval {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    ticker
+}
+
+/// `init_log_filter` makes the log filter installed by `handle` reloadable.
+///
+/// # Arguments
+/// * `handle` - Reload handle of the installed filter.
+/// * `extra` - Directive appended to every reloaded filter, if any.
+pub fn init_log_filter(handle: reload::Handle<EnvFilter, Registry>, extra: Option<String>) {
+    let _ = LOG_FILTER.set((handle, extra));
+}
+
+/// `load` resolves the settings from `args` and applies the log level of the
+/// `--config` file.
+///
+/// # Arguments
+/// * `args` - Metrics configuration arguments.
+///
+/// # Errors
+/// Returns an error if the configuration cannot be loaded or is invalid.
+pub fn load(args: &MetricsArgs) -> Result<Settings> {
+    let settings = Settings::resolve(args)?;
+    if let Some(path) = &args.config
+        && let Some(level) = config::log_level(path)?
+    {
+        set_log_level(&level)?;
+    }
+    Ok(settings)
+}
+
+/// `spawn` loads the settings and reloads them on SIGHUP until shutdown.
+///
+/// Without `--config` or `--workload-file` there is nothing to reload and SIGHUP
+/// keeps its default behaviour.
+///
+/// # Arguments
+/// * `args` - Metrics configuration arguments.
+/// * `shutdown` - Watch channel signaling shutdown.
+///
+/// # Returns
+/// Receiver of the settings in effect.
+///
+/// # Errors
+/// Returns an error if the initial configuration cannot be loaded or is invalid.
+pub fn spawn(
+    args: &MetricsArgs,
+    shutdown: watch::Receiver<bool>,
+) -> Result<watch::Receiver<Settings>> {
+    let (tx, rx) = watch::channel(load(args)?);
+    if args.config.is_some() || args.workload_file.is_some() {
+        tokio::spawn(reload_on_hangup(tx, shutdown));
+    }
+    Ok(rx)
+}
+
+async fn reload_on_hangup(tx: watch::Sender<Settings>, mut shutdown: watch::Receiver<bool>) {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(sig) => sig,
+        Err(err) => {
+            warn!("reload: cannot watch SIGHUP; settings stay fixed: {err}");
+            return;
+        }
+    };
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => match config::reparse_metrics_args().and_then(|args| load(&args)) {
+                Ok(settings) => {
+                    info!(interval = ?settings.interval, "reload: SIGHUP -> settings reloaded");
+                    tx.send_replace(settings);
+                }
+                Err(err) => warn!("reload: keeping current settings: {err:#}"),
+            },
+            changed = shutdown.changed() => {
+                if changed.is_err() || *shutdown.borrow() {
+                    break;
+                }
+            },
+        }
+    }
+}
+
+/// `set_log_level` replaces the log filter with `directives`.
+fn set_log_level(directives: &str) -> Result<()> {
+    let Some((handle, extra)) = LOG_FILTER.get() else {
+        return Ok(());
+    };
+    let mut filter = EnvFilter::try_new(directives)
+        .with_context(|| format!("invalid log_level {directives:?}"))?;
+    if let Some(extra) = extra {
+        filter = filter.add_directive(extra.parse()?);
+    }
+    handle.reload(filter).context("failed to reload log filter")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::WorkloadProfile;
+    use crate::fs::test_utils::temp_dir;
+    use std::path::Path;
+
+    fn reparse(config: &Path, extra: &[&str]) -> Result<Settings> {
+        let config = config.display().to_string();
+        let mut args = vec!["raid-cli", "metrics", "--config", &config];
+        args.extend(extra);
+        let args =
+            config::metrics_args(config::try_parse_from(args)?.command).expect("metrics arguments");
+        load(&args)
+    }
+
+    #[test]
+    fn reparsing_picks_up_config_changes() {
+        let dir = temp_dir("raid-cli-reload");
+        let path = dir.join("config.toml");
+        let flags = ["--interval-ms", "1000"];
+
+        std::fs::write(&path, "log_level = \"debug\"\nworkload = \"idle\"\n")
+            .expect("write config");
+        let settings = reparse(&path, &flags).expect("load");
+        assert_eq!(settings.interval, Duration::from_secs(1));
+        assert_eq!(settings.workload, Workload::profile(WorkloadProfile::Idle));
+
+        std::fs::write(&path, "interval_ms = 250\nworkload = \"oltp\"\n").expect("write config");
+        let settings = reparse(&path, &flags).expect("reload");
+        assert_eq!(settings.interval, Duration::from_secs(1));
+        assert_eq!(settings.workload, Workload::profile(WorkloadProfile::Oltp));
+        let settings = reparse(&path, &[]).expect("reload");
+        assert_eq!(settings.interval, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let dir = temp_dir("raid-cli-reload-invalid");
+        let path = dir.join("config.toml");
+
+        for raw in [
+            "interval_ms = 0\n",
+            "workload = \"busy\"\n",
+            "latency = 1\n",
+            "log_level = 1\n",
+        ] {
+            std::fs::write(&path, raw).expect("write config");
+            assert!(reparse(&path, &[]).is_err(), "{raw}");
+        }
+        std::fs::remove_file(&path).expect("remove config");
+        assert!(reparse(&path, &[]).is_err());
+    }
+}
@@ -0,0 +1,496 @@
+//! Failure and repair scenarios run by the `scenario` subcommand.
+//!
+//! A scenario is a YAML document describing an array and the steps to run on it.
+//! Steps run in order; `at` holds a step back until that offset from the start of
+//! the run (same syntax as failure schedules), otherwise it follows the previous
+//! step immediately:
+//!
+//! ```yaml
+//! name: raid3 survives a single disk failure
+//! raid: raid3
+//! disks: 4
+//! disk_size: 1048576
+//! steps:
+//!   - action: write
+//!     seed: 7
+//!   - action: fail
+//!     disk: 1
+//!   - action: assert
+//!     data_intact: true
+//!     degraded: true
+//!   - at: 2s
+//!     action: replace
+//!     disk: 1
+//!   - action: corrupt
+//!     disk: 3
+//!     offset: 0
+//!     bytes: 16
+//!   - action: scrub
+//!   - action: bench
+//!     block_size: 65536
+//!   - action: assert
+//!     data_intact: true
+//!     repairable_stripes: 0
+//! ```
+//!
+//! `write` fills the volume with a pattern derived from `seed`, which later
+//! `data_intact` assertions compare against; until the first write the expected
+//! content is all zeros. `fail`, `replace`, `swap`, `readd` and `rebuild` act like
+//! the failure schedule actions of the same name. `corrupt` flips bytes of a disk
+//! image behind the array's back, `scrub` checks every stripe and repairs the
+//! inconsistent ones unless `repair: false`, and `bench` times a read and a write
+//! pass over the volume that leave its content unchanged.
+//!
+//! With `write_cache` set, every disk acknowledges writes from a drive cache:
+//!
+//! ```yaml
+//! write_cache:
+//!   capacity: 65536
+//!   volatility: 0.5
+//! steps:
+//!   - action: write
+//!   - action: flush
+//!   - action: write
+//!     seed: 8
+//!   - action: power_fail
+//!   - action: assert
+//!     data_intact: false
+//! ```
+//!
+//! `flush` is a write barrier that stores every cached write on the images.
+//! `power_fail` cuts the power: each cached write is lost with the `volatility`
+//! chance, then the array restarts from its images and resyncs its redundancy.
+//!
+//! `torn_write` demonstrates the write hole: it writes `bytes` bytes derived from
+//! `seed` at `offset` and cuts the power after the data chunks of the first stripe
+//! reached the disks but before its parity or mirrors did. The array restarts
+//! without a resync, so the stale redundancy is left for `scrub` and assertions to
+//! find. `write_hole` selects the mitigation to compare against:
+//!
+//! ```yaml
+//! raid: raid3
+//! disks: 3
+//! write_hole: journal
+//! steps:
+//!   - action: write
+//!   - action: torn_write
+//!     offset: 0
+//!     bytes: 4
+//!   - action: assert
+//!     repairable_stripes: 0
+//! ```
+//!
+//! `unprotected` (the default) writes stripes in place, `journal` journals every
+//! stripe next to the images and replays it on restart, `ppl` logs only the
+//! partial parity of each write and recomputes the parity on restart, and
+//! `full_stripe` rejects writes that do not cover whole stripes.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use raid_rs::retention::disk::WriteCachePlan;
+use raid_rs::retention::volume::WriteHolePolicy;
+
+use crate::cli::RaidMode;
+use crate::schedule::{FailureAction, deserialize_offset};
+use crate::volume::validate_geometry;
+
+/// `DEFAULT_DISK_SIZE` is the disk image size of scenarios that do not set one.
+pub const DEFAULT_DISK_SIZE: u64 = 1 << 20;
+
+/// `Scenario` is an array geometry and the steps to run against it.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    pub raid: RaidMode,
+    pub disks: usize,
+    #[serde(default = "default_disk_size")]
+    pub disk_size: u64,
+    /// Drive write cache of every disk, if the disks cache writes.
+    #[serde(default)]
+    pub write_cache: Option<WriteCache>,
+    /// How stripe writes are protected against a crash between data and parity.
+    #[serde(default)]
+    pub write_hole: WriteHolePolicy,
+    pub steps: Vec<Step>,
+}
+
+/// `WriteCache` configures the drive write caches of a scenario.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WriteCache {
+    /// Bytes of writes each disk holds before writing back the oldest; 0 holds
+    /// them until the next flush.
+    #[serde(default)]
+    pub capacity: usize,
+    /// Chance that a cached write is lost on power failure.
+    #[serde(default = "default_volatility")]
+    pub volatility: f64,
+    /// Seed for the writes that survive a power failure.
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl WriteCache {
+    #[must_use]
+    /// `plan` returns the cache plan of the member disks.
+    pub const fn plan(&self) -> WriteCachePlan {
+        WriteCachePlan {
+            seed: self.seed,
+            capacity: self.capacity,
+            volatility: self.volatility,
+        }
+    }
+}
+
+/// `Step` is one action, optionally held back until a time offset.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Step {
+    #[serde(default, deserialize_with = "deserialize_at")]
+    pub at: Option<Duration>,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// `Action` is what a step does.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Action {
+    /// Fill the volume with a pattern derived from `seed`.
+    Write {
+        #[serde(default)]
+        seed: u64,
+    },
+    Fail {
+        disk: usize,
+    },
+    Replace {
+        disk: usize,
+    },
+    Swap {
+        disk: usize,
+    },
+    Readd {
+        disk: usize,
+    },
+    Rebuild {
+        disk: usize,
+    },
+    /// Invert `bytes` bytes of a disk image starting at `offset`.
+    Corrupt {
+        disk: usize,
+        #[serde(default)]
+        offset: u64,
+        #[serde(default = "default_corrupt_bytes")]
+        bytes: u64,
+    },
+    /// Check every stripe, repairing inconsistent ones when `repair` is set.
+    Scrub {
+        #[serde(default = "default_repair")]
+        repair: bool,
+    },
+    /// Time a read and a write pass over the whole volume.
+    Bench {
+        #[serde(default = "default_block_size")]
+        block_size: usize,
+    },
+    /// Store every cached write on the disk images.
+    Flush,
+    /// Lose cached writes as a power failure would, then restart the array.
+    #[serde(rename = "power_fail")]
+    PowerFail,
+    /// Write `bytes` bytes at `offset` and lose power halfway through the first
+    /// stripe, then restart the array without a resync.
+    #[serde(rename = "torn_write")]
+    TornWrite {
+        #[serde(default)]
+        offset: u64,
+        #[serde(default = "default_torn_bytes")]
+        bytes: u64,
+        #[serde(default)]
+        seed: u64,
+    },
+    Assert(Assertion),
+}
+
+/// `Assertion` lists the expectations of an `assert` step; unset fields are not checked.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Assertion {
+    /// The volume reads back the content of the last `write`.
+    pub data_intact: Option<bool>,
+    pub failed_disks: Option<u32>,
+    /// Some member is missing or awaiting rebuild.
+    pub degraded: Option<bool>,
+    /// Inconsistent stripes a check finds that redundancy can repair.
+    pub repairable_stripes: Option<u64>,
+    /// Stripes a check finds that redundancy cannot repair.
+    pub uncorrectable_stripes: Option<u64>,
+}
+
+impl Scenario {
+    /// `load` reads and validates a scenario file.
+    ///
+    /// # Arguments
+    /// * `path` - YAML scenario file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or the scenario is invalid.
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read scenario {}", path.display()))?;
+        Self::from_yaml(&raw).with_context(|| format!("invalid scenario {}", path.display()))
+    }
+
+    /// `from_yaml` parses and validates a scenario.
+    ///
+    /// # Errors
+    /// Returns an error if the document is not a valid scenario.
+    pub fn from_yaml(raw: &str) -> Result<Self> {
+        let scenario: Self = serde_yaml::from_str(raw)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> Result<()> {
+        validate_geometry(self.raid, self.disks)?;
+        if self.disk_size == 0 {
+            anyhow::bail!("disk_size must be greater than zero");
+        }
+        if let Some(cache) = self.write_cache
+            && !(0.0..=1.0).contains(&cache.volatility)
+        {
+            anyhow::bail!("write_cache volatility must be between 0 and 1");
+        }
+        for (n, step) in self.steps.iter().enumerate() {
+            step.action
+                .validate(self.disks, self.disk_size)
+                .with_context(|| format!("step {}", n + 1))?;
+        }
+        Ok(())
+    }
+}
+
+impl Action {
+    #[must_use]
+    /// `failure` returns the failure action and disk of disk steps.
+    pub const fn failure(&self) -> Option<(FailureAction, usize)> {
+        match *self {
+            Self::Fail { disk } => Some((FailureAction::Fail, disk)),
+            Self::Replace { disk } => Some((FailureAction::Replace, disk)),
+            Self::Swap { disk } => Some((FailureAction::Swap, disk)),
+            Self::Readd { disk } => Some((FailureAction::Readd, disk)),
+            Self::Rebuild { disk } => Some((FailureAction::Rebuild, disk)),
+            _ => None,
+        }
+    }
+
+    fn validate(&self, disks: usize, disk_size: u64) -> Result<()> {
+        let disk = match self {
+            Self::Corrupt { disk, .. } => Some(*disk),
+            _ => self.failure().map(|(_, disk)| disk),
+        };
+        if let Some(disk) = disk.filter(|&disk| disk >= disks) {
+            anyhow::bail!("disk {disk} is outside the array of {disks} disks");
+        }
+        match self {
+            Self::Corrupt { offset, bytes, .. }
+                if *bytes == 0 || offset.saturating_add(*bytes) > disk_size =>
+            {
+                anyhow::bail!(
+                    "corrupt range {offset}+{bytes} must be non-empty and fit in {disk_size}-byte disks"
+                );
+            }
+            Self::TornWrite { bytes: 0, .. } => {
+                anyhow::bail!("torn_write bytes must be greater than zero")
+            }
+            Self::Bench { block_size: 0 } => {
+                anyhow::bail!("bench block_size must be greater than zero")
+            }
+            Self::Assert(assertion) if *assertion == Assertion::default() => {
+                anyhow::bail!("assert needs at least one expectation")
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+const fn default_disk_size() -> u64 {
+    DEFAULT_DISK_SIZE
+}
+
+const fn default_volatility() -> f64 {
+    0.5
+}
+
+const fn default_corrupt_bytes() -> u64 {
+    1
+}
+
+const fn default_torn_bytes() -> u64 {
+    1
+}
+
+const fn default_repair() -> bool {
+    true
+}
+
+const fn default_block_size() -> usize {
+    4096
+}
+
+fn deserialize_at<'de, De>(deserializer: De) -> Result<Option<Duration>, De::Error>
+where
+    De: serde::Deserializer<'de>,
+{
+    deserialize_offset(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_with_defaults_and_offsets() {
+        let scenario = Scenario::from_yaml(
+            "
+raid: raid1
+disks: 2
+steps:
+  - action: write
+  - at: 1500ms
+    action: fail
+    disk: 1
+  - action: corrupt
+    disk: 0
+  - action: scrub
+  - action: assert
+    data_intact: true
+    failed_disks: 1
+",
+        )
+        .expect("scenario");
+
+        assert_eq!(scenario.disk_size, DEFAULT_DISK_SIZE);
+        assert_eq!(scenario.steps.len(), 5);
+        assert_eq!(scenario.steps[0].action, Action::Write { seed: 0 });
+        assert_eq!(scenario.steps[1].at, Some(Duration::from_millis(1500)));
+        assert_eq!(
+            scenario.steps[1].action.failure(),
+            Some((FailureAction::Fail, 1))
+        );
+        assert_eq!(
+            scenario.steps[2].action,
+            Action::Corrupt {
+                disk: 0,
+                offset: 0,
+                bytes: 1,
+            }
+        );
+        assert_eq!(scenario.steps[3].action, Action::Scrub { repair: true });
+        assert_eq!(
+            scenario.steps[4].action,
+            Action::Assert(Assertion {
+                data_intact: Some(true),
+                failed_disks: Some(1),
+                ..Assertion::default()
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_steps() {
+        let with_step = |step: &str| {
+            Scenario::from_yaml(&format!("raid: raid3\ndisks: 3\nsteps:\n  - {step}\n"))
+        };
+
+        assert!(with_step("action: write").is_ok());
+        let err = with_step("action: fail\n    disk: 3").expect_err("disk out of range");
+        assert!(format!("{err:#}").contains("disk 3 is outside"));
+        assert!(with_step("action: assert").is_err());
+        assert!(with_step("action: melt").is_err());
+        assert!(with_step("action: corrupt\n    disk: 0\n    offset: 1048576").is_err());
+        assert!(Scenario::from_yaml("raid: raid3\ndisks: 1\nsteps: []\n").is_err());
+    }
+
+    #[test]
+    fn parses_write_cache_and_power_fail() {
+        let scenario = Scenario::from_yaml(
+            "
+raid: raid3
+disks: 3
+write_cache:
+  capacity: 4096
+steps:
+  - action: flush
+  - action: power_fail
+",
+        )
+        .expect("scenario");
+
+        assert_eq!(
+            scenario.write_cache.map(|cache| cache.plan()),
+            Some(WriteCachePlan {
+                seed: 0,
+                capacity: 4096,
+                volatility: 0.5,
+            })
+        );
+        assert_eq!(scenario.steps[0].action, Action::Flush);
+        assert_eq!(scenario.steps[1].action, Action::PowerFail);
+        assert!(
+            Scenario::from_yaml(
+                "raid: raid1\ndisks: 2\nwrite_cache:\n  volatility: 2\nsteps: []\n"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_write_hole_policy_and_torn_write() {
+        let scenario = Scenario::from_yaml(
+            "
+raid: raid3
+disks: 3
+write_hole: full_stripe
+steps:
+  - action: torn_write
+    offset: 8
+    bytes: 4
+  - action: torn_write
+",
+        )
+        .expect("scenario");
+
+        assert_eq!(scenario.write_hole, WriteHolePolicy::FullStripe);
+        assert_eq!(
+            scenario.steps[0].action,
+            Action::TornWrite {
+                offset: 8,
+                bytes: 4,
+                seed: 0,
+            }
+        );
+        assert_eq!(
+            scenario.steps[1].action,
+            Action::TornWrite {
+                offset: 0,
+                bytes: 1,
+                seed: 0,
+            }
+        );
+        let with = |tail: &str| Scenario::from_yaml(&format!("raid: raid3\ndisks: 3\n{tail}"));
+        assert!(with("steps:\n  - action: torn_write\n    bytes: 0\n").is_err());
+        assert!(with("write_hole: battery\nsteps: []\n").is_err());
+        assert_eq!(
+            with("steps: []\n").expect("scenario").write_hole,
+            WriteHolePolicy::Unprotected
+        );
+    }
+}
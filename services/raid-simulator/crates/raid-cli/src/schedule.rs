@@ -0,0 +1,305 @@
+//! Deterministic failure-injection schedules for mounted arrays.
+//!
+//! A schedule lists disk actions and when to run them, relative to the mount:
+//!
+//! ```toml
+//! [[step]]
+//! at = "30s"
+//! action = "fail"
+//! disk = 2
+//!
+//! [[step]]
+//! at = "90s"
+//! action = "replace"
+//! disk = 2
+//! ```
+//!
+//! The same structure is accepted as JSON (`{"step": [...]}`). The actions mirror
+//! the commands written to the control file.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use raid_rs::retention::volume::DynVolume;
+use serde::Deserialize;
+
+/// `FailureAction` is a disk operation run by a schedule step.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureAction {
+    /// Fail the disk, as if it was pulled from the array.
+    Fail,
+    /// Install a blank disk and rebuild it.
+    Replace,
+    /// Fail the disk and immediately replace it.
+    Swap,
+    /// Reattach the last failed image and resync it from the write-intent bitmap.
+    Readd,
+    /// Rebuild the disk in place.
+    Rebuild,
+}
+
+/// `ScheduledStep` runs one action on one disk at a fixed time after mounting.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ScheduledStep {
+    #[serde(deserialize_with = "deserialize_offset")]
+    pub at: Duration,
+    pub action: FailureAction,
+    pub disk: usize,
+}
+
+/// `FailureSchedule` is an ordered list of steps.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct FailureSchedule {
+    #[serde(default, rename = "step")]
+    pub steps: Vec<ScheduledStep>,
+}
+
+impl FailureSchedule {
+    /// `load` reads a schedule from a `.toml` or `.json` file.
+    ///
+    /// # Arguments
+    /// * `path` - Schedule file; the extension selects the format.
+    /// * `disks` - Number of member disks, used to validate disk indices.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or a step names a disk
+    /// outside the array.
+    pub fn load(path: &Path, disks: usize) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read failure schedule {}", path.display()))?;
+        let schedule = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&raw),
+            Some("json") => Self::from_json(&raw),
+            _ => Err(anyhow::anyhow!(
+                "failure schedule must be a .toml or .json file"
+            )),
+        }
+        .with_context(|| format!("invalid failure schedule {}", path.display()))?;
+        schedule.validate(disks)?;
+        Ok(schedule)
+    }
+
+    /// `from_toml` parses a schedule and orders its steps by time.
+    ///
+    /// # Errors
+    /// Returns an error if the document is not a valid schedule.
+    pub fn from_toml(raw: &str) -> Result<Self> {
+        Ok(toml::from_str::<Self>(raw)?.sorted())
+    }
+
+    /// `from_json` parses a schedule and orders its steps by time.
+    ///
+    /// # Errors
+    /// Returns an error if the document is not a valid schedule.
+    pub fn from_json(raw: &str) -> Result<Self> {
+        Ok(serde_json::from_str::<Self>(raw)?.sorted())
+    }
+
+    fn sorted(mut self) -> Self {
+        self.steps.sort_by_key(|step| step.at);
+        self
+    }
+
+    fn validate(&self, disks: usize) -> Result<()> {
+        if let Some(step) = self.steps.iter().find(|step| step.disk >= disks) {
+            anyhow::bail!(
+                "failure schedule step at {:?} targets disk {} but the array has {disks} disks",
+                step.at,
+                step.disk
+            );
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    /// `is_empty` reports whether the schedule has no steps.
+    pub const fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl ScheduledStep {
+    /// `apply` runs the step against a volume.
+    ///
+    /// # Arguments
+    /// * `volume` - Volume to act on.
+    /// * `logical_end` - Logical byte position that rebuilds cover.
+    ///
+    /// # Errors
+    /// Returns an error if the disk operation or its rebuild fails.
+    pub fn apply(&self, volume: &mut dyn DynVolume, logical_end: u64) -> Result<()> {
+        self.action.apply(volume, self.disk, logical_end)
+    }
+}
+
+impl FailureAction {
+    /// `apply` runs the action on one disk of a volume.
+    ///
+    /// # Arguments
+    /// * `volume` - Volume to act on.
+    /// * `disk` - Index of the member disk.
+    /// * `logical_end` - Logical byte position that rebuilds cover.
+    ///
+    /// # Errors
+    /// Returns an error if the disk operation or its rebuild fails.
+    pub fn apply(self, volume: &mut dyn DynVolume, disk: usize, logical_end: u64) -> Result<()> {
+        let i = disk;
+        let result = match self {
+            Self::Fail => volume.fail_disk(i),
+            Self::Replace => {
+                volume.replace_disk(i)?;
+                volume.rebuild_disk_upto(i, logical_end)
+            }
+            Self::Swap => {
+                let _ = volume.fail_disk(i);
+                volume.replace_disk(i)?;
+                volume.rebuild_disk_upto(i, logical_end)
+            }
+            Self::Readd => {
+                volume.readd_disk(i)?;
+                volume.rebuild_disk_upto(i, logical_end)
+            }
+            Self::Rebuild => volume.rebuild_disk_upto(i, logical_end),
+        };
+        Ok(result?)
+    }
+}
+
+/// `parse_offset` reads a time offset such as `90`, `30s`, `1500ms`, or `2m`.
+///
+/// # Errors
+/// Returns an error if the value has no number or an unknown unit.
+pub fn parse_offset(raw: &str) -> Result<Duration> {
+    let raw = raw.trim();
+    let raw = raw.strip_prefix("t=").unwrap_or(raw);
+    let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+    let (value, unit) = raw.split_at(split);
+    let value: u64 = value
+        .parse()
+        .with_context(|| format!("invalid time offset {raw:?}"))?;
+    match unit {
+        "" | "s" => Ok(Duration::from_secs(value)),
+        "ms" => Ok(Duration::from_millis(value)),
+        "m" => Ok(Duration::from_secs(value.saturating_mul(60))),
+        "h" => Ok(Duration::from_secs(value.saturating_mul(3600))),
+        _ => Err(anyhow::anyhow!("unknown time unit {unit:?} in {raw:?}")),
+    }
+}
+
+/// `deserialize_offset` reads a time offset given as seconds or as text accepted
+/// by `parse_offset`.
+///
+/// # Errors
+/// Returns an error if the value is neither a number nor a valid offset.
+pub fn deserialize_offset<'de, De>(deserializer: De) -> Result<Duration, De::Error>
+where
+    De: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Offset {
+        Seconds(u64),
+        Text(String),
+    }
+
+    match Offset::deserialize(deserializer)? {
+        Offset::Seconds(secs) => Ok(Duration::from_secs(secs)),
+        Offset::Text(text) => parse_offset(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+    use raid_rs::layout::stripe::raid1::RAID1;
+    use raid_rs::retention::array::Array;
+    use raid_rs::retention::volume::Volume;
+
+    #[test]
+    fn parses_offsets_with_units() {
+        assert_eq!(parse_offset("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_offset("t=30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_offset("1500ms").unwrap(), Duration::from_millis(1500));
+        assert_eq!(parse_offset("2m").unwrap(), Duration::from_mins(2));
+        assert!(parse_offset("soon").is_err());
+        assert!(parse_offset("5d").is_err());
+    }
+
+    #[test]
+    fn toml_and_json_schedules_are_sorted_by_time() {
+        let toml = r#"
+            [[step]]
+            at = "90s"
+            action = "replace"
+            disk = 2
+
+            [[step]]
+            at = "30s"
+            action = "fail"
+            disk = 2
+        "#;
+        let json = r#"{"step": [
+            {"at": 90, "action": "replace", "disk": 2},
+            {"at": "t=30s", "action": "fail", "disk": 2}
+        ]}"#;
+
+        let from_toml = FailureSchedule::from_toml(toml).expect("toml schedule");
+        let from_json = FailureSchedule::from_json(json).expect("json schedule");
+
+        assert_eq!(from_toml, from_json);
+        assert_eq!(from_toml.steps[0].action, FailureAction::Fail);
+        assert_eq!(from_toml.steps[0].at, Duration::from_secs(30));
+        assert_eq!(from_toml.steps[1].action, FailureAction::Replace);
+    }
+
+    #[test]
+    fn load_rejects_unknown_disks_and_formats() {
+        let dir = temp_dir("raid-cli-schedule");
+        let path = dir.join("schedule.toml");
+        std::fs::write(&path, "[[step]]\nat = 1\naction = \"fail\"\ndisk = 3\n").unwrap();
+        assert!(FailureSchedule::load(&path, 4).is_ok());
+        let err = FailureSchedule::load(&path, 3).expect_err("disk out of range");
+        assert!(err.to_string().contains("targets disk 3"));
+
+        let yaml = dir.join("schedule.yaml");
+        std::fs::write(&yaml, "").unwrap();
+        assert!(FailureSchedule::load(&yaml, 3).is_err());
+    }
+
+    #[test]
+    fn steps_fail_and_replace_disks() {
+        let dir = temp_dir("raid-cli-schedule-apply");
+        let paths: [String; 2] = std::array::from_fn(|i| {
+            dir.join(format!("disk-{i}.img"))
+                .to_string_lossy()
+                .into_owned()
+        });
+        let mut volume = Volume::new(
+            Array::init_array(&paths, 256).expect("init array"),
+            RAID1::<2, 4>::zero(),
+        );
+        volume.clear_needs_rebuild_all();
+        volume.write_bytes(0, b"payload!");
+
+        let fail = ScheduledStep {
+            at: Duration::ZERO,
+            action: FailureAction::Fail,
+            disk: 1,
+        };
+        fail.apply(&mut volume, 8).expect("fail");
+        assert_eq!(volume.failed_disks(), 1);
+
+        let replace = ScheduledStep {
+            action: FailureAction::Replace,
+            ..fail
+        };
+        replace.apply(&mut volume, 8).expect("replace");
+        assert_eq!(volume.failed_disks(), 0);
+        let mut out = [0u8; 8];
+        volume.read_bytes(0, &mut out);
+        assert_eq!(&out, b"payload!");
+    }
+}
@@ -1,20 +1,29 @@
 //! Background sender for streaming metrics batches over gRPC.
 
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use rand::{Rng, SeedableRng, rngs::StdRng};
+use anyhow::Context;
+use rand::{Rng, rngs::StdRng};
+use tokio::signal::unix::{Signal, SignalKind, signal};
 use tokio::sync::{mpsc, watch};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::Request;
-use tonic::metadata::MetadataValue;
+use tonic::metadata::{Ascii, MetadataValue};
 use tracing::{debug, info, warn};
 
+use crate::health;
 use crate::pb::metrics as pb;
+use crate::seed::{self, Component};
+use crate::spool::Spool;
+use crate::tcp::{TlsFiles, connect_tcp};
 use crate::uds::connect_uds;
 
 /// `SenderConfig` captures connection and backoff settings for metrics streaming.
 pub struct SenderConfig {
     pub socket_path: String,
+    /// TCP endpoint (`http://` or `https://`) used instead of the socket when set.
+    pub endpoint: Option<String>,
     pub connect_timeout: Duration,
     pub rpc_timeout: Option<Duration>,
 
@@ -26,13 +35,42 @@ pub struct SenderConfig {
     pub shutdown_grace: Duration,
 
     pub auth_token: Option<String>,
+    /// File holding the auth token; read on every connect and takes precedence
+    /// over `auth_token`.
+    pub auth_token_file: Option<PathBuf>,
+    pub tls: TlsFiles,
+
+    /// Directory of the on-disk spool that keeps batches while disconnected.
+    pub spool_dir: Option<PathBuf>,
+    pub spool_max_bytes: u64,
+}
+
+impl SenderConfig {
+    /// `reloads_credentials` reports whether SIGHUP should reopen the stream.
+    const fn reloads_credentials(&self) -> bool {
+        self.auth_token_file.is_some() || self.tls.client_cert.is_some()
+    }
+}
+
+/// `DroppedEvents` counts simulator events shed by class before they were batched.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DroppedEvents {
+    pub disk_ops: u64,
+    pub raid_ops: u64,
+    pub fuse_ops: u64,
+    /// State changes dropped although op samples were shed first.
+    pub states: u64,
 }
 
 /// `SenderStats` summarizes sender outcomes after shutdown.
 pub struct SenderStats {
     pub dropped_batches: u64,
+    /// Events shed by the emitter; filled in by the caller that owns the emitter.
+    pub dropped_events: DroppedEvents,
     pub reconnects: u64,
     pub send_errors: u64,
+    /// Batches left in the spool at exit; they are replayed by the next run.
+    pub spool_depth: u64,
 }
 
 #[allow(clippy::cognitive_complexity, clippy::too_many_lines)]
@@ -52,14 +90,34 @@ pub async fn run_sender(
 ) -> SenderStats {
     let mut stats = SenderStats {
         dropped_batches: 0,
+        dropped_events: DroppedEvents::default(),
         reconnects: 0,
         send_errors: 0,
+        spool_depth: 0,
     };
 
-    let mut rng = StdRng::from_os_rng();
+    let mut spool =
+        cfg.spool_dir
+            .as_ref()
+            .and_then(|dir| match Spool::open(dir, cfg.spool_max_bytes) {
+                Ok(spool) => {
+                    info!(
+                        "sender: spool {} holds {} batches",
+                        dir.display(),
+                        spool.depth()
+                    );
+                    Some(spool)
+                }
+                Err(err) => {
+                    warn!("sender: spool disabled: {err:#}");
+                    None
+                }
+            });
+
+    let mut rng = seed::rng(Component::SenderJitter);
     let mut backoff = cfg.backoff_initial;
 
-    let auth_md: Option<MetadataValue<_>> = cfg
+    let static_auth: Option<MetadataValue<Ascii>> = cfg
         .auth_token
         .as_ref()
         .map(|s| s.trim())
@@ -74,36 +132,55 @@ pub async fn run_sender(
             },
         );
 
+    let mut hangup = if cfg.reloads_credentials() {
+        match signal(SignalKind::hangup()) {
+            Ok(sig) => Some(sig),
+            Err(err) => {
+                warn!("sender: cannot watch SIGHUP; credentials reload on reconnect only: {err}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     loop {
         if *shutdown.borrow() {
             info!("sender: shutdown requested");
             break;
         }
 
-        info!("sender: connecting via UDS: {}", cfg.socket_path);
+        let connected = async {
+            let auth_md = match &cfg.auth_token_file {
+                Some(path) => read_token_file(path)?,
+                None => static_auth.clone(),
+            };
+            let channel = if let Some(url) = &cfg.endpoint {
+                info!("sender: connecting via TCP: {url}");
+                connect_tcp(url, &cfg.tls, cfg.connect_timeout, cfg.rpc_timeout).await?
+            } else {
+                info!("sender: connecting via UDS: {}", cfg.socket_path);
+                connect_uds(&cfg.socket_path, cfg.connect_timeout, cfg.rpc_timeout).await?
+            };
+            anyhow::Ok((channel, auth_md))
+        };
 
-        let channel =
-            match connect_uds(&cfg.socket_path, cfg.connect_timeout, cfg.rpc_timeout).await {
-                Ok(ch) => {
-                    backoff = cfg.backoff_initial;
-                    ch
-                }
-                Err(err) => {
-                    stats.reconnects += 1;
-                    let sleep_dur = with_jitter(backoff, cfg.jitter_ratio, &mut rng);
-                    warn!("sender: connect failed: {err:#}; retry in {:?}", sleep_dur);
-
-                    tokio::select! {
-                        () = tokio::time::sleep(sleep_dur) => {},
-                        changed = shutdown.changed() => {
-                            let _ = changed;
-                        },
-                    }
+        let (channel, auth_md) = match connected.await {
+            Ok(conn) => {
+                backoff = cfg.backoff_initial;
+                conn
+            }
+            Err(err) => {
+                stats.reconnects += 1;
+                let sleep_dur = with_jitter(backoff, cfg.jitter_ratio, &mut rng);
+                warn!("sender: connect failed: {err:#}; retry in {:?}", sleep_dur);
 
-                    backoff = bump_backoff(backoff, cfg.backoff_max);
-                    continue;
-                }
-            };
+                wait_retry(sleep_dur, &mut shutdown, &mut rx, &mut spool, &mut stats).await;
+
+                backoff = bump_backoff(backoff, cfg.backoff_max);
+                continue;
+            }
+        };
 
         let mut client = pb::metrics_ingestor_client::MetricsIngestorClient::new(channel);
 
@@ -111,13 +188,14 @@ pub async fn run_sender(
         let outbound = ReceiverStream::new(conn_rx);
 
         let mut req = Request::new(outbound);
-        if let Some(tok) = auth_md.clone() {
+        if let Some(tok) = auth_md {
             req.metadata_mut().insert("x-metrics-token", tok);
         }
 
         let mut push_handle = tokio::spawn(async move { client.push(req).await });
 
         info!("sender: stream opened");
+        health::set_sender_connected(true);
 
         let mut push_result: Option<
             Result<
@@ -127,8 +205,23 @@ pub async fn run_sender(
         > = None;
 
         let conn_tx = conn_tx;
-        loop {
+        let mut reload = false;
+        let replayed = match spool.as_mut() {
+            Some(spool) => replay(spool, &conn_tx, &mut stats).await,
+            None => Ok(()),
+        };
+        let replayed = replayed.map_err(|err| {
+            stats.send_errors += 1;
+            warn!("sender: spool replay failed: {err:#} -> reconnect");
+        });
+        while replayed.is_ok() {
             tokio::select! {
+                () = recv_signal(&mut hangup) => {
+                    info!("sender: SIGHUP -> reloading credentials");
+                    reload = true;
+                    break;
+                },
+
                 changed = shutdown.changed() => {
                     if changed.is_err() || *shutdown.borrow() {
                         info!("sender: shutdown -> closing stream");
@@ -157,6 +250,7 @@ pub async fn run_sender(
         }
 
         drop(conn_tx);
+        health::set_sender_connected(false);
 
         if push_result.is_none() {
             match tokio::time::timeout(cfg.shutdown_grace, &mut push_handle).await {
@@ -167,6 +261,11 @@ pub async fn run_sender(
             }
         }
 
+        let acked = matches!(push_result, Some(Ok(Ok(_))));
+        if let Some(spool) = spool.as_mut() {
+            settle_replay(spool, acked);
+        }
+
         if let Some(push_outcome) = push_result {
             match push_outcome {
                 Ok(Ok(resp)) => {
@@ -192,24 +291,154 @@ pub async fn run_sender(
         }
 
         stats.reconnects += 1;
+        if reload {
+            continue;
+        }
         let sleep_dur = with_jitter(backoff, cfg.jitter_ratio, &mut rng);
         warn!("sender: reconnecting in {:?}", sleep_dur);
 
+        wait_retry(sleep_dur, &mut shutdown, &mut rx, &mut spool, &mut stats).await;
+
+        backoff = bump_backoff(backoff, cfg.backoff_max);
+    }
+
+    while let Ok(batch) = rx.try_recv() {
+        match spool.as_mut() {
+            Some(spool) => spool_batch(spool, &batch, &mut stats),
+            None => stats.dropped_batches += 1,
+        }
+    }
+
+    if let Some(spool) = spool.as_mut() {
+        if let Err(err) = spool.flush() {
+            warn!("sender: spool flush failed: {err:#}");
+        }
+        stats.spool_depth = spool.depth();
+    }
+
+    stats
+}
+
+/// `wait_retry` sleeps before a reconnect, spooling incoming batches meanwhile.
+async fn wait_retry(
+    sleep_dur: Duration,
+    shutdown: &mut watch::Receiver<bool>,
+    rx: &mut mpsc::Receiver<pb::MetricsBatch>,
+    spool: &mut Option<Spool>,
+    stats: &mut SenderStats,
+) {
+    let sleep = tokio::time::sleep(sleep_dur);
+    tokio::pin!(sleep);
+    loop {
         tokio::select! {
-            () = tokio::time::sleep(sleep_dur) => {},
+            () = &mut sleep => break,
             changed = shutdown.changed() => {
                 let _ = changed;
+                break;
+            },
+            Some(batch) = recv_to_spool(rx, spool.is_some()) => {
+                if let Some(spool) = spool.as_mut() {
+                    spool_batch(spool, &batch, stats);
+                }
             },
         }
+    }
+    if let Some(spool) = spool.as_mut()
+        && let Err(err) = spool.flush()
+    {
+        warn!("sender: spool flush failed: {err:#}");
+    }
+}
 
-        backoff = bump_backoff(backoff, cfg.backoff_max);
+async fn recv_to_spool(
+    rx: &mut mpsc::Receiver<pb::MetricsBatch>,
+    spooling: bool,
+) -> Option<pb::MetricsBatch> {
+    if spooling {
+        rx.recv().await
+    } else {
+        std::future::pending().await
     }
+}
 
-    while let Ok(_batch) = rx.try_recv() {
-        stats.dropped_batches += 1;
+fn spool_batch(spool: &mut Spool, batch: &pb::MetricsBatch, stats: &mut SenderStats) {
+    match spool.push(batch) {
+        Ok(evicted) => stats.dropped_batches += evicted,
+        Err(err) => {
+            stats.dropped_batches += 1;
+            warn!("sender: spool write failed: {err:#}");
+        }
     }
+}
 
-    stats
+/// `replay` sends spooled batches, oldest first, before live traffic resumes.
+///
+/// The replayed segments stay pinned in the spool until the stream is
+/// acknowledged; see `settle_replay`.
+async fn replay(
+    spool: &mut Spool,
+    conn_tx: &mpsc::Sender<pb::MetricsBatch>,
+    stats: &mut SenderStats,
+) -> anyhow::Result<()> {
+    let mut sent = 0u64;
+    loop {
+        let batches = match spool.take_next() {
+            Ok(Some(batches)) => batches,
+            Ok(None) => break,
+            Err(err) => {
+                warn!("sender: dropping unreadable spool segment: {err:#}");
+                stats.dropped_batches += 1;
+                continue;
+            }
+        };
+        for batch in batches {
+            conn_tx
+                .send(batch)
+                .await
+                .context("stream closed during replay")?;
+            sent += 1;
+        }
+    }
+    if sent > 0 {
+        info!("sender: replayed {sent} spooled batches");
+    }
+    Ok(())
+}
+
+/// `settle_replay` deletes the replayed segments once the gateway acknowledged the
+/// stream, or keeps them for the next connection when it did not.
+fn settle_replay(spool: &mut Spool, acked: bool) {
+    if !acked {
+        spool.release_taken();
+        return;
+    }
+    match spool.ack_taken() {
+        Ok(0) => {}
+        Ok(n) => debug!("sender: gateway acknowledged {n} spooled batches"),
+        Err(err) => warn!("sender: failed to delete acknowledged spool segments: {err:#}"),
+    }
+}
+
+/// `read_token_file` loads an auth token; an empty file disables the header.
+fn read_token_file(path: &Path) -> anyhow::Result<Option<MetadataValue<Ascii>>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("read auth token file {}", path.display()))?;
+    let token = raw.trim();
+    if token.is_empty() {
+        return Ok(None);
+    }
+    let value = MetadataValue::try_from(token)
+        .with_context(|| format!("auth token in {} is not a valid header", path.display()))?;
+    Ok(Some(value))
+}
+
+async fn recv_signal(sig: &mut Option<Signal>) {
+    match sig {
+        Some(sig) => {
+            sig.recv().await;
+        }
+        None => std::future::pending().await,
+    }
 }
 
 fn bump_backoff(cur: Duration, max: Duration) -> Duration {
@@ -237,6 +466,7 @@ fn with_jitter(base: Duration, ratio: f64, rng: &mut StdRng) -> Duration {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand::SeedableRng;
 
     #[test]
     fn bump_backoff_doubles_until_max() {
@@ -246,6 +476,23 @@ mod tests {
         assert_eq!(bump_backoff(Duration::from_millis(400), max), max);
     }
 
+    #[test]
+    fn read_token_file_trims_and_validates() {
+        let dir = crate::fs::test_utils::temp_dir("raid-cli-token");
+        let path = dir.join("token");
+
+        std::fs::write(&path, "secret-1\n").unwrap();
+        let token = read_token_file(&path).unwrap().unwrap();
+        assert_eq!(token.to_str().unwrap(), "secret-1");
+
+        std::fs::write(&path, "  \n").unwrap();
+        assert!(read_token_file(&path).unwrap().is_none());
+
+        std::fs::write(&path, "bad\u{7f}token").unwrap();
+        assert!(read_token_file(&path).is_err());
+        assert!(read_token_file(&dir.join("missing")).is_err());
+    }
+
     #[test]
     fn with_jitter_respects_zero_ratio() {
         let mut rng = StdRng::seed_from_u64(1);
@@ -3,10 +3,22 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use prost_types::Timestamp;
-use rand::{Rng, SeedableRng, rngs::StdRng};
+use raid_rs::metrics::{DiskOp, IoClass, RaidOp};
+use rand::{Rng, rngs::StdRng};
 use rand_distr::{Distribution, Exp};
 
+use crate::access::{self, ZipfOffsets};
 use crate::pb::metrics as pb;
+use crate::seed::{self, Component};
+use crate::trace::TraceOp;
+use crate::workload::Workload;
+
+/// `SYNTHETIC_CAPACITY` is the logical size of the volume synthetic RAID
+/// operations are spread over.
+const SYNTHETIC_CAPACITY: u64 = 1 << 30;
+
+/// `SYNTHETIC_BLOCK` is the granularity of synthetic RAID operation offsets.
+const SYNTHETIC_BLOCK: u64 = 4096;
 
 /// `SyntheticSimulator` generates randomized metrics batches for testing.
 pub struct SyntheticSimulator {
@@ -17,6 +29,8 @@ pub struct SyntheticSimulator {
     exp_raid: Exp<f64>,
     exp_fuse: Exp<f64>,
     cpu_seconds: f64,
+    offsets: ZipfOffsets,
+    workload: Workload,
 }
 
 impl SyntheticSimulator {
@@ -25,29 +39,45 @@ impl SyntheticSimulator {
     /// # Arguments
     /// * `disk_ids` - Disk identifiers to emit in samples.
     /// * `raid_ids` - RAID identifiers to emit in samples.
-    pub fn new(disk_ids: Vec<String>, raid_ids: Vec<String>) -> Self {
+    /// * `workload` - Operation mix of the generated batches.
+    pub fn new(disk_ids: Vec<String>, raid_ids: Vec<String>, workload: Workload) -> Self {
         let exp_disk = Exp::new(1.0 / 0.002).unwrap();
         let exp_raid = Exp::new(1.0 / 0.003).unwrap();
         let exp_fuse = Exp::new(1.0 / 0.0015).unwrap();
+        let offsets =
+            ZipfOffsets::new(SYNTHETIC_CAPACITY, SYNTHETIC_BLOCK, workload.zipf_skew).unwrap();
 
         Self {
-            rng: StdRng::from_os_rng(),
+            rng: seed::rng(Component::Simulator),
             disk_ids,
             raid_ids,
             exp_disk,
             exp_raid,
             exp_fuse,
             cpu_seconds: 0.0,
+            offsets,
+            workload,
         }
     }
 
+    /// `set_workload` switches the operation mix from the next batch on.
+    ///
+    /// # Arguments
+    /// * `workload` - New operation mix.
+    pub fn set_workload(&mut self, workload: Workload) {
+        self.offsets =
+            ZipfOffsets::new(SYNTHETIC_CAPACITY, SYNTHETIC_BLOCK, workload.zipf_skew).unwrap();
+        self.workload = workload;
+    }
+
     #[allow(clippy::too_many_lines)]
     /// `next_batch` emits a synthetic metrics batch using the current RNG state.
     ///
     /// # Arguments
     /// * `source_id` - Identifier for the metrics source.
     /// * `seq_no` - Monotonic sequence number for the batch.
-    /// * `ops_per_tick` - Approximate number of operations to generate.
+    /// * `ops_per_tick` - Average number of operations per batch before the
+    ///   workload's intensity and burstiness apply.
     ///
     /// # Returns
     /// A populated `MetricsBatch` ready to send.
@@ -69,6 +99,7 @@ impl SyntheticSimulator {
             disk_states.push(pb::DiskState {
                 disk_id: d.clone(),
                 queue_depth: self.rng.random_range(0.0..32.0),
+                ..Default::default()
             });
         }
 
@@ -93,17 +124,41 @@ impl SyntheticSimulator {
                 degraded,
                 failed_disks: failed,
                 rebuild_in_progress: rebuild,
+                pool_used_bytes: 0,
+                pool_capacity_bytes: 0,
+                check_stripes_checked: 0,
+                check_repairable_stripes: 0,
+                check_uncorrectable_stripes: 0,
+                uncorrectable_stripes: 0,
+                read_only: false,
+                rebuild_stripes_total: 0,
+                rebuild_stripes_done: 0,
+                rebuild_bytes_per_second: 0.0,
+                rebuild_eta_seconds: 0.0,
+                scrub_stripes_total: 0,
+                scrub_stripes_done: 0,
+                scrub_mismatches: 0,
+                stale_members: 0,
+                balance_bytes_total: 0,
+                balance_bytes_done: 0,
+                balance_bytes_per_second: 0.0,
+                write_hole_policy: String::new(),
+                torn_stripes: 0,
+                write_hole_replayed_stripes: 0,
+                full_stripe_rejected_writes: 0,
             });
         }
 
-        let per = ops_per_tick.max(1) as usize;
+        let per = self.ops_this_tick(ops_per_tick.max(1));
+        let read_ratio = self.workload.read_ratio;
+        let error_probability = self.workload.error_probability;
         for _ in 0..per {
             {
                 let disk_id = self.pick_disk().to_string();
-                let is_read = self.rng.random_bool(0.55);
+                let is_read = self.rng.random_bool(read_ratio);
                 let bytes = self.pick_bytes();
                 let latency = self.sample_disk_latency(0.050);
-                let error = self.rng.random_bool(0.001);
+                let error = self.rng.random_bool(error_probability);
 
                 disk_ops.push(pb::DiskOp {
                     disk_id,
@@ -120,10 +175,13 @@ impl SyntheticSimulator {
 
             {
                 let raid_id = self.pick_raid().to_string();
-                let is_read = self.rng.random_bool(0.50);
+                let is_read = self.rng.random_bool(read_ratio);
                 let bytes = self.pick_bytes();
                 let latency = self.sample_raid_latency(0.080);
-                let error = self.rng.random_bool(0.001);
+                let error = self.rng.random_bool(error_probability);
+                let offset = self.offsets.sample(&mut self.rng);
+                let region =
+                    u32::try_from(access::region(offset, SYNTHETIC_CAPACITY)).map_or(0, |r| r + 1);
 
                 let served_from_disk_id =
                     if raid_id == "raid1" && is_read && self.rng.random_bool(0.7) {
@@ -156,12 +214,19 @@ impl SyntheticSimulator {
                     raid3_parity_read: parity_r,
                     raid3_parity_write: parity_w,
                     raid3_partial_stripe_write: partial_w,
+                    degraded: false,
+                    region,
+                    read_ahead_hit_stripes: 0,
+                    read_ahead_miss_stripes: 0,
+                    parity_cache_hit_stripes: 0,
+                    parity_cache_miss_stripes: 0,
+                    background: false,
                 });
             }
 
             {
                 let roll: f64 = self.rng.random();
-                let (op, bytes, latency) = if roll < 0.45 {
+                let (op, bytes, latency) = if roll < 0.90 * read_ratio {
                     (
                         pb::FuseOpType::FuseOpRead,
                         self.pick_bytes(),
@@ -179,10 +244,13 @@ impl SyntheticSimulator {
                     (pb::FuseOpType::FuseOpFsync, 0, 0.0)
                 };
 
-                let error = self.rng.random_bool(0.0005);
+                let error = self.rng.random_bool(error_probability / 2.0);
+                let inode = self.rng.random_range(1..=16u64);
 
                 fuse_ops.push(pb::FuseOp {
                     op: op as i32,
+                    inode,
+                    name_hash: 0,
                     bytes,
                     latency_seconds: latency,
                     error,
@@ -196,6 +264,8 @@ impl SyntheticSimulator {
         let process = Some(pb::ProcessSample {
             cpu_seconds: self.cpu_seconds,
             resident_memory_bytes: resident_memory,
+            compression_ratio: 1.0,
+            compression_cpu_seconds: 0.0,
         });
 
         pb::MetricsBatch {
@@ -207,10 +277,45 @@ impl SyntheticSimulator {
             raid_ops,
             raid_states,
             fuse_ops,
+            volume_states: Vec::new(),
             process,
         }
     }
 
+    /// `replay_op` samples the RAID and disk operations a traced request causes.
+    ///
+    /// The request's bytes are spread evenly over the disks; latencies follow the
+    /// same distributions as `next_batch`.
+    ///
+    /// # Arguments
+    /// * `op` - Traced request to replay.
+    pub fn replay_op(&mut self, op: &TraceOp) -> (RaidOp, Vec<DiskOp>) {
+        let share = op.size.div_ceil(self.disk_ids.len().max(1) as u64);
+        let mut disk_ops = Vec::with_capacity(self.disk_ids.len());
+        for i in 0..self.disk_ids.len() {
+            disk_ops.push(DiskOp {
+                disk_id: self.disk_ids[i].clone(),
+                op: op.op,
+                bytes: share,
+                latency_seconds: self.sample_disk_latency(0.050),
+                error: false,
+            });
+        }
+        let raid_op = RaidOp {
+            op: op.op,
+            bytes: op.size,
+            latency_seconds: self.sample_raid_latency(0.080),
+            error: false,
+            degraded: false,
+            read_ahead_hits: 0,
+            read_ahead_misses: 0,
+            parity_cache_hits: 0,
+            parity_cache_misses: 0,
+            class: IoClass::Foreground,
+        };
+        (raid_op, disk_ops)
+    }
+
     fn pick_disk(&mut self) -> &str {
         let i = self.rng.random_range(0..self.disk_ids.len());
         &self.disk_ids[i]
@@ -221,10 +326,28 @@ impl SyntheticSimulator {
         &self.raid_ids[i]
     }
 
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    /// `ops_this_tick` scales the average load by the workload and concentrates it
+    /// into busy ticks according to the burstiness.
+    fn ops_this_tick(&mut self, ops_per_tick: u32) -> usize {
+        let busy = 1.0 - self.workload.burstiness;
+        if !self.rng.random_bool(busy) {
+            return 0;
+        }
+        (f64::from(ops_per_tick) * self.workload.intensity / busy).round() as usize
+    }
+
     fn pick_bytes(&mut self) -> u64 {
-        let choices = [4096u64, 8192, 16384, 32768, 65536, 131_072, 262_144];
-        let i = self.rng.random_range(0..choices.len());
-        choices[i]
+        let sizes = &self.workload.block_sizes;
+        let total: f64 = sizes.iter().map(|s| s.weight).sum();
+        let mut roll = self.rng.random_range(0.0..total);
+        for size in sizes {
+            if roll < size.weight {
+                return size.bytes;
+            }
+            roll -= size.weight;
+        }
+        sizes.last().map_or(0, |s| s.bytes)
     }
 
     fn sample_disk_latency(&mut self, cap_seconds: f64) -> f64 {
@@ -271,6 +394,8 @@ mod tests {
             exp_raid,
             exp_fuse,
             cpu_seconds: 0.0,
+            offsets: ZipfOffsets::new(SYNTHETIC_CAPACITY, SYNTHETIC_BLOCK, 0.0).unwrap(),
+            workload: Workload::default(),
         }
     }
 
@@ -300,6 +425,7 @@ mod tests {
         }
         for op in &batch.raid_ops {
             assert!(raid_set.contains(&op.raid_id));
+            assert!(op.region >= 1 && op.region as usize <= access::HEAT_REGIONS);
         }
         for state in &batch.raid_states {
             assert!(raid_set.contains(&state.raid_id));
@@ -334,6 +460,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn bursty_workload_concentrates_ops_into_busy_ticks() {
+        let mut sim = seeded_simulator(vec!["disk0".to_string()], vec!["raid0".to_string()]);
+        sim.workload = Workload::profile(crate::cli::WorkloadProfile::Idle);
+
+        let counts: Vec<usize> = (0..200).map(|_| sim.ops_this_tick(100)).collect();
+
+        assert!(counts.iter().all(|&n| n == 0 || n == 50));
+        assert!(counts.contains(&0));
+        assert!(counts.contains(&50));
+        assert!(sim.pick_bytes() <= 8192);
+    }
+
+    #[test]
+    fn replay_op_spreads_bytes_over_disks() {
+        let disk_ids = vec!["disk0".to_string(), "disk1".to_string()];
+        let mut sim = seeded_simulator(disk_ids, vec!["raid0".to_string()]);
+        let op = TraceOp {
+            timestamp: 0.0,
+            op: raid_rs::metrics::IoOpType::Write,
+            offset: 0,
+            size: 8193,
+        };
+
+        let (raid_op, disk_ops) = sim.replay_op(&op);
+
+        assert_eq!(raid_op.bytes, 8193);
+        assert_eq!(disk_ops.len(), 2);
+        assert_eq!(disk_ops[1].disk_id, "disk1");
+        assert!(disk_ops.iter().all(|d| d.bytes == 4097));
+        assert!((0.0..=0.08).contains(&raid_op.latency_seconds));
+    }
+
     #[test]
     fn sampled_values_stay_within_expected_ranges() {
         let disk_ids = vec!["disk0".to_string(), "disk1".to_string()];
@@ -0,0 +1,389 @@
+//! On-disk spool for metrics batches that could not be sent.
+//!
+//! Batches are appended as length-prefixed protobuf records to numbered segment
+//! files (`spool-<seq>.bin`). The spool is a ring of segments: once its total size
+//! exceeds the bound, the oldest segments are evicted. Segments left over from a
+//! previous run are picked up on open, so batches survive restarts as well as
+//! gateway outages. Replay is at-least-once: a segment handed to a stream is pinned,
+//! so eviction skips it, and it is deleted only once the stream acknowledged it.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use prost::Message;
+
+use crate::pb::metrics as pb;
+
+/// Number of segments the size bound is split into.
+const SEGMENTS_PER_SPOOL: u64 = 8;
+
+struct Segment {
+    seq: u64,
+    bytes: u64,
+    records: u64,
+    /// Handed to a stream that has not acknowledged it yet.
+    pinned: bool,
+}
+
+/// `Spool` persists metrics batches in a bounded ring of segment files.
+pub struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    segment_bytes: u64,
+    segments: VecDeque<Segment>,
+    writer: Option<BufWriter<File>>,
+}
+
+impl Spool {
+    /// `open` creates the spool directory or recovers the segments already in it.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory holding the segment files.
+    /// * `max_bytes` - Upper bound on the total size of all segments.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created or read.
+    pub fn open(dir: &Path, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("create spool dir {}", dir.display()))?;
+
+        let mut seqs = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            if let Some(seq) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("spool-"))
+                .and_then(|n| n.strip_suffix(".bin"))
+                .and_then(|n| n.parse::<u64>().ok())
+            {
+                seqs.push(seq);
+            }
+        }
+        seqs.sort_unstable();
+
+        let mut segments = VecDeque::with_capacity(seqs.len());
+        for seq in seqs {
+            let path = segment_path(dir, seq);
+            let (bytes, records) = recover_segment(&path)?;
+            if records == 0 {
+                std::fs::remove_file(&path)?;
+                continue;
+            }
+            segments.push_back(Segment {
+                seq,
+                bytes,
+                records,
+                pinned: false,
+            });
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_bytes: max_bytes.max(1),
+            segment_bytes: (max_bytes / SEGMENTS_PER_SPOOL).max(1),
+            segments,
+            writer: None,
+        })
+    }
+
+    #[must_use]
+    /// `depth` returns the number of batches held by the spool.
+    pub fn depth(&self) -> u64 {
+        self.segments.iter().map(|s| s.records).sum()
+    }
+
+    /// `push` appends a batch, evicting the oldest segments beyond the size bound.
+    ///
+    /// Segments pinned by `take_next` are never evicted; the spool may exceed its
+    /// bound until they are acknowledged or released.
+    ///
+    /// # Returns
+    /// The number of batches evicted to make room.
+    ///
+    /// # Errors
+    /// Returns an error if the segment file cannot be written.
+    pub fn push(&mut self, batch: &pb::MetricsBatch) -> Result<u64> {
+        let record = batch.encode_length_delimited_to_vec();
+        let len = record.len() as u64;
+
+        let rotate = self.writer.is_none()
+            || self
+                .segments
+                .back()
+                .is_none_or(|s| s.bytes + len > self.segment_bytes && s.records > 0);
+        if rotate {
+            self.flush()?;
+            let seq = self.segments.back().map_or(0, |s| s.seq + 1);
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(segment_path(&self.dir, seq))?;
+            self.writer = Some(BufWriter::new(file));
+            self.segments.push_back(Segment {
+                seq,
+                bytes: 0,
+                records: 0,
+                pinned: false,
+            });
+        }
+
+        if let (Some(writer), Some(segment)) = (self.writer.as_mut(), self.segments.back_mut()) {
+            writer.write_all(&record)?;
+            segment.bytes += len;
+            segment.records += 1;
+        }
+
+        let mut evicted = 0;
+        while self.total_bytes() > self.max_bytes {
+            let active = self.segments.len().saturating_sub(1);
+            let Some(index) = self.segments.iter().take(active).position(|s| !s.pinned) else {
+                break;
+            };
+            if let Some(oldest) = self.segments.remove(index) {
+                std::fs::remove_file(segment_path(&self.dir, oldest.seq))?;
+                evicted += oldest.records;
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// `take_next` reads every batch of the oldest segment not yet handed to a
+    /// stream and pins it until `ack_taken` or `release_taken`.
+    ///
+    /// Batches pushed afterwards go to a new segment, so acknowledging the taken
+    /// segments never deletes batches that were not sent.
+    ///
+    /// # Errors
+    /// Returns an error if the segment cannot be read; the unreadable segment is
+    /// deleted so replay can move past it.
+    pub fn take_next(&mut self) -> Result<Option<Vec<pb::MetricsBatch>>> {
+        let Some(index) = self.segments.iter().position(|s| !s.pinned) else {
+            return Ok(None);
+        };
+        if index + 1 == self.segments.len() {
+            self.flush()?;
+            self.writer = None;
+        }
+        let seq = self.segments[index].seq;
+        let path = segment_path(&self.dir, seq);
+        let decoded = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|raw| {
+                let mut buf = raw.as_slice();
+                let mut batches = Vec::new();
+                while !buf.is_empty() {
+                    batches.push(pb::MetricsBatch::decode_length_delimited(&mut buf)?);
+                }
+                Ok(batches)
+            });
+        match decoded {
+            Ok(batches) => {
+                self.segments[index].pinned = true;
+                Ok(Some(batches))
+            }
+            Err(err) => {
+                self.segments.remove(index);
+                std::fs::remove_file(&path)?;
+                Err(err.context(format!("read spool segment {}", path.display())))
+            }
+        }
+    }
+
+    /// `ack_taken` deletes the segments the stream acknowledged.
+    ///
+    /// # Returns
+    /// The number of batches deleted.
+    ///
+    /// # Errors
+    /// Returns an error if a segment file cannot be removed.
+    pub fn ack_taken(&mut self) -> Result<u64> {
+        let mut acked = 0;
+        while let Some(index) = self.segments.iter().position(|s| s.pinned) {
+            if let Some(segment) = self.segments.remove(index) {
+                std::fs::remove_file(segment_path(&self.dir, segment.seq))?;
+                acked += segment.records;
+            }
+        }
+        if self.segments.is_empty() {
+            self.writer = None;
+        }
+        Ok(acked)
+    }
+
+    /// `release_taken` unpins the taken segments so the next replay sends them again.
+    pub fn release_taken(&mut self) {
+        for segment in &mut self.segments {
+            segment.pinned = false;
+        }
+    }
+
+    /// `flush` writes buffered records of the active segment to disk.
+    ///
+    /// # Errors
+    /// Returns an error if the segment file cannot be written.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.segments.iter().map(|s| s.bytes).sum()
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn segment_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("spool-{seq:020}.bin"))
+}
+
+/// `recover_segment` counts the complete records of a segment and cuts off a torn
+/// tail left by a crash mid-write.
+fn recover_segment(path: &Path) -> Result<(u64, u64)> {
+    let mut raw = Vec::new();
+    File::open(path)?.read_to_end(&mut raw)?;
+
+    let mut buf = raw.as_slice();
+    let mut records = 0;
+    while !buf.is_empty() {
+        let before = buf;
+        if pb::MetricsBatch::decode_length_delimited(&mut buf).is_err() {
+            buf = before;
+            break;
+        }
+        records += 1;
+    }
+    let valid = (raw.len() - buf.len()) as u64;
+    if !buf.is_empty() {
+        OpenOptions::new().write(true).open(path)?.set_len(valid)?;
+    }
+    Ok((valid, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+
+    fn batch(seq_no: u64) -> pb::MetricsBatch {
+        pb::MetricsBatch {
+            source_id: "spool-test".to_string(),
+            seq_no,
+            ..Default::default()
+        }
+    }
+
+    fn drain(spool: &mut Spool) -> Vec<u64> {
+        let mut seqs = Vec::new();
+        while let Some(batches) = spool.take_next().unwrap() {
+            seqs.extend(batches.iter().map(|b| b.seq_no));
+            spool.ack_taken().unwrap();
+        }
+        seqs
+    }
+
+    #[test]
+    fn replays_batches_in_order_across_reopen() {
+        let dir = temp_dir("raid-cli-spool");
+        {
+            let mut spool = Spool::open(&dir, 1 << 20).unwrap();
+            for seq in 1..=5 {
+                assert_eq!(spool.push(&batch(seq)).unwrap(), 0);
+            }
+            assert_eq!(spool.depth(), 5);
+        }
+
+        let mut spool = Spool::open(&dir, 1 << 20).unwrap();
+        assert_eq!(spool.depth(), 5);
+        spool.push(&batch(6)).unwrap();
+        assert_eq!(drain(&mut spool), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(spool.depth(), 0);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn evicts_oldest_segments_beyond_the_bound() {
+        let dir = temp_dir("raid-cli-spool-bound");
+        let record = batch(1).encode_length_delimited_to_vec().len() as u64;
+        let mut spool = Spool::open(&dir, record * 16).unwrap();
+
+        let evicted: u64 = (1..=40).map(|seq| spool.push(&batch(seq)).unwrap()).sum();
+
+        assert_eq!(spool.depth() + evicted, 40);
+        assert!(spool.total_bytes() <= record * 16);
+        let seqs = drain(&mut spool);
+        assert_eq!(seqs.last(), Some(&40));
+        assert!(seqs.windows(2).all(|w| w[1] == w[0] + 1));
+    }
+
+    #[test]
+    fn eviction_skips_the_segment_being_replayed() {
+        let dir = temp_dir("raid-cli-spool-pinned");
+        let record = batch(1).encode_length_delimited_to_vec().len() as u64;
+        let mut spool = Spool::open(&dir, record * 16).unwrap();
+        for seq in 1..=16 {
+            spool.push(&batch(seq)).unwrap();
+        }
+
+        let taken = spool.take_next().unwrap().expect("segment to replay");
+        let taken: Vec<u64> = taken.iter().map(|b| b.seq_no).collect();
+        assert_eq!(taken, [1, 2]);
+        let evicted: u64 = (17..=40).map(|seq| spool.push(&batch(seq)).unwrap()).sum();
+        assert!(evicted > 0);
+
+        // The pinned segment survived eviction and is deleted by the ack alone.
+        assert_eq!(spool.ack_taken().unwrap(), 2);
+        assert_eq!(spool.depth() + evicted, 38);
+        let rest = drain(&mut spool);
+        assert_eq!(rest.last(), Some(&40));
+        assert!(rest.iter().all(|&seq| seq > 2));
+        assert!(rest.windows(2).all(|w| w[1] == w[0] + 1));
+    }
+
+    #[test]
+    fn released_segments_are_replayed_again() {
+        let dir = temp_dir("raid-cli-spool-release");
+        let mut spool = Spool::open(&dir, 1 << 20).unwrap();
+        spool.push(&batch(1)).unwrap();
+        assert_eq!(spool.take_next().unwrap().map(|b| b.len()), Some(1));
+        spool.push(&batch(2)).unwrap();
+        assert!(spool.take_next().unwrap().is_some());
+        assert!(spool.take_next().unwrap().is_none());
+
+        spool.release_taken();
+        assert_eq!(spool.depth(), 2);
+        assert_eq!(drain(&mut spool), vec![1, 2]);
+    }
+
+    #[test]
+    fn open_truncates_a_torn_record() {
+        let dir = temp_dir("raid-cli-spool-torn");
+        {
+            let mut spool = Spool::open(&dir, 1 << 20).unwrap();
+            spool.push(&batch(1)).unwrap();
+            spool.push(&batch(2)).unwrap();
+        }
+        let path = segment_path(&dir, 0);
+        let len = std::fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 2)
+            .unwrap();
+
+        let mut spool = Spool::open(&dir, 1 << 20).unwrap();
+        assert_eq!(spool.depth(), 1);
+        spool.push(&batch(3)).unwrap();
+        assert_eq!(drain(&mut spool), vec![1, 3]);
+    }
+}
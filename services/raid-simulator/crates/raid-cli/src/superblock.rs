@@ -0,0 +1,940 @@
+//! Per-member superblocks identifying the disks of an array.
+//!
+//! Every member image keeps a superblock at the start of its metadata tail, the
+//! [`METADATA_BYTES`] the disk never serves, recording the array it belongs to, its
+//! role in the array and the layout. The superblock travels with the image when it
+//! is renamed, failed or split off, and a blank replacement image has none. Arrays
+//! are stamped when they are opened, so `assemble` can later put members back in
+//! order without being told the geometry.
+//!
+//! The generation counts membership changes. Each change writes the new generation,
+//! and the roles missing at that point, to the members in sync. An image that was
+//! pulled from the array keeps an older generation and is rebuilt when it returns;
+//! two members that each carried on without the other have split and are refused.
+//! A member that returns while the array still records it as absent comes back
+//! with the contents it left with, so only the regions the write-intent bitmap
+//! marked since then are resynced.
+
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use raid_rs::retention::array::{MemberState, MemberStatus};
+use raid_rs::retention::disk::METADATA_BYTES;
+
+use crate::cli::RaidMode;
+use crate::commands::disk_image_path;
+use crate::fs::DEFAULT_CHUNK_SIZE;
+
+/// `SUPERBLOCK_SIZE` is the encoded size of a superblock in bytes.
+pub const SUPERBLOCK_SIZE: usize = 64;
+
+/// `MAX_MEMBERS` bounds the members of an array, one bit of `absent` each.
+pub const MAX_MEMBERS: usize = 64;
+
+const MAGIC: [u8; 8] = *b"RAIDSB01";
+
+/// `Superblock` describes one member disk and the array it belongs to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Superblock {
+    pub array_uuid: [u8; 16],
+    /// Position of the member in the array.
+    pub role: usize,
+    pub disks: usize,
+    pub layout: RaidMode,
+    pub chunk_size: usize,
+    pub disk_size: u64,
+    /// Membership changes seen by the member, starting at 1.
+    pub generation: u64,
+    /// Roles missing from the array when the generation was written, one bit each.
+    pub absent: u64,
+}
+
+/// `Standing` is how a member relates to the newest member of its array.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Standing {
+    /// The member saw every membership change.
+    Current,
+    /// The member missed membership changes; its contents are out of date.
+    Stale,
+    /// The member belongs to another array.
+    Foreign,
+    /// The member and the newest member each carried on without the other.
+    SplitBrain,
+}
+
+impl Superblock {
+    #[must_use]
+    /// `new` describes a fresh array with a random UUID.
+    ///
+    /// # Arguments
+    /// * `layout` - RAID mode of the array.
+    /// * `disks` - Number of member disks.
+    /// * `disk_size` - Size of each disk image in bytes.
+    pub fn new(layout: RaidMode, disks: usize, disk_size: u64) -> Self {
+        Self {
+            array_uuid: rand::random(),
+            role: 0,
+            disks,
+            layout,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            disk_size,
+            generation: 1,
+            absent: 0,
+        }
+    }
+
+    #[must_use]
+    /// `for_role` returns the superblock of another member of the same array.
+    pub const fn for_role(self, role: usize) -> Self {
+        Self { role, ..self }
+    }
+
+    #[must_use]
+    /// `standing` classifies this member against the newest member of the array.
+    ///
+    /// # Arguments
+    /// * `newest` - Superblock with the highest generation among the members.
+    pub fn standing(&self, newest: &Self) -> Standing {
+        if self.array_uuid != newest.array_uuid {
+            Standing::Foreign
+        } else if self.role != newest.role
+            && self.absent & role_bit(newest.role) != 0
+            && newest.absent & role_bit(self.role) != 0
+        {
+            Standing::SplitBrain
+        } else if self.generation < newest.generation {
+            Standing::Stale
+        } else {
+            Standing::Current
+        }
+    }
+
+    #[must_use]
+    /// `same_geometry` reports whether two superblocks describe the same layout.
+    pub fn same_geometry(&self, other: &Self) -> bool {
+        self.disks == other.disks
+            && self.layout == other.layout
+            && self.chunk_size == other.chunk_size
+            && self.disk_size == other.disk_size
+    }
+
+    #[must_use]
+    /// `uuid` formats the array UUID in the usual 8-4-4-4-12 form.
+    pub fn uuid(&self) -> String {
+        let hex = self
+            .array_uuid
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .concat();
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+
+    #[must_use]
+    /// `to_bytes` encodes the superblock in little-endian order.
+    pub fn to_bytes(self) -> [u8; SUPERBLOCK_SIZE] {
+        let mut out = [0u8; SUPERBLOCK_SIZE];
+        out[0..8].copy_from_slice(&MAGIC);
+        out[8..24].copy_from_slice(&self.array_uuid);
+        out[24..28].copy_from_slice(&u32::try_from(self.role).unwrap_or(u32::MAX).to_le_bytes());
+        out[28..32].copy_from_slice(&u32::try_from(self.disks).unwrap_or(u32::MAX).to_le_bytes());
+        out[32..36].copy_from_slice(&layout_code(self.layout).to_le_bytes());
+        out[36..40].copy_from_slice(
+            &u32::try_from(self.chunk_size)
+                .unwrap_or(u32::MAX)
+                .to_le_bytes(),
+        );
+        out[40..48].copy_from_slice(&self.disk_size.to_le_bytes());
+        out[48..56].copy_from_slice(&self.generation.to_le_bytes());
+        out[56..64].copy_from_slice(&self.absent.to_le_bytes());
+        out
+    }
+
+    /// `from_bytes` decodes a superblock written by `to_bytes`.
+    ///
+    /// # Errors
+    /// Returns an error if the buffer is short, the magic is wrong, the layout is unknown
+    /// or the role does not fit in an array of at most [`MAX_MEMBERS`] members.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < SUPERBLOCK_SIZE || bytes[0..8] != MAGIC {
+            anyhow::bail!("not a raid superblock");
+        }
+        let u32_at = |at: usize| {
+            let mut buf = [0u8; 4];
+            buf.copy_from_slice(&bytes[at..at + 4]);
+            u32::from_le_bytes(buf) as usize
+        };
+        let u64_at = |at: usize| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[at..at + 8]);
+            u64::from_le_bytes(buf)
+        };
+        let mut array_uuid = [0u8; 16];
+        array_uuid.copy_from_slice(&bytes[8..24]);
+        let layout = match u32_at(32) {
+            0 => RaidMode::Raid0,
+            1 => RaidMode::Raid1,
+            3 => RaidMode::Raid3,
+            4 => RaidMode::Declustered,
+            code => anyhow::bail!("unknown raid layout {code}"),
+        };
+        let (role, disks) = (u32_at(24), u32_at(28));
+        if disks > MAX_MEMBERS || role >= disks {
+            anyhow::bail!(
+                "superblock records role {role} of {disks} members; at most {MAX_MEMBERS} are supported"
+            );
+        }
+        Ok(Self {
+            array_uuid,
+            role,
+            disks,
+            layout,
+            chunk_size: u32_at(36),
+            disk_size: u64_at(40),
+            generation: u64_at(48),
+            absent: u64_at(56),
+        })
+    }
+}
+
+const fn layout_code(layout: RaidMode) -> u32 {
+    match layout {
+        RaidMode::Raid0 => 0,
+        RaidMode::Raid1 => 1,
+        RaidMode::Raid3 => 3,
+        RaidMode::Declustered => 4,
+    }
+}
+
+/// `read` loads the superblock from the metadata tail of a member image.
+///
+/// # Arguments
+/// * `image` - Path of the member image.
+///
+/// # Returns
+/// `None` if the image is missing or carries no superblock.
+///
+/// # Errors
+/// Returns an error if the image cannot be read or its superblock cannot be decoded.
+pub fn read(image: &Path) -> Result<Option<Superblock>> {
+    let file = match File::open(image) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("open {}", image.display())),
+    };
+    let len = file
+        .metadata()
+        .with_context(|| format!("stat {}", image.display()))?
+        .len();
+    let Some(at) = len.checked_sub(METADATA_BYTES) else {
+        return Ok(None);
+    };
+    let mut bytes = [0u8; SUPERBLOCK_SIZE];
+    file.read_exact_at(&mut bytes, at)
+        .with_context(|| format!("read {}", image.display()))?;
+    if bytes[..MAGIC.len()] != MAGIC {
+        return Ok(None);
+    }
+    Superblock::from_bytes(&bytes)
+        .with_context(|| format!("decode the superblock of {}", image.display()))
+        .map(Some)
+}
+
+/// `write` stores the superblock in the metadata tail of a member image.
+///
+/// The image is sized for the data the superblock describes, as opening the array
+/// would size it.
+///
+/// # Arguments
+/// * `image` - Path of the member image.
+/// * `superblock` - Superblock describing the member.
+///
+/// # Errors
+/// Returns an error if the image does not exist or cannot be written.
+pub fn write(image: &Path, superblock: &Superblock) -> Result<()> {
+    let file = OpenOptions::new()
+        .write(true)
+        .open(image)
+        .with_context(|| format!("open {}", image.display()))?;
+    file.set_len(superblock.disk_size.saturating_add(METADATA_BYTES))
+        .and_then(|()| file.write_all_at(&superblock.to_bytes(), superblock.disk_size))
+        .with_context(|| format!("write the superblock of {}", image.display()))
+}
+
+/// `Stamp` is the outcome of stamping the members of an array that is being opened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stamp {
+    /// Superblock of the array as opened, with role 0.
+    pub array: Superblock,
+    /// Members whose contents are older than the array and must be rebuilt.
+    pub stale: Vec<usize>,
+    /// Stale members the array recorded as absent, which only need the regions
+    /// written while they were away.
+    pub rejoined: Vec<usize>,
+    /// Members stamped before their image exists; see [`Stamp::write_pending`].
+    pub pending: Vec<usize>,
+}
+
+impl Stamp {
+    /// `write_pending` stamps the members whose image did not exist yet.
+    ///
+    /// A superblock lives inside its image, so call this once opening the array has
+    /// created the missing images.
+    ///
+    /// # Arguments
+    /// * `disk_dir` - Directory containing disk images.
+    ///
+    /// # Errors
+    /// Returns an error if a superblock cannot be written.
+    pub fn write_pending(&self, disk_dir: &Path) -> Result<()> {
+        for &role in &self.pending {
+            write(&disk_image_path(disk_dir, role), &self.array.for_role(role))?;
+        }
+        Ok(())
+    }
+}
+
+/// `stamp` checks the members of an array against their superblocks and records the
+/// geometry it is opened with.
+///
+/// A fresh array gets a new UUID. Members that missed membership changes, or that
+/// carry no superblock while others do, are reported as stale instead of trusted.
+/// Opening the array with members missing is a membership change and advances the
+/// generation of the members that take part.
+///
+/// # Arguments
+/// * `layout` - RAID mode of the array.
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Number of member disks.
+/// * `disk_size` - Size of each disk image in bytes.
+///
+/// # Errors
+/// Returns an error if the array has more than [`MAX_MEMBERS`] members, the
+/// superblocks describe another geometry, a member belongs to another array, sits in
+/// another role or was used apart from the rest of the array, or if a superblock
+/// cannot be read or written.
+pub fn stamp(layout: RaidMode, disk_dir: &Path, disks: usize, disk_size: u64) -> Result<Stamp> {
+    stamp_geometry(layout, disk_dir, disks, disk_size, Stamping::Stamp)
+}
+
+/// `check` validates the members of an array like [`stamp`] without writing to
+/// them, for arrays opened read-only.
+///
+/// The returned stamp describes the array as found; its generation is not advanced
+/// and nothing is left pending.
+///
+/// # Errors
+/// Returns an error if the array has more than [`MAX_MEMBERS`] members, the
+/// superblocks describe another geometry, a member belongs to another array, sits in
+/// another role or was used apart from the rest of the array, or if a superblock
+/// cannot be read.
+pub fn check(layout: RaidMode, disk_dir: &Path, disks: usize, disk_size: u64) -> Result<Stamp> {
+    stamp_geometry(layout, disk_dir, disks, disk_size, Stamping::Check)
+}
+
+/// `restamp` is [`stamp`] for an array whose data was just moved into a new
+/// geometry by a grow, shrink or migration.
+///
+/// The superblocks take the new geometry and a new generation, and every member
+/// is rewritten.
+///
+/// # Errors
+/// Returns an error if the array has more than [`MAX_MEMBERS`] members, a member
+/// belongs to another array, sits in another role or was used apart from the rest of
+/// the array, or if a superblock cannot be read or written.
+pub fn restamp(layout: RaidMode, disk_dir: &Path, disks: usize, disk_size: u64) -> Result<Stamp> {
+    stamp_geometry(layout, disk_dir, disks, disk_size, Stamping::Restamp)
+}
+
+/// `Stamping` is what opening an array may do to its superblocks.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Stamping {
+    /// Validate them without writing.
+    Check,
+    /// Record membership changes.
+    Stamp,
+    /// Record membership changes and a new geometry.
+    Restamp,
+}
+
+fn stamp_geometry(
+    layout: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+    stamping: Stamping,
+) -> Result<Stamp> {
+    if disks > MAX_MEMBERS {
+        anyhow::bail!("superblocks track at most {MAX_MEMBERS} members, not {disks}");
+    }
+    std::fs::create_dir_all(disk_dir)
+        .with_context(|| format!("failed to create disk directory {}", disk_dir.display()))?;
+    let images: Vec<PathBuf> = (0..disks).map(|i| disk_image_path(disk_dir, i)).collect();
+    let existing = images
+        .iter()
+        .map(|image| read(image))
+        .collect::<Result<Vec<_>>>()?;
+    let Some(newest) = existing
+        .iter()
+        .flatten()
+        .max_by_key(|sb| sb.generation)
+        .copied()
+    else {
+        return stamp_fresh(Superblock::new(layout, disks, disk_size), &images, stamping);
+    };
+
+    let mut stale = Vec::new();
+    let mut rejoined = Vec::new();
+    for (role, (image, sb)) in images.iter().zip(&existing).enumerate() {
+        let Some(sb) = sb else {
+            if image.exists() {
+                stale.push(role);
+            }
+            continue;
+        };
+        if sb.role != role {
+            anyhow::bail!(
+                "{} holds role {} of the array; run assemble to restore the member order",
+                image.display(),
+                sb.role
+            );
+        }
+        match sb.standing(&newest) {
+            Standing::Foreign => anyhow::bail!(
+                "{} belongs to array {}, not {}",
+                image.display(),
+                sb.uuid(),
+                newest.uuid()
+            ),
+            Standing::SplitBrain => anyhow::bail!(
+                "{} and {} were each used without the other; remove the one to discard",
+                image.display(),
+                disk_image_path(disk_dir, newest.role).display()
+            ),
+            Standing::Stale if image.exists() && newest.absent & role_bit(role) != 0 => {
+                rejoined.push(role);
+            }
+            Standing::Stale if image.exists() => stale.push(role),
+            Standing::Stale | Standing::Current => {}
+        }
+    }
+
+    let mut array = Superblock {
+        role: 0,
+        disks,
+        layout,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        disk_size,
+        ..newest
+    };
+    let missing = images
+        .iter()
+        .enumerate()
+        .filter(|(_, image)| !image.exists())
+        .fold(0, |bits, (role, _)| bits | role_bit(role));
+    let reshaped = !newest.same_geometry(&array);
+    if reshaped && stamping != Stamping::Restamp {
+        return Err(geometry_mismatch(&newest, &array));
+    }
+    if reshaped {
+        stale.clear();
+        rejoined.clear();
+    } else if missing == 0 || stamping == Stamping::Check {
+        return Ok(Stamp {
+            array,
+            stale,
+            rejoined,
+            pending: Vec::new(),
+        });
+    }
+
+    array.generation += 1;
+    array.absent = if reshaped { 0 } else { missing };
+    let mut pending = Vec::new();
+    for (role, image) in images.iter().enumerate() {
+        if reshaped || (image.exists() && !stale.contains(&role) && !rejoined.contains(&role)) {
+            write_or_defer(image, &array.for_role(role), &mut pending)?;
+        }
+    }
+    Ok(Stamp {
+        array,
+        stale,
+        rejoined,
+        pending,
+    })
+}
+
+/// `geometry_mismatch` is the error of opening an array with a geometry its
+/// superblocks do not describe.
+fn geometry_mismatch(recorded: &Superblock, requested: &Superblock) -> anyhow::Error {
+    anyhow::anyhow!(
+        "geometry mismatch: the superblocks describe {:?} x{} with {}-byte chunks on \
+         {}-byte disks, not {:?} x{} with {}-byte chunks on {}-byte disks; use grow, shrink \
+         or migrate to change the geometry",
+        recorded.layout,
+        recorded.disks,
+        recorded.chunk_size,
+        recorded.disk_size,
+        requested.layout,
+        requested.disks,
+        requested.chunk_size,
+        requested.disk_size
+    )
+}
+
+/// `stamp_fresh` writes the superblocks of a new array to its members unless it is
+/// only checked.
+fn stamp_fresh(array: Superblock, images: &[PathBuf], stamping: Stamping) -> Result<Stamp> {
+    let mut pending = Vec::new();
+    if stamping != Stamping::Check {
+        for (role, image) in images.iter().enumerate() {
+            write_or_defer(image, &array.for_role(role), &mut pending)?;
+        }
+    }
+    Ok(Stamp {
+        array,
+        stale: Vec::new(),
+        rejoined: Vec::new(),
+        pending,
+    })
+}
+
+/// `write_or_defer` stamps a member, or leaves it to [`Stamp::write_pending`] when
+/// its image does not exist yet.
+fn write_or_defer(image: &Path, superblock: &Superblock, pending: &mut Vec<usize>) -> Result<()> {
+    if image.exists() {
+        write(image, superblock)
+    } else {
+        pending.push(superblock.role);
+        Ok(())
+    }
+}
+
+/// `advance_generation` records a membership change in the superblocks of the
+/// members that are present and in sync.
+///
+/// Members awaiting a rebuild keep their superblock until `record_rebuilt`.
+///
+/// # Arguments
+/// * `members` - Status of every member after the change.
+///
+/// # Returns
+/// The new generation, or `None` if no member in sync has a superblock.
+///
+/// # Errors
+/// Returns an error if a superblock cannot be read or written.
+pub fn advance_generation(members: &[MemberStatus]) -> Result<Option<u64>> {
+    let Some(newest) = newest_in_sync(members, None)? else {
+        return Ok(None);
+    };
+    let absent = members
+        .iter()
+        .filter(|m| m.state == MemberState::Failed || !m.image_exists)
+        .fold(0, |bits, m| bits | role_bit(m.index));
+
+    let next = Superblock {
+        generation: newest.generation + 1,
+        absent,
+        ..newest
+    };
+    for m in members.iter().filter(|m| in_sync(m)) {
+        write(Path::new(&m.path), &next.for_role(m.index))?;
+    }
+    Ok(Some(next.generation))
+}
+
+/// `record_rebuilt` gives a member that finished its rebuild the superblock of the
+/// members it was rebuilt from.
+///
+/// # Arguments
+/// * `members` - Status of every member after the rebuild.
+/// * `index` - Index of the rebuilt member.
+///
+/// # Errors
+/// Returns an error if a superblock cannot be read or written.
+pub fn record_rebuilt(members: &[MemberStatus], index: usize) -> Result<()> {
+    let (Some(newest), Some(member)) = (
+        newest_in_sync(members, Some(index))?,
+        members.iter().find(|m| m.index == index),
+    ) else {
+        return Ok(());
+    };
+    let rebuilt = Superblock {
+        absent: newest.absent & !role_bit(index),
+        ..newest.for_role(index)
+    };
+    write(Path::new(&member.path), &rebuilt)
+}
+
+fn in_sync(member: &MemberStatus) -> bool {
+    member.state == MemberState::Ok && member.image_exists
+}
+
+/// `newest_in_sync` returns the newest superblock among members in sync, other than `skip`.
+fn newest_in_sync(members: &[MemberStatus], skip: Option<usize>) -> Result<Option<Superblock>> {
+    let mut newest: Option<Superblock> = None;
+    for m in members
+        .iter()
+        .filter(|m| in_sync(m) && Some(m.index) != skip)
+    {
+        if let Some(sb) = read(Path::new(&m.path))?
+            && newest.is_none_or(|n| sb.generation > n.generation)
+        {
+            newest = Some(sb);
+        }
+    }
+    Ok(newest)
+}
+
+/// `role_bit` returns the `absent` bit of a role; stamping and decoding refuse roles
+/// past [`MAX_MEMBERS`].
+const fn role_bit(role: usize) -> u64 {
+    assert!(role < MAX_MEMBERS, "role out of range");
+    1 << role
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::FileExt;
+    use std::path::Path;
+
+    use raid_rs::retention::disk::METADATA_BYTES;
+
+    use super::SUPERBLOCK_SIZE;
+
+    /// `erase` zeroes the superblock in the metadata tail of a member image.
+    pub fn erase(image: &Path) {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(image)
+            .expect("open image");
+        let len = file.metadata().expect("stat image").len();
+        file.write_all_at(&[0; SUPERBLOCK_SIZE], len - METADATA_BYTES)
+            .expect("erase superblock");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::ValueEnum;
+
+    use super::test_utils::erase;
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+
+    /// `touch` creates the member images that do not exist yet.
+    fn touch(dir: &Path, disks: usize) {
+        for i in 0..disks {
+            let image = disk_image_path(dir, i);
+            if !image.exists() {
+                std::fs::write(image, [0u8; 64]).expect("write image");
+            }
+        }
+    }
+
+    /// `stamped` stamps an array whose member images all exist.
+    fn stamped(layout: RaidMode, dir: &Path, disks: usize) -> Stamp {
+        touch(dir, disks);
+        stamp(layout, dir, disks, 64).expect("stamp")
+    }
+
+    fn member(dir: &Path, index: usize, state: MemberState) -> MemberStatus {
+        let path = disk_image_path(dir, index);
+        MemberStatus {
+            index,
+            state,
+            image_exists: path.exists(),
+            path: path.display().to_string(),
+            io: raid_rs::retention::disk::DiskStats::default(),
+            health: raid_rs::retention::disk::DiskHealth::default(),
+        }
+    }
+
+    #[test]
+    fn superblock_round_trips() {
+        let sb = Superblock {
+            role: 2,
+            generation: 7,
+            absent: 0b1001,
+            ..Superblock::new(RaidMode::Raid3, 4, 4096)
+        };
+
+        let decoded = Superblock::from_bytes(&sb.to_bytes()).expect("decode");
+
+        assert_eq!(decoded, sb);
+        assert_eq!(sb.uuid().len(), 36);
+        assert!(Superblock::from_bytes(&[0u8; SUPERBLOCK_SIZE]).is_err());
+    }
+
+    #[test]
+    fn superblocks_cover_every_layout() {
+        for &layout in RaidMode::value_variants() {
+            let sb = Superblock::new(layout, 4, 4096).for_role(3);
+            let decoded = Superblock::from_bytes(&sb.to_bytes()).expect("decode");
+            assert_eq!(decoded, sb, "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn superblocks_refuse_roles_past_the_absent_bitmap() {
+        let widest = Superblock::new(RaidMode::Raid0, MAX_MEMBERS, 64).for_role(MAX_MEMBERS - 1);
+        assert!(Superblock::from_bytes(&widest.to_bytes()).is_ok());
+
+        let too_wide = Superblock::new(RaidMode::Raid0, MAX_MEMBERS + 1, 64).for_role(MAX_MEMBERS);
+        let err = Superblock::from_bytes(&too_wide.to_bytes()).expect_err("role 64");
+        assert!(err.to_string().contains("at most 64"));
+        let outside = Superblock::new(RaidMode::Raid0, 2, 64).for_role(2);
+        assert!(Superblock::from_bytes(&outside.to_bytes()).is_err());
+
+        let dir = temp_dir("raid-cli-sb-too-wide");
+        let err = stamp(RaidMode::Raid0, &dir, MAX_MEMBERS + 1, 64).expect_err("65 members");
+        assert!(err.to_string().contains("at most 64 members"));
+    }
+
+    #[test]
+    fn superblock_lives_in_the_image_tail() {
+        let dir = temp_dir("raid-cli-sb-tail");
+        let first = stamped(RaidMode::Raid3, &dir, 3);
+        let image = disk_image_path(&dir, 1);
+
+        let len = std::fs::metadata(&image).expect("stat").len();
+        assert_eq!(len, 64 + METADATA_BYTES);
+        let moved = dir.join("moved.img");
+        std::fs::rename(&image, &moved).expect("move image");
+        let sb = read(&moved).expect("read").expect("superblock");
+        assert_eq!((sb.role, sb.array_uuid), (1, first.array.array_uuid));
+        assert!(read(&image).expect("read").is_none());
+
+        std::fs::write(&image, [0u8; 64]).expect("blank image");
+        assert!(read(&image).expect("read").is_none());
+    }
+
+    #[test]
+    fn stamp_writes_every_member_once() {
+        let dir = temp_dir("raid-cli-sb-stamp");
+        let first = stamped(RaidMode::Raid3, &dir, 3);
+        let again = stamp(RaidMode::Raid3, &dir, 3, 64).expect("stamp again");
+
+        assert_eq!(again, first);
+        assert!(first.stale.is_empty() && first.pending.is_empty());
+        let first = first.array;
+        let sb = read(&disk_image_path(&dir, 2))
+            .expect("read")
+            .expect("superblock");
+        assert_eq!(sb.role, 2);
+        assert_eq!(sb.array_uuid, first.array_uuid);
+        assert_eq!(sb.generation, 1);
+    }
+
+    #[test]
+    fn stamp_defers_members_until_their_image_exists() {
+        let dir = temp_dir("raid-cli-sb-pending");
+        let first = stamp(RaidMode::Raid3, &dir, 3, 64).expect("stamp");
+
+        assert_eq!(first.pending, vec![0, 1, 2]);
+        assert!(read(&disk_image_path(&dir, 0)).expect("read").is_none());
+
+        touch(&dir, 3);
+        first.write_pending(&dir).expect("write pending");
+        let sb = read(&disk_image_path(&dir, 2))
+            .expect("read")
+            .expect("superblock");
+        assert_eq!((sb.role, sb.array_uuid), (2, first.array.array_uuid));
+    }
+
+    #[test]
+    fn stamp_bumps_generation_on_reshape() {
+        let dir = temp_dir("raid-cli-sb-reshape");
+        stamped(RaidMode::Raid3, &dir, 3);
+
+        let grown = restamp(RaidMode::Raid3, &dir, 4, 64).expect("restamp grown");
+        touch(&dir, 4);
+        grown.write_pending(&dir).expect("write pending");
+
+        assert_eq!(grown.array.generation, 2);
+        assert_eq!(grown.pending, vec![3]);
+        for i in 0..4 {
+            let sb = read(&disk_image_path(&dir, i))
+                .expect("read")
+                .expect("superblock");
+            assert_eq!((sb.disks, sb.generation), (4, 2));
+        }
+    }
+
+    #[test]
+    fn stamp_refuses_another_geometry() {
+        let dir = temp_dir("raid-cli-sb-mismatch");
+        let first = stamped(RaidMode::Raid3, &dir, 3);
+        touch(&dir, 4);
+
+        for (layout, disks, disk_size) in [
+            (RaidMode::Raid3, 4, 64),
+            (RaidMode::Raid0, 3, 64),
+            (RaidMode::Raid3, 3, 128),
+        ] {
+            let err = stamp(layout, &dir, disks, disk_size).expect_err("other geometry");
+            assert!(err.to_string().contains("geometry mismatch"), "{err:#}");
+        }
+        for i in 0..3 {
+            let sb = read(&disk_image_path(&dir, i))
+                .expect("read")
+                .expect("superblock");
+            assert_eq!(sb, first.array.for_role(i));
+        }
+    }
+
+    #[test]
+    fn check_validates_without_writing() {
+        let dir = temp_dir("raid-cli-sb-check");
+        let first = stamped(RaidMode::Raid3, &dir, 3);
+        std::fs::remove_file(disk_image_path(&dir, 2)).expect("remove member");
+
+        let checked = check(RaidMode::Raid3, &dir, 3, 64).expect("check");
+        assert_eq!(checked.array, first.array);
+        assert!(checked.pending.is_empty());
+        for i in 0..2 {
+            let sb = read(&disk_image_path(&dir, i))
+                .expect("read")
+                .expect("superblock");
+            assert_eq!(sb.generation, 1);
+        }
+        let err = check(RaidMode::Raid3, &dir, 4, 64).expect_err("other geometry");
+        assert!(err.to_string().contains("geometry mismatch"), "{err:#}");
+    }
+
+    #[test]
+    fn stamp_refuses_foreign_and_misplaced_members() {
+        let dir = temp_dir("raid-cli-sb-refuse");
+        stamped(RaidMode::Raid1, &dir, 2);
+        let image = disk_image_path(&dir, 1);
+        let sb = read(&image).expect("read").expect("superblock");
+
+        write(&image, &Superblock::new(RaidMode::Raid1, 2, 64).for_role(1)).expect("write");
+        let err = stamp(RaidMode::Raid1, &dir, 2, 64).expect_err("foreign member");
+        assert!(err.to_string().contains("belongs to array"));
+
+        write(&image, &sb.for_role(0)).expect("write");
+        let err = stamp(RaidMode::Raid1, &dir, 2, 64).expect_err("misplaced member");
+        assert!(err.to_string().contains("holds role 0"));
+    }
+
+    #[test]
+    fn stamp_reports_stale_members_without_trusting_them() {
+        let dir = temp_dir("raid-cli-sb-stale");
+        stamped(RaidMode::Raid3, &dir, 3);
+        let sb = read(&disk_image_path(&dir, 0))
+            .expect("read")
+            .expect("superblock");
+        write(
+            &disk_image_path(&dir, 0),
+            &Superblock {
+                generation: 2,
+                absent: role_bit(1),
+                ..sb
+            },
+        )
+        .expect("write");
+        erase(&disk_image_path(&dir, 2));
+
+        let stamped = stamp(RaidMode::Raid3, &dir, 3, 64).expect("stamp");
+
+        assert_eq!(stamped.stale, vec![2]);
+        assert_eq!(stamped.rejoined, vec![1]);
+        assert_eq!(stamped.array.generation, 2);
+        assert!(read(&disk_image_path(&dir, 2)).expect("read").is_none());
+    }
+
+    #[test]
+    fn stamp_refuses_members_that_split() {
+        let dir = temp_dir("raid-cli-sb-split");
+        stamped(RaidMode::Raid1, &dir, 2);
+        for (role, other) in [(0, 1), (1, 0)] {
+            let image = disk_image_path(&dir, role);
+            let sb = read(&image).expect("read").expect("superblock");
+            let split = Superblock {
+                generation: 2 + role as u64,
+                absent: role_bit(other),
+                ..sb
+            };
+            write(&image, &split).expect("write");
+        }
+
+        let err = stamp(RaidMode::Raid1, &dir, 2, 64).expect_err("split brain");
+
+        assert!(err.to_string().contains("each used without the other"));
+    }
+
+    #[test]
+    fn stamp_advances_generation_when_members_are_missing() {
+        let dir = temp_dir("raid-cli-sb-missing");
+        stamped(RaidMode::Raid3, &dir, 3);
+        let pulled = dir.join("pulled.img");
+        std::fs::rename(disk_image_path(&dir, 1), &pulled).expect("pull image");
+
+        let stamped = stamp(RaidMode::Raid3, &dir, 3, 64).expect("stamp");
+
+        assert_eq!(stamped.array.generation, 2);
+        assert!(stamped.pending.is_empty());
+        let sb = read(&disk_image_path(&dir, 0))
+            .expect("read")
+            .expect("superblock");
+        assert_eq!((sb.generation, sb.absent), (2, role_bit(1)));
+        let left = read(&pulled).expect("read").expect("superblock");
+        assert_eq!(left.standing(&sb), Standing::Stale);
+    }
+
+    #[test]
+    fn recreated_images_are_rebuilt_in_full() {
+        let dir = temp_dir("raid-cli-sb-recreated");
+        stamped(RaidMode::Raid3, &dir, 3);
+        std::fs::remove_file(disk_image_path(&dir, 1)).expect("remove image");
+
+        stamp(RaidMode::Raid3, &dir, 3, 64).expect("stamp");
+        let reopened = stamped(RaidMode::Raid3, &dir, 3);
+
+        assert_eq!(reopened.stale, vec![1]);
+        assert!(reopened.rejoined.is_empty());
+    }
+
+    #[test]
+    fn advance_generation_skips_members_out_of_sync() {
+        let dir = temp_dir("raid-cli-sb-advance");
+        stamped(RaidMode::Raid3, &dir, 3);
+        let members = [
+            member(&dir, 0, MemberState::Ok),
+            member(&dir, 1, MemberState::Failed),
+            member(&dir, 2, MemberState::NeedsRebuild),
+        ];
+
+        assert_eq!(advance_generation(&members).expect("advance"), Some(2));
+
+        let generation = |i| {
+            read(&disk_image_path(&dir, i))
+                .expect("read")
+                .expect("superblock")
+                .generation
+        };
+        assert_eq!((generation(0), generation(1), generation(2)), (2, 1, 1));
+
+        let rebuilt = [
+            member(&dir, 0, MemberState::Ok),
+            member(&dir, 1, MemberState::Failed),
+            member(&dir, 2, MemberState::Ok),
+        ];
+        record_rebuilt(&rebuilt, 2).expect("record rebuilt");
+        let sb = read(&disk_image_path(&dir, 2))
+            .expect("read")
+            .expect("superblock");
+        assert_eq!((sb.role, sb.generation, sb.absent), (2, 2, role_bit(1)));
+    }
+}
@@ -0,0 +1,158 @@
+//! TCP and TLS helpers for connecting to the metrics gateway over the network.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, bail};
+use http::Uri;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+
+/// `TlsFiles` names PEM files used for TLS connections.
+///
+/// The files are read on every connect, so replacing them on disk takes effect at
+/// the next reconnect.
+#[derive(Clone, Debug, Default)]
+pub struct TlsFiles {
+    /// Extra CA certificate trusted next to the web PKI roots.
+    pub ca_cert: Option<PathBuf>,
+    /// Client certificate presented for mutual TLS.
+    pub client_cert: Option<PathBuf>,
+    /// Private key of the client certificate.
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsFiles {
+    fn client_config(&self) -> anyhow::Result<ClientTlsConfig> {
+        let mut config = ClientTlsConfig::new().with_enabled_roots();
+        if let Some(path) = &self.ca_cert {
+            config = config.ca_certificate(Certificate::from_pem(read_pem(path)?));
+        }
+        match (&self.client_cert, &self.client_key) {
+            (Some(cert), Some(key)) => {
+                config = config.identity(Identity::from_pem(read_pem(cert)?, read_pem(key)?));
+            }
+            (None, None) => {}
+            _ => bail!("a TLS client certificate and key must be given together"),
+        }
+        Ok(config)
+    }
+
+    const fn is_set(&self) -> bool {
+        self.ca_cert.is_some() || self.client_cert.is_some() || self.client_key.is_some()
+    }
+}
+
+fn read_pem(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("read {}", path.display()))
+}
+
+/// `connect_tcp` connects to a gRPC endpoint given as an `http://` or `https://` URL.
+///
+/// `https` endpoints are verified against the bundled web PKI roots and the CA in
+/// `tls`; a client certificate in `tls` is presented for mutual TLS.
+///
+/// # Arguments
+/// * `url` - Gateway address, e.g. `https://metrics.example.com:443`.
+/// * `tls` - Certificate files for `https` endpoints.
+/// * `connect_timeout` - Timeout for establishing the connection.
+/// * `rpc_timeout` - Optional per-RPC timeout.
+///
+/// # Returns
+/// A configured gRPC channel.
+///
+/// # Errors
+/// Returns an error if the URL is invalid or the connection cannot be established.
+pub async fn connect_tcp(
+    url: &str,
+    tls: &TlsFiles,
+    connect_timeout: Duration,
+    rpc_timeout: Option<Duration>,
+) -> anyhow::Result<Channel> {
+    let mut endpoint = endpoint(url)?.connect_timeout(connect_timeout);
+
+    if let Some(t) = rpc_timeout {
+        endpoint = endpoint.timeout(t);
+    }
+
+    if endpoint.uri().scheme_str() == Some("https") {
+        endpoint = endpoint
+            .tls_config(tls.client_config()?)
+            .context("configure TLS")?;
+    } else if tls.is_set() {
+        bail!("TLS certificates require an https:// metrics endpoint");
+    }
+
+    let channel = endpoint
+        .connect()
+        .await
+        .context("connect to TCP endpoint")?;
+
+    Ok(channel)
+}
+
+fn endpoint(url: &str) -> anyhow::Result<Endpoint> {
+    let uri: Uri = url
+        .parse()
+        .with_context(|| format!("invalid metrics endpoint {url:?}"))?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        bail!("metrics endpoint {url:?} must use http:// or https://");
+    }
+    Ok(Endpoint::from(uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_requires_http_scheme() {
+        assert!(endpoint("http://gateway:50051").is_ok());
+        assert!(endpoint("https://gateway").is_ok());
+        let err = endpoint("grpc://gateway:50051").expect_err("expected error");
+        assert!(err.to_string().contains("must use http:// or https://"));
+    }
+
+    #[tokio::test]
+    async fn connect_tcp_errors_for_closed_port() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let err = connect_tcp(
+            &format!("http://{addr}"),
+            &TlsFiles::default(),
+            Duration::from_millis(100),
+            None,
+        )
+        .await
+        .expect_err("expected error");
+        let msg = format!("{err:#}");
+        assert!(msg.contains("connect to TCP endpoint"));
+    }
+
+    #[tokio::test]
+    async fn connect_tcp_validates_tls_files() {
+        let half = TlsFiles {
+            client_cert: Some(PathBuf::from("/tmp/raid-cli-client.pem")),
+            ..TlsFiles::default()
+        };
+        let err = connect_tcp("https://gateway", &half, Duration::from_millis(10), None)
+            .await
+            .expect_err("expected error");
+        assert!(err.to_string().contains("certificate and key"));
+
+        let missing = TlsFiles {
+            ca_cert: Some(PathBuf::from("/tmp/raid-cli-missing-ca.pem")),
+            ..TlsFiles::default()
+        };
+        let err = connect_tcp("https://gateway", &missing, Duration::from_millis(10), None)
+            .await
+            .expect_err("expected error");
+        assert!(err.to_string().contains("raid-cli-missing-ca.pem"));
+
+        let err = connect_tcp("http://gateway", &missing, Duration::from_millis(10), None)
+            .await
+            .expect_err("expected error");
+        assert!(err.to_string().contains("require an https://"));
+    }
+}
@@ -0,0 +1,164 @@
+//! Block IO traces replayed by the `replay` subcommand.
+//!
+//! Two text formats are accepted and may not be mixed within one file:
+//! * CSV rows of `timestamp,op,offset,size`, with the timestamp in seconds, `op`
+//!   being `r`/`read` or `w`/`write` and offset and size in bytes. A header row
+//!   and `#` comments are skipped.
+//! * `blkparse` default output. Only queue (`Q`) events are replayed; sectors are
+//!   512 bytes and events without a read or write direction, as well as the
+//!   summary blkparse prints at the end, are skipped.
+//!
+//! The format is picked from the first line that is not blank or a comment.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use raid_rs::metrics::IoOpType;
+
+/// `SECTOR_SIZE` is the sector size `blkparse` reports offsets and lengths in.
+const SECTOR_SIZE: u64 = 512;
+
+/// `TraceOp` is one IO request of a workload trace.
+#[derive(Copy, Clone, Debug)]
+pub struct TraceOp {
+    /// Seconds since an arbitrary trace epoch.
+    pub timestamp: f64,
+    pub op: IoOpType,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// `load` reads and parses a trace file.
+///
+/// # Arguments
+/// * `path` - CSV or `blkparse` trace file.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or a line cannot be parsed.
+pub fn load(path: &Path) -> Result<Vec<TraceOp>> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("read trace {}", path.display()))?;
+    parse(&text).with_context(|| format!("parse trace {}", path.display()))
+}
+
+/// `parse` parses trace text; ops are returned in timestamp order.
+///
+/// # Arguments
+/// * `text` - CSV or `blkparse` trace.
+///
+/// # Errors
+/// Returns an error naming the first line that cannot be parsed.
+pub fn parse(text: &str) -> Result<Vec<TraceOp>> {
+    let mut ops = Vec::new();
+    let mut csv = None;
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let first = csv.is_none();
+        let parsed = if *csv.get_or_insert_with(|| line.split(',').count() == 4) {
+            parse_csv(line, first)
+        } else {
+            parse_blkparse(line)
+        };
+        if let Some(op) = parsed.with_context(|| format!("line {}: {line}", n + 1))? {
+            ops.push(op);
+        }
+    }
+    ops.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    Ok(ops)
+}
+
+fn parse_csv(line: &str, first: bool) -> Result<Option<TraceOp>> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [timestamp, op, offset, size] = fields[..] else {
+        anyhow::bail!("expected timestamp,op,offset,size");
+    };
+    let Ok(timestamp) = timestamp.parse::<f64>() else {
+        if first {
+            return Ok(None);
+        }
+        anyhow::bail!("invalid timestamp {timestamp:?}");
+    };
+    let op = match op.to_ascii_lowercase().as_str() {
+        "r" | "read" => IoOpType::Read,
+        "w" | "write" => IoOpType::Write,
+        other => anyhow::bail!("unknown op {other:?}"),
+    };
+    Ok(Some(TraceOp {
+        timestamp,
+        op,
+        offset: offset.parse().context("invalid offset")?,
+        size: size.parse().context("invalid size")?,
+    }))
+}
+
+fn parse_blkparse(line: &str) -> Result<Option<TraceOp>> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    // dev cpu seq timestamp pid action rwbs sector + sectors [process]
+    let [_, _, _, timestamp, _, action, rwbs, rest @ ..] = &fields[..] else {
+        return Ok(None);
+    };
+    if *action != "Q" {
+        return Ok(None);
+    }
+    let op = if rwbs.contains('R') {
+        IoOpType::Read
+    } else if rwbs.contains('W') {
+        IoOpType::Write
+    } else {
+        return Ok(None);
+    };
+    let [sector, plus, sectors, ..] = rest else {
+        return Ok(None);
+    };
+    if *plus != "+" {
+        return Ok(None);
+    }
+    let sector: u64 = sector.parse().context("invalid sector")?;
+    let sectors: u64 = sectors.parse().context("invalid sector count")?;
+    Ok(Some(TraceOp {
+        timestamp: timestamp.parse().context("invalid timestamp")?,
+        op,
+        offset: sector * SECTOR_SIZE,
+        size: sectors * SECTOR_SIZE,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_csv_with_header_and_sorts_by_time() {
+        let ops = parse("timestamp,op,offset,size\n0.5,w,4096,8192\n# comment\n0.1,read,0,512\n")
+            .expect("parse");
+
+        assert_eq!(ops.len(), 2);
+        assert!(matches!(ops[0].op, IoOpType::Read));
+        assert_eq!(ops[0].offset, 0);
+        assert!(matches!(ops[1].op, IoOpType::Write));
+        assert_eq!((ops[1].offset, ops[1].size), (4096, 8192));
+        assert!(parse("0.1,x,0,512\n").is_err());
+    }
+
+    #[test]
+    fn parses_blkparse_queue_events() {
+        let text = "\
+  8,0    3        1     0.000000000   697  Q   W 223490 + 8 [kjournald]
+  8,0    3        2     0.000001000   697  G   W 223490 + 8 [kjournald]
+  8,0    0        3     0.250000000   911  Q  RA 1024 + 16 [cat]
+  8,0    0        4     0.300000000   911  Q  FN [sync]
+CPU0 (8,0):
+ Reads Queued:           1,        8KiB
+";
+        let ops = parse(text).expect("parse");
+
+        assert_eq!(ops.len(), 2);
+        assert!(matches!(ops[0].op, IoOpType::Write));
+        assert_eq!((ops[0].offset, ops[0].size), (223_490 * 512, 4096));
+        assert!(matches!(ops[1].op, IoOpType::Read));
+        assert!((ops[1].timestamp - 0.25).abs() < f64::EPSILON);
+    }
+}
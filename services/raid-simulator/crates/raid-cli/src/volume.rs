@@ -0,0 +1,398 @@
+//! Runtime selection of RAID volumes for offline commands.
+
+use std::path::Path;
+
+use anyhow::Result;
+use raid_rs::layout::stripe::declustered::Declustered;
+use raid_rs::layout::stripe::raid0::RAID0;
+use raid_rs::layout::stripe::raid1::RAID1;
+use raid_rs::layout::stripe::raid3::RAID3;
+use raid_rs::retention::disk::DiskIo;
+use raid_rs::retention::volume::DynVolume;
+use raid_rs::simulator::SimulatorBuilder;
+
+use crate::cli::{DiskIoMode, RaidMode};
+use crate::fs::{DEFAULT_CHUNK_SIZE, HEADER_SIZE, RaidFs};
+use crate::superblock;
+
+type HeaderFs = RaidFs<1, DEFAULT_CHUNK_SIZE, RAID0<1, DEFAULT_CHUNK_SIZE>>;
+
+/// `DECLUSTERED_GROUP_WIDTH` is the parity group width of declustered arrays.
+pub const DECLUSTERED_GROUP_WIDTH: usize = 3;
+
+/// `DeclusteredLayout` is the declustered stripe layout mounted for `RaidMode::Declustered`.
+pub type DeclusteredLayout<const D: usize, const N: usize> =
+    Declustered<D, N, DECLUSTERED_GROUP_WIDTH>;
+
+/// `validate_geometry` checks that the RAID mode supports the requested disk count.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disks` - Number of member disks.
+///
+/// # Errors
+/// Returns an error if the combination is not supported.
+pub fn validate_geometry(mode: RaidMode, disks: usize) -> Result<()> {
+    match (mode, disks) {
+        (_, 0 | 9..) => Err(anyhow::anyhow!(
+            "unsupported disk count {disks}; supported range is 1-8"
+        )),
+        (RaidMode::Raid0, 1) => Ok(()),
+        (RaidMode::Declustered, ..DECLUSTERED_GROUP_WIDTH) => Err(anyhow::anyhow!(
+            "declustered layout requires at least {DECLUSTERED_GROUP_WIDTH} disks"
+        )),
+        (_, 1) => Err(anyhow::anyhow!("raid mode requires at least 2 disks")),
+        _ => Ok(()),
+    }
+}
+
+/// `data_disks` returns how many members of the array hold data chunks.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disks` - Number of member disks.
+#[must_use]
+pub const fn data_disks(mode: RaidMode, disks: usize) -> usize {
+    match mode {
+        RaidMode::Raid0 => disks,
+        RaidMode::Raid1 => 1,
+        RaidMode::Raid3 => disks.saturating_sub(1),
+        RaidMode::Declustered => disks - disks / DECLUSTERED_GROUP_WIDTH,
+    }
+}
+
+/// `logical_capacity` computes the logical capacity without opening any disk images.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disks` - Number of member disks.
+/// * `disk_size` - Size of each disk image in bytes.
+#[must_use]
+pub const fn logical_capacity(mode: RaidMode, disks: usize, disk_size: u64) -> u64 {
+    disk_size.saturating_mul(data_disks(mode, disks) as u64)
+}
+
+/// `disk_io` resolves a CLI disk access mode to a backend compiled into this binary.
+///
+/// # Arguments
+/// * `mode` - Requested disk access mode.
+///
+/// # Errors
+/// Returns an error if the backend was not compiled in.
+pub fn disk_io(mode: DiskIoMode) -> Result<DiskIo> {
+    match mode {
+        DiskIoMode::Mmap => Ok(DiskIo::Mmap),
+        #[cfg(feature = "io-uring")]
+        DiskIoMode::IoUring => Ok(DiskIo::IoUring),
+        #[cfg(not(feature = "io-uring"))]
+        DiskIoMode::IoUring => Err(anyhow::anyhow!(
+            "io-uring disk access requires building raid-cli with --features io-uring"
+        )),
+        #[cfg(target_os = "linux")]
+        DiskIoMode::Direct => Ok(DiskIo::Direct),
+        #[cfg(not(target_os = "linux"))]
+        DiskIoMode::Direct => Err(anyhow::anyhow!(
+            "direct disk access is only supported on Linux"
+        )),
+    }
+}
+
+/// `open_volume` opens (or creates) the disk images of an array as a type-erased volume.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Number of member disks.
+/// * `disk_size` - Size of each disk image in bytes.
+///
+/// # Errors
+/// Returns an error if the geometry is unsupported, the disk directory cannot be created,
+/// or the member superblocks do not match the array.
+pub fn open_volume(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+) -> Result<Box<dyn DynVolume>> {
+    open_volume_with(mode, disk_dir, disks, disk_size, DiskIo::Mmap)
+}
+
+/// `open_volume_with` opens (or creates) an array whose disks use the given backend.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Number of member disks.
+/// * `disk_size` - Size of each disk image in bytes.
+/// * `io` - Access method for the disk images.
+///
+/// # Errors
+/// Returns an error if the geometry is unsupported, the disk directory cannot be created,
+/// or the member superblocks do not match the array.
+pub fn open_volume_with(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+    io: DiskIo,
+) -> Result<Box<dyn DynVolume>> {
+    open_dispatch(mode, disk_dir, disks, disk_size, io, Superblocks::Stamp)
+}
+
+/// `open_reshaped_volume` opens an array whose data a grow, shrink or migration
+/// is moving into the given geometry.
+///
+/// Unlike [`open_volume`] it accepts superblocks describing another geometry and
+/// rewrites them to describe this one.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Number of member disks.
+/// * `disk_size` - Size of each disk image in bytes.
+///
+/// # Errors
+/// Returns an error if the geometry is unsupported, the disk directory cannot be created,
+/// or the member superblocks belong to another array.
+pub fn open_reshaped_volume(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+) -> Result<Box<dyn DynVolume>> {
+    open_dispatch(
+        mode,
+        disk_dir,
+        disks,
+        disk_size,
+        DiskIo::Mmap,
+        Superblocks::Restamp,
+    )
+}
+
+/// `open_balance_source` opens the geometry an array had before a background grow.
+///
+/// The member superblocks already describe the grown array, so they are neither
+/// checked nor rewritten; the volume only serves the data a balance has not moved yet.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Member count before the grow.
+/// * `disk_size` - Size of each disk image in bytes.
+/// * `io` - Access method for the disk images.
+///
+/// # Errors
+/// Returns an error if the geometry is unsupported or the disk images cannot be opened.
+pub fn open_balance_source(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+    io: DiskIo,
+) -> Result<Box<dyn DynVolume>> {
+    open_dispatch(mode, disk_dir, disks, disk_size, io, Superblocks::Keep)
+}
+
+/// `open_reshape_target` opens the grown geometry an online grow moves data onto.
+///
+/// Missing images of the appended members are created. The superblocks still
+/// describe the mounted array, so they are rewritten only once the reshape finishes.
+///
+/// # Arguments
+/// * `mode` - RAID mode of the array.
+/// * `disk_dir` - Directory containing disk images.
+/// * `disks` - Member count after the grow.
+/// * `disk_size` - Size of each disk image in bytes.
+/// * `io` - Access method for the disk images.
+///
+/// # Errors
+/// Returns an error if the geometry is unsupported or the disk images cannot be opened.
+pub fn open_reshape_target(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+    io: DiskIo,
+) -> Result<Box<dyn DynVolume>> {
+    open_dispatch(mode, disk_dir, disks, disk_size, io, Superblocks::Keep)
+}
+
+/// `Superblocks` is what opening an array does with the member superblocks.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Superblocks {
+    /// Leave them as they are.
+    Keep,
+    /// Check them against the geometry and record the members that take part.
+    Stamp,
+    /// Record a geometry the data has just been moved into.
+    Restamp,
+}
+
+fn open_dispatch(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disks: usize,
+    disk_size: u64,
+    io: DiskIo,
+    superblocks: Superblocks,
+) -> Result<Box<dyn DynVolume>> {
+    validate_geometry(mode, disks)?;
+    let disk_size = disk_size.max(1);
+
+    match disks {
+        1 => open_with::<1>(mode, disk_dir, disk_size, io, superblocks),
+        2 => open_with::<2>(mode, disk_dir, disk_size, io, superblocks),
+        3 => open_with::<3>(mode, disk_dir, disk_size, io, superblocks),
+        4 => open_with::<4>(mode, disk_dir, disk_size, io, superblocks),
+        5 => open_with::<5>(mode, disk_dir, disk_size, io, superblocks),
+        6 => open_with::<6>(mode, disk_dir, disk_size, io, superblocks),
+        7 => open_with::<7>(mode, disk_dir, disk_size, io, superblocks),
+        8 => open_with::<8>(mode, disk_dir, disk_size, io, superblocks),
+        _ => Err(anyhow::anyhow!(
+            "unsupported disk count {disks}; supported range is 1-8"
+        )),
+    }
+}
+
+fn open_with<const D: usize>(
+    mode: RaidMode,
+    disk_dir: &Path,
+    disk_size: u64,
+    io: DiskIo,
+    superblocks: Superblocks,
+) -> Result<Box<dyn DynVolume>> {
+    let builder = SimulatorBuilder::<D, DEFAULT_CHUNK_SIZE>::new(disk_dir)
+        .disk_size(disk_size)
+        .disk_io(io);
+    let build = |builder: SimulatorBuilder<D, DEFAULT_CHUNK_SIZE>| -> Result<Box<dyn DynVolume>> {
+        Ok(match mode {
+            RaidMode::Raid0 => Box::new(builder.build(RAID0::zero())?),
+            RaidMode::Raid1 => Box::new(builder.build(RAID1::zero())?),
+            RaidMode::Raid3 => Box::new(builder.build(RAID3::zero())?),
+            RaidMode::Declustered => Box::new(builder.build(DeclusteredLayout::zero())?),
+        })
+    };
+    let stamp = match superblocks {
+        Superblocks::Keep => return build(builder),
+        Superblocks::Stamp => superblock::stamp(mode, disk_dir, D, disk_size)?,
+        Superblocks::Restamp => superblock::restamp(mode, disk_dir, D, disk_size)?,
+    };
+    let mut volume = build(builder)?;
+    stamp.write_pending(disk_dir)?;
+    for &i in &stamp.stale {
+        volume.mark_stale(i)?;
+    }
+    for &i in &stamp.rejoined {
+        volume.mark_rejoined(i)?;
+    }
+    Ok(volume)
+}
+
+/// `used_extent` returns the logical end of the data written by the filesystem.
+///
+/// # Arguments
+/// * `volume` - Volume to inspect.
+///
+/// # Returns
+/// `Some(end)` when the volume carries a valid filesystem header, otherwise `None`.
+/// A quota table reaches to the end of the volume.
+pub fn used_extent(volume: &mut dyn DynVolume) -> Option<u64> {
+    let mut header_buf = [0u8; HEADER_SIZE];
+    volume.read_bytes(0, &mut header_buf);
+    let header = HeaderFs::parse_header(&header_buf)?;
+    if header.quotas {
+        return Some(volume.logical_capacity_bytes());
+    }
+    Some(header.next_free.max(HeaderFs::data_start()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::test_utils::temp_dir;
+    use raid_rs::retention::array::MemberState;
+
+    #[test]
+    fn validate_geometry_matches_fuse_rules() {
+        assert!(validate_geometry(RaidMode::Raid0, 1).is_ok());
+        assert!(validate_geometry(RaidMode::Raid1, 1).is_err());
+        assert!(validate_geometry(RaidMode::Raid3, 3).is_ok());
+        assert!(validate_geometry(RaidMode::Raid3, 9).is_err());
+        assert!(validate_geometry(RaidMode::Raid0, 0).is_err());
+        assert!(validate_geometry(RaidMode::Declustered, 2).is_err());
+        assert!(validate_geometry(RaidMode::Declustered, 3).is_ok());
+    }
+
+    #[test]
+    fn logical_capacity_follows_data_disks() {
+        assert_eq!(logical_capacity(RaidMode::Raid0, 3, 100), 300);
+        assert_eq!(logical_capacity(RaidMode::Raid1, 3, 100), 100);
+        assert_eq!(logical_capacity(RaidMode::Raid3, 3, 100), 200);
+        assert_eq!(logical_capacity(RaidMode::Declustered, 7, 100), 500);
+    }
+
+    #[test]
+    fn open_volume_reports_expected_capacity() {
+        let dir = temp_dir("raid-cli-open-volume");
+        let volume = open_volume(RaidMode::Raid3, &dir, 4, 256).expect("open volume");
+        assert_eq!(
+            volume.logical_capacity_bytes(),
+            logical_capacity(RaidMode::Raid3, 4, 256)
+        );
+    }
+
+    #[test]
+    fn open_volume_rebuilds_members_without_a_superblock() {
+        let dir = temp_dir("raid-cli-open-stale");
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 256).expect("open volume");
+        volume.clear_needs_rebuild_all();
+        drop(volume);
+        superblock::test_utils::erase(&crate::commands::disk_image_path(&dir, 2));
+
+        let volume = open_volume(RaidMode::Raid3, &dir, 3, 256).expect("reopen");
+
+        let states: Vec<MemberState> = volume.status().members.iter().map(|m| m.state).collect();
+        assert_eq!(
+            states,
+            [MemberState::Ok, MemberState::Ok, MemberState::NeedsRebuild]
+        );
+    }
+
+    #[test]
+    fn open_volume_resyncs_a_returning_member_from_the_bitmap() {
+        let dir = temp_dir("raid-cli-open-rejoined");
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 4096).expect("open volume");
+        volume.clear_needs_rebuild_all();
+        volume.write_bytes(0, b"before");
+        volume.fail_disk(1).expect("fail disk");
+        superblock::advance_generation(&volume.status().members).expect("advance");
+        volume.write_bytes(4000, b"while away");
+        drop(volume);
+        let failed = std::fs::read_dir(&dir)
+            .expect("read dir")
+            .filter_map(Result::ok)
+            .find(|e| e.file_name().to_string_lossy().contains(".failed."))
+            .expect("failed image")
+            .path();
+        std::fs::rename(failed, crate::commands::disk_image_path(&dir, 1)).expect("restore");
+
+        let mut volume = open_volume(RaidMode::Raid3, &dir, 3, 4096).expect("reopen");
+
+        let capacity = volume.logical_capacity_bytes();
+        let resync = volume.stripes_to_repair(capacity).len() as u64;
+        assert!(resync > 0 && resync < volume.stripes_needed_for_logical_end(capacity));
+        volume.rebuild_disk_upto(1, capacity).expect("rebuild");
+        volume.fail_disk(0).expect("fail disk");
+        let mut out = [0u8; 10];
+        volume.read_bytes(4000, &mut out);
+        assert_eq!(&out, b"while away");
+    }
+
+    #[test]
+    fn used_extent_is_none_without_header() {
+        let dir = temp_dir("raid-cli-used-extent");
+        let mut volume = open_volume(RaidMode::Raid0, &dir, 2, 256).expect("open volume");
+        assert!(used_extent(volume.as_mut()).is_none());
+    }
+}
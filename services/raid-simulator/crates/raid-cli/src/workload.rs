@@ -0,0 +1,284 @@
+//! Workload profiles shaping the operations of the synthetic metrics generator.
+//!
+//! `--workload` picks a named profile. A TOML file passed with `--workload-file`
+//! overrides any of its fields, and the `--read-ratio`, `--burstiness`,
+//! `--error-probability` and `--zipf-skew` flags override both:
+//!
+//! ```toml
+//! read_ratio = 0.8
+//! burstiness = 0.5
+//! error_probability = 0.0001
+//! intensity = 2.0
+//! zipf_skew = 1.1
+//!
+//! [[block_size]]
+//! bytes = 4096
+//! weight = 3
+//!
+//! [[block_size]]
+//! bytes = 65536
+//! weight = 1
+//! ```
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::cli::{MetricsArgs, WorkloadProfile};
+
+/// `BlockSize` is one entry of a block-size distribution.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlockSize {
+    pub bytes: u64,
+    /// Relative frequency of this size.
+    pub weight: f64,
+}
+
+/// `Workload` describes the operation mix produced by the generator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workload {
+    /// Fraction of read operations.
+    pub read_ratio: f64,
+    pub block_sizes: Vec<BlockSize>,
+    /// `0` spreads operations evenly over ticks; values towards `1` deliver the
+    /// same average load in rarer, larger bursts.
+    pub burstiness: f64,
+    /// Probability that an operation fails.
+    pub error_probability: f64,
+    /// Multiplier applied to `--ops-per-tick`.
+    pub intensity: f64,
+    /// Zipf exponent of RAID operation offsets; `0` spreads them evenly.
+    pub zipf_skew: f64,
+}
+
+/// `WorkloadFile` holds the profile fields a workload file may override.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkloadFile {
+    read_ratio: Option<f64>,
+    #[serde(rename = "block_size")]
+    block_sizes: Option<Vec<BlockSize>>,
+    burstiness: Option<f64>,
+    error_probability: Option<f64>,
+    intensity: Option<f64>,
+    zipf_skew: Option<f64>,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self::profile(WorkloadProfile::MixedVm)
+    }
+}
+
+impl Workload {
+    #[must_use]
+    /// `profile` returns the settings of a named profile.
+    pub fn profile(profile: WorkloadProfile) -> Self {
+        let sizes = |sizes: &[(u64, f64)]| {
+            sizes
+                .iter()
+                .map(|&(bytes, weight)| BlockSize { bytes, weight })
+                .collect()
+        };
+        match profile {
+            WorkloadProfile::Oltp => Self {
+                read_ratio: 0.7,
+                block_sizes: sizes(&[(4096, 6.0), (8192, 3.0), (16384, 1.0)]),
+                burstiness: 0.2,
+                error_probability: 0.001,
+                intensity: 1.0,
+                zipf_skew: 1.1,
+            },
+            WorkloadProfile::SequentialBackup => Self {
+                read_ratio: 0.1,
+                block_sizes: sizes(&[(262_144, 3.0), (1_048_576, 7.0)]),
+                burstiness: 0.0,
+                error_probability: 0.0005,
+                intensity: 0.5,
+                zipf_skew: 0.0,
+            },
+            WorkloadProfile::MixedVm => Self {
+                read_ratio: 0.5,
+                block_sizes: sizes(&[
+                    (4096, 1.0),
+                    (8192, 1.0),
+                    (16384, 1.0),
+                    (32768, 1.0),
+                    (65536, 1.0),
+                    (131_072, 1.0),
+                    (262_144, 1.0),
+                ]),
+                burstiness: 0.0,
+                error_probability: 0.001,
+                intensity: 1.0,
+                zipf_skew: 0.0,
+            },
+            WorkloadProfile::Idle => Self {
+                read_ratio: 0.6,
+                block_sizes: sizes(&[(4096, 7.0), (8192, 3.0)]),
+                burstiness: 0.9,
+                error_probability: 0.0001,
+                intensity: 0.05,
+                zipf_skew: 0.8,
+            },
+        }
+    }
+
+    /// `resolve` builds the workload selected by the metrics arguments.
+    ///
+    /// # Arguments
+    /// * `args` - Metrics arguments naming the profile, file and overrides.
+    ///
+    /// # Errors
+    /// Returns an error if the workload file cannot be read or parsed, or a
+    /// resulting setting is out of range.
+    pub fn resolve(args: &MetricsArgs) -> Result<Self> {
+        let mut workload = Self::profile(args.workload);
+        if let Some(path) = &args.workload_file {
+            workload.apply(load_file(path)?);
+        }
+        workload.apply(WorkloadFile {
+            read_ratio: args.read_ratio,
+            burstiness: args.burstiness,
+            error_probability: args.error_probability,
+            zipf_skew: args.zipf_skew,
+            ..WorkloadFile::default()
+        });
+        workload.validate()?;
+        Ok(workload)
+    }
+
+    fn apply(&mut self, file: WorkloadFile) {
+        if let Some(v) = file.read_ratio {
+            self.read_ratio = v;
+        }
+        if let Some(v) = file.block_sizes {
+            self.block_sizes = v;
+        }
+        if let Some(v) = file.burstiness {
+            self.burstiness = v;
+        }
+        if let Some(v) = file.error_probability {
+            self.error_probability = v;
+        }
+        if let Some(v) = file.intensity {
+            self.intensity = v;
+        }
+        if let Some(v) = file.zipf_skew {
+            self.zipf_skew = v;
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let probability = |name: &str, v: f64| {
+            if (0.0..=1.0).contains(&v) {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!("{name} must be between 0 and 1, got {v}"))
+            }
+        };
+        probability("read_ratio", self.read_ratio)?;
+        probability("error_probability", self.error_probability)?;
+        if !(0.0..1.0).contains(&self.burstiness) {
+            anyhow::bail!(
+                "burstiness must be at least 0 and below 1, got {}",
+                self.burstiness
+            );
+        }
+        if !self.intensity.is_finite() || self.intensity < 0.0 {
+            anyhow::bail!("intensity must be non-negative, got {}", self.intensity);
+        }
+        if !self.zipf_skew.is_finite() || self.zipf_skew < 0.0 {
+            anyhow::bail!("zipf_skew must be non-negative, got {}", self.zipf_skew);
+        }
+        if self.block_sizes.is_empty() {
+            anyhow::bail!("block_size needs at least one entry");
+        }
+        if let Some(size) = self
+            .block_sizes
+            .iter()
+            .find(|s| s.bytes == 0 || !s.weight.is_finite() || s.weight <= 0.0)
+        {
+            anyhow::bail!(
+                "block_size entries need positive bytes and weight, got {} bytes with weight {}",
+                size.bytes,
+                size.weight
+            );
+        }
+        Ok(())
+    }
+}
+
+fn load_file(path: &Path) -> Result<WorkloadFile> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read workload file {}", path.display()))?;
+    toml::from_str(&raw).with_context(|| format!("invalid workload file {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use crate::fs::test_utils::temp_dir;
+    use clap::Parser;
+
+    fn metrics_args(extra: &[&str]) -> MetricsArgs {
+        let cmdline = ["raid-cli", "metrics"].iter().chain(extra);
+        let Command::Metrics(args) = Cli::parse_from(cmdline).command else {
+            panic!("expected metrics command");
+        };
+        args.metrics
+    }
+
+    #[test]
+    fn every_profile_is_valid() {
+        for profile in [
+            WorkloadProfile::Oltp,
+            WorkloadProfile::SequentialBackup,
+            WorkloadProfile::MixedVm,
+            WorkloadProfile::Idle,
+        ] {
+            Workload::profile(profile)
+                .validate()
+                .expect("valid profile");
+        }
+    }
+
+    #[test]
+    fn file_and_flags_override_the_profile() {
+        let path = temp_dir("raid-cli-workload").join("workload.toml");
+        std::fs::write(
+            &path,
+            "read_ratio = 0.9\nintensity = 2.0\n\n[[block_size]]\nbytes = 512\nweight = 1\n",
+        )
+        .expect("write workload");
+        let file = path.display().to_string();
+
+        let workload = Workload::resolve(&metrics_args(&[
+            "--workload",
+            "oltp",
+            "--workload-file",
+            &file,
+            "--read-ratio",
+            "0.25",
+            "--zipf-skew",
+            "0.5",
+        ]))
+        .expect("resolve");
+
+        assert!((workload.read_ratio - 0.25).abs() < f64::EPSILON);
+        assert!((workload.intensity - 2.0).abs() < f64::EPSILON);
+        assert_eq!(workload.block_sizes.len(), 1);
+        assert_eq!(workload.block_sizes[0].bytes, 512);
+        assert!((workload.burstiness - 0.2).abs() < f64::EPSILON);
+        assert!((workload.zipf_skew - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        assert!(Workload::resolve(&metrics_args(&["--burstiness", "1"])).is_err());
+        assert!(Workload::resolve(&metrics_args(&["--error-probability", "1.5"])).is_err());
+    }
+}
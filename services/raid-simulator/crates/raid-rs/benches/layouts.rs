@@ -0,0 +1,64 @@
+//! Stripe encode, decode and reconstruction throughput of the built-in layouts.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use raid_rs::layout::bits::Bits;
+use raid_rs::layout::stripe::raid0::RAID0;
+use raid_rs::layout::stripe::raid1::RAID1;
+use raid_rs::layout::stripe::raid3::RAID3;
+use raid_rs::layout::stripe::traits::restore::Restore;
+use raid_rs::layout::stripe::traits::stripe::Stripe;
+
+/// `DISKS` is the member count of every benchmarked layout.
+const DISKS: usize = 4;
+
+fn chunks<const N: usize>(count: usize) -> Vec<Bits<N>> {
+    (0..count)
+        .map(|i| Bits(std::array::from_fn(|j| (i * 31 + j).to_le_bytes()[0])))
+        .collect()
+}
+
+fn bench_codec<const N: usize, T: Stripe<DISKS, N>>(c: &mut Criterion, name: &str, mut layout: T) {
+    let data = chunks::<N>(T::DATA);
+    let mut out = vec![Bits::<N>::zero(); T::DATA];
+    let mut group = c.benchmark_group(format!("{name}/{N}B"));
+    group.throughput(Throughput::Bytes((T::DATA * N) as u64));
+    group.bench_function("encode", |b| b.iter(|| layout.write(black_box(&data))));
+    layout.write(&data);
+    group.bench_function("decode", |b| {
+        b.iter(|| {
+            layout.read(black_box(&mut out));
+        });
+    });
+    group.finish();
+}
+
+fn bench_restore<const N: usize, T>(c: &mut Criterion, name: &str, mut layout: T)
+where
+    T: Stripe<DISKS, N> + Restore,
+{
+    layout.write(&chunks::<N>(T::DATA));
+    let mut group = c.benchmark_group(format!("{name}/{N}B"));
+    group.throughput(Throughput::Bytes(N as u64));
+    for member in [0, DISKS - 1] {
+        group.bench_with_input(BenchmarkId::new("restore", member), &member, |b, &i| {
+            b.iter(|| layout.restore(black_box(i)));
+        });
+    }
+    group.finish();
+}
+
+fn layouts(c: &mut Criterion) {
+    bench_codec(c, "raid0", RAID0::<DISKS, 4>::zero());
+    bench_codec(c, "raid0", RAID0::<DISKS, 4096>::zero());
+    bench_codec(c, "raid1", RAID1::<DISKS, 4>::zero());
+    bench_codec(c, "raid1", RAID1::<DISKS, 4096>::zero());
+    bench_codec(c, "raid3", RAID3::<DISKS, 4>::zero());
+    bench_codec(c, "raid3", RAID3::<DISKS, 4096>::zero());
+    bench_restore(c, "raid1", RAID1::<DISKS, 4096>::zero());
+    bench_restore(c, "raid3", RAID3::<DISKS, 4096>::zero());
+}
+
+criterion_group!(benches, layouts);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+//! `Volume` byte I/O throughput across block sizes, healthy and degraded, and
+//! stripe sweeps through byte I/O against the batch API.
+
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use raid_rs::layout::stripe::raid3::RAID3;
+use raid_rs::retention::array::Array;
+use raid_rs::retention::volume::{BATCH_STRIPES, Volume};
+use tempfile::TempDir;
+
+const DISKS: usize = 4;
+/// `CHUNK` matches the chunk size the CLI builds volumes with.
+const CHUNK: usize = 4;
+const DISK_LEN: u64 = 1 << 20;
+const BLOCK_SIZES: [usize; 3] = [512, 4096, 65536];
+
+type BenchVolume = Volume<DISKS, CHUNK, RAID3<DISKS, CHUNK>>;
+
+fn volume(dir: &TempDir) -> BenchVolume {
+    let paths: [String; DISKS] = std::array::from_fn(|i| {
+        dir.path()
+            .join(format!("disk-{i}.img"))
+            .to_string_lossy()
+            .into_owned()
+    });
+    let mut volume = Volume::new(
+        Array::init_array(&paths, DISK_LEN).expect("init array"),
+        RAID3::zero(),
+    );
+    volume.clear_needs_rebuild_all();
+    volume
+}
+
+fn write_bytes(c: &mut Criterion) {
+    let dir = TempDir::new().expect("temp dir");
+    let mut volume = volume(&dir);
+    let mut group = c.benchmark_group("volume/write_bytes");
+    for block in BLOCK_SIZES {
+        let payload = vec![0x5a; block];
+        group.throughput(Throughput::Bytes(block as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(block),
+            &payload,
+            |b, payload| {
+                b.iter(|| volume.write_bytes(black_box(0), black_box(payload)));
+            },
+        );
+    }
+    group.finish();
+}
+
+fn read_bytes(c: &mut Criterion) {
+    let dir = TempDir::new().expect("temp dir");
+    let mut volume = volume(&dir);
+    volume.write_bytes(0, &vec![0x5a; BLOCK_SIZES[BLOCK_SIZES.len() - 1]]);
+    let mut group = c.benchmark_group("volume/read_bytes");
+    for state in ["healthy", "degraded"] {
+        if state == "degraded" {
+            volume.fail_disk(1).expect("fail disk");
+        }
+        for block in BLOCK_SIZES {
+            let mut out = vec![0; block];
+            group.throughput(Throughput::Bytes(block as u64));
+            group.bench_function(BenchmarkId::new(state, block), |b| {
+                b.iter(|| volume.read_bytes(black_box(0), black_box(&mut out)));
+            });
+        }
+    }
+    group.finish();
+}
+
+fn stripe_sweep(c: &mut Criterion) {
+    let dir = TempDir::new().expect("temp dir");
+    let mut volume = volume(&dir);
+    let stripe_bytes = volume.geometry().bytes_per_stripe;
+    let stripes = BATCH_STRIPES as u64;
+    let payload = vec![0x5a; BATCH_STRIPES * stripe_bytes];
+    let mut out = vec![0; payload.len()];
+    let mut group = c.benchmark_group("volume/stripe_sweep");
+    group.throughput(Throughput::Bytes(payload.len() as u64));
+    group.bench_function("write_bytes", |b| {
+        b.iter(|| volume.write_bytes(black_box(0), black_box(&payload)));
+    });
+    group.bench_function("write_stripes", |b| {
+        b.iter(|| volume.write_stripes(black_box(0..stripes), black_box(&payload)));
+    });
+    group.bench_function("read_bytes", |b| {
+        b.iter(|| volume.read_bytes(black_box(0), black_box(&mut out)));
+    });
+    group.bench_function("read_stripes", |b| {
+        b.iter(|| volume.read_stripes(black_box(0..stripes)));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, write_bytes, read_bytes, stripe_sweep);
+criterion_main!(benches);
@@ -0,0 +1,82 @@
+//! Error type returned by every fallible operation of the library.
+
+use std::fmt;
+use std::io;
+use std::num::TryFromIntError;
+use std::path::{Path, PathBuf};
+
+use crate::retention::IoError;
+
+/// `Result` is the result of a fallible library operation.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// `Error` classifies why an operation failed, so callers can match on the cause.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A disk image could not be created, opened, mapped or synced.
+    Io { path: PathBuf, source: io::Error },
+    /// A disk index, size or range does not fit the array.
+    Geometry(String),
+    /// The members left cannot serve the request, or the volume refuses writes.
+    Degraded(IoError),
+    /// Metadata stored on the members is inconsistent.
+    Corrupt(String),
+    /// A thin pool, snapshot reserve or table has no room left.
+    OutOfSpace(String),
+    /// The request does not apply to the current state of the volume, such as a
+    /// feature that is already enabled or a snapshot that does not exist.
+    Invalid(String),
+}
+
+impl Error {
+    /// `io` returns a conversion of IO errors on the image at `path`, for `map_err`.
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl From<IoError> for Error {
+    /// Requests past the end of a disk or volume are geometry errors; every other
+    /// `IoError` means the volume is degraded.
+    fn from(err: IoError) -> Self {
+        match err {
+            IoError::OutOfRange { .. } => Self::Geometry(err.to_string()),
+            _ => Self::Degraded(err),
+        }
+    }
+}
+
+impl From<TryFromIntError> for Error {
+    /// Sizes and indices that overflow the integer types of the platform do not fit
+    /// the array.
+    fn from(err: TryFromIntError) -> Self {
+        Self::Geometry(err.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Degraded(err) => err.fmt(f),
+            Self::Geometry(msg)
+            | Self::Corrupt(msg)
+            | Self::OutOfSpace(msg)
+            | Self::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Degraded(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+
+/// `slow_mul` multiplies by shift-and-add with reduction, independent of the tables.
+fn slow_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= (POLY & 0xff) as u8;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+#[test]
+fn mul_matches_shift_and_add_for_every_pair() {
+    for a in 0..=255u8 {
+        for b in 0..=255u8 {
+            assert_eq!(mul(a, b), slow_mul(a, b), "{a} * {b}");
+        }
+    }
+}
+
+#[test]
+fn div_undoes_mul_for_every_pair() {
+    for a in 0..=255u8 {
+        assert_eq!(div(a, 0), None);
+        for b in 1..=255u8 {
+            assert_eq!(div(mul(a, b), b), Some(a), "{a} * {b} / {b}");
+        }
+    }
+}
+
+#[test]
+fn every_non_zero_byte_has_an_inverse() {
+    assert_eq!(inv(0), None);
+    for a in 1..=255u8 {
+        let inverse = inv(a).expect("inverse");
+        assert_eq!(mul(a, inverse), 1, "{a} * {inverse}");
+    }
+}
+
+#[test]
+fn generator_powers_cover_the_field() {
+    let mut seen = [false; 256];
+    for n in 0..255 {
+        let value = exp(n);
+        assert!(!seen[usize::from(value)], "g^{n} repeats");
+        seen[usize::from(value)] = true;
+        assert_eq!(log(value), Some(u8::try_from(n).expect("log fits")));
+        assert_eq!(pow(GENERATOR, n), value);
+    }
+    assert!(!seen[0]);
+    assert_eq!(exp(255), 1);
+    assert_eq!(log(0), None);
+}
+
+#[test]
+fn pow_repeats_multiplication() {
+    for a in 0..=255u8 {
+        let mut expected = 1u8;
+        for n in 0..300 {
+            assert_eq!(pow(a, n), expected, "{a}^{n}");
+            expected = mul(expected, a);
+        }
+    }
+}
+
+#[test]
+fn slice_helpers_match_scalar_mul() {
+    let src: Vec<u8> = (0..=255u8).chain(0..37).collect();
+    for c in 0..=255u8 {
+        for len in [0, 1, 15, 16, 17, 33, src.len()] {
+            let src = &src[..len];
+            let mut dst = vec![0xA5u8; len + 3];
+            mul_slice(c, src, &mut dst);
+            for (i, &s) in src.iter().enumerate() {
+                assert_eq!(dst[i], mul(c, s), "c={c} len={len} i={i}");
+            }
+            assert_eq!(&dst[len..], &[0xA5; 3]);
+
+            let seed: Vec<u8> = src.iter().rev().copied().collect();
+            let mut acc = seed.clone();
+            mul_slice_xor(c, src, &mut acc);
+            for (i, &s) in src.iter().enumerate() {
+                assert_eq!(acc[i], seed[i] ^ mul(c, s), "c={c} len={len} i={i}");
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "range end index")]
+fn slice_helpers_reject_short_destinations() {
+    let mut dst = [0u8; 2];
+    mul_slice(3, &[1, 2, 3], &mut dst);
+}
@@ -0,0 +1,247 @@
+//! Arithmetic in GF(2^8), the byte field used by Reed-Solomon style parity.
+//!
+//! The field is built over the polynomial `x^8 + x^4 + x^3 + x^2 + 1` (`0x11d`) with
+//! generator `2`, the same choice as Linux md RAID6, so a Q syndrome computed here
+//! matches the usual `Q = sum(g^i * D_i)`. Addition and subtraction are XOR.
+//! Multiplication and division go through log/exp tables built at compile time; the
+//! slice helpers use PSHUFB nibble lookups when the CPU supports SSSE3.
+
+#[cfg(test)]
+mod gf256_tests;
+
+/// `POLY` is the field polynomial, including the `x^8` term.
+pub const POLY: u16 = 0x11d;
+
+/// `GENERATOR` is the field element whose powers enumerate every non-zero byte.
+pub const GENERATOR: u8 = 2;
+
+/// `EXP` maps `i` to `g^i`; it is doubled so `EXP[log a + log b]` needs no reduction.
+const EXP: [u8; 512] = build_exp();
+
+/// `LOG` maps a non-zero byte to its discrete logarithm; `LOG[0]` is unused.
+const LOG: [u8; 256] = build_log();
+
+#[allow(clippy::cast_possible_truncation)]
+const fn build_exp() -> [u8; 512] {
+    let mut exp = [0u8; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        exp[i + 255] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= POLY;
+        }
+        i += 1;
+    }
+    exp[510] = exp[0];
+    exp[511] = exp[1];
+    exp
+}
+
+#[allow(clippy::cast_possible_truncation)]
+const fn build_log() -> [u8; 256] {
+    let exp = build_exp();
+    let mut log = [0u8; 256];
+    let mut i = 0;
+    while i < 255 {
+        log[exp[i] as usize] = i as u8;
+        i += 1;
+    }
+    log
+}
+
+#[inline]
+#[must_use]
+/// `add` returns `a + b`, which is also `a - b` in GF(2^8).
+pub const fn add(a: u8, b: u8) -> u8 {
+    a ^ b
+}
+
+#[inline]
+#[must_use]
+/// `mul` returns the field product `a * b`.
+pub const fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    EXP[LOG[a as usize] as usize + LOG[b as usize] as usize]
+}
+
+#[inline]
+#[must_use]
+/// `div` returns `a / b`, or `None` when `b` is zero.
+pub const fn div(a: u8, b: u8) -> Option<u8> {
+    if b == 0 {
+        return None;
+    }
+    if a == 0 {
+        return Some(0);
+    }
+    Some(EXP[LOG[a as usize] as usize + 255 - LOG[b as usize] as usize])
+}
+
+#[inline]
+#[must_use]
+/// `inv` returns the multiplicative inverse of `a`, or `None` for zero.
+pub const fn inv(a: u8) -> Option<u8> {
+    div(1, a)
+}
+
+#[inline]
+#[must_use]
+/// `exp` returns `g^n` for the field generator `g`.
+pub const fn exp(n: usize) -> u8 {
+    EXP[n % 255]
+}
+
+#[inline]
+#[must_use]
+/// `log` returns the discrete logarithm of `a` to the generator, or `None` for zero.
+pub const fn log(a: u8) -> Option<u8> {
+    if a == 0 { None } else { Some(LOG[a as usize]) }
+}
+
+#[inline]
+#[must_use]
+/// `pow` returns `a^n`, with `0^0 = 1`.
+pub const fn pow(a: u8, n: usize) -> u8 {
+    if n == 0 {
+        return 1;
+    }
+    if a == 0 {
+        return 0;
+    }
+    EXP[(LOG[a as usize] as usize * (n % 255)) % 255]
+}
+
+/// `mul_slice` writes `c * src[i]` into `dst[i]`.
+///
+/// # Arguments
+/// * `c` - Constant factor.
+/// * `src` - Input bytes.
+/// * `dst` - Output bytes; only the first `src.len()` are written.
+///
+/// # Panics
+/// Panics if `dst` is shorter than `src`.
+pub fn mul_slice(c: u8, src: &[u8], dst: &mut [u8]) {
+    let dst = &mut dst[..src.len()];
+    match c {
+        0 => dst.fill(0),
+        1 => dst.copy_from_slice(src),
+        _ => {
+            let done = simd::mul_slice(c, src, dst, false);
+            for (d, &s) in dst[done..].iter_mut().zip(&src[done..]) {
+                *d = mul(c, s);
+            }
+        }
+    }
+}
+
+/// `mul_slice_xor` adds `c * src[i]` into `dst[i]`, the step of a syndrome sum.
+///
+/// # Arguments
+/// * `c` - Constant factor.
+/// * `src` - Input bytes.
+/// * `dst` - Accumulator; only the first `src.len()` are updated.
+///
+/// # Panics
+/// Panics if `dst` is shorter than `src`.
+pub fn mul_slice_xor(c: u8, src: &[u8], dst: &mut [u8]) {
+    let dst = &mut dst[..src.len()];
+    match c {
+        0 => {}
+        1 => {
+            for (d, &s) in dst.iter_mut().zip(src) {
+                *d ^= s;
+            }
+        }
+        _ => {
+            let done = simd::mul_slice(c, src, dst, true);
+            for (d, &s) in dst[done..].iter_mut().zip(&src[done..]) {
+                *d ^= mul(c, s);
+            }
+        }
+    }
+}
+
+#[must_use]
+/// `accelerated` reports whether the slice helpers use PSHUFB on this CPU.
+pub fn accelerated() -> bool {
+    simd::available()
+}
+
+#[cfg(target_arch = "x86_64")]
+mod simd {
+    use std::arch::x86_64::{
+        __m128i, _mm_and_si128, _mm_loadu_si128, _mm_set1_epi8, _mm_shuffle_epi8, _mm_srli_epi64,
+        _mm_storeu_si128, _mm_xor_si128,
+    };
+
+    use super::mul;
+
+    pub fn available() -> bool {
+        std::is_x86_feature_detected!("ssse3")
+    }
+
+    /// `mul_slice` handles the whole 16-byte blocks of `src` and returns how many
+    /// bytes it processed, or zero when SSSE3 is unavailable.
+    pub fn mul_slice(c: u8, src: &[u8], dst: &mut [u8], xor: bool) -> usize {
+        if !available() {
+            return 0;
+        }
+        // SAFETY: SSSE3 support was checked above.
+        unsafe { mul_blocks(c, src, dst, xor) }
+    }
+
+    /// `nibble_tables` returns the products of `c` with every low and high nibble.
+    fn nibble_tables(c: u8) -> ([u8; 16], [u8; 16]) {
+        let mut low = [0u8; 16];
+        let mut high = [0u8; 16];
+        for i in 0..16u8 {
+            low[usize::from(i)] = mul(c, i);
+            high[usize::from(i)] = mul(c, i << 4);
+        }
+        (low, high)
+    }
+
+    // `loadu`/`storeu` accept unaligned pointers, so the casts need no alignment.
+    #[allow(clippy::cast_ptr_alignment)]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn mul_blocks(c: u8, src: &[u8], dst: &mut [u8], xor: bool) -> usize {
+        let (low, high) = nibble_tables(c);
+        let blocks = src.len() / 16;
+        // SAFETY: every load and store stays within the first `blocks * 16` bytes
+        // of `src` and `dst`, and `dst` is at least as long as `src`.
+        unsafe {
+            let low = _mm_loadu_si128(low.as_ptr().cast::<__m128i>());
+            let high = _mm_loadu_si128(high.as_ptr().cast::<__m128i>());
+            let mask = _mm_set1_epi8(0x0f);
+            for block in 0..blocks {
+                let s = _mm_loadu_si128(src.as_ptr().add(block * 16).cast::<__m128i>());
+                let lo = _mm_and_si128(s, mask);
+                let hi = _mm_and_si128(_mm_srli_epi64(s, 4), mask);
+                let mut product =
+                    _mm_xor_si128(_mm_shuffle_epi8(low, lo), _mm_shuffle_epi8(high, hi));
+                let out = dst.as_mut_ptr().add(block * 16).cast::<__m128i>();
+                if xor {
+                    product = _mm_xor_si128(product, _mm_loadu_si128(out));
+                }
+                _mm_storeu_si128(out, product);
+            }
+        }
+        blocks * 16
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+mod simd {
+    pub const fn available() -> bool {
+        false
+    }
+
+    pub const fn mul_slice(_c: u8, _src: &[u8], _dst: &mut [u8], _xor: bool) -> usize {
+        0
+    }
+}
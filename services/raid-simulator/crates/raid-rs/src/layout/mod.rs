@@ -1,4 +1,5 @@
-//! RAID layout building blocks, including bit operations and stripe layouts.
+//! RAID layout building blocks, including bit operations, GF(2^8) math and stripe layouts.
 
 pub mod bits;
+pub mod gf256;
 pub mod stripe;
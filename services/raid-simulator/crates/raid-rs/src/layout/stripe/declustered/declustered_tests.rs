@@ -0,0 +1,125 @@
+use crate::layout::bits::Bits;
+use crate::layout::stripe::declustered::Declustered;
+use crate::layout::stripe::traits::restore::Restore;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::testing;
+
+type Pool = Declustered<7, 4, 3>;
+
+#[test]
+fn layout_conforms_across_placements() {
+    testing::check_layout(&mut Declustered::<4, 4, 2>::zero(), 64);
+    testing::check_layout(&mut Pool::zero(), 64);
+    testing::check_layout(&mut Declustered::<8, 16, 8>::zero(), 16);
+}
+
+#[test]
+fn groups_take_the_leftover_disks() {
+    assert_eq!(Pool::GROUPS, 2);
+    assert_eq!(<Pool as Stripe<7, 4>>::DATA, 5);
+    let roles: Vec<ChunkRole> = (0..7).map(Pool::slot_role).collect();
+    assert_eq!(
+        roles,
+        [
+            ChunkRole::Data(0),
+            ChunkRole::Data(1),
+            ChunkRole::Parity,
+            ChunkRole::Data(2),
+            ChunkRole::Data(3),
+            ChunkRole::Data(4),
+            ChunkRole::Parity,
+        ]
+    );
+}
+
+#[test]
+fn narrow_pool_forms_a_single_group() {
+    type Narrow = Declustered<2, 4, 3>;
+    assert_eq!(Narrow::WIDTH, 2);
+    assert_eq!(Narrow::GROUPS, 1);
+    assert_eq!(<Narrow as Stripe<2, 4>>::DATA, 1);
+    testing::check_layout(&mut Narrow::zero(), 16);
+}
+
+#[test]
+fn placement_is_a_permutation_that_varies_by_stripe() {
+    let mut seen = std::collections::HashSet::new();
+    for stripe in 0..64 {
+        let mut slots = Pool::placement(stripe);
+        assert_eq!(Pool::placement(stripe), slots, "placement is deterministic");
+        seen.insert(slots);
+        slots.sort_unstable();
+        assert_eq!(slots, [0, 1, 2, 3, 4, 5, 6]);
+    }
+    assert!(seen.len() > 32, "only {} distinct placements", seen.len());
+}
+
+#[test]
+fn roles_follow_the_placement() {
+    for stripe in 0..16 {
+        let slots = Pool::placement(stripe);
+        for (slot, &disk) in slots.iter().enumerate() {
+            assert_eq!(Pool::role_at(stripe, disk), Pool::slot_role(slot));
+        }
+    }
+    assert_eq!(Pool::role(3), Pool::role_at(0, 3));
+}
+
+#[test]
+fn restore_reads_only_group_peers() {
+    let mut layout = Pool::zero();
+    layout.place(9);
+    let data: Vec<Bits<4>> = (1..=5u8).map(|i| Bits([i; 4])).collect();
+    layout.write(&data);
+    let mut encoded = [Bits::<4>::zero(); 7];
+    layout.read_raw(&mut encoded);
+
+    for lost in 0..7 {
+        let peers = Pool::peers(9, lost);
+        let mut raw = encoded;
+        for (disk, chunk) in raw.iter_mut().enumerate() {
+            if disk != lost && !peers.contains(&disk) {
+                *chunk = Bits([0xEE; 4]);
+            }
+        }
+        raw[lost] = Bits::zero();
+        layout.write_raw(&raw);
+        layout.restore(lost);
+        let mut out = [Bits::<4>::zero(); 7];
+        layout.read_raw(&mut out);
+        assert_eq!(out[lost], encoded[lost], "disk {lost} from peers {peers:?}");
+    }
+}
+
+#[test]
+fn scrub_rewrites_stale_parity_only() {
+    let mut layout = Pool::zero();
+    layout.place(3);
+    let data: Vec<Bits<4>> = (1..=5u8).map(|i| Bits([i; 4])).collect();
+    layout.write(&data);
+    let parity = (0..7).find(|&d| Pool::role_at(3, d) == ChunkRole::Parity);
+    let parity = parity.expect("parity disk");
+    let mut raw = [Bits::<4>::zero(); 7];
+    layout.read_raw(&mut raw);
+    raw[parity] = Bits([0xFF; 4]);
+    layout.write_raw(&raw);
+
+    assert_eq!(layout.scrub(), vec![parity]);
+    assert!(layout.scrub().is_empty());
+}
+
+#[test]
+fn rebuild_peers_spread_over_the_pool() {
+    let mut reads = [0u64; 7];
+    for stripe in 0..700 {
+        let peers = Pool::peers(stripe, 0);
+        assert!((2..=3).contains(&peers.len()));
+        for peer in peers {
+            reads[peer] += 1;
+        }
+    }
+    assert_eq!(reads[0], 0);
+    for (disk, &n) in reads.iter().enumerate().skip(1) {
+        assert!(n > 200, "disk {disk} supplied only {n} chunks");
+    }
+}
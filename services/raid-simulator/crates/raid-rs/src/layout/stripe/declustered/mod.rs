@@ -0,0 +1,126 @@
+//! Declustered parity layout that scatters parity groups over a wider disk pool.
+//!
+//! Every stripe splits the `D` disks into parity groups of `W` members (the last
+//! group also takes the `D % W` left over, and a pool narrower than `W` forms one
+//! group of all `D` disks), each holding one XOR parity chunk and data on the rest.
+//! Which disk plays which slot is drawn per stripe with a CRUSH-style straw hash of
+//! the stripe index and disk number, so the members a lost disk is rebuilt from
+//! change from stripe to stripe and a rebuild reads from the whole pool instead of
+//! `W - 1` fixed peers.
+
+use std::ops::Range;
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::ChunkRole;
+
+#[cfg(test)]
+mod declustered_tests;
+mod restore_impl;
+mod stripe_impl;
+
+/// Declustered stores `D / W` parity groups of width `W` per stripe, placed pseudo-randomly.
+///
+/// `W` is capped at `D`, so one group width serves every pool size.
+pub struct Declustered<const D: usize, const N: usize, const W: usize> {
+    /// Chunks in disk order.
+    chunks: [Bits<N>; D],
+    /// Disk holding each logical slot of the placed stripe.
+    slots: [usize; D],
+    /// Stripe whose placement `slots` holds.
+    placed: u64,
+}
+
+impl<const D: usize, const N: usize, const W: usize> Declustered<D, N, W> {
+    const VALID: () = assert!(W >= 2, "group width must be at least 2");
+
+    /// `WIDTH` is the width of every parity group: `W`, or `D` when the pool is narrower.
+    pub const WIDTH: usize = if W < D { W } else { D };
+
+    /// `GROUPS` is the number of parity groups in every stripe.
+    pub const GROUPS: usize = D / Self::WIDTH;
+
+    #[must_use]
+    /// `zero` returns a zero-initialized stripe placed at stripe 0.
+    pub fn zero() -> Self {
+        let () = Self::VALID;
+        Self {
+            chunks: [Bits::<N>::zero(); D],
+            slots: Self::placement(0),
+            placed: 0,
+        }
+    }
+
+    #[must_use]
+    /// `placement` returns the disk holding each logical slot of stripe `stripe_index`.
+    ///
+    /// Every disk draws a straw from a hash of the stripe and its number; the longest
+    /// straw takes slot 0, the next slot 1, and so on.
+    pub fn placement(stripe_index: u64) -> [usize; D] {
+        let mut slots: [usize; D] = std::array::from_fn(|disk| disk);
+        slots.sort_by_key(|&disk| std::cmp::Reverse(straw(stripe_index, disk)));
+        slots
+    }
+
+    #[must_use]
+    /// `slot_role` returns what logical slot `slot` of every stripe stores.
+    pub const fn slot_role(slot: usize) -> ChunkRole {
+        let group = Self::group_of(slot);
+        if slot + 1 == Self::group_slots(group).end {
+            ChunkRole::Parity
+        } else {
+            ChunkRole::Data(slot - group)
+        }
+    }
+
+    /// `group_of` returns the parity group logical slot `slot` belongs to.
+    const fn group_of(slot: usize) -> usize {
+        let group = slot / Self::WIDTH;
+        if group < Self::GROUPS {
+            group
+        } else {
+            Self::GROUPS - 1
+        }
+    }
+
+    /// `group_slots` returns the logical slots of parity group `group`; the last
+    /// group runs to the end of the stripe.
+    const fn group_slots(group: usize) -> Range<usize> {
+        let end = if group + 1 == Self::GROUPS {
+            D
+        } else {
+            (group + 1) * Self::WIDTH
+        };
+        group * Self::WIDTH..end
+    }
+
+    /// `slot_of` returns the logical slot disk `disk` plays in `slots`.
+    fn slot_of(slots: &[usize; D], disk: usize) -> usize {
+        slots.iter().position(|&d| d == disk).unwrap_or(disk)
+    }
+
+    /// `group_parity` returns the XOR of the data chunks of parity group `group`.
+    fn group_parity(&self, group: usize) -> Bits<N> {
+        let slots = Self::group_slots(group);
+        let mut parity = Bits::<N>::zero();
+        for slot in slots.start..slots.end - 1 {
+            parity ^= self.chunks[self.slots[slot]];
+        }
+        parity
+    }
+
+    fn write_parity(&mut self) {
+        for group in 0..Self::GROUPS {
+            let parity = self.group_parity(group);
+            self.chunks[self.slots[Self::group_slots(group).end - 1]] = parity;
+        }
+    }
+}
+
+/// `straw` hashes a stripe and disk into the length of the disk's straw.
+const fn straw(stripe_index: u64, disk: usize) -> u64 {
+    // splitmix64 finalizer over the stripe and disk.
+    let mut x = stripe_index ^ (disk as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,31 @@
+use crate::layout::bits::Bits;
+use crate::layout::stripe::declustered::Declustered;
+use crate::layout::stripe::traits::restore::Restore;
+
+impl<const D: usize, const N: usize, const W: usize> Restore for Declustered<D, N, W> {
+    fn restore(&mut self, i: usize) {
+        let group = Self::group_of(Self::slot_of(&self.slots, i));
+        let mut acc = Bits::<N>::zero();
+        for slot in Self::group_slots(group) {
+            let disk = self.slots[slot];
+            if disk != i {
+                acc ^= self.chunks[disk];
+            }
+        }
+        self.chunks[i] = acc;
+    }
+
+    fn scrub(&mut self) -> Vec<usize> {
+        let mut rewrites = Vec::new();
+        for group in 0..Self::GROUPS {
+            let parity = self.group_parity(group);
+            let disk = self.slots[Self::group_slots(group).end - 1];
+            if self.chunks[disk] != parity {
+                self.chunks[disk] = parity;
+                rewrites.push(disk);
+            }
+        }
+        rewrites.sort_unstable();
+        rewrites
+    }
+}
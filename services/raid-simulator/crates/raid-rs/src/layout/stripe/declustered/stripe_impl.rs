@@ -0,0 +1,91 @@
+use crate::layout::bits::Bits;
+use crate::layout::stripe::declustered::Declustered;
+use crate::layout::stripe::traits::restore::Restore;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+
+impl<const D: usize, const N: usize, const W: usize> Stripe<D, N> for Declustered<D, N, W> {
+    const DATA: usize = D - Self::GROUPS;
+    const DISKS: usize = D;
+    const FAULT_TOLERANCE: usize = 1;
+
+    fn write(&mut self, data: &[Bits<N>]) {
+        assert_eq!(
+            data.len(),
+            Self::DATA,
+            "Declustered expects {} chunks.",
+            Self::DATA
+        );
+        for (slot, &disk) in self.slots.iter().enumerate() {
+            if let ChunkRole::Data(chunk) = Self::slot_role(slot) {
+                self.chunks[disk] = data[chunk];
+            }
+        }
+        self.write_parity();
+    }
+
+    fn write_raw(&mut self, data: &[Bits<N>]) {
+        assert_eq!(
+            data.len(),
+            Self::DISKS,
+            "Declustered expects {} chunks.",
+            Self::DISKS
+        );
+        self.chunks.copy_from_slice(&data[..Self::DISKS]);
+    }
+
+    fn read(&self, out: &mut [Bits<N>]) {
+        assert_eq!(
+            out.len(),
+            Self::DATA,
+            "Output buffer must be {} chunks.",
+            Self::DATA
+        );
+        for (slot, &disk) in self.slots.iter().enumerate() {
+            if let ChunkRole::Data(chunk) = Self::slot_role(slot) {
+                out[chunk] = self.chunks[disk];
+            }
+        }
+    }
+
+    fn read_raw(&self, out: &mut [Bits<N>]) {
+        assert_eq!(
+            out.len(),
+            Self::DISKS,
+            "Output buffer must be {} chunks.",
+            Self::DISKS
+        );
+        out[..Self::DISKS].copy_from_slice(&self.chunks);
+    }
+
+    fn role(disk: usize) -> ChunkRole {
+        Self::role_at(0, disk)
+    }
+
+    fn role_at(stripe_index: u64, disk: usize) -> ChunkRole {
+        Self::slot_role(Self::slot_of(&Self::placement(stripe_index), disk))
+    }
+
+    fn peers(stripe_index: u64, disk: usize) -> Vec<usize> {
+        let slots = Self::placement(stripe_index);
+        let group = Self::group_of(Self::slot_of(&slots, disk));
+        Self::group_slots(group)
+            .map(|slot| slots[slot])
+            .filter(|&d| d != disk)
+            .collect()
+    }
+
+    fn place(&mut self, stripe_index: u64) {
+        if stripe_index != self.placed {
+            self.slots = Self::placement(stripe_index);
+            self.placed = stripe_index;
+        }
+    }
+
+    fn as_restore(&self) -> Option<&dyn Restore> {
+        Some(self)
+    }
+
+    fn as_restore_mut(&mut self) -> Option<&mut dyn Restore> {
+        Some(self)
+    }
+}
@@ -1,5 +1,6 @@
 //! Stripe layout implementations for supported RAID modes.
 
+pub mod declustered;
 pub mod raid0;
 pub mod raid1;
 pub mod raid3;
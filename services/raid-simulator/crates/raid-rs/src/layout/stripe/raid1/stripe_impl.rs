@@ -1,7 +1,7 @@
 use crate::layout::bits::Bits;
 use crate::layout::stripe::raid1::RAID1;
 use crate::layout::stripe::traits::restore::Restore;
-use crate::layout::stripe::traits::stripe::Stripe;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
 
 impl<const D: usize, const N: usize> Stripe<D, N> for RAID1<D, N> {
     const DATA: usize = 1;
@@ -52,6 +52,10 @@ impl<const D: usize, const N: usize> Stripe<D, N> for RAID1<D, N> {
         out[..Self::DISKS].copy_from_slice(&self.0[..Self::DISKS]);
     }
 
+    fn role(disk: usize) -> ChunkRole {
+        ChunkRole::Mirror(disk)
+    }
+
     fn as_restore(&self) -> Option<&dyn Restore> {
         Some(self)
     }
@@ -1,6 +1,6 @@
 use crate::layout::bits::Bits;
 use crate::layout::stripe::raid1::RAID1;
-use crate::layout::stripe::traits::stripe::Stripe;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
 
 #[test]
 fn stripe_data_const_is_one() {
@@ -86,3 +86,9 @@ fn stripe_as_restore_returns_some() {
     let r = RAID1::<2, 4>([Bits::zero(); 2]);
     assert!(r.as_restore().is_some());
 }
+
+#[test]
+fn stripe_roles_are_mirrors() {
+    assert_eq!(<RAID1<3, 4> as Stripe<3, 4>>::role(0), ChunkRole::Mirror(0));
+    assert_eq!(<RAID1<3, 4> as Stripe<3, 4>>::role(2), ChunkRole::Mirror(2));
+}
@@ -6,6 +6,7 @@ use crate::layout::stripe::traits::stripe::Stripe;
 impl<const D: usize, const N: usize> Stripe<D, N> for RAID3<D, N> {
     const DATA: usize = D - 1;
     const DISKS: usize = D;
+    const XOR_PARITY: bool = true;
 
     fn write(&mut self, data: &[Bits<N>]) {
         assert_eq!(
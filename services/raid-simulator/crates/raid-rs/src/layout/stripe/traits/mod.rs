@@ -1,4 +1,4 @@
 //! Common trait abstractions for stripe operations and rebuild workflows.
 
-pub(crate) mod restore;
+pub mod restore;
 pub mod stripe;
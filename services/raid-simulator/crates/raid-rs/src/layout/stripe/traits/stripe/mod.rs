@@ -3,8 +3,35 @@
 #[cfg(test)]
 mod stripe_tests;
 
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
 use crate::layout::bits::Bits;
 use crate::layout::stripe::traits::restore::Restore;
+use crate::{Error, Result};
+
+/// `ChunkRole` is what a disk slot of a stripe stores.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkRole {
+    /// Data chunk with the given index.
+    Data(usize),
+    /// Full copy of the stripe data; the index numbers the copy.
+    Mirror(usize),
+    /// Parity computed over the data chunks.
+    Parity,
+}
+
+impl fmt::Display for ChunkRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Data(i) => f.pad(&format!("data {i}")),
+            Self::Mirror(i) => f.pad(&format!("mirror {i}")),
+            Self::Parity => f.pad("parity"),
+        }
+    }
+}
 
 /// Stripe describes read/write behavior for a RAID stripe.
 pub trait Stripe<const D: usize, const N: usize> {
@@ -12,6 +39,11 @@ pub trait Stripe<const D: usize, const N: usize> {
     const DATA: usize;
     /// DISKS is the total number of disks used by the stripe layout.
     const DISKS: usize;
+    /// `XOR_PARITY` is true when every parity slot holds the XOR of the data chunks,
+    /// so a write can update parity from the chunks it changes alone.
+    const XOR_PARITY: bool = false;
+    /// `FAULT_TOLERANCE` is how many members a stripe can lose and still be decoded.
+    const FAULT_TOLERANCE: usize = Self::DISKS - Self::DATA;
 
     /// `write` encodes data into the stripe layout.
     ///
@@ -33,6 +65,99 @@ pub trait Stripe<const D: usize, const N: usize> {
     /// # Arguments
     /// * `out` - The output buffer to populate with raw blocks.
     fn read_raw(&self, out: &mut [Bits<N>]);
+    /// `try_write` encodes data into the stripe layout after checking its length.
+    ///
+    /// # Arguments
+    /// * `data` - The data blocks to encode into the stripe.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if `data` does not hold exactly `DATA` blocks.
+    fn try_write(&mut self, data: &[Bits<N>]) -> Result<()> {
+        check_len("data", Self::DATA, data.len())?;
+        self.write(data);
+        Ok(())
+    }
+    /// `try_write_raw` writes raw blocks into the stripe after checking their length.
+    ///
+    /// # Arguments
+    /// * `data` - The raw blocks to copy into the stripe.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if `data` does not hold exactly `DISKS` blocks.
+    fn try_write_raw(&mut self, data: &[Bits<N>]) -> Result<()> {
+        check_len("raw", Self::DISKS, data.len())?;
+        self.write_raw(data);
+        Ok(())
+    }
+    /// `try_read` decodes the stripe layout after checking the output length.
+    ///
+    /// # Arguments
+    /// * `out` - The output buffer to populate with decoded data blocks.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if `out` does not hold exactly `DATA` blocks.
+    fn try_read(&self, out: &mut [Bits<N>]) -> Result<()> {
+        check_len("data", Self::DATA, out.len())?;
+        self.read(out);
+        Ok(())
+    }
+    /// `try_read_raw` reads raw blocks from the stripe after checking the output length.
+    ///
+    /// # Arguments
+    /// * `out` - The output buffer to populate with raw blocks.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if `out` does not hold exactly `DISKS` blocks.
+    fn try_read_raw(&self, out: &mut [Bits<N>]) -> Result<()> {
+        check_len("raw", Self::DISKS, out.len())?;
+        self.read_raw(out);
+        Ok(())
+    }
+    #[must_use]
+    /// `role` returns what disk slot `disk` stores.
+    ///
+    /// The default places data chunk `c` in slot `c` and parity in the remaining slots.
+    fn role(disk: usize) -> ChunkRole
+    where
+        Self: Sized,
+    {
+        if disk < Self::DATA {
+            ChunkRole::Data(disk)
+        } else {
+            ChunkRole::Parity
+        }
+    }
+    #[must_use]
+    /// `role_at` returns what disk slot `disk` stores in stripe `stripe_index`.
+    ///
+    /// The default ignores the stripe; layouts that move chunks between stripes
+    /// override it together with `place`.
+    fn role_at(stripe_index: u64, disk: usize) -> ChunkRole
+    where
+        Self: Sized,
+    {
+        let _ = stripe_index;
+        Self::role(disk)
+    }
+    #[must_use]
+    /// `peers` returns the members whose chunks rebuild `disk` in stripe `stripe_index`.
+    ///
+    /// The default names every other member of the stripe.
+    fn peers(stripe_index: u64, disk: usize) -> Vec<usize>
+    where
+        Self: Sized,
+    {
+        let _ = stripe_index;
+        (0..Self::DISKS).filter(|&d| d != disk).collect()
+    }
+    /// `place` selects the placement of stripe `stripe_index` before it is encoded,
+    /// decoded or restored.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Physical index of the stripe about to be handled.
+    fn place(&mut self, stripe_index: u64) {
+        let _ = stripe_index;
+    }
     /// `as_restore` returns a restoration trait object if supported.
     fn as_restore(&self) -> Option<&dyn Restore> {
         None
@@ -42,3 +167,14 @@ pub trait Stripe<const D: usize, const N: usize> {
         None
     }
 }
+
+/// `check_len` fails unless a buffer of `kind` blocks holds exactly `expected` of them.
+fn check_len(kind: &str, expected: usize, len: usize) -> Result<()> {
+    if len == expected {
+        Ok(())
+    } else {
+        Err(Error::Geometry(format!(
+            "stripe expects {expected} {kind} chunks, got {len}"
+        )))
+    }
+}
@@ -1,5 +1,5 @@
 use crate::layout::bits::Bits;
-use crate::layout::stripe::traits::stripe::Stripe;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
 
 #[derive(Default)]
 struct DummyStripe<const D: usize, const N: usize>;
@@ -18,3 +18,48 @@ fn default_as_restore_is_none_for_concrete_type() {
     let s = DummyStripe::<3, 4>;
     assert!(s.as_restore().is_none());
 }
+
+#[test]
+fn default_role_puts_parity_after_data() {
+    struct TwoData;
+    impl Stripe<3, 4> for TwoData {
+        const DATA: usize = 2;
+        const DISKS: usize = 3;
+        fn write(&mut self, _data: &[Bits<4>]) {}
+        fn write_raw(&mut self, _data: &[Bits<4>]) {}
+        fn read(&self, _out: &mut [Bits<4>]) {}
+        fn read_raw(&self, _out: &mut [Bits<4>]) {}
+    }
+
+    assert_eq!(TwoData::role(1), ChunkRole::Data(1));
+    assert_eq!(TwoData::role(2), ChunkRole::Parity);
+    assert_eq!(format!("[{:<8}]", ChunkRole::Data(0)), "[data 0  ]");
+}
+
+#[test]
+fn checked_access_rejects_wrong_lengths() {
+    use crate::Error;
+    use crate::layout::stripe::raid3::RAID3;
+
+    let mut r = RAID3::<3, 2>::zero();
+    let chunks = [Bits::<2>([1, 2]), Bits::<2>([3, 4]), Bits::<2>([5, 6])];
+
+    assert!(matches!(r.try_write(&chunks), Err(Error::Geometry(_))));
+    assert!(matches!(
+        r.try_write_raw(&chunks[..2]),
+        Err(Error::Geometry(_))
+    ));
+    let mut out = [Bits::<2>::zero(); 3];
+    assert!(matches!(r.try_read(&mut out), Err(Error::Geometry(_))));
+    assert!(matches!(
+        r.try_read_raw(&mut out[..1]),
+        Err(Error::Geometry(_))
+    ));
+
+    r.try_write(&chunks[..2]).expect("two data chunks");
+    r.try_read_raw(&mut out).expect("three raw chunks");
+    assert_eq!(out[2], Bits::<2>([1 ^ 3, 2 ^ 4]));
+    let mut data = [Bits::<2>::zero(); 2];
+    r.try_read(&mut data).expect("two data chunks");
+    assert_eq!(data, [chunks[0], chunks[1]]);
+}
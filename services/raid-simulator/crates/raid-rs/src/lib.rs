@@ -1,6 +1,11 @@
 //! Core RAID layout and retention primitives used by the simulator.
 #![allow(clippy::cargo_common_metadata)]
 
+mod error;
 pub mod layout;
 pub mod metrics;
 pub mod retention;
+pub mod simulator;
+pub mod testing;
+
+pub use error::{Error, Result};
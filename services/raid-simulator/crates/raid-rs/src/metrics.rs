@@ -1,16 +1,39 @@
 //! Lightweight metrics hooks for recording RAID simulator events.
 
-use std::sync::{Arc, OnceLock};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// `IO_TRACE_TARGET` is the tracing target of the per-operation IO spans.
+///
+/// The spans are emitted at `TRACE` level, so they cost a level check unless a
+/// subscriber enables this target.
+pub const IO_TRACE_TARGET: &str = "raid_io";
 
 /// `IoOpType` describes a read or write operation.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum IoOpType {
     Read,
     Write,
 }
 
+/// `IoClass` separates IO users ask for from work the volume does on its own.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IoClass {
+    /// Reads and writes issued by users of the volume.
+    #[default]
+    Foreground,
+    /// Rebuild and scrub traffic.
+    Background,
+}
+
 /// `DiskOp` captures disk IO metrics emitted by the simulator.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DiskOp {
     pub disk_id: String,
     pub op: IoOpType,
@@ -20,12 +43,25 @@ pub struct DiskOp {
 }
 
 /// `RaidOp` captures RAID IO metrics emitted by the simulator.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct RaidOp {
     pub op: IoOpType,
     pub bytes: u64,
     pub latency_seconds: f64,
     pub error: bool,
+    /// The volume had missing or rebuilding members when the operation ran.
+    pub degraded: bool,
+    /// Stripes the read took from the read-ahead cache.
+    pub read_ahead_hits: u64,
+    /// Stripes the read loaded from the disks while read-ahead was active.
+    pub read_ahead_misses: u64,
+    /// Stripes the write updated from cached parity.
+    pub parity_cache_hits: u64,
+    /// Partial-stripe writes that found no cached parity while the cache was active.
+    pub parity_cache_misses: u64,
+    /// Whether the operation served users of the volume or rebuild and scrub work.
+    #[serde(default)]
+    pub class: IoClass,
 }
 
 /// `MetricsSink` records disk and RAID operations from the simulator.
@@ -36,41 +72,218 @@ pub trait MetricsSink: Send + Sync + 'static {
     fn record_raid_op(&self, op: RaidOp);
 }
 
-static METRICS_SINK: OnceLock<Arc<dyn MetricsSink>> = OnceLock::new();
+struct Registered {
+    name: String,
+    enabled: bool,
+    sink: Arc<dyn MetricsSink>,
+}
+
+/// Registered sinks in registration order; every enabled sink receives each event.
+static SINKS: RwLock<Vec<Registered>> = RwLock::new(Vec::new());
+/// Count of enabled sinks, so hot paths can skip timing when nobody listens.
+static ENABLED_SINKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Bytes handed to block compression, bytes it produced, and nanoseconds spent
+/// compressing and decompressing, summed over every compressed volume.
+static COMPRESS_INPUT: AtomicU64 = AtomicU64::new(0);
+static COMPRESS_OUTPUT: AtomicU64 = AtomicU64::new(0);
+static COMPRESS_NANOS: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Array the operations recorded on this thread belong to, if labelled.
+    static SCOPE: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+fn sinks_mut() -> RwLockWriteGuard<'static, Vec<Registered>> {
+    SINKS.write().unwrap_or_else(PoisonError::into_inner)
+}
 
-/// `install_metrics_sink` installs a global metrics sink for the simulator.
+fn sinks() -> RwLockReadGuard<'static, Vec<Registered>> {
+    SINKS.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn refresh_enabled(sinks: &[Registered]) {
+    let enabled = sinks.iter().filter(|s| s.enabled).count();
+    ENABLED_SINKS.store(enabled, Ordering::Relaxed);
+}
+
+#[must_use]
+/// `register_sink` adds an enabled sink that receives every simulator event.
 ///
 /// # Arguments
+/// * `name` - Unique name used to toggle or remove the sink later.
 /// * `sink` - Sink implementation to register.
 ///
 /// # Returns
-/// `true` if the sink was installed, `false` if one was already registered.
-pub fn install_metrics_sink(sink: Arc<dyn MetricsSink>) -> bool {
-    METRICS_SINK.set(sink).is_ok()
+/// `true` if the sink was registered, `false` if the name is already taken.
+pub fn register_sink(name: &str, sink: Arc<dyn MetricsSink>) -> bool {
+    let mut sinks = sinks_mut();
+    if sinks.iter().any(|s| s.name == name) {
+        return false;
+    }
+    sinks.push(Registered {
+        name: name.to_string(),
+        enabled: true,
+        sink,
+    });
+    refresh_enabled(&sinks);
+    drop(sinks);
+    true
+}
+
+#[must_use]
+/// `unregister_sink` removes a sink from the registry.
+///
+/// # Arguments
+/// * `name` - Name the sink was registered under.
+///
+/// # Returns
+/// `true` if a sink was removed.
+pub fn unregister_sink(name: &str) -> bool {
+    let mut sinks = sinks_mut();
+    let before = sinks.len();
+    sinks.retain(|s| s.name != name);
+    refresh_enabled(&sinks);
+    let removed = sinks.len() != before;
+    drop(sinks);
+    removed
 }
 
-/// `is_enabled` reports whether a metrics sink has been installed.
+#[must_use]
+/// `set_sink_enabled` pauses or resumes delivery to a registered sink.
+///
+/// # Arguments
+/// * `name` - Name the sink was registered under.
+/// * `enabled` - Whether the sink should receive events.
+///
+/// # Returns
+/// `true` if a sink with that name exists.
+pub fn set_sink_enabled(name: &str, enabled: bool) -> bool {
+    let mut sinks = sinks_mut();
+    let Some(entry) = sinks.iter_mut().find(|s| s.name == name) else {
+        return false;
+    };
+    entry.enabled = enabled;
+    refresh_enabled(&sinks);
+    drop(sinks);
+    true
+}
+
+#[must_use]
+/// `registered_sinks` lists registered sink names with their enabled flag.
+pub fn registered_sinks() -> Vec<(String, bool)> {
+    sinks()
+        .iter()
+        .map(|s| (s.name.clone(), s.enabled))
+        .collect()
+}
+
+/// `set_thread_scope` labels the operations recorded on the current thread with
+/// the array they belong to, so sinks shared by several arrays can tell them apart.
+///
+/// # Arguments
+/// * `scope` - Array label, or `None` to clear it.
+pub fn set_thread_scope(scope: Option<&str>) {
+    SCOPE.with(|current| *current.borrow_mut() = scope.map(Arc::from));
+}
+
+#[must_use]
+/// `thread_scope` returns the array label of the current thread, if any.
+pub fn thread_scope() -> Option<Arc<str>> {
+    SCOPE.with(|current| current.borrow().clone())
+}
+
+/// `is_enabled` reports whether at least one enabled metrics sink is registered.
 pub fn is_enabled() -> bool {
-    METRICS_SINK.get().is_some()
+    ENABLED_SINKS.load(Ordering::Relaxed) > 0
 }
 
-/// `record_disk_op` forwards a disk operation to the installed sink.
+/// `record_disk_op` forwards a disk operation to every enabled sink.
 ///
 /// # Arguments
 /// * `op` - Disk operation to record.
 pub fn record_disk_op(op: DiskOp) {
-    if let Some(sink) = METRICS_SINK.get() {
-        sink.record_disk_op(op);
+    if !is_enabled() {
+        return;
     }
+    let sinks = sinks();
+    let mut enabled = sinks.iter().filter(|s| s.enabled).peekable();
+    while let Some(entry) = enabled.next() {
+        if enabled.peek().is_none() {
+            entry.sink.record_disk_op(op);
+            break;
+        }
+        entry.sink.record_disk_op(op.clone());
+    }
+    drop(sinks);
 }
 
-/// `record_raid_op` forwards a RAID operation to the installed sink.
+/// `record_raid_op` forwards a RAID operation to every enabled sink.
 ///
 /// # Arguments
 /// * `op` - RAID operation to record.
 pub fn record_raid_op(op: RaidOp) {
-    if let Some(sink) = METRICS_SINK.get() {
-        sink.record_raid_op(op);
+    if !is_enabled() {
+        return;
+    }
+    for entry in sinks().iter().filter(|s| s.enabled) {
+        entry.sink.record_raid_op(op);
+    }
+}
+
+/// `CompressionTotals` sums the block compression work of every compressed volume.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompressionTotals {
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub cpu_seconds: f64,
+}
+
+impl CompressionTotals {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// `ratio` returns input bytes per output byte; 1 before anything was compressed.
+    pub fn ratio(&self) -> f64 {
+        if self.output_bytes == 0 {
+            1.0
+        } else {
+            self.input_bytes as f64 / self.output_bytes as f64
+        }
+    }
+}
+
+fn add_nanos(elapsed: Duration) {
+    let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+    COMPRESS_NANOS.fetch_add(nanos, Ordering::Relaxed);
+}
+
+/// `record_compression` adds one compressed block to the compression totals.
+///
+/// # Arguments
+/// * `input` - Uncompressed bytes.
+/// * `output` - Bytes produced by the compressor.
+/// * `elapsed` - Time spent compressing.
+pub fn record_compression(input: usize, output: usize, elapsed: Duration) {
+    COMPRESS_INPUT.fetch_add(input as u64, Ordering::Relaxed);
+    COMPRESS_OUTPUT.fetch_add(output as u64, Ordering::Relaxed);
+    add_nanos(elapsed);
+}
+
+/// `record_decompression` adds the time spent decompressing one block.
+///
+/// # Arguments
+/// * `elapsed` - Time spent decompressing.
+pub fn record_decompression(elapsed: Duration) {
+    add_nanos(elapsed);
+}
+
+#[must_use]
+/// `compression_totals` returns the block compression work done so far.
+pub fn compression_totals() -> CompressionTotals {
+    CompressionTotals {
+        input_bytes: COMPRESS_INPUT.load(Ordering::Relaxed),
+        output_bytes: COMPRESS_OUTPUT.load(Ordering::Relaxed),
+        cpu_seconds: Duration::from_nanos(COMPRESS_NANOS.load(Ordering::Relaxed)).as_secs_f64(),
     }
 }
 
@@ -79,11 +292,27 @@ mod tests {
     use super::*;
     use std::sync::Mutex;
 
+    #[derive(Default)]
     struct TestSink {
         disk_ops: Mutex<Vec<DiskOp>>,
         raid_ops: Mutex<Vec<RaidOp>>,
     }
 
+    impl TestSink {
+        fn disk_ops_for(&self, disk_id: &str) -> Vec<DiskOp> {
+            let ops = self.disk_ops.lock().unwrap();
+            ops.iter()
+                .filter(|op| op.disk_id == disk_id)
+                .cloned()
+                .collect()
+        }
+
+        fn raid_ops_of(&self, bytes: u64) -> Vec<RaidOp> {
+            let ops = self.raid_ops.lock().unwrap();
+            ops.iter().filter(|op| op.bytes == bytes).copied().collect()
+        }
+    }
+
     impl MetricsSink for TestSink {
         fn record_disk_op(&self, op: DiskOp) {
             self.disk_ops.lock().unwrap().push(op);
@@ -94,45 +323,87 @@ mod tests {
         }
     }
 
+    fn disk_op(disk_id: &str) -> DiskOp {
+        DiskOp {
+            disk_id: disk_id.to_string(),
+            op: IoOpType::Write,
+            bytes: 2048,
+            latency_seconds: 0.15,
+            error: false,
+        }
+    }
+
     #[test]
     fn metrics_sink_records_ops_when_enabled() {
-        let sink = Arc::new(TestSink {
-            disk_ops: Mutex::new(Vec::new()),
-            raid_ops: Mutex::new(Vec::new()),
-        });
+        let sink = Arc::new(TestSink::default());
 
-        assert!(install_metrics_sink(sink.clone()));
+        assert!(register_sink("test-records", sink.clone()));
         assert!(is_enabled());
 
-        record_disk_op(DiskOp {
-            disk_id: "disk1".to_string(),
-            op: IoOpType::Write,
-            bytes: 2048,
-            latency_seconds: 0.15,
-            error: false,
-        });
+        record_disk_op(disk_op("disk-records"));
         record_raid_op(RaidOp {
             op: IoOpType::Read,
-            bytes: 512,
+            bytes: 51_234,
             latency_seconds: 0.05,
             error: true,
+            degraded: true,
+            read_ahead_hits: 0,
+            read_ahead_misses: 0,
+            parity_cache_hits: 0,
+            parity_cache_misses: 0,
+            class: IoClass::Foreground,
         });
 
-        {
-            let disk_ops = sink.disk_ops.lock().unwrap();
-            assert_eq!(disk_ops.len(), 1);
-            assert_eq!(disk_ops[0].disk_id, "disk1");
-            assert_eq!(disk_ops[0].bytes, 2048);
-            assert!(!disk_ops[0].error);
-            drop(disk_ops);
-        }
+        let disk_ops = sink.disk_ops_for("disk-records");
+        assert_eq!(disk_ops.len(), 1);
+        assert_eq!(disk_ops[0].bytes, 2048);
+        assert!(!disk_ops[0].error);
 
-        {
-            let raid_ops = sink.raid_ops.lock().unwrap();
-            assert_eq!(raid_ops.len(), 1);
-            assert_eq!(raid_ops[0].bytes, 512);
-            assert!(raid_ops[0].error);
-            drop(raid_ops);
-        }
+        let raid_ops = sink.raid_ops_of(51_234);
+        assert_eq!(raid_ops.len(), 1);
+        assert!(raid_ops[0].error);
+        assert!(raid_ops[0].degraded);
+
+        assert!(unregister_sink("test-records"));
+    }
+
+    #[test]
+    fn registry_fans_out_and_toggles_sinks() {
+        let first = Arc::new(TestSink::default());
+        let second = Arc::new(TestSink::default());
+        assert!(register_sink("test-fan-a", first.clone()));
+        assert!(register_sink("test-fan-b", second.clone()));
+        assert!(!register_sink("test-fan-a", second.clone()));
+
+        record_disk_op(disk_op("disk-fan-1"));
+        assert_eq!(first.disk_ops_for("disk-fan-1").len(), 1);
+        assert_eq!(second.disk_ops_for("disk-fan-1").len(), 1);
+
+        assert!(set_sink_enabled("test-fan-b", false));
+        assert!(registered_sinks().contains(&("test-fan-b".to_string(), false)));
+        record_disk_op(disk_op("disk-fan-2"));
+        assert_eq!(first.disk_ops_for("disk-fan-2").len(), 1);
+        assert!(second.disk_ops_for("disk-fan-2").is_empty());
+
+        assert!(set_sink_enabled("test-fan-b", true));
+        assert!(unregister_sink("test-fan-a"));
+        assert!(!unregister_sink("test-fan-a"));
+        assert!(!set_sink_enabled("test-fan-a", true));
+        record_disk_op(disk_op("disk-fan-3"));
+        assert!(first.disk_ops_for("disk-fan-3").is_empty());
+        assert_eq!(second.disk_ops_for("disk-fan-3").len(), 1);
+
+        assert!(unregister_sink("test-fan-b"));
+    }
+
+    #[test]
+    fn thread_scope_labels_only_the_current_thread() {
+        assert_eq!(thread_scope(), None);
+        set_thread_scope(Some("md0"));
+        assert_eq!(thread_scope().as_deref(), Some("md0"));
+        let other = std::thread::spawn(thread_scope).join().unwrap();
+        assert_eq!(other, None);
+        set_thread_scope(None);
+        assert_eq!(thread_scope(), None);
     }
 }
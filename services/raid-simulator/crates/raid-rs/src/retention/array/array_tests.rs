@@ -1,6 +1,9 @@
 use super::Array;
+use crate::Error;
 use crate::layout::bits::Bits;
 use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::IoError;
+use crate::retention::disk::DiskIo;
 use std::array::from_fn;
 use tempfile::NamedTempFile;
 
@@ -55,7 +58,7 @@ fn write_persists_data_to_each_disk() {
     const N: usize = 16;
     const DISK_LEN: u64 = 1024;
     let (_temps, paths) = tmp_paths::<D>();
-    let mut array = Array::<D, N>::init_array(&paths, DISK_LEN);
+    let mut array = Array::<D, N>::init_array(&paths, DISK_LEN).expect("init array");
 
     let write_data: [Bits<N>; D] = [Bits([0x11; N]), Bits([0x22; N]), Bits([0x33; N])];
     let stripe = SimpleStripe::new(write_data);
@@ -79,7 +82,7 @@ fn read_restores_data_into_stripe() {
     const N: usize = 8;
     const DISK_LEN: u64 = 1024;
     let (_temps, paths) = tmp_paths::<D>();
-    let mut array = Array::<D, N>::init_array(&paths, DISK_LEN);
+    let mut array = Array::<D, N>::init_array(&paths, DISK_LEN).expect("init array");
 
     let disk_contents: [Bits<N>; D] = [
         Bits([0xAA; N]),
@@ -98,3 +101,126 @@ fn read_restores_data_into_stripe() {
 
     assert_eq!(stripe.data(), disk_contents, "stripe must match disk data");
 }
+
+#[test]
+fn peek_returns_raw_chunks_and_skips_missing_disks() {
+    const D: usize = 3;
+    const N: usize = 4;
+    const DISK_LEN: u64 = 64;
+    let (_temps, paths) = tmp_paths::<D>();
+    let mut array = Array::<D, N>::init_array(&paths, DISK_LEN).expect("init array");
+
+    let off = 8u64;
+    array.0[0].write_at(off, &[1, 2, 3, 4]);
+    array.0[2].write_at(off, &[9, 9, 9, 9]);
+    array.fail_disk(1).expect("fail disk");
+
+    let chunks = array.peek(off);
+
+    assert_eq!(chunks[0], Some(Bits([1, 2, 3, 4])));
+    assert_eq!(chunks[1], None);
+    assert_eq!(chunks[2], Some(Bits([9, 9, 9, 9])));
+}
+
+#[test]
+fn peek_disk_copies_raw_member_bytes() {
+    const D: usize = 2;
+    const N: usize = 4;
+    const DISK_LEN: u64 = 64;
+    let (_temps, paths) = tmp_paths::<D>();
+    let mut array = Array::<D, N>::init_array(&paths, DISK_LEN).expect("init array");
+    array.0[0].write_at(10, b"member");
+
+    let mut buf = [0u8; 6];
+    array.peek_disk(0, 10, &mut buf).expect("peek disk");
+    assert_eq!(&buf, b"member");
+
+    assert!(matches!(
+        array.peek_disk(0, DISK_LEN - 2, &mut buf),
+        Err(Error::Geometry(_))
+    ));
+    assert!(matches!(
+        array.peek_disk(D, 0, &mut buf),
+        Err(Error::Geometry(_))
+    ));
+    array.fail_disk(1).expect("fail disk");
+    assert!(matches!(
+        array.peek_disk(1, 0, &mut buf),
+        Err(Error::Degraded(IoError::DiskMissing { .. }))
+    ));
+}
+
+#[test]
+fn in_flight_counts_operations_until_they_finish() {
+    const D: usize = 2;
+    const N: usize = 4;
+    let (_temps, paths) = tmp_paths::<D>();
+    let mut array = Array::<D, N>::init_array(&paths, 64).expect("init array");
+    let in_flight = array.in_flight();
+
+    {
+        let _first = array.1.begin(1);
+        let _second = array.1.begin(1);
+        assert_eq!(in_flight.depths(), vec![0, 2]);
+    }
+    assert_eq!(in_flight.depth(1), 0);
+    assert_eq!(in_flight.depth(D), 0);
+
+    array.write(0, &SimpleStripe::<D, N>::new([Bits([1; N]); D]));
+    let mut stripe = SimpleStripe::<D, N>::empty();
+    array.read(0, &mut stripe);
+    assert_eq!(in_flight.depths(), vec![0, 0]);
+}
+
+#[test]
+fn sized_array_uses_the_smallest_member() {
+    const D: usize = 3;
+    const N: usize = 4;
+    let (_temps, paths) = tmp_paths::<D>();
+    let array =
+        Array::<D, N>::init_array_sized(&paths, &[128, 64, 256], DiskIo::Mmap).expect("init array");
+
+    assert_eq!(array.disk_len(), 64);
+    assert_eq!(array.0[2].len(), 256);
+    assert_eq!(array.status().disk_bytes, 64);
+}
+
+#[test]
+fn replace_disk_with_rejects_images_below_the_usable_length() {
+    const D: usize = 2;
+    const N: usize = 4;
+    let (_temps, paths) = tmp_paths::<D>();
+    let mut array = Array::<D, N>::init_array(&paths, 64).expect("init array");
+    let spare = NamedTempFile::new().expect("tmp file");
+    array.fail_disk(1).expect("fail disk");
+
+    let err = array
+        .replace_disk_with(1, spare.path(), 32)
+        .expect_err("image too small");
+    assert!(err.to_string().contains("smaller than the 64 bytes"));
+    assert!(array.0[1].is_missing());
+    assert!(array.replace_disk_with(2, spare.path(), 64).is_err());
+
+    array
+        .replace_disk_with(1, spare.path(), 128)
+        .expect("replace disk");
+    assert_eq!(array.0[1].path(), spare.path());
+    assert_eq!(array.disk_len(), 64);
+}
+
+#[test]
+fn status_round_trips_through_json() {
+    use super::ArrayStatus;
+
+    const D: usize = 2;
+    const N: usize = 4;
+    let (_temps, paths) = tmp_paths::<D>();
+    let mut array = Array::<D, N>::init_array(&paths, 64).expect("init array");
+    array.fail_disk(1).expect("fail disk");
+    let status = array.status();
+
+    let json = serde_json::to_value(&status).expect("serialize");
+    assert_eq!(json["members"][1]["state"], "failed");
+    let back: ArrayStatus = serde_json::from_value(json).expect("deserialize");
+    assert_eq!(back, status);
+}
@@ -0,0 +1,50 @@
+//! In-flight operation counters for the disks of an array.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// `InFlight` counts the operations each disk of an array is currently serving.
+///
+/// Clones share the counters, so a handle taken before the array is locked for IO
+/// observes the queue depth of that IO from another thread.
+#[derive(Clone, Debug)]
+pub struct InFlight(Arc<[AtomicU64]>);
+
+impl InFlight {
+    pub(super) fn new(disks: usize) -> Self {
+        Self((0..disks).map(|_| AtomicU64::new(0)).collect())
+    }
+
+    #[must_use]
+    /// `depth` returns the number of operations disk `i` is serving; `0` when out of range.
+    pub fn depth(&self, i: usize) -> u64 {
+        self.0
+            .get(i)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    #[must_use]
+    /// `depths` returns the number of operations each disk is serving.
+    pub fn depths(&self) -> Vec<u64> {
+        self.0
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// `begin` counts an operation on disk `i` until the returned guard is dropped.
+    pub(super) fn begin(&self, i: usize) -> InFlightGuard<'_> {
+        let count = &self.0[i];
+        count.fetch_add(1, Ordering::Relaxed);
+        InFlightGuard(count)
+    }
+}
+
+/// `InFlightGuard` ends an in-flight operation when dropped.
+pub(super) struct InFlightGuard<'a>(&'a AtomicU64);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -2,38 +2,96 @@
 
 #[cfg(test)]
 mod array_tests;
+mod in_flight;
+mod status;
+
+pub use in_flight::InFlight;
+pub use status::{ArrayStatus, MemberState, MemberStatus, RebuildStatus};
 
 use crate::layout::bits::Bits;
 use crate::layout::stripe::traits::stripe::Stripe;
 use crate::metrics::{DiskOp, IoOpType};
-use crate::retention::disk::Disk;
-use std::fmt::Write;
+use crate::retention::IoError;
+use crate::retention::disk::{Disk, DiskIo};
+use crate::{Error, Result};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 /// Array manages a fixed set of disk images for a RAID volume.
-pub struct Array<const D: usize, const N: usize>(pub [Disk; D]);
+///
+/// The second field counts the operations each disk is serving.
+pub struct Array<const D: usize, const N: usize>(pub [Disk; D], InFlight);
 
 impl<const D: usize, const N: usize> Array<D, N> {
-    #[must_use]
     /// `init_array` creates and opens a disk array using the provided paths.
     ///
     /// # Arguments
     /// * `paths` - Disk image paths, one per disk.
     /// * `len` - Length of each disk image in bytes.
     ///
-    /// # Panics
-    /// Panics if any disk image cannot be created or opened.
-    pub fn init_array(paths: &[String; D], len: u64) -> Self {
-        let array: [Disk; D] =
-            std::array::from_fn(|i| Disk::open_prealloc(&paths[i], len).unwrap());
+    /// # Errors
+    /// Returns an error if any disk image cannot be created or opened.
+    pub fn init_array(paths: &[String; D], len: u64) -> Result<Self> {
+        Self::init_array_with(paths, len, DiskIo::Mmap)
+    }
+
+    /// `init_array_with` creates and opens a disk array accessed through `io`.
+    ///
+    /// # Arguments
+    /// * `paths` - Disk image paths, one per disk.
+    /// * `len` - Length of each disk image in bytes.
+    /// * `io` - Access method for every disk image.
+    ///
+    /// # Errors
+    /// Returns an error if any disk image cannot be created or opened.
+    pub fn init_array_with(paths: &[String; D], len: u64, io: DiskIo) -> Result<Self> {
+        Self::init_array_sized(paths, &[len; D], io)
+    }
+
+    /// `init_array_sized` creates and opens a disk array whose members differ in size.
+    ///
+    /// Only the length of the smallest member is used on every disk.
+    ///
+    /// # Arguments
+    /// * `paths` - Disk image paths, one per disk; they may live in different directories.
+    /// * `lens` - Length of each disk image in bytes.
+    /// * `io` - Access method for every disk image.
+    ///
+    /// # Errors
+    /// Returns an error if any disk image cannot be created or opened.
+    pub fn init_array_sized(paths: &[String; D], lens: &[u64; D], io: DiskIo) -> Result<Self> {
+        let disks = paths
+            .iter()
+            .zip(lens)
+            .map(|(path, &len)| Disk::open_with(path, len, io))
+            .collect::<Result<Vec<_>>>()?;
+        let array: [Disk; D] = disks
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("one disk is opened per path"));
+
+        Ok(Self(array, InFlight::new(D)))
+    }
+
+    /// `check_index` fails unless `i` names a member of the array.
+    pub(crate) fn check_index(i: usize) -> Result<()> {
+        if i >= D {
+            return Err(Error::Geometry(format!(
+                "disk index out of range: {i} (D={D})"
+            )));
+        }
+        Ok(())
+    }
 
-        Self(array)
+    #[must_use]
+    /// `in_flight` returns a handle to the per-disk in-flight operation counters.
+    pub fn in_flight(&self) -> InFlight {
+        self.1.clone()
     }
 
     #[must_use]
-    /// `disk_len` returns the length of the first disk in the array.
+    /// `disk_len` returns the length usable on every disk: that of the smallest member.
     pub fn disk_len(&self) -> u64 {
-        self.0.first().map_or(0, Disk::len)
+        self.0.iter().map(Disk::len).min().unwrap_or(0)
     }
 
     /// `fail_disk` simulates a disk failure at the specified index.
@@ -43,10 +101,8 @@ impl<const D: usize, const N: usize> Array<D, N> {
     ///
     /// # Errors
     /// Returns an error if the index is out of range or the disk cannot fail.
-    pub fn fail_disk(&mut self, i: usize) -> anyhow::Result<()> {
-        if i >= D {
-            anyhow::bail!("disk index out of range: {i} (D={D})");
-        }
+    pub fn fail_disk(&mut self, i: usize) -> Result<()> {
+        Self::check_index(i)?;
         self.0[i].fail()
     }
 
@@ -57,33 +113,74 @@ impl<const D: usize, const N: usize> Array<D, N> {
     ///
     /// # Errors
     /// Returns an error if the index is out of range or the disk cannot be replaced.
-    pub fn replace_disk(&mut self, i: usize) -> anyhow::Result<()> {
-        if i >= D {
-            anyhow::bail!("disk index out of range: {i} (D={D})");
-        }
+    pub fn replace_disk(&mut self, i: usize) -> Result<()> {
+        Self::check_index(i)?;
         self.0[i].replace()
     }
 
+    /// `replace_disk_with` replaces the disk at the specified index with a blank image
+    /// at a new location.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to replace.
+    /// * `path` - Path of the new disk image.
+    /// * `len` - Length of the new disk image in bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range, the image is smaller than the
+    /// usable length of the array, or the image cannot be created.
+    pub fn replace_disk_with(&mut self, i: usize, path: &Path, len: u64) -> Result<()> {
+        Self::check_index(i)?;
+        let usable = self.disk_len();
+        if len < usable {
+            return Err(Error::Geometry(format!(
+                "replacement image of {len} bytes is smaller than the {usable} bytes used per disk"
+            )));
+        }
+        self.0[i].replace_with(path, len)
+    }
+
+    /// `reattach_disk` restores the last failed image of the disk at the specified index.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to reattach.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range or the image cannot be restored.
+    pub fn reattach_disk(&mut self, i: usize) -> Result<()> {
+        Self::check_index(i)?;
+        self.0[i].reattach()
+    }
+
+    /// `split_disk` detaches the disk at the specified index and keeps its image.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to split off.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range, the disk is missing, or its
+    /// image cannot be moved aside.
+    pub fn split_disk(&mut self, i: usize) -> Result<PathBuf> {
+        Self::check_index(i)?;
+        self.0[i].split()
+    }
+
+    /// `rejoin_disk` restores the last split image of the disk at the specified index.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to rejoin.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range or the image cannot be restored.
+    pub fn rejoin_disk(&mut self, i: usize) -> Result<()> {
+        Self::check_index(i)?;
+        self.0[i].rejoin()
+    }
+
     #[must_use]
-    /// `status_string` returns a human-readable status summary for each disk.
+    /// `status_string` returns a human-readable, mdstat-style status summary.
     pub fn status_string(&self) -> String {
-        let mut out = String::new();
-        for (i, d) in self.0.iter().enumerate() {
-            let state = if d.is_missing() {
-                "FAILED"
-            } else if d.needs_rebuild {
-                "NEEDS_REBUILD"
-            } else {
-                "OK"
-            };
-            let exists = d.path().exists();
-            let _ = writeln!(
-                out,
-                "disk {i}: {state} (image_exists={exists}, path={})",
-                d.path().display()
-            );
-        }
-        out
+        self.status().to_string()
     }
 
     /// `write` persists a stripe to disk at the specified offset.
@@ -95,26 +192,136 @@ impl<const D: usize, const N: usize> Array<D, N> {
         let mut data_buf: [Bits<N>; D] = [Bits::zero(); D];
         stripe.read_raw(&mut data_buf);
 
-        for (i, (disk, data)) in self.0.iter_mut().zip(&data_buf).enumerate() {
-            if !disk.is_missing() {
-                let start = crate::metrics::is_enabled().then(Instant::now);
-                let written = disk.write_at(off, &data.0);
-                if written == data.0.len() {
-                    disk.needs_rebuild = false;
-                }
-                if let Some(start) = start {
-                    let bytes = u64::try_from(data.0.len()).unwrap_or(u64::MAX);
-                    let error = written != data.0.len();
-                    crate::metrics::record_disk_op(DiskOp {
-                        disk_id: format!("disk{i}"),
-                        op: IoOpType::Write,
-                        bytes,
-                        latency_seconds: start.elapsed().as_secs_f64(),
-                        error,
-                    });
+        for (i, data) in data_buf.iter().enumerate() {
+            self.write_member(i, off, &data.0);
+        }
+    }
+
+    /// `write_span` persists consecutive stripes starting at `off` with one write per disk.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset of the first stripe within each disk.
+    /// * `stripes` - Raw chunks of every stripe, one entry per disk.
+    pub fn write_span(&mut self, off: u64, stripes: &[[Bits<N>; D]]) {
+        let mut buf = vec![0u8; stripes.len() * N];
+        for i in 0..D {
+            for (dst, raw) in buf.chunks_exact_mut(N).zip(stripes) {
+                dst.copy_from_slice(&raw[i].0);
+            }
+            self.write_member(i, off, &buf);
+        }
+    }
+
+    /// `read_chunk` reads the chunk one disk stores at the specified offset.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk.
+    /// * `off` - Byte offset within the disk.
+    /// * `chunk` - Chunk to populate; left untouched when the disk is missing.
+    ///
+    /// # Panics
+    /// Panics if `i` is not a disk of the array.
+    pub fn read_chunk(&self, i: usize, off: u64, chunk: &mut Bits<N>) {
+        self.read_member(i, off, &mut chunk.0);
+    }
+
+    /// `write_chunk` writes the chunk of one disk at the specified offset.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk; missing disks are skipped.
+    /// * `off` - Byte offset within the disk.
+    /// * `chunk` - Chunk to write.
+    ///
+    /// # Panics
+    /// Panics if `i` is not a disk of the array.
+    pub fn write_chunk(&mut self, i: usize, off: u64, chunk: &Bits<N>) {
+        self.write_member(i, off, &chunk.0);
+    }
+
+    /// `peek` returns the raw chunk stored on each disk at the specified offset.
+    ///
+    /// Unlike `read`, nothing is reconstructed or written back.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset within each disk.
+    ///
+    /// # Returns
+    /// One entry per disk; `None` when the disk is missing.
+    #[must_use]
+    pub fn peek(&self, off: u64) -> Vec<Option<Bits<N>>> {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(i, disk)| {
+                if disk.is_missing() {
+                    return None;
                 }
+                let mut chunk = Bits::<N>::zero();
+                let _guard = self.1.begin(i);
+                disk.read_at(off, &mut chunk.0);
+                Some(chunk)
+            })
+            .collect()
+    }
+
+    /// `peek_disk` copies the raw bytes of one disk at `off` into `buf`.
+    ///
+    /// Like `peek`, nothing is reconstructed or written back.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk.
+    /// * `off` - Byte offset within the disk.
+    /// * `buf` - Buffer to fill.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range, the disk is missing, or the
+    /// range reaches past the end of the disk.
+    pub fn peek_disk(&self, i: usize, off: u64, buf: &mut [u8]) -> Result<()> {
+        Self::check_index(i)?;
+        let disk = &self.0[i];
+        if disk.is_missing() {
+            return Err(IoError::DiskMissing {
+                path: disk.path().to_path_buf(),
+            }
+            .into());
+        }
+        let len = buf.len() as u64;
+        if off.checked_add(len).is_none_or(|end| end > disk.len()) {
+            return Err(IoError::OutOfRange {
+                offset: off,
+                len,
+                limit: disk.len(),
             }
+            .into());
         }
+        let _guard = self.1.begin(i);
+        disk.read_at(off, buf);
+        Ok(())
+    }
+
+    /// `borrow` returns `len` bytes of one disk at `off` without copying them.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk.
+    /// * `off` - Byte offset within the disk.
+    /// * `len` - Number of bytes to borrow.
+    ///
+    /// # Returns
+    /// `None` when the disk is missing or its backend cannot lend its contents.
+    #[must_use]
+    pub fn borrow(&self, i: usize, off: u64, len: usize) -> Option<&[u8]> {
+        let start = crate::metrics::is_enabled().then(Instant::now);
+        let data = self.0.get(i)?.borrow_at(off, len)?;
+        if let Some(start) = start {
+            crate::metrics::record_disk_op(DiskOp {
+                disk_id: format!("disk{i}"),
+                op: IoOpType::Read,
+                bytes: u64::try_from(len).unwrap_or(u64::MAX),
+                latency_seconds: start.elapsed().as_secs_f64(),
+                error: false,
+            });
+        }
+        Some(data)
     }
 
     /// `read` loads a stripe from disk at the specified offset.
@@ -124,47 +331,131 @@ impl<const D: usize, const N: usize> Array<D, N> {
     /// * `stripe` - Stripe object to populate.
     pub fn read<T: Stripe<D, N>>(&mut self, off: u64, stripe: &mut T) {
         let mut data_buf: [Bits<N>; D] = [Bits::zero(); D];
+        let skipped = self.unreadable(stripe.as_restore().is_some());
 
-        let mut missing_or_untrusted: Vec<usize> = Vec::new();
-        let supports_restore = stripe.as_restore().is_some();
+        for (i, data) in data_buf.iter_mut().enumerate() {
+            if !skipped.contains(&i) {
+                self.read_member(i, off, &mut data.0);
+            }
+        }
 
-        for (i, (disk, data)) in self.0.iter_mut().zip(data_buf.iter_mut()).enumerate() {
-            let disk_missing = disk.is_missing();
-            let untrusted = disk.needs_rebuild;
+        stripe.place(off / N as u64);
+        stripe.write_raw(&data_buf);
+        self.settle(off, stripe, &skipped);
+    }
 
-            if disk_missing || (supports_restore && untrusted) {
-                missing_or_untrusted.push(i);
-                continue;
-            }
-            let start = crate::metrics::is_enabled().then(Instant::now);
-            let read = disk.read_at(off, &mut data.0);
-            if let Some(start) = start {
-                let bytes = u64::try_from(data.0.len()).unwrap_or(u64::MAX);
-                let error = read != data.0.len();
-                crate::metrics::record_disk_op(DiskOp {
-                    disk_id: format!("disk{i}"),
-                    op: IoOpType::Read,
-                    bytes,
-                    latency_seconds: start.elapsed().as_secs_f64(),
-                    error,
-                });
+    /// `read_span` loads `count` consecutive stripes starting at `off` with one read per disk.
+    ///
+    /// Each stripe is reconstructed, scrubbed and repaired on disk exactly as `read`
+    /// does before `visit` sees it in `stripe`.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset of the first stripe within each disk.
+    /// * `count` - Number of stripes to load.
+    /// * `stripe` - Stripe object each stripe is loaded into.
+    /// * `visit` - Called with the position of the stripe in the span and its contents.
+    pub fn read_span<T: Stripe<D, N>>(
+        &mut self,
+        off: u64,
+        count: usize,
+        stripe: &mut T,
+        mut visit: impl FnMut(usize, &T),
+    ) {
+        let skipped = self.unreadable(stripe.as_restore().is_some());
+        let members: Vec<Vec<u8>> = (0..D)
+            .map(|i| {
+                let mut buf = vec![0u8; count * N];
+                if !skipped.contains(&i) {
+                    self.read_member(i, off, &mut buf);
+                }
+                buf
+            })
+            .collect();
+
+        let mut data_buf: [Bits<N>; D] = [Bits::zero(); D];
+        for k in 0..count {
+            for (data, member) in data_buf.iter_mut().zip(&members) {
+                data.0.copy_from_slice(&member[k * N..(k + 1) * N]);
             }
+            stripe.place(off / N as u64 + k as u64);
+            stripe.write_raw(&data_buf);
+            self.settle(off + (k * N) as u64, stripe, &skipped);
+            visit(k, stripe);
         }
+    }
 
-        stripe.write_raw(&data_buf);
+    /// `unreadable` lists the disks whose contents a read must reconstruct instead.
+    ///
+    /// Disks awaiting rebuild only count when the layout can restore them.
+    fn unreadable(&self, supports_restore: bool) -> Vec<usize> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter(|(_, disk)| disk.is_missing() || (supports_restore && disk.needs_rebuild))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// `read_member` reads one disk and records the operation.
+    fn read_member(&self, i: usize, off: u64, buf: &mut [u8]) {
+        let start = crate::metrics::is_enabled().then(Instant::now);
+        let guard = self.1.begin(i);
+        let read = self.0[i].read_at(off, buf);
+        drop(guard);
+        if let Some(start) = start {
+            let bytes = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+            crate::metrics::record_disk_op(DiskOp {
+                disk_id: format!("disk{i}"),
+                op: IoOpType::Read,
+                bytes,
+                latency_seconds: start.elapsed().as_secs_f64(),
+                error: read != buf.len(),
+            });
+        }
+    }
+
+    /// `write_member` writes one attached disk and records the operation.
+    ///
+    /// A complete write clears the disk's rebuild flag.
+    fn write_member(&mut self, i: usize, off: u64, data: &[u8]) {
+        let disk = &mut self.0[i];
+        if disk.is_missing() {
+            return;
+        }
+        let start = crate::metrics::is_enabled().then(Instant::now);
+        let guard = self.1.begin(i);
+        let written = disk.write_at(off, data);
+        drop(guard);
+        if written == data.len() {
+            disk.needs_rebuild = false;
+        }
+        if let Some(start) = start {
+            let bytes = u64::try_from(data.len()).unwrap_or(u64::MAX);
+            crate::metrics::record_disk_op(DiskOp {
+                disk_id: format!("disk{i}"),
+                op: IoOpType::Write,
+                bytes,
+                latency_seconds: start.elapsed().as_secs_f64(),
+                error: written != data.len(),
+            });
+        }
+    }
 
+    /// `settle` restores the skipped members of a loaded stripe, scrubs it, and
+    /// writes every repaired chunk back to its disk.
+    fn settle<T: Stripe<D, N>>(&mut self, off: u64, stripe: &mut T, skipped: &[usize]) {
         let mut repaired_indices: Vec<usize> = Vec::new();
 
         if let Some(restorer) = stripe.as_restore_mut() {
             let raid1_like = T::DATA == 1 && T::DISKS == D;
 
             if raid1_like {
-                for &i in &missing_or_untrusted {
+                for &i in skipped {
                     restorer.restore(i);
                     repaired_indices.push(i);
                 }
-            } else if missing_or_untrusted.len() == 1 {
-                let i = missing_or_untrusted[0];
+            } else if skipped.len() == 1 {
+                let i = skipped[0];
                 restorer.restore(i);
                 repaired_indices.push(i);
             }
@@ -188,6 +479,7 @@ impl<const D: usize, const N: usize> Array<D, N> {
                     continue;
                 }
 
+                let _guard = self.1.begin(i);
                 self.0[i].write_at(off, &raw[i].0);
             }
         }
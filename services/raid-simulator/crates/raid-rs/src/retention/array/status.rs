@@ -0,0 +1,177 @@
+//! Structured array status with an mdstat-style text rendering.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::retention::array::Array;
+use crate::retention::disk::{DiskHealth, DiskStats};
+
+/// `MemberState` is the health of one member disk.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberState {
+    Ok,
+    NeedsRebuild,
+    Failed,
+}
+
+impl MemberState {
+    #[must_use]
+    /// `label` returns the upper-case name used in text output.
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Ok => "OK",
+            Self::NeedsRebuild => "NEEDS_REBUILD",
+            Self::Failed => "FAILED",
+        }
+    }
+}
+
+/// `MemberStatus` describes one member disk of the array.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberStatus {
+    pub index: usize,
+    pub state: MemberState,
+    pub path: String,
+    pub image_exists: bool,
+    #[serde(flatten)]
+    pub io: DiskStats,
+    pub health: DiskHealth,
+}
+
+/// `RebuildStatus` reports the progress of a running rebuild.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RebuildStatus {
+    pub done_stripes: u64,
+    pub total_stripes: u64,
+    /// Completed share of the rebuild in percent.
+    pub percent: f64,
+    /// Average repair rate since the rebuild started.
+    pub stripes_per_second: f64,
+    /// Chunks each member supplied to the repaired stripes, the rebuild fan-out.
+    #[serde(default)]
+    pub source_chunks: Vec<u64>,
+}
+
+/// `ArrayStatus` is a point-in-time summary of the array.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArrayStatus {
+    pub disks: usize,
+    /// Number of disks worth of capacity that hold data; `0` when unknown.
+    pub data_disks: usize,
+    pub chunk_bytes: usize,
+    pub disk_bytes: u64,
+    pub members: Vec<MemberStatus>,
+    pub rebuild: Option<RebuildStatus>,
+}
+
+impl ArrayStatus {
+    #[must_use]
+    /// `working` returns the number of members that are not failed.
+    pub fn working(&self) -> usize {
+        self.members
+            .iter()
+            .filter(|m| m.state != MemberState::Failed)
+            .count()
+    }
+}
+
+impl fmt::Display for ArrayStatus {
+    /// Renders the status in the spirit of `/proc/mdstat`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let health: String = self
+            .members
+            .iter()
+            .map(|m| if m.state == MemberState::Ok { 'U' } else { '_' })
+            .collect();
+        write!(f, "array : {} disks", self.disks)?;
+        if self.data_disks > 0 {
+            write!(f, ", {} data", self.data_disks)?;
+        }
+        writeln!(
+            f,
+            ", {} bytes per disk, {}B chunks [{}/{}] [{health}]",
+            self.disk_bytes,
+            self.chunk_bytes,
+            self.disks,
+            self.working()
+        )?;
+        if let Some(rebuild) = &self.rebuild {
+            let filled = rebuild
+                .done_stripes
+                .saturating_mul(20)
+                .checked_div(rebuild.total_stripes)
+                .map_or(20, |n| usize::try_from(n.min(20)).unwrap_or(20));
+            writeln!(
+                f,
+                "      [{}>{}]  recovery = {:.1}% ({}/{}) speed={:.0} stripes/sec",
+                "=".repeat(filled),
+                ".".repeat(20 - filled),
+                rebuild.percent,
+                rebuild.done_stripes,
+                rebuild.total_stripes,
+                rebuild.stripes_per_second
+            )?;
+            if rebuild.source_chunks.iter().any(|&n| n > 0) {
+                let fan_out: Vec<String> = rebuild
+                    .source_chunks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, n)| format!("disk{i}={n}"))
+                    .collect();
+                writeln!(f, "      fan-out: {}", fan_out.join(" "))?;
+            }
+        }
+        for m in &self.members {
+            writeln!(
+                f,
+                "disk {}: {}{} (image_exists={}, reads={}, writes={}, read_errors={}, write_errors={}, path={})",
+                m.index,
+                m.state.label(),
+                if m.health.pre_fail { " PRE-FAIL" } else { "" },
+                m.image_exists,
+                m.io.reads,
+                m.io.writes,
+                m.io.read_errors,
+                m.io.write_errors,
+                m.path
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<const D: usize, const N: usize> Array<D, N> {
+    #[must_use]
+    /// `status` returns the structured status of every member disk.
+    pub fn status(&self) -> ArrayStatus {
+        let members = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, d)| MemberStatus {
+                index,
+                state: if d.is_missing() {
+                    MemberState::Failed
+                } else if d.needs_rebuild {
+                    MemberState::NeedsRebuild
+                } else {
+                    MemberState::Ok
+                },
+                path: d.path().display().to_string(),
+                image_exists: d.path().exists(),
+                io: d.stats(),
+                health: d.health(),
+            })
+            .collect();
+        ArrayStatus {
+            disks: D,
+            data_disks: 0,
+            chunk_bytes: N,
+            disk_bytes: self.disk_len(),
+            members,
+            rebuild: None,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Access methods for disk images.
+
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::{MmapMut, MmapOptions};
+
+use crate::{Error, Result};
+
+/// `DiskIo` selects how a disk image is read and written.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DiskIo {
+    /// Memory-map the image; IO is served from the page cache.
+    #[default]
+    Mmap,
+    /// Submit reads and writes to an `io_uring` owned by the disk.
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    IoUring,
+    /// Open the image with `O_DIRECT`; IO bypasses the page cache.
+    #[cfg(target_os = "linux")]
+    Direct,
+}
+
+/// `Backend` is an open access method for one disk image.
+pub(super) enum Backend {
+    Mmap(MmapMut),
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    IoUring(Box<super::uring::Ring>),
+    #[cfg(target_os = "linux")]
+    Direct(super::direct::Direct),
+    Cached(Box<super::cache::WriteCache>),
+}
+
+impl Backend {
+    /// `open` prepares `file` of `len` bytes, found at `path`, for IO with the selected method.
+    pub(super) fn open(io: DiskIo, path: &Path, file: &File, len: u64) -> Result<Self> {
+        match io {
+            DiskIo::Mmap => {
+                let map_len = usize::try_from(len).map_err(|_| {
+                    Error::Geometry(format!("disk length {len} exceeds addressable size"))
+                })?;
+                let map = unsafe { MmapOptions::new().len(map_len).map_mut(file) }
+                    .map_err(Error::io(path))?;
+                Ok(Self::Mmap(map))
+            }
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            DiskIo::IoUring => Ok(Self::IoUring(Box::new(
+                super::uring::Ring::new(file).map_err(Error::io(path))?,
+            ))),
+            #[cfg(target_os = "linux")]
+            DiskIo::Direct => Ok(Self::Direct(
+                super::direct::Direct::open(path, len).map_err(Error::io(path))?,
+            )),
+        }
+    }
+
+    /// `read` copies `buf.len()` bytes at `off`, which the caller keeps inside the image.
+    pub(super) fn read(&self, off: usize, buf: &mut [u8]) -> usize {
+        match self {
+            Self::Mmap(map) => {
+                buf.copy_from_slice(&map[off..off + buf.len()]);
+                buf.len()
+            }
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Self::IoUring(ring) => ring.read(off as u64, buf),
+            #[cfg(target_os = "linux")]
+            Self::Direct(direct) => direct.read(off, buf),
+            Self::Cached(cache) => cache.read(off, buf),
+        }
+    }
+
+    /// `slice` borrows `len` bytes at `off` straight from a memory-mapped image.
+    ///
+    /// Other backends hold no mapping to borrow from and return `None`.
+    pub(super) fn slice(&self, off: usize, len: usize) -> Option<&[u8]> {
+        match self {
+            Self::Mmap(map) => map.get(off..off.checked_add(len)?),
+            _ => None,
+        }
+    }
+
+    /// `write` stores `data` at `off`, which the caller keeps inside the image.
+    pub(super) fn write(&mut self, off: usize, data: &[u8]) -> usize {
+        match self {
+            Self::Mmap(map) => {
+                map[off..off + data.len()].copy_from_slice(data);
+                data.len()
+            }
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Self::IoUring(ring) => ring.write(off as u64, data),
+            #[cfg(target_os = "linux")]
+            Self::Direct(direct) => direct.write(off, data),
+            Self::Cached(cache) => cache.write(off, data),
+        }
+    }
+
+    /// `sync` makes completed writes durable; `file` is the image behind the backend.
+    pub(super) fn sync(&mut self, file: &File) -> std::io::Result<()> {
+        match self {
+            Self::Mmap(map) => map.flush(),
+            Self::Cached(cache) => cache.sync(file),
+            #[cfg(all(feature = "io-uring", target_os = "linux"))]
+            Self::IoUring(_) => file.sync_data(),
+            #[cfg(target_os = "linux")]
+            Self::Direct(_) => file.sync_data(),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Drive write cache with power-loss simulation.
+//!
+//! Writes are acknowledged as soon as they sit in the cache and reach the image
+//! only when the disk is flushed, or when the cache runs out of room and writes
+//! back its oldest entries. A power failure loses each cached write with a
+//! configurable probability and persists the survivors, the way a volatile drive
+//! cache may persist or lose any part of its unflushed data; a battery-backed cache
+//! loses nothing.
+
+use std::fs::File;
+
+use serde::{Deserialize, Serialize};
+
+use super::backend::Backend;
+use super::rng::SplitMix64;
+
+/// `WriteCachePlan` configures the write cache of a disk.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WriteCachePlan {
+    /// Seed for the writes that survive a power failure; each disk of a volume
+    /// derives its own stream.
+    pub seed: u64,
+    /// Bytes of writes held before the oldest are written back; 0 holds every write
+    /// until the next flush.
+    pub capacity: usize,
+    /// Chance that a cached write is lost on power failure: 0 for a battery-backed
+    /// cache, 1 for a cache that loses everything.
+    pub volatility: f64,
+}
+
+impl Default for WriteCachePlan {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            capacity: 0,
+            volatility: 0.5,
+        }
+    }
+}
+
+/// `WriteCacheStats` counts what the write cache of a disk did with its writes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteCacheStats {
+    /// Writes acknowledged from the cache.
+    pub cached_writes: u64,
+    /// Cached writes stored on the image by a flush or to make room.
+    pub written_back: u64,
+    pub flushes: u64,
+    /// Cached writes a power failure dropped.
+    pub lost_writes: u64,
+    pub lost_bytes: u64,
+}
+
+impl std::ops::Add for WriteCacheStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            cached_writes: self.cached_writes + other.cached_writes,
+            written_back: self.written_back + other.written_back,
+            flushes: self.flushes + other.flushes,
+            lost_writes: self.lost_writes + other.lost_writes,
+            lost_bytes: self.lost_bytes + other.lost_bytes,
+        }
+    }
+}
+
+/// `WriteCache` wraps the backend of a disk and buffers writes until a flush.
+pub(super) struct WriteCache {
+    inner: Backend,
+    plan: WriteCachePlan,
+    pending: Vec<(usize, Vec<u8>)>,
+    pending_bytes: usize,
+    rng: SplitMix64,
+    crashed: bool,
+    stats: WriteCacheStats,
+}
+
+impl WriteCache {
+    /// `new` starts caching writes to `inner` as configured by `plan`.
+    pub(super) fn new(inner: Backend, plan: WriteCachePlan) -> Self {
+        Self {
+            inner,
+            plan,
+            pending: Vec::new(),
+            pending_bytes: 0,
+            rng: SplitMix64::new(plan.seed),
+            crashed: false,
+            stats: WriteCacheStats::default(),
+        }
+    }
+
+    /// `read` serves image data overlaid with writes that are still cached.
+    pub(super) fn read(&self, off: usize, buf: &mut [u8]) -> usize {
+        let n = self.inner.read(off, buf);
+        let end = off + buf.len();
+        for (at, data) in &self.pending {
+            let from = off.max(*at);
+            let to = end.min(at + data.len());
+            if from < to {
+                buf[from - off..to - off].copy_from_slice(&data[from - at..to - at]);
+            }
+        }
+        n
+    }
+
+    /// `write` caches `data`; after a power failure writes are accepted and lost.
+    pub(super) fn write(&mut self, off: usize, data: &[u8]) -> usize {
+        if self.crashed {
+            return data.len();
+        }
+        self.pending.push((off, data.to_vec()));
+        self.pending_bytes += data.len();
+        self.stats.cached_writes += 1;
+        if self.plan.capacity > 0 && self.pending_bytes > self.plan.capacity {
+            let mut spilled = 0;
+            let mut left = self.pending_bytes;
+            for (_, data) in &self.pending {
+                if left <= self.plan.capacity {
+                    break;
+                }
+                left -= data.len();
+                spilled += 1;
+            }
+            self.write_back(spilled);
+        }
+        data.len()
+    }
+
+    /// `flush` writes every cached write to the image in submission order.
+    pub(super) fn flush(&mut self) {
+        self.write_back(self.pending.len());
+        self.stats.flushes += 1;
+    }
+
+    /// `sync` flushes the cache and makes the image durable.
+    pub(super) fn sync(&mut self, file: &File) -> std::io::Result<()> {
+        self.flush();
+        self.inner.sync(file)
+    }
+
+    /// `crash` drops cached writes by the volatility and persists the rest.
+    ///
+    /// Survivors land in submission order: the cache holds the latest data of a
+    /// sector, so an older write never overwrites a newer one.
+    pub(super) fn crash(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        self.pending_bytes = 0;
+        for (off, data) in pending {
+            if self.rng.uniform() < self.plan.volatility {
+                self.stats.lost_writes += 1;
+                self.stats.lost_bytes += data.len() as u64;
+            } else {
+                self.inner.write(off, &data);
+            }
+        }
+        self.crashed = true;
+    }
+
+    /// `unflushed` returns the number of cached writes.
+    pub(super) const fn unflushed(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// `stats` returns the counters of the cache.
+    pub(super) const fn stats(&self) -> WriteCacheStats {
+        self.stats
+    }
+
+    /// `write_back` stores the `count` oldest cached writes on the image.
+    fn write_back(&mut self, count: usize) {
+        for (off, data) in self.pending.drain(..count) {
+            self.inner.write(off, &data);
+            self.pending_bytes -= data.len();
+            self.stats.written_back += 1;
+        }
+    }
+}
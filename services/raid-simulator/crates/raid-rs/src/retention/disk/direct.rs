@@ -0,0 +1,140 @@
+//! `O_DIRECT` access to a disk image.
+//!
+//! Transfers bypass the page cache, so every request reaches the device. The kernel
+//! requires offsets, lengths and buffers to be block aligned; requests are widened to
+//! whole blocks and staged through an aligned bounce buffer, and unaligned writes
+//! read the surrounding blocks first.
+
+use std::alloc::{self, Layout};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::path::Path;
+
+/// Alignment of offsets, lengths and buffers for `O_DIRECT` transfers.
+pub(super) const ALIGN: usize = 4096;
+
+/// `Direct` owns a second descriptor of the image opened with `O_DIRECT`.
+pub(super) struct Direct {
+    file: File,
+}
+
+impl Direct {
+    /// `open` reopens the image at `path`, whose length must be a multiple of [`ALIGN`].
+    pub(super) fn open(path: &Path, len: u64) -> io::Result<Self> {
+        if !len.is_multiple_of(ALIGN as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("direct IO needs a disk length that is a multiple of {ALIGN} bytes"),
+            ));
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)?;
+        Ok(Self { file })
+    }
+
+    /// `read` fills `buf` from `off` and returns the length of the prefix that was read.
+    pub(super) fn read(&self, off: usize, buf: &mut [u8]) -> usize {
+        if buf.is_empty() {
+            return 0;
+        }
+        let (start, end) = widen(off, buf.len());
+        let mut bounce = AlignedBuf::new(end - start);
+        let got = self.read_blocks(start, bounce.as_mut_slice());
+        let n = got.saturating_sub(off - start).min(buf.len());
+        buf[..n].copy_from_slice(&bounce.as_slice()[off - start..off - start + n]);
+        n
+    }
+
+    /// `write` stores `data` at `off` and returns the length of the prefix that was written.
+    pub(super) fn write(&self, off: usize, data: &[u8]) -> usize {
+        if data.is_empty() {
+            return 0;
+        }
+        let (start, end) = widen(off, data.len());
+        let mut bounce = AlignedBuf::new(end - start);
+        let blocks = bounce.as_mut_slice();
+        let head_partial = off != start;
+        let tail_partial = off + data.len() != end;
+        if head_partial && self.read_blocks(start, &mut blocks[..ALIGN]) < ALIGN {
+            return 0;
+        }
+        if tail_partial && (!head_partial || end - start > ALIGN) {
+            let last = blocks.len() - ALIGN;
+            if self.read_blocks(end - ALIGN, &mut blocks[last..]) < ALIGN {
+                return 0;
+            }
+        }
+        blocks[off - start..off - start + data.len()].copy_from_slice(data);
+
+        let put = self.write_blocks(start, blocks);
+        put.saturating_sub(off - start).min(data.len())
+    }
+
+    fn read_blocks(&self, start: usize, blocks: &mut [u8]) -> usize {
+        let mut done = 0;
+        while done < blocks.len() {
+            match self
+                .file
+                .read_at(&mut blocks[done..], (start + done) as u64)
+            {
+                Ok(0) | Err(_) => break,
+                Ok(n) => done += n,
+            }
+        }
+        done
+    }
+
+    fn write_blocks(&self, start: usize, blocks: &[u8]) -> usize {
+        let mut done = 0;
+        while done < blocks.len() {
+            match self.file.write_at(&blocks[done..], (start + done) as u64) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => done += n,
+            }
+        }
+        done
+    }
+}
+
+/// `widen` rounds the byte range `off..off + len` out to whole blocks.
+const fn widen(off: usize, len: usize) -> (usize, usize) {
+    let start = off / ALIGN * ALIGN;
+    let end = (off + len).div_ceil(ALIGN) * ALIGN;
+    (start, end)
+}
+
+/// `AlignedBuf` is a zeroed heap buffer aligned to [`ALIGN`].
+struct AlignedBuf {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+impl AlignedBuf {
+    fn new(len: usize) -> Self {
+        let layout = Layout::from_size_align(len, ALIGN)
+            .unwrap_or_else(|_| alloc::handle_alloc_error(Layout::new::<u8>()));
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+        Self { ptr, layout }
+    }
+
+    const fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.layout.size()) }
+    }
+
+    const fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuf {
+    fn drop(&mut self) {
+        unsafe { alloc::dealloc(self.ptr, self.layout) };
+    }
+}
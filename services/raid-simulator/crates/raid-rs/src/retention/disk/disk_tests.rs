@@ -1,6 +1,9 @@
-use crate::retention::disk::Disk;
+use crate::retention::IoError;
+use crate::retention::disk::{
+    Disk, DiskHealth, DiskStats, METADATA_BYTES, WearPlan, WriteCachePlan, rng::SplitMix64,
+};
 use rand::RngCore;
-use tempfile::NamedTempFile;
+use tempfile::{NamedTempFile, TempDir};
 
 const DISK_LEN: u64 = 1 << 20;
 
@@ -16,7 +19,11 @@ fn open_prealloc_creates_and_sizes_file() {
     let d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
     assert_eq!(d.len(), DISK_LEN, "disk length must match requested");
     let meta = std::fs::metadata(&path).expect("metadata");
-    assert_eq!(meta.len(), DISK_LEN, "backing file must be pre-sized");
+    assert_eq!(
+        meta.len(),
+        DISK_LEN + METADATA_BYTES,
+        "backing file must be pre-sized with the metadata tail"
+    );
 
     drop(d);
 }
@@ -84,6 +91,74 @@ fn durability_reopen_and_read_back() {
     }
 }
 
+#[test]
+fn reattach_restores_failed_image_contents() {
+    let dir = TempDir::new().expect("tmp dir");
+    let path = dir.path().join("disk-0.img").to_string_lossy().into_owned();
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    d.write_at(64, b"survivor");
+
+    assert!(d.reattach().is_err(), "attached disk cannot be reattached");
+    d.fail().expect("fail");
+    assert!(d.is_missing());
+
+    d.reattach().expect("reattach");
+    assert!(!d.is_missing());
+    assert!(d.needs_rebuild);
+    let mut buf = [0u8; 8];
+    d.read_at(64, &mut buf);
+    assert_eq!(&buf, b"survivor");
+}
+
+#[test]
+fn replace_with_moves_the_image_and_resizes_it() {
+    let dir = TempDir::new().expect("tmp dir");
+    let old = dir.path().join("disk-0.img");
+    let new = dir.path().join("spare").join("disk-0.img");
+    std::fs::create_dir_all(new.parent().unwrap()).expect("spare dir");
+    let mut d = Disk::open_prealloc(&old.to_string_lossy(), 4096).expect("open_prealloc");
+    d.write_at(0, b"original");
+    d.fail().expect("fail");
+
+    d.replace_with(&new, 8192).expect("replace_with");
+
+    assert_eq!(d.path(), new.as_path());
+    assert_eq!(d.len(), 8192);
+    assert!(!d.is_missing());
+    assert!(d.needs_rebuild);
+    assert_eq!(
+        std::fs::metadata(&new).expect("new image").len(),
+        8192 + METADATA_BYTES
+    );
+    let mut buf = [0xffu8; 8];
+    d.read_at(0, &mut buf);
+    assert_eq!(buf, [0; 8]);
+}
+
+#[test]
+fn metadata_tail_survives_reopen_and_stays_out_of_reach() {
+    use std::os::unix::fs::FileExt;
+
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    drop(Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc"));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .expect("open image");
+    file.write_all_at(b"superblock", DISK_LEN)
+        .expect("write tail");
+
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("reopen");
+    assert_eq!(d.write_at(DISK_LEN - 4, b"overflow"), 4);
+    drop(d);
+
+    let mut tail = [0u8; 10];
+    file.read_exact_at(&mut tail, DISK_LEN).expect("read tail");
+    assert_eq!(&tail, b"superblock");
+}
+
 #[test]
 fn read_past_end_is_truncated() {
     let tf = NamedTempFile::new().expect("tmp file");
@@ -159,3 +234,331 @@ fn large_random_roundtrips() {
         assert_eq!(back, data);
     }
 }
+
+#[test]
+fn try_io_reports_range_and_missing_errors() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+
+    d.try_write_at(DISK_LEN - 4, b"tail").expect("write fits");
+    let mut buf = [0u8; 8];
+    assert_eq!(
+        d.try_read_at(DISK_LEN - 4, &mut buf),
+        Err(IoError::OutOfRange {
+            offset: DISK_LEN - 4,
+            len: 8,
+            limit: DISK_LEN,
+        })
+    );
+
+    d.fail().expect("fail");
+    assert!(matches!(
+        d.try_write_at(0, b"x"),
+        Err(IoError::DiskMissing { .. })
+    ));
+}
+
+#[test]
+fn stats_count_io_and_short_transfers() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+
+    d.write_at(0, &[1u8; 64]);
+    d.write_at(DISK_LEN - 10, &[2u8; 20]);
+    let mut buf = [0u8; 32];
+    d.read_at(0, &mut buf);
+
+    let stats = d.stats();
+    assert_eq!(stats.writes, 2);
+    assert_eq!(stats.bytes_written, 74);
+    assert_eq!(stats.write_errors, 1);
+    assert_eq!(stats.reads, 1);
+    assert_eq!(stats.bytes_read, 32);
+    assert_eq!(stats.read_errors, 0);
+    assert!(stats.last_error_at.is_some());
+
+    d.fail().expect("fail");
+    d.replace().expect("replace");
+    assert_eq!(d.stats(), DiskStats::default());
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn io_uring_backend_roundtrips_large_and_short_transfers() {
+    use crate::retention::disk::DiskIo;
+
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let Ok(mut d) = Disk::open_with(&path, DISK_LEN, DiskIo::IoUring) else {
+        eprintln!("io_uring is not available here; skipping");
+        return;
+    };
+    assert_eq!(d.io(), DiskIo::IoUring);
+
+    let mut data = vec![0u8; 300 * 1024];
+    rand::rng().fill_bytes(&mut data);
+    assert_eq!(d.write_at(4096, &data), data.len());
+    let mut back = vec![0u8; data.len()];
+    assert_eq!(d.read_at(4096, &mut back), back.len());
+    assert_eq!(back, data);
+
+    assert_eq!(d.write_at(DISK_LEN - 10, &[7u8; 20]), 10);
+    drop(d);
+
+    let reopened = Disk::open_prealloc(&path, DISK_LEN).expect("reopen with mmap");
+    let mut tail = [0u8; 10];
+    reopened.read_at(DISK_LEN - 10, &mut tail);
+    assert_eq!(tail, [7u8; 10]);
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[test]
+fn io_uring_transfers_spanning_several_queues_keep_their_completions_apart() {
+    use crate::retention::disk::DiskIo;
+
+    const LEN: u64 = 8 << 20;
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let Ok(mut d) = Disk::open_with(&path, LEN, DiskIo::IoUring) else {
+        eprintln!("io_uring is not available here; skipping");
+        return;
+    };
+
+    let mut data = vec![0u8; 5 << 20];
+    rand::rng().fill_bytes(&mut data);
+    assert_eq!(d.write_at(0, &data), data.len());
+    for _ in 0..2 {
+        let mut back = vec![0u8; data.len()];
+        assert_eq!(d.read_at(0, &mut back), back.len());
+        assert_eq!(back, data);
+    }
+    let mut tail = vec![0u8; 5 << 20];
+    assert_eq!(d.read_at(LEN - 4096, &mut tail), 4096);
+}
+
+#[test]
+fn borrow_at_lends_mapped_bytes_only() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    d.write_at(100, b"borrowed");
+
+    assert_eq!(d.borrow_at(100, 8), Some(&b"borrowed"[..]));
+    assert_eq!(d.borrow_at(DISK_LEN - 4, 8), None);
+
+    d.simulate_crashes(1);
+    assert_eq!(d.borrow_at(100, 8), None);
+    d.sync().expect("sync");
+
+    d.fail().expect("fail");
+    assert_eq!(d.borrow_at(100, 8), None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn direct_backend_handles_unaligned_transfers() {
+    use crate::retention::disk::DiskIo;
+
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let Ok(mut d) = Disk::open_with(&path, DISK_LEN, DiskIo::Direct) else {
+        eprintln!("O_DIRECT is not supported here; skipping");
+        return;
+    };
+    assert_eq!(d.io(), DiskIo::Direct);
+
+    let mut data = vec![0u8; 3 * 4096 + 123];
+    rand::rng().fill_bytes(&mut data);
+    assert_eq!(d.write_at(4000, &data), data.len());
+    assert_eq!(d.write_at(4000 + data.len() as u64, &[9u8; 5]), 5);
+    let mut back = vec![0u8; data.len()];
+    assert_eq!(d.read_at(4000, &mut back), back.len());
+    assert_eq!(back, data);
+
+    assert_eq!(d.write_at(DISK_LEN - 10, &[7u8; 20]), 10);
+    drop(d);
+
+    let reopened = Disk::open_prealloc(&path, DISK_LEN).expect("reopen with mmap");
+    let mut edges = [0u8; 6];
+    reopened.read_at(3999, &mut edges[..1]);
+    reopened.read_at(4000 + data.len() as u64 - 1, &mut edges[1..]);
+    assert_eq!(edges, [0, data[data.len() - 1], 9, 9, 9, 9]);
+    let mut tail = [0u8; 10];
+    reopened.read_at(DISK_LEN - 10, &mut tail);
+    assert_eq!(tail, [7u8; 10]);
+
+    assert!(Disk::open_with(&path, DISK_LEN + 1, DiskIo::Direct).is_err());
+}
+
+#[test]
+fn crash_keeps_synced_writes_and_may_drop_unsynced_ones() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    d.simulate_crashes(3);
+
+    d.write_at(0, &[1u8; 8]);
+    d.sync().expect("sync");
+    d.write_at(4, &[2u8; 8]);
+    d.write_at(100, &[3u8; 4]);
+    assert_eq!(d.unsynced_writes(), 2);
+    let mut cached = [0u8; 12];
+    d.read_at(0, &mut cached);
+    assert_eq!(cached, [1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2]);
+
+    d.crash();
+    assert_eq!(d.unsynced_writes(), 0);
+    assert_eq!(d.write_at(200, &[4u8; 4]), 4);
+    drop(d);
+
+    let reopened = Disk::open_prealloc(&path, DISK_LEN).expect("reopen");
+    let mut head = [0u8; 4];
+    reopened.read_at(0, &mut head);
+    assert_eq!(head, [1u8; 4]);
+    let mut late = [0u8; 4];
+    reopened.read_at(200, &mut late);
+    assert_eq!(late, [0u8; 4]);
+}
+
+#[test]
+fn wear_reallocates_defects_until_pre_fail() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    d.write_at(0, &[1u8; 1024]);
+    assert_eq!(
+        d.health().grown_defects,
+        0,
+        "disks do not wear without a plan"
+    );
+
+    d.simulate_wear(WearPlan {
+        defects_per_gib: 1_048_576.0,
+        spare_sectors: 6,
+        pre_fail_threshold: 4,
+        ..WearPlan::default()
+    });
+    for _ in 0..3 {
+        d.write_at(0, &[1u8; 1024]);
+    }
+    let health = d.health();
+    assert_eq!(health.grown_defects, 3);
+    assert_eq!(health.reallocated_sectors, 3);
+    assert!(!health.pre_fail);
+
+    let mut buf = [0u8; 1024];
+    d.read_at(0, &mut buf);
+    assert!(d.health().pre_fail, "reads wear the disk too");
+    for _ in 0..4 {
+        d.write_at(0, &[1u8; 1024]);
+    }
+    let health = d.health();
+    assert_eq!(health.grown_defects, 8);
+    assert_eq!(
+        health.reallocated_sectors, 6,
+        "reallocation stops without spares"
+    );
+
+    d.replace().expect("replace");
+    assert_eq!(
+        d.health(),
+        DiskHealth {
+            temperature_celsius: 30,
+            ..DiskHealth::default()
+        }
+    );
+}
+
+#[test]
+fn load_heats_the_disk() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    assert_eq!(d.health().temperature_celsius, 30);
+
+    let data = vec![5u8; 1 << 20];
+    for _ in 0..100 {
+        d.write_at(0, &data);
+    }
+
+    assert!(d.health().temperature_celsius >= 34);
+}
+
+#[test]
+fn write_cache_holds_writes_until_flush() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    d.enable_write_cache(WriteCachePlan {
+        capacity: 8,
+        volatility: 1.0,
+        ..WriteCachePlan::default()
+    });
+
+    d.write_at(0, &[1u8; 4]);
+    d.write_at(4, &[2u8; 4]);
+    assert_eq!(std::fs::read(&path).expect("image")[..8], [0u8; 8]);
+    let mut cached = [0u8; 8];
+    d.read_at(0, &mut cached);
+    assert_eq!(cached, [1, 1, 1, 1, 2, 2, 2, 2]);
+
+    d.write_at(8, &[3u8; 4]);
+    assert_eq!(
+        d.write_cache_stats().written_back,
+        1,
+        "a full cache writes back its oldest entry"
+    );
+    assert_eq!(std::fs::read(&path).expect("image")[..4], [1u8; 4]);
+
+    d.flush();
+    d.write_at(12, &[4u8; 4]);
+    d.crash();
+    let stats = d.write_cache_stats();
+    assert_eq!(
+        (stats.cached_writes, stats.written_back, stats.flushes),
+        (4, 3, 1)
+    );
+    assert_eq!((stats.lost_writes, stats.lost_bytes), (1, 4));
+    drop(d);
+
+    let image = std::fs::read(&path).expect("image");
+    assert_eq!(
+        image[..16],
+        [1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn battery_backed_cache_survives_power_loss_and_closing_flushes() {
+    let tf = NamedTempFile::new().expect("tmp file");
+    let path = tmp_path_str(&tf);
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("open_prealloc");
+    d.enable_write_cache(WriteCachePlan {
+        volatility: 0.0,
+        ..WriteCachePlan::default()
+    });
+    d.write_at(0, &[5u8; 4]);
+    d.crash();
+    assert_eq!(d.write_cache_stats().lost_writes, 0);
+    drop(d);
+    assert_eq!(std::fs::read(&path).expect("image")[..4], [5u8; 4]);
+
+    let mut d = Disk::open_prealloc(&path, DISK_LEN).expect("reopen");
+    d.enable_write_cache(WriteCachePlan::default());
+    d.write_at(4, &[6u8; 4]);
+    drop(d);
+    assert_eq!(std::fs::read(&path).expect("image")[4..8], [6u8; 4]);
+}
+
+#[test]
+fn fault_stream_replays_its_seed() {
+    let mut a = SplitMix64::new(7);
+    let mut b = SplitMix64::new(7);
+    let draws: Vec<f64> = (0..64).map(|_| a.uniform()).collect();
+
+    assert!(draws.iter().all(|x| (0.0..1.0).contains(x)));
+    assert_eq!(draws, (0..64).map(|_| b.uniform()).collect::<Vec<_>>());
+    assert_ne!(draws[0].to_bits(), SplitMix64::new(8).uniform().to_bits());
+}
@@ -0,0 +1,169 @@
+//! SMART-like wear model kept by every disk image.
+//!
+//! Moving data wears the medium: defects grow at a configurable rate per GiB of IO
+//! and are remapped to spare sectors until the spares run out. The drive also heats
+//! up under load and cools back towards ambient while idle. Once enough sectors have
+//! been reallocated the disk reports itself as pre-fail, the way a SMART attribute
+//! crossing its threshold warns ahead of the failure itself.
+
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use super::rng::SplitMix64;
+use crate::retention::disk::Disk;
+
+const MIB: f64 = 1_048_576.0;
+const GIB: f64 = 1_073_741_824.0;
+const AMBIENT_CELSIUS: f64 = 30.0;
+const MAX_RISE_CELSIUS: f64 = 35.0;
+const CELSIUS_PER_MIB: f64 = 0.05;
+const COOLING_HALF_LIFE_SECS: f64 = 30.0;
+
+/// `WearPlan` configures how quickly a disk wears out.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WearPlan {
+    /// Seed for when defects appear; each disk of a volume derives its own stream.
+    pub seed: u64,
+    /// Mean number of defects grown per GiB read or written.
+    pub defects_per_gib: f64,
+    /// Spare sectors available to remap defects.
+    pub spare_sectors: u64,
+    /// Reallocated sectors at which the disk reports pre-fail.
+    pub pre_fail_threshold: u64,
+}
+
+impl Default for WearPlan {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            defects_per_gib: 0.0,
+            spare_sectors: 1024,
+            pre_fail_threshold: 256,
+        }
+    }
+}
+
+/// `DiskHealth` is a SMART-like snapshot of the condition of a disk.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskHealth {
+    /// Defects found since the disk was installed.
+    pub grown_defects: u64,
+    /// Defects remapped to spare sectors.
+    pub reallocated_sectors: u64,
+    pub temperature_celsius: u32,
+    /// The disk predicts its own failure: reallocations reached the threshold or
+    /// the spare sectors ran out.
+    pub pre_fail: bool,
+}
+
+/// `Wear` accumulates the damage and heat caused by the IO of one disk.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Wear {
+    plan: WearPlan,
+    rng: SplitMix64,
+    grown_defects: u64,
+    reallocated: u64,
+    heat: f64,
+    heated_at: Option<Instant>,
+}
+
+impl Default for Wear {
+    fn default() -> Self {
+        Self::new(WearPlan::default())
+    }
+}
+
+impl Wear {
+    /// `new` starts a disk in mint condition that wears according to `plan`.
+    pub(super) const fn new(plan: WearPlan) -> Self {
+        Self {
+            plan,
+            rng: SplitMix64::new(plan.seed),
+            grown_defects: 0,
+            reallocated: 0,
+            heat: 0.0,
+            heated_at: None,
+        }
+    }
+
+    /// `renew` models a new drive in the same slot: the damage is gone, the plan stays.
+    pub(super) const fn renew(&mut self) {
+        self.grown_defects = 0;
+        self.reallocated = 0;
+        self.heat = 0.0;
+        self.heated_at = None;
+    }
+
+    /// `record` wears the disk by an IO that moved `bytes` bytes.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    pub(super) fn record(&mut self, bytes: usize, now: Instant) {
+        let bytes = bytes as f64;
+        self.heat = (bytes / MIB).mul_add(CELSIUS_PER_MIB, self.heat_at(now));
+        self.heated_at = Some(now);
+
+        let expected = self.plan.defects_per_gib * bytes / GIB;
+        let grown = expected.trunc() as u64 + u64::from(self.rng.uniform() < expected.fract());
+        for _ in 0..grown {
+            self.grown_defects += 1;
+            if self.reallocated < self.plan.spare_sectors {
+                self.reallocated += 1;
+            }
+        }
+    }
+
+    /// `health` returns the SMART-like attributes as of `now`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub(super) fn health(&self, now: Instant) -> DiskHealth {
+        let rise = self.heat_at(now).min(MAX_RISE_CELSIUS);
+        DiskHealth {
+            grown_defects: self.grown_defects,
+            reallocated_sectors: self.reallocated,
+            temperature_celsius: (AMBIENT_CELSIUS + rise).round() as u32,
+            pre_fail: self.grown_defects > self.plan.spare_sectors
+                || (self.plan.pre_fail_threshold > 0
+                    && self.reallocated >= self.plan.pre_fail_threshold),
+        }
+    }
+
+    /// `heat_at` returns the temperature rise left at `now` after cooling.
+    fn heat_at(&self, now: Instant) -> f64 {
+        self.heated_at.map_or(0.0, |at| {
+            let idle = now.saturating_duration_since(at).as_secs_f64();
+            self.heat * 0.5f64.powf(idle / COOLING_HALF_LIFE_SECS)
+        })
+    }
+}
+
+impl Disk {
+    /// `simulate_wear` makes IO grow defects on this disk according to `plan`.
+    ///
+    /// Damage already done is kept. Replacing the disk installs a new drive that
+    /// starts without defects and wears by the same plan.
+    ///
+    /// # Arguments
+    /// * `plan` - Defect rate, spare sectors and pre-fail threshold of the disk.
+    pub fn simulate_wear(&mut self, plan: WearPlan) {
+        let mut wear = self.wear.get();
+        wear.plan = plan;
+        wear.rng = SplitMix64::new(plan.seed);
+        self.wear.set(wear);
+    }
+
+    #[must_use]
+    /// `health` returns the SMART-like attributes of this disk.
+    pub fn health(&self) -> DiskHealth {
+        self.wear.get().health(Instant::now())
+    }
+
+    /// `record_wear` wears the disk by an IO that moved `bytes` bytes.
+    pub(super) fn record_wear(&self, bytes: usize) {
+        let mut wear = self.wear.get();
+        wear.record(bytes, Instant::now());
+        self.wear.set(wear);
+    }
+}
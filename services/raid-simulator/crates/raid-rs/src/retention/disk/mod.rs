@@ -1,22 +1,54 @@
 //! Disk-backed storage primitives for RAID retention.
 
+mod backend;
+mod cache;
+#[cfg(target_os = "linux")]
+mod direct;
 #[cfg(test)]
 mod disk_tests;
+mod health;
+mod rng;
+mod stats;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring;
 
-use memmap2::{MmapMut, MmapOptions};
+pub use backend::DiskIo;
+pub use cache::{WriteCachePlan, WriteCacheStats};
+pub use health::{DiskHealth, WearPlan};
+pub use stats::DiskStats;
+
+use backend::Backend;
+use cache::WriteCache;
+use health::Wear;
+
+use crate::retention::IoError;
+use crate::{Error, Result};
+use std::cell::Cell;
 use std::fs::File;
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Disk manages a file-backed disk image with optional memory mapping.
+/// `METADATA_BYTES` is the tail of every disk image kept for member metadata, such as
+/// a superblock; the disk serves only the bytes before it.
+pub const METADATA_BYTES: u64 = 4096;
+
+/// `VOLUME_METADATA_OFFSET` is where the second half of the metadata tail starts,
+/// which belongs to the volume; the first half is left to the caller's superblock.
+pub const VOLUME_METADATA_OFFSET: u64 = METADATA_BYTES / 2;
+
+/// Disk manages a file-backed disk image accessed through a selectable backend.
 pub struct Disk {
     path: PathBuf,
     file: Option<File>,
-    map: Option<MmapMut>,
+    backend: Option<Backend>,
+    io: DiskIo,
     len: u64,
 
     pub needs_rebuild: bool,
+    stats: Cell<DiskStats>,
+    wear: Cell<Wear>,
+    cache: Option<WriteCachePlan>,
 }
 
 impl Disk {
@@ -28,7 +60,21 @@ impl Disk {
     ///
     /// # Errors
     /// Returns an error if the file cannot be created, resized, or memory-mapped.
-    pub fn open_prealloc(path: &str, len: u64) -> anyhow::Result<Self> {
+    pub fn open_prealloc(path: &str, len: u64) -> Result<Self> {
+        Self::open_with(path, len, DiskIo::Mmap)
+    }
+
+    /// `open_with` opens or creates a preallocated disk image using the given backend.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the disk image file.
+    /// * `len` - Desired length of the disk image in bytes.
+    /// * `io` - Access method for the image.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created or resized, or the backend
+    /// cannot be set up.
+    pub fn open_with(path: &str, len: u64, io: DiskIo) -> Result<Self> {
         let path = PathBuf::from(path);
         let existed = path.exists();
 
@@ -37,21 +83,24 @@ impl Disk {
             .write(true)
             .create(true)
             .truncate(false)
-            .open(&path)?;
+            .open(&path)
+            .map_err(Error::io(&path))?;
 
         let prev_len = file.metadata().map(|m| m.len()).unwrap_or(0);
-        file.set_len(len)?;
-
-        let map_len = usize::try_from(len)
-            .map_err(|_| anyhow::anyhow!("disk length {len} exceeds addressable size"))?;
-        let map = unsafe { MmapOptions::new().len(map_len).map_mut(&file)? };
+        file.set_len(len.saturating_add(METADATA_BYTES))
+            .map_err(Error::io(&path))?;
+        let backend = Backend::open(io, &path, &file, len)?;
 
         Ok(Self {
             path,
             file: Some(file),
-            map: Some(map),
+            backend: Some(backend),
+            io,
             len,
             needs_rebuild: !existed || prev_len == 0,
+            stats: Cell::default(),
+            wear: Cell::default(),
+            cache: None,
         })
     }
 
@@ -59,7 +108,7 @@ impl Disk {
     ///
     /// # Errors
     /// Returns an error if the disk image cannot be renamed.
-    pub fn fail(&mut self) -> anyhow::Result<()> {
+    pub fn fail(&mut self) -> Result<()> {
         if self.path.exists() {
             let ts = SystemTime::now()
                 .duration_since(UNIX_EPOCH)
@@ -69,7 +118,7 @@ impl Disk {
             let _ = std::fs::rename(&self.path, &failed_path);
         }
 
-        self.map.take();
+        self.backend.take();
         self.file.take();
         Ok(())
     }
@@ -78,24 +127,220 @@ impl Disk {
     ///
     /// # Errors
     /// Returns an error if the disk image cannot be recreated or mapped.
-    pub fn replace(&mut self) -> anyhow::Result<()> {
+    pub fn replace(&mut self) -> Result<()> {
+        let path = self.path.clone();
+        self.replace_with(&path, self.len)
+    }
+
+    /// `replace_with` swaps in a blank image at `path` and marks it for rebuild.
+    ///
+    /// The previous image, if any, is left where it is.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the new disk image; an existing file is truncated.
+    /// * `len` - Length of the new disk image in bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the disk image cannot be created or mapped.
+    pub fn replace_with(&mut self, path: &Path, len: u64) -> Result<()> {
         let file = std::fs::OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
             .truncate(true)
-            .open(&self.path)?;
-        file.set_len(self.len)?;
-        let map_len = usize::try_from(self.len)
-            .map_err(|_| anyhow::anyhow!("disk length {} exceeds addressable size", self.len))?;
-        let map = unsafe { MmapOptions::new().len(map_len).map_mut(&file)? };
+            .open(path)
+            .map_err(Error::io(path))?;
+        file.set_len(len.saturating_add(METADATA_BYTES))
+            .map_err(Error::io(path))?;
+        let backend = self.with_cache(Backend::open(self.io, path, &file, len)?);
 
+        self.path = path.to_path_buf();
+        self.len = len;
+        self.backend = Some(backend);
         self.file = Some(file);
-        self.map = Some(map);
         self.needs_rebuild = true;
+        self.stats.take();
+        let mut wear = self.wear.get();
+        wear.renew();
+        self.wear.set(wear);
         Ok(())
     }
 
+    /// `split` detaches the disk and keeps its image as a standalone copy.
+    ///
+    /// Unlike [`Disk::fail`], the image must be renamed for the split to succeed,
+    /// since the caller relies on the copy it returns.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is missing or its image cannot be renamed.
+    pub fn split(&mut self) -> Result<PathBuf> {
+        if self.is_missing() {
+            return Err(Error::Invalid(format!(
+                "disk {} is not attached",
+                self.path.display()
+            )));
+        }
+        self.sync()?;
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let split_path = self.path.with_extension(format!("img.split.{ts}"));
+        std::fs::rename(&self.path, &split_path).map_err(Error::io(&self.path))?;
+
+        self.backend.take();
+        self.file.take();
+        Ok(split_path)
+    }
+
+    /// `reattach` restores the most recently failed image of this disk.
+    ///
+    /// The restored image keeps its old contents and is marked for rebuild so the
+    /// writes it missed can be resynced.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is still attached, no failed image exists, or the
+    /// image cannot be restored and mapped.
+    pub fn reattach(&mut self) -> Result<()> {
+        self.restore_image("failed")
+    }
+
+    /// `rejoin` restores the most recently split image of this disk.
+    ///
+    /// Like [`Disk::reattach`], the image is marked for rebuild so the writes it
+    /// missed while split off can be resynced.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is still attached, no split image exists, or the
+    /// image cannot be restored and mapped.
+    pub fn rejoin(&mut self) -> Result<()> {
+        self.restore_image("split")
+    }
+
+    /// `restore_image` moves the newest `<image>.<tag>.<ts>` file back into place.
+    fn restore_image(&mut self, tag: &str) -> Result<()> {
+        if !self.is_missing() {
+            return Err(Error::Invalid(format!(
+                "disk {} is still attached",
+                self.path.display()
+            )));
+        }
+        let name = self
+            .path
+            .file_name()
+            .map(|n| format!("{}.{tag}.", n.to_string_lossy()))
+            .unwrap_or_default();
+        let dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        let previous = std::fs::read_dir(dir)
+            .map_err(Error::io(dir))?
+            .filter_map(Result::ok)
+            .filter_map(|e| {
+                let file_name = e.file_name().to_string_lossy().into_owned();
+                let ts = file_name.strip_prefix(&name)?.parse::<u64>().ok()?;
+                Some((ts, e.path()))
+            })
+            .max_by_key(|(ts, _)| *ts)
+            .map(|(_, path)| path)
+            .ok_or_else(|| {
+                Error::Invalid(format!("no {tag} image found for {}", self.path.display()))
+            })?;
+        std::fs::rename(&previous, &self.path).map_err(Error::io(&previous))?;
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.path)
+            .map_err(Error::io(&self.path))?;
+        file.set_len(self.len.saturating_add(METADATA_BYTES))
+            .map_err(Error::io(&self.path))?;
+        let backend = self.with_cache(Backend::open(self.io, &self.path, &file, self.len)?);
+
+        self.backend = Some(backend);
+        self.file = Some(file);
+        self.needs_rebuild = true;
+        Ok(())
+    }
+
+    /// `enable_write_cache` acknowledges writes from a drive cache until [`Disk::flush`].
+    ///
+    /// Reads still observe cached writes. [`Disk::crash`] then decides which of them
+    /// reach the image. A replacement or reattached image gets the same cache; a
+    /// failed disk loses whatever its cache held.
+    ///
+    /// # Arguments
+    /// * `plan` - Capacity and volatility of the cache.
+    pub fn enable_write_cache(&mut self, plan: WriteCachePlan) {
+        if self.cache.is_none() {
+            self.cache = Some(plan);
+            self.backend = self.backend.take().map(|inner| self.with_cache(inner));
+        }
+    }
+
+    /// `simulate_crashes` holds back writes in a volatile cache until [`Disk::sync`].
+    ///
+    /// # Arguments
+    /// * `seed` - Seed for the choice and order of writes that survive a crash.
+    pub fn simulate_crashes(&mut self, seed: u64) {
+        self.enable_write_cache(WriteCachePlan {
+            seed,
+            ..WriteCachePlan::default()
+        });
+    }
+
+    /// `flush` writes every cached write to the image, like a cache flush command.
+    pub fn flush(&mut self) {
+        if let Some(Backend::Cached(cache)) = self.backend.as_mut() {
+            cache.flush();
+        }
+    }
+
+    /// `sync` flushes the write cache and makes every completed write durable.
+    ///
+    /// # Errors
+    /// Returns an error if the image cannot be flushed.
+    pub fn sync(&mut self) -> Result<()> {
+        if let (Some(backend), Some(file)) = (self.backend.as_mut(), self.file.as_ref()) {
+            backend.sync(file).map_err(Error::io(&self.path))?;
+        }
+        Ok(())
+    }
+
+    /// `crash` simulates power loss on a disk with a write cache.
+    ///
+    /// Each unflushed write is lost with the volatility of the cache and the rest are
+    /// persisted; all writes issued after the crash are lost.
+    pub fn crash(&mut self) {
+        if let Some(Backend::Cached(cache)) = self.backend.as_mut() {
+            cache.crash();
+        }
+    }
+
+    #[must_use]
+    /// `unsynced_writes` returns the number of writes a crash could still lose.
+    pub fn unsynced_writes(&self) -> usize {
+        match self.backend.as_ref() {
+            Some(Backend::Cached(cache)) => cache.unflushed(),
+            _ => 0,
+        }
+    }
+
+    #[must_use]
+    /// `write_cache_stats` returns the counters of the write cache since the image was attached.
+    pub fn write_cache_stats(&self) -> WriteCacheStats {
+        match self.backend.as_ref() {
+            Some(Backend::Cached(cache)) => cache.stats(),
+            _ => WriteCacheStats::default(),
+        }
+    }
+
+    /// `with_cache` puts the configured write cache, if any, in front of `backend`.
+    fn with_cache(&self, backend: Backend) -> Backend {
+        match self.cache {
+            Some(plan) => Backend::Cached(Box::new(WriteCache::new(backend, plan))),
+            None => backend,
+        }
+    }
+
     #[must_use]
     /// `path` returns the filesystem path of the disk image.
     pub fn path(&self) -> &Path {
@@ -103,15 +348,21 @@ impl Disk {
     }
 
     #[must_use]
-    /// `len` returns the disk image length in bytes.
+    /// `len` returns the length of the disk in bytes, without the metadata tail.
     pub const fn len(&self) -> u64 {
         self.len
     }
 
     #[must_use]
-    /// `is_operational` reports whether the disk is open and mapped.
+    /// `io` returns the access method of the disk image.
+    pub const fn io(&self) -> DiskIo {
+        self.io
+    }
+
+    #[must_use]
+    /// `is_operational` reports whether the disk is open and ready for IO.
     pub const fn is_operational(&self) -> bool {
-        self.file.is_some() && self.map.is_some()
+        self.file.is_some() && self.backend.is_some()
     }
 
     #[must_use]
@@ -132,6 +383,58 @@ impl Disk {
             .unwrap_or(true)
     }
 
+    /// `read_metadata` reads bytes of the metadata tail, past the bytes the disk serves.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset within the metadata tail.
+    /// * `buf` - Output buffer to populate.
+    ///
+    /// # Returns
+    /// `false` without reading if the disk has no image attached.
+    ///
+    /// # Errors
+    /// Returns an error if the range leaves the tail or the image cannot be read.
+    pub fn read_metadata(&self, off: u64, buf: &mut [u8]) -> Result<bool> {
+        let Some(file) = self.file.as_ref() else {
+            return Ok(false);
+        };
+        let at = self.metadata_at(off, buf.len())?;
+        std::os::unix::fs::FileExt::read_exact_at(file, buf, at).map_err(Error::io(&self.path))?;
+        Ok(true)
+    }
+
+    /// `write_metadata` writes bytes of the metadata tail and makes them durable.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset within the metadata tail.
+    /// * `data` - Bytes to write.
+    ///
+    /// # Returns
+    /// `false` without writing if the disk has no image attached.
+    ///
+    /// # Errors
+    /// Returns an error if the range leaves the tail or the image cannot be written.
+    pub fn write_metadata(&mut self, off: u64, data: &[u8]) -> Result<bool> {
+        let Some(file) = self.file.as_ref() else {
+            return Ok(false);
+        };
+        let at = self.metadata_at(off, data.len())?;
+        std::os::unix::fs::FileExt::write_all_at(file, data, at)
+            .and_then(|()| file.sync_data())
+            .map_err(Error::io(&self.path))?;
+        Ok(true)
+    }
+
+    /// `metadata_at` returns the image offset of `len` bytes at `off` in the tail.
+    fn metadata_at(&self, off: u64, len: usize) -> Result<u64> {
+        if off.saturating_add(len as u64) > METADATA_BYTES {
+            return Err(Error::Geometry(format!(
+                "{len} bytes at {off} leave the {METADATA_BYTES} byte metadata tail"
+            )));
+        }
+        Ok(self.len + off)
+    }
+
     /// `read_at` reads bytes starting at the given offset into the buffer.
     ///
     /// # Arguments
@@ -141,7 +444,13 @@ impl Disk {
     /// # Returns
     /// The number of bytes copied into `buf`.
     pub fn read_at(&self, off: u64, buf: &mut [u8]) -> usize {
-        let Some(map) = self.map.as_ref() else {
+        let n = self.copy_out(off, buf);
+        self.record_read(buf.len(), n);
+        n
+    }
+
+    fn copy_out(&self, off: u64, buf: &mut [u8]) -> usize {
+        let Some(backend) = self.backend.as_ref() else {
             return 0;
         };
         let Ok(off) = usize::try_from(off) else {
@@ -154,10 +463,42 @@ impl Disk {
             return 0;
         }
         let end = off.saturating_add(buf.len()).min(disk_len);
-        let src = &map[off..end];
-        let n = src.len();
-        buf[..n].copy_from_slice(src);
-        n
+        backend.read(off, &mut buf[..end - off])
+    }
+
+    /// `borrow_at` returns `len` bytes at the given offset without copying them.
+    ///
+    /// Only memory-mapped images can lend their contents; other backends, missing
+    /// disks, and ranges past the end of the image return `None`.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset within the disk image.
+    /// * `len` - Number of bytes to borrow.
+    pub fn borrow_at(&self, off: u64, len: usize) -> Option<&[u8]> {
+        let data = self
+            .backend
+            .as_ref()?
+            .slice(usize::try_from(off).ok()?, len)?;
+        self.record_read(len, len);
+        Some(data)
+    }
+
+    /// `try_read_at` reads exactly `buf.len()` bytes starting at the given offset.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset within the disk image.
+    /// * `buf` - Output buffer to populate.
+    ///
+    /// # Errors
+    /// Returns [`IoError::DiskMissing`] if the disk is not mapped, or
+    /// [`IoError::OutOfRange`] if the range ends past the image.
+    pub fn try_read_at(&self, off: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        if let Err(err) = self.check_io(off, buf.len()) {
+            self.record_read(buf.len(), 0);
+            return Err(err);
+        }
+        self.read_at(off, buf);
+        Ok(())
     }
 
     /// `write_at` writes bytes starting at the given offset from the input slice.
@@ -169,7 +510,13 @@ impl Disk {
     /// # Returns
     /// The number of bytes written from `data`.
     pub fn write_at(&mut self, off: u64, data: &[u8]) -> usize {
-        let Some(map) = self.map.as_mut() else {
+        let n = self.copy_in(off, data);
+        self.record_write(data.len(), n);
+        n
+    }
+
+    fn copy_in(&mut self, off: u64, data: &[u8]) -> usize {
+        let Some(backend) = self.backend.as_mut() else {
             return 0;
         };
         let Ok(off) = usize::try_from(off) else {
@@ -182,10 +529,48 @@ impl Disk {
             return 0;
         }
         let end = off.saturating_add(data.len()).min(disk_len);
-        let dst = &mut map[off..end];
-        let n = dst.len();
-        dst.copy_from_slice(&data[..n]);
+        backend.write(off, &data[..end - off])
+    }
 
-        n
+    /// `try_write_at` writes all of `data` starting at the given offset.
+    ///
+    /// # Arguments
+    /// * `off` - Byte offset within the disk image.
+    /// * `data` - Bytes to write.
+    ///
+    /// # Errors
+    /// Returns [`IoError::DiskMissing`] if the disk is not mapped, or
+    /// [`IoError::OutOfRange`] if the range ends past the image.
+    pub fn try_write_at(&mut self, off: u64, data: &[u8]) -> Result<(), IoError> {
+        if let Err(err) = self.check_io(off, data.len()) {
+            self.record_write(data.len(), 0);
+            return Err(err);
+        }
+        self.write_at(off, data);
+        Ok(())
+    }
+
+    fn check_io(&self, off: u64, len: usize) -> Result<(), IoError> {
+        if self.backend.is_none() {
+            return Err(IoError::DiskMissing {
+                path: self.path.clone(),
+            });
+        }
+        let len = len as u64;
+        if off.checked_add(len).is_none_or(|end| end > self.len) {
+            return Err(IoError::OutOfRange {
+                offset: off,
+                len,
+                limit: self.len,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Disk {
+    /// Closing a disk is a clean shutdown: its write cache is flushed first.
+    fn drop(&mut self) {
+        self.flush();
     }
 }
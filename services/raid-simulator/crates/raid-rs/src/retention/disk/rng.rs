@@ -0,0 +1,23 @@
+//! Seeded random stream shared by the fault models of a disk.
+
+/// `SplitMix64` is a splitmix64 stream, so a seed replays the same faults.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// `new` starts the stream at `seed`.
+    pub(super) const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// `uniform` draws a value in `0.0..1.0`.
+    #[allow(clippy::cast_precision_loss)]
+    pub(super) const fn uniform(&mut self) -> f64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
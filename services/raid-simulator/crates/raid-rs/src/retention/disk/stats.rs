@@ -0,0 +1,63 @@
+//! IO counters kept by every disk image.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::retention::disk::Disk;
+
+/// `DiskStats` counts the IO served by one disk image since it was opened or replaced.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskStats {
+    pub reads: u64,
+    pub writes: u64,
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+    /// Reads that returned fewer bytes than requested.
+    pub read_errors: u64,
+    /// Writes that stored fewer bytes than requested.
+    pub write_errors: u64,
+    /// Unix time in seconds of the most recent read or write error.
+    pub last_error_at: Option<u64>,
+}
+
+impl Disk {
+    #[must_use]
+    /// `stats` returns a snapshot of the IO counters of this disk.
+    pub const fn stats(&self) -> DiskStats {
+        self.stats.get()
+    }
+
+    /// `record_read` accounts a read of `requested` bytes that returned `done` bytes.
+    pub(super) fn record_read(&self, requested: usize, done: usize) {
+        let mut stats = self.stats.get();
+        stats.reads += 1;
+        stats.bytes_read += done as u64;
+        if done < requested {
+            stats.read_errors += 1;
+            stats.last_error_at = Some(now_secs());
+        }
+        self.stats.set(stats);
+        self.record_wear(done);
+    }
+
+    /// `record_write` accounts a write of `requested` bytes that stored `done` bytes.
+    pub(super) fn record_write(&self, requested: usize, done: usize) {
+        let mut stats = self.stats.get();
+        stats.writes += 1;
+        stats.bytes_written += done as u64;
+        if done < requested {
+            stats.write_errors += 1;
+            stats.last_error_at = Some(now_secs());
+        }
+        self.stats.set(stats);
+        self.record_wear(done);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,171 @@
+//! `io_uring` access to a disk image.
+//!
+//! Each request is split into segments that are queued together and submitted with
+//! a single system call, so large stripes cost one round trip instead of one per
+//! segment.
+
+use std::cell::{Cell, RefCell};
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+
+use io_uring::{IoUring, opcode, squeue, types};
+
+/// Number of submission queue entries of each ring.
+const QUEUE_DEPTH: u32 = 64;
+/// Largest transfer carried by one submission queue entry.
+const SEGMENT_BYTES: usize = 64 * 1024;
+
+/// `Ring` owns the `io_uring` instance used by one disk.
+pub(super) struct Ring {
+    ring: RefCell<IoUring>,
+    fd: types::Fd,
+    /// `user_data` of the next queued entry, never reused while the ring lives.
+    next_tag: Cell<u64>,
+    /// Set once an entry may be stranded in the submission queue; the ring is
+    /// then never entered again so the entry cannot reach a freed buffer.
+    broken: Cell<bool>,
+}
+
+impl Ring {
+    /// `new` creates a ring for IO on `file`, which must outlive the ring.
+    pub(super) fn new(file: &File) -> io::Result<Self> {
+        Ok(Self {
+            ring: RefCell::new(IoUring::new(QUEUE_DEPTH)?),
+            fd: types::Fd(file.as_raw_fd()),
+            next_tag: Cell::new(0),
+            broken: Cell::new(false),
+        })
+    }
+
+    /// `read` fills `buf` from `off` and returns the length of the prefix that was read.
+    pub(super) fn read(&self, off: u64, buf: &mut [u8]) -> usize {
+        let entries: Vec<_> = buf
+            .chunks_mut(SEGMENT_BYTES)
+            .scan(off, |pos, seg| {
+                let at = *pos;
+                *pos += seg.len() as u64;
+                let len = u32::try_from(seg.len()).unwrap_or(u32::MAX);
+                Some((
+                    opcode::Read::new(self.fd, seg.as_mut_ptr(), len)
+                        .offset(at)
+                        .build(),
+                    seg.len(),
+                ))
+            })
+            .collect();
+        self.submit(&entries)
+    }
+
+    /// `write` stores `data` at `off` and returns the length of the prefix that was written.
+    pub(super) fn write(&self, off: u64, data: &[u8]) -> usize {
+        let entries: Vec<_> = data
+            .chunks(SEGMENT_BYTES)
+            .scan(off, |pos, seg| {
+                let at = *pos;
+                *pos += seg.len() as u64;
+                let len = u32::try_from(seg.len()).unwrap_or(u32::MAX);
+                Some((
+                    opcode::Write::new(self.fd, seg.as_ptr(), len)
+                        .offset(at)
+                        .build(),
+                    seg.len(),
+                ))
+            })
+            .collect();
+        self.submit(&entries)
+    }
+
+    /// `submit` queues the entries in batches of the queue depth and waits for them.
+    ///
+    /// The buffers behind the entries stay borrowed by the caller until every
+    /// completion of a batch has been reaped, so a batch is drained even when
+    /// queueing or submitting fails part way.
+    fn submit(&self, entries: &[(squeue::Entry, usize)]) -> usize {
+        let mut done = vec![0usize; entries.len()];
+        if self.broken.get() {
+            return 0;
+        }
+        let mut ring = self.ring.borrow_mut();
+        let base = self.next_tag.get();
+        self.next_tag.set(base.wrapping_add(entries.len() as u64));
+        let depth = QUEUE_DEPTH as usize;
+        for (batch_no, batch) in entries.chunks(depth).enumerate() {
+            let first = batch_no * depth;
+            let mut queued = 0;
+            for (i, (entry, _)) in batch.iter().enumerate() {
+                let entry = entry
+                    .clone()
+                    .user_data(base.wrapping_add((first + i) as u64));
+                if unsafe { ring.submission().push(&entry) }.is_err() {
+                    break;
+                }
+                queued += 1;
+            }
+            if !self.drain(&mut ring, queued, base, &mut done) || queued < batch.len() {
+                break;
+            }
+        }
+        prefix_len(entries, &done)
+    }
+
+    /// `drain` submits `queued` entries and reaps completions until none of them
+    /// is in flight, recording each result in `done` by its tag relative to `base`.
+    ///
+    /// Interrupted and busy submissions are retried. Any other failure breaks the
+    /// ring, after which completions of entries the kernel already took are still
+    /// awaited.
+    ///
+    /// # Returns
+    /// Whether every queued entry completed.
+    fn drain(&self, ring: &mut IoUring, queued: usize, base: u64, done: &mut [usize]) -> bool {
+        let mut unsubmitted = queued;
+        let mut in_flight = 0;
+        loop {
+            for cqe in ring.completion() {
+                let slot = cqe.user_data().wrapping_sub(base);
+                if let Some(n) = usize::try_from(slot).ok().and_then(|s| done.get_mut(s)) {
+                    *n = usize::try_from(cqe.result()).unwrap_or(0);
+                    in_flight -= 1;
+                }
+            }
+            if self.broken.get() {
+                if in_flight == 0 {
+                    return false;
+                }
+                std::thread::yield_now();
+                continue;
+            }
+            if unsubmitted + in_flight == 0 {
+                return true;
+            }
+            match ring.submit_and_wait(unsubmitted + in_flight) {
+                Ok(n) => {
+                    let n = n.min(unsubmitted);
+                    unsubmitted -= n;
+                    in_flight += n;
+                }
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::Interrupted
+                            | io::ErrorKind::WouldBlock
+                            | io::ErrorKind::ResourceBusy
+                    ) => {}
+                Err(_) => self.broken.set(true),
+            }
+        }
+    }
+}
+
+/// `prefix_len` sums completed segments up to and including the first short one.
+fn prefix_len(entries: &[(squeue::Entry, usize)], done: &[usize]) -> usize {
+    let mut total = 0;
+    for ((_, want), &got) in entries.iter().zip(done) {
+        total += got.min(*want);
+        if got < *want {
+            break;
+        }
+    }
+    total
+}
@@ -0,0 +1,36 @@
+//! Typed failures reported by the fallible disk and volume IO paths.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// `IoError` describes why a read or write could not be served in full.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IoError {
+    /// The request reaches past the end of the disk or volume.
+    OutOfRange { offset: u64, len: u64, limit: u64 },
+    /// A member disk needed for the request is not attached.
+    DiskMissing { path: PathBuf },
+    /// Too many members are unavailable to reconstruct the stripe.
+    Uncorrectable { stripe_index: u64 },
+    /// The volume refuses writes because of its degraded policy or lost stripes.
+    ReadOnly,
+}
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange { offset, len, limit } => write!(
+                f,
+                "range {offset}..{} is outside the first {limit} bytes",
+                offset.saturating_add(*len)
+            ),
+            Self::DiskMissing { path } => write!(f, "disk {} is missing", path.display()),
+            Self::Uncorrectable { stripe_index } => {
+                write!(f, "stripe {stripe_index} cannot be reconstructed")
+            }
+            Self::ReadOnly => f.write_str("volume is read-only"),
+        }
+    }
+}
+
+impl std::error::Error for IoError {}
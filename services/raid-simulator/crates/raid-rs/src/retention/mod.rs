@@ -2,4 +2,7 @@
 
 pub mod array;
 pub mod disk;
+mod io_error;
 pub mod volume;
+
+pub use io_error::IoError;
@@ -0,0 +1,340 @@
+//! Background balance that restripes a grown array onto all of its members.
+//!
+//! Appending members changes where every logical byte lives, so the data written
+//! under the old geometry has to be moved before the new members carry their
+//! share. Instead of rewriting the whole array before the first mount, a balance
+//! keeps the old geometry open as a source volume and moves it forward one batch
+//! of stripes at a time. Logical bytes before the balance position live in the
+//! new geometry and are served by the volume itself; bytes at or after it are
+//! still read from and written to the source.
+//!
+//! Moving whole new stripes in increasing order only overwrites disk offsets the
+//! source has already handed over, so no data is lost between batches. The
+//! position is saved next to the disk images after every batch, so a balance
+//! resumes where it stopped on the next mount. Until it finishes, the volume
+//! keeps the capacity of the source; the stripes past it are zeroed as part of
+//! the balance so the added capacity starts out empty.
+
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::{Access, DynVolume, Volume};
+use crate::{Error, Result};
+
+/// `BALANCE_FILE_NAME` is the file next to the disk images holding the position of
+/// an unfinished balance.
+pub const BALANCE_FILE_NAME: &str = "balance.checkpoint";
+const BALANCE_MAGIC: [u8; 8] = *b"RAIDBAL1";
+const BALANCE_LEN: usize = 24;
+
+/// `BalanceCheckpoint` records how far the balance of a grown array has come.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceCheckpoint {
+    /// Member count of the geometry the data is moved from.
+    pub source_disks: usize,
+    /// Logical bytes already moved to the new geometry.
+    pub position: u64,
+}
+
+impl BalanceCheckpoint {
+    /// `load` reads the checkpoint of an unfinished balance stored in `disk_dir`.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint exists but cannot be read or is malformed.
+    pub fn load(disk_dir: &Path) -> Result<Option<Self>> {
+        let path = disk_dir.join(BALANCE_FILE_NAME);
+        let raw = match std::fs::read(&path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::io(&path)(err)),
+        };
+        if raw.len() != BALANCE_LEN || raw[0..8] != BALANCE_MAGIC {
+            return Err(Error::Corrupt(format!(
+                "malformed balance checkpoint in {}",
+                disk_dir.display()
+            )));
+        }
+        let word = |at: usize| raw[at..at + 8].try_into().map_or(0, u64::from_le_bytes);
+        Ok(Some(Self {
+            source_disks: usize::try_from(word(8))?,
+            position: word(16),
+        }))
+    }
+
+    /// `save` stores the checkpoint in `disk_dir`, replacing any previous one.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint cannot be written.
+    pub fn save(&self, disk_dir: &Path) -> Result<()> {
+        let mut raw = [0u8; BALANCE_LEN];
+        raw[0..8].copy_from_slice(&BALANCE_MAGIC);
+        raw[8..16].copy_from_slice(&(self.source_disks as u64).to_le_bytes());
+        raw[16..24].copy_from_slice(&self.position.to_le_bytes());
+        let path = disk_dir.join(BALANCE_FILE_NAME);
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, raw).map_err(Error::io(&tmp))?;
+        std::fs::rename(&tmp, &path).map_err(Error::io(&path))?;
+        Ok(())
+    }
+}
+
+/// `BalanceProgress` reports how much of the data a running balance has moved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceProgress {
+    pub moved_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl BalanceProgress {
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// `fraction` returns the share of the data moved so far, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            self.moved_bytes as f64 / self.total_bytes as f64
+        }
+    }
+}
+
+/// `Balance` is the state of a running balance.
+pub(super) struct Balance {
+    source: Box<dyn DynVolume>,
+    source_disks: usize,
+    position: u64,
+    /// Capacity of the source.
+    source_end: u64,
+    /// Capacity of this volume once the balance finishes.
+    end: u64,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `balance_dir` returns the directory holding the disk images.
+    pub(super) fn balance_dir(&self) -> Option<PathBuf> {
+        self.array
+            .0
+            .first()
+            .and_then(|disk| disk.path().parent().map(Path::to_path_buf))
+    }
+
+    /// `begin_balance` starts moving the data of `source` onto this volume.
+    ///
+    /// `source` is the array before it grew, opened on its original members. Bytes
+    /// before `position` must already be in this volume's geometry.
+    ///
+    /// # Arguments
+    /// * `source` - Volume with the geometry the data is moved from.
+    /// * `source_disks` - Member count of `source`, recorded in the checkpoint.
+    /// * `position` - Logical bytes already moved, a multiple of the stripe size.
+    ///
+    /// # Errors
+    /// Returns an error if a balance is already running, the volume is thin,
+    /// compressed or has snapshots, `source` does not fit into the volume or
+    /// `position` is not stripe aligned, or the checkpoint cannot be written.
+    pub fn begin_balance(
+        &mut self,
+        source: Box<dyn DynVolume>,
+        source_disks: usize,
+        position: u64,
+    ) -> Result<()> {
+        if self.balance.is_some() || self.reshape.is_some() {
+            return Err(Error::Invalid(
+                "a balance or reshape is already running".to_string(),
+            ));
+        }
+        if self.thin.is_some() || self.compression.is_some() || self.snapshots.is_some() {
+            return Err(Error::Invalid(
+                "thin, compressed and snapshotted volumes cannot be balanced".to_string(),
+            ));
+        }
+        let source_end = source.logical_capacity_bytes();
+        let end = self.raw_capacity_bytes();
+        if source_end > end {
+            return Err(Error::Geometry(format!(
+                "source of {source_end} bytes does not fit into {end} bytes"
+            )));
+        }
+        if !position.is_multiple_of(self.geom.bytes_per_stripe as u64) {
+            return Err(Error::Geometry(format!(
+                "balance position {position} is not stripe aligned"
+            )));
+        }
+        self.balance = Some(Balance {
+            source,
+            source_disks,
+            position: position.min(end),
+            source_end,
+            end,
+        });
+        self.save_balance()
+    }
+
+    /// `is_balancing` reports whether data is still being moved from a source volume.
+    pub const fn is_balancing(&self) -> bool {
+        self.balance.is_some()
+    }
+
+    /// `balance_progress` returns how much data a running balance has moved.
+    pub fn balance_progress(&self) -> Option<BalanceProgress> {
+        self.balance.as_ref().map(|balance| BalanceProgress {
+            moved_bytes: balance.position,
+            total_bytes: balance.end,
+        })
+    }
+
+    /// `balance_step` moves up to `max_stripes` stripes from the source volume.
+    ///
+    /// The moved stripes count as background IO. Once everything is moved the
+    /// source is released, the checkpoint is removed and the volume reports its
+    /// full capacity.
+    ///
+    /// # Returns
+    /// The number of stripes moved; 0 once no balance is running.
+    ///
+    /// # Errors
+    /// Returns an error if the stripes cannot be written or the checkpoint cannot
+    /// be updated. The position does not advance in that case.
+    pub fn balance_step(&mut self, max_stripes: usize) -> Result<usize> {
+        let Some(balance) = self.balance.as_mut() else {
+            return Ok(0);
+        };
+        let start = Instant::now();
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let position = balance.position;
+        let len = (max_stripes.max(1) as u64 * stripe_bytes).min(balance.end - position);
+        let mut buf = vec![0u8; usize::try_from(len)?];
+        let from_source = usize::try_from(len.min(balance.source_end.saturating_sub(position)))?;
+        if from_source > 0 {
+            balance.source.read_bytes(position, &mut buf[..from_source]);
+        }
+        self.write_logical(position, &buf, Access::Live)?;
+
+        let stripes = usize::try_from(len.div_ceil(stripe_bytes))?;
+        self.record_background(stripes, start);
+        let Some(balance) = self.balance.as_mut() else {
+            return Ok(stripes);
+        };
+        balance.position += len;
+        if balance.position < balance.end {
+            self.save_balance()?;
+        } else {
+            self.balance = None;
+            if let Some(dir) = self.balance_dir() {
+                let path = dir.join(BALANCE_FILE_NAME);
+                match std::fs::remove_file(&path) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(Error::io(&path)(err));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(stripes)
+    }
+
+    fn save_balance(&self) -> Result<()> {
+        let (Some(balance), Some(dir)) = (self.balance.as_ref(), self.balance_dir()) else {
+            return Ok(());
+        };
+        BalanceCheckpoint {
+            source_disks: balance.source_disks,
+            position: balance.position,
+        }
+        .save(&dir)
+    }
+
+    /// `balance_capacity` returns the capacity of the source while a balance runs.
+    pub(super) fn balance_capacity(&self) -> Option<u64> {
+        self.balance.as_ref().map(|balance| balance.source_end)
+    }
+
+    /// `write_balanced` writes bytes before the balance position to this volume and
+    /// the rest to the source.
+    pub(super) fn write_balanced(
+        &mut self,
+        byte_offset: u64,
+        payload: &[u8],
+        strict: bool,
+    ) -> Result<()> {
+        let Some(mut balance) = self.balance.take() else {
+            return self.write_bytes_checked(byte_offset, payload, strict);
+        };
+        let head = split_at(balance.position, byte_offset, payload.len());
+        let (front, back) = payload.split_at(head);
+        let tail_offset = byte_offset + head as u64;
+        let result = check_end(balance.source_end, byte_offset, payload.len(), strict)
+            .and_then(|()| {
+                if front.is_empty() {
+                    return Ok(());
+                }
+                self.write_bytes_checked(byte_offset, front, strict)
+            })
+            .and_then(|()| {
+                if back.is_empty() {
+                    Ok(())
+                } else if strict {
+                    balance.source.try_write_bytes(tail_offset, back)
+                } else {
+                    balance.source.write_bytes(tail_offset, back);
+                    Ok(())
+                }
+            });
+        self.balance = Some(balance);
+        result
+    }
+
+    /// `read_balanced` reads bytes before the balance position from this volume and
+    /// the rest from the source.
+    pub(super) fn read_balanced(
+        &mut self,
+        byte_offset: u64,
+        out: &mut [u8],
+        strict: bool,
+    ) -> Result<()> {
+        let Some(mut balance) = self.balance.take() else {
+            return self.read_bytes_checked(byte_offset, out, strict);
+        };
+        let head = split_at(balance.position, byte_offset, out.len());
+        let len = out.len();
+        let (front, back) = out.split_at_mut(head);
+        let tail_offset = byte_offset + head as u64;
+        let result = check_end(balance.source_end, byte_offset, len, strict)
+            .and_then(|()| {
+                if front.is_empty() {
+                    return Ok(());
+                }
+                self.read_bytes_checked(byte_offset, front, strict)
+            })
+            .and_then(|()| {
+                if back.is_empty() {
+                    Ok(())
+                } else if strict {
+                    balance.source.try_read_bytes(tail_offset, back)
+                } else {
+                    balance.source.read_bytes(tail_offset, back);
+                    Ok(())
+                }
+            });
+        self.balance = Some(balance);
+        result
+    }
+}
+
+/// `split_at` returns how many of the `len` bytes at `byte_offset` lie before `position`.
+pub(super) fn split_at(position: u64, byte_offset: u64, len: usize) -> usize {
+    usize::try_from(position.saturating_sub(byte_offset)).map_or(len, |head| head.min(len))
+}
+
+/// `check_end` rejects strict requests reaching past the capacity served so far.
+pub(super) fn check_end(end: u64, byte_offset: u64, len: usize, strict: bool) -> Result<()> {
+    if strict && byte_offset.saturating_add(len as u64) > end {
+        return Err(Error::Geometry(format!(
+            "{len} bytes at {byte_offset} reach past the {end} bytes available"
+        )));
+    }
+    Ok(())
+}
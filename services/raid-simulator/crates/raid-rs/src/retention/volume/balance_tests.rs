@@ -0,0 +1,123 @@
+use super::test_utils::fresh_volume;
+use super::*;
+use crate::layout::stripe::raid0::RAID0;
+use tempfile::TempDir;
+
+const CHUNK_SIZE: usize = 64;
+const DISK_LEN: u64 = 1024;
+const OLD_DISKS: usize = 2;
+const NEW_DISKS: usize = 3;
+
+type OldVolume = Volume<OLD_DISKS, CHUNK_SIZE, RAID0<OLD_DISKS, CHUNK_SIZE>>;
+type NewVolume = Volume<NEW_DISKS, CHUNK_SIZE, RAID0<NEW_DISKS, CHUNK_SIZE>>;
+
+fn old_volume(dir: &TempDir) -> OldVolume {
+    fresh_volume(dir, DISK_LEN, RAID0::zero())
+}
+
+fn new_volume(dir: &TempDir) -> NewVolume {
+    fresh_volume(dir, DISK_LEN, RAID0::zero())
+}
+
+fn pattern(len: u64) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+fn read<const D: usize, T: Stripe<D, CHUNK_SIZE>>(
+    volume: &mut Volume<D, CHUNK_SIZE, T>,
+    offset: u64,
+    len: usize,
+) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.try_read_bytes(offset, &mut out).expect("read");
+    out
+}
+
+/// `grown_volume` fills an old array and opens it grown by one member, balancing.
+fn grown_volume(dir: &TempDir, position: u64) -> (NewVolume, Vec<u8>) {
+    let mut old = old_volume(dir);
+    let data = pattern(old.logical_capacity_bytes());
+    old.write_bytes(0, &data);
+    let mut volume = new_volume(dir);
+    volume
+        .begin_balance(Box::new(old), OLD_DISKS, position)
+        .expect("begin balance");
+    (volume, data)
+}
+
+#[test]
+fn balance_moves_data_while_io_follows_the_position() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, mut data) = grown_volume(&dir, 0);
+    let old_capacity = data.len() as u64;
+    assert!(volume.is_balancing());
+    assert_eq!(volume.logical_capacity_bytes(), old_capacity);
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+
+    assert_eq!(volume.balance_step(2).expect("step"), 2);
+    let moved = volume.balance_progress().expect("progress").moved_bytes;
+    assert_eq!(moved, 2 * 192);
+    volume
+        .try_write_bytes(moved - 4, b"straddle")
+        .expect("write");
+    data[usize::try_from(moved).unwrap() - 4..][..8].copy_from_slice(b"straddle");
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    assert!(volume.try_write_bytes(old_capacity, b"x").is_err());
+
+    while volume.balance_step(3).expect("step") > 0 {}
+    assert!(!volume.is_balancing());
+    assert!(!dir.path().join(BALANCE_FILE_NAME).exists());
+    assert_eq!(volume.logical_capacity_bytes(), 3 * DISK_LEN);
+    drop(volume);
+
+    let mut volume = new_volume(&dir);
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    let tail = usize::try_from(3 * DISK_LEN - old_capacity).unwrap();
+    assert!(
+        read(&mut volume, old_capacity, tail)
+            .iter()
+            .all(|&b| b == 0)
+    );
+}
+
+#[test]
+fn balance_resumes_from_its_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, data) = grown_volume(&dir, 0);
+    volume.balance_step(4).expect("step");
+    drop(volume);
+
+    let checkpoint = BalanceCheckpoint::load(dir.path())
+        .expect("load")
+        .expect("checkpoint");
+    assert_eq!(checkpoint.source_disks, OLD_DISKS);
+    assert_eq!(checkpoint.position, 4 * 192);
+
+    let mut volume = new_volume(&dir);
+    volume
+        .begin_balance(Box::new(old_volume(&dir)), OLD_DISKS, checkpoint.position)
+        .expect("resume");
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    while volume.balance_step(64).expect("step") > 0 {}
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    assert_eq!(BalanceCheckpoint::load(dir.path()).expect("load"), None);
+}
+
+#[test]
+fn begin_balance_rejects_misaligned_positions_and_running_balances() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, _) = grown_volume(&dir, 0);
+    assert!(
+        volume
+            .begin_balance(Box::new(old_volume(&dir)), OLD_DISKS, 0)
+            .is_err()
+    );
+    assert!(volume.init_snapshots(192, 0).is_err());
+
+    let mut fresh = new_volume(&dir);
+    assert!(
+        fresh
+            .begin_balance(Box::new(old_volume(&dir)), OLD_DISKS, 100)
+            .is_err()
+    );
+}
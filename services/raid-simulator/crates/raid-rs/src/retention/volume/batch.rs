@@ -0,0 +1,336 @@
+//! Bulk IO over ranges of whole stripes.
+//!
+//! Byte IO loads and stores one stripe per disk operation. The batch API groups a
+//! stripe range into runs that are stored back to back on the members, at most
+//! `BATCH_STRIPES` long, and moves each run with a single read or write per disk.
+//! Whole stripes are written, so writes skip the read-modify-write of byte IO.
+
+use std::ops::Range;
+use std::time::Instant;
+
+use tracing::trace_span;
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::metrics::{IO_TRACE_TARGET, IoClass, IoOpType, RaidOp};
+use crate::retention::volume::mapper::stripe_byte_offset;
+use crate::retention::volume::{Access, Volume};
+use crate::{Error, Result};
+
+/// `BATCH_STRIPES` caps the stripes moved by a single operation on each disk.
+pub const BATCH_STRIPES: usize = 64;
+
+/// `Run` is a stretch of logically consecutive stripes stored back to back.
+struct Run {
+    /// First physical stripe; `None` for unallocated thin stripes.
+    physical: Option<u64>,
+    count: usize,
+}
+
+impl Run {
+    /// `next` returns the physical stripe that would extend the run.
+    fn next(&self) -> Option<u64> {
+        self.physical.map(|p| p + self.count as u64)
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `read_stripes` reads the data of a range of logical stripes.
+    ///
+    /// # Arguments
+    /// * `stripes` - Logical stripe indices to read.
+    ///
+    /// # Returns
+    /// `bytes_per_stripe` bytes per stripe; unallocated thin stripes read as zeros.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if the range is outside the volume, or
+    /// [`Error::Degraded`] if too many members are unavailable to reconstruct it.
+    pub fn read_stripes(&mut self, stripes: Range<u64>) -> Result<Vec<u8>> {
+        let start = crate::metrics::is_enabled().then(Instant::now);
+
+        let (byte_offset, len) = self.stripe_span(&stripes);
+        if self.balance.is_some() || self.reshape.is_some() {
+            let mut out = vec![0u8; len];
+            self.try_read_bytes(byte_offset, &mut out)?;
+            return Ok(out);
+        }
+        let degraded = self.is_degraded();
+        if degraded {
+            self.record_uncorrectable(byte_offset, len);
+        }
+        let mut out = Vec::new();
+        let result = self
+            .check_io(byte_offset, len, IoOpType::Read)
+            .map_err(Into::into)
+            .and_then(|()| {
+                out.resize(len, 0);
+                if self.compression.is_some() {
+                    return self.read_compressed(byte_offset, &mut out);
+                }
+                self.read_runs(stripes, &mut out)
+            });
+        if result.is_ok() {
+            self.record_foreground(len);
+        }
+
+        record_batch_op(start, IoOpType::Read, len, result.is_err(), degraded);
+        result?;
+        Ok(out)
+    }
+
+    /// `write_stripes` overwrites a range of logical stripes with new data.
+    ///
+    /// Thin stripes are allocated and snapshots preserve the old contents, as with
+    /// `try_write_bytes`.
+    ///
+    /// # Arguments
+    /// * `stripes` - Logical stripe indices to write.
+    /// * `data` - Exactly `bytes_per_stripe` bytes per stripe.
+    ///
+    /// # Errors
+    /// Returns an error if `data` does not fill the range, and otherwise fails like
+    /// `try_write_bytes`. Nothing is written in either case.
+    pub fn write_stripes(&mut self, stripes: Range<u64>, data: &[u8]) -> Result<()> {
+        let start = crate::metrics::is_enabled().then(Instant::now);
+
+        let (byte_offset, len) = self.stripe_span(&stripes);
+        if (self.balance.is_some() || self.reshape.is_some()) && data.len() == len {
+            return self.try_write_bytes(byte_offset, data);
+        }
+        let degraded = self.is_degraded();
+        let result = if data.len() == len {
+            self.check_io(byte_offset, len, IoOpType::Write)
+                .map_err(Into::into)
+                .and_then(|()| self.thin_reserve(byte_offset, len))
+        } else {
+            Err(Error::Geometry(format!(
+                "{} bytes do not fill stripes {}..{} ({len} bytes)",
+                data.len(),
+                stripes.start,
+                stripes.end
+            )))
+        };
+        let result = result.and_then(|()| {
+            if self.compression.is_some() {
+                return self.write_compressed(byte_offset, data);
+            }
+            self.write_runs(stripes, data, degraded)
+        });
+        if result.is_ok() {
+            self.record_foreground(len);
+        }
+
+        record_batch_op(
+            start,
+            IoOpType::Write,
+            data.len(),
+            result.is_err(),
+            degraded,
+        );
+        result
+    }
+
+    /// `repair_stripes` forces reads of many stripes to rebuild missing data.
+    ///
+    /// Consecutive stripes are read together; every stripe is reconstructed and
+    /// written back as `repair_stripe` would. The work counts as background IO.
+    ///
+    /// # Arguments
+    /// * `stripes` - Physical indices of the stripes to repair.
+    pub fn repair_stripes(&mut self, stripes: &[u64]) {
+        let mut rest = stripes;
+        while let Some(&first) = rest.first() {
+            let count = rest
+                .iter()
+                .zip(first..)
+                .take(BATCH_STRIPES)
+                .take_while(|&(&s, expected)| s == expected)
+                .count();
+            let _span = trace_span!(
+                target: IO_TRACE_TARGET,
+                "stripe_batch_read",
+                first_stripe = first,
+                stripes = count
+            )
+            .entered();
+            let start = Instant::now();
+            let sources: Vec<Vec<usize>> = (first..first + count as u64)
+                .map(|s| self.rebuild_sources(s))
+                .collect();
+            self.array.read_span(
+                stripe_byte_offset::<N>(first),
+                count,
+                &mut self.layout,
+                |_, _| {},
+            );
+            if let Some(rebuild) = self.rebuild.as_mut() {
+                for s in &sources {
+                    rebuild.advance(s);
+                }
+            }
+            self.record_background(count, start);
+            rest = &rest[count..];
+        }
+    }
+
+    /// `stripe_span` converts a stripe range to its logical byte offset and length.
+    fn stripe_span(&self, stripes: &Range<u64>) -> (u64, usize) {
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let count = stripes.end.saturating_sub(stripes.start);
+        let len = usize::try_from(count.saturating_mul(stripe_bytes)).unwrap_or(usize::MAX);
+        (stripes.start.saturating_mul(stripe_bytes), len)
+    }
+
+    /// `runs` splits a logical stripe range into runs of at most `BATCH_STRIPES`.
+    fn runs(&self, stripes: Range<u64>, access: Access) -> Vec<Run> {
+        let mut runs: Vec<Run> = Vec::new();
+        for stripe_index in stripes {
+            let physical = self.physical_stripe(stripe_index, access);
+            match runs.last_mut() {
+                Some(run) if run.count < BATCH_STRIPES && run.next() == physical => {
+                    run.count += 1;
+                }
+                _ => runs.push(Run { physical, count: 1 }),
+            }
+        }
+        runs
+    }
+
+    /// `read_runs` decodes every stripe of the range into `out`.
+    fn read_runs(&mut self, stripes: Range<u64>, out: &mut [u8]) -> Result<()> {
+        let stripe_bytes = self.geom.bytes_per_stripe;
+        let mut data_chunks = vec![Bits::<N>::zero(); T::DATA];
+        let mut decoded = Ok(());
+        let mut pos = 0;
+        for run in self.runs(stripes, Access::Live) {
+            let span = &mut out[pos..pos + run.count * stripe_bytes];
+            pos += span.len();
+            let Some(first) = run.physical else {
+                continue;
+            };
+            let _span = trace_span!(
+                target: IO_TRACE_TARGET,
+                "stripe_batch_read",
+                first_stripe = first,
+                stripes = run.count
+            )
+            .entered();
+            self.array.read_span(
+                stripe_byte_offset::<N>(first),
+                run.count,
+                &mut self.layout,
+                |k, layout| {
+                    if decoded.is_err() {
+                        return;
+                    }
+                    decoded = layout.try_read(&mut data_chunks);
+                    let dst = &mut span[k * stripe_bytes..(k + 1) * stripe_bytes];
+                    for (chunk, bytes) in data_chunks.iter().zip(dst.chunks_exact_mut(N)) {
+                        bytes.copy_from_slice(chunk.as_bytes());
+                    }
+                },
+            );
+        }
+        decoded
+    }
+
+    /// `write_runs` encodes and stores every stripe of the range from `data`.
+    ///
+    /// Runs are cut short at a pending simulated crash, so the crash still lands
+    /// on the same stripe boundary as with one write per stripe, and tears that
+    /// stripe when the crash plan asks for a write hole.
+    fn write_runs(&mut self, stripes: Range<u64>, data: &[u8], degraded: bool) -> Result<()> {
+        for stripe_index in stripes.clone() {
+            self.preserve_stripe(stripe_index)?;
+        }
+        let mut pending = data.chunks_exact(self.geom.bytes_per_stripe);
+        for run in self.runs(stripes, Access::Live) {
+            let Some(first) = run.physical else {
+                pending.by_ref().take(run.count).for_each(drop);
+                continue;
+            };
+            let mut done = 0;
+            while done < run.count {
+                let take = self.crash_budget().min(run.count - done);
+                let from = first + done as u64;
+                let raw: Vec<[Bits<N>; D]> = pending
+                    .by_ref()
+                    .take(take)
+                    .zip(from..)
+                    .map(|(stripe, physical)| self.encode_stripe(physical, stripe))
+                    .collect::<Result<_>>()?;
+                let _span = trace_span!(
+                    target: IO_TRACE_TARGET,
+                    "stripe_batch_write",
+                    first_stripe = from,
+                    stripes = take
+                )
+                .entered();
+                if degraded {
+                    (from..from + take as u64).for_each(|s| self.intent_mark(s));
+                }
+                for (s, stripe) in (from..).zip(&raw) {
+                    self.journal_raw(s, stripe)?;
+                    self.log_partial_parity(s, 0..T::DATA, &[])?;
+                }
+                if self.tears_next_write() {
+                    self.write_torn(from, &raw[0]);
+                    done += take;
+                    continue;
+                }
+                self.array.write_span(stripe_byte_offset::<N>(from), &raw);
+                for s in from..from + take as u64 {
+                    self.read_ahead_invalidate(s);
+                    self.parity_cache_invalidate(s);
+                    self.count_stripe_write();
+                }
+                done += take;
+            }
+        }
+        self.journal_settle();
+        Ok(())
+    }
+
+    /// `encode_stripe` returns the raw chunks of one stripe of data.
+    fn encode_stripe(&mut self, physical: u64, data: &[u8]) -> Result<[Bits<N>; D]> {
+        let chunks: Vec<Bits<N>> = data
+            .chunks_exact(N)
+            .map(|bytes| Bits(bytes.try_into().unwrap_or([0; N])))
+            .collect();
+        self.layout.place(physical);
+        self.layout.try_write(&chunks)?;
+        let mut raw = [Bits::zero(); D];
+        self.layout.try_read_raw(&mut raw)?;
+        Ok(raw)
+    }
+
+    /// `crash_budget` returns how many stripes may be written before a simulated crash.
+    fn crash_budget(&self) -> usize {
+        // A torn stripe is written on its own, after the stripes before it.
+        let torn = usize::from(self.write_hole.tear);
+        self.crash_after.map_or(BATCH_STRIPES, |left| {
+            usize::try_from(left).map_or(BATCH_STRIPES, |left| {
+                left.saturating_sub(torn).clamp(1, BATCH_STRIPES)
+            })
+        })
+    }
+}
+
+/// `record_batch_op` records a batch request as a single RAID operation.
+fn record_batch_op(start: Option<Instant>, op: IoOpType, len: usize, error: bool, degraded: bool) {
+    if let Some(start) = start {
+        crate::metrics::record_raid_op(RaidOp {
+            op,
+            bytes: u64::try_from(len).unwrap_or(u64::MAX),
+            latency_seconds: start.elapsed().as_secs_f64(),
+            error,
+            degraded,
+            read_ahead_hits: 0,
+            read_ahead_misses: 0,
+            parity_cache_hits: 0,
+            parity_cache_misses: 0,
+            class: IoClass::Foreground,
+        });
+    }
+}
@@ -0,0 +1,93 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+/// Long enough for runs past `BATCH_STRIPES`.
+const DISK_LEN: u64 = 1024;
+/// Logical bytes per RAID3 stripe: two data chunks.
+const STRIPE: usize = 2 * CHUNK_SIZE;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7).to_le_bytes()[0]).collect()
+}
+
+#[test]
+fn write_stripes_matches_byte_io() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let stripes = BATCH_STRIPES as u64 + 10;
+    let data = pattern(STRIPE * (BATCH_STRIPES + 10));
+
+    volume.write_stripes(3..3 + stripes, &data).unwrap();
+
+    let mut out = vec![0; data.len()];
+    volume.read_bytes(3 * STRIPE as u64, &mut out);
+    assert_eq!(out, data);
+    assert_eq!(volume.read_stripes(3..3 + stripes).unwrap(), data);
+    assert!(volume.check().is_clean());
+}
+
+#[test]
+fn read_stripes_reconstructs_missing_member() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let data = pattern(STRIPE * 16);
+    volume.write_bytes(0, &data);
+
+    volume.fail_disk(0).unwrap();
+
+    assert_eq!(volume.read_stripes(0..16).unwrap(), data);
+}
+
+#[test]
+fn write_stripes_rejects_short_data_and_bad_ranges() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let end = volume.stripes_needed_for_logical_end(volume.logical_capacity_bytes());
+
+    assert!(volume.write_stripes(0..2, &[1; STRIPE]).is_err());
+    assert!(volume.write_stripes(end..end + 1, &[1; STRIPE]).is_err());
+    assert!(volume.read_stripes(end..end + 1).is_err());
+    assert_eq!(volume.read_stripes(0..2).unwrap(), vec![0; 2 * STRIPE]);
+}
+
+#[test]
+fn repair_stripes_rebuilds_replaced_member() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let data = pattern(STRIPE * 100);
+    volume.write_bytes(0, &data);
+
+    volume.fail_disk(2).unwrap();
+    volume.replace_disk(2).unwrap();
+    let stripes: Vec<u64> = (0..volume.physical_stripes()).collect();
+    volume.repair_stripes(&stripes);
+    volume.clear_needs_rebuild_all();
+
+    assert!(volume.check().is_clean());
+    assert_eq!(volume.read_stripes(0..100).unwrap(), data);
+}
+
+#[test]
+fn write_stripes_stops_at_simulated_crash() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.simulate_crashes(CrashPlan {
+        seed: 1,
+        crash_after_writes: Some(3),
+        write_hole: false,
+    });
+
+    volume.write_stripes(0..8, &pattern(8 * STRIPE)).unwrap();
+    drop(volume);
+
+    let mut reopened = make_volume(&dir);
+    assert_eq!(reopened.read_stripes(3..8).unwrap(), vec![0; 5 * STRIPE]);
+}
@@ -0,0 +1,168 @@
+//! Reads that lend member data to the caller instead of staging it in stripes.
+//!
+//! Byte reads copy every chunk of a stripe off the disks, decode the stripe into
+//! data chunks and copy those into the output buffer. On a healthy, fully
+//! provisioned volume with memory-mapped members, data chunks can be taken from
+//! the member images as stored: a range kept contiguously on one member, such as
+//! any read of a mirror, is lent without a copy, and other ranges are gathered
+//! into a single buffer.
+//!
+//! Borrowed reads neither scrub nor repair. Parity and mirror copies that disagree
+//! with the data chunks are left for `check` and rebuilds to find.
+
+use std::time::Instant;
+
+use crate::Result;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::metrics::{IoClass, IoOpType, RaidOp};
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::{locate_byte, stripe_byte_offset};
+
+/// `Segment` is a stretch of logical bytes stored contiguously on one member.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) struct Segment {
+    pub(super) disk: usize,
+    pub(super) disk_offset: u64,
+    pub(super) len: usize,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `try_read_with` reads a logical range and hands it to `f`, borrowing it from
+    /// the member images when possible.
+    ///
+    /// Degraded, thin, compressed and balancing volumes, and members that are not memory-mapped, fall back
+    /// to `try_read_bytes` and pass `f` a temporary buffer.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `len` - Number of bytes to read.
+    /// * `f` - Consumer of the data; called once if the read succeeds.
+    ///
+    /// # Errors
+    /// Fails like `try_read_bytes`; `f` is not called in that case.
+    pub fn try_read_with<R>(
+        &mut self,
+        byte_offset: u64,
+        len: usize,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R> {
+        if self.is_degraded()
+            || self.thin.is_some()
+            || self.compression.is_some()
+            || self.balance.is_some()
+            || self.reshape.is_some()
+        {
+            return self.read_copied(byte_offset, len, f);
+        }
+        let start = crate::metrics::is_enabled().then(Instant::now);
+        let checked = self.check_io(byte_offset, len, IoOpType::Read);
+        if let Err(err) = checked {
+            record_borrowed_read(start, len, true);
+            return Err(err.into());
+        }
+        let Some(segments) = self.segments(byte_offset, len) else {
+            return self.read_copied(byte_offset, len, f);
+        };
+
+        if let [segment] = segments[..]
+            && let Some(data) = self
+                .array
+                .borrow(segment.disk, segment.disk_offset, segment.len)
+        {
+            record_borrowed_read(start, len, false);
+            let read = f(data);
+            self.record_foreground(len);
+            return Ok(read);
+        }
+        let Some(data) = self.gather(&segments, len) else {
+            return self.read_copied(byte_offset, len, f);
+        };
+        record_borrowed_read(start, len, false);
+        self.record_foreground(len);
+        Ok(f(&data))
+    }
+
+    /// `read_copied` serves `try_read_with` through the stripe read path.
+    fn read_copied<R>(
+        &mut self,
+        byte_offset: u64,
+        len: usize,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R> {
+        let mut buf = vec![0u8; len];
+        self.try_read_bytes(byte_offset, &mut buf)?;
+        Ok(f(&buf))
+    }
+
+    /// `segments` maps a logical range onto the data chunks that store it.
+    ///
+    /// Returns `None` if the layout does not name a member for some data chunk.
+    pub(super) fn segments(&self, byte_offset: u64, len: usize) -> Option<Vec<Segment>> {
+        let chunk_bytes = self.geom.bytes_per_chunk;
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut done = 0;
+        while done < len {
+            let (stripe_index, in_stripe_byte) = locate_byte(byte_offset, done, &self.geom);
+            let byte_in_chunk = in_stripe_byte % chunk_bytes;
+            let disk = Self::data_disk(stripe_index, in_stripe_byte / chunk_bytes)?;
+            let disk_offset = stripe_byte_offset::<N>(stripe_index) + byte_in_chunk as u64;
+            let take = (chunk_bytes - byte_in_chunk).min(len - done);
+            match segments.last_mut() {
+                Some(last)
+                    if last.disk == disk && last.disk_offset + last.len as u64 == disk_offset =>
+                {
+                    last.len += take;
+                }
+                _ => segments.push(Segment {
+                    disk,
+                    disk_offset,
+                    len: take,
+                }),
+            }
+            done += take;
+        }
+        Some(segments)
+    }
+
+    /// `data_disk` returns the member storing data chunk `chunk` of a stripe as is.
+    pub(super) fn data_disk(stripe_index: u64, chunk: usize) -> Option<usize> {
+        (0..D).find(|&disk| match T::role_at(stripe_index, disk) {
+            ChunkRole::Data(c) => c == chunk,
+            ChunkRole::Mirror(_) => chunk == 0,
+            ChunkRole::Parity => false,
+        })
+    }
+
+    /// `gather` copies the segments of a range into one buffer.
+    ///
+    /// Returns `None` if a member cannot lend its contents.
+    fn gather(&self, segments: &[Segment], len: usize) -> Option<Vec<u8>> {
+        let mut data = Vec::with_capacity(len);
+        for segment in segments {
+            data.extend_from_slice(self.array.borrow(
+                segment.disk,
+                segment.disk_offset,
+                segment.len,
+            )?);
+        }
+        Some(data)
+    }
+}
+
+/// `record_borrowed_read` records a borrowed read as a RAID operation.
+fn record_borrowed_read(start: Option<Instant>, len: usize, error: bool) {
+    if let Some(start) = start {
+        crate::metrics::record_raid_op(RaidOp {
+            op: IoOpType::Read,
+            bytes: u64::try_from(len).unwrap_or(u64::MAX),
+            latency_seconds: start.elapsed().as_secs_f64(),
+            error,
+            degraded: false,
+            read_ahead_hits: 0,
+            read_ahead_misses: 0,
+            parity_cache_hits: 0,
+            parity_cache_misses: 0,
+            class: IoClass::Foreground,
+        });
+    }
+}
@@ -0,0 +1,109 @@
+use super::borrowed::Segment;
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid0::RAID0;
+use crate::layout::stripe::raid1::RAID1;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn make_volume<T: Stripe<TEST_DISKS, CHUNK_SIZE>>(
+    dir: &TempDir,
+    layout: T,
+) -> Volume<TEST_DISKS, CHUNK_SIZE, T> {
+    synced_volume(dir, DISK_LEN, layout)
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 13 + 1).to_le_bytes()[0]).collect()
+}
+
+/// `assert_matches_byte_reads` compares borrowed reads of unaligned ranges with `read_bytes`.
+fn assert_matches_byte_reads<T: Stripe<TEST_DISKS, CHUNK_SIZE>>(
+    volume: &mut Volume<TEST_DISKS, CHUNK_SIZE, T>,
+) {
+    let data = pattern(100);
+    volume.write_bytes(0, &data);
+    for (offset, len) in [(0, 100), (3, 1), (5, 17), (37, 40), (99, 1), (10, 0)] {
+        let borrowed = volume
+            .try_read_with(offset, len, <[u8]>::to_vec)
+            .expect("borrowed read");
+        let start = usize::try_from(offset).unwrap();
+        assert_eq!(borrowed, data[start..start + len], "{offset}+{len}");
+    }
+}
+
+#[test]
+fn borrowed_reads_match_byte_reads_for_every_layout() {
+    let dir = TempDir::new().unwrap();
+    assert_matches_byte_reads(&mut make_volume(&dir, RAID0::zero()));
+    let dir = TempDir::new().unwrap();
+    assert_matches_byte_reads(&mut make_volume(&dir, RAID1::zero()));
+    let dir = TempDir::new().unwrap();
+    assert_matches_byte_reads(&mut make_volume(&dir, RAID3::zero()));
+}
+
+#[test]
+fn mirror_reads_are_lent_from_one_member() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir, RAID1::<TEST_DISKS, CHUNK_SIZE>::zero());
+
+    assert_eq!(
+        volume.segments(2, 30),
+        Some(vec![Segment {
+            disk: 0,
+            disk_offset: 2,
+            len: 30,
+        }])
+    );
+}
+
+#[test]
+fn striped_reads_split_at_chunk_boundaries() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero());
+
+    // Byte 6 is chunk 1 of stripe 0; the read continues into chunk 0 of stripe 1.
+    assert_eq!(
+        volume.segments(6, 4),
+        Some(vec![
+            Segment {
+                disk: 1,
+                disk_offset: 2,
+                len: 2,
+            },
+            Segment {
+                disk: 0,
+                disk_offset: 4,
+                len: 2,
+            },
+        ])
+    );
+}
+
+#[test]
+fn degraded_reads_fall_back_to_reconstruction() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero());
+    let data = pattern(40);
+    volume.write_bytes(0, &data);
+    volume.fail_disk(0).unwrap();
+
+    let borrowed = volume.try_read_with(0, 40, <[u8]>::to_vec).unwrap();
+
+    assert_eq!(borrowed, data);
+}
+
+#[test]
+fn out_of_range_reads_fail_without_calling_back() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir, RAID0::<TEST_DISKS, CHUNK_SIZE>::zero());
+    let capacity = volume.logical_capacity_bytes();
+
+    let result = volume.try_read_with(capacity - 1, 2, |_| panic!("called back"));
+
+    assert!(result.is_err());
+}
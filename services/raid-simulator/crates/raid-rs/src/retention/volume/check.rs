@@ -0,0 +1,152 @@
+//! Read-only consistency check of mirror copies and parity.
+//!
+//! Unlike a scrub, the check never writes to the disks. Each stripe is classified
+//! by whether a later repair could restore it from the redundancy that is left.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::mapper::stripe_byte_offset;
+use crate::retention::volume::{Volume, VolumeEvent};
+
+/// `StripeCheck` is the verdict for a single stripe.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StripeCheck {
+    /// All present chunks agree with each other.
+    Clean,
+    /// Some chunks disagree, but the redundancy identifies the correct contents.
+    Repairable { mismatched_chunks: u64 },
+    /// The stored chunks do not determine the correct contents.
+    Uncorrectable,
+    /// A disk is missing, so the remaining chunks cannot be cross-checked.
+    Unverified,
+}
+
+/// `CheckReport` summarizes a consistency check over many stripes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckReport {
+    pub stripes_checked: u64,
+    pub mismatched_chunks: u64,
+    pub repairable_stripes: u64,
+    pub uncorrectable_stripes: u64,
+    pub unverified_stripes: u64,
+}
+
+impl CheckReport {
+    /// `add` folds the verdict of one stripe into the report.
+    ///
+    /// # Arguments
+    /// * `verdict` - Result of checking a stripe.
+    pub const fn add(&mut self, verdict: StripeCheck) {
+        self.stripes_checked += 1;
+        match verdict {
+            StripeCheck::Clean => {}
+            StripeCheck::Repairable { mismatched_chunks } => {
+                self.repairable_stripes += 1;
+                self.mismatched_chunks += mismatched_chunks;
+            }
+            StripeCheck::Uncorrectable => self.uncorrectable_stripes += 1,
+            StripeCheck::Unverified => self.unverified_stripes += 1,
+        }
+    }
+
+    #[must_use]
+    /// `is_clean` reports whether no inconsistency was found.
+    pub const fn is_clean(&self) -> bool {
+        self.repairable_stripes == 0 && self.uncorrectable_stripes == 0
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `physical_stripes` returns the number of stripes stored on the member disks.
+    pub fn physical_stripes(&self) -> u64 {
+        self.array.disk_len() / N as u64
+    }
+
+    /// `check_stripe` compares the chunks of one physical stripe without writing.
+    ///
+    /// Disks awaiting rebuild are treated like missing ones because their
+    /// contents are not trusted yet. Stripes that are not clean are reported to
+    /// subscribers as `VolumeEvent::CheckFinding`. The read counts as background IO.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Physical index of the stripe to check.
+    pub fn check_stripe(&mut self, stripe_index: u64) -> StripeCheck {
+        let start = Instant::now();
+        let verdict = self.classify_stripe(stripe_index);
+        self.record_background(1, start);
+        if verdict != StripeCheck::Clean {
+            self.emit(&VolumeEvent::CheckFinding {
+                stripe_index,
+                verdict,
+            });
+        }
+        verdict
+    }
+
+    fn classify_stripe(&mut self, stripe_index: u64) -> StripeCheck {
+        let mut chunks = self.array.peek(stripe_byte_offset::<N>(stripe_index));
+        for (chunk, disk) in chunks.iter_mut().zip(&self.array.0) {
+            if disk.needs_rebuild {
+                *chunk = None;
+            }
+        }
+        let present = chunks.iter().filter(|c| c.is_some()).count();
+        if D - present > T::FAULT_TOLERANCE {
+            return StripeCheck::Uncorrectable;
+        }
+
+        if T::DATA == 1 && D > 1 {
+            return Self::check_copies(&chunks, present);
+        }
+        if present < D {
+            return StripeCheck::Unverified;
+        }
+
+        let raw: Vec<Bits<N>> = chunks.iter().map(|c| c.unwrap_or(Bits::zero())).collect();
+        let Ok(expected) = self.reencode(stripe_index, &raw) else {
+            return StripeCheck::Uncorrectable;
+        };
+
+        let mismatched = raw.iter().zip(&expected).filter(|(a, b)| a != b).count() as u64;
+        if mismatched == 0 {
+            StripeCheck::Clean
+        } else {
+            StripeCheck::Repairable {
+                mismatched_chunks: mismatched,
+            }
+        }
+    }
+
+    /// `check` scans every physical stripe and returns the combined report.
+    pub fn check(&mut self) -> CheckReport {
+        let mut report = CheckReport::default();
+        for stripe_index in 0..self.physical_stripes() {
+            report.add(self.check_stripe(stripe_index));
+        }
+        report
+    }
+
+    /// `check_copies` votes between mirror copies; a strict majority wins.
+    fn check_copies(chunks: &[Option<Bits<N>>], present: usize) -> StripeCheck {
+        let mut votes: HashMap<Bits<N>, usize> = HashMap::new();
+        for chunk in chunks.iter().flatten() {
+            *votes.entry(*chunk).or_default() += 1;
+        }
+        let majority = votes.values().copied().max().unwrap_or(0);
+        if majority == present {
+            StripeCheck::Clean
+        } else if majority * 2 > present {
+            StripeCheck::Repairable {
+                mismatched_chunks: (present - majority) as u64,
+            }
+        } else {
+            StripeCheck::Uncorrectable
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid1::RAID1;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 64;
+
+fn raid3(dir: &TempDir) -> Volume<3, CHUNK_SIZE, RAID3<3, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn raid1<const D: usize>(dir: &TempDir) -> Volume<D, CHUNK_SIZE, RAID1<D, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID1::zero())
+}
+
+#[test]
+fn check_reports_clean_volume() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = raid3(&dir);
+    volume.write_bytes(0, b"consistent data!");
+
+    let report = volume.check();
+
+    assert_eq!(report.stripes_checked, DISK_LEN / CHUNK_SIZE as u64);
+    assert!(report.is_clean());
+}
+
+#[test]
+fn check_counts_parity_mismatch_as_repairable_without_writing() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = raid3(&dir);
+    volume.write_bytes(0, b"abcdefgh");
+    volume.array.0[2].write_at(0, &[0xff]);
+
+    let report = volume.check();
+
+    assert_eq!(report.repairable_stripes, 1);
+    assert_eq!(report.mismatched_chunks, 1);
+    assert_eq!(report.uncorrectable_stripes, 0);
+    assert_eq!(volume.check(), report);
+}
+
+#[test]
+fn check_votes_between_mirror_copies() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = raid1::<3>(&dir);
+    volume.write_bytes(0, b"copy");
+    volume.array.0[1].write_at(0, b"c0py");
+
+    assert_eq!(
+        volume.check_stripe(0),
+        StripeCheck::Repairable {
+            mismatched_chunks: 1
+        }
+    );
+}
+
+#[test]
+fn check_flags_two_way_mirror_split_as_uncorrectable() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = raid1::<2>(&dir);
+    volume.write_bytes(0, b"copy");
+    volume.array.0[0].write_at(0, b"c0py");
+
+    assert_eq!(volume.check_stripe(0), StripeCheck::Uncorrectable);
+}
+
+#[test]
+fn check_distinguishes_unverified_from_lost_stripes() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = raid3(&dir);
+
+    volume.fail_disk(0).expect("fail disk");
+    assert_eq!(volume.check_stripe(0), StripeCheck::Unverified);
+
+    volume.fail_disk(1).expect("fail disk");
+    assert_eq!(volume.check_stripe(0), StripeCheck::Uncorrectable);
+}
+
+/// `Oversized` claims one more disk than the array has, like a misconfigured layout.
+struct Oversized(RAID3<3, CHUNK_SIZE>);
+
+impl Stripe<3, CHUNK_SIZE> for Oversized {
+    const DATA: usize = 2;
+    const DISKS: usize = 4;
+
+    fn write(&mut self, data: &[Bits<CHUNK_SIZE>]) {
+        self.0.write(data);
+    }
+    fn write_raw(&mut self, data: &[Bits<CHUNK_SIZE>]) {
+        self.0.write_raw(data);
+    }
+    fn read(&self, out: &mut [Bits<CHUNK_SIZE>]) {
+        self.0.read(out);
+    }
+    fn read_raw(&self, out: &mut [Bits<CHUNK_SIZE>]) {
+        self.0.read_raw(out);
+    }
+}
+
+#[test]
+fn check_reports_layout_mismatch_instead_of_panicking() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = synced_volume(&dir, DISK_LEN, Oversized(RAID3::zero()));
+    volume.write_bytes(0, b"mismatch");
+
+    assert_eq!(volume.check_stripe(0), StripeCheck::Uncorrectable);
+    assert!(volume.inspect_stripe(0).expected.is_empty());
+}
@@ -0,0 +1,476 @@
+//! Transparent block compression: logical blocks are stored zstd-compressed.
+//!
+//! A compressed volume advertises a virtual capacity that may exceed the array.
+//! Its logical space is cut into `COMPRESS_BLOCK` byte blocks; every written block
+//! is compressed and stored in a run of consecutive physical stripes just long
+//! enough for the compressed bytes. Blocks that do not shrink are stored as they
+//! are, and blocks of zeros are not stored at all. The block map (per block, the
+//! first stripe of its run and the stored length) and a superblock occupy physical
+//! stripes at the end of the array; everything before them forms the pool.
+//!
+//! A rewritten block goes to a fresh run before the map points at it, so a crash
+//! leaves either the old or the new contents of the block.
+
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::{Access, Volume};
+use crate::{Error, Result};
+
+/// `COMPRESS_BLOCK` is the logical size of the blocks compressed on their own.
+pub const COMPRESS_BLOCK: usize = 4096;
+const BLOCK_BYTES: u64 = COMPRESS_BLOCK as u64;
+const COMPRESS_MAGIC: [u8; 8] = *b"RAIDZST1";
+const COMPRESS_VERSION: u8 = 1;
+const ZSTD_LEVEL: i32 = 3;
+const SUPERBLOCK_LEN: usize = 64;
+const SUPERBLOCK_SIZE: u64 = SUPERBLOCK_LEN as u64;
+const MAP_ENTRY_LEN: usize = 8;
+const MAP_ENTRY_SIZE: u64 = MAP_ENTRY_LEN as u64;
+
+/// `pool_exhausted` is the error of a block write to a compressed volume with too
+/// few free physical stripes in a row.
+fn pool_exhausted(needed: u64) -> Error {
+    Error::OutOfSpace(format!(
+        "compressed pool exhausted: block needs {needed} free stripes in a row"
+    ))
+}
+
+/// `CompressionUsage` reports how well the blocks of a compressed volume compress.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionUsage {
+    pub virtual_bytes: u64,
+    pub block_bytes: u64,
+    /// Blocks holding data; blocks of zeros are not stored.
+    pub stored_blocks: u64,
+    /// Bytes the stored blocks take after compression.
+    pub compressed_bytes: u64,
+    /// Physical bytes of the stripe runs holding the stored blocks.
+    pub used_bytes: u64,
+    pub pool_bytes: u64,
+}
+
+impl CompressionUsage {
+    #[must_use]
+    /// `logical_bytes` returns the uncompressed size of the stored blocks.
+    pub const fn logical_bytes(&self) -> u64 {
+        self.stored_blocks * self.block_bytes
+    }
+
+    #[must_use]
+    /// `free_bytes` returns the physical bytes that can still be allocated.
+    pub const fn free_bytes(&self) -> u64 {
+        self.pool_bytes.saturating_sub(self.used_bytes)
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    /// `ratio` returns the logical bytes stored per physical byte used; 1 when empty.
+    pub fn ratio(&self) -> f64 {
+        if self.used_bytes == 0 {
+            1.0
+        } else {
+            self.logical_bytes() as f64 / self.used_bytes as f64
+        }
+    }
+}
+
+/// `Slot` is where a block is stored.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+struct Slot {
+    /// First stripe of the run plus one; 0 for blocks never stored.
+    first: u32,
+    /// Stored length in bytes; `COMPRESS_BLOCK` for blocks stored uncompressed.
+    len: u32,
+}
+
+impl Slot {
+    fn to_bytes(self) -> [u8; MAP_ENTRY_LEN] {
+        let mut raw = [0u8; MAP_ENTRY_LEN];
+        raw[0..4].copy_from_slice(&self.first.to_le_bytes());
+        raw[4..8].copy_from_slice(&self.len.to_le_bytes());
+        raw
+    }
+
+    fn from_bytes(raw: &[u8]) -> Self {
+        let word = |range: std::ops::Range<usize>| {
+            raw.get(range)
+                .and_then(|bytes| bytes.try_into().ok())
+                .map_or(0, u32::from_le_bytes)
+        };
+        Self {
+            first: word(0..4),
+            len: word(4..8),
+        }
+    }
+}
+
+/// `CompressMap` is the in-memory copy of the block map.
+pub struct CompressMap {
+    virtual_bytes: u64,
+    map_start: u64,
+    stripe_bytes: u64,
+    pool_stripes: u64,
+    map: Vec<Slot>,
+    used: Vec<bool>,
+    used_stripes: u64,
+    next_free: u64,
+}
+
+impl CompressMap {
+    /// `virtual_bytes` returns the advertised capacity of the volume.
+    pub const fn virtual_bytes(&self) -> u64 {
+        self.virtual_bytes
+    }
+
+    /// `run` returns the first stripe and stored length of a block, if it is stored.
+    fn run(&self, block: u64) -> Option<(u64, usize)> {
+        let slot = self.map.get(usize::try_from(block).ok()?)?;
+        if slot.first == 0 {
+            return None;
+        }
+        Some((u64::from(slot.first) - 1, usize::try_from(slot.len).ok()?))
+    }
+
+    /// `stripes_for` returns the stripes a run of `len` stored bytes takes.
+    const fn stripes_for(&self, len: usize) -> u64 {
+        (len as u64).div_ceil(self.stripe_bytes)
+    }
+
+    /// `metadata_stripes` returns the physical stripes holding the map and superblock.
+    const fn metadata_stripes(&self, usable: u64) -> std::ops::Range<u64> {
+        self.map_start / self.stripe_bytes..usable / self.stripe_bytes
+    }
+
+    /// `stored_stripes` lists the physical stripes holding stored blocks.
+    fn stored_stripes(&self) -> Vec<u64> {
+        (0..self.pool_stripes)
+            .filter(|&s| usize::try_from(s).is_ok_and(|s| self.used[s]))
+            .collect()
+    }
+
+    /// `mark` claims or releases a run of stripes.
+    fn mark(&mut self, first: u64, count: u64, used: bool) {
+        for s in first..first + count {
+            if let Some(taken) = usize::try_from(s).ok().and_then(|s| self.used.get_mut(s))
+                && *taken != used
+            {
+                *taken = used;
+                if used {
+                    self.used_stripes += 1;
+                } else {
+                    self.used_stripes -= 1;
+                }
+            }
+        }
+    }
+
+    /// `allocate` claims the first run of `count` free stripes at or after the
+    /// last allocation, wrapping around to the start of the pool.
+    fn allocate(&mut self, count: u64) -> Option<u64> {
+        if count == 0 || count > self.pool_stripes {
+            return None;
+        }
+        let fits = |first: u64| {
+            (first..first + count).all(|s| usize::try_from(s).is_ok_and(|s| !self.used[s]))
+        };
+        let last_start = self.pool_stripes - count;
+        let start = self.next_free.min(last_start + 1);
+        let first = (start..=last_start)
+            .chain(0..start)
+            .find(|&first| fits(first))?;
+        self.mark(first, count, true);
+        self.next_free = first + count;
+        Some(first)
+    }
+
+    fn usage(&self) -> CompressionUsage {
+        let stored = self.map.iter().filter(|slot| slot.first != 0);
+        CompressionUsage {
+            virtual_bytes: self.virtual_bytes,
+            block_bytes: BLOCK_BYTES,
+            stored_blocks: stored.clone().count() as u64,
+            compressed_bytes: stored.map(|slot| u64::from(slot.len)).sum(),
+            used_bytes: self.used_stripes * self.stripe_bytes,
+            pool_bytes: self.pool_stripes * self.stripe_bytes,
+        }
+    }
+}
+
+/// `compress_block` compresses a block, returning `None` if it does not shrink.
+fn compress_block(data: &[u8]) -> Option<Vec<u8>> {
+    let start = Instant::now();
+    let packed = zstd::bulk::compress(data, ZSTD_LEVEL).ok();
+    let out = packed.as_ref().map_or(data.len(), Vec::len);
+    crate::metrics::record_compression(data.len(), out, start.elapsed());
+    packed.filter(|packed| packed.len() < data.len())
+}
+
+/// `decompress_block` restores a block stored compressed.
+fn decompress_block(block: u64, packed: &[u8]) -> Result<Vec<u8>> {
+    let start = Instant::now();
+    let data = zstd::bulk::decompress(packed, COMPRESS_BLOCK);
+    crate::metrics::record_decompression(start.elapsed());
+    match data {
+        Ok(data) if data.len() == COMPRESS_BLOCK => Ok(data),
+        _ => Err(Error::Corrupt(format!(
+            "compressed block {block} does not decompress"
+        ))),
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `is_compressed` reports whether the volume compresses its blocks.
+    pub const fn is_compressed(&self) -> bool {
+        self.compression.is_some()
+    }
+
+    /// `compression_usage` returns how well the blocks of a compressed volume compress.
+    pub fn compression_usage(&self) -> Option<CompressionUsage> {
+        self.compression.as_ref().map(CompressMap::usage)
+    }
+
+    /// `init_compression` converts the volume into a compressed volume with the
+    /// given virtual size.
+    ///
+    /// The data before `data_end` is read first and written back compressed.
+    ///
+    /// # Arguments
+    /// * `virtual_bytes` - Logical capacity advertised by the compressed volume.
+    /// * `data_end` - End of the live data that must stay readable.
+    ///
+    /// # Errors
+    /// Returns an error if the volume is already compressed, thin or has snapshots,
+    /// or if the pool could not hold the live data even uncompressed.
+    pub fn init_compression(&mut self, virtual_bytes: u64, data_end: u64) -> Result<()> {
+        if self.compression.is_some() {
+            return Err(Error::Invalid("volume is already compressed".to_string()));
+        }
+        if self.thin.is_some() || self.snapshots.is_some() {
+            return Err(Error::Invalid(
+                "compression cannot be combined with thin provisioning or snapshots".to_string(),
+            ));
+        }
+        let blocks = virtual_bytes / BLOCK_BYTES;
+        if blocks == 0 || blocks >= u64::from(u32::MAX) {
+            return Err(Error::Geometry(format!(
+                "unsupported compressed volume size of {virtual_bytes} bytes"
+            )));
+        }
+
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let usable = self.physical_usable_bytes();
+        let meta_bytes = blocks * MAP_ENTRY_SIZE + SUPERBLOCK_SIZE;
+        let Some(map_start) = usable
+            .checked_sub(meta_bytes)
+            .map(|start| start / stripe_bytes * stripe_bytes)
+        else {
+            return Err(Error::OutOfSpace(format!(
+                "array is too small for a {virtual_bytes} byte block map"
+            )));
+        };
+        let pool_stripes = map_start / stripe_bytes;
+        let keep = data_end.min(blocks * BLOCK_BYTES);
+        if keep.div_ceil(BLOCK_BYTES) * BLOCK_BYTES.div_ceil(stripe_bytes) > pool_stripes {
+            return Err(Error::OutOfSpace(format!(
+                "compressed pool could not hold {data_end} bytes of live data"
+            )));
+        }
+        let mut live = vec![0u8; usize::try_from(keep)?];
+        self.read_logical(0, &mut live, Access::Physical)?;
+
+        let len = usize::try_from(blocks)?;
+        let mut sb = [0u8; SUPERBLOCK_LEN];
+        sb[0..8].copy_from_slice(&COMPRESS_MAGIC);
+        sb[8] = COMPRESS_VERSION;
+        sb[16..24].copy_from_slice(&(blocks * BLOCK_BYTES).to_le_bytes());
+        sb[24..32].copy_from_slice(&map_start.to_le_bytes());
+        self.write_logical(map_start, &vec![0u8; len * MAP_ENTRY_LEN], Access::Physical)?;
+        self.write_logical(usable - SUPERBLOCK_SIZE, &sb, Access::Physical)?;
+        self.compression = Some(CompressMap {
+            virtual_bytes: blocks * BLOCK_BYTES,
+            map_start,
+            stripe_bytes,
+            pool_stripes,
+            map: vec![Slot::default(); len],
+            used: vec![false; usize::try_from(pool_stripes)?],
+            used_stripes: 0,
+            next_free: 0,
+        });
+        self.save_features()?;
+        self.write_compressed(0, &live)
+    }
+
+    /// `write_compressed` writes payload bytes through the block map.
+    ///
+    /// Blocks the payload covers only in part are read and merged first.
+    pub(super) fn write_compressed(&mut self, byte_offset: u64, payload: &[u8]) -> Result<()> {
+        let mut done = 0;
+        while done < payload.len() {
+            let pos = byte_offset + done as u64;
+            let block = pos / BLOCK_BYTES;
+            let in_block = usize::try_from(pos % BLOCK_BYTES)?;
+            let take = (COMPRESS_BLOCK - in_block).min(payload.len() - done);
+            let mut data = if take == COMPRESS_BLOCK {
+                vec![0u8; COMPRESS_BLOCK]
+            } else {
+                self.read_block(block)?
+            };
+            data[in_block..in_block + take].copy_from_slice(&payload[done..done + take]);
+            self.store_block(block, &data)?;
+            done += take;
+        }
+        Ok(())
+    }
+
+    /// `read_compressed` reads bytes through the block map; blocks never stored
+    /// read back as zeros.
+    pub(super) fn read_compressed(&mut self, byte_offset: u64, out: &mut [u8]) -> Result<()> {
+        let mut done = 0;
+        while done < out.len() {
+            let pos = byte_offset + done as u64;
+            let block = pos / BLOCK_BYTES;
+            let in_block = usize::try_from(pos % BLOCK_BYTES)?;
+            let take = (COMPRESS_BLOCK - in_block).min(out.len() - done);
+            let data = self.read_block(block)?;
+            out[done..done + take].copy_from_slice(&data[in_block..in_block + take]);
+            done += take;
+        }
+        Ok(())
+    }
+
+    /// `read_block` returns the uncompressed contents of a block.
+    fn read_block(&mut self, block: u64) -> Result<Vec<u8>> {
+        let Some((first, len)) = self.compression.as_ref().and_then(|map| map.run(block)) else {
+            return Ok(vec![0u8; COMPRESS_BLOCK]);
+        };
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let mut stored = vec![0u8; len];
+        self.read_logical(first * stripe_bytes, &mut stored, Access::Physical)?;
+        if len == COMPRESS_BLOCK {
+            return Ok(stored);
+        }
+        decompress_block(block, &stored)
+    }
+
+    /// `store_block` compresses a block into a fresh run and points the map at it.
+    ///
+    /// When no fresh run is free, a block that still fits its old run is
+    /// rewritten in place.
+    fn store_block(&mut self, block: u64, data: &[u8]) -> Result<()> {
+        let Some(map) = self.compression.as_mut() else {
+            return Ok(());
+        };
+        let old = map.run(block);
+        let entry_offset = map.map_start + block * MAP_ENTRY_SIZE;
+        let stored = if data.iter().all(|&b| b == 0) {
+            None
+        } else {
+            Some(compress_block(data).unwrap_or_else(|| data.to_vec()))
+        };
+
+        let slot = match &stored {
+            None => Slot::default(),
+            Some(stored) => {
+                let count = map.stripes_for(stored.len());
+                let first = match (map.allocate(count), old) {
+                    (Some(first), _) => first,
+                    (None, Some((first, len))) if count <= map.stripes_for(len) => first,
+                    (None, _) => return Err(pool_exhausted(count)),
+                };
+                Slot {
+                    first: u32::try_from(first + 1)?,
+                    len: u32::try_from(stored.len())?,
+                }
+            }
+        };
+        if let Some(stored) = &stored {
+            let stripe_bytes = self.geom.bytes_per_stripe as u64;
+            let offset = (u64::from(slot.first) - 1) * stripe_bytes;
+            self.write_logical(offset, stored, Access::Physical)?;
+        }
+        self.write_logical(entry_offset, &slot.to_bytes(), Access::Physical)?;
+
+        let Some(map) = self.compression.as_mut() else {
+            return Ok(());
+        };
+        if let Some((first, len)) = old
+            && slot.first != u32::try_from(first + 1).unwrap_or(0)
+        {
+            map.mark(first, map.stripes_for(len), false);
+        }
+        if let Some(entry) = usize::try_from(block).ok().and_then(|b| map.map.get_mut(b)) {
+            *entry = slot;
+        }
+        Ok(())
+    }
+
+    /// `compression_metadata_stripes` returns the physical stripes holding the block map.
+    pub(super) fn compression_metadata_stripes(&self) -> std::ops::Range<u64> {
+        self.compression.as_ref().map_or(0..0, |map| {
+            map.metadata_stripes(self.physical_usable_bytes())
+        })
+    }
+
+    /// `compressed_stripes` lists the physical stripes holding stored blocks of a
+    /// compressed volume.
+    pub(super) fn compressed_stripes(&self) -> Option<Vec<u64>> {
+        self.compression.as_ref().map(CompressMap::stored_stripes)
+    }
+
+    pub(super) fn load_compress_map(&mut self) -> Option<CompressMap> {
+        let usable = self.physical_usable_bytes();
+        if usable < SUPERBLOCK_SIZE {
+            return None;
+        }
+        let mut sb = [0u8; SUPERBLOCK_LEN];
+        self.read_logical(usable - SUPERBLOCK_SIZE, &mut sb, Access::Physical)
+            .ok()?;
+        if sb[0..8] != COMPRESS_MAGIC || sb[8] != COMPRESS_VERSION {
+            return None;
+        }
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let virtual_bytes = u64::from_le_bytes(sb[16..24].try_into().ok()?);
+        let map_start = u64::from_le_bytes(sb[24..32].try_into().ok()?);
+        let blocks = virtual_bytes / BLOCK_BYTES;
+        if !map_start.is_multiple_of(stripe_bytes)
+            || map_start + blocks * MAP_ENTRY_SIZE > usable - SUPERBLOCK_SIZE
+        {
+            return None;
+        }
+
+        let pool_stripes = map_start / stripe_bytes;
+        let mut raw = vec![0u8; usize::try_from(blocks * MAP_ENTRY_SIZE).ok()?];
+        self.read_logical(map_start, &mut raw, Access::Physical)
+            .ok()?;
+        let mut map = CompressMap {
+            virtual_bytes,
+            map_start,
+            stripe_bytes,
+            pool_stripes,
+            map: Vec::new(),
+            used: vec![false; usize::try_from(pool_stripes).ok()?],
+            used_stripes: 0,
+            next_free: 0,
+        };
+        for raw in raw.chunks_exact(MAP_ENTRY_LEN) {
+            let slot = Slot::from_bytes(raw);
+            let len = usize::try_from(slot.len).unwrap_or(usize::MAX);
+            let count = map.stripes_for(len);
+            let first = u64::from(slot.first).wrapping_sub(1);
+            let valid = slot.first != 0
+                && (1..=COMPRESS_BLOCK).contains(&len)
+                && first + count <= pool_stripes
+                && (first..first + count).all(|s| usize::try_from(s).is_ok_and(|s| !map.used[s]));
+            if valid {
+                map.mark(first, count, true);
+                map.map.push(slot);
+            } else {
+                map.map.push(Slot::default());
+            }
+        }
+        Some(map)
+    }
+}
@@ -0,0 +1,153 @@
+use super::test_utils::fresh_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 64;
+const DISK_LEN: u64 = 65536;
+const BLOCK: u64 = COMPRESS_BLOCK as u64;
+const VIRTUAL_BYTES: u64 = 4 * 2 * DISK_LEN;
+
+type TestVolume = Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>;
+
+fn make_volume(dir: &TempDir) -> TestVolume {
+    fresh_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn compressed_volume(dir: &TempDir) -> TestVolume {
+    let mut volume = make_volume(dir);
+    volume
+        .init_compression(VIRTUAL_BYTES, 0)
+        .expect("init compression");
+    volume
+}
+
+fn read(volume: &mut TestVolume, offset: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.try_read_bytes(offset, &mut out).expect("read");
+    out
+}
+
+fn text(len: usize) -> Vec<u8> {
+    b"stripe slinger "
+        .iter()
+        .copied()
+        .cycle()
+        .take(len)
+        .collect()
+}
+
+fn noise(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed.to_le_bytes()[0]
+        })
+        .collect()
+}
+
+#[test]
+fn compressible_data_beyond_physical_size_round_trips_and_persists() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = compressed_volume(&dir);
+    assert!(volume.is_compressed());
+    assert_eq!(volume.logical_capacity_bytes(), VIRTUAL_BYTES);
+
+    let data = text(usize::try_from(3 * DISK_LEN).unwrap());
+    volume.try_write_bytes(100, &data).expect("write");
+    assert_eq!(read(&mut volume, 100, data.len()), data);
+
+    let usage = volume.compression_usage().expect("usage");
+    assert!(usage.logical_bytes() > 2 * DISK_LEN);
+    assert!(usage.used_bytes < usage.pool_bytes);
+    assert!(usage.ratio() > 4.0);
+    assert!(!volume.reserved_stripes().is_empty());
+    drop(volume);
+
+    let mut volume = make_volume(&dir);
+    assert!(volume.is_compressed());
+    assert_eq!(volume.compression_usage(), Some(usage));
+    assert_eq!(read(&mut volume, 100, data.len()), data);
+    assert_eq!(read(&mut volume, 0, 100), vec![0u8; 100]);
+}
+
+#[test]
+fn incompressible_blocks_are_stored_raw_and_rewrites_free_their_run() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = compressed_volume(&dir);
+
+    volume
+        .try_write_bytes(BLOCK, &noise(COMPRESS_BLOCK, 7))
+        .expect("write noise");
+    let usage = volume.compression_usage().expect("usage");
+    assert_eq!(usage.stored_blocks, 1);
+    assert_eq!(usage.compressed_bytes, BLOCK);
+
+    let fresh = noise(COMPRESS_BLOCK, 11);
+    volume.try_write_bytes(BLOCK, &fresh).expect("rewrite");
+    assert_eq!(volume.compression_usage(), Some(usage));
+    assert_eq!(read(&mut volume, BLOCK, COMPRESS_BLOCK), fresh);
+
+    volume
+        .try_write_bytes(BLOCK, &vec![0u8; COMPRESS_BLOCK])
+        .expect("zero");
+    let usage = volume.compression_usage().expect("usage");
+    assert_eq!(usage.stored_blocks, 0);
+    assert_eq!(usage.used_bytes, 0);
+}
+
+#[test]
+fn partial_block_writes_merge_with_stored_contents() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = compressed_volume(&dir);
+
+    volume
+        .try_write_bytes(0, &text(2 * COMPRESS_BLOCK))
+        .expect("write");
+    volume
+        .try_write_bytes(BLOCK - 3, b"across")
+        .expect("straddling write");
+
+    let mut expected = text(2 * COMPRESS_BLOCK);
+    expected[COMPRESS_BLOCK - 3..COMPRESS_BLOCK + 3].copy_from_slice(b"across");
+    assert_eq!(read(&mut volume, 0, expected.len()), expected);
+}
+
+#[test]
+fn init_compression_keeps_live_data_and_excludes_thin_volumes() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"live data");
+    volume
+        .init_compression(VIRTUAL_BYTES, 9)
+        .expect("init compression");
+    assert_eq!(read(&mut volume, 0, 9), b"live data");
+    assert!(volume.init_thin(VIRTUAL_BYTES, 0).is_err());
+    assert!(volume.init_snapshots(BLOCK, 0).is_err());
+
+    let thin_dir = TempDir::new().unwrap();
+    let mut thin = make_volume(&thin_dir);
+    thin.init_thin(VIRTUAL_BYTES, 0).expect("init thin");
+    assert!(thin.init_compression(VIRTUAL_BYTES, 0).is_err());
+}
+
+#[test]
+fn stored_blocks_survive_a_member_rebuild() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = compressed_volume(&dir);
+    let data = text(3 * COMPRESS_BLOCK);
+    volume.try_write_bytes(0, &data).expect("write");
+
+    volume.fail_disk(1).expect("fail");
+    volume.replace_disk(1).expect("replace");
+    let stripes = volume.stripes_to_repair(volume.logical_capacity_bytes());
+    volume.repair_stripes(&stripes);
+    volume.clear_needs_rebuild_all();
+
+    volume.fail_disk(0).expect("fail");
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    assert_eq!(volume.compression_usage().expect("usage").stored_blocks, 3);
+}
@@ -0,0 +1,156 @@
+//! Drive write caches, power-loss simulation and a harness for crash-consistency tests.
+//!
+//! Every member disk caches its writes until the volume is flushed or synced. A
+//! crash, either requested explicitly or triggered after a number of stripe writes,
+//! persists part of each disk's cached writes and drops the rest. Reopening the
+//! images and resyncing must then yield a consistent volume. A plan with
+//! `write_hole` set instead tears the stripe it crashes on; see `write_hole`.
+
+use crate::Result;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::disk::{Disk, WriteCachePlan, WriteCacheStats};
+use crate::retention::volume::{BATCH_STRIPES, Volume};
+
+/// `CrashPlan` configures power-loss simulation for a volume.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CrashPlan {
+    /// Seed for the writes that survive a crash; each disk derives its own stream.
+    pub seed: u64,
+    /// Crash automatically once this many stripes have been written.
+    pub crash_after_writes: Option<u64>,
+    /// Crash between the data and the redundancy writes of the stripe that
+    /// reaches `crash_after_writes`, leaving it torn.
+    pub write_hole: bool,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `simulate_crashes` starts caching writes on every member disk until `sync`.
+    ///
+    /// # Arguments
+    /// * `plan` - Seed and optional crash point of the simulation.
+    pub fn simulate_crashes(&mut self, plan: CrashPlan) {
+        for (i, disk) in (0u64..).zip(&mut self.array.0) {
+            disk.simulate_crashes(plan.seed.wrapping_add(i));
+        }
+        self.crash_after = plan.crash_after_writes;
+        self.write_hole.tear = plan.write_hole;
+        self.write_hole.crashed = false;
+    }
+
+    /// `enable_write_cache` puts a drive write cache in front of every member disk.
+    ///
+    /// # Arguments
+    /// * `plan` - Capacity and volatility of the caches; each disk derives its own seed.
+    pub fn enable_write_cache(&mut self, plan: WriteCachePlan) {
+        for (i, disk) in (0u64..).zip(&mut self.array.0) {
+            disk.enable_write_cache(WriteCachePlan {
+                seed: plan.seed.wrapping_add(i),
+                ..plan
+            });
+        }
+    }
+
+    /// `flush` writes the cached writes of all members to their images.
+    ///
+    /// This is the write barrier of the volume: a power failure after it loses
+    /// nothing that was written before it.
+    pub fn flush(&mut self) {
+        for disk in &mut self.array.0 {
+            disk.flush();
+        }
+        self.journal_settle();
+    }
+
+    /// `sync` makes every completed write durable on all members.
+    ///
+    /// # Errors
+    /// Returns an error if a disk image cannot be flushed.
+    pub fn sync(&mut self) -> Result<()> {
+        for disk in &mut self.array.0 {
+            disk.sync()?;
+        }
+        self.journal_settle();
+        Ok(())
+    }
+
+    /// `crash` simulates power loss on every member at once.
+    ///
+    /// Writes issued after the crash are accepted but never reach the images.
+    pub fn crash(&mut self) {
+        for disk in &mut self.array.0 {
+            disk.crash();
+        }
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.crash_after = None;
+        self.write_hole.crashed = true;
+    }
+
+    #[must_use]
+    /// `write_cache_stats` returns the write cache counters summed over all members.
+    pub fn write_cache_stats(&self) -> WriteCacheStats {
+        self.array
+            .0
+            .iter()
+            .map(Disk::write_cache_stats)
+            .fold(WriteCacheStats::default(), |sum, stats| sum + stats)
+    }
+
+    #[must_use]
+    /// `unsynced_writes` returns the number of disk writes a crash could still lose.
+    pub fn unsynced_writes(&self) -> usize {
+        self.array.0.iter().map(Disk::unsynced_writes).sum()
+    }
+
+    /// `resync` rewrites the redundancy of every physical stripe from its data.
+    ///
+    /// This is the recovery step after an unclean shutdown: stripes torn by a crash
+    /// get their parity or mirror copies brought back in line.
+    pub fn resync(&mut self) {
+        let total = self.physical_stripes();
+        for first in (0..total).step_by(BATCH_STRIPES) {
+            let batch: Vec<u64> = (first..total.min(first + BATCH_STRIPES as u64)).collect();
+            self.repair_stripes(&batch);
+        }
+    }
+
+    /// `count_stripe_write` advances the crash point after a stripe is stored.
+    pub(super) fn count_stripe_write(&mut self) {
+        match self.crash_after {
+            Some(left) if left <= 1 => self.crash(),
+            Some(left) => self.crash_after = Some(left - 1),
+            None => {}
+        }
+    }
+}
+
+/// `crash_test` runs a workload under power-loss simulation and recovers the volume.
+///
+/// The volume returned by `open` has crash simulation enabled, runs `workload`, and
+/// then crashes unless the plan already did. The images are reopened with `open`
+/// and resynced, and the recovered volume is returned for assertions.
+///
+/// # Arguments
+/// * `open` - Opens the volume over the same disk images on each call.
+/// * `plan` - Seed and optional crash point of the simulation.
+/// * `workload` - IO to run before the crash; call `sync` to make writes durable.
+pub fn crash_test<const D: usize, const N: usize, T, O, W>(
+    open: O,
+    plan: CrashPlan,
+    workload: W,
+) -> Volume<D, N, T>
+where
+    T: Stripe<D, N>,
+    O: Fn() -> Volume<D, N, T>,
+    W: FnOnce(&mut Volume<D, N, T>),
+{
+    let mut volume = open();
+    volume.simulate_crashes(plan);
+    workload(&mut volume);
+    volume.crash();
+    drop(volume);
+
+    let mut recovered = open();
+    recovered.resync();
+    recovered
+}
@@ -0,0 +1,137 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid1::RAID1;
+use crate::layout::stripe::raid3::RAID3;
+use crate::retention::disk::WriteCachePlan;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn open<T: Stripe<TEST_DISKS, CHUNK_SIZE>>(
+    dir: &TempDir,
+    layout: T,
+) -> Volume<TEST_DISKS, CHUNK_SIZE, T> {
+    synced_volume(dir, DISK_LEN, layout)
+}
+
+fn pattern(len: usize, salt: u8) -> Vec<u8> {
+    (0..=u8::MAX)
+        .cycle()
+        .skip(usize::from(salt))
+        .take(len)
+        .collect()
+}
+
+#[test]
+fn raid3_recovers_consistently_from_any_crash_point() {
+    // The first eight stripe writes carry `durable`; crash anywhere after them.
+    for seed in 0..24 {
+        let dir = TempDir::new().unwrap();
+        let durable = pattern(64, 1);
+        let plan = CrashPlan {
+            seed,
+            crash_after_writes: Some(9 + seed % 12),
+            write_hole: false,
+        };
+
+        let mut recovered = crash_test(
+            || open(&dir, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero()),
+            plan,
+            |volume| {
+                volume.write_bytes(0, &durable);
+                volume.sync().expect("sync");
+                assert_eq!(volume.unsynced_writes(), 0);
+                volume.write_bytes(64, &pattern(64, 9));
+                volume.write_bytes(4, &[0xEE; 8]);
+            },
+        );
+
+        assert!(recovered.check().is_clean(), "seed {seed}");
+        let mut out = vec![0u8; 64];
+        recovered.read_bytes(0, &mut out);
+        for (i, (&got, &want)) in out.iter().zip(&durable).enumerate() {
+            assert!(
+                got == want || (4..12).contains(&i) && got == 0xEE,
+                "seed {seed}: byte {i} is {got:#x}"
+            );
+        }
+    }
+}
+
+#[test]
+fn raid1_mirrors_agree_after_crash() {
+    for seed in 0..8 {
+        let dir = TempDir::new().unwrap();
+
+        let mut recovered = crash_test(
+            || open(&dir, RAID1::<TEST_DISKS, CHUNK_SIZE>::zero()),
+            CrashPlan {
+                seed,
+                crash_after_writes: None,
+                write_hole: false,
+            },
+            |volume| {
+                volume.write_bytes(0, &pattern(32, 3));
+                volume.sync().expect("sync");
+                volume.write_bytes(16, &pattern(32, 7));
+                assert!(volume.unsynced_writes() > 0);
+            },
+        );
+
+        assert!(recovered.check().is_clean(), "seed {seed}");
+        let mut out = vec![0u8; 16];
+        recovered.read_bytes(0, &mut out);
+        assert_eq!(out, pattern(16, 3));
+    }
+}
+
+#[test]
+fn writes_after_the_crash_point_are_lost() {
+    let dir = TempDir::new().unwrap();
+
+    let mut recovered = crash_test(
+        || open(&dir, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero()),
+        CrashPlan {
+            seed: 7,
+            crash_after_writes: Some(1),
+            write_hole: false,
+        },
+        |volume| {
+            volume.write_bytes(0, &[1; 8]);
+            volume.sync().expect("sync after crash");
+            volume.write_bytes(32, &[2; 8]);
+            volume.sync().expect("sync after crash");
+        },
+    );
+
+    let mut out = [0u8; 8];
+    recovered.read_bytes(32, &mut out);
+    assert_eq!(out, [0; 8]);
+}
+
+#[test]
+fn flush_is_a_write_barrier() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = open(&dir, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero());
+    volume.enable_write_cache(WriteCachePlan {
+        volatility: 1.0,
+        ..WriteCachePlan::default()
+    });
+
+    volume.write_bytes(0, &pattern(32, 1));
+    volume.flush();
+    volume.write_bytes(32, &pattern(32, 2));
+    volume.crash();
+    let stats = volume.write_cache_stats();
+    assert!(stats.lost_writes > 0);
+    assert_eq!(stats.lost_writes, stats.cached_writes - stats.written_back);
+    drop(volume);
+
+    let mut recovered = open(&dir, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero());
+    let mut out = [0u8; 64];
+    recovered.read_bytes(0, &mut out);
+    assert_eq!(out[..32], pattern(32, 1));
+    assert_eq!(out[32..], [0u8; 32]);
+}
@@ -0,0 +1,80 @@
+//! Policy for serving IO while members are missing or awaiting rebuild.
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::metrics::IoOpType;
+use crate::retention::IoError;
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::locate_byte;
+
+/// `DegradedPolicy` selects how the fallible IO paths behave on a degraded volume.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DegradedPolicy {
+    /// Fail reads and writes whose data cannot be kept consistent.
+    #[default]
+    FailFast,
+    /// Serve whatever the remaining members hold; lost data reads back as zeros.
+    BestEffort,
+    /// Fail like `FailFast` and refuse every write until the volume is healthy.
+    ReadOnly,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `set_degraded_policy` changes how `try_read_bytes` and `try_write_bytes` handle
+    /// a degraded volume.
+    ///
+    /// # Arguments
+    /// * `policy` - Policy to apply from now on.
+    pub const fn set_degraded_policy(&mut self, policy: DegradedPolicy) {
+        self.degraded_policy = policy;
+    }
+
+    #[must_use]
+    /// `degraded_policy` returns the policy applied to a degraded volume.
+    pub const fn degraded_policy(&self) -> DegradedPolicy {
+        self.degraded_policy
+    }
+
+    /// `is_degraded` reports whether any member is missing or awaiting rebuild.
+    pub fn is_degraded(&self) -> bool {
+        self.array
+            .0
+            .iter()
+            .any(|d| d.is_missing() || d.needs_rebuild)
+    }
+
+    /// `check_degraded` applies the degraded policy to a request at `byte_offset`.
+    pub(super) fn check_degraded(&self, byte_offset: u64, op: IoOpType) -> Result<(), IoError> {
+        if !self.is_degraded() {
+            return Ok(());
+        }
+        match (self.degraded_policy, op) {
+            (DegradedPolicy::BestEffort, _) => Ok(()),
+            (DegradedPolicy::ReadOnly, IoOpType::Write) => Err(IoError::ReadOnly),
+            _ => self.unrecoverable(byte_offset).map_or(Ok(()), Err),
+        }
+    }
+
+    /// `unrecoverable` returns why data at `byte_offset` cannot be served, if it cannot.
+    ///
+    /// Layouts without redundancy need every member; the others tolerate as many
+    /// missing or untrusted members as they have redundant chunks per stripe.
+    pub(super) fn unrecoverable(&self, byte_offset: u64) -> Option<IoError> {
+        let restore = self.layout.as_restore().is_some();
+        let mut unavailable = self
+            .array
+            .0
+            .iter()
+            .filter(|d| d.is_missing() || (restore && d.needs_rebuild));
+        let first = unavailable.next()?;
+        if !restore {
+            return Some(IoError::DiskMissing {
+                path: first.path().to_path_buf(),
+            });
+        }
+        if 1 + unavailable.count() > T::FAULT_TOLERANCE {
+            let (stripe_index, _) = locate_byte(byte_offset, 0, &self.geom);
+            return Some(IoError::Uncorrectable { stripe_index });
+        }
+        None
+    }
+}
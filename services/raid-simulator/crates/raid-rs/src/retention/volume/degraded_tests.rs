@@ -0,0 +1,72 @@
+use super::test_utils::{fresh_volume, synced_volume};
+use super::*;
+use crate::layout::stripe::raid0::RAID0;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn make_raid0(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID0<TEST_DISKS, CHUNK_SIZE>> {
+    fresh_volume(dir, DISK_LEN, RAID0::zero())
+}
+
+fn make_raid3(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+#[test]
+fn fail_fast_is_the_default_policy() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_raid0(&dir);
+    volume.try_write_bytes(0, &[9; 12]).expect("healthy write");
+    volume.fail_disk(1).expect("fail disk");
+
+    assert_eq!(volume.degraded_policy(), DegradedPolicy::FailFast);
+    assert!(volume.is_degraded());
+    let mut out = [0u8; 12];
+    let err = volume.try_read_bytes(0, &mut out).unwrap_err();
+    assert!(matches!(err, Error::Degraded(IoError::DiskMissing { .. })));
+}
+
+#[test]
+fn best_effort_reads_lost_chunks_as_zeros() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_raid0(&dir);
+    volume.try_write_bytes(0, &[9; 12]).expect("healthy write");
+    volume.fail_disk(1).expect("fail disk");
+    volume.set_degraded_policy(DegradedPolicy::BestEffort);
+
+    let mut out = [0xAA; 12];
+    volume
+        .try_read_bytes(0, &mut out)
+        .expect("best-effort read");
+
+    assert_eq!(out, [9, 9, 9, 9, 0, 0, 0, 0, 9, 9, 9, 9]);
+}
+
+#[test]
+fn read_only_refuses_writes_but_serves_reconstructable_reads() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_raid3(&dir);
+    volume.try_write_bytes(0, &[5; 16]).expect("healthy write");
+    volume.set_degraded_policy(DegradedPolicy::ReadOnly);
+    volume.fail_disk(0).expect("fail disk");
+
+    let err = volume.try_write_bytes(0, &[6; 4]).unwrap_err();
+    assert!(matches!(err, Error::Degraded(IoError::ReadOnly)));
+
+    let mut out = [0u8; 16];
+    volume
+        .try_read_bytes(0, &mut out)
+        .expect("reconstructed read");
+    assert_eq!(out, [5; 16]);
+
+    volume.fail_disk(1).expect("fail second disk");
+    let err = volume.try_read_bytes(0, &mut out).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Degraded(IoError::Uncorrectable { stripe_index: 0 })
+    ));
+}
@@ -0,0 +1,724 @@
+//! Object-safe view over `Volume` for callers that pick the layout at runtime.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::Result;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::array::ArrayStatus;
+use crate::retention::disk::{DiskHealth, WearPlan, WriteCachePlan, WriteCacheStats};
+use crate::retention::volume::{
+    BalanceProgress, ByteLocation, CheckReport, ChunkMapping, CompressionUsage, CrashPlan,
+    DiskStatus, Geometry, SnapshotInfo, SnapshotUsage, StripeCheck, StripeInspection, ThinUsage,
+    Volume, VolumeEvent, WriteHolePolicy, WriteHoleStats,
+};
+
+/// `DynVolume` exposes logical volume operations without the const-generic geometry.
+pub trait DynVolume: Send {
+    /// `logical_capacity_bytes` returns the logical data capacity of the volume.
+    fn logical_capacity_bytes(&self) -> u64;
+
+    /// `read_bytes` reads bytes from the volume into the output buffer.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `out` - Output buffer to populate.
+    fn read_bytes(&mut self, byte_offset: u64, out: &mut [u8]);
+
+    /// `write_bytes` writes payload bytes into the volume at the logical offset.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `payload` - Bytes to write.
+    fn write_bytes(&mut self, byte_offset: u64, payload: &[u8]);
+
+    /// `try_read_bytes` reads bytes from the volume, failing instead of returning zeros.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `out` - Output buffer to populate.
+    ///
+    /// # Errors
+    /// Returns `IoError` if the range cannot be served.
+    fn try_read_bytes(&mut self, byte_offset: u64, out: &mut [u8]) -> Result<()>;
+
+    /// `try_write_bytes` writes payload bytes, allocating thin stripes on first write.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `payload` - Bytes to write.
+    ///
+    /// # Errors
+    /// Returns `IoError` if the range cannot be served, or `PoolExhausted` if a thin
+    /// volume cannot back the write.
+    fn try_write_bytes(&mut self, byte_offset: u64, payload: &[u8]) -> Result<()>;
+
+    /// `try_read_with` reads bytes and hands them to `f`, borrowed from the members
+    /// when possible.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `len` - Number of bytes to read.
+    /// * `f` - Consumer of the data; called once if the read succeeds.
+    ///
+    /// # Errors
+    /// Returns `IoError` if the range cannot be served.
+    fn try_read_with(
+        &mut self,
+        byte_offset: u64,
+        len: usize,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> Result<()>;
+
+    /// `read_stripes` reads the data of a range of logical stripes.
+    ///
+    /// # Arguments
+    /// * `stripes` - Logical stripe indices to read.
+    ///
+    /// # Errors
+    /// Returns `IoError` if the range cannot be served.
+    fn read_stripes(&mut self, stripes: Range<u64>) -> Result<Vec<u8>>;
+
+    /// `write_stripes` overwrites a range of logical stripes with new data.
+    ///
+    /// # Arguments
+    /// * `stripes` - Logical stripe indices to write.
+    /// * `data` - Exactly `bytes_per_stripe` bytes per stripe.
+    ///
+    /// # Errors
+    /// Returns an error if `data` does not fill the range or the range cannot be served.
+    fn write_stripes(&mut self, stripes: Range<u64>, data: &[u8]) -> Result<()>;
+
+    /// `failed_disks` returns the number of missing disks.
+    fn failed_disks(&self) -> u32;
+
+    /// `fail_disk` marks the disk at the given index as failed.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to fail.
+    ///
+    /// # Errors
+    /// Returns an error if the disk cannot be failed.
+    fn fail_disk(&mut self, i: usize) -> Result<()>;
+
+    /// `replace_disk` replaces the disk image at the given index with a blank one.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to replace.
+    ///
+    /// # Errors
+    /// Returns an error if the disk cannot be replaced.
+    fn replace_disk(&mut self, i: usize) -> Result<()>;
+
+    /// `replace_disk_with` replaces the disk at the given index with a blank image at `path`.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to replace.
+    /// * `path` - Path of the new disk image.
+    /// * `len` - Length of the new disk image in bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the disk cannot be replaced.
+    fn replace_disk_with(&mut self, i: usize, path: &Path, len: u64) -> Result<()>;
+
+    /// `mark_stale` schedules a full rebuild of a member holding outdated contents.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the stale disk.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is out of range or missing.
+    fn mark_stale(&mut self, i: usize) -> Result<()>;
+
+    /// `mark_rejoined` schedules a bitmap-based resync of a member that is back with
+    /// the contents it had when it left.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the rejoined disk.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range or the disk is missing.
+    fn mark_rejoined(&mut self, i: usize) -> Result<()>;
+
+    /// `readd_disk` reattaches the last failed image of a disk for a bitmap-based resync.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to reattach.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is still attached or has no failed image to restore.
+    fn readd_disk(&mut self, i: usize) -> Result<()>;
+
+    /// `split_mirror` detaches a RAID1 member as a standalone, consistent image.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the member to split off.
+    ///
+    /// # Errors
+    /// Returns an error if the layout is not a mirror or the member cannot be split off.
+    fn split_mirror(&mut self, i: usize) -> Result<PathBuf>;
+
+    /// `re_add` restores a split-off member and schedules a bitmap-based resync.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the member to re-add.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is still attached or has no split image to restore.
+    fn re_add(&mut self, i: usize) -> Result<()>;
+
+    /// `rebuild_disk_upto` rebuilds a specific disk up to the provided logical end.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to rebuild.
+    /// * `logical_end` - Logical byte position to rebuild up to.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is out of range or still missing.
+    fn rebuild_disk_upto(&mut self, i: usize, logical_end: u64) -> Result<()>;
+
+    /// `locate` maps a logical byte offset to its stripe, chunk, and disk position.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    fn locate(&self, byte_offset: u64) -> ByteLocation;
+
+    /// `geometry` returns the stripe geometry of the volume's layout.
+    fn geometry(&self) -> Geometry;
+
+    /// `map_logical` maps a logical byte offset to the chunk every disk stores for its stripe.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    fn map_logical(&self, byte_offset: u64) -> Vec<ChunkMapping>;
+
+    /// `inspect_stripe` reads a stripe without repairing it and compares it to its re-encoding.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Index of the stripe to inspect.
+    fn inspect_stripe(&mut self, stripe_index: u64) -> StripeInspection;
+
+    /// `physical_stripes` returns the number of stripes stored on the member disks.
+    fn physical_stripes(&self) -> u64;
+
+    /// `check_stripe` compares the chunks of one physical stripe without writing.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Physical index of the stripe to check.
+    fn check_stripe(&mut self, stripe_index: u64) -> StripeCheck;
+
+    /// `check` scans every physical stripe and returns the combined report.
+    fn check(&mut self) -> CheckReport;
+
+    /// `subscribe` returns a receiver for all volume events emitted from now on.
+    fn subscribe(&mut self) -> std::sync::mpsc::Receiver<VolumeEvent>;
+
+    /// `disk_statuses` returns a list of disk status summaries.
+    fn disk_statuses(&self) -> Vec<DiskStatus>;
+
+    /// `disk_healths` returns the SMART-like attributes of every member disk.
+    fn disk_healths(&self) -> Vec<DiskHealth>;
+
+    /// `simulate_wear` makes IO grow defects on every member disk.
+    ///
+    /// # Arguments
+    /// * `plan` - Wear model of the members; each disk derives its own seed from it.
+    fn simulate_wear(&mut self, plan: WearPlan);
+
+    /// `enable_write_cache` puts a drive write cache in front of every member disk.
+    ///
+    /// # Arguments
+    /// * `plan` - Capacity and volatility of the caches; each disk derives its own seed.
+    fn enable_write_cache(&mut self, plan: WriteCachePlan);
+
+    /// `flush` writes the cached writes of all members to their images.
+    fn flush(&mut self);
+
+    /// `sync` makes every completed write durable on all members.
+    ///
+    /// # Errors
+    /// Returns an error if a disk image cannot be flushed.
+    fn sync(&mut self) -> Result<()>;
+
+    /// `crash` simulates power loss on every member at once.
+    fn crash(&mut self);
+
+    /// `resync` rewrites the redundancy of every physical stripe from its data.
+    fn resync(&mut self);
+
+    /// `write_cache_stats` returns the write cache counters summed over all members.
+    fn write_cache_stats(&self) -> WriteCacheStats;
+
+    /// `simulate_crashes` starts caching writes on every member disk until `sync`.
+    ///
+    /// # Arguments
+    /// * `plan` - Seed, optional crash point and whether the crash tears a stripe.
+    fn simulate_crashes(&mut self, plan: CrashPlan);
+
+    /// `set_write_hole_policy` changes how stripe writes are protected.
+    ///
+    /// # Arguments
+    /// * `policy` - Policy to apply from now on.
+    fn set_write_hole_policy(&mut self, policy: WriteHolePolicy);
+
+    /// `write_hole_stats` returns the write-hole counters since the volume was opened.
+    fn write_hole_stats(&self) -> WriteHoleStats;
+
+    /// `disk_status_string` returns a human-readable, mdstat-style status summary.
+    fn disk_status_string(&self) -> String;
+
+    /// `status` returns the array status including layout and rebuild progress.
+    fn status(&self) -> ArrayStatus;
+
+    /// `stripes_needed_for_logical_end` returns the stripe count for the given logical end.
+    ///
+    /// # Arguments
+    /// * `logical_end` - Logical byte position at the end of interest.
+    fn stripes_needed_for_logical_end(&self, logical_end: u64) -> u64;
+
+    /// `stripes_to_repair` returns the physical stripes a rebuild up to `logical_end` must read.
+    ///
+    /// # Arguments
+    /// * `logical_end` - Logical byte position to rebuild up to.
+    fn stripes_to_repair(&self, logical_end: u64) -> Vec<u64>;
+
+    /// `repair_stripe` forces a stripe read to rebuild missing data.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Physical index of the stripe to repair.
+    fn repair_stripe(&mut self, stripe_index: u64);
+
+    /// `repair_stripes` forces reads of many stripes, consecutive ones together.
+    ///
+    /// # Arguments
+    /// * `stripes` - Physical indices of the stripes to repair.
+    fn repair_stripes(&mut self, stripes: &[u64]);
+
+    /// `clear_needs_rebuild_all` clears rebuild flags on all operational disks.
+    fn clear_needs_rebuild_all(&mut self);
+
+    /// `rebuild_all` rebuilds all disks across the full logical range.
+    ///
+    /// # Errors
+    /// Returns an error if rebuilding fails.
+    fn rebuild_all(&mut self) -> Result<()>;
+
+    /// `reserved_stripes` returns the stripes that hold snapshot, thin or compression
+    /// metadata.
+    fn reserved_stripes(&self) -> std::ops::Range<u64>;
+
+    /// `is_thin` reports whether the volume is thinly provisioned.
+    fn is_thin(&self) -> bool;
+
+    /// `thin_usage` returns pool usage for thin volumes.
+    fn thin_usage(&self) -> Option<ThinUsage>;
+
+    /// `init_thin` converts the volume into a thin volume with the given virtual size.
+    ///
+    /// # Arguments
+    /// * `virtual_bytes` - Logical capacity advertised by the thin volume.
+    /// * `data_end` - End of the live data that must stay readable.
+    ///
+    /// # Errors
+    /// Returns an error if the volume cannot be converted.
+    fn init_thin(&mut self, virtual_bytes: u64, data_end: u64) -> Result<()>;
+
+    /// `is_compressed` reports whether the volume compresses its blocks.
+    fn is_compressed(&self) -> bool;
+
+    /// `begin_balance` starts moving the data of `source` onto this volume.
+    ///
+    /// # Arguments
+    /// * `source` - Volume with the geometry the data is moved from.
+    /// * `source_disks` - Member count of `source`, recorded in the checkpoint.
+    /// * `position` - Logical bytes already moved, a multiple of the stripe size.
+    ///
+    /// # Errors
+    /// Returns an error if the balance cannot be started.
+    fn begin_balance(
+        &mut self,
+        source: Box<dyn DynVolume>,
+        source_disks: usize,
+        position: u64,
+    ) -> Result<()>;
+
+    /// `balance_progress` returns how much data a running balance has moved.
+    fn balance_progress(&self) -> Option<BalanceProgress>;
+
+    /// `balance_step` moves up to `max_stripes` stripes from the source volume.
+    ///
+    /// # Errors
+    /// Returns an error if the stripes or the checkpoint cannot be written.
+    fn balance_step(&mut self, max_stripes: usize) -> Result<usize>;
+
+    /// `compression_usage` returns how well the blocks of a compressed volume compress.
+    fn compression_usage(&self) -> Option<CompressionUsage>;
+
+    /// `init_compression` converts the volume into a compressed volume with the given
+    /// virtual size.
+    ///
+    /// # Arguments
+    /// * `virtual_bytes` - Logical capacity advertised by the compressed volume.
+    /// * `data_end` - End of the live data that must stay readable.
+    ///
+    /// # Errors
+    /// Returns an error if the volume cannot be converted.
+    fn init_compression(&mut self, virtual_bytes: u64, data_end: u64) -> Result<()>;
+
+    /// `snapshots_enabled` reports whether the volume has a snapshot region.
+    fn snapshots_enabled(&self) -> bool;
+
+    /// `init_snapshots` reserves the tail of the volume for snapshot storage.
+    ///
+    /// # Arguments
+    /// * `reserve_bytes` - Number of logical bytes to reserve.
+    /// * `data_end` - End of the live data that must stay outside the reserved region.
+    ///
+    /// # Errors
+    /// Returns an error if the region cannot be reserved.
+    fn init_snapshots(&mut self, reserve_bytes: u64, data_end: u64) -> Result<()>;
+
+    /// `disable_snapshots` releases the reserved region back to the live volume.
+    ///
+    /// # Errors
+    /// Returns an error if snapshots are not enabled or any snapshot still exists.
+    fn disable_snapshots(&mut self) -> Result<()>;
+
+    /// `snapshot_list` returns the snapshots stored in the volume.
+    fn snapshot_list(&self) -> Vec<SnapshotInfo>;
+
+    /// `snapshot_usage` returns copy-on-write pool usage when snapshots are enabled.
+    fn snapshot_usage(&self) -> Option<SnapshotUsage>;
+
+    /// `snapshot_create` records the current volume contents under a new name.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the new snapshot.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot cannot be created.
+    fn snapshot_create(&mut self, name: &str) -> Result<SnapshotInfo>;
+
+    /// `snapshot_delete` removes a snapshot and releases its preserved blocks.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the snapshot to delete.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist.
+    fn snapshot_delete(&mut self, name: &str) -> Result<()>;
+
+    /// `snapshot_rollback` restores the live volume to the contents of a snapshot.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the snapshot to restore.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist or is no longer valid.
+    fn snapshot_rollback(&mut self, name: &str) -> Result<()>;
+
+    /// `read_snapshot_bytes` reads bytes as they were when a snapshot was taken.
+    ///
+    /// # Arguments
+    /// * `index` - Snapshot table index.
+    /// * `byte_offset` - Logical byte offset within the snapshot.
+    /// * `out` - Output buffer to populate.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist, is invalid, or the range is out of bounds.
+    fn read_snapshot_bytes(&mut self, index: usize, byte_offset: u64, out: &mut [u8])
+    -> Result<()>;
+
+    /// `snapshot_changed_ranges` returns the logical byte ranges written since a snapshot.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the snapshot to compare against.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist or is no longer valid.
+    fn snapshot_changed_ranges(&self, name: &str) -> Result<Vec<Range<u64>>>;
+}
+
+impl<const D: usize, const N: usize, T> DynVolume for Volume<D, N, T>
+where
+    T: Stripe<D, N> + Send,
+{
+    fn logical_capacity_bytes(&self) -> u64 {
+        Self::logical_capacity_bytes(self)
+    }
+
+    fn read_bytes(&mut self, byte_offset: u64, out: &mut [u8]) {
+        Self::read_bytes(self, byte_offset, out);
+    }
+
+    fn write_bytes(&mut self, byte_offset: u64, payload: &[u8]) {
+        Self::write_bytes(self, byte_offset, payload);
+    }
+
+    fn try_read_bytes(&mut self, byte_offset: u64, out: &mut [u8]) -> Result<()> {
+        Self::try_read_bytes(self, byte_offset, out)
+    }
+
+    fn try_write_bytes(&mut self, byte_offset: u64, payload: &[u8]) -> Result<()> {
+        Self::try_write_bytes(self, byte_offset, payload)
+    }
+
+    fn try_read_with(
+        &mut self,
+        byte_offset: u64,
+        len: usize,
+        f: &mut dyn FnMut(&[u8]),
+    ) -> Result<()> {
+        Self::try_read_with(self, byte_offset, len, f)
+    }
+
+    fn read_stripes(&mut self, stripes: Range<u64>) -> Result<Vec<u8>> {
+        Self::read_stripes(self, stripes)
+    }
+
+    fn write_stripes(&mut self, stripes: Range<u64>, data: &[u8]) -> Result<()> {
+        Self::write_stripes(self, stripes, data)
+    }
+
+    fn failed_disks(&self) -> u32 {
+        Self::failed_disks(self)
+    }
+
+    fn fail_disk(&mut self, i: usize) -> Result<()> {
+        Self::fail_disk(self, i)
+    }
+
+    fn replace_disk(&mut self, i: usize) -> Result<()> {
+        Self::replace_disk(self, i)
+    }
+
+    fn replace_disk_with(&mut self, i: usize, path: &Path, len: u64) -> Result<()> {
+        Self::replace_disk_with(self, i, path, len)
+    }
+
+    fn mark_stale(&mut self, i: usize) -> Result<()> {
+        Self::mark_stale(self, i)
+    }
+
+    fn mark_rejoined(&mut self, i: usize) -> Result<()> {
+        Self::mark_rejoined(self, i)
+    }
+
+    fn readd_disk(&mut self, i: usize) -> Result<()> {
+        Self::readd_disk(self, i)
+    }
+
+    fn split_mirror(&mut self, i: usize) -> Result<PathBuf> {
+        Self::split_mirror(self, i)
+    }
+
+    fn re_add(&mut self, i: usize) -> Result<()> {
+        Self::re_add(self, i)
+    }
+
+    fn rebuild_disk_upto(&mut self, i: usize, logical_end: u64) -> Result<()> {
+        Self::rebuild_disk_upto(self, i, logical_end)
+    }
+
+    fn locate(&self, byte_offset: u64) -> ByteLocation {
+        Self::locate(self, byte_offset)
+    }
+
+    fn geometry(&self) -> Geometry {
+        Self::geometry(self)
+    }
+
+    fn map_logical(&self, byte_offset: u64) -> Vec<ChunkMapping> {
+        Self::map_logical(self, byte_offset)
+    }
+
+    fn inspect_stripe(&mut self, stripe_index: u64) -> StripeInspection {
+        Self::inspect_stripe(self, stripe_index)
+    }
+
+    fn physical_stripes(&self) -> u64 {
+        Self::physical_stripes(self)
+    }
+
+    fn check_stripe(&mut self, stripe_index: u64) -> StripeCheck {
+        Self::check_stripe(self, stripe_index)
+    }
+
+    fn check(&mut self) -> CheckReport {
+        Self::check(self)
+    }
+
+    fn subscribe(&mut self) -> std::sync::mpsc::Receiver<VolumeEvent> {
+        Self::subscribe(self)
+    }
+
+    fn disk_statuses(&self) -> Vec<DiskStatus> {
+        Self::disk_statuses(self)
+    }
+
+    fn disk_healths(&self) -> Vec<DiskHealth> {
+        Self::disk_healths(self)
+    }
+
+    fn simulate_wear(&mut self, plan: WearPlan) {
+        Self::simulate_wear(self, plan);
+    }
+
+    fn enable_write_cache(&mut self, plan: WriteCachePlan) {
+        Self::enable_write_cache(self, plan);
+    }
+
+    fn flush(&mut self) {
+        Self::flush(self);
+    }
+
+    fn sync(&mut self) -> Result<()> {
+        Self::sync(self)
+    }
+
+    fn crash(&mut self) {
+        Self::crash(self);
+    }
+
+    fn resync(&mut self) {
+        Self::resync(self);
+    }
+
+    fn write_cache_stats(&self) -> WriteCacheStats {
+        Self::write_cache_stats(self)
+    }
+
+    fn simulate_crashes(&mut self, plan: CrashPlan) {
+        Self::simulate_crashes(self, plan);
+    }
+
+    fn set_write_hole_policy(&mut self, policy: WriteHolePolicy) {
+        Self::set_write_hole_policy(self, policy);
+    }
+
+    fn write_hole_stats(&self) -> WriteHoleStats {
+        Self::write_hole_stats(self)
+    }
+
+    fn disk_status_string(&self) -> String {
+        Self::disk_status_string(self)
+    }
+
+    fn status(&self) -> ArrayStatus {
+        Self::status(self)
+    }
+
+    fn stripes_needed_for_logical_end(&self, logical_end: u64) -> u64 {
+        Self::stripes_needed_for_logical_end(self, logical_end)
+    }
+
+    fn stripes_to_repair(&self, logical_end: u64) -> Vec<u64> {
+        Self::stripes_to_repair(self, logical_end)
+    }
+
+    fn repair_stripe(&mut self, stripe_index: u64) {
+        Self::repair_stripe(self, stripe_index);
+    }
+
+    fn repair_stripes(&mut self, stripes: &[u64]) {
+        Self::repair_stripes(self, stripes);
+    }
+
+    fn clear_needs_rebuild_all(&mut self) {
+        Self::clear_needs_rebuild_all(self);
+    }
+
+    fn rebuild_all(&mut self) -> Result<()> {
+        Self::rebuild_all(self)
+    }
+
+    fn reserved_stripes(&self) -> std::ops::Range<u64> {
+        Self::reserved_stripes(self)
+    }
+
+    fn is_thin(&self) -> bool {
+        Self::is_thin(self)
+    }
+
+    fn thin_usage(&self) -> Option<ThinUsage> {
+        Self::thin_usage(self)
+    }
+
+    fn init_thin(&mut self, virtual_bytes: u64, data_end: u64) -> Result<()> {
+        Self::init_thin(self, virtual_bytes, data_end)
+    }
+
+    fn is_compressed(&self) -> bool {
+        Self::is_compressed(self)
+    }
+
+    fn begin_balance(
+        &mut self,
+        source: Box<dyn DynVolume>,
+        source_disks: usize,
+        position: u64,
+    ) -> Result<()> {
+        Self::begin_balance(self, source, source_disks, position)
+    }
+
+    fn balance_progress(&self) -> Option<BalanceProgress> {
+        Self::balance_progress(self)
+    }
+
+    fn balance_step(&mut self, max_stripes: usize) -> Result<usize> {
+        Self::balance_step(self, max_stripes)
+    }
+
+    fn compression_usage(&self) -> Option<CompressionUsage> {
+        Self::compression_usage(self)
+    }
+
+    fn init_compression(&mut self, virtual_bytes: u64, data_end: u64) -> Result<()> {
+        Self::init_compression(self, virtual_bytes, data_end)
+    }
+
+    fn snapshots_enabled(&self) -> bool {
+        Self::snapshots_enabled(self)
+    }
+
+    fn init_snapshots(&mut self, reserve_bytes: u64, data_end: u64) -> Result<()> {
+        Self::init_snapshots(self, reserve_bytes, data_end)
+    }
+
+    fn disable_snapshots(&mut self) -> Result<()> {
+        Self::disable_snapshots(self)
+    }
+
+    fn snapshot_list(&self) -> Vec<SnapshotInfo> {
+        Self::snapshot_list(self)
+    }
+
+    fn snapshot_usage(&self) -> Option<SnapshotUsage> {
+        Self::snapshot_usage(self)
+    }
+
+    fn snapshot_create(&mut self, name: &str) -> Result<SnapshotInfo> {
+        Self::snapshot_create(self, name)
+    }
+
+    fn snapshot_delete(&mut self, name: &str) -> Result<()> {
+        Self::snapshot_delete(self, name)
+    }
+
+    fn snapshot_rollback(&mut self, name: &str) -> Result<()> {
+        Self::snapshot_rollback(self, name)
+    }
+
+    fn snapshot_changed_ranges(&self, name: &str) -> Result<Vec<Range<u64>>> {
+        Self::snapshot_changed_ranges(self, name)
+    }
+
+    fn read_snapshot_bytes(
+        &mut self,
+        index: usize,
+        byte_offset: u64,
+        out: &mut [u8],
+    ) -> Result<()> {
+        Self::read_snapshot_bytes(self, index, byte_offset, out)
+    }
+}
@@ -0,0 +1,98 @@
+//! Change notifications for consumers that would otherwise poll disk statuses.
+//!
+//! Subscribers receive events over a channel. Sending never blocks the volume, and
+//! subscribers whose receiver was dropped are forgotten on the next event.
+
+use std::sync::mpsc;
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::{DiskStatus, RebuildProgress, StripeCheck, Volume};
+
+/// `DiskChange` names the transition reported by a `VolumeEvent::Disk`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DiskChange {
+    Failed,
+    Replaced,
+    Reattached,
+    Rebuilt,
+    /// The disk rejoined with contents older than the array and awaits a rebuild.
+    Stale,
+    /// The disk was split off as a standalone mirror image and awaits a re-add.
+    Split,
+}
+
+/// `VolumeEvent` is a notification about a state change in the volume.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VolumeEvent {
+    /// A member disk changed state; `status` is its state after the change.
+    Disk {
+        change: DiskChange,
+        status: DiskStatus,
+    },
+    /// A rebuild pass over `stripes` physical stripes started.
+    RebuildStarted { stripes: u64 },
+    /// The disks in `disks` no longer need a rebuild.
+    RebuildFinished { disks: Vec<usize> },
+    /// A consistency check found a stripe that is not clean.
+    CheckFinding {
+        stripe_index: u64,
+        verdict: StripeCheck,
+    },
+    /// A read hit stripes that cannot be reconstructed; `stripes` is the new total.
+    Uncorrectable { stripes: u64, read_only: bool },
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `subscribe` returns a receiver for all events emitted from now on.
+    pub fn subscribe(&mut self) -> mpsc::Receiver<VolumeEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.watchers.push(tx);
+        rx
+    }
+
+    /// `begin_rebuild` returns the stripes to repair and announces the rebuild.
+    ///
+    /// Progress reported by `status` counts the `repair_stripe` calls that follow.
+    ///
+    /// # Arguments
+    /// * `logical_end` - Logical byte position to rebuild up to.
+    pub fn begin_rebuild(&mut self, logical_end: u64) -> Vec<u64> {
+        let stripes = self.stripes_to_repair(logical_end);
+        self.rebuild = Some(RebuildProgress::new(stripes.len() as u64, D));
+        self.emit(&VolumeEvent::RebuildStarted {
+            stripes: stripes.len() as u64,
+        });
+        stripes
+    }
+
+    /// `emit` delivers an event to every live subscriber.
+    pub(super) fn emit(&mut self, event: &VolumeEvent) {
+        if self.watchers.is_empty() {
+            return;
+        }
+        self.watchers.retain(|tx| tx.send(event.clone()).is_ok());
+    }
+
+    /// `emit_disk` reports the current status of a disk after a change.
+    pub(super) fn emit_disk(&mut self, change: DiskChange, i: usize) {
+        if let Some(status) = self.disk_statuses().get(i).copied() {
+            self.emit(&VolumeEvent::Disk { change, status });
+        }
+    }
+
+    /// `emit_rebuilt` reports disks whose rebuild flag was cleared.
+    pub(super) fn emit_rebuilt(&mut self, before: &[DiskStatus]) {
+        let disks: Vec<usize> = before
+            .iter()
+            .filter(|s| s.needs_rebuild && !self.array.0[s.index].needs_rebuild)
+            .map(|s| s.index)
+            .collect();
+        if disks.is_empty() {
+            return;
+        }
+        for &i in &disks {
+            self.emit_disk(DiskChange::Rebuilt, i);
+        }
+        self.emit(&VolumeEvent::RebuildFinished { disks });
+    }
+}
@@ -0,0 +1,113 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+#[test]
+fn disk_lifecycle_is_reported_in_order() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let events = volume.subscribe();
+
+    volume.fail_disk(1).expect("fail disk");
+    volume.replace_disk(1).expect("replace disk");
+    volume.rebuild_disk(1).expect("rebuild disk");
+
+    let got: Vec<VolumeEvent> = events.try_iter().collect();
+    let status = |missing, needs_rebuild| DiskStatus {
+        index: 1,
+        missing,
+        needs_rebuild,
+    };
+    assert_eq!(
+        got,
+        vec![
+            VolumeEvent::Disk {
+                change: DiskChange::Failed,
+                status: status(true, false),
+            },
+            VolumeEvent::Disk {
+                change: DiskChange::Replaced,
+                status: status(false, true),
+            },
+            VolumeEvent::RebuildStarted {
+                stripes: DISK_LEN / CHUNK_SIZE as u64,
+            },
+            VolumeEvent::Disk {
+                change: DiskChange::Rebuilt,
+                status: status(false, false),
+            },
+            VolumeEvent::RebuildFinished { disks: vec![1] },
+        ]
+    );
+}
+
+#[test]
+fn stale_disks_are_reported_and_rebuilt() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"abcdefgh");
+    volume.array.0[0].write_at(0, &[0xff]);
+    let events = volume.subscribe();
+
+    volume.mark_stale(0).expect("mark stale");
+    assert!(volume.mark_stale(TEST_DISKS).is_err());
+    volume.rebuild_disk(0).expect("rebuild disk");
+
+    let got: Vec<VolumeEvent> = events.try_iter().take(1).collect();
+    assert_eq!(
+        got,
+        vec![VolumeEvent::Disk {
+            change: DiskChange::Stale,
+            status: DiskStatus {
+                index: 0,
+                missing: false,
+                needs_rebuild: true,
+            },
+        }]
+    );
+    assert!(volume.check().is_clean());
+}
+
+#[test]
+fn check_findings_are_reported() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"abcdefgh");
+    volume.array.0[2].write_at(0, &[0xff]);
+    let events = volume.subscribe();
+
+    volume.check();
+
+    let got: Vec<VolumeEvent> = events.try_iter().collect();
+    assert_eq!(
+        got,
+        vec![VolumeEvent::CheckFinding {
+            stripe_index: 0,
+            verdict: StripeCheck::Repairable {
+                mismatched_chunks: 1
+            },
+        }]
+    );
+}
+
+#[test]
+fn dropped_subscribers_are_forgotten() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    drop(volume.subscribe());
+    let events = volume.subscribe();
+
+    volume.fail_disk(0).expect("fail disk");
+
+    assert_eq!(volume.watchers.len(), 1);
+    assert_eq!(events.try_iter().count(), 1);
+}
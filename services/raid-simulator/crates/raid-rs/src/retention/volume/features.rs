@@ -0,0 +1,80 @@
+//! Volume features recorded outside the data a volume serves.
+//!
+//! Thin provisioning, compression and snapshots keep their maps and superblocks in
+//! the tail stripes of the array, which a plain volume hands out like any other
+//! logical bytes. Which of them a volume uses is therefore recorded in the
+//! metadata tail of every member, and a volume only looks for their superblocks
+//! when its members say it uses them. Data that merely looks like one of those
+//! superblocks cannot change how the volume is opened.
+
+use crate::Result;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::disk::VOLUME_METADATA_OFFSET;
+use crate::retention::volume::Volume;
+
+const FEATURES_MAGIC: [u8; 8] = *b"RAIDFEA1";
+const FEATURES_LEN: usize = 16;
+const THIN: u8 = 1;
+const COMPRESSED: u8 = 1 << 1;
+const SNAPSHOTS: u8 = 1 << 2;
+
+/// `Features` is the set of features recorded on the members of a volume.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(super) struct Features(u8);
+
+impl Features {
+    pub(super) const fn thin(self) -> bool {
+        self.0 & THIN != 0
+    }
+
+    pub(super) const fn compressed(self) -> bool {
+        self.0 & COMPRESSED != 0
+    }
+
+    pub(super) const fn snapshots(self) -> bool {
+        self.0 & SNAPSHOTS != 0
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `load_features` returns the features recorded on the attached members.
+    ///
+    /// A member replaced since a feature was enabled carries no record, so the
+    /// records of all members are combined.
+    pub(super) fn load_features(&self) -> Features {
+        let mut bits = 0;
+        for disk in &self.array.0 {
+            let mut raw = [0u8; FEATURES_LEN];
+            if disk
+                .read_metadata(VOLUME_METADATA_OFFSET, &mut raw)
+                .unwrap_or(false)
+                && raw[0..8] == FEATURES_MAGIC
+            {
+                bits |= raw[8];
+            }
+        }
+        Features(bits)
+    }
+
+    /// `save_features` records the features the volume uses on every attached member.
+    ///
+    /// # Errors
+    /// Returns an error if a member's metadata tail cannot be written.
+    pub(super) fn save_features(&mut self) -> Result<()> {
+        let mut raw = [0u8; FEATURES_LEN];
+        raw[0..8].copy_from_slice(&FEATURES_MAGIC);
+        for (enabled, bit) in [
+            (self.thin.is_some(), THIN),
+            (self.compression.is_some(), COMPRESSED),
+            (self.snapshots.is_some(), SNAPSHOTS),
+        ] {
+            if enabled {
+                raw[8] |= bit;
+            }
+        }
+        for disk in &mut self.array.0 {
+            disk.write_metadata(VOLUME_METADATA_OFFSET, &raw)?;
+        }
+        Ok(())
+    }
+}
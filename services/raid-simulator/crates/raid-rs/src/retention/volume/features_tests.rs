@@ -0,0 +1,81 @@
+use super::test_utils::{disk_paths, synced_volume};
+use super::*;
+use crate::layout::stripe::raid0::RAID0;
+use crate::retention::disk::VOLUME_METADATA_OFFSET;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 2;
+const CHUNK_SIZE: usize = 64;
+const DISK_LEN: u64 = 4096;
+
+type TestVolume = Volume<TEST_DISKS, CHUNK_SIZE, RAID0<TEST_DISKS, CHUNK_SIZE>>;
+
+fn make_volume(dir: &TempDir) -> TestVolume {
+    synced_volume(dir, DISK_LEN, RAID0::zero())
+}
+
+/// `forget_features` erases the feature records, leaving the data of the members
+/// as a plain volume's user could have written it.
+fn forget_features(volume: &mut TestVolume) {
+    for disk in &mut volume.array.0 {
+        disk.write_metadata(VOLUME_METADATA_OFFSET, &[0; 16])
+            .expect("erase features");
+    }
+}
+
+/// `plain_copy_of` writes the logical bytes of `source` onto a plain volume.
+fn plain_copy_of(source: &TempDir) -> (TempDir, TestVolume) {
+    let dir = TempDir::new().unwrap();
+    for (from, to) in disk_paths::<TEST_DISKS>(source)
+        .iter()
+        .zip(disk_paths::<TEST_DISKS>(&dir))
+    {
+        std::fs::copy(from, to).expect("copy image");
+    }
+    let mut volume = make_volume(&dir);
+    forget_features(&mut volume);
+    drop(volume);
+    let volume = make_volume(&dir);
+    (dir, volume)
+}
+
+#[test]
+fn user_data_that_looks_like_feature_metadata_stays_data() {
+    let plain = make_volume(&TempDir::new().unwrap()).logical_capacity_bytes();
+    let setups: [fn(&mut TestVolume); 3] = [
+        |volume| volume.init_thin(256, 0).expect("init thin"),
+        |volume| volume.init_compression(4096, 0).expect("init compression"),
+        |volume| volume.init_snapshots(2048, 0).expect("init snapshots"),
+    ];
+    for setup in setups {
+        let source = TempDir::new().unwrap();
+        let mut volume = make_volume(&source);
+        setup(&mut volume);
+        drop(volume);
+        assert_ne!(make_volume(&source).logical_capacity_bytes(), plain);
+
+        let (_dir, mut copy) = plain_copy_of(&source);
+        assert!(!copy.is_thin() && !copy.is_compressed() && !copy.snapshots_enabled());
+        assert_eq!(copy.logical_capacity_bytes(), plain);
+        copy.try_write_bytes(0, b"data").expect("write");
+        let mut out = [0u8; 4];
+        copy.try_read_bytes(0, &mut out).expect("read");
+        assert_eq!(&out, b"data");
+    }
+}
+
+#[test]
+fn replaced_members_carry_the_features() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    volume.replace_disk(0).expect("replace");
+    for disk in &mut volume.array.0[1..] {
+        disk.write_metadata(VOLUME_METADATA_OFFSET, &[0; 16])
+            .expect("erase features");
+    }
+    assert!(volume.load_features().snapshots());
+
+    volume.disable_snapshots().expect("disable snapshots");
+    assert_eq!(volume.load_features(), features::Features::default());
+}
@@ -0,0 +1,204 @@
+//! Read-only introspection of how logical bytes land on the member disks.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::Geometry;
+use crate::retention::volume::mapper::{locate_byte, stripe_byte_offset};
+
+/// `ByteLocation` describes where a logical byte is stored.
+///
+/// `disk_index` is the member holding the byte's data chunk in its stripe, which
+/// for declustered layouts differs from stripe to stripe.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ByteLocation {
+    pub stripe_index: u64,
+    pub chunk_index: usize,
+    pub byte_in_chunk: usize,
+    pub disk_index: usize,
+    pub disk_offset: u64,
+}
+
+/// `ChunkMapping` is the chunk one disk stores for the stripe holding a logical byte.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkMapping {
+    pub disk: usize,
+    /// Offset on the disk that lines up with the logical byte.
+    pub disk_offset: u64,
+    pub role: ChunkRole,
+}
+
+/// `PlacementMap` counts the chunks every member stores over a range of stripes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacementMap {
+    pub stripes: u64,
+    /// Data chunks per disk.
+    pub data: Vec<u64>,
+    /// Mirror copies per disk.
+    pub mirror: Vec<u64>,
+    /// Parity chunks per disk.
+    pub parity: Vec<u64>,
+}
+
+/// `StripeInspection` captures the stored and expected contents of one stripe.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StripeInspection {
+    pub stripe_index: u64,
+    pub disk_offset: u64,
+    /// Raw chunk per disk; `None` when the disk is missing.
+    pub stored: Vec<Option<Vec<u8>>>,
+    /// Chunk per disk re-encoded from the stored data chunks; empty if the layout
+    /// cannot decode the stripe.
+    pub expected: Vec<Vec<u8>>,
+    /// Indices of present disks whose stored chunk differs from the expected one.
+    pub mismatched: Vec<usize>,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `geometry` returns the stripe geometry of the volume's layout.
+    pub const fn geometry(&self) -> Geometry {
+        self.geom
+    }
+
+    /// `locate` maps a logical byte offset to its stripe, chunk, and disk position.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    pub fn locate(&self, byte_offset: u64) -> ByteLocation {
+        let (stripe_index, in_stripe) = locate_byte(byte_offset, 0, &self.geom);
+        let chunk_index = in_stripe / self.geom.bytes_per_chunk;
+        let byte_in_chunk = in_stripe % self.geom.bytes_per_chunk;
+        ByteLocation {
+            stripe_index,
+            chunk_index,
+            byte_in_chunk,
+            disk_index: Self::data_disk(stripe_index, chunk_index).unwrap_or(chunk_index),
+            disk_offset: stripe_byte_offset::<N>(stripe_index) + byte_in_chunk as u64,
+        }
+    }
+
+    /// `map_logical` maps a logical byte offset to the chunk every disk stores for its stripe.
+    ///
+    /// All chunks of a stripe share one disk offset; the entry whose role is the byte's
+    /// data chunk (or any mirror) holds the byte itself.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    pub fn map_logical(&self, byte_offset: u64) -> Vec<ChunkMapping> {
+        let loc = self.locate(byte_offset);
+        (0..D)
+            .map(|disk| ChunkMapping {
+                disk,
+                disk_offset: loc.disk_offset,
+                role: T::role_at(loc.stripe_index, disk),
+            })
+            .collect()
+    }
+
+    /// `placement_map` counts the roles each member plays over a range of stripes.
+    ///
+    /// # Arguments
+    /// * `stripes` - Physical stripe indices to count.
+    pub fn placement_map(&self, stripes: Range<u64>) -> PlacementMap {
+        let mut map = PlacementMap {
+            stripes: stripes.end.saturating_sub(stripes.start),
+            data: vec![0; D],
+            mirror: vec![0; D],
+            parity: vec![0; D],
+        };
+        for stripe_index in stripes {
+            for disk in 0..D {
+                let count = match T::role_at(stripe_index, disk) {
+                    ChunkRole::Data(_) => &mut map.data,
+                    ChunkRole::Mirror(_) => &mut map.mirror,
+                    ChunkRole::Parity => &mut map.parity,
+                };
+                count[disk] += 1;
+            }
+        }
+        map
+    }
+
+    /// `rebuild_fan_out` counts the chunks each member supplies to rebuild `disk`
+    /// over a range of stripes.
+    ///
+    /// A wide spread means a rebuild loads the whole pool evenly; a layout with
+    /// fixed peers reads every stripe from the same members.
+    ///
+    /// # Arguments
+    /// * `disk` - Member being rebuilt.
+    /// * `stripes` - Physical stripe indices to count.
+    pub fn rebuild_fan_out(&self, disk: usize, stripes: Range<u64>) -> Vec<u64> {
+        let mut fan_out = vec![0; D];
+        for stripe_index in stripes {
+            for peer in T::peers(stripe_index, disk) {
+                fan_out[peer] += 1;
+            }
+        }
+        fan_out
+    }
+
+    /// `inspect_stripe` reads a stripe without repairing it and compares it to its re-encoding.
+    ///
+    /// Missing disks contribute zeroed chunks to the re-encoding.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Index of the stripe to inspect.
+    pub fn inspect_stripe(&mut self, stripe_index: u64) -> StripeInspection {
+        let disk_offset = stripe_byte_offset::<N>(stripe_index);
+        let chunks = self.array.peek(disk_offset);
+
+        let raw: Vec<Bits<N>> = chunks.iter().map(|c| c.unwrap_or(Bits::zero())).collect();
+        let expected = self.reencode(stripe_index, &raw).unwrap_or_default();
+
+        let mismatched = chunks
+            .iter()
+            .zip(&expected)
+            .enumerate()
+            .filter_map(|(i, (stored, want))| match stored {
+                Some(stored) if stored != want => Some(i),
+                _ => None,
+            })
+            .collect();
+
+        StripeInspection {
+            stripe_index,
+            disk_offset,
+            stored: chunks.iter().map(|c| c.map(|b| b.0.to_vec())).collect(),
+            expected: expected.iter().map(|b| b.0.to_vec()).collect(),
+            mismatched,
+        }
+    }
+
+    /// `peek_disk` copies the raw bytes member `disk` stores at `offset` into `out`,
+    /// without reconstructing anything.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`](crate::Error::Geometry) if `disk` is not a member
+    /// or the range passes the end of the disk, and
+    /// [`Error::Degraded`](crate::Error::Degraded) if the disk is missing.
+    pub fn peek_disk(&self, disk: usize, offset: u64, out: &mut [u8]) -> Result<()> {
+        self.array.peek_disk(disk, offset, out)
+    }
+
+    /// `reencode` decodes raw chunks through the layout and encodes the data again.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`](crate::Error::Geometry) if the layout rejects the
+    /// chunk buffers.
+    pub(super) fn reencode(&mut self, stripe_index: u64, raw: &[Bits<N>]) -> Result<Vec<Bits<N>>> {
+        let mut data = vec![Bits::<N>::zero(); T::DATA];
+        self.layout.place(stripe_index);
+        self.layout.try_write_raw(raw)?;
+        self.layout.try_read(&mut data)?;
+        self.layout.try_write(&data)?;
+        let mut expected = vec![Bits::<N>::zero(); D];
+        self.layout.try_read_raw(&mut expected)?;
+        Ok(expected)
+    }
+}
@@ -0,0 +1,127 @@
+use super::test_utils::fresh_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use crate::layout::stripe::traits::stripe::ChunkRole;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    fresh_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+#[test]
+fn locate_maps_offset_to_chunk_and_disk() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir);
+
+    // Two data chunks of four bytes per stripe: byte 13 is stripe 1, chunk 1, byte 1.
+    let loc = volume.locate(13);
+
+    assert_eq!(
+        loc,
+        ByteLocation {
+            stripe_index: 1,
+            chunk_index: 1,
+            byte_in_chunk: 1,
+            disk_index: 1,
+            disk_offset: 5,
+        }
+    );
+}
+
+#[test]
+fn inspect_stripe_reports_consistent_parity() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let inspection = volume.inspect_stripe(0);
+
+    assert_eq!(inspection.stored[0], Some(vec![1, 2, 3, 4]));
+    assert_eq!(inspection.expected[2], vec![1 ^ 5, 2 ^ 6, 3 ^ 7, 4 ^ 8]);
+    assert!(inspection.mismatched.is_empty());
+}
+
+#[test]
+fn inspect_stripe_flags_corrupt_parity_without_repairing() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, &[1, 2, 3, 4, 5, 6, 7, 8]);
+    volume.array.0[2].write_at(0, &[0xFF; CHUNK_SIZE]);
+
+    let inspection = volume.inspect_stripe(0);
+
+    assert_eq!(inspection.mismatched, vec![2]);
+    let mut on_disk = [0u8; CHUNK_SIZE];
+    volume.array.0[2].read_at(0, &mut on_disk);
+    assert_eq!(on_disk, [0xFF; CHUNK_SIZE]);
+}
+
+#[test]
+fn geometry_reports_layout_counts() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir);
+
+    let geom = volume.geometry();
+
+    assert_eq!(geom.bytes_per_chunk, CHUNK_SIZE);
+    assert_eq!(geom.bytes_per_stripe, 2 * CHUNK_SIZE);
+    assert_eq!(
+        (geom.disks, geom.data_disks, geom.redundant_disks),
+        (3, 2, 1)
+    );
+}
+
+#[test]
+fn map_logical_lists_every_chunk_of_the_stripe() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir);
+
+    let map = volume.map_logical(13);
+
+    let roles: Vec<_> = map.iter().map(|chunk| (chunk.disk, chunk.role)).collect();
+    assert_eq!(
+        roles,
+        [
+            (0, ChunkRole::Data(0)),
+            (1, ChunkRole::Data(1)),
+            (2, ChunkRole::Parity),
+        ]
+    );
+    assert!(map.iter().all(|chunk| chunk.disk_offset == 5));
+}
+
+#[test]
+fn geometry_and_chunk_roles_round_trip_through_json() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir);
+
+    let geometry = volume.geometry();
+    let json = serde_json::to_string(&geometry).expect("serialize");
+    assert_eq!(
+        serde_json::from_str::<Geometry>(&json).expect("parse"),
+        geometry
+    );
+
+    let chunks = volume.map_logical(0);
+    let json = serde_json::to_value(&chunks).expect("serialize");
+    assert_eq!(json[0]["role"], serde_json::json!({ "data": 0 }));
+    assert_eq!(json[2]["role"], "parity");
+}
+
+#[test]
+fn placement_map_and_fan_out_follow_fixed_parity() {
+    let dir = TempDir::new().unwrap();
+    let volume = make_volume(&dir);
+
+    let map = volume.placement_map(0..10);
+
+    assert_eq!(map.stripes, 10);
+    assert_eq!(map.data, vec![10, 10, 0]);
+    assert_eq!(map.mirror, vec![0, 0, 0]);
+    assert_eq!(map.parity, vec![0, 0, 10]);
+    assert_eq!(volume.rebuild_fan_out(1, 0..10), vec![10, 0, 10]);
+}
@@ -0,0 +1,194 @@
+//! Write-intent bitmap that limits resync work after a disk is reattached.
+//!
+//! While any member is missing or untrusted, every written stripe marks its region
+//! dirty. When a failed disk comes back with its old contents, only dirty regions
+//! have to be resynced. The bitmap lives next to the disk images and is cleared
+//! once the whole array is back in sync.
+
+use std::path::PathBuf;
+
+use crate::Result;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::{DiskChange, Volume};
+
+/// Number of stripes tracked by one bitmap bit.
+pub const INTENT_REGION_STRIPES: u64 = 16;
+
+const INTENT_FILE_NAME: &str = "write-intent.bitmap";
+const INTENT_MAGIC: [u8; 8] = *b"RAIDWIB1";
+const INTENT_HEADER_LEN: usize = 24;
+
+/// `WriteIntent` is the in-memory copy of the dirty region bitmap.
+pub struct WriteIntent {
+    path: Option<PathBuf>,
+    bits: Vec<u8>,
+    regions: u64,
+    reattached: Vec<bool>,
+}
+
+impl WriteIntent {
+    /// `load` reads the bitmap stored at `path`, or starts a clean one.
+    pub(super) fn load(path: Option<PathBuf>, regions: u64, disks: usize) -> Self {
+        let len = usize::try_from(regions.div_ceil(8)).unwrap_or(0);
+        let bits = path
+            .as_ref()
+            .and_then(|p| std::fs::read(p).ok())
+            .filter(|raw| raw.len() == INTENT_HEADER_LEN + len)
+            .filter(|raw| raw[0..8] == INTENT_MAGIC)
+            .filter(|raw| raw[8..16] == INTENT_REGION_STRIPES.to_le_bytes())
+            .filter(|raw| raw[16..24] == regions.to_le_bytes())
+            .map_or_else(|| vec![0; len], |raw| raw[INTENT_HEADER_LEN..].to_vec());
+        Self {
+            path,
+            bits,
+            regions,
+            reattached: vec![false; disks],
+        }
+    }
+
+    /// `is_dirty` reports whether the region holding a physical stripe is dirty.
+    pub(super) fn is_dirty(&self, stripe_index: u64) -> bool {
+        let region = stripe_index / INTENT_REGION_STRIPES;
+        usize::try_from(region / 8)
+            .ok()
+            .and_then(|byte| self.bits.get(byte))
+            .is_some_and(|byte| byte & (1 << (region % 8)) != 0)
+    }
+
+    /// `dirty_regions` returns the number of dirty regions.
+    pub(super) fn dirty_regions(&self) -> u64 {
+        self.bits.iter().map(|b| u64::from(b.count_ones())).sum()
+    }
+
+    /// `mark` sets the region bit for a physical stripe and reports whether it changed.
+    fn mark(&mut self, stripe_index: u64) -> bool {
+        let region = stripe_index / INTENT_REGION_STRIPES;
+        if region >= self.regions {
+            return false;
+        }
+        let Some(byte) = usize::try_from(region / 8)
+            .ok()
+            .and_then(|byte| self.bits.get_mut(byte))
+        else {
+            return false;
+        };
+        let mask = 1 << (region % 8);
+        let changed = *byte & mask == 0;
+        *byte |= mask;
+        changed
+    }
+
+    fn save(&self) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        let mut raw = Vec::with_capacity(INTENT_HEADER_LEN + self.bits.len());
+        raw.extend_from_slice(&INTENT_MAGIC);
+        raw.extend_from_slice(&INTENT_REGION_STRIPES.to_le_bytes());
+        raw.extend_from_slice(&self.regions.to_le_bytes());
+        raw.extend_from_slice(&self.bits);
+        let _ = std::fs::write(path, raw);
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `intent_path` returns where the bitmap of this array is persisted.
+    pub(super) fn intent_path(&self) -> Option<PathBuf> {
+        self.array
+            .0
+            .first()
+            .map(|disk| disk.path().with_file_name(INTENT_FILE_NAME))
+    }
+
+    /// `intent_regions` returns the number of bitmap regions covering the disks.
+    pub(super) fn intent_regions(&self) -> u64 {
+        (self.array.disk_len() / N as u64).div_ceil(INTENT_REGION_STRIPES)
+    }
+
+    /// `dirty_regions` returns the number of bitmap regions written while degraded.
+    pub fn dirty_regions(&self) -> u64 {
+        self.intent.dirty_regions()
+    }
+
+    /// `readd_disk` reattaches the last failed image of a disk for a bitmap-based resync.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to reattach.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is still attached or has no failed image to restore.
+    pub fn readd_disk(&mut self, i: usize) -> Result<()> {
+        self.array.reattach_disk(i)?;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_reattach(i);
+        self.emit_disk(DiskChange::Reattached, i);
+        Ok(())
+    }
+
+    /// `mark_rejoined` schedules a bitmap-based resync of a member that is back with
+    /// the contents it had when it left, such as an image restored after it went
+    /// missing.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the rejoined disk.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range or the disk is missing.
+    pub fn mark_rejoined(&mut self, i: usize) -> Result<()> {
+        self.check_present(i)?;
+        self.array.0[i].needs_rebuild = true;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_reattach(i);
+        self.emit_disk(DiskChange::Stale, i);
+        Ok(())
+    }
+
+    /// `intent_mark` records a write to a physical stripe issued while degraded.
+    pub(super) fn intent_mark(&mut self, stripe_index: u64) {
+        if self.intent.mark(stripe_index) {
+            self.intent.save();
+        }
+    }
+
+    /// `intent_applies` reports whether a rebuild may skip clean regions.
+    ///
+    /// That is only safe when every disk awaiting rebuild was reattached with its
+    /// old contents rather than replaced.
+    pub(super) fn intent_applies(&self) -> bool {
+        self.array
+            .0
+            .iter()
+            .zip(&self.intent.reattached)
+            .all(|(disk, &reattached)| !disk.needs_rebuild || disk.is_missing() || reattached)
+    }
+
+    /// `intent_settle` drops rebuild bookkeeping once the array is back in sync.
+    pub(super) fn intent_settle(&mut self) {
+        for (disk, reattached) in self.array.0.iter().zip(&mut self.intent.reattached) {
+            if !disk.needs_rebuild {
+                *reattached = false;
+            }
+        }
+        if !self.is_degraded() && self.intent.dirty_regions() > 0 {
+            self.intent.bits.fill(0);
+            self.intent.save();
+        }
+    }
+
+    /// `intent_reattach` lets a rebuild of a disk that kept its old contents skip
+    /// clean regions.
+    pub(super) fn intent_reattach(&mut self, i: usize) {
+        if let Some(reattached) = self.intent.reattached.get_mut(i) {
+            *reattached = true;
+        }
+    }
+
+    /// `intent_forget` drops the reattached state of a disk that received a blank image.
+    pub(super) fn intent_forget(&mut self, i: usize) {
+        if let Some(reattached) = self.intent.reattached.get_mut(i) {
+            *reattached = false;
+        }
+    }
+}
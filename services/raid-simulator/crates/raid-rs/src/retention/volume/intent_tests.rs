@@ -0,0 +1,118 @@
+use super::test_utils::{fresh_volume, synced_volume};
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 4096;
+const STRIPE_BYTES: u64 = 8;
+const REGION_BYTES: u64 = INTENT_REGION_STRIPES * STRIPE_BYTES;
+
+type TestVolume = Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>;
+
+fn make_volume(dir: &TempDir) -> TestVolume {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn read(volume: &mut TestVolume, offset: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.read_bytes(offset, &mut out);
+    out
+}
+
+#[test]
+fn healthy_writes_leave_bitmap_clean() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+
+    volume.write_bytes(0, b"healthy");
+    assert_eq!(volume.dirty_regions(), 0);
+    assert!(!dir.path().join("write-intent.bitmap").exists());
+}
+
+#[test]
+fn readd_resyncs_only_regions_written_while_missing() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"old-data");
+    volume.write_bytes(5 * REGION_BYTES, b"old-tail");
+
+    volume.fail_disk(1).expect("fail disk");
+    volume.write_bytes(5 * REGION_BYTES, b"new-tail");
+    assert_eq!(volume.dirty_regions(), 1);
+
+    volume.readd_disk(1).expect("readd disk");
+    let end = volume.logical_capacity_bytes();
+    assert_eq!(
+        volume.stripes_to_repair(end).len() as u64,
+        INTENT_REGION_STRIPES
+    );
+
+    volume.rebuild_disk_upto(1, end).expect("rebuild");
+    assert_eq!(volume.dirty_regions(), 0);
+    volume.fail_disk(0).expect("fail disk");
+    assert_eq!(read(&mut volume, 0, 8), b"old-data");
+    assert_eq!(read(&mut volume, 5 * REGION_BYTES, 8), b"new-tail");
+}
+
+#[test]
+fn replaced_disk_still_gets_full_resync() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+
+    volume.fail_disk(2).expect("fail disk");
+    volume.write_bytes(0, b"degraded");
+    volume.replace_disk(2).expect("replace disk");
+
+    let end = volume.logical_capacity_bytes();
+    assert_eq!(
+        volume.stripes_to_repair(end).len() as u64,
+        volume.stripes_needed_for_logical_end(end)
+    );
+}
+
+#[test]
+fn bitmap_persists_across_reopen() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+
+    volume.fail_disk(0).expect("fail disk");
+    volume.write_bytes(REGION_BYTES, b"while missing");
+    volume.write_bytes(3 * REGION_BYTES, b"again");
+    drop(volume);
+
+    let volume = fresh_volume(&dir, DISK_LEN, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero());
+    assert_eq!(volume.dirty_regions(), 2);
+}
+
+#[test]
+fn rejoined_image_resyncs_only_regions_written_while_away() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"old-data");
+    volume.fail_disk(1).expect("fail disk");
+    volume.write_bytes(5 * REGION_BYTES, b"new-tail");
+    drop(volume);
+
+    let failed = std::fs::read_dir(dir.path())
+        .unwrap()
+        .filter_map(Result::ok)
+        .find(|e| e.file_name().to_string_lossy().contains(".failed."))
+        .expect("failed image")
+        .path();
+    std::fs::rename(failed, dir.path().join("disk-1.img")).unwrap();
+    let mut volume = fresh_volume(&dir, DISK_LEN, RAID3::<TEST_DISKS, CHUNK_SIZE>::zero());
+
+    volume.mark_rejoined(1).expect("mark rejoined");
+    let end = volume.logical_capacity_bytes();
+    assert_eq!(
+        volume.stripes_to_repair(end).len() as u64,
+        INTENT_REGION_STRIPES
+    );
+
+    volume.rebuild_disk_upto(1, end).expect("rebuild");
+    volume.fail_disk(0).expect("fail disk");
+    assert_eq!(read(&mut volume, 0, 8), b"old-data");
+    assert_eq!(read(&mut volume, 5 * REGION_BYTES, 8), b"new-tail");
+}
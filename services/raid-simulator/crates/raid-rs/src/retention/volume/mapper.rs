@@ -1,11 +1,19 @@
 //! Geometry helpers for mapping logical byte offsets to stripes.
 
+use serde::{Deserialize, Serialize};
+
 use crate::layout::stripe::traits::stripe::Stripe;
 
 /// Geometry describes the byte layout of stripes and chunks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Geometry {
     pub bytes_per_chunk: usize,
+    /// Logical bytes per stripe, excluding mirrors and parity.
     pub bytes_per_stripe: usize,
+    pub disks: usize,
+    pub data_disks: usize,
+    /// Disks holding mirrors or parity.
+    pub redundant_disks: usize,
 }
 
 /// `geometry` computes the stripe geometry for a given layout.
@@ -16,6 +24,9 @@ pub fn geometry<const D: usize, const N: usize, S: Stripe<D, N>>() -> Geometry {
     Geometry {
         bytes_per_chunk: N,
         bytes_per_stripe: S::DATA * N,
+        disks: S::DISKS,
+        data_disks: S::DATA,
+        redundant_disks: S::DISKS - S::DATA,
     }
 }
 
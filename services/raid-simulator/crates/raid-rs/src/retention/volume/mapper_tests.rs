@@ -20,6 +20,10 @@ fn geometry_uses_stripe_constants() {
     let geom = geometry::<3, 8, DummyStripe>();
     assert_eq!(geom.bytes_per_chunk, 8);
     assert_eq!(geom.bytes_per_stripe, 16);
+    assert_eq!(
+        (geom.disks, geom.data_disks, geom.redundant_disks),
+        (3, 2, 1)
+    );
 }
 
 #[test]
@@ -27,6 +31,9 @@ fn locate_byte_maps_offset_into_stripe() {
     let geom = Geometry {
         bytes_per_chunk: 8,
         bytes_per_stripe: 24,
+        disks: 4,
+        data_disks: 3,
+        redundant_disks: 1,
     };
 
     let (stripe, in_stripe) = locate_byte(5, 30, &geom);
@@ -44,6 +51,9 @@ fn locate_byte_panics_on_overflow() {
     let geom = Geometry {
         bytes_per_chunk: 1,
         bytes_per_stripe: 1,
+        disks: 1,
+        data_disks: 1,
+        redundant_disks: 0,
     };
 
     let _ = locate_byte(u64::MAX, 1, &geom);
@@ -1,52 +1,240 @@
 //! Logical volume management built on top of disk arrays and stripe layouts.
 
+mod balance;
+#[cfg(test)]
+mod balance_tests;
+mod batch;
+#[cfg(test)]
+mod batch_tests;
+mod borrowed;
+#[cfg(test)]
+mod borrowed_tests;
+mod check;
+#[cfg(test)]
+mod check_tests;
+mod compress;
+#[cfg(test)]
+mod compress_tests;
+mod crash;
+#[cfg(test)]
+mod crash_tests;
+mod degraded;
+#[cfg(test)]
+mod degraded_tests;
+mod dyn_volume;
+mod events;
+#[cfg(test)]
+mod events_tests;
+mod features;
+#[cfg(test)]
+mod features_tests;
+mod inspect;
+#[cfg(test)]
+mod inspect_tests;
+mod intent;
+#[cfg(test)]
+mod intent_tests;
 mod mapper;
 #[cfg(test)]
 mod mapper_tests;
+mod parity_cache;
+#[cfg(test)]
+mod parity_cache_tests;
+mod ppl;
+#[cfg(test)]
+mod ppl_tests;
+mod priority;
+#[cfg(test)]
+mod priority_tests;
+mod readahead;
+#[cfg(test)]
+mod readahead_tests;
+mod reshape;
+#[cfg(test)]
+mod reshape_tests;
+mod snapshot;
+#[cfg(test)]
+mod snapshot_tests;
+mod split;
+#[cfg(test)]
+mod split_tests;
+mod status;
+#[cfg(test)]
+mod status_tests;
+#[cfg(test)]
+mod test_utils;
+mod thin;
+#[cfg(test)]
+mod thin_tests;
+mod uncorrectable;
+#[cfg(test)]
+mod uncorrectable_tests;
 #[cfg(test)]
 mod volume_tests;
-
-use anyhow::Result;
-use mapper::{Geometry, geometry, locate_byte, stripe_byte_offset};
+mod write_hole;
+#[cfg(test)]
+mod write_hole_tests;
+
+pub use balance::{BALANCE_FILE_NAME, BalanceCheckpoint, BalanceProgress};
+pub use batch::BATCH_STRIPES;
+pub use check::{CheckReport, StripeCheck};
+pub use compress::{COMPRESS_BLOCK, CompressionUsage};
+pub use crash::{CrashPlan, crash_test};
+pub use degraded::DegradedPolicy;
+pub use dyn_volume::DynVolume;
+pub use events::{DiskChange, VolumeEvent};
+pub use inspect::{ByteLocation, ChunkMapping, PlacementMap, StripeInspection};
+pub use intent::INTENT_REGION_STRIPES;
+pub use mapper::Geometry;
+pub use parity_cache::ParityCacheStats;
+pub use priority::{IoClassStats, PRIORITY_WINDOW};
+pub use readahead::ReadAheadStats;
+pub use reshape::{RESHAPE_FILE_NAME, ReshapeCheckpoint};
+pub use snapshot::{MAX_SNAPSHOTS, SNAPSHOT_NAME_LEN, SnapshotInfo, SnapshotUsage};
+pub use thin::ThinUsage;
+pub use write_hole::{WriteHolePolicy, WriteHoleStats};
+
+use balance::Balance;
+use compress::CompressMap;
+use intent::WriteIntent;
+use mapper::{geometry, locate_byte, stripe_byte_offset};
+use parity_cache::ParityCache;
+use priority::IoScheduler;
+use readahead::ReadAhead;
+use reshape::Reshape;
+use snapshot::SnapshotStore;
+use status::RebuildProgress;
+use thin::ThinMap;
+use uncorrectable::UncorrectableLog;
+use write_hole::WriteHole;
 
 use crate::layout::bits::Bits;
 use crate::layout::stripe::traits::stripe::Stripe;
-use crate::metrics::{IoOpType, RaidOp};
-use crate::retention::array::Array;
+use crate::metrics::{IO_TRACE_TARGET, IoClass, IoOpType, RaidOp};
+use crate::retention::IoError;
+use crate::retention::array::{Array, InFlight};
+use crate::retention::disk::{Disk, DiskHealth, WearPlan};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::time::Instant;
+use tracing::trace_span;
 
 /// `DiskStatus` summarizes the health of a disk within the volume.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DiskStatus {
     pub index: usize,
     pub missing: bool,
     pub needs_rebuild: bool,
 }
 
+/// `Access` selects how logical stripe indices reach the disks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Access {
+    /// Filesystem IO: snapshots preserve old data and thin volumes map stripes.
+    Live,
+    /// Volume metadata IO: thin volumes map stripes but nothing is preserved.
+    Internal,
+    /// Stripe indices address the disks directly.
+    Physical,
+}
+
 /// Volume combines a disk array with a stripe layout for logical IO.
 pub struct Volume<const D: usize, const N: usize, T: Stripe<D, N>> {
     array: Array<D, N>,
     layout: T,
     geom: Geometry,
+    snapshots: Option<SnapshotStore>,
+    thin: Option<ThinMap>,
+    compression: Option<CompressMap>,
+    balance: Option<Balance>,
+    reshape: Option<Reshape>,
+    intent: WriteIntent,
+    watchers: Vec<std::sync::mpsc::Sender<VolumeEvent>>,
+    rebuild: Option<RebuildProgress>,
+    degraded_policy: DegradedPolicy,
+    uncorrectable: UncorrectableLog,
+    crash_after: Option<u64>,
+    read_ahead: Option<ReadAhead<N>>,
+    parity_cache: Option<ParityCache<N>>,
+    priority: IoScheduler,
+    write_hole: WriteHole,
 }
 
 impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
     /// `new` constructs a `Volume` from a disk array and stripe layout.
     ///
+    /// An existing thin allocation map, compression block map or snapshot store at the
+    /// end of the volume is loaded automatically, as is the write-intent bitmap stored next to the disks.
+    /// Stripes left in the write-hole journal or partial parity log are replayed first.
+    ///
     /// # Arguments
     /// * `array` - Disk array backing the volume.
     /// * `layout` - Stripe layout implementation.
     pub fn new(array: Array<D, N>, layout: T) -> Self {
+        let mut volume = Self::open(array, layout);
+        volume.replay_journal();
+        volume.replay_ppl();
+        volume.load_maps();
+        volume
+    }
+
+    /// `new_read_only` constructs a `Volume` like [`Volume::new`] but leaves the
+    /// write-hole journal and partial parity log alone, so opening it writes nothing
+    /// to the members.
+    ///
+    /// Stripes the logs would repair read as the crash left them; the logs are
+    /// replayed by the next `new`.
+    ///
+    /// # Arguments
+    /// * `array` - Disk array backing the volume.
+    /// * `layout` - Stripe layout implementation.
+    pub fn new_read_only(array: Array<D, N>, layout: T) -> Self {
+        let mut volume = Self::open(array, layout);
+        volume.load_maps();
+        volume
+    }
+
+    fn open(array: Array<D, N>, layout: T) -> Self {
         Self {
             array,
             geom: geometry::<D, N, T>(),
             layout,
+            snapshots: None,
+            thin: None,
+            compression: None,
+            balance: None,
+            reshape: None,
+            intent: WriteIntent::load(None, 0, D),
+            watchers: Vec::new(),
+            rebuild: None,
+            degraded_policy: DegradedPolicy::default(),
+            uncorrectable: UncorrectableLog::default(),
+            crash_after: None,
+            read_ahead: None,
+            parity_cache: None,
+            priority: IoScheduler::default(),
+            write_hole: WriteHole::default(),
         }
     }
 
-    /// `disk_status_string` returns a human-readable status summary.
+    /// `load_maps` loads the write-intent bitmap and the metadata of the features
+    /// recorded on the members.
+    fn load_maps(&mut self) {
+        self.intent = WriteIntent::load(self.intent_path(), self.intent_regions(), D);
+        let features = self.load_features();
+        if features.thin() {
+            self.thin = self.load_thin_map();
+        } else if features.compressed() {
+            self.compression = self.load_compress_map();
+        } else if features.snapshots() {
+            self.snapshots = self.load_snapshot_store();
+        }
+    }
+
+    /// `disk_status_string` returns a human-readable, mdstat-style status summary.
     pub fn disk_status_string(&self) -> String {
-        self.array.status_string()
+        self.status().to_string()
     }
 
     /// `fail_disk` marks the disk at the given index as failed.
@@ -57,7 +245,11 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
     /// # Errors
     /// Returns an error if the disk cannot be failed.
     pub fn fail_disk(&mut self, i: usize) -> Result<()> {
-        self.array.fail_disk(i)
+        self.array.fail_disk(i)?;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.emit_disk(DiskChange::Failed, i);
+        Ok(())
     }
 
     /// `replace_disk` replaces the disk image at the given index.
@@ -68,7 +260,63 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
     /// # Errors
     /// Returns an error if the disk cannot be replaced.
     pub fn replace_disk(&mut self, i: usize) -> Result<()> {
-        self.array.replace_disk(i)
+        self.array.replace_disk(i)?;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_forget(i);
+        self.save_features()?;
+        self.emit_disk(DiskChange::Replaced, i);
+        Ok(())
+    }
+
+    /// `replace_disk_with` replaces the disk at the given index with a blank image
+    /// elsewhere on the host.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the disk to replace.
+    /// * `path` - Path of the new disk image.
+    /// * `len` - Length of the new disk image in bytes; at least the length used per disk.
+    ///
+    /// # Errors
+    /// Returns an error if the disk cannot be replaced.
+    pub fn replace_disk_with(&mut self, i: usize, path: &Path, len: u64) -> Result<()> {
+        self.array.replace_disk_with(i, path, len)?;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_forget(i);
+        self.save_features()?;
+        self.emit_disk(DiskChange::Replaced, i);
+        Ok(())
+    }
+
+    /// `mark_stale` schedules a full rebuild of a member whose contents are older
+    /// than the rest of the array, such as a disk reinserted after missing writes.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the stale disk.
+    ///
+    /// # Errors
+    /// Returns an error if the index is out of range or the disk is missing.
+    pub fn mark_stale(&mut self, i: usize) -> Result<()> {
+        self.check_present(i)?;
+        self.array.0[i].needs_rebuild = true;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_forget(i);
+        self.emit_disk(DiskChange::Stale, i);
+        Ok(())
+    }
+
+    /// `check_present` fails unless disk `i` exists and is attached.
+    fn check_present(&self, i: usize) -> Result<()> {
+        Array::<D, N>::check_index(i)?;
+        let disk = &self.array.0[i];
+        if disk.is_missing() {
+            return Err(Error::Degraded(IoError::DiskMissing {
+                path: disk.path().to_path_buf(),
+            }));
+        }
+        Ok(())
     }
 
     /// `any_needs_rebuild` reports whether any disk needs rebuild work.
@@ -104,9 +352,52 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
             .collect()
     }
 
+    /// `disk_healths` returns the SMART-like attributes of every member disk.
+    pub fn disk_healths(&self) -> Vec<DiskHealth> {
+        self.array.0.iter().map(Disk::health).collect()
+    }
+
+    /// `simulate_wear` makes IO grow defects on every member disk.
+    ///
+    /// # Arguments
+    /// * `plan` - Wear model of the members; each disk derives its own seed from it.
+    pub fn simulate_wear(&mut self, plan: WearPlan) {
+        for (i, disk) in (0u64..).zip(&mut self.array.0) {
+            disk.simulate_wear(WearPlan {
+                seed: plan.seed.wrapping_add(i),
+                ..plan
+            });
+        }
+    }
+
+    /// `in_flight` returns a handle to the in-flight operation counters of the disks.
+    pub fn in_flight(&self) -> InFlight {
+        self.array.in_flight()
+    }
+
     /// `logical_capacity_bytes` returns the logical data capacity of the volume.
+    ///
+    /// Space reserved for snapshots is not included. Thin volumes report their
+    /// virtual size, and a volume being balanced the capacity of its source. A
+    /// reshaped volume reports the capacity of its target once the reshape finishes.
     pub fn logical_capacity_bytes(&self) -> u64 {
-        self.array.disk_len().saturating_mul(T::DATA as u64)
+        if let Some(end) = self.balance_capacity().or_else(|| self.reshape_capacity()) {
+            return end;
+        }
+        self.snapshots
+            .as_ref()
+            .map_or_else(|| self.raw_capacity_bytes(), SnapshotStore::region_start)
+    }
+
+    /// `raw_capacity_bytes` returns the logical capacity including any reserved region.
+    fn raw_capacity_bytes(&self) -> u64 {
+        if let Some(map) = self.compression.as_ref() {
+            return map.virtual_bytes();
+        }
+        self.thin.as_ref().map_or_else(
+            || self.array.disk_len().saturating_mul(T::DATA as u64),
+            ThinMap::virtual_bytes,
+        )
     }
 
     /// `stripes_needed_for_logical_end` returns the stripe count for the given logical end.
@@ -125,21 +416,74 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
         end.div_ceil(bytes_per_stripe)
     }
 
-    /// `repair_stripe` forces a stripe read to rebuild missing data.
+    /// `reserved_stripes` returns the stripes that hold snapshot, thin or compression
+    /// metadata.
+    ///
+    /// Rebuilds covering only the filesystem extent must also repair these stripes.
+    pub fn reserved_stripes(&self) -> std::ops::Range<u64> {
+        if self.thin.is_some() {
+            return self.thin_metadata_stripes();
+        }
+        if self.compression.is_some() {
+            return self.compression_metadata_stripes();
+        }
+        let Some(store) = self.snapshots.as_ref() else {
+            return 0..0;
+        };
+        let bytes_per_stripe = self.geom.bytes_per_stripe as u64;
+        let start = store.region_start() / bytes_per_stripe;
+        let end = self.raw_capacity_bytes() / bytes_per_stripe;
+        start..end
+    }
+
+    /// `stripes_to_repair` returns the physical stripes a rebuild up to `logical_end` must read.
+    ///
+    /// On thin volumes only allocated stripes are included, in allocation-map order;
+    /// on compressed volumes, every stripe holding a stored block.
+    /// When every disk awaiting rebuild was reattached with `readd_disk`, stripes in
+    /// regions the write-intent bitmap reports as clean are skipped.
+    ///
+    /// # Arguments
+    /// * `logical_end` - Logical byte position to rebuild up to.
+    pub fn stripes_to_repair(&self, logical_end: u64) -> Vec<u64> {
+        let stripes = self.stripes_needed_for_logical_end(logical_end);
+        let dirty_only = self.intent_applies();
+        let data = self.compressed_stripes().unwrap_or_else(|| {
+            (0..stripes)
+                .filter_map(|s| self.physical_stripe(s, Access::Internal))
+                .collect()
+        });
+        data.into_iter()
+            .chain(self.reserved_stripes())
+            .filter(|&s| !dirty_only || self.intent.is_dirty(s))
+            .collect()
+    }
+
+    /// `repair_stripe` forces a stripe read to rebuild missing data as background IO.
     ///
     /// # Arguments
-    /// * `stripe_index` - Index of the stripe to repair.
+    /// * `stripe_index` - Physical index of the stripe to repair.
     pub fn repair_stripe(&mut self, stripe_index: u64) {
+        let start = Instant::now();
+        let sources = self.rebuild_sources(stripe_index);
         self.load_stripe(stripe_index);
+        if let Some(rebuild) = self.rebuild.as_mut() {
+            rebuild.advance(&sources);
+        }
+        self.record_background(1, start);
     }
 
     /// `clear_needs_rebuild_all` clears rebuild flags on all operational disks.
     pub fn clear_needs_rebuild_all(&mut self) {
+        let before = self.disk_statuses();
         for d in &mut self.array.0 {
             if !d.is_missing() {
                 d.needs_rebuild = false;
             }
         }
+        self.rebuild = None;
+        self.intent_settle();
+        self.emit_rebuilt(&before);
     }
 
     /// `clear_needs_rebuild_disk` clears the rebuild flag for a specific disk.
@@ -147,9 +491,13 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
     /// # Arguments
     /// * `i` - Index of the disk to clear.
     pub fn clear_needs_rebuild_disk(&mut self, i: usize) {
+        let before = self.disk_statuses();
         if i < D && !self.array.0[i].is_missing() {
             self.array.0[i].needs_rebuild = false;
         }
+        self.rebuild = None;
+        self.intent_settle();
+        self.emit_rebuilt(&before);
     }
 
     /// `rebuild` triggers a best-effort rebuild across all disks.
@@ -172,10 +520,10 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
             return Ok(());
         }
 
-        let stripes = self.stripes_needed_for_logical_end(logical_end);
-        for s in 0..stripes {
-            self.load_stripe(s);
-        }
+        let stripes = self.begin_rebuild(logical_end);
+        let _span = trace_span!(target: IO_TRACE_TARGET, "rebuild_batch", stripes = stripes.len())
+            .entered();
+        self.repair_stripes(&stripes);
 
         self.clear_needs_rebuild_all();
         Ok(())
@@ -190,23 +538,24 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
     /// # Errors
     /// Returns an error if rebuilding fails.
     pub fn rebuild_disk_upto(&mut self, i: usize, logical_end: u64) -> Result<()> {
-        if i >= D {
-            anyhow::bail!("disk index out of range: {i} (D={D})");
-        }
+        Array::<D, N>::check_index(i)?;
         if self.layout.as_restore().is_none() {
             return Ok(());
         }
-        if self.array.0[i].is_missing() {
-            anyhow::bail!("disk {i} is missing/failed; replace it first");
-        }
+        self.check_present(i)?;
         if !self.array.0[i].needs_rebuild {
             return Ok(());
         }
 
-        let stripes = self.stripes_needed_for_logical_end(logical_end);
-        for s in 0..stripes {
-            self.load_stripe(s);
-        }
+        let stripes = self.begin_rebuild(logical_end);
+        let _span = trace_span!(
+            target: IO_TRACE_TARGET,
+            "rebuild_batch",
+            disk = i,
+            stripes = stripes.len()
+        )
+        .entered();
+        self.repair_stripes(&stripes);
 
         self.clear_needs_rebuild_disk(i);
         Ok(())
@@ -233,54 +582,259 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
 
     /// `write_bytes` writes payload bytes into the volume at the logical offset.
     ///
+    /// Writes are best-effort: members that are missing are skipped and writes that
+    /// a thin volume cannot back are dropped. Use `try_write_bytes` to observe failures.
+    ///
     /// # Arguments
     /// * `byte_offset` - Logical byte offset within the volume.
     /// * `payload` - Bytes to write.
     pub fn write_bytes(&mut self, byte_offset: u64, payload: &[u8]) {
-        let start = crate::metrics::is_enabled().then(Instant::now);
-        let mut data_chunks = vec![Bits::<N>::zero(); T::DATA];
-
-        let mut written: usize = 0;
-        let total = payload.len();
-        while written < total {
-            let (stripe_index, in_stripe_byte) = locate_byte(byte_offset, written, &self.geom);
-            let stripe_bytes = self.geom.bytes_per_stripe - in_stripe_byte;
-            let take = stripe_bytes.min(total - written);
+        let _ = self.write_bytes_checked(byte_offset, payload, false);
+    }
 
-            self.load_stripe(stripe_index);
+    /// `try_write_bytes` writes payload bytes, allocating thin stripes on first write.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `payload` - Bytes to write.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if the range is outside the volume,
+    /// [`Error::Degraded`] if the stripes cannot be kept consistent with the members
+    /// that are left, and [`Error::OutOfSpace`] if a thin volume cannot allocate every
+    /// stripe the write touches. Under [`WriteHolePolicy::FullStripe`] a write that
+    /// does not cover whole stripes fails with [`Error::Invalid`]. Nothing is written
+    /// in any case.
+    pub fn try_write_bytes(&mut self, byte_offset: u64, payload: &[u8]) -> Result<()> {
+        self.write_bytes_checked(byte_offset, payload, true)
+    }
 
-            self.layout.read(&mut data_chunks);
+    fn write_bytes_checked(
+        &mut self,
+        byte_offset: u64,
+        payload: &[u8],
+        strict: bool,
+    ) -> Result<()> {
+        if self.balance.is_some() {
+            return self.write_balanced(byte_offset, payload, strict);
+        }
+        if self.reshape.is_some() {
+            return self.write_reshaped(byte_offset, payload, strict);
+        }
+        let start = crate::metrics::is_enabled().then(Instant::now);
 
-            for i in 0..take {
-                let byte_in_stripe = in_stripe_byte + i;
-                let chunk_index = byte_in_stripe / self.geom.bytes_per_chunk;
-                let byte_index = byte_in_stripe % self.geom.bytes_per_chunk;
-                data_chunks[chunk_index].as_bytes_mut()[byte_index] = payload[written + i];
+        let degraded = self.is_degraded();
+        let checked = if strict {
+            self.check_io(byte_offset, payload.len(), IoOpType::Write)
+        } else {
+            Ok(())
+        };
+        let reserved = checked
+            .map_err(Into::into)
+            .and_then(|()| self.check_full_stripe(byte_offset, payload.len()))
+            .and_then(|()| self.thin_reserve(byte_offset, payload.len()));
+        let cache_before = self.parity_cache_stats();
+        let result = reserved.and_then(|()| {
+            if self.compression.is_some() {
+                self.write_compressed(byte_offset, payload)
+            } else {
+                self.write_logical(byte_offset, payload, Access::Live)
             }
-
-            self.layout.write(&data_chunks);
-            self.store_stripe(stripe_index);
-            written += take;
+        });
+        if result.is_ok() {
+            self.record_foreground(payload.len());
         }
 
         if let Some(start) = start {
             let bytes = u64::try_from(payload.len()).unwrap_or(u64::MAX);
+            let cache = self.parity_cache_stats();
             crate::metrics::record_raid_op(RaidOp {
                 op: IoOpType::Write,
                 bytes,
                 latency_seconds: start.elapsed().as_secs_f64(),
-                error: false,
+                error: result.is_err(),
+                degraded,
+                read_ahead_hits: 0,
+                read_ahead_misses: 0,
+                parity_cache_hits: cache.hits - cache_before.hits,
+                parity_cache_misses: cache.misses - cache_before.misses,
+                class: IoClass::Foreground,
             });
         }
+        result
     }
 
     /// `read_bytes` reads bytes from the volume into the output buffer.
     ///
+    /// Reads are best-effort: data that cannot be reconstructed comes back as zeros.
+    /// Use `try_read_bytes` to observe failures.
+    ///
     /// # Arguments
     /// * `byte_offset` - Logical byte offset within the volume.
     /// * `out` - Output buffer to populate.
     pub fn read_bytes(&mut self, byte_offset: u64, out: &mut [u8]) {
+        let _ = self.read_bytes_checked(byte_offset, out, false);
+    }
+
+    /// `try_read_bytes` reads bytes from the volume, failing instead of returning zeros.
+    ///
+    /// # Arguments
+    /// * `byte_offset` - Logical byte offset within the volume.
+    /// * `out` - Output buffer to populate.
+    ///
+    /// # Errors
+    /// Returns [`Error::Geometry`] if the range is outside the volume, or
+    /// [`Error::Degraded`] if too many members are unavailable to reconstruct the
+    /// data. `out` is left untouched in that case.
+    pub fn try_read_bytes(&mut self, byte_offset: u64, out: &mut [u8]) -> Result<()> {
+        self.read_bytes_checked(byte_offset, out, true)
+    }
+
+    fn read_bytes_checked(&mut self, byte_offset: u64, out: &mut [u8], strict: bool) -> Result<()> {
+        if self.balance.is_some() {
+            return self.read_balanced(byte_offset, out, strict);
+        }
+        if self.reshape.is_some() {
+            return self.read_reshaped(byte_offset, out, strict);
+        }
         let start = crate::metrics::is_enabled().then(Instant::now);
+
+        let degraded = self.is_degraded();
+        if degraded {
+            self.record_uncorrectable(byte_offset, out.len());
+        }
+        let checked = if strict {
+            self.check_io(byte_offset, out.len(), IoOpType::Read)
+        } else {
+            Ok(())
+        };
+        let cache_before = self.read_ahead_stats();
+        let result = checked.map_err(Into::into).and_then(|()| {
+            if self.compression.is_some() {
+                return self.read_compressed(byte_offset, out);
+            }
+            self.read_ahead_begin(degraded);
+            let read = self.read_logical(byte_offset, out, Access::Live);
+            self.read_ahead_end(byte_offset, out.len());
+            read
+        });
+        if result.is_ok() {
+            self.record_foreground(out.len());
+        }
+
+        if let Some(start) = start {
+            let bytes = u64::try_from(out.len()).unwrap_or(u64::MAX);
+            let cache = self.read_ahead_stats();
+            crate::metrics::record_raid_op(RaidOp {
+                op: IoOpType::Read,
+                bytes,
+                latency_seconds: start.elapsed().as_secs_f64(),
+                error: result.is_err(),
+                degraded,
+                read_ahead_hits: cache.hits - cache_before.hits,
+                read_ahead_misses: cache.misses - cache_before.misses,
+                parity_cache_hits: 0,
+                parity_cache_misses: 0,
+                class: IoClass::Foreground,
+            });
+        }
+        result
+    }
+
+    /// `check_io` verifies that a logical range lies inside the volume and that the
+    /// degraded policy allows the request.
+    fn check_io(
+        &self,
+        byte_offset: u64,
+        len: usize,
+        op: IoOpType,
+    ) -> std::result::Result<(), IoError> {
+        let limit = self.logical_capacity_bytes();
+        let len = len as u64;
+        if byte_offset.checked_add(len).is_none_or(|end| end > limit) {
+            return Err(IoError::OutOfRange {
+                offset: byte_offset,
+                len,
+                limit,
+            });
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        if self.is_read_only() && matches!(op, IoOpType::Write) {
+            return Err(IoError::ReadOnly);
+        }
+        self.check_degraded(byte_offset, op)
+    }
+
+    /// `write_logical` performs a read-modify-write of every stripe touched by the payload.
+    ///
+    /// Stripes the payload covers completely are encoded without reading them first.
+    /// `Access::Live` writes copy the previous stripe contents into any snapshot
+    /// that has not captured the stripe yet. Unallocated thin stripes are skipped.
+    ///
+    /// Fails with [`Error::Geometry`] if the layout rejects the chunk buffers.
+    fn write_logical(&mut self, byte_offset: u64, payload: &[u8], access: Access) -> Result<()> {
+        let mut data_chunks = vec![Bits::<N>::zero(); T::DATA];
+
+        let degraded = self.is_degraded();
+        let mut written: usize = 0;
+        let total = payload.len();
+        while written < total {
+            let (stripe_index, in_stripe_byte) = locate_byte(byte_offset, written, &self.geom);
+            let stripe_bytes = self.geom.bytes_per_stripe - in_stripe_byte;
+            let take = stripe_bytes.min(total - written);
+
+            let Some(physical) = self.physical_stripe(stripe_index, access) else {
+                written += take;
+                continue;
+            };
+            if access == Access::Live {
+                self.preserve_stripe(stripe_index)?;
+            }
+            self.layout.place(physical);
+            let bytes = &payload[written..written + take];
+            if take == self.geom.bytes_per_stripe {
+                // A whole stripe is re-encoded from the payload alone.
+                self.log_partial_parity(physical, 0..T::DATA, &[])?;
+                for (chunk, src) in data_chunks.iter_mut().zip(bytes.chunks_exact(N)) {
+                    chunk.as_bytes_mut().copy_from_slice(src);
+                }
+            } else if self.write_with_cached_parity(physical, in_stripe_byte, bytes) {
+                written += take;
+                continue;
+            } else {
+                self.load_stripe(physical);
+                self.layout.try_read(&mut data_chunks)?;
+                let chunk_bytes = self.geom.bytes_per_chunk;
+                let changed =
+                    in_stripe_byte / chunk_bytes..(in_stripe_byte + take).div_ceil(chunk_bytes);
+                self.log_partial_parity(physical, changed, &data_chunks)?;
+
+                for (i, &byte) in bytes.iter().enumerate() {
+                    let byte_in_stripe = in_stripe_byte + i;
+                    let chunk_index = byte_in_stripe / self.geom.bytes_per_chunk;
+                    let byte_index = byte_in_stripe % self.geom.bytes_per_chunk;
+                    data_chunks[chunk_index].as_bytes_mut()[byte_index] = byte;
+                }
+            }
+
+            self.layout.try_write(&data_chunks)?;
+            if degraded {
+                self.intent_mark(physical);
+            }
+            self.journal_stripe(physical)?;
+            self.store_stripe(physical);
+            written += take;
+        }
+        Ok(())
+    }
+
+    /// `read_logical` reads every stripe touched by the output buffer.
+    ///
+    /// Unallocated thin stripes read back as zeros. Fails with [`Error::Geometry`] if
+    /// the layout rejects the chunk buffer.
+    fn read_logical(&mut self, byte_offset: u64, out: &mut [u8], access: Access) -> Result<()> {
         let mut data_chunks = vec![Bits::<N>::zero(); T::DATA];
 
         let mut read: usize = 0;
@@ -290,9 +844,16 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
             let stripe_bytes = self.geom.bytes_per_stripe - in_stripe_byte;
             let take = stripe_bytes.min(total - read);
 
-            self.load_stripe(stripe_index);
+            let Some(physical) = self.physical_stripe(stripe_index, access) else {
+                out[read..read + take].fill(0);
+                read += take;
+                continue;
+            };
+            if access != Access::Live || !self.load_cached_stripe(physical) {
+                self.load_stripe(physical);
+            }
 
-            self.layout.read(&mut data_chunks);
+            self.layout.try_read(&mut data_chunks)?;
 
             for i in 0..take {
                 let byte_in_stripe = in_stripe_byte + i;
@@ -303,25 +864,40 @@ impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
 
             read += take;
         }
+        Ok(())
+    }
 
-        if let Some(start) = start {
-            let bytes = u64::try_from(out.len()).unwrap_or(u64::MAX);
-            crate::metrics::record_raid_op(RaidOp {
-                op: IoOpType::Read,
-                bytes,
-                latency_seconds: start.elapsed().as_secs_f64(),
-                error: false,
-            });
+    /// `physical_stripe` resolves a stripe index for the given access mode.
+    ///
+    /// Returns `None` for thin stripes that have not been allocated yet.
+    fn physical_stripe(&self, stripe_index: u64, access: Access) -> Option<u64> {
+        match (&self.thin, access) {
+            (Some(thin), Access::Live | Access::Internal) => thin.physical(stripe_index),
+            _ => Some(stripe_index),
         }
     }
 
     fn load_stripe(&mut self, stripe_index: u64) {
+        let _span =
+            trace_span!(target: IO_TRACE_TARGET, "stripe_read", stripe = stripe_index).entered();
         let byte_offset = stripe_byte_offset::<N>(stripe_index);
         self.array.read(byte_offset, &mut self.layout);
     }
 
     fn store_stripe(&mut self, stripe_index: u64) {
+        let _span =
+            trace_span!(target: IO_TRACE_TARGET, "stripe_write", stripe = stripe_index).entered();
+        if self.tears_next_write() {
+            let mut raw = [Bits::<N>::zero(); D];
+            self.layout.read_raw(&mut raw);
+            self.write_torn(stripe_index, &raw);
+            return;
+        }
         let byte_offset = stripe_byte_offset::<N>(stripe_index);
         self.array.write(byte_offset, &self.layout);
+        self.read_ahead_invalidate(stripe_index);
+        self.parity_cache_store(stripe_index);
+        self.count_stripe_write();
+        self.journal_settle();
     }
 }
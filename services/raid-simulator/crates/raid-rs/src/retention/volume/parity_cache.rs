@@ -0,0 +1,193 @@
+//! Parity cache for partial-stripe writes to XOR-parity layouts.
+//!
+//! A write that covers only part of a stripe normally reads every member to
+//! re-encode the stripe. With the parity of the stripe cached, the write instead
+//! reads just the data chunks it changes, folds the old and new contents into the
+//! cached parity, and writes the changed chunks and the parity back.
+//!
+//! Parity is cached for the last stripes stored, so runs of small writes to one
+//! stripe hit after the first. The cache serves healthy volumes only and is
+//! dropped whenever a member changes; it assumes the volume is the only writer of
+//! its member images.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::stripe_byte_offset;
+
+/// `ParityCacheStats` counts partial-stripe writes that found or missed cached parity.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParityCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// `ParityCache` keeps the parity chunks of the most recently stored stripes.
+pub(super) struct ParityCache<const N: usize> {
+    capacity: usize,
+    parity: HashMap<u64, Bits<N>>,
+    /// Stripe indices from least to most recently stored.
+    order: VecDeque<u64>,
+    stats: ParityCacheStats,
+}
+
+impl<const N: usize> ParityCache<N> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            parity: HashMap::new(),
+            order: VecDeque::new(),
+            stats: ParityCacheStats::default(),
+        }
+    }
+
+    /// `lookup` returns the cached parity of a stripe and counts the hit or miss.
+    fn lookup(&mut self, stripe_index: u64) -> Option<Bits<N>> {
+        let parity = self.parity.get(&stripe_index).copied();
+        if parity.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        parity
+    }
+
+    /// `insert` records the parity of a stored stripe, evicting the oldest entry.
+    fn insert(&mut self, stripe_index: u64, parity: Bits<N>) {
+        if self.parity.insert(stripe_index, parity).is_some() {
+            self.order.retain(|&s| s != stripe_index);
+        }
+        self.order.push_back(stripe_index);
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.parity.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, stripe_index: u64) {
+        if self.parity.remove(&stripe_index).is_some() {
+            self.order.retain(|&s| s != stripe_index);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.parity.clear();
+        self.order.clear();
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `set_parity_cache` caches the parity of the last `stripes` stripes written.
+    ///
+    /// Only layouts with XOR parity use the cache. Passing 0 disables it.
+    ///
+    /// # Arguments
+    /// * `stripes` - Number of stripes whose parity is kept.
+    pub fn set_parity_cache(&mut self, stripes: usize) {
+        self.parity_cache = (stripes > 0 && T::XOR_PARITY).then(|| ParityCache::new(stripes));
+    }
+
+    /// `parity_cache_stats` returns the parity cache counters; all zero when it is disabled.
+    pub fn parity_cache_stats(&self) -> ParityCacheStats {
+        self.parity_cache
+            .as_ref()
+            .map_or_else(ParityCacheStats::default, |cache| cache.stats)
+    }
+
+    /// `write_with_cached_parity` writes part of a stripe by updating its cached parity.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Physical index of the stripe.
+    /// * `in_stripe_byte` - Offset of `bytes` within the stripe data.
+    /// * `bytes` - New contents; must end inside the stripe.
+    ///
+    /// # Returns
+    /// `false` if the cache cannot serve the write and nothing was written.
+    pub(super) fn write_with_cached_parity(
+        &mut self,
+        stripe_index: u64,
+        in_stripe_byte: usize,
+        bytes: &[u8],
+    ) -> bool {
+        if self.parity_cache.is_none()
+            || bytes.is_empty()
+            || self.is_degraded()
+            || !self.allows_parity_delta()
+        {
+            return false;
+        }
+        let mut pieces = Vec::new();
+        let mut written = 0;
+        while written < bytes.len() {
+            let byte_in_stripe = in_stripe_byte + written;
+            let byte_in_chunk = byte_in_stripe % N;
+            let take = (N - byte_in_chunk).min(bytes.len() - written);
+            let Some(disk) = Self::data_disk(stripe_index, byte_in_stripe / N) else {
+                return false;
+            };
+            pieces.push((disk, byte_in_chunk, &bytes[written..written + take]));
+            written += take;
+        }
+        let Some(mut parity) = self
+            .parity_cache
+            .as_mut()
+            .and_then(|cache| cache.lookup(stripe_index))
+        else {
+            return false;
+        };
+
+        let off = stripe_byte_offset::<N>(stripe_index);
+        for (disk, byte_in_chunk, new) in pieces {
+            let mut chunk = Bits::<N>::zero();
+            self.array.read_chunk(disk, off, &mut chunk);
+            parity ^= chunk;
+            chunk.as_bytes_mut()[byte_in_chunk..byte_in_chunk + new.len()].copy_from_slice(new);
+            parity ^= chunk;
+            self.array.write_chunk(disk, off, &chunk);
+        }
+        for disk in (0..D).filter(|&disk| T::role_at(stripe_index, disk) == ChunkRole::Parity) {
+            self.array.write_chunk(disk, off, &parity);
+        }
+
+        if let Some(cache) = self.parity_cache.as_mut() {
+            cache.insert(stripe_index, parity);
+        }
+        self.read_ahead_invalidate(stripe_index);
+        self.count_stripe_write();
+        true
+    }
+
+    /// `parity_cache_store` caches the parity of the stripe held by the layout.
+    pub(super) fn parity_cache_store(&mut self, stripe_index: u64) {
+        let Some(cache) = self.parity_cache.as_mut() else {
+            return;
+        };
+        let Some(disk) = (0..D).find(|&disk| T::role_at(stripe_index, disk) == ChunkRole::Parity)
+        else {
+            return;
+        };
+        let mut raw = [Bits::<N>::zero(); D];
+        if self.layout.try_read_raw(&mut raw).is_ok() {
+            cache.insert(stripe_index, raw[disk]);
+        }
+    }
+
+    /// `parity_cache_invalidate` drops the cached parity of a rewritten stripe.
+    pub(super) fn parity_cache_invalidate(&mut self, stripe_index: u64) {
+        if let Some(cache) = self.parity_cache.as_mut() {
+            cache.remove(stripe_index);
+        }
+    }
+
+    /// `parity_cache_clear` drops the cache after a member changes.
+    pub(super) fn parity_cache_clear(&mut self) {
+        if let Some(cache) = self.parity_cache.as_mut() {
+            cache.clear();
+        }
+    }
+}
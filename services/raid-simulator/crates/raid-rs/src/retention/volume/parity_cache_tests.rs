@@ -0,0 +1,128 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid1::RAID1;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+/// Logical bytes per RAID3 stripe: two data chunks.
+const STRIPE: usize = 2 * CHUNK_SIZE;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i.to_le_bytes()[0]).collect()
+}
+
+fn read_all(
+    volume: &mut Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>,
+    len: usize,
+) -> Vec<u8> {
+    let mut out = vec![0; len];
+    volume.read_bytes(0, &mut out);
+    out
+}
+
+#[test]
+fn small_writes_to_one_stripe_hit_after_the_first() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.set_parity_cache(4);
+    let mut expected = vec![0u8; 2 * STRIPE];
+
+    for (i, offset) in [STRIPE + 1, STRIPE + 3, STRIPE + 5, STRIPE + 2]
+        .into_iter()
+        .enumerate()
+    {
+        let bytes = [0xa0 + i.to_le_bytes()[0]; 2];
+        volume.write_bytes(offset as u64, &bytes);
+        expected[offset..offset + 2].copy_from_slice(&bytes);
+    }
+
+    assert_eq!(
+        volume.parity_cache_stats(),
+        ParityCacheStats { hits: 3, misses: 1 }
+    );
+    assert_eq!(read_all(&mut volume, 2 * STRIPE), expected);
+    assert!(volume.check().is_clean());
+}
+
+#[test]
+fn cached_writes_match_uncached_writes() {
+    let cached_dir = TempDir::new().unwrap();
+    let plain_dir = TempDir::new().unwrap();
+    let mut cached = make_volume(&cached_dir);
+    let mut plain = make_volume(&plain_dir);
+    cached.set_parity_cache(4);
+    let base = pattern(6 * STRIPE);
+    cached.write_bytes(0, &base);
+    plain.write_bytes(0, &base);
+
+    for (offset, len) in [(1, 3), (9, 6), (20, 1), (3, 4), (33, 10), (12, 2)] {
+        let bytes: Vec<u8> = (0..len).map(|i: usize| 0xf0 ^ i.to_le_bytes()[0]).collect();
+        cached.write_bytes(offset, &bytes);
+        plain.write_bytes(offset, &bytes);
+    }
+
+    assert!(cached.parity_cache_stats().hits > 0);
+    assert_eq!(
+        read_all(&mut cached, 6 * STRIPE),
+        read_all(&mut plain, 6 * STRIPE)
+    );
+    assert!(cached.check().is_clean());
+}
+
+#[test]
+fn cache_evicts_the_oldest_stripe() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.set_parity_cache(2);
+    volume.write_bytes(0, &pattern(3 * STRIPE));
+
+    volume.write_bytes((2 * STRIPE + 1) as u64, &[1]);
+    volume.write_bytes((STRIPE + 1) as u64, &[2]);
+    assert_eq!(volume.parity_cache_stats().misses, 0);
+    volume.write_bytes(1, &[3]);
+
+    assert_eq!(
+        volume.parity_cache_stats(),
+        ParityCacheStats { hits: 2, misses: 1 }
+    );
+    assert!(volume.check().is_clean());
+}
+
+#[test]
+fn degraded_writes_bypass_the_cache() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.set_parity_cache(4);
+    volume.set_degraded_policy(DegradedPolicy::BestEffort);
+    volume.write_bytes(0, &pattern(STRIPE));
+    volume.fail_disk(0).unwrap();
+
+    volume.write_bytes(1, &[0x55, 0x66]);
+
+    assert_eq!(volume.parity_cache_stats(), ParityCacheStats::default());
+    let mut expected = pattern(STRIPE);
+    expected[1..3].copy_from_slice(&[0x55, 0x66]);
+    assert_eq!(read_all(&mut volume, STRIPE), expected);
+}
+
+#[test]
+fn layouts_without_xor_parity_leave_the_cache_disabled() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = synced_volume(&dir, DISK_LEN, RAID1::<TEST_DISKS, CHUNK_SIZE>::zero());
+    volume.set_parity_cache(4);
+
+    volume.write_bytes(0, &[1, 2]);
+    volume.write_bytes(1, &[3]);
+
+    assert_eq!(volume.parity_cache_stats(), ParityCacheStats::default());
+    let mut out = [0u8; 2];
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out, [1, 3]);
+}
@@ -0,0 +1,162 @@
+//! Partial parity log that closes the write hole without journaling whole stripes.
+//!
+//! Like md's PPL, every stripe write first logs which data chunks it changes and
+//! the parity of the chunks it leaves alone, computed from their contents before
+//! the write. After a crash the parity of a logged stripe is that partial parity
+//! plus whatever the changed chunks hold on disk, which is consistent whether or
+//! not each changed chunk reached its member. Unlike a resync, this still works
+//! when an unchanged data member is missing.
+//!
+//! An entry is the stripe index, a bitmap of the members that hold changed data
+//! and one chunk per parity member: `N` bytes per parity disk instead of the
+//! `D * N` of a journal entry. Layouts without parity have nothing to log.
+//!
+//! The partial parity is only valid while the chunks it covers are durable. A
+//! stripe written again while its previous entry is in flight is therefore
+//! preceded by a flush, as md does for members with volatile write caches.
+
+use std::ops::Range;
+
+use crate::Result;
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::stripe_byte_offset;
+use crate::retention::volume::write_hole::WriteHolePolicy;
+
+pub(super) const PPL_FILE_NAME: &str = "partial-parity.log";
+pub(super) const PPL_MAGIC: [u8; 8] = *b"RAIDPPL1";
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `log_partial_parity` logs the parity of the data chunks a stripe write
+    /// leaves alone.
+    ///
+    /// # Arguments
+    /// * `stripe_index` - Physical index of the stripe.
+    /// * `changed` - Data chunks the write replaces.
+    /// * `old` - Data chunks of the stripe before the write; only read outside `changed`.
+    ///
+    /// Encodes through the layout, so it must run before the new stripe is encoded.
+    pub(super) fn log_partial_parity(
+        &mut self,
+        stripe_index: u64,
+        changed: Range<usize>,
+        old: &[Bits<N>],
+    ) -> Result<()> {
+        if self.write_hole_policy() != WriteHolePolicy::Ppl {
+            return Ok(());
+        }
+        let parity = Self::parity_disks(stripe_index);
+        if parity.is_empty() {
+            return Ok(());
+        }
+        if self.write_hole.logged.contains(&stripe_index) {
+            self.flush();
+            self.write_hole.stats.ppl_flushes += 1;
+        }
+        let kept: Vec<Bits<N>> = (0..T::DATA)
+            .map(|chunk| {
+                if changed.contains(&chunk) {
+                    Bits::zero()
+                } else {
+                    old[chunk]
+                }
+            })
+            .collect();
+        let partial = self.encode_parity(stripe_index, &kept)?;
+
+        let mut mask = vec![0u8; D.div_ceil(8)];
+        for disk in changed.filter_map(|chunk| Self::data_disk(stripe_index, chunk)) {
+            mask[disk / 8] |= 1 << (disk % 8);
+        }
+        let mut entry = Vec::with_capacity(8 + mask.len() + parity.len() * N);
+        entry.extend_from_slice(&stripe_index.to_le_bytes());
+        entry.extend_from_slice(&mask);
+        for disk in parity {
+            entry.extend_from_slice(partial[disk].as_bytes());
+        }
+        if self.append_log(&entry)? {
+            self.write_hole.logged.insert(stripe_index);
+            self.write_hole.stats.ppl_stripes += 1;
+            self.write_hole.stats.ppl_bytes += entry.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// `replay_ppl` recomputes the parity of every logged stripe and removes the log.
+    ///
+    /// Entries are applied in order, so the last write of a stripe decides its
+    /// parity. A stripe whose changed data member is missing is left alone, as is a
+    /// trailing entry cut short by the crash.
+    pub(super) fn replay_ppl(&mut self) {
+        let Some((path, body)) = self.read_log(PPL_FILE_NAME, PPL_MAGIC) else {
+            return;
+        };
+        let stripes = self.physical_stripes();
+        let mut rest = body.as_slice();
+        while rest.len() >= 8 + D.div_ceil(8) {
+            let (index, tail) = rest.split_at(8);
+            let (mask, tail) = tail.split_at(D.div_ceil(8));
+            let stripe_index = u64::from_le_bytes(index.try_into().unwrap_or([0xFF; 8]));
+            if stripe_index >= stripes {
+                break;
+            }
+            let parity = Self::parity_disks(stripe_index);
+            let Some((partial, tail)) = tail.split_at_checked(parity.len() * N) else {
+                break;
+            };
+            rest = tail;
+            let changed = |disk: usize| mask[disk / 8] & (1 << (disk % 8)) != 0;
+            self.apply_partial_parity(stripe_index, &parity, partial, changed);
+        }
+        self.finish_replay(&path);
+    }
+
+    /// `apply_partial_parity` writes the parity of one logged stripe.
+    fn apply_partial_parity(
+        &mut self,
+        stripe_index: u64,
+        parity: &[usize],
+        partial: &[u8],
+        changed: impl Fn(usize) -> bool,
+    ) {
+        let off = stripe_byte_offset::<N>(stripe_index);
+        let members = self.array.peek(off);
+        let mut data = vec![Bits::<N>::zero(); T::DATA];
+        for (chunk, slot) in data.iter_mut().enumerate() {
+            let Some(disk) = Self::data_disk(stripe_index, chunk).filter(|&d| changed(d)) else {
+                continue;
+            };
+            let Some(current) = members[disk] else {
+                return;
+            };
+            *slot = current;
+        }
+        let Ok(encoded) = self.encode_parity(stripe_index, &data) else {
+            return;
+        };
+        for (&disk, bytes) in parity.iter().zip(partial.chunks_exact(N)) {
+            let mut chunk = Bits::<N>::zero();
+            chunk.as_bytes_mut().copy_from_slice(bytes);
+            chunk ^= encoded[disk];
+            self.array.write_chunk(disk, off, &chunk);
+        }
+        self.write_hole.stats.replayed_stripes += 1;
+    }
+
+    /// `encode_parity` encodes `data` as stripe `stripe_index` and returns the raw chunks.
+    fn encode_parity(&mut self, stripe_index: u64, data: &[Bits<N>]) -> Result<[Bits<N>; D]> {
+        self.layout.place(stripe_index);
+        self.layout.try_write(data)?;
+        let mut raw = [Bits::<N>::zero(); D];
+        self.layout.read_raw(&mut raw);
+        Ok(raw)
+    }
+
+    /// `parity_disks` returns the members holding parity in a stripe.
+    fn parity_disks(stripe_index: u64) -> Vec<usize> {
+        (0..D)
+            .filter(|&disk| T::role_at(stripe_index, disk) == ChunkRole::Parity)
+            .collect()
+    }
+}
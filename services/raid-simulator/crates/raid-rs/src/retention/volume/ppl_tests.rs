@@ -0,0 +1,148 @@
+use super::test_utils::{disk_paths, synced_volume};
+use super::*;
+use crate::layout::stripe::raid1::RAID1;
+use crate::layout::stripe::raid3::RAID3;
+use std::os::unix::fs::FileExt;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+const STRIPE: usize = 2 * CHUNK_SIZE;
+
+fn open<T: Stripe<TEST_DISKS, CHUNK_SIZE>>(
+    dir: &TempDir,
+    layout: T,
+) -> Volume<TEST_DISKS, CHUNK_SIZE, T> {
+    synced_volume(dir, DISK_LEN, layout)
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (1..=u8::MAX).cycle().take(len).collect()
+}
+
+/// `tear_chunk_zero` writes a pattern, then tears a write to the first data chunk.
+fn tear_chunk_zero(dir: &TempDir) -> WriteHoleStats {
+    let mut volume = open(dir, RAID3::zero());
+    volume.write_bytes(0, &pattern(4 * STRIPE));
+    volume.sync().expect("sync");
+    volume.set_write_hole_policy(WriteHolePolicy::Ppl);
+    volume.simulate_crashes(CrashPlan {
+        seed: 1,
+        crash_after_writes: Some(1),
+        write_hole: true,
+    });
+    volume.write_bytes(0, &[0xEE; CHUNK_SIZE]);
+    volume.write_hole_stats()
+}
+
+#[test]
+fn ppl_logs_one_chunk_per_parity_member() {
+    let dir = TempDir::new().unwrap();
+    let stats = tear_chunk_zero(&dir);
+
+    assert_eq!(stats.torn_stripes, 1);
+    assert_eq!(stats.ppl_stripes, 1);
+    assert_eq!(stats.ppl_bytes, (8 + 1 + CHUNK_SIZE) as u64);
+    assert_eq!(stats.journaled_stripes, 0);
+}
+
+#[test]
+fn ppl_replay_closes_the_write_hole() {
+    let dir = TempDir::new().unwrap();
+    tear_chunk_zero(&dir);
+
+    let mut volume = open(&dir, RAID3::zero());
+    assert_eq!(volume.write_hole_stats().replayed_stripes, 1);
+    assert!(volume.check().is_clean());
+    assert!(!dir.path().join("partial-parity.log").exists());
+
+    volume.fail_disk(1).expect("fail disk");
+    let mut out = [0u8; STRIPE];
+    volume.read_bytes(0, &mut out);
+    let mut expected = pattern(STRIPE);
+    expected[..CHUNK_SIZE].fill(0xEE);
+    assert_eq!(out.to_vec(), expected);
+}
+
+#[test]
+fn ppl_replay_never_reads_unchanged_members() {
+    let dir = TempDir::new().unwrap();
+    tear_chunk_zero(&dir);
+    // Garble the unchanged chunk: a resync would fold it into the parity.
+    let disk1 = std::fs::OpenOptions::new()
+        .write(true)
+        .open(dir.path().join("disk-1.img"))
+        .unwrap();
+    disk1.write_all_at(&[0xAA; CHUNK_SIZE], 0).unwrap();
+    drop(disk1);
+
+    let mut volume = open(&dir, RAID3::zero());
+    volume.fail_disk(1).expect("fail disk");
+    let mut out = [0u8; STRIPE];
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out[CHUNK_SIZE..], pattern(STRIPE)[CHUNK_SIZE..]);
+}
+
+#[test]
+fn last_logged_write_decides_the_parity() {
+    for seed in 0..8 {
+        let dir = TempDir::new().unwrap();
+        let mut volume = open(&dir, RAID3::zero());
+        volume.set_write_hole_policy(WriteHolePolicy::Ppl);
+        volume.simulate_crashes(CrashPlan {
+            seed,
+            ..CrashPlan::default()
+        });
+        volume.write_bytes(0, &[1; 3]);
+        volume.write_bytes(2, &[2; 4]);
+        volume
+            .write_stripes(1..3, &pattern(2 * STRIPE))
+            .expect("write stripes");
+        volume.write_bytes(STRIPE as u64 + 5, &[3; 6]);
+        let stats = volume.write_hole_stats();
+        assert_eq!((stats.ppl_stripes, stats.ppl_flushes), (6, 2));
+        volume.crash();
+        drop(volume);
+
+        let mut volume = open(&dir, RAID3::zero());
+        assert!(volume.check().is_clean(), "seed {seed}");
+    }
+}
+
+#[test]
+fn layouts_without_parity_log_nothing() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = open(&dir, RAID1::zero());
+    volume.set_write_hole_policy(WriteHolePolicy::Ppl);
+    volume.write_bytes(0, &pattern(16));
+
+    assert_eq!(volume.write_hole_stats().ppl_stripes, 0);
+    assert!(!dir.path().join("partial-parity.log").exists());
+}
+
+#[test]
+fn read_only_open_leaves_the_log_and_members_alone() {
+    let dir = TempDir::new().unwrap();
+    tear_chunk_zero(&dir);
+    let images =
+        disk_paths::<TEST_DISKS>(&dir).map(|path| std::fs::read(path).expect("read image"));
+
+    let volume = Volume::new_read_only(
+        Array::init_array(&disk_paths(&dir), DISK_LEN).expect("init array"),
+        RAID3::<TEST_DISKS, CHUNK_SIZE>::zero(),
+    );
+    assert_eq!(volume.write_hole_stats().replayed_stripes, 0);
+    drop(volume);
+
+    assert!(dir.path().join("partial-parity.log").exists());
+    for (path, before) in disk_paths::<TEST_DISKS>(&dir).iter().zip(&images) {
+        assert_eq!(&std::fs::read(path).expect("read image"), before);
+    }
+    assert_eq!(
+        open(&dir, RAID3::zero())
+            .write_hole_stats()
+            .replayed_stripes,
+        1
+    );
+}
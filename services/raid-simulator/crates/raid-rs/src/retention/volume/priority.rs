@@ -0,0 +1,171 @@
+//! Bandwidth split between foreground and background IO.
+//!
+//! Foreground IO is what users of the volume ask for; background IO is the rebuild
+//! and scrub work the volume does on its own. Every operation is counted against
+//! its class. With a background share set, the scheduler measures how long
+//! background operations keep the array busy in windows of `PRIORITY_WINDOW`.
+//! While foreground IO arrived within the last window, `background_wait` pauses
+//! background work once it has used its share of the current window, so users keep
+//! the rest of the array. An otherwise idle volume rebuilds at full speed.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::metrics::{IoClass, IoOpType, RaidOp};
+use crate::retention::volume::Volume;
+
+/// `PRIORITY_WINDOW` is the period over which the background share is enforced.
+pub const PRIORITY_WINDOW: Duration = Duration::from_millis(100);
+
+/// `IoClassStats` counts the traffic of each IO class.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IoClassStats {
+    pub foreground_bytes: u64,
+    pub background_bytes: u64,
+    /// Times background work was asked to pause for foreground IO.
+    pub background_waits: u64,
+}
+
+/// `IoScheduler` tracks recent IO of both classes and paces background work.
+pub(super) struct IoScheduler {
+    /// Percent of each window background work may use while users are active.
+    share: Option<u8>,
+    window_start: Instant,
+    /// Time background operations kept the array busy in the current window.
+    background_busy: Duration,
+    last_foreground: Option<Instant>,
+    stats: IoClassStats,
+}
+
+impl Default for IoScheduler {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl IoScheduler {
+    /// `new` starts an unlimited scheduler whose first window opens at `now`.
+    pub(super) const fn new(now: Instant) -> Self {
+        Self {
+            share: None,
+            window_start: now,
+            background_busy: Duration::ZERO,
+            last_foreground: None,
+            stats: IoClassStats {
+                foreground_bytes: 0,
+                background_bytes: 0,
+                background_waits: 0,
+            },
+        }
+    }
+
+    /// `set_share` limits background work to `percent` of each window; `None` or
+    /// 100 lets it compete freely.
+    pub(super) fn set_share(&mut self, percent: Option<u8>) {
+        self.share = percent.map(|p| p.clamp(1, 100)).filter(|&p| p < 100);
+    }
+
+    /// `record` counts an operation of `bytes` that kept the array busy for `busy`.
+    pub(super) fn record(&mut self, class: IoClass, bytes: u64, busy: Duration, now: Instant) {
+        self.roll(now);
+        match class {
+            IoClass::Foreground => {
+                self.stats.foreground_bytes = self.stats.foreground_bytes.saturating_add(bytes);
+                self.last_foreground = Some(now);
+            }
+            IoClass::Background => {
+                self.stats.background_bytes = self.stats.background_bytes.saturating_add(bytes);
+                self.background_busy += busy;
+            }
+        }
+    }
+
+    /// `wait` returns how long background work must pause before its next operation.
+    pub(super) fn wait(&mut self, now: Instant) -> Duration {
+        let Some(share) = self.share else {
+            return Duration::ZERO;
+        };
+        let users_active = self
+            .last_foreground
+            .is_some_and(|t| now.saturating_duration_since(t) < PRIORITY_WINDOW);
+        if !users_active {
+            return Duration::ZERO;
+        }
+        self.roll(now);
+        if self.background_busy < PRIORITY_WINDOW * u32::from(share) / 100 {
+            return Duration::ZERO;
+        }
+        self.stats.background_waits += 1;
+        (self.window_start + PRIORITY_WINDOW).saturating_duration_since(now)
+    }
+
+    /// `roll` starts a new window once the current one has passed.
+    fn roll(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.window_start) >= PRIORITY_WINDOW {
+            self.window_start = now;
+            self.background_busy = Duration::ZERO;
+        }
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `set_background_share` caps rebuild and scrub work at `percent` of the array's
+    /// time while users are issuing IO.
+    ///
+    /// # Arguments
+    /// * `percent` - Share of each `PRIORITY_WINDOW` background work may use, from
+    ///   1 to 100; `None` or 100 lets it compete freely with users.
+    pub fn set_background_share(&mut self, percent: Option<u8>) {
+        self.priority.set_share(percent);
+    }
+
+    /// `background_share` returns the configured background share, if limited.
+    pub const fn background_share(&self) -> Option<u8> {
+        self.priority.share
+    }
+
+    /// `background_wait` returns how long background work should pause before its
+    /// next batch to leave foreground IO its share of the array.
+    ///
+    /// Callers holding the volume behind a lock should release it while they wait.
+    pub fn background_wait(&mut self) -> Duration {
+        self.priority.wait(Instant::now())
+    }
+
+    /// `io_class_stats` returns the bytes moved by each IO class so far.
+    pub const fn io_class_stats(&self) -> IoClassStats {
+        self.priority.stats
+    }
+
+    /// `record_foreground` counts a user read or write of `len` bytes.
+    pub(super) fn record_foreground(&mut self, len: usize) {
+        let bytes = u64::try_from(len).unwrap_or(u64::MAX);
+        self.priority
+            .record(IoClass::Foreground, bytes, Duration::ZERO, Instant::now());
+    }
+
+    /// `record_background` counts `stripes` stripes of rebuild, scrub or balance work
+    /// that started at `start` and records them as a background RAID operation.
+    pub(super) fn record_background(&mut self, stripes: usize, start: Instant) {
+        let now = Instant::now();
+        let busy = now.saturating_duration_since(start);
+        let bytes = (stripes as u64).saturating_mul(self.geom.bytes_per_stripe as u64);
+        self.priority.record(IoClass::Background, bytes, busy, now);
+        if crate::metrics::is_enabled() {
+            crate::metrics::record_raid_op(RaidOp {
+                op: IoOpType::Read,
+                bytes,
+                latency_seconds: busy.as_secs_f64(),
+                error: false,
+                degraded: self.is_degraded(),
+                read_ahead_hits: 0,
+                read_ahead_misses: 0,
+                parity_cache_hits: 0,
+                parity_cache_misses: 0,
+                class: IoClass::Background,
+            });
+        }
+    }
+}
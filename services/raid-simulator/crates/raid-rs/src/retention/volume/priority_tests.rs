@@ -0,0 +1,100 @@
+use super::priority::IoScheduler;
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use crate::metrics::IoClass;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+/// Logical bytes per RAID3 stripe: two data chunks.
+const STRIPE: usize = 2 * CHUNK_SIZE;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+#[test]
+fn unlimited_scheduler_never_pauses_background_work() {
+    let mut scheduler = IoScheduler::default();
+    let now = Instant::now();
+    scheduler.record(IoClass::Foreground, 512, Duration::ZERO, now);
+    scheduler.record(IoClass::Background, 512, PRIORITY_WINDOW * 4, now);
+    assert_eq!(scheduler.wait(now), Duration::ZERO);
+}
+
+#[test]
+fn background_pauses_after_its_share_while_users_are_active() {
+    let start = Instant::now();
+    let mut scheduler = IoScheduler::new(start);
+    scheduler.set_share(Some(20));
+
+    scheduler.record(IoClass::Background, 64, PRIORITY_WINDOW / 2, start);
+    assert_eq!(
+        scheduler.wait(start),
+        Duration::ZERO,
+        "idle users leave background work unthrottled"
+    );
+
+    let now = start + PRIORITY_WINDOW / 4;
+    scheduler.record(IoClass::Foreground, 64, Duration::ZERO, now);
+    assert_eq!(scheduler.wait(now), PRIORITY_WINDOW * 3 / 4);
+
+    let next = start + PRIORITY_WINDOW;
+    assert_eq!(scheduler.wait(next), Duration::ZERO, "a new window starts");
+    scheduler.record(IoClass::Background, 64, PRIORITY_WINDOW / 10, next);
+    assert_eq!(scheduler.wait(next), Duration::ZERO);
+    scheduler.record(IoClass::Background, 64, PRIORITY_WINDOW / 10, next);
+    assert_eq!(scheduler.wait(next), PRIORITY_WINDOW);
+
+    let idle = next + PRIORITY_WINDOW;
+    assert_eq!(scheduler.wait(idle), Duration::ZERO);
+}
+
+#[test]
+fn share_is_clamped_and_full_share_disables_the_limit() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    assert_eq!(volume.background_share(), None);
+
+    volume.set_background_share(Some(0));
+    assert_eq!(volume.background_share(), Some(1));
+    volume.set_background_share(Some(150));
+    assert_eq!(volume.background_share(), None);
+    volume.set_background_share(Some(30));
+    assert_eq!(volume.background_share(), Some(30));
+}
+
+#[test]
+fn user_io_and_repairs_are_counted_per_class() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+
+    volume.write_bytes(0, &[7; STRIPE * 2]);
+    let mut out = [0u8; STRIPE];
+    volume.read_bytes(0, &mut out);
+    volume.repair_stripes(&[0, 1, 2]);
+    volume.repair_stripe(5);
+    volume.check_stripe(0);
+
+    assert_eq!(
+        volume.io_class_stats(),
+        IoClassStats {
+            foreground_bytes: 3 * STRIPE as u64,
+            background_bytes: 5 * STRIPE as u64,
+            background_waits: 0,
+        }
+    );
+}
+
+#[test]
+fn rejected_user_io_is_not_counted() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let past_end = volume.logical_capacity_bytes();
+
+    assert!(volume.try_write_bytes(past_end, &[1; STRIPE]).is_err());
+    assert_eq!(volume.io_class_stats().foreground_bytes, 0);
+}
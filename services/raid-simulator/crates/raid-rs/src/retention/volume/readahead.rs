@@ -0,0 +1,339 @@
+//! Read-ahead of sequential reads into a stripe cache.
+//!
+//! A read that starts where the previous one ended counts as sequential and asks a
+//! worker thread to fetch the next `window` stripes straight from the member images,
+//! one contiguous read per disk. Fetched stripes wait in a bounded cache until a read
+//! consumes them. Every stripe write drops the cached copy and the stripe from any
+//! fetch still in flight, so the cache never serves data older than the disks.
+//!
+//! The cache only serves healthy, fully provisioned volumes whose member images hold
+//! every completed write: degraded reads, thin, compressed and balancing volumes,
+//! and volumes with unsynced crash-simulation writes go to the array as before.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::locate_byte;
+
+/// `ReadAheadStats` counts stripe loads served from and missing the read-ahead cache.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadAheadStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Stripes fetched by the worker and accepted into the cache.
+    pub prefetched: u64,
+}
+
+struct Request {
+    generation: u64,
+    /// Invalidations counted when the request was issued.
+    issued: u64,
+    first: u64,
+    count: u64,
+    paths: Vec<PathBuf>,
+}
+
+struct Fetched {
+    generation: u64,
+    issued: u64,
+    first: u64,
+    count: u64,
+    /// Bytes of `count` consecutive chunks, one buffer per disk; `None` if a read failed.
+    disks: Option<Vec<Vec<u8>>>,
+}
+
+/// `ReadAhead` tracks the sequential stream, the stripe cache, and the worker channels.
+pub(super) struct ReadAhead<const N: usize> {
+    window: u64,
+    /// Whether the current read may use the cache.
+    active: bool,
+    /// Offset a sequential read would start at.
+    next_offset: Option<u64>,
+    /// End of the stripes already requested from the worker.
+    requested_end: u64,
+    /// Bumped on every clear; fetches from older generations are dropped.
+    generation: u64,
+    /// Stripes invalidated so far.
+    invalidations: u64,
+    /// Invalidation count at which each stripe was last rewritten, kept while a fetch
+    /// issued before it may still be in flight.
+    rewritten: HashMap<u64, u64>,
+    /// Invalidation counts of the requests in flight, oldest first.
+    in_flight: VecDeque<u64>,
+    cache: HashMap<u64, Vec<Bits<N>>>,
+    /// Cached stripes, oldest first; holds exactly the keys of `cache`.
+    pub(super) order: VecDeque<u64>,
+    requests: mpsc::Sender<Request>,
+    fetched: mpsc::Receiver<Fetched>,
+    stats: ReadAheadStats,
+}
+
+impl<const N: usize> ReadAhead<N> {
+    /// `new` starts a worker prefetching up to `window` stripes ahead.
+    fn new(window: u64) -> Self {
+        let (requests, worker_requests) = mpsc::channel::<Request>();
+        let (worker_fetched, fetched) = mpsc::channel();
+        let scope = crate::metrics::thread_scope();
+        std::thread::spawn(move || {
+            crate::metrics::set_thread_scope(scope.as_deref());
+            for request in worker_requests {
+                let batch = Fetched {
+                    generation: request.generation,
+                    issued: request.issued,
+                    first: request.first,
+                    count: request.count,
+                    disks: fetch::<N>(&request),
+                };
+                if worker_fetched.send(batch).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            window,
+            active: false,
+            next_offset: None,
+            requested_end: 0,
+            generation: 0,
+            invalidations: 0,
+            rewritten: HashMap::new(),
+            in_flight: VecDeque::new(),
+            cache: HashMap::new(),
+            order: VecDeque::new(),
+            requests,
+            fetched,
+            stats: ReadAheadStats::default(),
+        }
+    }
+
+    /// `capacity` bounds the cache at two windows, the one being read and the next.
+    fn capacity(&self) -> usize {
+        usize::try_from(self.window.saturating_mul(2)).unwrap_or(usize::MAX)
+    }
+
+    /// `drain` moves finished fetches of the current generation into the cache,
+    /// skipping stripes rewritten after the fetch was issued.
+    fn drain(&mut self) {
+        while let Ok(batch) = self.fetched.try_recv() {
+            if batch.generation != self.generation {
+                continue;
+            }
+            if let Some(disks) = &batch.disks {
+                self.accept(batch.issued, batch.first..batch.first + batch.count, disks);
+            }
+            self.in_flight.pop_front();
+            match self.in_flight.front() {
+                Some(&oldest) => self.rewritten.retain(|_, &mut at| at > oldest),
+                None => self.rewritten.clear(),
+            }
+            while self.cache.len() > self.capacity() {
+                let Some(oldest) = self.order.pop_front() else {
+                    break;
+                };
+                self.cache.remove(&oldest);
+            }
+        }
+    }
+
+    /// `accept` caches the fetched `stripes` not rewritten since the fetch was issued.
+    fn accept(&mut self, issued: u64, stripes: Range<u64>, disks: &[Vec<u8>]) {
+        for (k, stripe) in stripes.enumerate() {
+            if self.rewritten.get(&stripe).is_some_and(|&at| at > issued) {
+                continue;
+            }
+            let chunks = disks
+                .iter()
+                .map(|disk| Bits(disk[k * N..(k + 1) * N].try_into().unwrap_or([0; N])))
+                .collect();
+            if self.cache.insert(stripe, chunks).is_none() {
+                self.order.push_back(stripe);
+            }
+            self.stats.prefetched += 1;
+        }
+    }
+
+    /// `lookup` returns the cached raw chunks of a stripe and counts the hit or miss.
+    fn lookup(&mut self, stripe_index: u64) -> Option<Vec<Bits<N>>> {
+        if !self.active {
+            return None;
+        }
+        let raw = self.cache.get(&stripe_index).cloned();
+        if raw.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        raw
+    }
+
+    /// `prefetch` requests the stripes of the window starting at `from` not yet requested.
+    fn prefetch(&mut self, from: u64, limit: u64, paths: Vec<PathBuf>) {
+        let end = from.saturating_add(self.window).min(limit);
+        let start = if (from..end).contains(&self.requested_end) {
+            self.requested_end
+        } else {
+            from
+        };
+        if start >= end || self.requested_end >= end {
+            return;
+        }
+        self.requested_end = end;
+        self.in_flight.push_back(self.invalidations);
+        let _ = self.requests.send(Request {
+            generation: self.generation,
+            issued: self.invalidations,
+            first: start,
+            count: end - start,
+            paths,
+        });
+    }
+
+    /// `invalidate` drops a stripe from the cache and from the fetches still in flight.
+    fn invalidate(&mut self, stripe_index: u64) {
+        if self.cache.remove(&stripe_index).is_some() {
+            self.order.retain(|&stripe| stripe != stripe_index);
+        }
+        if !self.in_flight.is_empty() {
+            self.invalidations += 1;
+            self.rewritten.insert(stripe_index, self.invalidations);
+        }
+        self.requested_end = 0;
+    }
+
+    /// `clear` drops the whole cache and every fetch still in flight.
+    fn clear(&mut self) {
+        self.cache.clear();
+        self.order.clear();
+        self.rewritten.clear();
+        self.in_flight.clear();
+        self.generation += 1;
+        self.requested_end = 0;
+    }
+}
+
+/// `fetch` reads the chunks of a request from every member image.
+///
+/// Returns `None` if any image cannot be read, so partial stripes are never cached.
+fn fetch<const N: usize>(request: &Request) -> Option<Vec<Vec<u8>>> {
+    let len = usize::try_from(request.count).ok()?.checked_mul(N)?;
+    let offset = request.first.checked_mul(N as u64)?;
+    request
+        .paths
+        .iter()
+        .map(|path| {
+            let mut buf = vec![0; len];
+            File::open(path)
+                .and_then(|file| file.read_exact_at(&mut buf, offset))
+                .ok()?;
+            Some(buf)
+        })
+        .collect()
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `set_read_ahead` prefetches `stripes` stripes ahead of sequential reads.
+    ///
+    /// Passing 0 disables read-ahead and drops the cache.
+    ///
+    /// # Arguments
+    /// * `stripes` - Size of the read-ahead window in stripes.
+    pub fn set_read_ahead(&mut self, stripes: u64) {
+        self.read_ahead = (stripes > 0).then(|| ReadAhead::new(stripes));
+    }
+
+    /// `read_ahead_stats` returns the read-ahead counters; all zero when it is disabled.
+    pub fn read_ahead_stats(&self) -> ReadAheadStats {
+        self.read_ahead
+            .as_ref()
+            .map_or_else(ReadAheadStats::default, |ra| ra.stats)
+    }
+
+    /// `read_ahead_begin` collects finished fetches and decides whether a read may use the cache.
+    pub(super) fn read_ahead_begin(&mut self, degraded: bool) {
+        if self.read_ahead.is_none() {
+            return;
+        }
+        let usable = !degraded
+            && self.thin.is_none()
+            && self.compression.is_none()
+            && self.balance.is_none()
+            && self.reshape.is_none()
+            && self.unsynced_writes() == 0;
+        if let Some(ra) = self.read_ahead.as_mut() {
+            ra.drain();
+            ra.active = usable;
+        }
+    }
+
+    /// `read_ahead_end` records where a read ended and prefetches past it if it was sequential.
+    pub(super) fn read_ahead_end(&mut self, byte_offset: u64, len: usize) {
+        let end = byte_offset.saturating_add(len as u64);
+        let limit = self.stripes_needed_for_logical_end(self.logical_capacity_bytes());
+        let (from, _) = locate_byte(end.min(self.logical_capacity_bytes()), 0, &self.geom);
+        let paths = self
+            .array
+            .0
+            .iter()
+            .map(|d| d.path().to_path_buf())
+            .collect();
+        let Some(ra) = self.read_ahead.as_mut() else {
+            return;
+        };
+        let sequential = ra.next_offset == Some(byte_offset);
+        ra.next_offset = Some(end);
+        if sequential && ra.active {
+            ra.prefetch(from, limit, paths);
+        }
+        ra.active = false;
+    }
+
+    /// `load_cached_stripe` loads a stripe from the read-ahead cache into the layout.
+    ///
+    /// A cached stripe that fails its scrub is dropped so the caller reads and repairs
+    /// it through the array.
+    ///
+    /// # Returns
+    /// `true` if the layout now holds the stripe.
+    pub(super) fn load_cached_stripe(&mut self, stripe_index: u64) -> bool {
+        let Some(raw) = self
+            .read_ahead
+            .as_mut()
+            .and_then(|ra| ra.lookup(stripe_index))
+        else {
+            return false;
+        };
+        self.layout.place(stripe_index);
+        let consistent = self.layout.try_write_raw(&raw).is_ok()
+            && self
+                .layout
+                .as_restore_mut()
+                .is_none_or(|restorer| restorer.scrub().is_empty());
+        if !consistent {
+            self.read_ahead_invalidate(stripe_index);
+        }
+        consistent
+    }
+
+    /// `read_ahead_invalidate` drops a rewritten stripe from the cache.
+    pub(super) fn read_ahead_invalidate(&mut self, stripe_index: u64) {
+        if let Some(ra) = self.read_ahead.as_mut() {
+            ra.invalidate(stripe_index);
+        }
+    }
+
+    /// `read_ahead_clear` drops the cache after a member changes.
+    pub(super) fn read_ahead_clear(&mut self) {
+        if let Some(ra) = self.read_ahead.as_mut() {
+            ra.clear();
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use std::time::Duration;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+/// Logical bytes per RAID3 stripe: two data chunks.
+const STRIPE: usize = 2 * CHUNK_SIZE;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i.to_le_bytes()[0]).collect()
+}
+
+/// `wait_for_prefetch` collects worker fetches until at least `stripes` have arrived.
+fn wait_for_prefetch(
+    volume: &mut Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>,
+    stripes: u64,
+) {
+    for _ in 0..500 {
+        volume.read_ahead_begin(false);
+        if volume.read_ahead_stats().prefetched >= stripes {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("worker did not prefetch {stripes} stripes");
+}
+
+fn read_at(
+    volume: &mut Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>,
+    offset: usize,
+) -> Vec<u8> {
+    let mut out = vec![0; STRIPE];
+    volume.read_bytes(offset as u64, &mut out);
+    out
+}
+
+#[test]
+fn sequential_reads_are_served_from_the_cache() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let data = pattern(8 * STRIPE);
+    volume.write_bytes(0, &data);
+    volume.set_read_ahead(4);
+
+    read_at(&mut volume, 0);
+    read_at(&mut volume, STRIPE);
+    wait_for_prefetch(&mut volume, 4);
+
+    for stripe in 2..6 {
+        let offset = stripe * STRIPE;
+        assert_eq!(read_at(&mut volume, offset), data[offset..offset + STRIPE]);
+    }
+    let stats = volume.read_ahead_stats();
+    assert_eq!(stats.hits, 4);
+    assert_eq!(stats.misses, 2);
+}
+
+#[test]
+fn random_reads_do_not_prefetch() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, &pattern(8 * STRIPE));
+    volume.set_read_ahead(4);
+
+    read_at(&mut volume, 5 * STRIPE);
+    read_at(&mut volume, 0);
+    read_at(&mut volume, 3 * STRIPE);
+    std::thread::sleep(Duration::from_millis(50));
+    volume.read_ahead_begin(false);
+
+    assert_eq!(volume.read_ahead_stats().prefetched, 0);
+}
+
+#[test]
+fn writes_invalidate_cached_stripes() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, &pattern(8 * STRIPE));
+    volume.set_read_ahead(4);
+    read_at(&mut volume, 0);
+    read_at(&mut volume, STRIPE);
+    wait_for_prefetch(&mut volume, 4);
+
+    let fresh = [0xAB; STRIPE];
+    volume.write_bytes(2 * STRIPE as u64, &fresh);
+
+    assert_eq!(read_at(&mut volume, 2 * STRIPE), fresh);
+    assert_eq!(volume.read_ahead_stats().hits, 0);
+}
+
+#[test]
+fn writes_discard_only_the_rewritten_stripes_of_a_fetch() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let data = pattern(8 * STRIPE);
+    volume.write_bytes(0, &data);
+    volume.set_read_ahead(4);
+    read_at(&mut volume, 0);
+    read_at(&mut volume, STRIPE);
+
+    // Stripes 2..6 are in flight; one write lands inside the fetch, one outside it.
+    let fresh = [0xAB; STRIPE];
+    volume.write_bytes(3 * STRIPE as u64, &fresh);
+    volume.write_bytes(7 * STRIPE as u64, &fresh);
+    wait_for_prefetch(&mut volume, 3);
+
+    assert_eq!(volume.read_ahead_stats().prefetched, 3);
+    assert_eq!(
+        read_at(&mut volume, 2 * STRIPE),
+        data[2 * STRIPE..3 * STRIPE]
+    );
+    assert_eq!(read_at(&mut volume, 3 * STRIPE), fresh);
+}
+
+#[test]
+fn rewritten_stripes_leave_the_eviction_queue() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, &pattern(8 * STRIPE));
+    volume.set_read_ahead(4);
+
+    for round in 1..=10 {
+        read_at(&mut volume, 0);
+        read_at(&mut volume, STRIPE);
+        wait_for_prefetch(&mut volume, round * 4);
+        for stripe in 2..6 {
+            volume.write_bytes((stripe * STRIPE) as u64, &[round.to_le_bytes()[0]; STRIPE]);
+        }
+    }
+
+    let queued = volume.read_ahead.as_ref().map(|ra| ra.order.len());
+    assert_eq!(queued, Some(0), "every queued stripe was rewritten");
+}
+
+#[test]
+fn degraded_reads_bypass_the_cache() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let data = pattern(8 * STRIPE);
+    volume.write_bytes(0, &data);
+    volume.set_read_ahead(4);
+    read_at(&mut volume, 0);
+    read_at(&mut volume, STRIPE);
+    wait_for_prefetch(&mut volume, 4);
+
+    volume.fail_disk(1).unwrap();
+
+    assert_eq!(
+        read_at(&mut volume, 2 * STRIPE),
+        data[2 * STRIPE..3 * STRIPE]
+    );
+    assert_eq!(volume.read_ahead_stats().hits, 0);
+}
+
+#[test]
+fn disabled_read_ahead_reports_no_activity() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, &pattern(4 * STRIPE));
+
+    read_at(&mut volume, 0);
+    read_at(&mut volume, STRIPE);
+    read_at(&mut volume, 2 * STRIPE);
+
+    assert_eq!(volume.read_ahead_stats(), ReadAheadStats::default());
+}
+
+#[test]
+fn compressed_volumes_do_not_prefetch() {
+    let dir = TempDir::new().unwrap();
+    let mut volume: Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> =
+        synced_volume(&dir, 64 * DISK_LEN, RAID3::zero());
+    volume
+        .init_compression(4 * 64 * DISK_LEN, 0)
+        .expect("init compression");
+    let block = crate::retention::volume::COMPRESS_BLOCK;
+    let data = pattern(4 * block);
+    volume.write_bytes(0, &data);
+    volume.set_read_ahead(4);
+
+    // Drive the hooks too: the raw stripes of the pool are not the logical data.
+    let mut out = vec![0; block];
+    for i in 0..3 {
+        volume.read_ahead_begin(false);
+        volume.read_bytes((i * block) as u64, &mut out);
+        volume.read_ahead_end((i * block) as u64, block);
+        assert_eq!(out, data[i * block..(i + 1) * block]);
+    }
+    std::thread::sleep(Duration::from_millis(50));
+    volume.read_ahead_begin(false);
+
+    assert_eq!(volume.read_ahead_stats().prefetched, 0);
+}
@@ -0,0 +1,428 @@
+//! Online reshape that restripes a mounted array onto appended members.
+//!
+//! A balance moves data into the geometry a volume was opened with, so it needs
+//! the array reopened with its new member count. A reshape is its counterpart for
+//! a volume that keeps serving IO: the volume stays in its own geometry and moves
+//! its data forward onto a target volume opened on all members, old and new, one
+//! batch of stripes at a time. Logical bytes before the reshape position are
+//! served by the target and the rest by the volume itself.
+//!
+//! New stripes are written in increasing order, but a batch of them still lands on
+//! disk offsets that hold source stripes past the saved position, so a crash in
+//! the middle of a batch would leave data the resumed reshape can no longer read
+//! from the source. Every batch is therefore first copied to a backup file next
+//! to the disk images and synced, then written to the target, which is synced
+//! before the checkpoint moves past the batch. A reshape resumed at the position
+//! of the backup writes the batch from the backup again. The checkpoint and the
+//! backup are synced together with their directory, so a resumed reshape never
+//! sees a position ahead of the data. Once every stripe has moved both files are
+//! removed, and the volume serves all IO through the target and reports the
+//! target's capacity.
+
+use std::fs::File;
+use std::io::Write as _;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::balance::{check_end, split_at};
+use crate::retention::volume::{Access, BalanceProgress, DynVolume, Volume};
+use crate::{Error, Result};
+
+/// `RESHAPE_FILE_NAME` is the file next to the disk images holding the position of
+/// an unfinished reshape.
+pub const RESHAPE_FILE_NAME: &str = "reshape.checkpoint";
+const RESHAPE_MAGIC: [u8; 8] = *b"RAIDRSH1";
+const RESHAPE_LEN: usize = 32;
+/// `BACKUP_FILE_NAME` is the file next to the disk images holding a copy of the
+/// batch a reshape is writing.
+const BACKUP_FILE_NAME: &str = "reshape.backup";
+const BACKUP_MAGIC: [u8; 8] = *b"RAIDRSB1";
+const BACKUP_HEADER_LEN: usize = 16;
+
+/// `write_durably` replaces `path` with `bytes` so that a crash leaves either the
+/// old or the new contents, and the new ones survive once it returns.
+fn write_durably(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp).map_err(Error::io(&tmp))?;
+    file.write_all(bytes)
+        .and_then(|()| file.sync_all())
+        .map_err(Error::io(&tmp))?;
+    std::fs::rename(&tmp, path).map_err(Error::io(path))?;
+    sync_dir(path)
+}
+
+/// `sync_dir` makes the directory entries next to `path` durable.
+fn sync_dir(path: &Path) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    File::open(dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(Error::io(dir))
+}
+
+/// `remove_durably` deletes `path` if it exists and syncs its directory.
+fn remove_durably(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Ok(()) => sync_dir(path),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(Error::io(path)(err)),
+    }
+}
+
+/// `load_backup` reads the batch stored in the backup file of `disk_dir`.
+///
+/// # Returns
+/// The logical position and bytes of the batch, or `None` without a usable backup.
+fn load_backup(disk_dir: &Path) -> Result<Option<(u64, Vec<u8>)>> {
+    let path = disk_dir.join(BACKUP_FILE_NAME);
+    let raw = match std::fs::read(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(Error::io(&path)(err)),
+    };
+    if raw.len() < BACKUP_HEADER_LEN || raw[0..8] != BACKUP_MAGIC {
+        return Err(Error::Corrupt(format!(
+            "malformed reshape backup in {}",
+            disk_dir.display()
+        )));
+    }
+    let position = raw[8..16].try_into().map_or(0, u64::from_le_bytes);
+    Ok(Some((position, raw[BACKUP_HEADER_LEN..].to_vec())))
+}
+
+/// `ReshapeCheckpoint` records how far the online reshape of an array has come.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReshapeCheckpoint {
+    /// Member count of the geometry the data is moved from.
+    pub source_disks: usize,
+    /// Member count of the geometry the data is moved to.
+    pub target_disks: usize,
+    /// Logical bytes already moved to the target geometry.
+    pub position: u64,
+}
+
+impl ReshapeCheckpoint {
+    /// `load` reads the checkpoint of an unfinished reshape stored in `disk_dir`.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint exists but cannot be read or is malformed.
+    pub fn load(disk_dir: &Path) -> Result<Option<Self>> {
+        let path = disk_dir.join(RESHAPE_FILE_NAME);
+        let raw = match std::fs::read(&path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::io(&path)(err)),
+        };
+        if raw.len() != RESHAPE_LEN || raw[0..8] != RESHAPE_MAGIC {
+            return Err(Error::Corrupt(format!(
+                "malformed reshape checkpoint in {}",
+                disk_dir.display()
+            )));
+        }
+        let word = |at: usize| raw[at..at + 8].try_into().map_or(0, u64::from_le_bytes);
+        Ok(Some(Self {
+            source_disks: usize::try_from(word(8))?,
+            target_disks: usize::try_from(word(16))?,
+            position: word(24),
+        }))
+    }
+
+    /// `save` stores the checkpoint in `disk_dir`, replacing any previous one.
+    ///
+    /// # Errors
+    /// Returns an error if the checkpoint cannot be written.
+    pub fn save(&self, disk_dir: &Path) -> Result<()> {
+        let mut raw = [0u8; RESHAPE_LEN];
+        raw[0..8].copy_from_slice(&RESHAPE_MAGIC);
+        raw[8..16].copy_from_slice(&(self.source_disks as u64).to_le_bytes());
+        raw[16..24].copy_from_slice(&(self.target_disks as u64).to_le_bytes());
+        raw[24..32].copy_from_slice(&self.position.to_le_bytes());
+        write_durably(&disk_dir.join(RESHAPE_FILE_NAME), &raw)
+    }
+}
+
+/// `Reshape` is the state of a running or finished reshape.
+pub(super) struct Reshape {
+    target: Box<dyn DynVolume>,
+    target_disks: usize,
+    position: u64,
+    /// Capacity of this volume in its own geometry.
+    source_end: u64,
+    /// Capacity of the target.
+    end: u64,
+}
+
+impl Reshape {
+    /// `capacity` returns the bytes served while the reshape is at its position.
+    const fn capacity(&self) -> u64 {
+        if self.position < self.end {
+            self.source_end
+        } else {
+            self.end
+        }
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `begin_reshape` starts moving the data of this volume onto `target`.
+    ///
+    /// `target` is the array grown by appended members, opened on all of them.
+    /// Every stripe of it is rewritten by the reshape, so its pending rebuilds are
+    /// dropped. Bytes before `position` must already be in the target's geometry.
+    ///
+    /// # Arguments
+    /// * `target` - Volume with the geometry the data is moved to.
+    /// * `target_disks` - Member count of `target`, recorded in the checkpoint.
+    /// * `position` - Logical bytes already moved, a multiple of the target's
+    ///   stripe size.
+    ///
+    /// A batch left in the backup file at `position` by an interrupted reshape is
+    /// written to the target again, and the reshape goes on after it.
+    ///
+    /// # Errors
+    /// Returns an error if a balance or reshape is already running, the volume is
+    /// thin, compressed, has snapshots or members out of sync, `target` has no
+    /// more members or does not hold this volume, `position` is not stripe
+    /// aligned, or the backup cannot be replayed or the checkpoint written.
+    pub fn begin_reshape(
+        &mut self,
+        mut target: Box<dyn DynVolume>,
+        target_disks: usize,
+        position: u64,
+    ) -> Result<()> {
+        if self.balance.is_some() || self.reshape.is_some() {
+            return Err(Error::Invalid(
+                "a balance or reshape is already running".to_string(),
+            ));
+        }
+        if self.thin.is_some() || self.compression.is_some() || self.snapshots.is_some() {
+            return Err(Error::Invalid(
+                "thin, compressed and snapshotted volumes cannot be reshaped".to_string(),
+            ));
+        }
+        if self.is_degraded() || self.any_needs_rebuild() {
+            return Err(Error::Invalid(
+                "every member must be present and in sync to reshape".to_string(),
+            ));
+        }
+        if target_disks <= D {
+            return Err(Error::Geometry(format!(
+                "a reshape of {D} members needs more of them, not {target_disks}"
+            )));
+        }
+        let source_end = self.logical_capacity_bytes();
+        let end = target.logical_capacity_bytes();
+        if source_end > end {
+            return Err(Error::Geometry(format!(
+                "{source_end} bytes do not fit into a target of {end} bytes"
+            )));
+        }
+        if !position.is_multiple_of(target.geometry().bytes_per_stripe as u64) {
+            return Err(Error::Geometry(format!(
+                "reshape position {position} is not stripe aligned"
+            )));
+        }
+        self.flush();
+        target.clear_needs_rebuild_all();
+        let mut position = position;
+        if let Some(dir) = self.balance_dir()
+            && let Some((at, batch)) = load_backup(&dir)?
+            && at == position
+        {
+            // The source stripes under the batch may already be overwritten, so
+            // the reshape goes on after it.
+            target.try_write_bytes(at, &batch)?;
+            target.sync()?;
+            position = at + batch.len() as u64;
+        }
+        self.reshape = Some(Reshape {
+            target,
+            target_disks,
+            position: position.min(end),
+            source_end,
+            end,
+        });
+        self.save_reshape()
+    }
+
+    /// `is_reshaping` reports whether data is still being moved onto a target volume.
+    pub fn is_reshaping(&self) -> bool {
+        self.reshape
+            .as_ref()
+            .is_some_and(|reshape| reshape.position < reshape.end)
+    }
+
+    /// `reshape_progress` returns how much data a running or finished reshape has moved.
+    pub fn reshape_progress(&self) -> Option<BalanceProgress> {
+        self.reshape.as_ref().map(|reshape| BalanceProgress {
+            moved_bytes: reshape.position,
+            total_bytes: reshape.end,
+        })
+    }
+
+    /// `reshape_step` moves up to `max_stripes` target stripes onto the target volume.
+    ///
+    /// The moved stripes count as background IO. Once everything is moved the
+    /// checkpoint is removed and the volume reports the capacity of the target.
+    ///
+    /// # Returns
+    /// The number of stripes moved; 0 once no reshape is running.
+    ///
+    /// # Errors
+    /// Returns an error if the stripes cannot be read or written or the checkpoint
+    /// cannot be updated. The position does not advance in that case.
+    pub fn reshape_step(&mut self, max_stripes: usize) -> Result<usize> {
+        let Some(mut reshape) = self.reshape.take() else {
+            return Ok(0);
+        };
+        let result = self.move_stripes(&mut reshape, max_stripes);
+        self.reshape = Some(reshape);
+        let stripes = result?;
+        if stripes > 0 && !self.is_reshaping() {
+            self.read_ahead_clear();
+            self.parity_cache_clear();
+            if let Some(dir) = self.balance_dir() {
+                remove_durably(&dir.join(RESHAPE_FILE_NAME))?;
+                remove_durably(&dir.join(BACKUP_FILE_NAME))?;
+            }
+        }
+        Ok(stripes)
+    }
+
+    /// `move_stripes` copies the next batch of a reshape from this volume's own
+    /// geometry onto the target and advances the position.
+    ///
+    /// The batch is backed up before the target overwrites the source stripes
+    /// under it, and the target and the checkpoint are synced before the position
+    /// moves past it.
+    fn move_stripes(&mut self, reshape: &mut Reshape, max_stripes: usize) -> Result<usize> {
+        if reshape.position >= reshape.end {
+            return Ok(0);
+        }
+        let start = Instant::now();
+        let stripe_bytes = reshape.target.geometry().bytes_per_stripe as u64;
+        let position = reshape.position;
+        let len = (max_stripes.max(1) as u64 * stripe_bytes).min(reshape.end - position);
+        let mut buf = vec![0u8; usize::try_from(len)?];
+        let from_source = usize::try_from(len.min(reshape.source_end.saturating_sub(position)))?;
+        if from_source > 0 {
+            self.read_logical(position, &mut buf[..from_source], Access::Internal)?;
+        }
+        // Cached member writes must land before the target overwrites their offsets.
+        self.flush();
+        let dir = self.balance_dir();
+        if let Some(dir) = dir.as_deref() {
+            let mut backup = Vec::with_capacity(BACKUP_HEADER_LEN + buf.len());
+            backup.extend_from_slice(&BACKUP_MAGIC);
+            backup.extend_from_slice(&position.to_le_bytes());
+            backup.extend_from_slice(&buf);
+            write_durably(&dir.join(BACKUP_FILE_NAME), &backup)?;
+        }
+        reshape.target.try_write_bytes(position, &buf)?;
+        reshape.target.sync()?;
+        if let Some(dir) = dir.as_deref() {
+            ReshapeCheckpoint {
+                source_disks: D,
+                target_disks: reshape.target_disks,
+                position: position + len,
+            }
+            .save(dir)?;
+        }
+        reshape.position += len;
+
+        let stripes = usize::try_from(len.div_ceil(stripe_bytes))?;
+        self.record_background(stripes, start);
+        Ok(stripes)
+    }
+
+    fn save_reshape(&self) -> Result<()> {
+        let (Some(reshape), Some(dir)) = (self.reshape.as_ref(), self.balance_dir()) else {
+            return Ok(());
+        };
+        ReshapeCheckpoint {
+            source_disks: D,
+            target_disks: reshape.target_disks,
+            position: reshape.position,
+        }
+        .save(&dir)
+    }
+
+    /// `reshape_capacity` returns the capacity served while a reshape is running
+    /// or after it finished.
+    pub(super) fn reshape_capacity(&self) -> Option<u64> {
+        self.reshape.as_ref().map(Reshape::capacity)
+    }
+
+    /// `write_reshaped` writes bytes before the reshape position to the target and
+    /// the rest to this volume.
+    pub(super) fn write_reshaped(
+        &mut self,
+        byte_offset: u64,
+        payload: &[u8],
+        strict: bool,
+    ) -> Result<()> {
+        let Some(mut reshape) = self.reshape.take() else {
+            return self.write_bytes_checked(byte_offset, payload, strict);
+        };
+        let head = split_at(reshape.position, byte_offset, payload.len());
+        let (front, back) = payload.split_at(head);
+        let tail_offset = byte_offset + head as u64;
+        let result = check_end(reshape.capacity(), byte_offset, payload.len(), strict)
+            .and_then(|()| {
+                if front.is_empty() {
+                    Ok(())
+                } else if strict {
+                    reshape.target.try_write_bytes(byte_offset, front)
+                } else {
+                    reshape.target.write_bytes(byte_offset, front);
+                    Ok(())
+                }
+            })
+            .and_then(|()| {
+                if back.is_empty() {
+                    return Ok(());
+                }
+                self.write_bytes_checked(tail_offset, back, strict)
+            });
+        self.reshape = Some(reshape);
+        result
+    }
+
+    /// `read_reshaped` reads bytes before the reshape position from the target and
+    /// the rest from this volume.
+    pub(super) fn read_reshaped(
+        &mut self,
+        byte_offset: u64,
+        out: &mut [u8],
+        strict: bool,
+    ) -> Result<()> {
+        let Some(mut reshape) = self.reshape.take() else {
+            return self.read_bytes_checked(byte_offset, out, strict);
+        };
+        let head = split_at(reshape.position, byte_offset, out.len());
+        let len = out.len();
+        let (front, back) = out.split_at_mut(head);
+        let tail_offset = byte_offset + head as u64;
+        let result = check_end(reshape.capacity(), byte_offset, len, strict)
+            .and_then(|()| {
+                if front.is_empty() {
+                    Ok(())
+                } else if strict {
+                    reshape.target.try_read_bytes(byte_offset, front)
+                } else {
+                    reshape.target.read_bytes(byte_offset, front);
+                    Ok(())
+                }
+            })
+            .and_then(|()| {
+                if back.is_empty() {
+                    return Ok(());
+                }
+                self.read_bytes_checked(tail_offset, back, strict)
+            });
+        self.reshape = Some(reshape);
+        result
+    }
+}
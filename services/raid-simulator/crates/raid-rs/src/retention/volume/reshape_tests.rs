@@ -0,0 +1,168 @@
+use super::test_utils::{fresh_volume, synced_volume};
+use super::*;
+use crate::layout::stripe::raid0::RAID0;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const CHUNK_SIZE: usize = 64;
+const DISK_LEN: u64 = 1024;
+const OLD_DISKS: usize = 3;
+const NEW_DISKS: usize = 4;
+/// Logical bytes per stripe of the grown RAID3 array: three data chunks.
+const NEW_STRIPE: u64 = 3 * CHUNK_SIZE as u64;
+
+type OldVolume = Volume<OLD_DISKS, CHUNK_SIZE, RAID3<OLD_DISKS, CHUNK_SIZE>>;
+type NewVolume = Volume<NEW_DISKS, CHUNK_SIZE, RAID3<NEW_DISKS, CHUNK_SIZE>>;
+
+fn old_volume(dir: &TempDir) -> OldVolume {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn new_volume(dir: &TempDir) -> NewVolume {
+    fresh_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn pattern(len: u64) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+fn read<const D: usize, T: Stripe<D, CHUNK_SIZE>>(
+    volume: &mut Volume<D, CHUNK_SIZE, T>,
+    offset: u64,
+    len: usize,
+) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.try_read_bytes(offset, &mut out).expect("read");
+    out
+}
+
+/// `reshaping_volume` fills an array and starts reshaping it onto one more member.
+fn reshaping_volume(dir: &TempDir, position: u64) -> (OldVolume, Vec<u8>) {
+    let mut volume = old_volume(dir);
+    let data = pattern(volume.logical_capacity_bytes());
+    volume.write_bytes(0, &data);
+    volume
+        .begin_reshape(Box::new(new_volume(dir)), NEW_DISKS, position)
+        .expect("begin reshape");
+    (volume, data)
+}
+
+#[test]
+fn reshape_moves_data_while_io_follows_the_position() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, mut data) = reshaping_volume(&dir, 0);
+    let old_capacity = data.len() as u64;
+    assert!(volume.is_reshaping());
+    assert_eq!(volume.logical_capacity_bytes(), old_capacity);
+
+    assert_eq!(volume.reshape_step(2).expect("step"), 2);
+    let moved = volume.reshape_progress().expect("progress").moved_bytes;
+    assert_eq!(moved, 2 * NEW_STRIPE);
+    volume
+        .try_write_bytes(moved - 4, b"straddle")
+        .expect("write");
+    data[usize::try_from(moved).unwrap() - 4..][..8].copy_from_slice(b"straddle");
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    assert!(volume.try_write_bytes(old_capacity, b"x").is_err());
+
+    while volume.reshape_step(3).expect("step") > 0 {}
+    assert!(!volume.is_reshaping());
+    assert!(!dir.path().join(RESHAPE_FILE_NAME).exists());
+    let new_capacity = 3 * DISK_LEN;
+    assert_eq!(volume.logical_capacity_bytes(), new_capacity);
+    volume
+        .try_write_bytes(new_capacity - 4, b"tail")
+        .expect("write past the old capacity");
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    drop(volume);
+
+    let mut volume = new_volume(&dir);
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    let tail = usize::try_from(new_capacity - old_capacity).unwrap();
+    let added = read(&mut volume, old_capacity, tail);
+    assert!(added[..tail - 4].iter().all(|&b| b == 0));
+    assert_eq!(&added[tail - 4..], b"tail");
+}
+
+#[test]
+fn reshape_resumes_from_its_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, data) = reshaping_volume(&dir, 0);
+    volume.reshape_step(4).expect("step");
+    drop(volume);
+
+    let checkpoint = ReshapeCheckpoint::load(dir.path())
+        .expect("load")
+        .expect("checkpoint");
+    assert_eq!(
+        checkpoint,
+        ReshapeCheckpoint {
+            source_disks: OLD_DISKS,
+            target_disks: NEW_DISKS,
+            position: 4 * NEW_STRIPE,
+        }
+    );
+
+    let mut volume = old_volume(&dir);
+    volume
+        .begin_reshape(Box::new(new_volume(&dir)), NEW_DISKS, checkpoint.position)
+        .expect("resume");
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    while volume.reshape_step(64).expect("step") > 0 {}
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    assert_eq!(ReshapeCheckpoint::load(dir.path()).expect("load"), None);
+}
+
+#[test]
+fn begin_reshape_rejects_running_work_and_bad_targets() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, _) = reshaping_volume(&dir, 0);
+    assert!(
+        volume
+            .begin_reshape(Box::new(new_volume(&dir)), NEW_DISKS, 0)
+            .is_err()
+    );
+    assert!(volume.init_snapshots(NEW_STRIPE, 0).is_err());
+
+    let mut fresh = old_volume(&dir);
+    let narrower = fresh_volume(&dir, DISK_LEN, RAID0::<2, CHUNK_SIZE>::zero());
+    assert!(fresh.begin_reshape(Box::new(narrower), 2, 0).is_err());
+    assert!(
+        fresh
+            .begin_reshape(Box::new(new_volume(&dir)), NEW_DISKS, 100)
+            .is_err()
+    );
+
+    fresh.fail_disk(1).expect("fail");
+    assert!(
+        fresh
+            .begin_reshape(Box::new(new_volume(&dir)), NEW_DISKS, 0)
+            .is_err()
+    );
+}
+
+#[test]
+fn reshape_replays_a_batch_interrupted_before_its_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let (mut volume, data) = reshaping_volume(&dir, 0);
+    volume.reshape_step(2).expect("step");
+    volume.reshape_step(3).expect("step");
+    drop(volume);
+
+    // A crash after the batch reached the target but before the checkpoint moved
+    // past it leaves the checkpoint at the start of the batch.
+    let interrupted = ReshapeCheckpoint {
+        source_disks: OLD_DISKS,
+        target_disks: NEW_DISKS,
+        position: 2 * NEW_STRIPE,
+    };
+    interrupted.save(dir.path()).expect("save checkpoint");
+
+    let mut volume = old_volume(&dir);
+    volume
+        .begin_reshape(Box::new(new_volume(&dir)), NEW_DISKS, interrupted.position)
+        .expect("resume");
+    while volume.reshape_step(64).expect("step") > 0 {}
+    assert_eq!(read(&mut volume, 0, data.len()), data);
+    assert!(!dir.path().join("reshape.backup").exists());
+}
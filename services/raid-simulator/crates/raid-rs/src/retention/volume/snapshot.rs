@@ -0,0 +1,666 @@
+//! Copy-on-write snapshots kept in a reserved region at the end of the volume.
+//!
+//! Copies are made per COW block, a run of whole stripes of roughly `COW_BLOCK_BYTES`.
+//! The region starts with a table of snapshots followed by one COW map per snapshot
+//! and a pool of block-sized slots. Each map entry records which slot preserves the
+//! block as it was when the snapshot was taken (`0` means the live block is still
+//! unchanged). A superblock in the last bytes of the volume locates the region.
+
+use std::ops::Range;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::{Access, Volume};
+use crate::{Error, Result};
+
+/// `MAX_SNAPSHOTS` is the number of snapshots a volume can hold at once.
+pub const MAX_SNAPSHOTS: usize = 8;
+/// `SNAPSHOT_NAME_LEN` is the maximum snapshot name length in bytes.
+pub const SNAPSHOT_NAME_LEN: usize = 32;
+
+const SNAP_MAGIC: [u8; 8] = *b"RAIDSNP1";
+const SNAP_VERSION: u8 = 1;
+const SUPERBLOCK_SIZE: u64 = SUPERBLOCK_LEN as u64;
+const SUPERBLOCK_LEN: usize = 64;
+const TABLE_ENTRY_SIZE: u64 = TABLE_ENTRY_LEN as u64;
+const TABLE_ENTRY_LEN: usize = 64;
+const MAP_ENTRY_SIZE: u64 = 4;
+const COW_BLOCK_BYTES: u64 = 256;
+
+/// `SnapshotInfo` describes a snapshot stored in the volume.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// Position of the snapshot in the snapshot table.
+    pub index: usize,
+    pub id: u64,
+    pub name: String,
+    pub created_unix: u64,
+    /// Number of COW blocks whose original contents have been preserved.
+    pub preserved_blocks: u64,
+    /// `false` once the slot pool overflowed and the snapshot lost data.
+    pub valid: bool,
+}
+
+/// `SnapshotUsage` reports how much of the copy-on-write pool the snapshots consume.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotUsage {
+    pub snapshots: u64,
+    pub block_bytes: u64,
+    /// Pool slots holding preserved blocks; a slot shared by snapshots counts once.
+    pub used_slots: u64,
+    pub pool_slots: u64,
+}
+
+impl SnapshotUsage {
+    #[must_use]
+    /// `used_bytes` returns the bytes of preserved blocks in the pool.
+    pub const fn used_bytes(&self) -> u64 {
+        self.used_slots * self.block_bytes
+    }
+
+    #[must_use]
+    /// `pool_bytes` returns the bytes the pool can preserve in total.
+    pub const fn pool_bytes(&self) -> u64 {
+        self.pool_slots * self.block_bytes
+    }
+
+    #[must_use]
+    /// `free_bytes` returns the bytes the pool can still preserve.
+    pub const fn free_bytes(&self) -> u64 {
+        self.pool_bytes().saturating_sub(self.used_bytes())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SnapshotMeta {
+    id: u64,
+    name: String,
+    created_unix: u64,
+    valid: bool,
+}
+
+/// `SnapshotStore` is the in-memory copy of the reserved snapshot region.
+pub struct SnapshotStore {
+    region_start: u64,
+    block_bytes: u64,
+    data_blocks: u64,
+    slots_start: u64,
+    slot_count: u64,
+    next_id: u64,
+    table: Vec<Option<SnapshotMeta>>,
+    maps: Vec<Vec<u32>>,
+    slot_refs: Vec<u32>,
+}
+
+impl SnapshotStore {
+    /// `region_start` returns the first logical byte of the reserved region.
+    pub const fn region_start(&self) -> u64 {
+        self.region_start
+    }
+
+    const fn table_offset(&self, index: usize) -> u64 {
+        self.region_start + index as u64 * TABLE_ENTRY_SIZE
+    }
+
+    const fn map_offset(&self, index: usize, block: u64) -> u64 {
+        let maps_start = self.region_start + MAX_SNAPSHOTS as u64 * TABLE_ENTRY_SIZE;
+        maps_start + (index as u64 * self.data_blocks + block) * MAP_ENTRY_SIZE
+    }
+
+    const fn slot_offset(&self, slot: u32) -> u64 {
+        self.slots_start + (slot as u64 - 1) * self.block_bytes
+    }
+
+    fn info(&self, index: usize) -> Option<SnapshotInfo> {
+        let meta = self.table.get(index)?.as_ref()?;
+        Some(SnapshotInfo {
+            index,
+            id: meta.id,
+            name: meta.name.clone(),
+            created_unix: meta.created_unix,
+            preserved_blocks: self.maps[index].iter().filter(|&&m| m != 0).count() as u64,
+            valid: meta.valid,
+        })
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.table
+            .iter()
+            .position(|meta| meta.as_ref().is_some_and(|m| m.name == name))
+    }
+
+    fn alloc_slot(&self) -> Option<u32> {
+        let pos = self.slot_refs.iter().position(|&refs| refs == 0)?;
+        u32::try_from(pos + 1).ok()
+    }
+
+    fn release_map(&mut self, index: usize) {
+        for m in std::mem::take(&mut self.maps[index]) {
+            if m != 0 {
+                self.slot_refs[m as usize - 1] -= 1;
+            }
+        }
+    }
+}
+
+fn encode_meta(meta: Option<&SnapshotMeta>) -> [u8; TABLE_ENTRY_LEN] {
+    let mut buf = [0u8; TABLE_ENTRY_LEN];
+    if let Some(meta) = meta {
+        buf[0] = 1;
+        buf[1] = u8::from(meta.valid);
+        buf[8..16].copy_from_slice(&meta.id.to_le_bytes());
+        buf[16..24].copy_from_slice(&meta.created_unix.to_le_bytes());
+        let name = meta.name.as_bytes();
+        let len = name.len().min(SNAPSHOT_NAME_LEN);
+        buf[24..24 + len].copy_from_slice(&name[..len]);
+    }
+    buf
+}
+
+fn decode_meta(buf: &[u8]) -> Option<SnapshotMeta> {
+    if buf[0] != 1 {
+        return None;
+    }
+    let name_bytes = &buf[24..24 + SNAPSHOT_NAME_LEN];
+    let name_len = name_bytes
+        .iter()
+        .position(|&b| b == 0)
+        .unwrap_or(SNAPSHOT_NAME_LEN);
+    Some(SnapshotMeta {
+        id: u64::from_le_bytes(buf[8..16].try_into().ok()?),
+        name: String::from_utf8_lossy(&name_bytes[..name_len]).into_owned(),
+        created_unix: u64::from_le_bytes(buf[16..24].try_into().ok()?),
+        valid: buf[1] == 1,
+    })
+}
+
+fn u64_at(buf: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(buf[at..at + 8].try_into().unwrap_or_default())
+}
+
+/// `RegionLayout` is the placement of the snapshot region for a given reserve.
+struct RegionLayout {
+    region_start: u64,
+    block_bytes: u64,
+    data_blocks: u64,
+    maps_end: u64,
+    usable: u64,
+}
+
+/// `snapshots_disabled` is the error of a snapshot operation on a volume without
+/// a snapshot store.
+fn snapshots_disabled() -> Error {
+    Error::Invalid("snapshots are not enabled on this volume".to_string())
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    fn region_layout(&self, region_start: Option<u64>, reserve_bytes: u64) -> RegionLayout {
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let block_bytes = COW_BLOCK_BYTES.div_ceil(stripe_bytes) * stripe_bytes;
+        let usable = self.raw_capacity_bytes() / stripe_bytes * stripe_bytes;
+        let region_start = region_start
+            .unwrap_or_else(|| usable.saturating_sub(reserve_bytes) / block_bytes * block_bytes);
+        let data_blocks = region_start / block_bytes;
+        let maps_end =
+            region_start + MAX_SNAPSHOTS as u64 * (TABLE_ENTRY_SIZE + data_blocks * MAP_ENTRY_SIZE);
+        RegionLayout {
+            region_start,
+            block_bytes,
+            data_blocks,
+            maps_end,
+            usable,
+        }
+    }
+
+    /// `snapshots_enabled` reports whether the volume has a snapshot region.
+    pub const fn snapshots_enabled(&self) -> bool {
+        self.snapshots.is_some()
+    }
+
+    /// `init_snapshots` reserves the tail of the volume for snapshot storage.
+    ///
+    /// # Arguments
+    /// * `reserve_bytes` - Number of logical bytes to reserve.
+    /// * `data_end` - End of the live data that must stay outside the reserved region.
+    ///
+    /// # Errors
+    /// Returns an error if snapshots are already enabled, the volume is thin,
+    /// compressed or being balanced, the reserve overlaps live data, or the reserve is
+    /// too small to hold a single preserved stripe.
+    pub fn init_snapshots(&mut self, reserve_bytes: u64, data_end: u64) -> Result<()> {
+        if self.snapshots.is_some() {
+            return Err(Error::Invalid("snapshots are already enabled".to_string()));
+        }
+        if self.thin.is_some() || self.compression.is_some() {
+            return Err(Error::Invalid(
+                "snapshots cannot be combined with thin provisioning or compression".to_string(),
+            ));
+        }
+        if self.balance.is_some() || self.reshape.is_some() {
+            return Err(Error::Invalid(
+                "snapshots cannot be enabled while a balance or reshape is running".to_string(),
+            ));
+        }
+        let layout = self.region_layout(None, reserve_bytes);
+        if layout.region_start < data_end {
+            return Err(Error::OutOfSpace(format!(
+                "reserving {reserve_bytes} bytes would overlap {data_end} bytes of live data"
+            )));
+        }
+
+        let slots_start = layout.maps_end.div_ceil(layout.block_bytes) * layout.block_bytes;
+        let slots_end = layout.usable.saturating_sub(SUPERBLOCK_SIZE);
+        if slots_end < slots_start + layout.block_bytes {
+            return Err(Error::Geometry(format!(
+                "snapshot reserve of {reserve_bytes} bytes is too small"
+            )));
+        }
+        let slot_count = (slots_end - slots_start) / layout.block_bytes;
+        let region_start = layout.region_start;
+
+        let store = SnapshotStore {
+            region_start,
+            block_bytes: layout.block_bytes,
+            data_blocks: layout.data_blocks,
+            slots_start,
+            slot_count,
+            next_id: 1,
+            table: vec![None; MAX_SNAPSHOTS],
+            maps: vec![Vec::new(); MAX_SNAPSHOTS],
+            slot_refs: vec![0; usize::try_from(slot_count).unwrap_or(0)],
+        };
+        let zeros = vec![0u8; usize::try_from(slots_start - region_start).unwrap_or(0)];
+        self.write_logical(region_start, &zeros, Access::Internal)?;
+        self.snapshots = Some(store);
+        self.save_superblock()?;
+        self.save_features()
+    }
+
+    /// `snapshot_list` returns the snapshots stored in the volume.
+    pub fn snapshot_list(&self) -> Vec<SnapshotInfo> {
+        self.snapshots.as_ref().map_or_else(Vec::new, |store| {
+            (0..MAX_SNAPSHOTS).filter_map(|i| store.info(i)).collect()
+        })
+    }
+
+    /// `snapshot_usage` returns copy-on-write pool usage when snapshots are enabled.
+    pub fn snapshot_usage(&self) -> Option<SnapshotUsage> {
+        self.snapshots.as_ref().map(|store| SnapshotUsage {
+            snapshots: store.table.iter().flatten().count() as u64,
+            block_bytes: store.block_bytes,
+            used_slots: store.slot_refs.iter().filter(|&&refs| refs > 0).count() as u64,
+            pool_slots: store.slot_count,
+        })
+    }
+
+    /// `snapshot_create` records a new point-in-time snapshot of the volume.
+    ///
+    /// # Arguments
+    /// * `name` - Unique snapshot name.
+    ///
+    /// # Errors
+    /// Returns an error if snapshots are not enabled, the name is invalid or taken,
+    /// or the snapshot table is full.
+    pub fn snapshot_create(&mut self, name: &str) -> Result<SnapshotInfo> {
+        let Some(store) = self.snapshots.as_mut() else {
+            return Err(snapshots_disabled());
+        };
+        if name.is_empty() || name.len() > SNAPSHOT_NAME_LEN || name.contains(['/', '\0']) {
+            return Err(Error::Invalid(format!("invalid snapshot name {name:?}")));
+        }
+        if store.find(name).is_some() {
+            return Err(Error::Invalid(format!("snapshot {name} already exists")));
+        }
+        let Some(index) = store.table.iter().position(Option::is_none) else {
+            return Err(Error::OutOfSpace(format!(
+                "snapshot table is full ({MAX_SNAPSHOTS} snapshots)"
+            )));
+        };
+
+        let created_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let meta = SnapshotMeta {
+            id: store.next_id,
+            name: name.to_string(),
+            created_unix,
+            valid: true,
+        };
+        store.next_id += 1;
+        store.maps[index] = vec![0; usize::try_from(store.data_blocks).unwrap_or(0)];
+        store.table[index] = Some(meta);
+
+        let map_start = store.map_offset(index, 0);
+        let map_len = usize::try_from(store.data_blocks * MAP_ENTRY_SIZE).unwrap_or(0);
+        self.write_logical(map_start, &vec![0u8; map_len], Access::Internal)?;
+        self.save_table_entry(index)?;
+        self.save_superblock()?;
+        self.snapshots
+            .as_ref()
+            .and_then(|store| store.info(index))
+            .ok_or_else(|| Error::Corrupt(format!("snapshot {name} vanished")))
+    }
+
+    /// `snapshot_delete` removes a snapshot and releases its preserved stripes.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the snapshot to delete.
+    ///
+    /// # Errors
+    /// Returns an error if snapshots are not enabled or the snapshot does not exist.
+    pub fn snapshot_delete(&mut self, name: &str) -> Result<()> {
+        let index = self.snapshot_index(name)?;
+        if let Some(store) = self.snapshots.as_mut() {
+            store.release_map(index);
+            store.table[index] = None;
+        }
+        self.save_table_entry(index)
+    }
+
+    /// `disable_snapshots` releases the reserved region back to the live volume.
+    ///
+    /// # Errors
+    /// Returns an error if snapshots are not enabled or any snapshot still exists.
+    pub fn disable_snapshots(&mut self) -> Result<()> {
+        let Some(store) = self.snapshots.as_ref() else {
+            return Err(snapshots_disabled());
+        };
+        if store.table.iter().any(Option::is_some) {
+            return Err(Error::Invalid(
+                "delete all snapshots before disabling them".to_string(),
+            ));
+        }
+        let region_start = store.region_start;
+        let usable = self.region_layout(Some(0), 0).usable;
+        self.snapshots = None;
+        self.save_features()?;
+        let zeros = vec![0u8; usize::try_from(usable - region_start).unwrap_or(0)];
+        self.write_logical(region_start, &zeros, Access::Internal)
+    }
+
+    /// `snapshot_rollback` restores the live volume to the contents of a snapshot.
+    ///
+    /// The snapshot is kept; other snapshots preserve the stripes the rollback overwrites.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the snapshot to roll back to.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist or is no longer valid.
+    pub fn snapshot_rollback(&mut self, name: &str) -> Result<()> {
+        let index = self.snapshot_index(name)?;
+        let Some(store) = self.snapshots.as_ref() else {
+            return Err(snapshots_disabled());
+        };
+        if !store.table[index].as_ref().is_some_and(|m| m.valid) {
+            return Err(Error::Corrupt(format!("snapshot {name} is invalid")));
+        }
+        let block_bytes = store.block_bytes;
+        let preserved: Vec<(u64, u64)> = store.maps[index]
+            .iter()
+            .enumerate()
+            .filter(|&(_, &m)| m != 0)
+            .map(|(block, &m)| (block as u64, store.slot_offset(m)))
+            .collect();
+
+        let mut buf = vec![0u8; usize::try_from(block_bytes).unwrap_or(0)];
+        for (block, slot_offset) in preserved {
+            self.read_logical(slot_offset, &mut buf, Access::Internal)?;
+            self.write_logical(block * block_bytes, &buf, Access::Live)?;
+        }
+        Ok(())
+    }
+
+    /// `read_snapshot_bytes` reads bytes from a snapshot as it was when it was taken.
+    ///
+    /// # Arguments
+    /// * `index` - Table index of the snapshot (see `SnapshotInfo::index`).
+    /// * `byte_offset` - Logical byte offset within the snapshot.
+    /// * `out` - Output buffer to populate.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist, is invalid, or the range
+    /// extends past the snapshotted capacity.
+    pub fn read_snapshot_bytes(
+        &mut self,
+        index: usize,
+        byte_offset: u64,
+        out: &mut [u8],
+    ) -> Result<()> {
+        let Some(store) = self.snapshots.as_ref() else {
+            return Err(snapshots_disabled());
+        };
+        if !store
+            .table
+            .get(index)
+            .and_then(Option::as_ref)
+            .is_some_and(|m| m.valid)
+        {
+            return Err(Error::Invalid(format!(
+                "snapshot {index} does not exist or is invalid"
+            )));
+        }
+        let end = byte_offset.saturating_add(out.len() as u64);
+        if end > store.region_start {
+            return Err(Error::Geometry(
+                "read past the end of the snapshot".to_string(),
+            ));
+        }
+
+        let block_bytes = store.block_bytes;
+        let mut pieces = Vec::new();
+        let mut done = 0usize;
+        while done < out.len() {
+            let pos = byte_offset + done as u64;
+            let block = usize::try_from(pos / block_bytes).unwrap_or(usize::MAX);
+            let in_block = pos % block_bytes;
+            let take = usize::try_from(block_bytes - in_block)
+                .unwrap_or(usize::MAX)
+                .min(out.len() - done);
+            let slot = store.maps[index][block];
+            let source = if slot == 0 {
+                pos
+            } else {
+                store.slot_offset(slot) + in_block
+            };
+            pieces.push((done, take, source));
+            done += take;
+        }
+
+        for (at, take, source) in pieces {
+            self.read_logical(source, &mut out[at..at + take], Access::Internal)?;
+        }
+        Ok(())
+    }
+
+    /// `snapshot_changed_ranges` returns the logical byte ranges written since a
+    /// snapshot was taken.
+    ///
+    /// A block is written after the snapshot exactly when the snapshot preserved
+    /// it, so the COW map doubles as a dirty map. Ranges cover whole COW blocks
+    /// and adjacent blocks are merged.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the snapshot to compare against.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot does not exist or is no longer valid.
+    pub fn snapshot_changed_ranges(&self, name: &str) -> Result<Vec<Range<u64>>> {
+        let index = self.snapshot_index(name)?;
+        let Some(store) = self.snapshots.as_ref() else {
+            return Err(snapshots_disabled());
+        };
+        if !store.table[index].as_ref().is_some_and(|m| m.valid) {
+            return Err(Error::Corrupt(format!("snapshot {name} is invalid")));
+        }
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for (block, _) in store.maps[index]
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| **m != 0)
+        {
+            let start = block as u64 * store.block_bytes;
+            let end = start + store.block_bytes;
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end = end,
+                _ => ranges.push(start..end),
+            }
+        }
+        Ok(ranges)
+    }
+
+    fn snapshot_index(&self, name: &str) -> Result<usize> {
+        let Some(store) = self.snapshots.as_ref() else {
+            return Err(snapshots_disabled());
+        };
+        store
+            .find(name)
+            .ok_or_else(|| Error::Invalid(format!("snapshot {name} does not exist")))
+    }
+
+    /// `preserve_stripe` copies the COW block holding a stripe into snapshots that still need it.
+    ///
+    /// Must run before the stripe is overwritten. When the slot pool is exhausted, the
+    /// snapshots that needed the copy are invalidated instead.
+    pub(super) fn preserve_stripe(&mut self, stripe_index: u64) -> Result<()> {
+        let Some(store) = self.snapshots.as_mut() else {
+            return Ok(());
+        };
+        let block = stripe_index * self.geom.bytes_per_stripe as u64 / store.block_bytes;
+        if block >= store.data_blocks {
+            return Ok(());
+        }
+        let Ok(block_idx) = usize::try_from(block) else {
+            return Ok(());
+        };
+        let pending: Vec<usize> = (0..MAX_SNAPSHOTS)
+            .filter(|&i| {
+                store.table[i].as_ref().is_some_and(|m| m.valid) && store.maps[i][block_idx] == 0
+            })
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let Some(slot) = store.alloc_slot() else {
+            for &i in &pending {
+                store.release_map(i);
+                store.maps[i] = vec![0; usize::try_from(store.data_blocks).unwrap_or(0)];
+                if let Some(meta) = store.table[i].as_mut() {
+                    meta.valid = false;
+                }
+            }
+            for i in pending {
+                self.save_table_entry(i)?;
+            }
+            return Ok(());
+        };
+
+        store.slot_refs[slot as usize - 1] = u32::try_from(pending.len()).unwrap_or(u32::MAX);
+        let slot_offset = store.slot_offset(slot);
+        let block_start = block * store.block_bytes;
+        let mut entries = Vec::with_capacity(pending.len());
+        for &i in &pending {
+            store.maps[i][block_idx] = slot;
+            entries.push(store.map_offset(i, block));
+        }
+
+        let mut bytes = vec![0u8; usize::try_from(store.block_bytes).unwrap_or(0)];
+        self.read_logical(block_start, &mut bytes, Access::Internal)?;
+        self.write_logical(slot_offset, &bytes, Access::Internal)?;
+        for map_offset in entries {
+            self.write_logical(map_offset, &slot.to_le_bytes(), Access::Internal)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn load_snapshot_store(&mut self) -> Option<SnapshotStore> {
+        let usable = self.region_layout(Some(0), 0).usable;
+        if usable < SUPERBLOCK_SIZE {
+            return None;
+        }
+        let mut sb = [0u8; SUPERBLOCK_LEN];
+        self.read_logical(usable - SUPERBLOCK_SIZE, &mut sb, Access::Internal)
+            .ok()?;
+        if sb[0..8] != SNAP_MAGIC || sb[8] != SNAP_VERSION {
+            return None;
+        }
+
+        let layout = self.region_layout(Some(u64_at(&sb, 16)), 0);
+        let slots_start = u64_at(&sb, 24);
+        let slot_count = u64_at(&sb, 32);
+        let next_id = u64_at(&sb, 40);
+        if !layout.region_start.is_multiple_of(layout.block_bytes) || layout.region_start >= usable
+        {
+            return None;
+        }
+        let slots_end = slot_count
+            .checked_mul(layout.block_bytes)
+            .and_then(|len| slots_start.checked_add(len))?;
+        if slots_start < layout.maps_end || slots_end > usable - SUPERBLOCK_SIZE {
+            return None;
+        }
+
+        let mut store = SnapshotStore {
+            region_start: layout.region_start,
+            block_bytes: layout.block_bytes,
+            data_blocks: layout.data_blocks,
+            slots_start,
+            slot_count,
+            next_id,
+            table: vec![None; MAX_SNAPSHOTS],
+            maps: vec![Vec::new(); MAX_SNAPSHOTS],
+            slot_refs: vec![0; usize::try_from(slot_count).ok()?],
+        };
+        let mut entry = [0u8; TABLE_ENTRY_LEN];
+        let mut raw_map = vec![0u8; usize::try_from(layout.data_blocks * MAP_ENTRY_SIZE).ok()?];
+        for i in 0..MAX_SNAPSHOTS {
+            self.read_logical(store.table_offset(i), &mut entry, Access::Internal)
+                .ok()?;
+            let Some(meta) = decode_meta(&entry) else {
+                continue;
+            };
+            self.read_logical(store.map_offset(i, 0), &mut raw_map, Access::Internal)
+                .ok()?;
+            let map: Vec<u32> = raw_map
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes(c.try_into().unwrap_or_default()))
+                .map(|m| if u64::from(m) > slot_count { 0 } else { m })
+                .collect();
+            for &m in &map {
+                if m != 0 {
+                    store.slot_refs[m as usize - 1] += 1;
+                }
+            }
+            store.maps[i] = map;
+            store.table[i] = Some(meta);
+        }
+        Some(store)
+    }
+
+    fn save_table_entry(&mut self, index: usize) -> Result<()> {
+        let Some(store) = self.snapshots.as_ref() else {
+            return Ok(());
+        };
+        let offset = store.table_offset(index);
+        let bytes = encode_meta(store.table[index].as_ref());
+        self.write_logical(offset, &bytes, Access::Internal)
+    }
+
+    fn save_superblock(&mut self) -> Result<()> {
+        let Some(store) = self.snapshots.as_ref() else {
+            return Ok(());
+        };
+        let mut sb = [0u8; SUPERBLOCK_LEN];
+        sb[0..8].copy_from_slice(&SNAP_MAGIC);
+        sb[8] = SNAP_VERSION;
+        sb[16..24].copy_from_slice(&store.region_start.to_le_bytes());
+        sb[24..32].copy_from_slice(&store.slots_start.to_le_bytes());
+        sb[32..40].copy_from_slice(&store.slot_count.to_le_bytes());
+        sb[40..48].copy_from_slice(&store.next_id.to_le_bytes());
+        let usable = self.region_layout(Some(0), 0).usable;
+        self.write_logical(usable - SUPERBLOCK_SIZE, &sb, Access::Internal)
+    }
+}
@@ -0,0 +1,186 @@
+use super::test_utils::fresh_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 4096;
+
+type TestVolume = Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>;
+
+fn make_volume(dir: &TempDir) -> TestVolume {
+    fresh_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn read(volume: &mut TestVolume, offset: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.read_bytes(offset, &mut out);
+    out
+}
+
+fn read_snapshot(volume: &mut TestVolume, index: usize, offset: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume
+        .read_snapshot_bytes(index, offset, &mut out)
+        .expect("read snapshot");
+    out
+}
+
+#[test]
+fn init_snapshots_reserves_tail_and_persists() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let raw = volume.logical_capacity_bytes();
+
+    volume.init_snapshots(2048, 100).expect("init snapshots");
+    let capacity = volume.logical_capacity_bytes();
+    assert!(capacity <= raw - 2048);
+    assert!(capacity >= 100);
+    assert!(!volume.reserved_stripes().is_empty());
+    drop(volume);
+
+    let volume = make_volume(&dir);
+    assert!(volume.snapshots_enabled());
+    assert_eq!(volume.logical_capacity_bytes(), capacity);
+}
+
+#[test]
+fn init_snapshots_refuses_to_overlap_live_data() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let raw = volume.logical_capacity_bytes();
+
+    assert!(volume.init_snapshots(2048, raw - 10).is_err());
+    assert!(!volume.snapshots_enabled());
+}
+
+#[test]
+fn snapshot_preserves_contents_across_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    volume.write_bytes(0, b"original data");
+
+    let snap = volume.snapshot_create("before").expect("create snapshot");
+    volume.write_bytes(0, b"modified DATA");
+
+    assert_eq!(read(&mut volume, 0, 13), b"modified DATA");
+    assert_eq!(
+        read_snapshot(&mut volume, snap.index, 0, 13),
+        b"original data"
+    );
+    let listed = volume.snapshot_list();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "before");
+    assert!(listed[0].preserved_blocks > 0);
+}
+
+#[test]
+fn snapshot_state_survives_reopen() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    volume.write_bytes(4, b"abcdef");
+    let snap = volume.snapshot_create("s1").expect("create snapshot");
+    volume.write_bytes(4, b"zzzzzz");
+    drop(volume);
+
+    let mut volume = make_volume(&dir);
+    assert_eq!(read_snapshot(&mut volume, snap.index, 4, 6), b"abcdef");
+    assert_eq!(read(&mut volume, 4, 6), b"zzzzzz");
+}
+
+#[test]
+fn rollback_restores_snapshot_and_keeps_newer_snapshots() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    volume.write_bytes(0, b"v1v1v1");
+    volume.snapshot_create("one").expect("create one");
+    volume.write_bytes(0, b"v2v2v2");
+    let two = volume.snapshot_create("two").expect("create two");
+
+    volume.snapshot_rollback("one").expect("rollback");
+
+    assert_eq!(read(&mut volume, 0, 6), b"v1v1v1");
+    assert_eq!(read_snapshot(&mut volume, two.index, 0, 6), b"v2v2v2");
+}
+
+#[test]
+fn delete_releases_snapshot_and_rejects_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    volume.snapshot_create("dup").expect("create");
+    assert!(volume.snapshot_create("dup").is_err());
+
+    volume.snapshot_delete("dup").expect("delete");
+
+    assert!(volume.snapshot_list().is_empty());
+    assert!(volume.snapshot_delete("dup").is_err());
+
+    let raw = DISK_LEN * 2;
+    volume.disable_snapshots().expect("disable");
+    assert!(!volume.snapshots_enabled());
+    assert_eq!(volume.logical_capacity_bytes(), raw);
+}
+
+#[test]
+fn snapshot_is_invalidated_when_slots_run_out() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    let snap = volume.snapshot_create("small").expect("create");
+    let capacity = usize::try_from(volume.logical_capacity_bytes()).expect("capacity");
+
+    volume.write_bytes(0, &vec![0xAB; capacity]);
+
+    assert!(!volume.snapshot_list()[0].valid);
+    let mut out = [0u8; 4];
+    assert!(volume.read_snapshot_bytes(snap.index, 0, &mut out).is_err());
+    assert!(volume.snapshot_rollback("small").is_err());
+}
+
+#[test]
+fn snapshot_usage_counts_shared_slots_once() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    assert_eq!(volume.snapshot_usage(), None);
+    volume.init_snapshots(2048, 0).expect("init snapshots");
+    volume.write_bytes(0, b"original");
+    volume.snapshot_create("a").expect("create a");
+    volume.snapshot_create("b").expect("create b");
+
+    volume.write_bytes(0, b"modified");
+
+    let usage = volume.snapshot_usage().expect("usage");
+    assert_eq!((usage.snapshots, usage.used_slots), (2, 1));
+    assert_eq!(usage.used_bytes(), usage.block_bytes);
+    assert_eq!(usage.free_bytes(), usage.pool_bytes() - usage.block_bytes);
+    volume.snapshot_delete("a").expect("delete a");
+    assert_eq!(volume.snapshot_usage().expect("usage").used_slots, 1);
+    volume.snapshot_delete("b").expect("delete b");
+    assert_eq!(volume.snapshot_usage().expect("usage").used_slots, 0);
+}
+
+#[test]
+fn changed_ranges_list_blocks_written_since_the_snapshot() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(4096, 0).expect("init snapshots");
+    volume.write_bytes(0, b"before");
+    volume.snapshot_create("base").expect("create");
+    assert_eq!(volume.snapshot_changed_ranges("base").expect("ranges"), []);
+    let block = volume.snapshot_usage().expect("usage").block_bytes;
+
+    volume.write_bytes(1, b"x");
+    volume.write_bytes(block, b"y");
+    volume.write_bytes(3 * block + 2, b"z");
+
+    assert_eq!(
+        volume.snapshot_changed_ranges("base").expect("ranges"),
+        [0..2 * block, 3 * block..4 * block]
+    );
+    assert!(volume.snapshot_changed_ranges("missing").is_err());
+}
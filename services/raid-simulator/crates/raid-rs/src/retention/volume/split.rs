@@ -0,0 +1,75 @@
+//! Splitting a mirror member off for backups and re-adding it afterwards.
+//!
+//! A split flushes the volume, then moves the image of one RAID1 member aside so
+//! it holds a point-in-time copy of the logical volume. The array keeps running
+//! degraded, and the write-intent bitmap records every region written meanwhile.
+//! Re-adding the image resyncs only those regions, so the copy must not be
+//! modified while it is split off.
+
+use std::path::PathBuf;
+
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::retention::volume::{DiskChange, Volume};
+use crate::{Error, Result};
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `split_mirror` detaches a RAID1 member as a standalone, consistent image.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the member to split off.
+    ///
+    /// # Returns
+    /// The path the member image was moved to.
+    ///
+    /// # Errors
+    /// Returns an error if the layout is not a mirror, the member is missing or out
+    /// of sync, no other in-sync member would remain, or the volume cannot be flushed.
+    pub fn split_mirror(&mut self, i: usize) -> Result<PathBuf> {
+        if !(0..D).all(|d| matches!(T::role(d), ChunkRole::Mirror(_))) {
+            return Err(Error::Invalid(
+                "only mirrored layouts can split off a member".into(),
+            ));
+        }
+        self.check_present(i)?;
+        if self.array.0[i].needs_rebuild {
+            return Err(Error::Invalid(format!(
+                "disk {i} is not in sync and cannot be split off"
+            )));
+        }
+        let in_sync = self
+            .array
+            .0
+            .iter()
+            .filter(|d| !d.is_missing() && !d.needs_rebuild)
+            .count();
+        if in_sync < 2 {
+            return Err(Error::Invalid(format!(
+                "disk {i} is the last in-sync mirror"
+            )));
+        }
+
+        self.sync()?;
+        let path = self.array.split_disk(i)?;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_forget(i);
+        self.emit_disk(DiskChange::Split, i);
+        Ok(path)
+    }
+
+    /// `re_add` restores a split-off member and schedules a bitmap-based resync.
+    ///
+    /// # Arguments
+    /// * `i` - Index of the member to re-add.
+    ///
+    /// # Errors
+    /// Returns an error if the disk is still attached or has no split image to restore.
+    pub fn re_add(&mut self, i: usize) -> Result<()> {
+        self.array.rejoin_disk(i)?;
+        self.read_ahead_clear();
+        self.parity_cache_clear();
+        self.intent_reattach(i);
+        self.emit_disk(DiskChange::Reattached, i);
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid1::RAID1;
+use crate::layout::stripe::raid3::RAID3;
+use crate::retention::disk::WriteCachePlan;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 2;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 4096;
+const REGION_BYTES: u64 = INTENT_REGION_STRIPES * CHUNK_SIZE as u64;
+
+type TestVolume = Volume<TEST_DISKS, CHUNK_SIZE, RAID1<TEST_DISKS, CHUNK_SIZE>>;
+
+fn make_volume(dir: &TempDir) -> TestVolume {
+    synced_volume(dir, DISK_LEN, RAID1::zero())
+}
+
+fn read(volume: &mut TestVolume, offset: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.read_bytes(offset, &mut out);
+    out
+}
+
+#[test]
+fn split_image_holds_the_flushed_volume() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.enable_write_cache(WriteCachePlan::default());
+    volume.write_bytes(0, b"point-in-time");
+
+    let image = volume.split_mirror(1).expect("split");
+    volume.write_bytes(0, b"after-split!!");
+
+    let copy = std::fs::read(&image).expect("read split image");
+    assert_eq!(&copy[..13], b"point-in-time");
+    assert!(!dir.path().join("disk-1.img").exists());
+    assert_eq!(volume.failed_disks(), 1);
+    assert_eq!(read(&mut volume, 0, 13), b"after-split!!");
+}
+
+#[test]
+fn re_add_resyncs_only_regions_written_while_split() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"old-data");
+    volume.write_bytes(5 * REGION_BYTES, b"old-tail");
+
+    volume.split_mirror(1).expect("split");
+    volume.write_bytes(5 * REGION_BYTES, b"new-tail");
+    assert_eq!(volume.dirty_regions(), 1);
+
+    volume.re_add(1).expect("re-add");
+    let end = volume.logical_capacity_bytes();
+    assert_eq!(
+        volume.stripes_to_repair(end).len() as u64,
+        INTENT_REGION_STRIPES
+    );
+
+    volume.rebuild_disk_upto(1, end).expect("rebuild");
+    assert_eq!(volume.dirty_regions(), 0);
+    volume.fail_disk(0).expect("fail disk");
+    assert_eq!(read(&mut volume, 0, 8), b"old-data");
+    assert_eq!(read(&mut volume, 5 * REGION_BYTES, 8), b"new-tail");
+}
+
+#[test]
+fn split_keeps_one_in_sync_mirror() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+
+    volume.split_mirror(0).expect("split");
+    assert!(volume.split_mirror(1).is_err());
+    assert!(volume.split_mirror(0).is_err());
+    assert!(volume.re_add(1).is_err());
+    volume.re_add(0).expect("re-add");
+    assert!(volume.split_mirror(1).is_err(), "disk 0 is still resyncing");
+}
+
+#[test]
+fn split_rejects_parity_layouts() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = synced_volume(&dir, DISK_LEN, RAID3::<3, CHUNK_SIZE>::zero());
+
+    assert!(matches!(volume.split_mirror(0), Err(Error::Invalid(_))));
+    assert_eq!(volume.failed_disks(), 0);
+}
@@ -0,0 +1,89 @@
+//! Volume-level status that adds layout and rebuild progress to the array status.
+
+use std::time::Instant;
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::array::{ArrayStatus, RebuildStatus};
+use crate::retention::volume::Volume;
+
+/// `RebuildProgress` tracks the rebuild pass started by `begin_rebuild`.
+pub(super) struct RebuildProgress {
+    total: u64,
+    done: u64,
+    started: Instant,
+    /// Chunks each member supplied to the repaired stripes.
+    sources: Vec<u64>,
+}
+
+impl RebuildProgress {
+    pub(super) fn new(total: u64, disks: usize) -> Self {
+        Self {
+            total,
+            done: 0,
+            started: Instant::now(),
+            sources: vec![0; disks],
+        }
+    }
+
+    /// `advance` records one repaired stripe rebuilt from the chunks of `sources`.
+    pub(super) fn advance(&mut self, sources: &[usize]) {
+        self.done = (self.done + 1).min(self.total);
+        for &disk in sources {
+            if let Some(count) = self.sources.get_mut(disk) {
+                *count += 1;
+            }
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn status(&self) -> RebuildStatus {
+        let percent = if self.total == 0 {
+            100.0
+        } else {
+            self.done as f64 * 100.0 / self.total as f64
+        };
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let stripes_per_second = if elapsed > 0.0 {
+            self.done as f64 / elapsed
+        } else {
+            0.0
+        };
+        RebuildStatus {
+            done_stripes: self.done,
+            total_stripes: self.total,
+            percent,
+            stripes_per_second,
+            source_chunks: self.sources.clone(),
+        }
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `rebuild_sources` lists the members whose chunks rebuild the unreadable
+    /// members of stripe `stripe_index`.
+    pub(super) fn rebuild_sources(&self, stripe_index: u64) -> Vec<usize> {
+        let restore = self.layout.as_restore().is_some();
+        let unreadable: Vec<usize> = (0..D)
+            .filter(|&i| {
+                let disk = &self.array.0[i];
+                disk.is_missing() || (restore && disk.needs_rebuild)
+            })
+            .collect();
+        let mut sources: Vec<usize> = unreadable
+            .iter()
+            .flat_map(|&disk| T::peers(stripe_index, disk))
+            .filter(|disk| !unreadable.contains(disk))
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    /// `status` returns the array status including layout and rebuild progress.
+    pub fn status(&self) -> ArrayStatus {
+        let mut status = self.array.status();
+        status.data_disks = T::DATA;
+        status.rebuild = self.rebuild.as_ref().map(RebuildProgress::status);
+        status
+    }
+}
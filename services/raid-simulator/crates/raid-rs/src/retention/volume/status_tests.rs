@@ -0,0 +1,123 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::declustered::Declustered;
+use crate::layout::stripe::raid3::RAID3;
+use crate::retention::array::MemberState;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+#[test]
+fn status_reports_members_and_layout() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.fail_disk(1).expect("fail disk");
+
+    let status = volume.status();
+
+    assert_eq!(status.disks, TEST_DISKS);
+    assert_eq!(status.data_disks, 2);
+    assert_eq!(status.disk_bytes, DISK_LEN);
+    assert_eq!(status.working(), 2);
+    assert_eq!(status.members[1].state, MemberState::Failed);
+    assert!(status.rebuild.is_none());
+    assert!(
+        status
+            .to_string()
+            .starts_with("array : 3 disks, 2 data, 256 bytes per disk, 4B chunks [3/2] [U_U]\n")
+    );
+}
+
+#[test]
+fn status_tracks_rebuild_progress() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.fail_disk(2).expect("fail disk");
+    volume.replace_disk(2).expect("replace disk");
+
+    let stripes = volume.begin_rebuild(volume.logical_capacity_bytes());
+    for &s in &stripes[..stripes.len() / 4] {
+        volume.repair_stripe(s);
+    }
+
+    let rebuild = volume.status().rebuild.expect("rebuild in progress");
+    assert_eq!(rebuild.total_stripes, stripes.len() as u64);
+    assert_eq!(rebuild.done_stripes, stripes.len() as u64 / 4);
+    assert!((rebuild.percent - 25.0).abs() < f64::EPSILON);
+    let quarter = stripes.len() as u64 / 4;
+    assert_eq!(rebuild.source_chunks, vec![quarter, quarter, 0]);
+    assert!(volume.disk_status_string().contains("recovery = 25.0%"));
+    assert!(
+        volume
+            .disk_status_string()
+            .contains(&format!("fan-out: disk0={quarter} disk1={quarter} disk2=0"))
+    );
+
+    volume.clear_needs_rebuild_all();
+    assert!(volume.status().rebuild.is_none());
+}
+
+#[test]
+fn status_flags_worn_members_as_pre_fail() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.simulate_wear(WearPlan {
+        defects_per_gib: 1_073_741_824.0,
+        spare_sectors: 8,
+        pre_fail_threshold: 4,
+        ..WearPlan::default()
+    });
+
+    volume.write_bytes(0, &[7u8; 16]);
+
+    let status = volume.status();
+    assert!(status.members.iter().all(|m| m.health.pre_fail));
+    assert!(
+        status
+            .members
+            .iter()
+            .all(|m| m.health.reallocated_sectors == 8)
+    );
+    assert_eq!(
+        volume.disk_healths(),
+        status.members.iter().map(|m| m.health).collect::<Vec<_>>()
+    );
+    assert!(status.to_string().contains("disk 0: OK PRE-FAIL ("));
+}
+
+#[test]
+fn declustered_rebuild_reads_from_the_whole_pool() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = synced_volume(&dir, DISK_LEN, Declustered::<6, CHUNK_SIZE, 3>::zero());
+    let capacity = usize::try_from(volume.logical_capacity_bytes()).expect("capacity");
+    let data: Vec<u8> = (0..=250u8).cycle().take(capacity).collect();
+    volume.write_bytes(0, &data);
+
+    volume.fail_disk(0).expect("fail disk");
+    let mut out = vec![0u8; capacity];
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out, data, "degraded reads rebuild from group peers");
+
+    volume.replace_disk(0).expect("replace disk");
+    let stripes = volume.begin_rebuild(volume.logical_capacity_bytes());
+    volume.repair_stripes(&stripes);
+    let rebuild = volume.status().rebuild.expect("rebuild status");
+    volume.clear_needs_rebuild_all();
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out, data);
+
+    let total = stripes.len() as u64;
+    assert_eq!(rebuild.source_chunks[0], 0);
+    for (disk, &n) in rebuild.source_chunks.iter().enumerate().skip(1) {
+        assert!(
+            n > 0 && n < total,
+            "disk {disk} supplied {n} of {total} stripes"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Fixtures shared by the volume tests.
+
+use tempfile::TempDir;
+
+use super::Volume;
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::array::Array;
+
+/// `disk_paths` names the images of `D` members inside `dir`.
+pub(super) fn disk_paths<const D: usize>(dir: &TempDir) -> [String; D] {
+    std::array::from_fn(|i| {
+        dir.path()
+            .join(format!("disk-{i}.img"))
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// `fresh_volume` opens a volume on `disk_len`-byte member images in `dir`, leaving
+/// newly created members marked for a rebuild.
+pub(super) fn fresh_volume<const D: usize, const N: usize, T: Stripe<D, N>>(
+    dir: &TempDir,
+    disk_len: u64,
+    layout: T,
+) -> Volume<D, N, T> {
+    Volume::new(
+        Array::init_array(&disk_paths(dir), disk_len).expect("init array"),
+        layout,
+    )
+}
+
+/// `synced_volume` opens a volume like `fresh_volume` with every member in sync.
+pub(super) fn synced_volume<const D: usize, const N: usize, T: Stripe<D, N>>(
+    dir: &TempDir,
+    disk_len: u64,
+    layout: T,
+) -> Volume<D, N, T> {
+    let mut volume = fresh_volume(dir, disk_len, layout);
+    volume.clear_needs_rebuild_all();
+    volume
+}
@@ -0,0 +1,311 @@
+//! Thin provisioning: logical stripes are backed by physical stripes on first write.
+//!
+//! A thin volume advertises a virtual capacity that may exceed the array. Stripes
+//! that were never written read back as zeros and consume no physical space. The
+//! allocation map (one `u32` per virtual stripe, `0` meaning unallocated) and a
+//! superblock occupy physical stripes at the end of the array; everything before
+//! them forms the allocation pool.
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::volume::{Access, Volume};
+use crate::{Error, Result};
+
+const THIN_MAGIC: [u8; 8] = *b"RAIDTHN1";
+const THIN_VERSION: u8 = 1;
+const SUPERBLOCK_LEN: usize = 64;
+const SUPERBLOCK_SIZE: u64 = SUPERBLOCK_LEN as u64;
+const MAP_ENTRY_LEN: usize = 4;
+const MAP_ENTRY_SIZE: u64 = MAP_ENTRY_LEN as u64;
+
+/// `pool_exhausted` is the error of a write to a thin volume with too few free
+/// physical stripes.
+fn pool_exhausted(needed: u64, free: u64) -> Error {
+    Error::OutOfSpace(format!(
+        "thin pool exhausted: write needs {needed} stripes but only {free} are free"
+    ))
+}
+
+/// `ThinUsage` reports how much of the physical pool a thin volume consumes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThinUsage {
+    pub virtual_bytes: u64,
+    pub stripe_bytes: u64,
+    pub allocated_stripes: u64,
+    pub pool_stripes: u64,
+}
+
+impl ThinUsage {
+    #[must_use]
+    /// `used_bytes` returns the physical bytes backing written stripes.
+    pub const fn used_bytes(&self) -> u64 {
+        self.allocated_stripes * self.stripe_bytes
+    }
+
+    #[must_use]
+    /// `pool_bytes` returns the physical bytes available to the volume in total.
+    pub const fn pool_bytes(&self) -> u64 {
+        self.pool_stripes * self.stripe_bytes
+    }
+
+    #[must_use]
+    /// `free_bytes` returns the physical bytes that can still be allocated.
+    pub const fn free_bytes(&self) -> u64 {
+        self.pool_bytes().saturating_sub(self.used_bytes())
+    }
+}
+
+/// `ThinMap` is the in-memory copy of the allocation map.
+pub struct ThinMap {
+    virtual_bytes: u64,
+    map_start: u64,
+    pool_stripes: u64,
+    map: Vec<u32>,
+    used: Vec<bool>,
+    allocated: u64,
+    next_free: u64,
+}
+
+impl ThinMap {
+    /// `virtual_bytes` returns the advertised capacity of the volume.
+    pub const fn virtual_bytes(&self) -> u64 {
+        self.virtual_bytes
+    }
+
+    /// `physical` returns the physical stripe backing a logical stripe, if any.
+    pub fn physical(&self, stripe_index: u64) -> Option<u64> {
+        let idx = usize::try_from(stripe_index).ok()?;
+        match self.map.get(idx).copied() {
+            Some(0) | None => None,
+            Some(slot) => Some(u64::from(slot) - 1),
+        }
+    }
+
+    /// `metadata_stripes` returns the physical stripes holding the map and superblock.
+    const fn metadata_stripes(&self, stripe_bytes: u64, usable: u64) -> std::ops::Range<u64> {
+        self.map_start / stripe_bytes..usable / stripe_bytes
+    }
+
+    fn missing(&self, stripes: std::ops::Range<u64>) -> u64 {
+        stripes.filter(|&s| self.physical(s).is_none()).count() as u64
+    }
+
+    fn allocate(&mut self, stripe_index: u64) -> Option<u64> {
+        let idx = usize::try_from(stripe_index).ok()?;
+        let start = self.next_free;
+        let phys = (start..self.pool_stripes)
+            .chain(0..start)
+            .find(|&p| !self.used[usize::try_from(p).unwrap_or(usize::MAX)])?;
+        self.used[usize::try_from(phys).ok()?] = true;
+        self.map[idx] = u32::try_from(phys + 1).ok()?;
+        self.allocated += 1;
+        self.next_free = phys + 1;
+        Some(phys)
+    }
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `physical_usable_bytes` returns the physical capacity rounded down to whole stripes.
+    pub(super) fn physical_usable_bytes(&self) -> u64 {
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        self.array.disk_len().saturating_mul(T::DATA as u64) / stripe_bytes * stripe_bytes
+    }
+
+    /// `is_thin` reports whether the volume is thinly provisioned.
+    pub const fn is_thin(&self) -> bool {
+        self.thin.is_some()
+    }
+
+    /// `thin_usage` returns pool usage for thin volumes.
+    pub fn thin_usage(&self) -> Option<ThinUsage> {
+        self.thin.as_ref().map(|thin| ThinUsage {
+            virtual_bytes: thin.virtual_bytes,
+            stripe_bytes: self.geom.bytes_per_stripe as u64,
+            allocated_stripes: thin.allocated,
+            pool_stripes: thin.pool_stripes,
+        })
+    }
+
+    /// `init_thin` converts the volume into a thin volume with the given virtual size.
+    ///
+    /// Stripes holding existing data are mapped onto themselves so their contents survive.
+    ///
+    /// # Arguments
+    /// * `virtual_bytes` - Logical capacity advertised by the thin volume.
+    /// * `data_end` - End of the live data that must stay readable.
+    ///
+    /// # Errors
+    /// Returns an error if the volume is already thin, compressed or has snapshots, or
+    /// the allocation map would overlap live data.
+    pub fn init_thin(&mut self, virtual_bytes: u64, data_end: u64) -> Result<()> {
+        if self.thin.is_some() {
+            return Err(Error::Invalid("volume is already thin".to_string()));
+        }
+        if self.snapshots.is_some() || self.compression.is_some() {
+            return Err(Error::Invalid(
+                "thin provisioning cannot be combined with snapshots or compression".to_string(),
+            ));
+        }
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let virtual_stripes = virtual_bytes / stripe_bytes;
+        if virtual_stripes == 0 || virtual_stripes >= u64::from(u32::MAX) {
+            return Err(Error::Geometry(format!(
+                "unsupported thin volume size of {virtual_bytes} bytes"
+            )));
+        }
+
+        let usable = self.physical_usable_bytes();
+        let meta_bytes = virtual_stripes * MAP_ENTRY_SIZE + SUPERBLOCK_SIZE;
+        let Some(map_start) = usable
+            .checked_sub(meta_bytes)
+            .map(|start| start / stripe_bytes * stripe_bytes)
+        else {
+            return Err(Error::OutOfSpace(format!(
+                "array is too small for a {virtual_bytes} byte thin map"
+            )));
+        };
+        let pool_stripes = map_start / stripe_bytes;
+        let data_stripes = data_end.div_ceil(stripe_bytes);
+        if data_stripes > pool_stripes || data_stripes > virtual_stripes {
+            return Err(Error::OutOfSpace(format!(
+                "thin map would overlap {data_end} bytes of live data"
+            )));
+        }
+
+        let len = usize::try_from(virtual_stripes)?;
+        let mut thin = ThinMap {
+            virtual_bytes: virtual_stripes * stripe_bytes,
+            map_start,
+            pool_stripes,
+            map: vec![0; len],
+            used: vec![false; usize::try_from(pool_stripes)?],
+            allocated: 0,
+            next_free: 0,
+        };
+        for s in 0..data_stripes {
+            let idx = usize::try_from(s)?;
+            thin.map[idx] = u32::try_from(s + 1)?;
+            thin.used[idx] = true;
+            thin.allocated += 1;
+        }
+        thin.next_free = data_stripes;
+
+        let mut raw = vec![0u8; len * MAP_ENTRY_LEN];
+        for (chunk, slot) in raw.chunks_exact_mut(MAP_ENTRY_LEN).zip(&thin.map) {
+            chunk.copy_from_slice(&slot.to_le_bytes());
+        }
+        let mut sb = [0u8; SUPERBLOCK_LEN];
+        sb[0..8].copy_from_slice(&THIN_MAGIC);
+        sb[8] = THIN_VERSION;
+        sb[16..24].copy_from_slice(&thin.virtual_bytes.to_le_bytes());
+        sb[24..32].copy_from_slice(&map_start.to_le_bytes());
+
+        self.write_logical(map_start, &raw, Access::Physical)?;
+        self.write_logical(usable - SUPERBLOCK_SIZE, &sb, Access::Physical)?;
+        self.thin = Some(thin);
+        self.save_features()
+    }
+
+    /// `thin_reserve` allocates every unbacked stripe in a logical range up front.
+    ///
+    /// Either all stripes are allocated or none are, so a failed write leaves no
+    /// partially allocated range behind.
+    pub(super) fn thin_reserve(&mut self, byte_offset: u64, len: usize) -> Result<()> {
+        let Some(thin) = self.thin.as_mut() else {
+            return Ok(());
+        };
+        if len == 0 {
+            return Ok(());
+        }
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let first = byte_offset / stripe_bytes;
+        let last = (byte_offset + len as u64).div_ceil(stripe_bytes);
+        let needed = thin.missing(first..last);
+        if needed == 0 {
+            return Ok(());
+        }
+        let free = thin.pool_stripes - thin.allocated;
+        if needed > free {
+            return Err(pool_exhausted(needed, free));
+        }
+
+        let mut entries = Vec::new();
+        for s in first..last {
+            if thin.physical(s).is_none() {
+                let phys = thin
+                    .allocate(s)
+                    .ok_or_else(|| pool_exhausted(needed, free))?;
+                entries.push((thin.map_start + s * MAP_ENTRY_SIZE, phys + 1));
+            }
+        }
+        for (offset, slot) in entries {
+            let slot = u32::try_from(slot).unwrap_or(0);
+            self.write_logical(offset, &slot.to_le_bytes(), Access::Physical)?;
+        }
+        Ok(())
+    }
+
+    /// `thin_metadata_stripes` returns the physical stripes holding thin metadata.
+    pub(super) fn thin_metadata_stripes(&self) -> std::ops::Range<u64> {
+        self.thin.as_ref().map_or(0..0, |thin| {
+            thin.metadata_stripes(
+                self.geom.bytes_per_stripe as u64,
+                self.physical_usable_bytes(),
+            )
+        })
+    }
+
+    pub(super) fn load_thin_map(&mut self) -> Option<ThinMap> {
+        let usable = self.physical_usable_bytes();
+        if usable < SUPERBLOCK_SIZE {
+            return None;
+        }
+        let mut sb = [0u8; SUPERBLOCK_LEN];
+        self.read_logical(usable - SUPERBLOCK_SIZE, &mut sb, Access::Physical)
+            .ok()?;
+        if sb[0..8] != THIN_MAGIC || sb[8] != THIN_VERSION {
+            return None;
+        }
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let virtual_bytes = u64::from_le_bytes(sb[16..24].try_into().ok()?);
+        let map_start = u64::from_le_bytes(sb[24..32].try_into().ok()?);
+        let virtual_stripes = virtual_bytes / stripe_bytes;
+        if !map_start.is_multiple_of(stripe_bytes)
+            || map_start + virtual_stripes * MAP_ENTRY_SIZE > usable - SUPERBLOCK_SIZE
+        {
+            return None;
+        }
+
+        let pool_stripes = map_start / stripe_bytes;
+        let mut raw = vec![0u8; usize::try_from(virtual_stripes * MAP_ENTRY_SIZE).ok()?];
+        self.read_logical(map_start, &mut raw, Access::Physical)
+            .ok()?;
+        let mut used = vec![false; usize::try_from(pool_stripes).ok()?];
+        let mut allocated = 0;
+        let map = raw
+            .chunks_exact(MAP_ENTRY_LEN)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap_or_default()))
+            .map(|slot| {
+                let phys = usize::try_from(slot).unwrap_or(usize::MAX).wrapping_sub(1);
+                match used.get_mut(phys) {
+                    Some(taken) if slot != 0 && !*taken => {
+                        *taken = true;
+                        allocated += 1;
+                        slot
+                    }
+                    _ => 0,
+                }
+            })
+            .collect();
+        Some(ThinMap {
+            virtual_bytes,
+            map_start,
+            pool_stripes,
+            map,
+            used,
+            allocated,
+            next_free: 0,
+        })
+    }
+}
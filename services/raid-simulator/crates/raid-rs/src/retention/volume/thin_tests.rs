@@ -0,0 +1,133 @@
+use super::test_utils::fresh_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 64;
+const DISK_LEN: u64 = 1024;
+const STRIPE_BYTES: u64 = 128;
+const VIRTUAL_BYTES: u64 = 8192;
+
+type TestVolume = Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>;
+
+fn make_volume(dir: &TempDir) -> TestVolume {
+    fresh_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn thin_volume(dir: &TempDir) -> TestVolume {
+    let mut volume = make_volume(dir);
+    volume.init_thin(VIRTUAL_BYTES, 0).expect("init thin");
+    volume
+}
+
+fn read(volume: &mut TestVolume, offset: u64, len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len];
+    volume.read_bytes(offset, &mut out);
+    out
+}
+
+#[test]
+fn init_thin_advertises_virtual_capacity_and_persists() {
+    let dir = TempDir::new().unwrap();
+    let volume = thin_volume(&dir);
+    let physical = DISK_LEN * 2;
+
+    assert!(volume.is_thin());
+    assert_eq!(volume.logical_capacity_bytes(), VIRTUAL_BYTES);
+    let usage = volume.thin_usage().expect("usage");
+    assert_eq!(usage.allocated_stripes, 0);
+    assert!(usage.pool_bytes() < physical);
+    assert!(!volume.reserved_stripes().is_empty());
+    drop(volume);
+
+    let volume = make_volume(&dir);
+    assert!(volume.is_thin());
+    assert_eq!(volume.logical_capacity_bytes(), VIRTUAL_BYTES);
+}
+
+#[test]
+fn writes_allocate_on_first_touch_and_holes_read_as_zeros() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = thin_volume(&dir);
+
+    volume
+        .try_write_bytes(6000, b"far away")
+        .expect("write beyond physical size");
+    assert_eq!(read(&mut volume, 6000, 8), b"far away");
+    assert_eq!(read(&mut volume, 0, 16), vec![0u8; 16]);
+    assert_eq!(volume.thin_usage().expect("usage").allocated_stripes, 1);
+
+    volume.write_bytes(6004, b"AWAY");
+    assert_eq!(volume.thin_usage().expect("usage").allocated_stripes, 1);
+    drop(volume);
+
+    let mut volume = make_volume(&dir);
+    assert_eq!(read(&mut volume, 6000, 8), b"far AWAY");
+    assert_eq!(volume.thin_usage().expect("usage").allocated_stripes, 1);
+}
+
+#[test]
+fn exhausted_pool_rejects_write_without_allocating() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = thin_volume(&dir);
+    let usage = volume.thin_usage().expect("usage");
+    let pool = usage.pool_stripes;
+
+    let too_big = vec![0xAB; usize::try_from((pool + 1) * STRIPE_BYTES).unwrap()];
+    let err = volume
+        .try_write_bytes(0, &too_big)
+        .expect_err("expected exhaustion");
+    assert!(matches!(err, Error::OutOfSpace(_)));
+    assert!(err.to_string().contains(&format!("{} stripes", pool + 1)));
+    assert_eq!(volume.thin_usage().expect("usage").allocated_stripes, 0);
+    assert_eq!(read(&mut volume, 0, 4), vec![0u8; 4]);
+
+    let fits = vec![0xCD; usize::try_from(pool * STRIPE_BYTES).unwrap()];
+    volume.try_write_bytes(0, &fits).expect("fill pool");
+    assert_eq!(volume.thin_usage().expect("usage").free_bytes(), 0);
+    assert!(volume.try_write_bytes(VIRTUAL_BYTES - 1, b"x").is_err());
+    volume
+        .try_write_bytes(STRIPE_BYTES, b"rewrite")
+        .expect("overwrite allocated stripe");
+}
+
+#[test]
+fn init_thin_keeps_existing_data() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.write_bytes(0, b"existing");
+    volume.write_bytes(200, b"tail");
+
+    volume.init_thin(VIRTUAL_BYTES, 204).expect("init thin");
+    assert_eq!(read(&mut volume, 0, 8), b"existing");
+    assert_eq!(read(&mut volume, 200, 4), b"tail");
+    assert_eq!(volume.thin_usage().expect("usage").allocated_stripes, 2);
+    assert!(volume.init_snapshots(512, 0).is_err());
+}
+
+#[test]
+fn init_thin_rejects_snapshot_volumes() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.init_snapshots(1536, 0).expect("init snapshots");
+    assert!(volume.init_thin(VIRTUAL_BYTES, 0).is_err());
+}
+
+#[test]
+fn rebuild_restores_allocated_stripes_and_map() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = thin_volume(&dir);
+    volume.write_bytes(7000, b"rebuild me");
+
+    volume.fail_disk(1).expect("fail disk");
+    volume.replace_disk(1).expect("replace disk");
+    volume.rebuild_all().expect("rebuild");
+    volume.fail_disk(0).expect("fail disk");
+    assert_eq!(read(&mut volume, 7000, 10), b"rebuild me");
+    drop(volume);
+
+    let mut volume = make_volume(&dir);
+    assert!(volume.is_thin());
+    assert_eq!(read(&mut volume, 7000, 10), b"rebuild me");
+}
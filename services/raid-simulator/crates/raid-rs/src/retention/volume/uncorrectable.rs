@@ -0,0 +1,94 @@
+//! Accounting of logical stripes that could not be reconstructed on read.
+//!
+//! Every lost stripe is remembered for the lifetime of the volume. Once the number
+//! of lost stripes reaches the configured limit, the volume refuses further writes.
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::retention::IoError;
+use crate::retention::volume::{Volume, VolumeEvent};
+
+/// `UncorrectableLog` tracks lost stripes and the write fence they may trigger.
+#[derive(Default)]
+pub(super) struct UncorrectableLog {
+    stripes: BTreeSet<u64>,
+    limit: Option<u64>,
+    read_only: bool,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `uncorrectable_stripes` returns how many logical stripes could not be reconstructed.
+    pub fn uncorrectable_stripes(&self) -> u64 {
+        self.uncorrectable.stripes.len() as u64
+    }
+
+    /// `uncorrectable_ranges` returns the logical byte ranges of lost stripes, merged
+    /// where stripes are adjacent.
+    pub fn uncorrectable_ranges(&self) -> Vec<Range<u64>> {
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let mut ranges: Vec<Range<u64>> = Vec::new();
+        for &stripe in &self.uncorrectable.stripes {
+            let start = stripe * stripe_bytes;
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end += stripe_bytes,
+                _ => ranges.push(start..start + stripe_bytes),
+            }
+        }
+        ranges
+    }
+
+    /// `set_uncorrectable_limit` makes the volume read-only once `limit` stripes are lost.
+    ///
+    /// # Arguments
+    /// * `limit` - Number of lost stripes that trips the fence; `None` disables it.
+    pub fn set_uncorrectable_limit(&mut self, limit: Option<u64>) {
+        self.uncorrectable.limit = limit;
+        self.update_read_only();
+    }
+
+    #[must_use]
+    /// `is_read_only` reports whether the uncorrectable-error limit was reached.
+    pub const fn is_read_only(&self) -> bool {
+        self.uncorrectable.read_only
+    }
+
+    /// `record_uncorrectable` remembers the stripes of a read that could not be served.
+    ///
+    /// Only reads the layout should have reconstructed count; a missing member of a
+    /// layout without redundancy is reported by its disk status instead.
+    pub(super) fn record_uncorrectable(&mut self, byte_offset: u64, len: usize) {
+        let end = byte_offset
+            .saturating_add(len as u64)
+            .min(self.logical_capacity_bytes());
+        if end <= byte_offset
+            || !matches!(
+                self.unrecoverable(byte_offset),
+                Some(IoError::Uncorrectable { .. })
+            )
+        {
+            return;
+        }
+        let stripe_bytes = self.geom.bytes_per_stripe as u64;
+        let first = byte_offset / stripe_bytes;
+        let last = (end - 1) / stripe_bytes;
+        let before = self.uncorrectable.stripes.len();
+        self.uncorrectable.stripes.extend(first..=last);
+        if self.uncorrectable.stripes.len() == before {
+            return;
+        }
+        self.update_read_only();
+        self.emit(&VolumeEvent::Uncorrectable {
+            stripes: self.uncorrectable_stripes(),
+            read_only: self.uncorrectable.read_only,
+        });
+    }
+
+    fn update_read_only(&mut self) {
+        let lost = self.uncorrectable_stripes();
+        if self.uncorrectable.limit.is_some_and(|limit| lost >= limit) {
+            self.uncorrectable.read_only = true;
+        }
+    }
+}
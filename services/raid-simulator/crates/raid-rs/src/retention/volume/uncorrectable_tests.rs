@@ -0,0 +1,68 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>> {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+#[test]
+fn lost_stripes_are_recorded_as_merged_ranges() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.fail_disk(0).expect("fail disk");
+
+    let mut out = [0u8; 16];
+    volume.read_bytes(0, &mut out);
+    assert_eq!(volume.uncorrectable_stripes(), 0);
+
+    volume.fail_disk(1).expect("fail second disk");
+    let events = volume.subscribe();
+    volume.read_bytes(4, &mut out);
+    volume.read_bytes(40, &mut [0u8; 2]);
+    volume.read_bytes(4, &mut out);
+
+    assert_eq!(volume.uncorrectable_stripes(), 4);
+    assert_eq!(volume.uncorrectable_ranges(), vec![0..24, 40..48]);
+    let got: Vec<VolumeEvent> = events.try_iter().collect();
+    assert_eq!(
+        got,
+        vec![
+            VolumeEvent::Uncorrectable {
+                stripes: 3,
+                read_only: false,
+            },
+            VolumeEvent::Uncorrectable {
+                stripes: 4,
+                read_only: false,
+            },
+        ]
+    );
+}
+
+#[test]
+fn reaching_the_limit_makes_the_volume_read_only() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    volume.set_degraded_policy(DegradedPolicy::BestEffort);
+    volume.set_uncorrectable_limit(Some(2));
+    volume.fail_disk(0).expect("fail disk");
+    volume.fail_disk(1).expect("fail second disk");
+
+    volume
+        .try_write_bytes(0, &[1; 4])
+        .expect("write before limit");
+    let mut out = [0u8; 16];
+    volume
+        .try_read_bytes(0, &mut out)
+        .expect("best-effort read");
+
+    assert!(volume.is_read_only());
+    let err = volume.try_write_bytes(64, &[1; 4]).unwrap_err();
+    assert!(matches!(err, Error::Degraded(IoError::ReadOnly)));
+}
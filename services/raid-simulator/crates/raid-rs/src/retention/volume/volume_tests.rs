@@ -1,41 +1,29 @@
+use super::test_utils::{disk_paths, fresh_volume};
 use super::*;
 use crate::layout::stripe::raid0::RAID0;
+use crate::layout::stripe::raid1::RAID1;
+use crate::retention::disk::DiskIo;
 use tempfile::TempDir;
 
 const TEST_DISKS: usize = 3;
 const CHUNK_SIZE: usize = 4;
 const DISK_LEN: u64 = 1024;
 
-fn disk_paths<const D: usize>(dir: &TempDir) -> [String; D] {
-    std::array::from_fn(|i| {
-        dir.path()
-            .join(format!("disk-{i}.img"))
-            .to_string_lossy()
-            .into_owned()
-    })
-}
-
-fn make_volume(
-    paths: &[String; TEST_DISKS],
-) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID0<TEST_DISKS, CHUNK_SIZE>> {
-    Volume::new(
-        Array::init_array(paths, DISK_LEN),
-        RAID0::<TEST_DISKS, CHUNK_SIZE>::zero(),
-    )
+fn make_volume(dir: &TempDir) -> Volume<TEST_DISKS, CHUNK_SIZE, RAID0<TEST_DISKS, CHUNK_SIZE>> {
+    fresh_volume(dir, DISK_LEN, RAID0::zero())
 }
 
 #[test]
 fn write_and_read_across_multiple_stripes() {
     let dir = TempDir::new().unwrap();
-    let paths = disk_paths::<TEST_DISKS>(&dir);
 
-    let mut volume = make_volume(&paths);
+    let mut volume = make_volume(&dir);
     let payload: Vec<u8> = (0..40)
         .map(|i| u8::try_from(i).expect("payload index fits in u8"))
         .collect();
     volume.write_bytes(0, &payload);
 
-    let mut volume = make_volume(&paths);
+    let mut volume = make_volume(&dir);
     let mut out = vec![0u8; 40];
     volume.read_bytes(0, &mut out);
 
@@ -48,13 +36,12 @@ fn write_and_read_across_multiple_stripes() {
 #[test]
 fn partial_write_preserves_unrelated_bytes() {
     let dir = TempDir::new().unwrap();
-    let paths = disk_paths::<TEST_DISKS>(&dir);
 
     let initial: Vec<u8> = (0..30)
         .map(|i| u8::try_from(i + 1).expect("initial index fits in u8"))
         .collect();
 
-    let mut volume = make_volume(&paths);
+    let mut volume = make_volume(&dir);
     volume.write_bytes(0, &initial);
 
     let patch_offset = 5u64;
@@ -62,10 +49,10 @@ fn partial_write_preserves_unrelated_bytes() {
         .map(|i| u8::try_from(i + 200).expect("patch index fits in u8"))
         .collect();
 
-    let mut volume = make_volume(&paths);
+    let mut volume = make_volume(&dir);
     volume.write_bytes(patch_offset, &patch);
 
-    let mut volume = make_volume(&paths);
+    let mut volume = make_volume(&dir);
     let mut out = vec![0u8; initial.len()];
     volume.read_bytes(0, &mut out);
 
@@ -74,3 +61,65 @@ fn partial_write_preserves_unrelated_bytes() {
     expected[patch_offset..patch_offset + patch.len()].copy_from_slice(&patch);
     assert_eq!(out, expected);
 }
+
+#[test]
+fn try_io_reports_typed_errors() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = make_volume(&dir);
+    let limit = volume.logical_capacity_bytes();
+
+    let err = volume.try_write_bytes(limit - 2, &[1; 4]).unwrap_err();
+    assert!(matches!(err, Error::Geometry(_)));
+    assert_eq!(
+        err.to_string(),
+        IoError::OutOfRange {
+            offset: limit - 2,
+            len: 4,
+            limit,
+        }
+        .to_string()
+    );
+
+    volume.try_write_bytes(0, &[7; 8]).expect("healthy write");
+    volume.fail_disk(1).expect("fail disk");
+
+    let mut out = [0xAA; 8];
+    let err = volume.try_read_bytes(0, &mut out).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Degraded(IoError::DiskMissing { path }) if path.ends_with("disk-1.img")
+    ));
+    assert_eq!(out, [0xAA; 8]);
+}
+
+#[test]
+fn failed_member_is_rebuilt_onto_an_image_elsewhere() {
+    let dir = TempDir::new().unwrap();
+    let spare_dir = TempDir::new().unwrap();
+    let paths = disk_paths::<2>(&dir);
+    let mut volume = Volume::new(
+        Array::<2, CHUNK_SIZE>::init_array_sized(&paths, &[DISK_LEN, 2 * DISK_LEN], DiskIo::Mmap)
+            .expect("init array"),
+        RAID1::<2, CHUNK_SIZE>::zero(),
+    );
+    volume.clear_needs_rebuild_all();
+    assert_eq!(volume.logical_capacity_bytes(), DISK_LEN);
+    let payload: Vec<u8> = (0..64).map(|i: usize| i.to_le_bytes()[0]).collect();
+    volume.write_bytes(0, &payload);
+
+    let spare = spare_dir.path().join("spare.img");
+    volume.fail_disk(0).expect("fail disk");
+    volume
+        .replace_disk_with(0, &spare, DISK_LEN)
+        .expect("replace disk");
+    volume
+        .rebuild_disk_upto(0, payload.len() as u64)
+        .expect("rebuild disk");
+    volume.fail_disk(1).expect("fail mirror");
+
+    let mut out = vec![0u8; payload.len()];
+    volume.set_degraded_policy(DegradedPolicy::BestEffort);
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out, payload);
+    assert_eq!(volume.status().members[0].path, spare.display().to_string());
+}
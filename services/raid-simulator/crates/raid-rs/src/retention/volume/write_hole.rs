@@ -0,0 +1,325 @@
+//! Write-hole simulation and the policies that close it.
+//!
+//! A stripe write reaches its members one at a time. If power fails after the data
+//! chunks landed but before the parity or mirror copies did, the stripe is left
+//! inconsistent: reads still return the new data, but the redundancy describes the
+//! old one, and rebuilding any member of the stripe returns garbage. A crash plan
+//! with `write_hole` set makes its crash land exactly there.
+//!
+//! `WriteHolePolicy::Journal` writes every stripe to a journal next to the disk
+//! images before its members, keeps it until the members are durable, and replays
+//! it when the volume is opened. `WriteHolePolicy::FullStripe` refuses writes that
+//! do not cover whole stripes, so a torn stripe only ever holds data that was never
+//! acknowledged and no read-modify-write can leave acknowledged data unprotected.
+//! `WriteHolePolicy::Ppl` logs only the partial parity of each write; see `ppl`.
+
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+use crate::retention::volume::Volume;
+use crate::retention::volume::mapper::stripe_byte_offset;
+use crate::retention::volume::ppl::{PPL_FILE_NAME, PPL_MAGIC};
+use crate::{Error, Result};
+
+const JOURNAL_FILE_NAME: &str = "stripe-journal.log";
+const JOURNAL_MAGIC: [u8; 8] = *b"RAIDSJL1";
+/// `LOG_HEADER_LEN` is the length of the magic and geometry heading each log.
+pub(super) const LOG_HEADER_LEN: usize = 24;
+
+/// `WriteHolePolicy` selects how stripe writes are protected against a torn update.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteHolePolicy {
+    /// Write members in place; a crash mid-stripe leaves stale redundancy.
+    #[default]
+    Unprotected,
+    /// Journal each stripe before writing its members and replay it on open.
+    Journal,
+    /// Reject writes that do not cover whole stripes.
+    FullStripe,
+    /// Log the parity of the chunks each write leaves alone, like md's PPL, and
+    /// rebuild the parity of in-flight stripes from it on open.
+    Ppl,
+}
+
+impl WriteHolePolicy {
+    #[must_use]
+    /// `name` returns the policy name used in configuration and metrics labels.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Unprotected => "unprotected",
+            Self::Journal => "journal",
+            Self::FullStripe => "full_stripe",
+            Self::Ppl => "ppl",
+        }
+    }
+
+    /// `log` returns the file name and magic of the log this policy appends to.
+    const fn log(self) -> Option<(&'static str, [u8; 8])> {
+        match self {
+            Self::Journal => Some((JOURNAL_FILE_NAME, JOURNAL_MAGIC)),
+            Self::Ppl => Some((PPL_FILE_NAME, PPL_MAGIC)),
+            Self::Unprotected | Self::FullStripe => None,
+        }
+    }
+}
+
+/// `WriteHoleStats` counts torn stripes and the work spent preventing them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteHoleStats {
+    /// Stripes whose data reached the members without their redundancy.
+    pub torn_stripes: u64,
+    /// Stripes written to the journal before their members.
+    pub journaled_stripes: u64,
+    /// Bytes appended to the journal, including stripe headers.
+    pub journal_bytes: u64,
+    /// Stripes rewritten from the journal or partial parity log when the volume
+    /// was opened.
+    pub replayed_stripes: u64,
+    /// Partial-stripe writes refused by the full-stripe policy.
+    pub rejected_writes: u64,
+    /// Stripe writes whose partial parity was logged.
+    pub ppl_stripes: u64,
+    /// Bytes appended to the partial parity log, including entry headers.
+    pub ppl_bytes: u64,
+    /// Write barriers issued because a stripe was written again while its logged
+    /// partial parity was still in flight.
+    pub ppl_flushes: u64,
+}
+
+/// `WriteHole` is the write-hole state of a volume.
+#[derive(Debug, Default)]
+pub(super) struct WriteHole {
+    policy: WriteHolePolicy,
+    /// The planned crash lands between the data and redundancy of a stripe.
+    pub(super) tear: bool,
+    /// Power is off; nothing issued from now on reaches the disks or the journal.
+    pub(super) crashed: bool,
+    /// Open log of the current policy.
+    log: Option<File>,
+    /// Stripes with a logged write that may not be durable yet.
+    pub(super) logged: HashSet<u64>,
+    pub(super) stats: WriteHoleStats,
+}
+
+impl<const D: usize, const N: usize, T: Stripe<D, N>> Volume<D, N, T> {
+    /// `set_write_hole_policy` changes how stripe writes are protected.
+    ///
+    /// The policy is not persisted; a journal left behind by an earlier policy is
+    /// still replayed when the volume is opened.
+    ///
+    /// # Arguments
+    /// * `policy` - Policy to apply from now on.
+    pub fn set_write_hole_policy(&mut self, policy: WriteHolePolicy) {
+        self.journal_settle();
+        if policy != self.write_hole.policy {
+            self.write_hole.log = None;
+        }
+        self.write_hole.policy = policy;
+    }
+
+    #[must_use]
+    /// `write_hole_policy` returns how stripe writes are protected.
+    pub const fn write_hole_policy(&self) -> WriteHolePolicy {
+        self.write_hole.policy
+    }
+
+    #[must_use]
+    /// `write_hole_stats` returns the write-hole counters since the volume was opened.
+    pub const fn write_hole_stats(&self) -> WriteHoleStats {
+        self.write_hole.stats
+    }
+
+    /// `log_path` returns where the log named `name` of this array is stored.
+    pub(super) fn log_path(&self, name: &str) -> Option<PathBuf> {
+        self.array
+            .0
+            .first()
+            .map(|disk| disk.path().with_file_name(name))
+    }
+
+    /// `append_log` appends an entry to the log of the current policy and waits
+    /// until it is durable.
+    ///
+    /// Returns whether the entry was logged; nothing is logged after a crash.
+    pub(super) fn append_log(&mut self, entry: &[u8]) -> Result<bool> {
+        let Some((name, magic)) = self.write_hole.policy.log() else {
+            return Ok(false);
+        };
+        let Some(path) = self.log_path(name).filter(|_| !self.write_hole.crashed) else {
+            return Ok(false);
+        };
+        let file = match self.write_hole.log.take() {
+            Some(file) => file,
+            None => open_log::<D, N>(&path, magic).map_err(Error::io(&path))?,
+        };
+        let file = self.write_hole.log.insert(file);
+        file.write_all(entry)
+            .and_then(|()| file.sync_data())
+            .map_err(Error::io(&path))?;
+        Ok(true)
+    }
+
+    /// `read_log` returns the entries of the log named `name`, if it was written
+    /// for this geometry.
+    pub(super) fn read_log(&self, name: &str, magic: [u8; 8]) -> Option<(PathBuf, Vec<u8>)> {
+        let path = self.log_path(name)?;
+        let raw = std::fs::read(&path).ok()?;
+        let body = raw
+            .strip_prefix(log_header::<D, N>(magic).as_slice())
+            .unwrap_or_default()
+            .to_vec();
+        Some((path, body))
+    }
+
+    /// `finish_replay` makes replayed stripes durable and removes the log at `path`.
+    pub(super) fn finish_replay(&mut self, path: &PathBuf) {
+        if self.write_hole.stats.replayed_stripes > 0 && self.sync().is_err() {
+            return;
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    /// `check_full_stripe` applies the full-stripe policy to a write request.
+    pub(super) fn check_full_stripe(&mut self, byte_offset: u64, len: usize) -> Result<()> {
+        let stripe = self.geom.bytes_per_stripe as u64;
+        let len = len as u64;
+        if self.write_hole.policy != WriteHolePolicy::FullStripe
+            || byte_offset.is_multiple_of(stripe) && len.is_multiple_of(stripe)
+        {
+            return Ok(());
+        }
+        self.write_hole.stats.rejected_writes += 1;
+        Err(Error::Invalid(format!(
+            "full-stripe policy rejects the {len}-byte write at {byte_offset}: not aligned to {stripe}-byte stripes"
+        )))
+    }
+
+    /// `allows_parity_delta` reports whether a stripe may be updated in place
+    /// through its cached parity instead of being stored whole.
+    pub(super) fn allows_parity_delta(&self) -> bool {
+        self.write_hole.policy == WriteHolePolicy::Unprotected && !self.tears_next_write()
+    }
+
+    /// `tears_next_write` reports whether the next stored stripe triggers the
+    /// planned crash between its data and redundancy.
+    pub(super) fn tears_next_write(&self) -> bool {
+        self.write_hole.tear && self.crash_after.is_some_and(|left| left <= 1)
+    }
+
+    /// `write_torn` stores only the data chunks of a stripe, makes them durable and
+    /// crashes before the parity or mirror copies are written.
+    pub(super) fn write_torn(&mut self, stripe_index: u64, raw: &[Bits<N>; D]) {
+        let off = stripe_byte_offset::<N>(stripe_index);
+        for (disk, chunk) in raw.iter().enumerate() {
+            if matches!(T::role_at(stripe_index, disk), ChunkRole::Data(_)) {
+                self.array.write_chunk(disk, off, chunk);
+            }
+        }
+        for disk in &mut self.array.0 {
+            disk.flush();
+        }
+        self.write_hole.stats.torn_stripes += 1;
+        self.read_ahead_invalidate(stripe_index);
+        self.parity_cache_invalidate(stripe_index);
+        self.crash();
+    }
+
+    /// `journal_stripe` journals the stripe held by the layout.
+    pub(super) fn journal_stripe(&mut self, stripe_index: u64) -> Result<()> {
+        if self.write_hole.policy != WriteHolePolicy::Journal {
+            return Ok(());
+        }
+        let mut raw = [Bits::<N>::zero(); D];
+        self.layout.read_raw(&mut raw);
+        self.journal_raw(stripe_index, &raw)
+    }
+
+    /// `journal_raw` appends the raw chunks of a stripe to the journal and waits
+    /// until they are durable.
+    pub(super) fn journal_raw(&mut self, stripe_index: u64, raw: &[Bits<N>; D]) -> Result<()> {
+        if self.write_hole.policy != WriteHolePolicy::Journal {
+            return Ok(());
+        }
+        let mut entry = Vec::with_capacity(8 + D * N);
+        entry.extend_from_slice(&stripe_index.to_le_bytes());
+        for chunk in raw {
+            entry.extend_from_slice(chunk.as_bytes());
+        }
+        if self.append_log(&entry)? {
+            self.write_hole.stats.journaled_stripes += 1;
+            self.write_hole.stats.journal_bytes += entry.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// `journal_settle` empties the log of the current policy once every logged
+    /// stripe is durable on the members.
+    pub(super) fn journal_settle(&mut self) {
+        if self.write_hole.crashed || self.unsynced_writes() > 0 {
+            return;
+        }
+        if let Some(file) = self.write_hole.log.as_ref() {
+            let _ = file.set_len(LOG_HEADER_LEN as u64);
+        }
+        self.write_hole.logged.clear();
+    }
+
+    /// `replay_journal` rewrites every journaled stripe to the members and removes
+    /// the journal.
+    ///
+    /// A journal written for a different geometry is ignored, as is a trailing
+    /// entry cut short by the crash; its members were never written.
+    pub(super) fn replay_journal(&mut self) {
+        let Some((path, body)) = self.read_log(JOURNAL_FILE_NAME, JOURNAL_MAGIC) else {
+            return;
+        };
+        let entry_len = 8 + D * N;
+        let stripes = self.physical_stripes();
+        for entry in body.chunks_exact(entry_len) {
+            let (index, chunks) = entry.split_at(8);
+            let stripe_index = u64::from_le_bytes(index.try_into().unwrap_or([0xFF; 8]));
+            if stripe_index >= stripes {
+                continue;
+            }
+            let mut stripe = [Bits::<N>::zero(); D];
+            for (chunk, bytes) in stripe.iter_mut().zip(chunks.chunks_exact(N)) {
+                chunk.as_bytes_mut().copy_from_slice(bytes);
+            }
+            self.layout.place(stripe_index);
+            self.layout.write_raw(&stripe);
+            self.array
+                .write(stripe_byte_offset::<N>(stripe_index), &self.layout);
+            self.write_hole.stats.replayed_stripes += 1;
+        }
+        self.finish_replay(&path);
+    }
+}
+
+/// `log_header` returns the header identifying a log for this geometry.
+fn log_header<const D: usize, const N: usize>(magic: [u8; 8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(LOG_HEADER_LEN);
+    header.extend_from_slice(&magic);
+    header.extend_from_slice(&(D as u64).to_le_bytes());
+    header.extend_from_slice(&(N as u64).to_le_bytes());
+    header
+}
+
+/// `open_log` opens a log for appending, writing its header when new.
+fn open_log<const D: usize, const N: usize>(
+    path: &PathBuf,
+    magic: [u8; 8],
+) -> std::io::Result<File> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() < LOG_HEADER_LEN as u64 {
+        file.set_len(0)?;
+        file.write_all(&log_header::<D, N>(magic))?;
+    }
+    Ok(file)
+}
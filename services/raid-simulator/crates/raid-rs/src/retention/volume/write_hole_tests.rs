@@ -0,0 +1,143 @@
+use super::test_utils::synced_volume;
+use super::*;
+use crate::layout::stripe::raid3::RAID3;
+use tempfile::TempDir;
+
+const TEST_DISKS: usize = 3;
+const CHUNK_SIZE: usize = 4;
+const DISK_LEN: u64 = 256;
+const STRIPE: usize = 2 * CHUNK_SIZE;
+
+type Raid3Volume = Volume<TEST_DISKS, CHUNK_SIZE, RAID3<TEST_DISKS, CHUNK_SIZE>>;
+
+fn open(dir: &TempDir) -> Raid3Volume {
+    synced_volume(dir, DISK_LEN, RAID3::zero())
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (1..=u8::MAX).cycle().take(len).collect()
+}
+
+const fn tear_plan(crash_after_writes: u64) -> CrashPlan {
+    CrashPlan {
+        seed: 3,
+        crash_after_writes: Some(crash_after_writes),
+        write_hole: true,
+    }
+}
+
+/// `tear_first_stripe` writes a pattern, then tears a small write to stripe 0.
+fn tear_first_stripe(dir: &TempDir, policy: WriteHolePolicy) -> WriteHoleStats {
+    let mut volume = open(dir);
+    volume.write_bytes(0, &pattern(4 * STRIPE));
+    volume.sync().expect("sync");
+    volume.set_write_hole_policy(policy);
+    volume.simulate_crashes(tear_plan(1));
+    volume.write_bytes(0, &[0xEE; 4]);
+    volume.write_bytes(STRIPE as u64, &[0xDD; 4]);
+    volume.write_hole_stats()
+}
+
+#[test]
+fn torn_stripe_is_found_by_scrub_and_breaks_rebuild() {
+    let dir = TempDir::new().unwrap();
+    let stats = tear_first_stripe(&dir, WriteHolePolicy::Unprotected);
+    assert_eq!(stats.torn_stripes, 1);
+    assert_eq!(stats.journaled_stripes, 0);
+
+    let mut volume = open(&dir);
+    let report = volume.check();
+    assert_eq!(report.repairable_stripes, 1);
+
+    volume.fail_disk(1).expect("fail disk");
+    let mut out = [0u8; STRIPE];
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out[..4], [0xEE; 4], "the data chunk reached its disk");
+    assert_ne!(
+        out[4..],
+        pattern(STRIPE)[4..],
+        "stale parity rebuilds garbage"
+    );
+}
+
+#[test]
+fn journal_replays_torn_stripe_on_open() {
+    let dir = TempDir::new().unwrap();
+    let stats = tear_first_stripe(&dir, WriteHolePolicy::Journal);
+    assert_eq!(stats.torn_stripes, 1);
+    assert_eq!(
+        stats.journaled_stripes, 1,
+        "nothing is journaled after the crash"
+    );
+    assert_eq!(stats.journal_bytes, (8 + TEST_DISKS * CHUNK_SIZE) as u64);
+
+    let mut volume = open(&dir);
+    assert_eq!(volume.write_hole_stats().replayed_stripes, 1);
+    assert!(volume.check().is_clean());
+    assert!(!dir.path().join("stripe-journal.log").exists());
+
+    volume.fail_disk(1).expect("fail disk");
+    let mut out = [0u8; 2 * STRIPE];
+    volume.read_bytes(0, &mut out);
+    let mut expected = pattern(2 * STRIPE);
+    expected[..4].fill(0xEE);
+    assert_eq!(out.to_vec(), expected, "writes after the crash stay lost");
+}
+
+#[test]
+fn journal_is_emptied_once_members_are_durable() {
+    let dir = TempDir::new().unwrap();
+    let journal = dir.path().join("stripe-journal.log");
+    let mut volume = open(&dir);
+    volume.set_write_hole_policy(WriteHolePolicy::Journal);
+
+    volume.write_bytes(3, &[1; 10]);
+    assert_eq!(volume.write_hole_stats().journaled_stripes, 2);
+    assert_eq!(std::fs::metadata(&journal).unwrap().len(), 24);
+
+    volume.simulate_crashes(CrashPlan::default());
+    volume.write_bytes(0, &[2; STRIPE]);
+    assert!(std::fs::metadata(&journal).unwrap().len() > 24);
+    volume.flush();
+    assert_eq!(std::fs::metadata(&journal).unwrap().len(), 24);
+}
+
+#[test]
+fn full_stripe_policy_rejects_partial_writes() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = open(&dir);
+    volume.set_write_hole_policy(WriteHolePolicy::FullStripe);
+
+    let err = volume
+        .try_write_bytes(2, &[7; STRIPE])
+        .expect_err("unaligned");
+    assert!(matches!(err, Error::Invalid(_)), "{err:?}");
+    assert!(volume.try_write_bytes(0, &[7; STRIPE - 1]).is_err());
+    volume
+        .try_write_bytes(STRIPE as u64, &pattern(2 * STRIPE))
+        .expect("whole stripes");
+    assert_eq!(volume.write_hole_stats().rejected_writes, 2);
+
+    let mut out = vec![0u8; 3 * STRIPE];
+    volume.read_bytes(0, &mut out);
+    assert_eq!(out[..STRIPE], [0; STRIPE]);
+    assert_eq!(out[STRIPE..], pattern(2 * STRIPE));
+}
+
+#[test]
+fn batch_writes_tear_the_crashing_stripe() {
+    let dir = TempDir::new().unwrap();
+    let mut volume = open(&dir);
+    volume.simulate_crashes(tear_plan(2));
+    volume
+        .write_stripes(0..4, &pattern(4 * STRIPE))
+        .expect("write stripes");
+    assert_eq!(volume.write_hole_stats().torn_stripes, 1);
+    drop(volume);
+
+    let mut volume = open(&dir);
+    let checks: Vec<StripeCheck> = (0..4).map(|s| volume.check_stripe(s)).collect();
+    assert_eq!(checks[0], StripeCheck::Clean);
+    assert!(matches!(checks[1], StripeCheck::Repairable { .. }));
+    assert_eq!(checks[2..], [StripeCheck::Clean, StripeCheck::Clean]);
+}
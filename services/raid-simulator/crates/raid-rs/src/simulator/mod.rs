@@ -0,0 +1,300 @@
+//! Programmatic assembly of simulated arrays.
+//!
+//! `SimulatorBuilder` opens the disk images of an array, stacks a volume with the
+//! chosen layout on top of them and applies failure injection and tuning, so other
+//! programs can drive a simulator without going through `raid-cli`:
+//!
+//! ```
+//! use raid_rs::layout::stripe::raid3::RAID3;
+//! use raid_rs::retention::volume::DegradedPolicy;
+//! use raid_rs::simulator::SimulatorBuilder;
+//!
+//! let dir = tempfile::tempdir()?;
+//! let mut volume = SimulatorBuilder::<3, 4>::new(dir.path())
+//!     .disk_size(256)
+//!     .degraded_policy(DegradedPolicy::BestEffort)
+//!     .fail_disk(1)
+//!     .build(RAID3::zero())?;
+//!
+//! volume.write_bytes(0, b"still served");
+//! let mut out = [0u8; 12];
+//! volume.read_bytes(0, &mut out);
+//! assert_eq!(&out, b"still served");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+#[cfg(test)]
+mod simulator_tests;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::layout::stripe::traits::stripe::Stripe;
+use crate::metrics::{self, MetricsSink};
+use crate::retention::array::Array;
+use crate::retention::disk::{DiskIo, WearPlan, WriteCachePlan};
+use crate::retention::volume::{CrashPlan, DegradedPolicy, Volume};
+use crate::{Error, Result};
+
+/// Size of each disk image when the builder is not given one.
+pub const DEFAULT_DISK_SIZE: u64 = 1 << 20;
+
+/// `disk_paths` returns the image paths of the members of an array, creating the
+/// directory that holds them.
+///
+/// # Arguments
+/// * `disk_dir` - Directory containing disk images.
+///
+/// # Errors
+/// Returns an error if the directory cannot be created.
+pub fn disk_paths<const D: usize>(disk_dir: &Path) -> Result<[String; D]> {
+    std::fs::create_dir_all(disk_dir).map_err(Error::io(disk_dir))?;
+    Ok(std::array::from_fn(|i| {
+        disk_dir
+            .join(format!("disk-{i}.img"))
+            .to_string_lossy()
+            .into_owned()
+    }))
+}
+
+/// `SimulatorBuilder` assembles an array of `D` disks with `N`-byte chunks into a
+/// volume.
+///
+/// Every setting defaults to what a plain `Volume::new` gives: healthy members that
+/// neither wear out nor cache writes, no read-ahead, no parity cache and no limit
+/// on background IO.
+pub struct SimulatorBuilder<const D: usize, const N: usize> {
+    disk_dir: PathBuf,
+    disk_size: u64,
+    io: DiskIo,
+    thin_size: Option<u64>,
+    compress_size: Option<u64>,
+    degraded: DegradedPolicy,
+    uncorrectable_limit: Option<u64>,
+    wear: Option<WearPlan>,
+    write_cache: Option<WriteCachePlan>,
+    crashes: Option<CrashPlan>,
+    read_ahead: u64,
+    parity_cache: usize,
+    background_share: Option<u8>,
+    read_only: bool,
+    failed: Vec<usize>,
+    sinks: Vec<(String, Arc<dyn MetricsSink>)>,
+}
+
+impl<const D: usize, const N: usize> SimulatorBuilder<D, N> {
+    #[must_use]
+    /// `new` starts a builder for the array whose images live in `disk_dir`.
+    ///
+    /// # Arguments
+    /// * `disk_dir` - Directory containing disk images; missing images are created.
+    pub fn new(disk_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            disk_dir: disk_dir.into(),
+            disk_size: DEFAULT_DISK_SIZE,
+            io: DiskIo::default(),
+            thin_size: None,
+            compress_size: None,
+            degraded: DegradedPolicy::default(),
+            uncorrectable_limit: None,
+            wear: None,
+            write_cache: None,
+            crashes: None,
+            read_ahead: 0,
+            parity_cache: 0,
+            background_share: None,
+            read_only: false,
+            failed: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    /// `disk_size` sets the size of each disk image in bytes.
+    pub fn disk_size(mut self, bytes: u64) -> Self {
+        self.disk_size = bytes.max(1);
+        self
+    }
+
+    #[must_use]
+    /// `disk_io` sets the access method for the disk images.
+    pub const fn disk_io(mut self, io: DiskIo) -> Self {
+        self.io = io;
+        self
+    }
+
+    #[must_use]
+    /// `thin` provisions `virtual_bytes` thinly unless the volume already is thin.
+    pub const fn thin(mut self, virtual_bytes: u64) -> Self {
+        self.thin_size = Some(virtual_bytes);
+        self
+    }
+
+    #[must_use]
+    /// `compress` stores blocks zstd-compressed behind a virtual size of
+    /// `virtual_bytes` unless the volume already is compressed.
+    pub const fn compress(mut self, virtual_bytes: u64) -> Self {
+        self.compress_size = Some(virtual_bytes);
+        self
+    }
+
+    #[must_use]
+    /// `degraded_policy` sets how IO behaves once redundancy is lost.
+    pub const fn degraded_policy(mut self, policy: DegradedPolicy) -> Self {
+        self.degraded = policy;
+        self
+    }
+
+    #[must_use]
+    /// `uncorrectable_limit` refuses writes once this many stripes were lost.
+    pub const fn uncorrectable_limit(mut self, limit: Option<u64>) -> Self {
+        self.uncorrectable_limit = limit;
+        self
+    }
+
+    #[must_use]
+    /// `wear` makes the members grow defects as they are used.
+    pub const fn wear(mut self, plan: WearPlan) -> Self {
+        self.wear = Some(plan);
+        self
+    }
+
+    #[must_use]
+    /// `write_cache` puts a drive write cache in front of every member.
+    pub const fn write_cache(mut self, plan: WriteCachePlan) -> Self {
+        self.write_cache = Some(plan);
+        self
+    }
+
+    #[must_use]
+    /// `crashes` simulates power loss according to `plan`.
+    pub const fn crashes(mut self, plan: CrashPlan) -> Self {
+        self.crashes = Some(plan);
+        self
+    }
+
+    #[must_use]
+    /// `read_ahead` prefetches this many stripes ahead of sequential reads.
+    pub const fn read_ahead(mut self, stripes: u64) -> Self {
+        self.read_ahead = stripes;
+        self
+    }
+
+    #[must_use]
+    /// `parity_cache` caches the parity of this many stripes for partial writes.
+    pub const fn parity_cache(mut self, stripes: usize) -> Self {
+        self.parity_cache = stripes;
+        self
+    }
+
+    #[must_use]
+    /// `background_share` caps rebuild and scrub work at this percent of the array's
+    /// time while users are issuing IO.
+    pub const fn background_share(mut self, percent: Option<u8>) -> Self {
+        self.background_share = percent;
+        self
+    }
+
+    #[must_use]
+    /// `read_only` opens the volume without replaying its write-hole logs and refuses
+    /// to provision it thinly or compressed, so building it writes nothing to the
+    /// members.
+    pub const fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    #[must_use]
+    /// `fail_disk` fails member `index` once the volume is assembled.
+    pub fn fail_disk(mut self, index: usize) -> Self {
+        self.failed.push(index);
+        self
+    }
+
+    #[must_use]
+    /// `metrics_sink` registers `sink` under `name` when the volume is built; it then
+    /// receives every disk and RAID operation of the process.
+    pub fn metrics_sink(mut self, name: &str, sink: Arc<dyn MetricsSink>) -> Self {
+        self.sinks.push((name.to_string(), sink));
+        self
+    }
+
+    /// `build` assembles the volume with the given layout.
+    ///
+    /// # Arguments
+    /// * `layout` - Stripe layout of the array.
+    ///
+    /// # Errors
+    /// Returns an error if the disk directory cannot be created, thin provisioning or
+    /// compression cannot be enabled, a disk cannot be failed or a sink name is
+    /// already taken.
+    pub fn build<T: Stripe<D, N>>(self, layout: T) -> Result<Volume<D, N, T>> {
+        self.build_with(layout, |_| 0)
+    }
+
+    /// `build_with` assembles the volume, asking `data_end` for the logical bytes
+    /// already in use when thin provisioning or compression is enabled on existing data.
+    ///
+    /// # Arguments
+    /// * `layout` - Stripe layout of the array.
+    /// * `data_end` - Logical end of the data the thin or block map must keep.
+    ///
+    /// # Errors
+    /// Returns an error if the disk directory cannot be created, thin provisioning or
+    /// compression cannot be enabled or would change a read-only volume, a disk
+    /// cannot be failed or a sink name is already taken.
+    pub fn build_with<T, F>(self, layout: T, data_end: F) -> Result<Volume<D, N, T>>
+    where
+        T: Stripe<D, N>,
+        F: FnOnce(&mut Volume<D, N, T>) -> u64,
+    {
+        let paths = disk_paths::<D>(&self.disk_dir)?;
+        let array = Array::<D, N>::init_array_with(&paths, self.disk_size, self.io)?;
+        let mut volume = if self.read_only {
+            Volume::new_read_only(array, layout)
+        } else {
+            Volume::new(array, layout)
+        };
+        let thin = self.thin_size.filter(|_| !volume.is_thin());
+        let compress = self.compress_size.filter(|_| !volume.is_compressed());
+        if self.read_only && (thin.is_some() || compress.is_some()) {
+            return Err(Error::Invalid(
+                "a read-only volume cannot be provisioned thinly or compressed".to_string(),
+            ));
+        }
+        if thin.is_some() || compress.is_some() {
+            let end = data_end(&mut volume);
+            if let Some(thin_size) = thin {
+                volume.init_thin(thin_size, end)?;
+            }
+            if let Some(compress_size) = compress {
+                volume.init_compression(compress_size, end)?;
+            }
+        }
+        volume.set_degraded_policy(self.degraded);
+        volume.set_uncorrectable_limit(self.uncorrectable_limit);
+        if let Some(plan) = self.wear {
+            volume.simulate_wear(plan);
+        }
+        if let Some(plan) = self.write_cache {
+            volume.enable_write_cache(plan);
+        }
+        if let Some(plan) = self.crashes {
+            volume.simulate_crashes(plan);
+        }
+        volume.set_read_ahead(self.read_ahead);
+        volume.set_parity_cache(self.parity_cache);
+        volume.set_background_share(self.background_share);
+        for index in self.failed {
+            volume.fail_disk(index)?;
+        }
+        for (name, sink) in self.sinks {
+            if !metrics::register_sink(&name, sink) {
+                return Err(Error::Invalid(format!(
+                    "metrics sink {name:?} is already registered"
+                )));
+            }
+        }
+        Ok(volume)
+    }
+}
@@ -0,0 +1,83 @@
+use super::*;
+use crate::layout::stripe::raid0::RAID0;
+use crate::layout::stripe::raid1::RAID1;
+use crate::metrics::{DiskOp, RaidOp};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tempfile::TempDir;
+
+const DISK_LEN: u64 = 256;
+
+#[derive(Default)]
+struct CountingSink {
+    raid_ops: AtomicU64,
+}
+
+impl MetricsSink for CountingSink {
+    fn record_disk_op(&self, _op: DiskOp) {}
+
+    fn record_raid_op(&self, _op: RaidOp) {
+        self.raid_ops.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn disk_paths_name_images_by_role() {
+    let dir = TempDir::new().expect("tempdir");
+    let root = dir.path().join("array");
+    let paths = disk_paths::<3>(&root).expect("paths");
+    assert!(root.is_dir());
+    assert!(paths[0].ends_with("disk-0.img"));
+    assert!(paths[2].ends_with("disk-2.img"));
+}
+
+#[test]
+fn builder_applies_settings() {
+    let dir = TempDir::new().expect("tempdir");
+    let volume = SimulatorBuilder::<2, 4>::new(dir.path())
+        .disk_size(DISK_LEN)
+        .degraded_policy(DegradedPolicy::ReadOnly)
+        .fail_disk(1)
+        .build(RAID1::zero())
+        .expect("build");
+
+    assert_eq!(volume.logical_capacity_bytes(), DISK_LEN);
+    assert_eq!(volume.failed_disks(), 1);
+    assert!(volume.disk_statuses()[1].missing);
+}
+
+#[test]
+fn builder_provisions_thinly_from_the_data_end() {
+    let dir = TempDir::new().expect("tempdir");
+    let mut asked = false;
+    let volume = SimulatorBuilder::<2, 4>::new(dir.path())
+        .disk_size(16 * DISK_LEN)
+        .thin(16 * DISK_LEN)
+        .build_with(RAID0::zero(), |_| {
+            asked = true;
+            0
+        })
+        .expect("build");
+
+    assert!(asked);
+    assert!(volume.is_thin());
+    assert_eq!(volume.logical_capacity_bytes(), 16 * DISK_LEN);
+}
+
+#[test]
+fn builder_registers_metrics_sinks_once() {
+    let dir = TempDir::new().expect("tempdir");
+    let sink = Arc::new(CountingSink::default());
+    let mut volume = SimulatorBuilder::<2, 4>::new(dir.path().join("a"))
+        .disk_size(DISK_LEN)
+        .metrics_sink("simulator-test", sink.clone())
+        .build(RAID0::zero())
+        .expect("build");
+    volume.write_bytes(0, b"counted");
+    assert!(sink.raid_ops.load(Ordering::Relaxed) > 0);
+
+    let taken = SimulatorBuilder::<2, 4>::new(dir.path().join("b"))
+        .metrics_sink("simulator-test", sink)
+        .build(RAID0::zero());
+    assert!(taken.is_err());
+    assert!(metrics::unregister_sink("simulator-test"));
+}
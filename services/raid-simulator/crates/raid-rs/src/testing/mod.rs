@@ -0,0 +1,271 @@
+//! Conformance checks for stripe layouts.
+//!
+//! Each check runs a property over `cases` pseudo-random stripes and panics with
+//! the failing case number, so it can be called straight from a `#[test]`:
+//!
+//! ```
+//! use raid_rs::layout::stripe::raid3::RAID3;
+//! use raid_rs::testing;
+//!
+//! testing::check_layout(&mut RAID3::<4, 8>::zero(), 64);
+//! ```
+//!
+//! Cases are generated deterministically from their number, so a failure
+//! reproduces on every run. Each case places the layout at the stripe of the same
+//! number, so layouts that move chunks between stripes are checked across placements. Layouts without redundancy only need
+//! `check_round_trip`; layouts implementing `Restore` should pass `check_layout`.
+
+#[cfg(test)]
+mod testing_tests;
+
+use crate::layout::bits::Bits;
+use crate::layout::stripe::traits::restore::Restore;
+use crate::layout::stripe::traits::stripe::{ChunkRole, Stripe};
+
+/// `check_layout` runs every check of this module against a redundant layout.
+///
+/// # Arguments
+/// * `layout` - Layout under test; its contents are overwritten.
+/// * `cases` - Number of random stripes per check.
+///
+/// # Panics
+/// Panics if the layout violates any property.
+pub fn check_layout<const D: usize, const N: usize, T>(layout: &mut T, cases: u64)
+where
+    T: Stripe<D, N> + Restore,
+{
+    check_round_trip(layout, cases);
+    check_degraded_read(layout, cases);
+    check_reconstruction(layout, cases);
+    check_clean_scrub(layout, cases);
+}
+
+/// `check_round_trip` verifies that encoded data decodes unchanged and that raw
+/// member contents survive a raw write and read.
+///
+/// Layouts declaring `XOR_PARITY` must also store the XOR of the data chunks in
+/// every parity slot.
+///
+/// # Arguments
+/// * `layout` - Layout under test; its contents are overwritten.
+/// * `cases` - Number of random stripes to check.
+///
+/// # Panics
+/// Panics if the layout violates the property.
+pub fn check_round_trip<const D: usize, const N: usize, T: Stripe<D, N>>(
+    layout: &mut T,
+    cases: u64,
+) {
+    check_geometry::<D, N, T>();
+    for case in 0..cases {
+        let mut rng = CaseRng::new(case);
+        layout.place(case);
+        let data = rng.chunks::<N>(T::DATA);
+        layout.write(&data);
+        assert_eq!(
+            read(layout),
+            data,
+            "case {case}: read does not return the written data"
+        );
+
+        if T::XOR_PARITY {
+            check_xor_parity(layout, case);
+        }
+
+        let raw = rng.chunks::<N>(T::DISKS);
+        layout.write_raw(&raw);
+        assert_eq!(
+            read_raw(layout),
+            raw,
+            "case {case}: read_raw does not return the raw members"
+        );
+    }
+}
+
+/// `check_degraded_read` verifies that data reads back unchanged after any
+/// single member is lost and restored.
+///
+/// The lost member is overwritten with random bytes rather than zeros, so
+/// restores cannot rely on its previous contents.
+///
+/// # Arguments
+/// * `layout` - Layout under test; its contents are overwritten.
+/// * `cases` - Number of random stripes to check.
+///
+/// # Panics
+/// Panics if the layout violates the property.
+pub fn check_degraded_read<const D: usize, const N: usize, T>(layout: &mut T, cases: u64)
+where
+    T: Stripe<D, N> + Restore,
+{
+    check_restore_exposed::<D, N, T>(layout);
+    for case in 0..cases {
+        let mut rng = CaseRng::new(case);
+        layout.place(case);
+        let data = rng.chunks::<N>(T::DATA);
+        for lost in 0..T::DISKS {
+            layout.write(&data);
+            lose(layout, lost, &mut rng);
+            layout.restore(lost);
+            assert_eq!(
+                read(layout),
+                data,
+                "case {case}: data differs after losing member {lost}"
+            );
+        }
+    }
+}
+
+/// `check_reconstruction` verifies that restoring a lost member rebuilds it bit
+/// for bit, parity included.
+///
+/// # Arguments
+/// * `layout` - Layout under test; its contents are overwritten.
+/// * `cases` - Number of random stripes to check.
+///
+/// # Panics
+/// Panics if the layout violates the property.
+pub fn check_reconstruction<const D: usize, const N: usize, T>(layout: &mut T, cases: u64)
+where
+    T: Stripe<D, N> + Restore,
+{
+    for case in 0..cases {
+        let mut rng = CaseRng::new(case);
+        layout.place(case);
+        let data = rng.chunks::<N>(T::DATA);
+        layout.write(&data);
+        let encoded = read_raw(layout);
+        for lost in 0..T::DISKS {
+            layout.write_raw(&encoded);
+            lose(layout, lost, &mut rng);
+            layout.restore(lost);
+            assert_eq!(
+                read_raw(layout),
+                encoded,
+                "case {case}: member {lost} is not rebuilt exactly"
+            );
+        }
+    }
+}
+
+/// `check_clean_scrub` verifies that scrubbing a freshly written stripe asks for
+/// no rewrites and leaves its members unchanged.
+///
+/// # Arguments
+/// * `layout` - Layout under test; its contents are overwritten.
+/// * `cases` - Number of random stripes to check.
+///
+/// # Panics
+/// Panics if the layout violates the property.
+pub fn check_clean_scrub<const D: usize, const N: usize, T>(layout: &mut T, cases: u64)
+where
+    T: Stripe<D, N> + Restore,
+{
+    for case in 0..cases {
+        let mut rng = CaseRng::new(case);
+        layout.place(case);
+        layout.write(&rng.chunks::<N>(T::DATA));
+        let encoded = read_raw(layout);
+        let rewrites = layout.scrub();
+        assert!(
+            rewrites.is_empty(),
+            "case {case}: scrub of a consistent stripe rewrites members {rewrites:?}"
+        );
+        assert_eq!(
+            read_raw(layout),
+            encoded,
+            "case {case}: scrub of a consistent stripe changed it"
+        );
+    }
+}
+
+/// `check_xor_parity` verifies the `XOR_PARITY` promise on a freshly written stripe.
+fn check_xor_parity<const D: usize, const N: usize, T: Stripe<D, N>>(layout: &T, case: u64) {
+    let raw = read_raw(layout);
+    let mut xor = Bits::<N>::zero();
+    for (disk, chunk) in raw.iter().enumerate() {
+        if matches!(T::role_at(case, disk), ChunkRole::Data(_)) {
+            xor ^= *chunk;
+        }
+    }
+    for (disk, chunk) in raw.iter().enumerate() {
+        if T::role_at(case, disk) == ChunkRole::Parity {
+            assert_eq!(
+                *chunk, xor,
+                "case {case}: parity slot {disk} is not the XOR of the data chunks"
+            );
+        }
+    }
+}
+
+fn check_geometry<const D: usize, const N: usize, T: Stripe<D, N>>() {
+    assert_eq!(T::DISKS, D, "DISKS must equal the disk count D");
+    assert!(
+        (1..=D).contains(&T::DATA),
+        "DATA must be between 1 and the disk count D"
+    );
+}
+
+/// `check_restore_exposed` catches layouts that implement `Restore` but leave
+/// `as_restore_mut` at its default, which makes arrays skip every rebuild.
+fn check_restore_exposed<const D: usize, const N: usize, T>(layout: &mut T)
+where
+    T: Stripe<D, N> + Restore,
+{
+    assert!(
+        layout.as_restore().is_some() && layout.as_restore_mut().is_some(),
+        "layouts implementing Restore must return it from as_restore and as_restore_mut"
+    );
+}
+
+fn read<const D: usize, const N: usize, T: Stripe<D, N>>(layout: &T) -> Vec<Bits<N>> {
+    let mut out = vec![Bits::zero(); T::DATA];
+    layout.read(&mut out);
+    out
+}
+
+fn read_raw<const D: usize, const N: usize, T: Stripe<D, N>>(layout: &T) -> Vec<Bits<N>> {
+    let mut out = vec![Bits::zero(); T::DISKS];
+    layout.read_raw(&mut out);
+    out
+}
+
+/// `lose` replaces one member with random bytes.
+fn lose<const D: usize, const N: usize, T: Stripe<D, N>>(
+    layout: &mut T,
+    member: usize,
+    rng: &mut CaseRng,
+) {
+    let mut raw = read_raw(layout);
+    raw[member] = rng.chunk();
+    layout.write_raw(&raw);
+}
+
+/// `CaseRng` is a `SplitMix64` generator seeded from the case number.
+struct CaseRng(u64);
+
+impl CaseRng {
+    const fn new(case: u64) -> Self {
+        Self(case)
+    }
+
+    const fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn chunk<const N: usize>(&mut self) -> Bits<N> {
+        let mut bits = Bits::zero();
+        for byte in bits.as_bytes_mut() {
+            *byte = self.next().to_le_bytes()[0];
+        }
+        bits
+    }
+
+    fn chunks<const N: usize>(&mut self, count: usize) -> Vec<Bits<N>> {
+        (0..count).map(|_| self.chunk()).collect()
+    }
+}
@@ -7,14 +7,20 @@ cleanup() {
   service nfs-kernel-server stop
   service rpcbind stop
   if [ -n "$FUSE_PID" ]; then
-    kill "$FUSE_PID" 2>/dev/null || true
+    # raid-cli unmounts and flushes its disks on SIGTERM.
+    kill -TERM "$FUSE_PID" 2>/dev/null || true
+    wait "$FUSE_PID" 2>/dev/null || true
+  fi
+  if mountpoint -q "$MOUNT_POINT"; then
+    umount -l "$MOUNT_POINT" 2>/dev/null || true
   fi
-  umount -l "$MOUNT_POINT" 2>/dev/null || true
   exit 0
 }
 
 trap cleanup SIGTERM SIGINT
 
+FUSE_PID=""
+
 AUTH_TOKEN="${GRPC_AUTH_TOKEN:-}"
 RAID_LEVEL="${RAID_LEVEL:-raid3}"
 DISK_SIZE="${DISK_SIZE:-100000000}"
@@ -31,7 +37,9 @@ raid-cli fuse \
     --raid "$RAID_LEVEL" \
     --disk-size "$DISK_SIZE" \
     --auth-token "$AUTH_TOKEN" \
-    --allow-other &
+    --allow-other \
+    --auto-unmount &
+FUSE_PID=$!
 
 echo "Waiting for FUSE mount at $MOUNT_POINT..."
 for _ in {1..50}; do